@@ -0,0 +1,384 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serenity::model::id::{MessageId, UserId};
+use std::collections::{HashMap, VecDeque};
+use std::path::Path;
+use tracing::{info, warn};
+
+/// Reaction that counts as a vote for more interjections like the one reacted to
+pub const FEEDBACK_UP_EMOJI: &str = "👍";
+/// Reaction that counts as a vote for fewer interjections like the one reacted to
+pub const FEEDBACK_DOWN_EMOJI: &str = "👎";
+
+// How many recent interjections we remember so feedback can be traced back to their type
+const MAX_TRACKED_INTERJECTIONS: usize = 200;
+
+/// The kinds of interjection, each with its own configured probability
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InterjectionKind {
+    Mst3k,
+    Memory,
+    Pondering,
+    Ai,
+    Fact,
+    News,
+}
+
+impl InterjectionKind {
+    pub const ALL: [InterjectionKind; 6] = [
+        InterjectionKind::Mst3k,
+        InterjectionKind::Memory,
+        InterjectionKind::Pondering,
+        InterjectionKind::Ai,
+        InterjectionKind::Fact,
+        InterjectionKind::News,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            InterjectionKind::Mst3k => "mst3k",
+            InterjectionKind::Memory => "memory",
+            InterjectionKind::Pondering => "pondering",
+            InterjectionKind::Ai => "ai",
+            InterjectionKind::Fact => "fact",
+            InterjectionKind::News => "news",
+        }
+    }
+
+    /// The type with this name, as used in commands and config
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "mst3k" => Some(InterjectionKind::Mst3k),
+            "memory" => Some(InterjectionKind::Memory),
+            "pondering" => Some(InterjectionKind::Pondering),
+            "ai" => Some(InterjectionKind::Ai),
+            "fact" => Some(InterjectionKind::Fact),
+            "news" => Some(InterjectionKind::News),
+            _ => None,
+        }
+    }
+}
+
+/// Whether people liked an interjection
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Feedback {
+    Positive,
+    Negative,
+}
+
+impl Feedback {
+    /// Map a feedback reaction to a vote, None for any other emoji
+    pub fn from_emoji(emoji: &str) -> Option<Self> {
+        match emoji {
+            FEEDBACK_UP_EMOJI => Some(Feedback::Positive),
+            FEEDBACK_DOWN_EMOJI => Some(Feedback::Negative),
+            _ => None,
+        }
+    }
+}
+
+/// How far feedback may move an interjection type away from its configured probability.
+/// Factors multiply the configured probability, so 1.0 leaves it unchanged.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AdaptiveSettings {
+    pub min_factor: f64,
+    pub max_factor: f64,
+    /// How much one piece of feedback moves the factor
+    pub step: f64,
+}
+
+impl Default for AdaptiveSettings {
+    fn default() -> Self {
+        Self {
+            min_factor: 0.5,
+            max_factor: 2.0,
+            step: 0.05,
+        }
+    }
+}
+
+/// Nudge a factor one step in the direction of the feedback, staying within bounds
+pub fn adjust_factor(factor: f64, feedback: Feedback, settings: &AdaptiveSettings) -> f64 {
+    let adjusted = match feedback {
+        Feedback::Positive => factor + settings.step,
+        Feedback::Negative => factor - settings.step,
+    };
+    adjusted.clamp(settings.min_factor, settings.max_factor)
+}
+
+/// The probability to actually use: the configured one scaled by the learned factor,
+/// never above 1. A type configured at 0 stays disabled.
+pub fn effective_probability(base: f64, factor: f64) -> f64 {
+    (base * factor).clamp(0.0, 1.0)
+}
+
+// Feedback counters and the factor they've produced for one interjection type
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct KindStats {
+    factor: f64,
+    positive: u32,
+    negative: u32,
+}
+
+impl Default for KindStats {
+    fn default() -> Self {
+        Self {
+            factor: 1.0,
+            positive: 0,
+            negative: 0,
+        }
+    }
+}
+
+// An interjection we sent and who has already given feedback on it
+struct TrackedInterjection {
+    message_id: MessageId,
+    kind: InterjectionKind,
+    voters: Vec<UserId>,
+}
+
+#[derive(Default)]
+struct AdaptiveState {
+    stats: HashMap<InterjectionKind, KindStats>,
+    sent: VecDeque<TrackedInterjection>,
+}
+
+/// Learns from 👍/👎 reactions on interjections, and from interjections being deleted,
+/// which interjection types a server likes, and scales each type's probability up or down
+/// in small steps within the configured bounds. The factors and counters are saved to a
+/// JSON file so they survive restarts.
+pub struct AdaptiveInterjections {
+    settings: AdaptiveSettings,
+    persistence_file: Option<String>,
+    state: std::sync::Mutex<AdaptiveState>,
+}
+
+impl AdaptiveInterjections {
+    pub fn new(settings: AdaptiveSettings) -> Self {
+        Self {
+            settings,
+            persistence_file: None,
+            state: std::sync::Mutex::new(AdaptiveState::default()),
+        }
+    }
+
+    /// Create one that loads its factors from, and saves them to, `persistence_file`
+    pub fn new_with_persistence(settings: AdaptiveSettings, persistence_file: String) -> Self {
+        let mut adaptive = Self::new(settings);
+        adaptive.persistence_file = Some(persistence_file);
+
+        if let Err(e) = adaptive.load() {
+            warn!("Failed to load adaptive interjection factors: {}", e);
+        }
+
+        adaptive
+    }
+
+    fn load(&self) -> Result<()> {
+        let Some(file_path) = &self.persistence_file else {
+            return Ok(());
+        };
+        if !Path::new(file_path).exists() {
+            return Ok(());
+        }
+
+        let content = std::fs::read_to_string(file_path)?;
+        let mut stats: HashMap<InterjectionKind, KindStats> = serde_json::from_str(&content)?;
+        // The bounds may have been tightened since the factors were saved
+        for kind_stats in stats.values_mut() {
+            kind_stats.factor = kind_stats
+                .factor
+                .clamp(self.settings.min_factor, self.settings.max_factor);
+        }
+
+        info!("Loaded adaptive interjection factors: {:?}", stats);
+        self.state.lock().unwrap().stats = stats;
+        Ok(())
+    }
+
+    async fn save(&self) -> Result<()> {
+        if let Some(file_path) = &self.persistence_file {
+            let content = serde_json::to_string(&self.state.lock().unwrap().stats)?;
+            tokio::fs::write(file_path, content).await?;
+        }
+        Ok(())
+    }
+
+    /// The probability to use for an interjection type configured at `base`
+    pub fn probability(&self, kind: InterjectionKind, base: f64) -> f64 {
+        effective_probability(base, self.factor(kind))
+    }
+
+    /// The learned factor for an interjection type (1.0 until it gets feedback)
+    pub fn factor(&self, kind: InterjectionKind) -> f64 {
+        self.state
+            .lock()
+            .unwrap()
+            .stats
+            .get(&kind)
+            .map_or(1.0, |stats| stats.factor)
+    }
+
+    /// Remember an interjection we just sent so feedback on it can be counted
+    pub fn record_sent(&self, message_id: MessageId, kind: InterjectionKind) {
+        let mut state = self.state.lock().unwrap();
+        if state.sent.len() >= MAX_TRACKED_INTERJECTIONS {
+            state.sent.pop_front();
+        }
+        state.sent.push_back(TrackedInterjection {
+            message_id,
+            kind,
+            voters: Vec::new(),
+        });
+    }
+
+    /// Count a reaction on one of our interjections. Each user's first vote on an
+    /// interjection counts once; returns the type it applied to.
+    pub async fn record_reaction(
+        &self,
+        message_id: MessageId,
+        user_id: UserId,
+        feedback: Feedback,
+    ) -> Option<InterjectionKind> {
+        let kind = {
+            let mut state = self.state.lock().unwrap();
+            let tracked = state
+                .sent
+                .iter_mut()
+                .find(|tracked| tracked.message_id == message_id)?;
+            if tracked.voters.contains(&user_id) {
+                return None;
+            }
+            tracked.voters.push(user_id);
+            tracked.kind
+        };
+
+        self.apply(kind, feedback).await;
+        Some(kind)
+    }
+
+    /// Count the deletion of one of our interjections as negative feedback; returns the
+    /// type it applied to
+    pub async fn record_deletion(&self, message_id: MessageId) -> Option<InterjectionKind> {
+        let kind = {
+            let mut state = self.state.lock().unwrap();
+            let index = state
+                .sent
+                .iter()
+                .position(|tracked| tracked.message_id == message_id)?;
+            state.sent.remove(index)?.kind
+        };
+
+        self.apply(kind, Feedback::Negative).await;
+        Some(kind)
+    }
+
+    async fn apply(&self, kind: InterjectionKind, feedback: Feedback) {
+        let factor = {
+            let mut state = self.state.lock().unwrap();
+            let stats = state.stats.entry(kind).or_default();
+            match feedback {
+                Feedback::Positive => stats.positive += 1,
+                Feedback::Negative => stats.negative += 1,
+            }
+            stats.factor = adjust_factor(stats.factor, feedback, &self.settings);
+            stats.factor
+        };
+
+        info!(
+            "{:?} feedback on a {:?} interjection, probability factor now {:.2}",
+            feedback, kind, factor
+        );
+        if let Err(e) = self.save().await {
+            warn!("Failed to save adaptive interjection factors: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_kind_names_round_trip() {
+        for kind in InterjectionKind::ALL {
+            assert_eq!(InterjectionKind::from_name(kind.name()), Some(kind));
+        }
+        assert_eq!(
+            InterjectionKind::from_name("MST3K"),
+            Some(InterjectionKind::Mst3k)
+        );
+        assert_eq!(InterjectionKind::from_name("haiku"), None);
+    }
+
+    #[test]
+    fn test_adjustment_steps_and_clamping() {
+        let settings = AdaptiveSettings {
+            min_factor: 0.5,
+            max_factor: 1.2,
+            step: 0.1,
+        };
+
+        // One small step per piece of feedback
+        let up = adjust_factor(1.0, Feedback::Positive, &settings);
+        assert!((up - 1.1).abs() < 1e-9);
+        let down = adjust_factor(1.0, Feedback::Negative, &settings);
+        assert!((down - 0.9).abs() < 1e-9);
+
+        // Clamped to the configured bounds however much feedback piles up
+        let mut factor = 1.0;
+        for _ in 0..50 {
+            factor = adjust_factor(factor, Feedback::Positive, &settings);
+        }
+        assert_eq!(factor, 1.2);
+        for _ in 0..50 {
+            factor = adjust_factor(factor, Feedback::Negative, &settings);
+        }
+        assert_eq!(factor, 0.5);
+
+        // Scaled probabilities never leave 0..=1, and disabled types stay disabled
+        assert!((effective_probability(0.01, 1.5) - 0.015).abs() < 1e-12);
+        assert_eq!(effective_probability(0.8, 2.0), 1.0);
+        assert_eq!(effective_probability(0.0, 2.0), 0.0);
+    }
+
+    #[tokio::test]
+    async fn test_feedback_moves_only_the_tracked_type() {
+        let adaptive = AdaptiveInterjections::new(AdaptiveSettings::default());
+        let alice = UserId::new(1);
+        let bob = UserId::new(2);
+        adaptive.record_sent(MessageId::new(10), InterjectionKind::News);
+        adaptive.record_sent(MessageId::new(11), InterjectionKind::Fact);
+
+        assert_eq!(
+            adaptive
+                .record_reaction(MessageId::new(10), alice, Feedback::Negative)
+                .await,
+            Some(InterjectionKind::News)
+        );
+        // A second vote from the same user doesn't count
+        assert_eq!(
+            adaptive
+                .record_reaction(MessageId::new(10), alice, Feedback::Negative)
+                .await,
+            None
+        );
+        adaptive
+            .record_reaction(MessageId::new(10), bob, Feedback::Negative)
+            .await;
+        assert!((adaptive.factor(InterjectionKind::News) - 0.9).abs() < 1e-9);
+
+        // Deleting an interjection counts against its type
+        assert_eq!(
+            adaptive.record_deletion(MessageId::new(11)).await,
+            Some(InterjectionKind::Fact)
+        );
+        assert!((adaptive.factor(InterjectionKind::Fact) - 0.95).abs() < 1e-9);
+
+        // Untracked messages and untouched types are left alone
+        assert_eq!(adaptive.record_deletion(MessageId::new(99)).await, None);
+        assert_eq!(adaptive.factor(InterjectionKind::Mst3k), 1.0);
+        assert!((adaptive.probability(InterjectionKind::News, 0.01) - 0.009).abs() < 1e-12);
+    }
+}
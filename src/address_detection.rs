@@ -0,0 +1,291 @@
+use regex::Regex;
+use tracing::{info, warn};
+
+/// Placeholder in configured address patterns that stands for the bot's name
+pub const NAME_PLACEHOLDER: &str = "{name}";
+
+/// Split a list of configured patterns. Patterns are separated by `;` or newlines,
+/// since commas are common inside regexes.
+pub fn parse_pattern_list(spec: &str) -> Vec<String> {
+    spec.split([';', '\n'])
+        .map(str::trim)
+        .filter(|pattern| !pattern.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Decides whether a message is talking to the bot by name rather than just mentioning it.
+/// Built from the bot's name, plus optional extra patterns for other nicknames or languages.
+pub struct AddressDetector {
+    bot_name: String,
+    name_boundary: Regex,
+    negative: Vec<Regex>,
+    positive: Vec<Regex>,
+    /// Configured patterns that always mean the bot is addressed
+    extra_positive: Vec<Regex>,
+    /// Configured patterns that always mean it isn't, checked before anything else
+    extra_negative: Vec<Regex>,
+}
+
+// Compile configured patterns case-insensitively, with {name} standing for the bot's name
+fn compile_extra_patterns(patterns: &[String], escaped_name: &str) -> Vec<Regex> {
+    patterns
+        .iter()
+        .filter_map(|pattern| {
+            let expanded = pattern.replace(NAME_PLACEHOLDER, escaped_name);
+            match Regex::new(&format!("(?i){expanded}")) {
+                Ok(re) => Some(re),
+                Err(e) => {
+                    warn!("Ignoring invalid address pattern '{}': {}", pattern, e);
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+impl AddressDetector {
+    pub fn new(bot_name: &str, extra_positive: &[String], extra_negative: &[String]) -> Self {
+        let name = bot_name.to_lowercase();
+        let escaped = regex::escape(&name);
+
+        let name_boundary = Regex::new(&format!(r"\b{escaped}\b")).unwrap();
+
+        let neg_strs = [
+            format!(r"than {escaped}\b"),
+            format!(r"like {escaped}\b"),
+            format!(r"about {escaped}\b"),
+            format!(r"with {escaped}\b"),
+            format!(r"and {escaped}\b"),
+            format!(r"or {escaped}\b"),
+            format!(r"for {escaped}\b"),
+            format!(r"the {escaped}\b"),
+            format!(r"a {escaped}\b"),
+            format!(r"an {escaped}\b"),
+            format!(r"this {escaped}\b"),
+            format!(r"that {escaped}\b"),
+            format!(r"my {escaped}\b"),
+            format!(r"your {escaped}\b"),
+            format!(r"our {escaped}\b"),
+            format!(r"their {escaped}\b"),
+            format!(r"his {escaped}\b"),
+            format!(r"her {escaped}\b"),
+            format!(r"its {escaped}\b"),
+            format!(r"picked {escaped}\b"),
+            format!(r"chose {escaped}\b"),
+            format!(r"selected {escaped}\b"),
+            format!(r"named {escaped}\b"),
+            format!(r"called {escaped}\b"),
+            format!(r"{escaped} is\b"),
+            format!(r"{escaped} was\b"),
+            format!(r"{escaped} has\b"),
+            format!(r"{escaped} isn't\b"),
+            format!(r"{escaped} doesn't\b"),
+            format!(r"{escaped} didn't\b"),
+            format!(r"{escaped} won't\b"),
+            format!(r"{escaped} can't\b"),
+            format!(r"{escaped} rhymes"),
+            format!(r"rhymes with {escaped}"),
+            format!(r"{escaped} and"),
+            format!(r"more of a {escaped}"),
+            format!(r"less of a {escaped}"),
+            format!(r"kind of {escaped}"),
+            format!(r"sort of {escaped}"),
+            format!(r"type of {escaped}"),
+        ];
+        let negative = neg_strs.iter().filter_map(|p| Regex::new(p).ok()).collect();
+
+        let pos_strs = [
+            format!(r"{escaped}\?"),
+            format!(r"{escaped}!"),
+            format!(r"{escaped},"),
+            format!(r"{escaped}:"),
+            format!(r"{escaped} can you"),
+            format!(r"{escaped} could you"),
+            format!(r"{escaped} will you"),
+            format!(r"{escaped} would you"),
+            format!(r"{escaped} please"),
+            format!(r"ask {escaped}"),
+            format!(r"tell {escaped}"),
+            format!(r", {escaped}"),
+            format!(r" {escaped}\."),
+        ];
+        let positive = pos_strs
+            .iter()
+            .filter_map(|p| Regex::new(&format!(r"\b{p}\b")).ok())
+            .collect();
+
+        Self {
+            extra_positive: compile_extra_patterns(extra_positive, &escaped),
+            extra_negative: compile_extra_patterns(extra_negative, &escaped),
+            bot_name: name,
+            name_boundary,
+            negative,
+            positive,
+        }
+    }
+
+    /// Whether the message is addressed to the bot
+    pub fn is_addressed(&self, content: &str) -> bool {
+        let bot_name = &self.bot_name;
+        let content_lower = content.to_lowercase();
+
+        // Configured exceptions win over everything else
+        if let Some(re) = self
+            .extra_negative
+            .iter()
+            .find(|re| re.is_match(&content_lower))
+        {
+            info!(
+                "Bot NOT addressed: matched configured negative pattern '{}'",
+                re.as_str()
+            );
+            return false;
+        }
+
+        // Direct mention at the start - the message must start with the bot's name
+        // followed by a space, punctuation, or end of string
+        if content_lower.starts_with(bot_name.as_str()) {
+            // Check what comes after the bot name
+            let remainder = &content_lower[bot_name.len()..];
+            if remainder.is_empty()
+                || remainder.starts_with(' ')
+                || remainder.starts_with('?')
+                || remainder.starts_with('!')
+                || remainder.starts_with(',')
+                || remainder.starts_with(':')
+            {
+                info!("Bot addressed: name at beginning of message");
+                return true;
+            }
+        }
+
+        // Common address patterns - these are explicit ways to address the bot
+        let address_patterns = [
+            format!("hey {bot_name}"),
+            format!("hi {bot_name}"),
+            format!("hello {bot_name}"),
+            format!("ok {bot_name}"),
+            format!("hey, {bot_name}"),
+            format!("hi, {bot_name}"),
+            format!("hello, {bot_name}"),
+            format!("ok, {bot_name}"),
+            format!("{bot_name}, "), // When name is used with a comma
+            format!("@{bot_name}"),  // Informal mention
+            format!("excuse me, {bot_name}"),
+            format!("by the way, {bot_name}"),
+            format!("btw, {bot_name}"),
+        ];
+
+        for pattern in &address_patterns {
+            if content_lower.contains(pattern) {
+                info!("Bot addressed: matched pattern '{}'", pattern);
+                return true;
+            }
+        }
+
+        // Configured patterns may not use the bot's name at all (nicknames, other languages)
+        if let Some(re) = self
+            .extra_positive
+            .iter()
+            .find(|re| re.is_match(&content_lower))
+        {
+            info!(
+                "Bot addressed: matched configured pattern '{}'",
+                re.as_str()
+            );
+            return true;
+        }
+
+        // Use regex with word boundaries to avoid false positives
+        if self.name_boundary.is_match(&content_lower) {
+            // Check for negative patterns first
+            for re in &self.negative {
+                if re.is_match(&content_lower) {
+                    info!(
+                        "Bot NOT addressed: matched negative pattern '{}'",
+                        re.as_str()
+                    );
+                    return false;
+                }
+            }
+
+            // Check for positive patterns
+            for re in &self.positive {
+                if re.is_match(&content_lower) {
+                    info!("Bot addressed: matched positive pattern '{}'", re.as_str());
+                    return true;
+                }
+            }
+
+            // If the bot name is at the beginning or end of the message, it's likely being addressed
+            if content_lower.trim().starts_with(bot_name.as_str())
+                || content_lower.trim().ends_with(bot_name.as_str())
+            {
+                info!("Bot addressed: name at beginning or end of trimmed message");
+                return true;
+            }
+
+            // Bot name found but not clearly addressed
+            info!("Bot name found as standalone word, but not clearly addressed");
+            return false;
+        }
+
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_address_detection() {
+        let detector = AddressDetector::new("Crow", &[], &[]);
+
+        for addressed in [
+            "Crow?",
+            "Crow, can you tell me a joke",
+            "crow what time is it",
+            "hey crow",
+            "so what do you think, Crow",
+            "I wonder if we should ask Crow about it",
+            "thanks Crow",
+        ] {
+            assert!(detector.is_addressed(addressed), "{addressed}");
+        }
+
+        for not_addressed in [
+            "anyone other than Crow",
+            "I like the crow in that movie",
+            "I think Crow is a puppet",
+            "crowbar",
+            "the crowd went wild",
+            "nothing to see here",
+        ] {
+            assert!(!detector.is_addressed(not_addressed), "{not_addressed}");
+        }
+    }
+
+    #[test]
+    fn test_configured_address_patterns() {
+        let positive = parse_pattern_list(r"\bcorvid\b; ^oye {name}\b");
+        let negative = parse_pattern_list(r"\bhey crow, never mind\b");
+        assert_eq!(positive, vec![r"\bcorvid\b", r"^oye {name}\b"]);
+        let detector = AddressDetector::new("Crow", &positive, &negative);
+
+        // Extra patterns work with or without the bot's name, in any case
+        assert!(detector.is_addressed("what do you think, Corvid"));
+        assert!(detector.is_addressed("Oye crow qué hora es"));
+        // Configured exceptions override even the built-in address phrases
+        assert!(!detector.is_addressed("hey Crow, never mind"));
+        // The defaults still apply
+        assert!(detector.is_addressed("Crow, can you help"));
+        assert!(!detector.is_addressed("other than Crow"));
+
+        // A different bot name gets the same treatment, and bad patterns are skipped
+        let detector = AddressDetector::new("Servo", &["(unclosed".to_string()], &[]);
+        assert!(detector.is_addressed("Servo?"));
+        assert!(!detector.is_addressed("Crow?"));
+    }
+}
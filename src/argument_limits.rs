@@ -0,0 +1,108 @@
+use std::collections::HashMap;
+use tracing::warn;
+
+/// Longest command arguments accepted when COMMAND_MAX_ARG_LENGTH isn't set
+pub const DEFAULT_MAX_ARG_LENGTH: usize = 500;
+
+/// Caps on how long a command's arguments may be, so an oversized `!imagine` prompt (or
+/// any other command) is turned away before it reaches Gemini or another paid API.
+/// Each command uses its own limit if one is configured, otherwise the global default.
+/// A limit of 0 means no limit.
+#[derive(Clone, Debug)]
+pub struct ArgumentLimits {
+    default: usize,
+    per_command: HashMap<String, usize>,
+}
+
+impl Default for ArgumentLimits {
+    fn default() -> Self {
+        Self {
+            default: DEFAULT_MAX_ARG_LENGTH,
+            per_command: HashMap::new(),
+        }
+    }
+}
+
+impl ArgumentLimits {
+    /// Build limits from the global default and a comma-separated list of `command=length`
+    /// overrides, skipping malformed entries
+    pub fn parse(default: usize, spec: &str) -> Self {
+        let per_command = spec
+            .split(',')
+            .filter(|entry| !entry.trim().is_empty())
+            .filter_map(|entry| {
+                let parsed = entry.split_once('=').and_then(|(command, length)| {
+                    let command = command.trim().trim_start_matches('!').to_lowercase();
+                    let length = length.trim().parse::<usize>().ok()?;
+                    (!command.is_empty()).then_some((command, length))
+                });
+                if parsed.is_none() {
+                    warn!("Ignoring malformed argument length limit: {}", entry.trim());
+                }
+                parsed
+            })
+            .collect();
+
+        Self {
+            default,
+            per_command,
+        }
+    }
+
+    /// The limit for a command, or None if it has no limit
+    pub fn limit_for(&self, command: &str) -> Option<usize> {
+        let limit = self
+            .per_command
+            .get(command)
+            .copied()
+            .unwrap_or(self.default);
+        (limit > 0).then_some(limit)
+    }
+
+    /// Check a command's arguments, returning the limit they exceed
+    pub fn check(&self, command: &str, args: &[&str]) -> Result<(), usize> {
+        let Some(limit) = self.limit_for(command) else {
+            return Ok(());
+        };
+        if args.join(" ").chars().count() > limit {
+            Err(limit)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// The reply for a command whose arguments are over its limit
+pub fn too_long_message(command: &str, limit: usize) -> String {
+    format!("That's too long for !{command}. Please keep it under {limit} characters.")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_over_length_arguments_are_rejected() {
+        let limits = ArgumentLimits::parse(20, "imagine=40, !search=10, calc=0, broken");
+        let long_prompt = "a castle made of cheese on a hill at sunset";
+
+        // Per-command limits win over the default
+        assert_eq!(limits.limit_for("imagine"), Some(40));
+        assert_eq!(limits.limit_for("search"), Some(10));
+        assert_eq!(limits.limit_for("slogan"), Some(20));
+        assert_eq!(limits.limit_for("calc"), None);
+
+        let words: Vec<&str> = long_prompt.split_whitespace().collect();
+        assert_eq!(limits.check("imagine", &words), Err(40));
+        assert_eq!(limits.check("imagine", &words[..4]), Ok(()));
+        assert_eq!(limits.check("slogan", &words), Err(20));
+        assert_eq!(limits.check("calc", &words), Ok(()));
+        assert_eq!(
+            too_long_message("imagine", 40),
+            "That's too long for !imagine. Please keep it under 40 characters."
+        );
+
+        // Characters are counted, not bytes
+        assert_eq!(limits.check("search", &["🍕🍕🍕🍕🍕🍕🍕🍕🍕🍕"]), Ok(()));
+    }
+}
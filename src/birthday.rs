@@ -0,0 +1,362 @@
+use anyhow::Result;
+use chrono::{Datelike, FixedOffset, NaiveDate, Utc};
+use serenity::all::Http;
+use serenity::model::channel::Message;
+use serenity::model::id::ChannelId;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio_rusqlite::Connection as SqliteConnection;
+use tracing::{error, info};
+
+// How often the scheduler checks whether a new day has started
+const BIRTHDAY_CHECK_INTERVAL: Duration = Duration::from_secs(15 * 60);
+// How many upcoming birthdays !birthday list shows
+const UPCOMING_LIMIT: usize = 10;
+
+const MONTH_NAMES: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+/// Parse a birthday given as MM-DD (or M-D, MM/DD). Feb 29 is allowed.
+pub fn parse_birthday(input: &str) -> Option<(u32, u32)> {
+    let (month, day) = input.trim().split_once(['-', '/'])?;
+    let month = month.parse::<u32>().ok()?;
+    let day = day.parse::<u32>().ok()?;
+
+    // Validate against a leap year so Feb 29 counts as a real date
+    NaiveDate::from_ymd_opt(2000, month, day)?;
+    Some((month, day))
+}
+
+/// The date a birthday is celebrated in `year`. Feb 29 birthdays are celebrated on
+/// Feb 28 in non-leap years.
+pub fn celebration_date(month: u32, day: u32, year: i32) -> Option<NaiveDate> {
+    NaiveDate::from_ymd_opt(year, month, day).or_else(|| {
+        if month == 2 && day == 29 {
+            NaiveDate::from_ymd_opt(year, 2, 28)
+        } else {
+            None
+        }
+    })
+}
+
+/// Whether a birthday should be celebrated on `today`
+pub fn is_birthday_on(month: u32, day: u32, today: NaiveDate) -> bool {
+    celebration_date(month, day, today.year()) == Some(today)
+}
+
+/// The next day (today included) this birthday is celebrated
+pub fn next_celebration(month: u32, day: u32, today: NaiveDate) -> Option<NaiveDate> {
+    match celebration_date(month, day, today.year()) {
+        Some(date) if date >= today => Some(date),
+        _ => celebration_date(month, day, today.year() + 1),
+    }
+}
+
+/// Today's date in the configured timezone, or the machine's local time if none is set
+pub fn today_in(offset: Option<FixedOffset>) -> NaiveDate {
+    match offset {
+        Some(offset) => Utc::now().with_timezone(&offset).date_naive(),
+        None => chrono::Local::now().date_naive(),
+    }
+}
+
+fn format_month_day(month: u32, day: u32) -> String {
+    format!("{} {}", MONTH_NAMES[(month - 1) as usize], day)
+}
+
+// Save or replace a user's birthday
+async fn set_birthday(
+    conn: Arc<Mutex<SqliteConnection>>,
+    user_id: String,
+    guild_id: String,
+    display_name: String,
+    month: u32,
+    day: u32,
+) -> Result<()> {
+    let conn_guard = conn.lock().await;
+    conn_guard
+        .call(move |conn| {
+            conn.execute(
+                "INSERT INTO birthdays (user_id, guild_id, display_name, month, day, last_announced_year)
+                 VALUES (?1, ?2, ?3, ?4, ?5, 0)
+                 ON CONFLICT(user_id) DO UPDATE SET
+                     guild_id = excluded.guild_id,
+                     display_name = excluded.display_name,
+                     month = excluded.month,
+                     day = excluded.day",
+                rusqlite::params![user_id, guild_id, display_name, month, day],
+            )?;
+            Ok::<_, rusqlite::Error>(())
+        })
+        .await?;
+    Ok(())
+}
+
+// Forget a user's birthday, returning whether one was stored
+async fn remove_birthday(conn: Arc<Mutex<SqliteConnection>>, user_id: String) -> Result<bool> {
+    let conn_guard = conn.lock().await;
+    let removed = conn_guard
+        .call(move |conn| {
+            let removed = conn.execute("DELETE FROM birthdays WHERE user_id = ?1", [&user_id])?;
+            Ok::<_, rusqlite::Error>(removed > 0)
+        })
+        .await?;
+    Ok(removed)
+}
+
+// All stored birthdays as (user_id, display_name, month, day, last_announced_year),
+// optionally limited to one guild
+async fn load_birthdays(
+    conn: Arc<Mutex<SqliteConnection>>,
+    guild_id: Option<String>,
+) -> Result<Vec<(String, String, u32, u32, i32)>> {
+    let conn_guard = conn.lock().await;
+    let birthdays = conn_guard
+        .call(move |conn| {
+            let mut stmt = conn.prepare(
+                "SELECT user_id, display_name, month, day, last_announced_year FROM birthdays
+                 WHERE ?1 IS NULL OR guild_id = ?1",
+            )?;
+            let rows = stmt.query_map([&guild_id], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, u32>(2)?,
+                    row.get::<_, u32>(3)?,
+                    row.get::<_, i32>(4)?,
+                ))
+            })?;
+            let result: Vec<_> = rows.collect::<Result<Vec<_>, _>>()?;
+            Ok::<_, rusqlite::Error>(result)
+        })
+        .await?;
+    Ok(birthdays)
+}
+
+// This function will be called from main.rs to handle the !birthday command
+pub async fn handle_birthday_command(
+    http: &Http,
+    msg: &Message,
+    args: &[&str],
+    message_db: Arc<Mutex<SqliteConnection>>,
+    utc_offset: Option<FixedOffset>,
+) -> Result<()> {
+    let usage = "Usage: !birthday set MM-DD | !birthday list | !birthday remove";
+    let user_id = msg.author.id.to_string();
+
+    match args.first().map(|a| a.to_lowercase()).as_deref() {
+        Some("set") => {
+            let Some((month, day)) = args.get(1).and_then(|d| parse_birthday(d)) else {
+                msg.reply(
+                    http,
+                    "Please give your birthday as MM-DD, e.g. `!birthday set 03-14`.",
+                )
+                .await?;
+                return Ok(());
+            };
+
+            let display_name = msg
+                .author
+                .global_name
+                .clone()
+                .unwrap_or_else(|| msg.author.name.clone());
+            let guild_id = msg.guild_id.map(|id| id.to_string()).unwrap_or_default();
+
+            set_birthday(message_db, user_id, guild_id, display_name, month, day).await?;
+            info!(
+                "Stored birthday {:02}-{:02} for {}",
+                month, day, msg.author.name
+            );
+
+            let note = if month == 2 && day == 29 {
+                " (I'll celebrate on February 28 in non-leap years)"
+            } else {
+                ""
+            };
+            msg.reply(
+                http,
+                format!(
+                    "Got it! I'll remember your birthday on {}{note} 🎂",
+                    format_month_day(month, day)
+                ),
+            )
+            .await?;
+        }
+        Some("remove") | Some("clear") => {
+            let reply = if remove_birthday(message_db, user_id).await? {
+                "Your birthday has been forgotten."
+            } else {
+                "I don't have a birthday stored for you."
+            };
+            msg.reply(http, reply).await?;
+        }
+        Some("list") => {
+            let today = today_in(utc_offset);
+            let guild_id = msg.guild_id.map(|id| id.to_string());
+            let mut upcoming: Vec<(NaiveDate, String)> = load_birthdays(message_db, guild_id)
+                .await?
+                .into_iter()
+                .filter_map(|(_, name, month, day, _)| {
+                    next_celebration(month, day, today).map(|date| (date, name))
+                })
+                .collect();
+            upcoming.sort();
+            upcoming.truncate(UPCOMING_LIMIT);
+
+            if upcoming.is_empty() {
+                msg.channel_id
+                    .say(
+                        http,
+                        "No birthdays stored yet. Add yours with `!birthday set MM-DD`.",
+                    )
+                    .await?;
+                return Ok(());
+            }
+
+            let lines = upcoming
+                .iter()
+                .map(|(date, name)| {
+                    let when = if *date == today {
+                        "today! 🎉".to_string()
+                    } else {
+                        format_month_day(date.month(), date.day())
+                    };
+                    format!("- {name}: {when}")
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+            msg.channel_id
+                .send_message(
+                    http,
+                    crate::utils::no_ping_message(format!("Upcoming birthdays:\n{lines}")),
+                )
+                .await?;
+        }
+        _ => {
+            msg.reply(http, usage).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Announce any birthdays falling on `today` that haven't been announced this year yet.
+/// Returns how many were announced.
+pub async fn announce_birthdays(
+    http: &Http,
+    conn: Arc<Mutex<SqliteConnection>>,
+    channel_id: ChannelId,
+    today: NaiveDate,
+) -> Result<usize> {
+    let due: Vec<_> = load_birthdays(conn.clone(), None)
+        .await?
+        .into_iter()
+        .filter(|(_, _, month, day, last_year)| {
+            *last_year < today.year() && is_birthday_on(*month, *day, today)
+        })
+        .collect();
+
+    for (user_id, name, _, _, _) in &due {
+        let message = format!("🎂 Happy birthday, <@{user_id}>! 🎉");
+        if let Err(e) = channel_id.say(http, &message).await {
+            error!("Error announcing birthday for {}: {:?}", name, e);
+            continue;
+        }
+        info!("Announced birthday for {}", name);
+
+        // Mark it done so restarts later in the day don't announce it again
+        let user_id = user_id.clone();
+        let year = today.year();
+        let conn_guard = conn.lock().await;
+        conn_guard
+            .call(move |conn| {
+                conn.execute(
+                    "UPDATE birthdays SET last_announced_year = ?1 WHERE user_id = ?2",
+                    rusqlite::params![year, user_id],
+                )?;
+                Ok::<_, rusqlite::Error>(())
+            })
+            .await?;
+    }
+
+    Ok(due.len())
+}
+
+/// Daily birthday scheduler: checks periodically and announces once per birthday per year
+pub async fn run_birthday_scheduler(
+    http: Arc<Http>,
+    conn: Arc<Mutex<SqliteConnection>>,
+    channel_id: ChannelId,
+    utc_offset: Option<FixedOffset>,
+) {
+    loop {
+        let today = today_in(utc_offset);
+        if let Err(e) = announce_birthdays(&http, conn.clone(), channel_id, today).await {
+            error!("Error running birthday check: {:?}", e);
+        }
+        tokio::time::sleep(BIRTHDAY_CHECK_INTERVAL).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn test_parse_birthday() {
+        assert_eq!(parse_birthday("03-14"), Some((3, 14)));
+        assert_eq!(parse_birthday("3/4"), Some((3, 4)));
+        assert_eq!(parse_birthday("02-29"), Some((2, 29)));
+        assert_eq!(parse_birthday("02-30"), None);
+        assert_eq!(parse_birthday("13-01"), None);
+        assert_eq!(parse_birthday("march 14"), None);
+    }
+
+    #[test]
+    fn test_birthday_date_matching_with_leap_years() {
+        assert!(is_birthday_on(3, 14, date(2025, 3, 14)));
+        assert!(!is_birthday_on(3, 14, date(2025, 3, 15)));
+
+        // Feb 29 birthdays land on Feb 29 in leap years and Feb 28 otherwise
+        assert!(is_birthday_on(2, 29, date(2024, 2, 29)));
+        assert!(!is_birthday_on(2, 29, date(2024, 2, 28)));
+        assert!(is_birthday_on(2, 29, date(2025, 2, 28)));
+        assert!(!is_birthday_on(2, 29, date(2025, 3, 1)));
+        assert!(is_birthday_on(2, 28, date(2025, 2, 28)));
+
+        // Upcoming dates roll over into next year
+        assert_eq!(
+            next_celebration(3, 14, date(2025, 3, 14)),
+            Some(date(2025, 3, 14))
+        );
+        assert_eq!(
+            next_celebration(1, 2, date(2025, 3, 14)),
+            Some(date(2026, 1, 2))
+        );
+        assert_eq!(
+            next_celebration(2, 29, date(2027, 3, 1)),
+            Some(date(2028, 2, 29))
+        );
+        assert_eq!(
+            next_celebration(2, 29, date(2025, 1, 1)),
+            Some(date(2025, 2, 28))
+        );
+    }
+}
@@ -0,0 +1,489 @@
+use anyhow::Result;
+use serenity::all::Http;
+use serenity::model::channel::Message;
+use std::fmt;
+
+// Longest expression we'll try to evaluate
+const MAX_EXPRESSION_CHARS: usize = 200;
+// Deepest nesting of parentheses/unary operators, so nothing can blow the stack
+const MAX_NESTING: usize = 50;
+
+/// Why an expression couldn't be evaluated, worded for the channel
+#[derive(Debug, Clone, PartialEq)]
+pub enum CalcError {
+    Empty,
+    TooLong,
+    TooDeep,
+    UnexpectedChar(char),
+    UnexpectedEnd,
+    UnexpectedToken(String),
+    UnknownName(String),
+    DivisionByZero,
+    Overflow,
+    Domain(&'static str),
+}
+
+impl fmt::Display for CalcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CalcError::Empty => write!(
+                f,
+                "Give me something to calculate, e.g. `!calc 3 * (4 + 5)`"
+            ),
+            CalcError::TooLong => write!(
+                f,
+                "That expression is too long (max {MAX_EXPRESSION_CHARS} characters)"
+            ),
+            CalcError::TooDeep => write!(f, "That expression is nested too deeply"),
+            CalcError::UnexpectedChar(c) => write!(f, "I don't understand '{c}'"),
+            CalcError::UnexpectedEnd => write!(f, "The expression ends too early"),
+            CalcError::UnexpectedToken(t) => write!(f, "Unexpected '{t}'"),
+            CalcError::UnknownName(name) => write!(f, "Unknown function or constant '{name}'"),
+            CalcError::DivisionByZero => write!(f, "Division by zero"),
+            CalcError::Overflow => write!(f, "The result is too large to calculate"),
+            CalcError::Domain(why) => write!(f, "Math error: {why}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Ident(String),
+    Plus,
+    Minus,
+    Star,
+    Slash,
+    Percent,
+    Caret,
+    LParen,
+    RParen,
+    Comma,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Number(n) => write!(f, "{n}"),
+            Token::Ident(name) => write!(f, "{name}"),
+            Token::Plus => write!(f, "+"),
+            Token::Minus => write!(f, "-"),
+            Token::Star => write!(f, "*"),
+            Token::Slash => write!(f, "/"),
+            Token::Percent => write!(f, "%"),
+            Token::Caret => write!(f, "^"),
+            Token::LParen => write!(f, "("),
+            Token::RParen => write!(f, ")"),
+            Token::Comma => write!(f, ","),
+        }
+    }
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, CalcError> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            c if c.is_whitespace() => {
+                i += 1;
+                continue;
+            }
+            '0'..='9' | '.' => {
+                let start = i;
+                while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                    i += 1;
+                }
+                // Scientific notation: 1e6, 2.5E-3
+                if i < chars.len() && (chars[i] == 'e' || chars[i] == 'E') {
+                    let mut j = i + 1;
+                    if j < chars.len() && (chars[j] == '+' || chars[j] == '-') {
+                        j += 1;
+                    }
+                    if j < chars.len() && chars[j].is_ascii_digit() {
+                        i = j;
+                        while i < chars.len() && chars[i].is_ascii_digit() {
+                            i += 1;
+                        }
+                    }
+                }
+                let text: String = chars[start..i].iter().collect();
+                let number = text
+                    .parse::<f64>()
+                    .map_err(|_| CalcError::UnexpectedToken(text.clone()))?;
+                tokens.push(Token::Number(number));
+                continue;
+            }
+            c if c.is_ascii_alphabetic() => {
+                let start = i;
+                while i < chars.len() && chars[i].is_ascii_alphanumeric() {
+                    i += 1;
+                }
+                let name: String = chars[start..i].iter().collect();
+                tokens.push(Token::Ident(name.to_lowercase()));
+                continue;
+            }
+            '+' => tokens.push(Token::Plus),
+            '-' | '−' => tokens.push(Token::Minus),
+            '*' | '×' | '·' => {
+                // Treat ** as exponentiation
+                if chars.get(i + 1) == Some(&'*') {
+                    i += 1;
+                    tokens.push(Token::Caret);
+                } else {
+                    tokens.push(Token::Star);
+                }
+            }
+            '/' | '÷' => tokens.push(Token::Slash),
+            '%' => tokens.push(Token::Percent),
+            '^' => tokens.push(Token::Caret),
+            '(' => tokens.push(Token::LParen),
+            ')' => tokens.push(Token::RParen),
+            ',' => tokens.push(Token::Comma),
+            other => return Err(CalcError::UnexpectedChar(other)),
+        }
+        i += 1;
+    }
+
+    Ok(tokens)
+}
+
+// Recursive descent parser that evaluates as it goes:
+//   expr    := term (('+' | '-') term)*
+//   term    := unary (('*' | '/' | '%') unary)*
+//   unary   := ('+' | '-') unary | power
+//   power   := primary ('^' unary)?        (right associative, binds tighter than unary minus)
+//   primary := number | name | name '(' args ')' | '(' expr ')'
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    depth: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<(), CalcError> {
+        match self.next() {
+            Some(token) if token == expected => Ok(()),
+            Some(token) => Err(CalcError::UnexpectedToken(token.to_string())),
+            None => Err(CalcError::UnexpectedEnd),
+        }
+    }
+
+    fn enter(&mut self) -> Result<(), CalcError> {
+        self.depth += 1;
+        if self.depth > MAX_NESTING {
+            return Err(CalcError::TooDeep);
+        }
+        Ok(())
+    }
+
+    fn expr(&mut self) -> Result<f64, CalcError> {
+        let mut value = self.term()?;
+        loop {
+            match self.peek() {
+                Some(Token::Plus) => {
+                    self.next();
+                    value = checked(value + self.term()?)?;
+                }
+                Some(Token::Minus) => {
+                    self.next();
+                    value = checked(value - self.term()?)?;
+                }
+                _ => return Ok(value),
+            }
+        }
+    }
+
+    fn term(&mut self) -> Result<f64, CalcError> {
+        let mut value = self.unary()?;
+        loop {
+            match self.peek() {
+                Some(Token::Star) => {
+                    self.next();
+                    value = checked(value * self.unary()?)?;
+                }
+                Some(Token::Slash) => {
+                    self.next();
+                    let divisor = self.unary()?;
+                    if divisor == 0.0 {
+                        return Err(CalcError::DivisionByZero);
+                    }
+                    value = checked(value / divisor)?;
+                }
+                Some(Token::Percent) => {
+                    self.next();
+                    let divisor = self.unary()?;
+                    if divisor == 0.0 {
+                        return Err(CalcError::DivisionByZero);
+                    }
+                    value = checked(value % divisor)?;
+                }
+                _ => return Ok(value),
+            }
+        }
+    }
+
+    fn unary(&mut self) -> Result<f64, CalcError> {
+        match self.peek() {
+            Some(Token::Minus) => {
+                self.next();
+                self.enter()?;
+                let value = -self.unary()?;
+                self.depth -= 1;
+                Ok(value)
+            }
+            Some(Token::Plus) => {
+                self.next();
+                self.enter()?;
+                let value = self.unary()?;
+                self.depth -= 1;
+                Ok(value)
+            }
+            _ => self.power(),
+        }
+    }
+
+    fn power(&mut self) -> Result<f64, CalcError> {
+        let base = self.primary()?;
+        if self.peek() == Some(&Token::Caret) {
+            self.next();
+            self.enter()?;
+            let exponent = self.unary()?;
+            self.depth -= 1;
+            if base == 0.0 && exponent < 0.0 {
+                return Err(CalcError::DivisionByZero);
+            }
+            if base < 0.0 && exponent.fract() != 0.0 {
+                return Err(CalcError::Domain("fractional power of a negative number"));
+            }
+            return checked(base.powf(exponent));
+        }
+        Ok(base)
+    }
+
+    fn primary(&mut self) -> Result<f64, CalcError> {
+        match self.next() {
+            Some(Token::Number(n)) => Ok(n),
+            Some(Token::LParen) => {
+                self.enter()?;
+                let value = self.expr()?;
+                self.expect(Token::RParen)?;
+                self.depth -= 1;
+                Ok(value)
+            }
+            Some(Token::Ident(name)) => {
+                if self.peek() == Some(&Token::LParen) {
+                    self.next();
+                    self.enter()?;
+                    let mut args = vec![self.expr()?];
+                    while self.peek() == Some(&Token::Comma) {
+                        self.next();
+                        args.push(self.expr()?);
+                    }
+                    self.expect(Token::RParen)?;
+                    self.depth -= 1;
+                    call_function(&name, &args)
+                } else {
+                    constant(&name)
+                }
+            }
+            Some(token) => Err(CalcError::UnexpectedToken(token.to_string())),
+            None => Err(CalcError::UnexpectedEnd),
+        }
+    }
+}
+
+fn checked(value: f64) -> Result<f64, CalcError> {
+    if value.is_finite() {
+        Ok(value)
+    } else {
+        Err(CalcError::Overflow)
+    }
+}
+
+fn constant(name: &str) -> Result<f64, CalcError> {
+    match name {
+        "pi" => Ok(std::f64::consts::PI),
+        "e" => Ok(std::f64::consts::E),
+        "tau" => Ok(std::f64::consts::TAU),
+        _ => Err(CalcError::UnknownName(name.to_string())),
+    }
+}
+
+fn call_function(name: &str, args: &[f64]) -> Result<f64, CalcError> {
+    let one = || match args {
+        [x] => Ok(*x),
+        _ => Err(CalcError::UnexpectedToken(",".to_string())),
+    };
+
+    let value = match name {
+        "sqrt" => {
+            let x = one()?;
+            if x < 0.0 {
+                return Err(CalcError::Domain("square root of a negative number"));
+            }
+            x.sqrt()
+        }
+        "ln" | "log" | "log10" | "log2" => {
+            let x = one()?;
+            if x <= 0.0 {
+                return Err(CalcError::Domain(
+                    "logarithm of a number that isn't positive",
+                ));
+            }
+            match name {
+                "ln" => x.ln(),
+                "log2" => x.log2(),
+                _ => x.log10(),
+            }
+        }
+        "sin" => one()?.sin(),
+        "cos" => one()?.cos(),
+        "tan" => one()?.tan(),
+        "asin" | "acos" => {
+            let x = one()?;
+            if !(-1.0..=1.0).contains(&x) {
+                return Err(CalcError::Domain(
+                    "inverse sine/cosine needs a value from -1 to 1",
+                ));
+            }
+            if name == "asin" {
+                x.asin()
+            } else {
+                x.acos()
+            }
+        }
+        "atan" => one()?.atan(),
+        "abs" => one()?.abs(),
+        "exp" => one()?.exp(),
+        "floor" => one()?.floor(),
+        "ceil" => one()?.ceil(),
+        "round" => one()?.round(),
+        "min" | "max" if !args.is_empty() => {
+            let fold = if name == "min" { f64::min } else { f64::max };
+            args.iter().copied().fold(args[0], fold)
+        }
+        _ => return Err(CalcError::UnknownName(name.to_string())),
+    };
+
+    checked(value)
+}
+
+/// Evaluate an arithmetic expression
+pub fn evaluate(expression: &str) -> Result<f64, CalcError> {
+    let expression = expression.trim();
+    if expression.is_empty() {
+        return Err(CalcError::Empty);
+    }
+    if expression.chars().count() > MAX_EXPRESSION_CHARS {
+        return Err(CalcError::TooLong);
+    }
+
+    let mut parser = Parser {
+        tokens: tokenize(expression)?,
+        pos: 0,
+        depth: 0,
+    };
+    let value = parser.expr()?;
+    match parser.next() {
+        None => Ok(value),
+        Some(token) => Err(CalcError::UnexpectedToken(token.to_string())),
+    }
+}
+
+/// Format a result without float noise: whole numbers without a decimal point,
+/// others to at most 10 decimal places, very large or small ones in scientific notation
+pub fn format_result(value: f64) -> String {
+    if value == 0.0 {
+        return "0".to_string();
+    }
+    let magnitude = value.abs();
+    if !(1e-6..1e15).contains(&magnitude) {
+        return format!("{value:e}");
+    }
+    if value.fract() == 0.0 {
+        return format!("{value:.0}");
+    }
+
+    let formatted = format!("{value:.10}");
+    let formatted = formatted.trim_end_matches('0').trim_end_matches('.');
+    if formatted == "-0" {
+        "0".to_string()
+    } else {
+        formatted.to_string()
+    }
+}
+
+// This function will be called from main.rs to handle the !calc command
+pub async fn handle_calc_command(http: &Http, msg: &Message, expression: &str) -> Result<()> {
+    let reply = match evaluate(expression) {
+        Ok(value) => format!("`{}` = **{}**", expression.trim(), format_result(value)),
+        Err(e) => e.to_string(),
+    };
+    msg.reply(http, reply).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_operator_precedence_and_parentheses() {
+        assert_eq!(evaluate("3 * (4 + 5) / 2"), Ok(13.5));
+        assert_eq!(evaluate("2 + 3 * 4"), Ok(14.0));
+        assert_eq!(evaluate("(2 + 3) * 4"), Ok(20.0));
+        assert_eq!(evaluate("10 - 4 - 3"), Ok(3.0));
+        assert_eq!(evaluate("2 ^ 3 ^ 2"), Ok(512.0));
+        assert_eq!(evaluate("2 ** 10"), Ok(1024.0));
+        assert_eq!(evaluate("-2 ^ 2"), Ok(-4.0));
+        assert_eq!(evaluate("2 ^ -1"), Ok(0.5));
+        assert_eq!(evaluate("6 ÷ 3 × 2 − 1"), Ok(3.0));
+        assert_eq!(evaluate("17 % 5"), Ok(2.0));
+        assert_eq!(evaluate("((1 + 2) * (3 + 4))"), Ok(21.0));
+        assert_eq!(evaluate("sqrt(16) + abs(-2)"), Ok(6.0));
+        assert_eq!(evaluate("max(1, 7, 3)"), Ok(7.0));
+        assert_eq!(evaluate("1.5e3"), Ok(1500.0));
+        assert!((evaluate("sin(pi / 2)").unwrap() - 1.0).abs() < 1e-12);
+
+        assert_eq!(format_result(evaluate("0.1 + 0.2").unwrap()), "0.3");
+        assert_eq!(format_result(13.5), "13.5");
+        assert_eq!(format_result(1024.0), "1024");
+        assert_eq!(format_result(1e20), "1e20");
+    }
+
+    #[test]
+    fn test_calc_errors() {
+        assert_eq!(evaluate("1 / 0"), Err(CalcError::DivisionByZero));
+        assert_eq!(evaluate("5 % (2 - 2)"), Err(CalcError::DivisionByZero));
+        assert_eq!(evaluate("10 ^ 400"), Err(CalcError::Overflow));
+        assert_eq!(evaluate("exp(1000)"), Err(CalcError::Overflow));
+        assert!(matches!(evaluate("sqrt(-1)"), Err(CalcError::Domain(_))));
+        assert!(matches!(evaluate("(-8) ^ 0.5"), Err(CalcError::Domain(_))));
+        assert_eq!(evaluate(""), Err(CalcError::Empty));
+        assert_eq!(evaluate("(1 + 2"), Err(CalcError::UnexpectedEnd));
+        assert_eq!(
+            evaluate("1 + 2)"),
+            Err(CalcError::UnexpectedToken(")".into()))
+        );
+        assert_eq!(evaluate("2 $ 3"), Err(CalcError::UnexpectedChar('$')));
+        assert_eq!(
+            evaluate("system(1)"),
+            Err(CalcError::UnknownName("system".into()))
+        );
+        assert_eq!(evaluate(&"(".repeat(100)), Err(CalcError::TooDeep));
+        assert_eq!(evaluate(&"1+".repeat(150)), Err(CalcError::TooLong));
+    }
+}
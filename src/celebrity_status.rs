@@ -7,9 +7,12 @@ use serenity::all::Http;
 use serenity::model::channel::Message;
 use tracing::{error, info};
 
+use crate::wikipedia::{self, Wikipedia};
+
 pub async fn handle_aliveordead_command(
     http: &Http,
     msg: &Message,
+    wikipedia: &Wikipedia,
     celebrity_name: &str,
 ) -> Result<()> {
     info!("Handling !alive command for celebrity: {}", celebrity_name);
@@ -20,7 +23,7 @@ pub async fn handle_aliveordead_command(
     }
 
     // Search for the celebrity using the Wikipedia API
-    match search_celebrity(celebrity_name).await {
+    match search_celebrity(wikipedia, celebrity_name).await {
         Ok(Some((result, thumbnail_url))) => {
             // Send the result with an embed if we have a thumbnail
             if let Some(image_url) = thumbnail_url {
@@ -278,7 +281,7 @@ fn is_fictional_character(text: &str, title: &str) -> bool {
 async fn find_actor_for_character(
     text: &str,
     character_name: &str,
-    client: &Client,
+    wikipedia: &Wikipedia,
 ) -> Result<Option<String>> {
     let text_lower = text.to_lowercase();
 
@@ -384,7 +387,7 @@ async fn find_actor_for_character(
                     info!("Found potential actor: {}", actor_name);
 
                     // Get information about this actor
-                    if let Ok(Some(actor_info)) = search_actor(actor_name, client).await {
+                    if let Ok(Some(actor_info)) = search_actor(actor_name, wikipedia).await {
                         return Ok(Some(format!(
                             "The character is most famously portrayed by {actor_info}."
                         )));
@@ -398,32 +401,18 @@ async fn find_actor_for_character(
     let search_query = format!("{character_name} actor");
     info!("Trying direct search for actor: {}", search_query);
 
-    // Search for the actor
-    let search_url = format!(
-        "https://en.wikipedia.org/w/api.php?action=query&list=search&srsearch={}&format=json&srlimit=1",
-        urlencoding::encode(&search_query)
-    );
-
-    let search_response = client.get(&search_url).send().await?;
-    let search_json: Value = search_response.json().await?;
-
-    // Extract the page title from search results
-    if let Some(title) = search_json
-        .get("query")
-        .and_then(|q| q.get("search"))
-        .and_then(|s| s.get(0))
-        .and_then(|r| r.get("title"))
-        .and_then(|t| t.as_str())
-    {
+    // Take the top search result
+    if let Some(hit) = wikipedia.search(&search_query).await?.first() {
         // Check if this looks like an actor's name (not the character again)
-        if !title
+        if !hit
+            .title
             .to_lowercase()
             .contains(&character_name.to_lowercase())
         {
-            info!("Found potential actor via search: {}", title);
+            info!("Found potential actor via search: {}", hit.title);
 
             // Get information about this actor
-            if let Ok(Some(actor_info)) = search_actor(title, client).await {
+            if let Ok(Some(actor_info)) = search_actor(&hit.title, wikipedia).await {
                 return Ok(Some(format!(
                     "The character is most famously portrayed by {actor_info}."
                 )));
@@ -435,70 +424,13 @@ async fn find_actor_for_character(
 }
 
 // Function to search for information about an actor
-async fn search_actor(name: &str, client: &Client) -> Result<Option<String>> {
-    // Search for the actor's page
-    let search_url = format!(
-        "https://en.wikipedia.org/w/api.php?action=query&list=search&srsearch={}&format=json&srlimit=1",
-        urlencoding::encode(name)
-    );
-
-    let search_response = client.get(&search_url).send().await?;
-    let search_json: Value = search_response.json().await?;
-
-    // Extract the page title from search results
-    let page_title = match search_json
-        .get("query")
-        .and_then(|q| q.get("search"))
-        .and_then(|s| s.get(0))
-        .and_then(|r| r.get("title"))
-        .and_then(|t| t.as_str())
-    {
-        Some(title) => title,
-        None => {
-            info!("No search results found for actor: {}", name);
-            return Ok(None);
-        }
-    };
-
-    // Now get the page content
-    let page_url = format!(
-        "https://en.wikipedia.org/w/api.php?action=query&prop=extracts&exintro&explaintext&redirects=1&titles={}&format=json",
-        urlencoding::encode(page_title)
-    );
-
-    let page_response = client.get(&page_url).send().await?;
-    let page_json: Value = page_response.json().await?;
-
-    // Extract the page ID
-    let pages = match page_json.get("query").and_then(|q| q.get("pages")) {
-        Some(p) => p,
-        None => {
-            info!("No page data found for actor: {}", page_title);
-            return Ok(None);
-        }
-    };
-
-    // Get the first page (there should only be one)
-    let page_id = match pages.as_object().and_then(|o| o.keys().next()) {
-        Some(id) => id,
-        None => {
-            info!("No page ID found for actor: {}", page_title);
-            return Ok(None);
-        }
-    };
-
-    // Extract the extract (page content)
-    let raw_extract = match pages
-        .get(page_id)
-        .and_then(|p| p.get("extract"))
-        .and_then(|e| e.as_str())
-    {
-        Some(e) => e,
-        None => {
-            info!("No extract found for actor page: {}", page_title);
-            return Ok(None);
-        }
+async fn search_actor(name: &str, wikipedia: &Wikipedia) -> Result<Option<String>> {
+    let Some(page) = wikipedia.find_intro(name, |_, _| false).await? else {
+        info!("No search results found for actor: {}", name);
+        return Ok(None);
     };
+    let page_title = page.title.as_str();
+    let raw_extract = page.extract.as_str();
 
     // Check if this is a person
     let is_person = raw_extract.contains(" born ")
@@ -538,12 +470,46 @@ async fn search_actor(name: &str, client: &Client) -> Result<Option<String>> {
     Ok(Some(actor_info))
 }
 
-async fn search_celebrity(name: &str) -> Result<Option<(String, Option<String>)>> {
+// Whether a search result's title and (lowercased) snippet look like they're about a real
+// person rather than a character or a work
+fn looks_like_real_person(title: &str, snippet: &str) -> bool {
+    // Prefer results with biographical indicators in the snippet
+    let has_bio_indicator = snippet.contains("born")
+        || snippet.contains("died")
+        || snippet.contains("was a")
+        || snippet.contains("is a")
+        || snippet.contains("writer")
+        || snippet.contains("author")
+        || snippet.contains("actor")
+        || snippet.contains("musician")
+        || snippet.contains("politician")
+        || snippet.contains("scientist")
+        || snippet.contains("professional")
+        || snippet.contains("athlete")
+        || snippet.contains("singer")
+        || snippet.contains("comedian")
+        || snippet.contains("director")
+        || snippet.contains("engineer")
+        || snippet.contains("businessman");
+
+    // Skip results that look like fictional characters
+    let looks_fictional = snippet.contains("fictional character")
+        || snippet.contains("character in")
+        || title.contains("(character)")
+        || title.contains("(film)");
+
+    has_bio_indicator && !looks_fictional
+}
+
+async fn search_celebrity(
+    wikipedia: &Wikipedia,
+    name: &str,
+) -> Result<Option<(String, Option<String>)>> {
     const MAX_RETRIES: usize = 5;
     const INITIAL_DELAY_MS: u64 = 1000; // 1 second
 
     for attempt in 0..MAX_RETRIES {
-        match search_celebrity_attempt(name).await {
+        match search_celebrity_attempt(wikipedia, name).await {
             Ok(result) => return Ok(result),
             Err(e) => {
                 if attempt == MAX_RETRIES - 1 {
@@ -568,216 +534,17 @@ async fn search_celebrity(name: &str) -> Result<Option<(String, Option<String>)>
     unreachable!()
 }
 
-async fn search_celebrity_attempt(name: &str) -> Result<Option<(String, Option<String>)>> {
-    let client = Client::builder()
-        .user_agent("CrowBot/1.0 (https://github.com/mwstowe/crowtdiscordbot)")
-        .build()?;
-
-    // First, search for the page - get multiple results to find the best match
-    let search_url = format!(
-        "https://en.wikipedia.org/w/api.php?action=query&list=search&srsearch={}&format=json&srlimit=5",
-        urlencoding::encode(name)
-    );
-
-    info!("Searching Wikipedia for: {}", name);
-    let search_response = client.get(&search_url).send().await?;
-
-    // Check if we got a successful HTTP response
-    if !search_response.status().is_success() {
-        error!(
-            "Wikipedia API returned HTTP {}: {}",
-            search_response.status(),
-            search_response
-                .status()
-                .canonical_reason()
-                .unwrap_or("Unknown")
-        );
-        return Err(anyhow::anyhow!(
-            "Wikipedia API returned HTTP {}",
-            search_response.status()
-        ));
-    }
-
-    // Get response text first to log it if JSON parsing fails
-    let response_text = search_response.text().await?;
-    let search_json: Value = match serde_json::from_str(&response_text) {
-        Ok(json) => json,
-        Err(e) => {
-            error!(
-                "Failed to parse Wikipedia search response as JSON. Response was: {}",
-                response_text.chars().take(200).collect::<String>()
-            );
-            return Err(anyhow::anyhow!("JSON parsing failed: {}", e));
-        }
-    };
-
-    // Extract search results and find the best person match
-    let search_results = match search_json
-        .get("query")
-        .and_then(|q| q.get("search"))
-        .and_then(|s| s.as_array())
-    {
-        Some(results) => results,
-        None => {
-            info!("No search results found for: {}", name);
-            return Ok(None);
-        }
-    };
-
-    if search_results.is_empty() {
-        info!("No search results found for: {}", name);
+async fn search_celebrity_attempt(
+    wikipedia: &Wikipedia,
+    name: &str,
+) -> Result<Option<(String, Option<String>)>> {
+    // Search for the page, preferring results that look like real people
+    let Some(page) = wikipedia.find_intro(name, looks_like_real_person).await? else {
         return Ok(None);
-    }
-
-    // Try each result, preferring ones that look like real people
-    // First: prefer a result whose title exactly matches the search query (case-insensitive)
-    let mut best_title: Option<&str> = None;
-    let name_lower = name.to_lowercase();
-    for result in search_results {
-        let title = result.get("title").and_then(|t| t.as_str()).unwrap_or("");
-        if title.to_lowercase() == name_lower {
-            info!("Found exact title match: {}", title);
-            best_title = Some(title);
-            break;
-        }
-    }
-
-    // Second pass: look for a result whose snippet contains biographical indicators
-    if best_title.is_none() {
-        for result in search_results {
-            let title = result.get("title").and_then(|t| t.as_str()).unwrap_or("");
-            let snippet = result
-                .get("snippet")
-                .and_then(|s| s.as_str())
-                .unwrap_or("")
-                .to_lowercase();
-
-            // Prefer results with biographical indicators in the snippet
-            let has_bio_indicator = snippet.contains("born")
-                || snippet.contains("died")
-                || snippet.contains("was a")
-                || snippet.contains("is a")
-                || snippet.contains("writer")
-                || snippet.contains("author")
-                || snippet.contains("actor")
-                || snippet.contains("musician")
-                || snippet.contains("politician")
-                || snippet.contains("scientist")
-                || snippet.contains("professional")
-                || snippet.contains("athlete")
-                || snippet.contains("singer")
-                || snippet.contains("comedian")
-                || snippet.contains("director")
-                || snippet.contains("engineer")
-                || snippet.contains("businessman");
-
-            // Skip results that look like fictional characters
-            let looks_fictional = snippet.contains("fictional character")
-                || snippet.contains("character in")
-                || title.contains("(character)")
-                || title.contains("(film)");
-
-            if has_bio_indicator && !looks_fictional {
-                info!("Found biographical result: {}", title);
-                best_title = Some(title);
-                break;
-            }
-        }
-    }
-
-    // Fall back to first result if no biographical match found
-    let page_title = match best_title {
-        Some(title) => title,
-        None => match search_results
-            .first()
-            .and_then(|r| r.get("title"))
-            .and_then(|t| t.as_str())
-        {
-            Some(title) => title,
-            None => {
-                info!("No search results found for: {}", name);
-                return Ok(None);
-            }
-        },
-    };
-
-    info!("Found Wikipedia page: {}", page_title);
-
-    // Now get the page content (including thumbnail image)
-    let page_url = format!(
-        "https://en.wikipedia.org/w/api.php?action=query&prop=extracts|pageprops|pageimages&exintro&explaintext&redirects=1&pithumbsize=300&titles={}&format=json",
-        urlencoding::encode(page_title)
-    );
-
-    let page_response = client.get(&page_url).send().await?;
-
-    // Check if we got a successful HTTP response
-    if !page_response.status().is_success() {
-        error!(
-            "Wikipedia page API returned HTTP {}: {}",
-            page_response.status(),
-            page_response
-                .status()
-                .canonical_reason()
-                .unwrap_or("Unknown")
-        );
-        return Err(anyhow::anyhow!(
-            "Wikipedia page API returned HTTP {}",
-            page_response.status()
-        ));
-    }
-
-    // Get response text first to log it if JSON parsing fails
-    let page_response_text = page_response.text().await?;
-    let page_json: Value = match serde_json::from_str(&page_response_text) {
-        Ok(json) => json,
-        Err(e) => {
-            error!(
-                "Failed to parse Wikipedia page response as JSON. Response was: {}",
-                page_response_text.chars().take(200).collect::<String>()
-            );
-            return Err(anyhow::anyhow!("JSON parsing failed: {}", e));
-        }
     };
-
-    // Extract the page ID
-    let pages = match page_json.get("query").and_then(|q| q.get("pages")) {
-        Some(p) => p,
-        None => {
-            info!("No page data found for: {}", page_title);
-            return Ok(None);
-        }
-    };
-
-    // Get the first page (there should only be one)
-    let page_id = match pages.as_object().and_then(|o| o.keys().next()) {
-        Some(id) => id,
-        None => {
-            info!("No page ID found for: {}", page_title);
-            return Ok(None);
-        }
-    };
-
-    // Extract the extract (page content)
-    let raw_extract = match pages
-        .get(page_id)
-        .and_then(|p| p.get("extract"))
-        .and_then(|e| e.as_str())
-    {
-        Some(e) => e,
-        None => {
-            info!("No extract found for page: {}", page_title);
-            return Ok(None);
-        }
-    };
-
-    // Extract thumbnail URL if available
-    let thumbnail_url = pages
-        .get(page_id)
-        .and_then(|p| p.get("thumbnail"))
-        .and_then(|t| t.get("source"))
-        .and_then(|s| s.as_str())
-        .map(|s| s.to_string());
+    let page_title = page.title.as_str();
+    let raw_extract = page.extract.as_str();
+    let thumbnail_url = page.thumbnail_url.clone();
 
     if let Some(ref url) = thumbnail_url {
         info!("Found thumbnail: {}", url);
@@ -804,7 +571,8 @@ async fn search_celebrity_attempt(name: &str) -> Result<Option<(String, Option<S
         info!("Detected fictional character: {}", page_title);
 
         // Try to find the actor associated with this character
-        if let Some(actor_info) = find_actor_for_character(raw_extract, page_title, &client).await?
+        if let Some(actor_info) =
+            find_actor_for_character(raw_extract, page_title, wikipedia).await?
         {
             return Ok(Some((
                 format!("**{page_title}** is a fictional character. {actor_info}"),
@@ -818,16 +586,9 @@ async fn search_celebrity_attempt(name: &str) -> Result<Option<(String, Option<S
         }
     }
 
-    // Get Wikidata ID from pageprops
-    let wikidata_id = pages
-        .get(page_id)
-        .and_then(|p| p.get("pageprops"))
-        .and_then(|pp| pp.get("wikibase_item"))
-        .and_then(|w| w.as_str());
-
     // Fetch structured data from Wikidata
-    let wikidata = if let Some(qid) = wikidata_id {
-        fetch_wikidata_person_info(&client, qid).await
+    let wikidata = if let Some(qid) = page.wikidata_id.as_deref() {
+        fetch_wikidata_person_info(wikipedia.client(), qid).await
     } else {
         None
     };
@@ -859,40 +620,7 @@ async fn search_celebrity_attempt(name: &str) -> Result<Option<(String, Option<S
 
     // Get a short description (first two sentences) from Wikipedia extract
     let (_, _, cleaned_extract) = extract_dates_from_parentheses(raw_extract);
-    let raw_parts: Vec<&str> = cleaned_extract.split('.').collect();
-    let mut sentences: Vec<String> = Vec::new();
-    let mut current = String::new();
-
-    for part in &raw_parts {
-        let trimmed = part.trim();
-        if trimmed.is_empty() {
-            continue;
-        }
-        if current.is_empty() {
-            current = trimmed.to_string();
-        } else {
-            let last_word = current.split_whitespace().last().unwrap_or("");
-            let ends_with_initial = last_word.len() <= 2
-                && last_word
-                    .chars()
-                    .all(|c| c.is_uppercase() || c.is_ascii_digit());
-
-            if ends_with_initial {
-                current.push_str(&format!(". {trimmed}"));
-            } else {
-                current.push('.');
-                sentences.push(current);
-                current = trimmed.to_string();
-                if sentences.len() >= 2 {
-                    break;
-                }
-            }
-        }
-    }
-    if !current.is_empty() && sentences.len() < 2 {
-        sentences.push(current);
-    }
-    let description = sentences.join(" ").trim().to_string();
+    let description = wikipedia::first_sentences(&cleaned_extract, 2);
 
     let mut response = format!("**{page_title}**: {description}");
 
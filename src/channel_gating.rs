@@ -0,0 +1,152 @@
+use serenity::all::{Channel, Http};
+use serenity::model::id::ChannelId;
+use std::collections::{HashMap, HashSet};
+
+/// Screenshot commands that share the `SCREENSHOT_CHANNELS` allowlist
+pub const SCREENSHOT_COMMANDS: &[&str] =
+    &["frinkiac", "morbotron", "masterofallscience", "subtitles"];
+
+/// The bits of a channel that gating decisions depend on
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ChannelInfo {
+    pub id: String,
+    pub name: String,
+    pub nsfw: bool,
+}
+
+impl ChannelInfo {
+    /// Look up a channel's name and NSFW flag. DMs and unreadable channels come back
+    /// with an empty name and `nsfw: false`.
+    pub async fn fetch(http: &Http, channel_id: ChannelId) -> Self {
+        let (name, nsfw) = match channel_id.to_channel(http).await {
+            Ok(Channel::Guild(guild_channel)) => (guild_channel.name, guild_channel.nsfw),
+            _ => (String::new(), false),
+        };
+
+        Self {
+            id: channel_id.to_string(),
+            name,
+            nsfw,
+        }
+    }
+}
+
+/// Per-command channel allowlists and NSFW requirements
+#[derive(Debug, Clone, Default)]
+pub struct CommandChannelRules {
+    allowlists: HashMap<String, Vec<String>>,
+    nsfw_only: HashSet<String>,
+}
+
+impl CommandChannelRules {
+    /// Restrict a command to these channels (names or IDs); an empty list means anywhere
+    pub fn set_allowed_channels(&mut self, command: &str, channels: &[String]) {
+        if channels.is_empty() {
+            self.allowlists.remove(command);
+        } else {
+            self.allowlists
+                .insert(command.to_string(), channels.to_vec());
+        }
+    }
+
+    /// Only allow a command in channels marked NSFW
+    pub fn require_nsfw(&mut self, command: &str) {
+        self.nsfw_only.insert(command.to_string());
+    }
+
+    /// Whether this command has any channel rules at all (so callers can skip the channel lookup)
+    pub fn is_gated(&self, command: &str) -> bool {
+        self.allowlists.contains_key(command) || self.nsfw_only.contains(command)
+    }
+
+    /// Whether the command may run in this channel
+    pub fn command_allowed_in(&self, channel: &ChannelInfo, command: &str) -> bool {
+        if self.nsfw_only.contains(command) && !channel.nsfw {
+            return false;
+        }
+
+        match self.allowlists.get(command) {
+            Some(allowed) => allowed
+                .iter()
+                .any(|c| *c == channel.name || *c == channel.id),
+            None => true,
+        }
+    }
+
+    /// Reply text explaining where the command can be used
+    pub fn denial_message(&self, command: &str) -> String {
+        let nsfw = self.nsfw_only.contains(command);
+        let where_allowed = match self.allowlists.get(command) {
+            Some(allowed) => {
+                let channels = allowed
+                    .iter()
+                    .map(|c| format!("#{c}"))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                if nsfw {
+                    format!("{channels} (and only when the channel is marked NSFW)")
+                } else {
+                    channels
+                }
+            }
+            None => "NSFW channels".to_string(),
+        };
+
+        format!("!{command} isn't allowed in this channel. It's only available in {where_allowed}.")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn channel(id: &str, name: &str, nsfw: bool) -> ChannelInfo {
+        ChannelInfo {
+            id: id.to_string(),
+            name: name.to_string(),
+            nsfw,
+        }
+    }
+
+    #[test]
+    fn test_command_allowed_in() {
+        let general = channel("1", "general", false);
+        let images = channel("2", "ai-images", false);
+        let late_night = channel("3", "late-night", true);
+
+        // No rules: everything allowed everywhere
+        let rules = CommandChannelRules::default();
+        assert!(!rules.is_gated("imagine"));
+        assert!(rules.command_allowed_in(&general, "imagine"));
+
+        // Allowlist by name or ID
+        let mut rules = CommandChannelRules::default();
+        rules.set_allowed_channels("imagine", &["ai-images".to_string(), "3".to_string()]);
+        assert!(rules.is_gated("imagine"));
+        assert!(!rules.command_allowed_in(&general, "imagine"));
+        assert!(rules.command_allowed_in(&images, "imagine"));
+        assert!(rules.command_allowed_in(&late_night, "imagine"));
+        assert!(rules.command_allowed_in(&general, "frinkiac"));
+
+        // NSFW requirement, alone and combined with an allowlist
+        rules.require_nsfw("frinkiac");
+        rules.require_nsfw("imagine");
+        assert!(!rules.command_allowed_in(&general, "frinkiac"));
+        assert!(rules.command_allowed_in(&late_night, "frinkiac"));
+        assert!(!rules.command_allowed_in(&images, "imagine"));
+        assert!(rules.command_allowed_in(&late_night, "imagine"));
+
+        // DMs have no name and aren't NSFW
+        assert!(!rules.command_allowed_in(&ChannelInfo::default(), "frinkiac"));
+
+        // Clearing an allowlist leaves the NSFW rule in place
+        rules.set_allowed_channels("imagine", &[]);
+        assert!(rules.command_allowed_in(&late_night, "imagine"));
+        assert!(!rules.command_allowed_in(&images, "imagine"));
+
+        assert_eq!(
+            rules.denial_message("frinkiac"),
+            "!frinkiac isn't allowed in this channel. It's only available in NSFW channels."
+        );
+    }
+}
@@ -0,0 +1,85 @@
+use serenity::all::{Channel, Http};
+use serenity::model::id::ChannelId;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tracing::debug;
+
+// Topics rarely change, so one lookup an hour per channel is plenty
+pub const CHANNEL_TOPIC_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// Per-channel cache of channel topics. Channels without a topic are cached too,
+/// so they don't trigger a lookup on every message.
+pub struct ChannelTopicCache {
+    ttl: Duration,
+    topics: std::sync::Mutex<HashMap<ChannelId, (Option<String>, Instant)>>,
+}
+
+impl ChannelTopicCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            topics: std::sync::Mutex::new(HashMap::new()),
+        }
+    }
+
+    // The cached topic if it's still fresh; the outer None means "not cached"
+    fn cached(&self, channel_id: ChannelId, now: Instant) -> Option<Option<String>> {
+        let topics = self.topics.lock().unwrap();
+        topics
+            .get(&channel_id)
+            .filter(|(_, fetched_at)| now.duration_since(*fetched_at) < self.ttl)
+            .map(|(topic, _)| topic.clone())
+    }
+
+    fn store(&self, channel_id: ChannelId, topic: Option<String>, now: Instant) {
+        self.topics.lock().unwrap().insert(channel_id, (topic, now));
+    }
+
+    /// The channel's topic, fetched from Discord when the cached copy is missing or stale
+    pub async fn get(&self, http: &Http, channel_id: ChannelId) -> Option<String> {
+        if let Some(topic) = self.cached(channel_id, Instant::now()) {
+            return topic;
+        }
+
+        let topic = match channel_id.to_channel(http).await {
+            Ok(Channel::Guild(guild_channel)) => guild_channel
+                .topic
+                .map(|topic| topic.trim().to_string())
+                .filter(|topic| !topic.is_empty()),
+            Ok(_) => None,
+            Err(e) => {
+                // Don't cache failures; try again next time
+                debug!("Couldn't fetch topic for channel {}: {:?}", channel_id, e);
+                return None;
+            }
+        };
+
+        self.store(channel_id, topic.clone(), Instant::now());
+        topic
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_topic_cache_expires() {
+        let cache = ChannelTopicCache::new(Duration::from_secs(60));
+        let channel = ChannelId::new(1);
+        let now = Instant::now();
+
+        assert_eq!(cache.cached(channel, now), None);
+
+        cache.store(channel, Some("Rust help".to_string()), now);
+        assert_eq!(
+            cache.cached(channel, now + Duration::from_secs(30)),
+            Some(Some("Rust help".to_string()))
+        );
+        assert_eq!(cache.cached(channel, now + Duration::from_secs(61)), None);
+
+        // "No topic" is remembered as well
+        cache.store(ChannelId::new(2), None, now);
+        assert_eq!(cache.cached(ChannelId::new(2), now), Some(None));
+    }
+}
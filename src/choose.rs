@@ -0,0 +1,123 @@
+use anyhow::Result;
+use rand::seq::IndexedRandom;
+use serenity::all::Http;
+use serenity::model::channel::Message;
+
+// Said before the pick, so the bot sounds like it thought about it
+const PREFIXES: &[&str] = &[
+    "I choose",
+    "Obviously",
+    "Easy:",
+    "No contest:",
+    "My gut says",
+    "After careful consideration:",
+    "The spirits have spoken:",
+    "If I had to bet my life on it,",
+];
+
+/// Split `pizza, tacos, or sushi` or `rock or paper or scissors` into its options.
+/// Case-insensitive duplicates are only counted once.
+pub fn split_options(text: &str) -> Vec<String> {
+    let mut options: Vec<String> = Vec::new();
+
+    for piece in text.split(',') {
+        let mut words = Vec::new();
+        // The trailing empty word ends the last option
+        for word in piece.split_whitespace().chain([""]) {
+            if word.is_empty() || word.eq_ignore_ascii_case("or") {
+                let option = words.join(" ");
+                words.clear();
+                if !option.is_empty()
+                    && !options
+                        .iter()
+                        .any(|existing| existing.to_lowercase() == option.to_lowercase())
+                {
+                    options.push(option);
+                }
+            } else {
+                words.push(word);
+            }
+        }
+    }
+
+    options
+}
+
+// What to say for some options
+fn choose_reply(options: &[String]) -> String {
+    let mut rng = rand::rng();
+    match options {
+        [] => "Give me something to choose from, e.g. `!choose pizza, tacos, sushi`".to_string(),
+        [only] => format!("That's not much of a choice, but sure: **{only}**"),
+        _ => {
+            let prefix = PREFIXES.choose(&mut rng).unwrap_or(&"I choose");
+            let choice = options.choose(&mut rng).map(String::as_str).unwrap_or("");
+            format!("{prefix} **{choice}**")
+        }
+    }
+}
+
+// This function will be called from main.rs to handle the !choose and !pick commands
+pub async fn handle_choose_command(http: &Http, msg: &Message, text: &str) -> Result<()> {
+    let reply = choose_reply(&split_options(text));
+    msg.reply(http, reply).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_on_commas() {
+        assert_eq!(
+            split_options("pizza, tacos,  sushi "),
+            vec!["pizza", "tacos", "sushi"]
+        );
+        assert_eq!(
+            split_options("fish and chips,curry, , "),
+            vec!["fish and chips", "curry"]
+        );
+    }
+
+    #[test]
+    fn test_split_on_or() {
+        assert_eq!(
+            split_options("rock or paper OR scissors"),
+            vec!["rock", "paper", "scissors"]
+        );
+        // An Oxford comma before the "or"
+        assert_eq!(
+            split_options("pizza, tacos, or sushi"),
+            vec!["pizza", "tacos", "sushi"]
+        );
+        // "or" inside a word doesn't split it
+        assert_eq!(
+            split_options("oranges or doritos"),
+            vec!["oranges", "doritos"]
+        );
+        assert_eq!(split_options("stay or"), vec!["stay"]);
+    }
+
+    #[test]
+    fn test_degenerate_choices() {
+        assert!(split_options("").is_empty());
+        assert!(split_options(" , or ,").is_empty());
+        assert_eq!(split_options("Pizza or pizza"), vec!["Pizza"]);
+
+        assert!(choose_reply(&[]).starts_with("Give me something"));
+        assert_eq!(
+            choose_reply(&["pizza".to_string()]),
+            "That's not much of a choice, but sure: **pizza**"
+        );
+
+        let options = split_options("pizza, tacos, sushi");
+        let reply = choose_reply(&options);
+        assert!(
+            options
+                .iter()
+                .any(|option| reply.ends_with(&format!("**{option}**"))),
+            "{reply}"
+        );
+    }
+}
@@ -0,0 +1,266 @@
+use serenity::model::channel::Message;
+use serenity::model::id::{MessageId, UserId};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+/// Reaction a user adds to a cooldown notice to have the command run once the cooldown ends
+pub const RETRY_EMOJI: &str = "🔁";
+
+// How long a cooldown notice keeps listening for the retry reaction after the cooldown ends
+const RETRY_WINDOW: Duration = Duration::from_secs(10 * 60);
+
+/// How often cooldowns that have run out are dropped
+pub const PRUNE_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// How much of a cooldown is left, `now` being some time after the command was last used
+pub fn remaining_cooldown(last_used: Instant, now: Instant, cooldown: Duration) -> Duration {
+    cooldown.saturating_sub(now.saturating_duration_since(last_used))
+}
+
+/// A wait rounded up to whole seconds, e.g. "1 second" or "2 minutes 5 seconds"
+pub fn format_wait(wait: Duration) -> String {
+    let secs = wait.as_secs() + u64::from(wait.subsec_nanos() > 0);
+    let secs = secs.max(1);
+    let plural = |n: u64, unit: &str| format!("{n} {unit}{}", if n == 1 { "" } else { "s" });
+
+    match (secs / 60, secs % 60) {
+        (0, s) => plural(s, "second"),
+        (m, 0) => plural(m, "minute"),
+        (m, s) => format!("{} {}", plural(m, "minute"), plural(s, "second")),
+    }
+}
+
+/// Parse per-command cooldowns from comma-separated `command=seconds` pairs, skipping
+/// malformed entries
+pub fn parse_cooldowns(spec: &str) -> HashMap<String, Duration> {
+    spec.split(',')
+        .filter(|entry| !entry.trim().is_empty())
+        .filter_map(|entry| {
+            let parsed = entry.split_once('=').and_then(|(command, secs)| {
+                let command = command.trim().trim_start_matches('!').to_lowercase();
+                let secs = secs.trim().parse::<u64>().ok()?;
+                (!command.is_empty()).then_some((command, Duration::from_secs(secs)))
+            });
+            if parsed.is_none() {
+                warn!("Ignoring malformed command cooldown: {}", entry.trim());
+            }
+            parsed
+        })
+        .collect()
+}
+
+/// Per-user, per-command cooldowns. Each command uses its own cooldown if one is
+/// configured, otherwise the default; a cooldown of zero turns it off.
+pub struct CommandCooldowns {
+    cooldown: Duration,
+    per_command: HashMap<String, Duration>,
+    last_used: std::sync::Mutex<HashMap<(UserId, String), Instant>>,
+}
+
+impl CommandCooldowns {
+    pub fn new(cooldown: Duration) -> Self {
+        Self {
+            cooldown,
+            per_command: HashMap::new(),
+            last_used: std::sync::Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Give particular commands their own cooldown instead of the default
+    pub fn with_overrides(mut self, per_command: HashMap<String, Duration>) -> Self {
+        self.per_command = per_command;
+        self
+    }
+
+    /// The cooldown for a command (zero if it has none)
+    pub fn cooldown_for(&self, command: &str) -> Duration {
+        self.per_command
+            .get(command)
+            .copied()
+            .unwrap_or(self.cooldown)
+    }
+
+    /// Record a use of the command, or return how long the user still has to wait
+    pub fn check(&self, user_id: UserId, command: &str) -> Result<(), Duration> {
+        self.check_at(user_id, command, Instant::now())
+    }
+
+    fn check_at(&self, user_id: UserId, command: &str, now: Instant) -> Result<(), Duration> {
+        let cooldown = self.cooldown_for(command);
+        if cooldown.is_zero() {
+            return Ok(());
+        }
+
+        let mut last_used = self.last_used.lock().unwrap();
+        let key = (user_id, command.to_string());
+        if let Some(last) = last_used.get(&key) {
+            let remaining = remaining_cooldown(*last, now, cooldown);
+            if !remaining.is_zero() {
+                return Err(remaining);
+            }
+        }
+
+        last_used.insert(key, now);
+        Ok(())
+    }
+
+    /// Drop cooldowns that have run out so the map doesn't grow forever. Returns how many
+    /// were dropped.
+    pub fn prune(&self) -> usize {
+        self.prune_at(Instant::now())
+    }
+
+    fn prune_at(&self, now: Instant) -> usize {
+        let mut last_used = self.last_used.lock().unwrap();
+        let before = last_used.len();
+        last_used.retain(|(_, command), last| {
+            now.saturating_duration_since(*last) < self.cooldown_for(command)
+        });
+        before - last_used.len()
+    }
+}
+
+// A command waiting for its author to react to the cooldown notice
+struct PendingRetry {
+    user_id: UserId,
+    command: Box<Message>,
+    ready_at: Instant,
+}
+
+/// Commands blocked by a cooldown, keyed by the bot's cooldown notice, that the author
+/// can have re-run by reacting with `RETRY_EMOJI`
+#[derive(Default)]
+pub struct PendingRetries {
+    retries: std::sync::Mutex<HashMap<MessageId, PendingRetry>>,
+}
+
+impl PendingRetries {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&self, notice_id: MessageId, command: Message, wait: Duration) {
+        let now = Instant::now();
+        let mut retries = self.retries.lock().unwrap();
+        retries.retain(|_, retry| now.saturating_duration_since(retry.ready_at) < RETRY_WINDOW);
+        retries.insert(
+            notice_id,
+            PendingRetry {
+                user_id: command.author.id,
+                command: Box::new(command),
+                ready_at: now + wait,
+            },
+        );
+    }
+
+    /// Claim the command behind a cooldown notice if `user_id` is the one who sent it.
+    /// Returns the command and how much longer its cooldown runs.
+    pub fn take(&self, notice_id: MessageId, user_id: UserId) -> Option<(Message, Duration)> {
+        let mut retries = self.retries.lock().unwrap();
+        if retries.get(&notice_id)?.user_id != user_id {
+            return None;
+        }
+        let retry = retries.remove(&notice_id)?;
+        let wait = retry.ready_at.saturating_duration_since(Instant::now());
+        Some((*retry.command, wait))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cooldown_wait_time() {
+        let cooldowns = CommandCooldowns::new(Duration::from_secs(30));
+        let alice = UserId::new(1);
+        let start = Instant::now();
+
+        assert_eq!(cooldowns.check_at(alice, "imagine", start), Ok(()));
+        assert_eq!(
+            cooldowns.check_at(alice, "imagine", start + Duration::from_millis(12_500)),
+            Err(Duration::from_millis(17_500))
+        );
+        // Other commands and other users have their own cooldowns
+        assert_eq!(
+            cooldowns.check_at(alice, "frinkiac", start + Duration::from_secs(1)),
+            Ok(())
+        );
+        assert_eq!(
+            cooldowns.check_at(UserId::new(2), "imagine", start + Duration::from_secs(1)),
+            Ok(())
+        );
+        // Free again once the cooldown is over
+        assert_eq!(
+            cooldowns.check_at(alice, "imagine", start + Duration::from_secs(30)),
+            Ok(())
+        );
+
+        assert_eq!(format_wait(Duration::from_millis(17_500)), "18 seconds");
+        assert_eq!(format_wait(Duration::from_millis(200)), "1 second");
+        assert_eq!(format_wait(Duration::from_secs(120)), "2 minutes");
+        assert_eq!(format_wait(Duration::from_secs(65)), "1 minute 5 seconds");
+
+        // A zero cooldown never blocks
+        let off = CommandCooldowns::new(Duration::ZERO);
+        assert_eq!(off.check_at(alice, "imagine", start), Ok(()));
+        assert_eq!(off.check_at(alice, "imagine", start), Ok(()));
+    }
+
+    #[test]
+    fn test_per_command_cooldowns() {
+        let overrides = parse_cooldowns("imagine=60, !Frinkiac=5, calc=0, broken, x=y");
+        assert_eq!(overrides.len(), 3);
+        let cooldowns = CommandCooldowns::new(Duration::from_secs(10)).with_overrides(overrides);
+        let alice = UserId::new(1);
+        let start = Instant::now();
+
+        assert_eq!(cooldowns.cooldown_for("imagine"), Duration::from_secs(60));
+        assert_eq!(cooldowns.cooldown_for("frinkiac"), Duration::from_secs(5));
+        assert_eq!(cooldowns.cooldown_for("slogan"), Duration::from_secs(10));
+
+        for command in ["imagine", "frinkiac", "slogan", "calc"] {
+            assert_eq!(cooldowns.check_at(alice, command, start), Ok(()));
+        }
+        let later = start + Duration::from_secs(20);
+        assert_eq!(
+            cooldowns.check_at(alice, "imagine", later),
+            Err(Duration::from_secs(40))
+        );
+        assert_eq!(cooldowns.check_at(alice, "frinkiac", later), Ok(()));
+        assert_eq!(cooldowns.check_at(alice, "slogan", later), Ok(()));
+        // A command with a zero cooldown is never blocked
+        assert_eq!(cooldowns.check_at(alice, "calc", start), Ok(()));
+
+        // Zero cooldowns are never recorded
+        assert!(!cooldowns
+            .last_used
+            .lock()
+            .unwrap()
+            .contains_key(&(alice, "calc".to_string())));
+    }
+
+    #[test]
+    fn test_prune_expired_cooldowns() {
+        let cooldowns = CommandCooldowns::new(Duration::from_secs(10))
+            .with_overrides(parse_cooldowns("imagine=60"));
+        let start = Instant::now();
+        for user in 1..=3 {
+            let user = UserId::new(user);
+            assert_eq!(cooldowns.check_at(user, "imagine", start), Ok(()));
+            assert_eq!(cooldowns.check_at(user, "slogan", start), Ok(()));
+        }
+
+        // Nothing has run out yet
+        assert_eq!(cooldowns.prune_at(start + Duration::from_secs(5)), 0);
+        // The default cooldowns have, the longer !imagine ones haven't
+        assert_eq!(cooldowns.prune_at(start + Duration::from_secs(10)), 3);
+        assert_eq!(
+            cooldowns.check_at(UserId::new(1), "imagine", start + Duration::from_secs(10)),
+            Err(Duration::from_secs(50))
+        );
+        assert_eq!(cooldowns.prune_at(start + Duration::from_secs(60)), 3);
+        assert!(cooldowns.last_used.lock().unwrap().is_empty());
+    }
+}
@@ -1,8 +1,34 @@
+use crate::adaptive::AdaptiveSettings;
+use crate::address_detection::parse_pattern_list;
+use crate::argument_limits::{ArgumentLimits, DEFAULT_MAX_ARG_LENGTH};
+use crate::channel_gating::{CommandChannelRules, SCREENSHOT_COMMANDS};
+use crate::command_cooldown::parse_cooldowns;
+use crate::daily_digest::{DailyDigestSettings, DigestSection};
+use crate::feature_unavailable::{FeatureUnavailable, FeatureUnavailableMessages};
+use crate::gemini_api::{
+    self, ApiFlavor, GeminiTemperatures, GenerationSettings, DEFAULT_MAX_RETRIES, MAX_TEMPERATURE,
+};
+use crate::http_client;
+use crate::image_generation::{ImageApiAuth, ImageBackendSettings, ImageRetryPolicy};
+use crate::keyword_reactions::KeywordReactions;
+use crate::media_utils::MediaLimits;
+use crate::message_store::MessageStoreSettings;
+use crate::rate_limiter::DEFAULT_RATE_LIMIT_REPLY;
+use crate::reaction_quote::DEFAULT_QUOTE_REACTION_EMOJI;
+use crate::screenshot::DEFAULT_GIF_MAX_DURATION;
+use crate::screenshot_sources::{
+    parse_screenshot_sources, ScreenshotSource, ScreenshotSourceConfig,
+};
+use crate::wikipedia;
 use anyhow::{Context as AnyhowContext, Result};
+use chrono::{FixedOffset, NaiveTime};
 use serde::Deserialize;
+use serenity::model::id::ChannelId;
+use std::collections::HashMap;
 use std::fs;
 use std::path::Path;
-use tracing::info;
+use std::time::Duration;
+use tracing::{info, warn};
 
 #[cfg(test)]
 mod tests {
@@ -33,6 +59,152 @@ MESSAGE_HISTORY_LIMIT = "5000"
         // Check that comments are preserved
         assert!(processed.contains("# Test config"));
     }
+
+    #[test]
+    fn test_parse_utc_offset() {
+        assert_eq!(parse_utc_offset("-05:00"), FixedOffset::west_opt(5 * 3600));
+        assert_eq!(
+            parse_utc_offset("+5:30"),
+            FixedOffset::east_opt(5 * 3600 + 30 * 60)
+        );
+        assert_eq!(parse_utc_offset("-8"), FixedOffset::west_opt(8 * 3600));
+        assert_eq!(parse_utc_offset("0"), FixedOffset::east_opt(0));
+        assert_eq!(parse_utc_offset("PST"), None);
+        assert_eq!(parse_utc_offset("+25"), None);
+    }
+
+    #[test]
+    fn test_parse_temperature() {
+        assert_eq!(parse_temperature("t", Some("0.3"), 1.0), 0.3);
+        assert_eq!(parse_temperature("t", Some("2"), 1.0), 2.0);
+        assert_eq!(parse_temperature("t", None, 1.0), 1.0);
+        // Out of range or unparseable values fall back to the default
+        assert_eq!(parse_temperature("t", Some("2.5"), 1.0), 1.0);
+        assert_eq!(parse_temperature("t", Some("-0.1"), 1.0), 1.0);
+        assert_eq!(parse_temperature("t", Some("hot"), 1.0), 1.0);
+    }
+
+    #[test]
+    fn test_parse_generation_limits() {
+        assert_eq!(parse_top_p(Some("0.9")), Some(0.9));
+        assert_eq!(parse_top_p(Some("1")), Some(1.0));
+        assert_eq!(parse_top_p(None), None);
+        assert_eq!(parse_top_p(Some("0")), None);
+        assert_eq!(parse_top_p(Some("1.5")), None);
+        assert_eq!(parse_max_output_tokens(Some(" 512 ")), Some(512));
+        assert_eq!(parse_max_output_tokens(Some("0")), None);
+        assert_eq!(parse_max_output_tokens(Some("lots")), None);
+    }
+
+    #[test]
+    fn test_parse_image_backend() {
+        assert_eq!(
+            parse_image_backend(None, None, None, None),
+            ImageBackendSettings::Pollinations
+        );
+        assert_eq!(
+            parse_image_backend(
+                Some("Stable-Diffusion"),
+                Some("http://gpu:7860"),
+                None,
+                None
+            ),
+            ImageBackendSettings::StableDiffusion {
+                url: "http://gpu:7860".to_string(),
+                auth: ImageApiAuth::None,
+            }
+        );
+        assert_eq!(
+            parse_image_backend(
+                Some("a1111"),
+                Some("http://gpu:7860"),
+                None,
+                Some("me:secret")
+            ),
+            ImageBackendSettings::StableDiffusion {
+                url: "http://gpu:7860".to_string(),
+                auth: ImageApiAuth::Basic {
+                    username: "me".to_string(),
+                    password: "secret".to_string(),
+                },
+            }
+        );
+        // An API key wins over basic auth
+        assert_eq!(
+            parse_image_backend(
+                Some("stable_diffusion"),
+                Some("https://sd.example"),
+                Some("token"),
+                Some("me:secret")
+            ),
+            ImageBackendSettings::StableDiffusion {
+                url: "https://sd.example".to_string(),
+                auth: ImageApiAuth::Bearer("token".to_string()),
+            }
+        );
+
+        // Without a URL, or with an unknown backend, it falls back to the default
+        assert_eq!(
+            parse_image_backend(Some("stable_diffusion"), None, Some("token"), None),
+            ImageBackendSettings::Pollinations
+        );
+        assert_eq!(
+            parse_image_backend(Some("dalle"), Some("http://gpu:7860"), None, None),
+            ImageBackendSettings::Pollinations
+        );
+    }
+
+    #[test]
+    fn test_parse_message_store() {
+        let sqlite = |path: &str| MessageStoreSettings::Sqlite {
+            path: path.to_string(),
+        };
+        assert_eq!(parse_message_store(None), MessageStoreSettings::default());
+        assert_eq!(
+            parse_message_store(Some("  ")),
+            MessageStoreSettings::default()
+        );
+        assert_eq!(
+            parse_message_store(Some("/data/crow.db")),
+            sqlite("/data/crow.db")
+        );
+        assert_eq!(
+            parse_message_store(Some("sqlite://crow.db")),
+            sqlite("crow.db")
+        );
+        assert_eq!(
+            parse_message_store(Some("sqlite:crow.db")),
+            sqlite("crow.db")
+        );
+        assert_eq!(
+            parse_message_store(Some("postgres://crow:secret@db/crow")),
+            MessageStoreSettings::Postgres {
+                url: "postgres://crow:secret@db/crow".to_string()
+            }
+        );
+        assert_eq!(
+            parse_message_store(Some("PostgreSQL://db/crow")),
+            MessageStoreSettings::Postgres {
+                url: "PostgreSQL://db/crow".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_reaction_features() {
+        assert!(parse_reaction_features(None).is_empty());
+        assert!(parse_reaction_features(Some(" , ")).is_empty());
+        assert_eq!(
+            parse_reaction_features(Some("Starboard, reaction-quote,,cooldown_retry")),
+            vec!["starboard", "reaction_quote", "cooldown_retry"]
+        );
+        // Unknown features are dropped, so they can't turn the reactions intent on
+        assert!(parse_reaction_features(Some("reaction_games, stars")).is_empty());
+        assert_eq!(
+            parse_reaction_features(Some("poll-tally, nonsense")),
+            vec!["poll_tally"]
+        );
+    }
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -44,19 +216,56 @@ pub struct Config {
     pub followed_channel_ids: Option<String>,
     pub followed_server_name: Option<String>,
     pub bot_name: Option<String>,
+    pub extra_address_patterns: Option<String>,
+    pub extra_negative_patterns: Option<String>,
     pub message_history_limit: Option<String>,
+    pub channel_history_limits: Option<String>,
+    pub message_store_url: Option<String>,
     pub db_trim_interval_secs: Option<String>,
+    pub trim_keep_protected: Option<String>,
+    pub db_health_check_interval_secs: Option<String>,
+    pub info_count_cache_secs: Option<String>,
+    pub wikipedia_cache_secs: Option<String>,
+    pub wikipedia_cache_max_entries: Option<String>,
+    pub http_timeout_seconds: Option<String>,
+    pub gemini_reply_timeout_seconds: Option<String>,
     pub gemini_rate_limit_minute: Option<String>,
     pub gemini_rate_limit_day: Option<String>,
+    pub user_reply_burst: Option<String>,
+    pub user_reply_refill_per_minute: Option<String>,
     pub gemini_image_rate_limit_minute: Option<String>,
     pub gemini_image_rate_limit_day: Option<String>,
     pub gemini_api_key: Option<String>,
     pub gemini_api_endpoint: Option<String>,
+    pub gemini_fallback_api_endpoint: Option<String>,
+    pub gemini_fallback_api_key: Option<String>,
+    pub llm_api_flavor: Option<String>,
+    pub llm_model: Option<String>,
     pub gemini_prompt_wrapper: Option<String>,
     pub gemini_interjection_prompt: Option<String>,
     pub gemini_context_messages: Option<String>,
     pub gemini_log_prompts: Option<String>,
     pub gemini_personality_description: Option<String>,
+    pub gemini_uncertainty_mode: Option<String>,
+    pub gemini_persona_examples: Option<String>,
+    pub gemini_temperature: Option<String>,
+    pub gemini_top_p: Option<String>,
+    pub gemini_max_output_tokens: Option<String>,
+    pub gemini_max_retries: Option<String>,
+    pub gemini_temperature_reply: Option<String>,
+    pub gemini_temperature_interjection: Option<String>,
+    pub gemini_temperature_image_prompt: Option<String>,
+    pub gemini_temperature_evaluation: Option<String>,
+    pub rephrase_edit_original: Option<String>,
+    pub gemini_stream_replies: Option<String>,
+    pub reply_chain_depth: Option<String>,
+    pub conversation_memory_exchanges: Option<String>,
+    pub conversation_memory_idle_mins: Option<String>,
+    pub media_max_attachments: Option<String>,
+    pub media_max_size_mb: Option<String>,
+    pub context_collapse_duplicates: Option<String>,
+    pub context_paste_line_threshold: Option<String>,
+    pub context_paste_mode: Option<String>,
     pub interjection_mst3k_probability: Option<String>,
     pub interjection_memory_probability: Option<String>,
     pub interjection_pondering_probability: Option<String>,
@@ -64,9 +273,25 @@ pub struct Config {
     pub interjection_fact_probability: Option<String>,
     pub interjection_news_probability: Option<String>,
     pub interjection_minimum_messages: Option<String>,
+    pub interjection_max_per_hour: Option<String>,
+    pub interjection_similarity_threshold: Option<String>,
+    pub skip_interjections_after_bot: Option<String>,
+    pub interjection_warmup_secs: Option<String>,
+    pub interjection_warmup_messages: Option<String>,
+    pub adaptive_interjections: Option<String>,
+    pub adaptive_interjection_min_factor: Option<String>,
+    pub adaptive_interjection_max_factor: Option<String>,
+    pub adaptive_interjection_step: Option<String>,
+    pub interjection_metrics: Option<String>,
     pub fill_silence_enabled: Option<String>,
     pub fill_silence_start_hours: Option<String>,
     pub fill_silence_max_hours: Option<String>,
+    pub fill_silence_quiet_start: Option<String>,
+    pub fill_silence_quiet_end: Option<String>,
+    pub fill_silence_utc_offset: Option<String>,
+    pub fill_silence_check_min_secs: Option<String>,
+    pub fill_silence_check_max_secs: Option<String>,
+    pub fill_silence_channels: Option<Vec<crate::fill_silence::FillSilenceChannelConfig>>,
     pub interjection_channel_name: Option<String>,
     pub interjection_channel_id: Option<String>,
     pub interjection_channel_names: Option<String>,
@@ -79,6 +304,35 @@ pub struct Config {
     pub db_password: Option<String>,
     pub gateway_bot_ids: Option<String>,
     pub imagine_channels: Option<String>,
+    pub screenshot_channels: Option<String>,
+    pub screenshot_alt_text: Option<String>,
+    pub eightball_use_ai: Option<String>,
+    pub urban_filter_nsfw: Option<String>,
+    pub urban_blocked_terms: Option<String>,
+    pub screenshot_gif_max_secs: Option<String>,
+    pub screenshot_sources: Option<Vec<ScreenshotSourceConfig>>,
+    pub phrase_responses: Option<Vec<crate::phrase_responses::PhraseResponseConfig>>,
+    pub owner_ids: Option<String>,
+    pub admin_user_ids: Option<String>,
+    pub fetch_uncached_members: Option<String>,
+    pub quote_dud_min_length: Option<String>,
+    pub all_guilds: Option<String>,
+    pub channel_topic_context: Option<String>,
+    pub mst3k_quotes_file: Option<String>,
+    pub nsfw_only_commands: Option<String>,
+    pub image_retry_attempts: Option<String>,
+    pub image_retry_rewrite_prompt: Option<String>,
+    pub image_backend: Option<String>,
+    pub image_api_url: Option<String>,
+    pub image_api_key: Option<String>,
+    pub image_api_basic_auth: Option<String>,
+    pub birthday_channel_id: Option<String>,
+    pub birthday_utc_offset: Option<String>,
+    pub reminder_utc_offset: Option<String>,
+    pub daily_digest_channel_id: Option<String>,
+    pub daily_digest_time: Option<String>,
+    pub daily_digest_utc_offset: Option<String>,
+    pub daily_digest_sections: Option<String>,
     pub pollinations_api_key: Option<String>,
     pub quiet_channel_name: Option<String>,
     pub quiet_channel_id: Option<String>,
@@ -86,6 +340,24 @@ pub struct Config {
     pub quiet_channel_ids: Option<String>,
     pub giphy_api_key: Option<String>,
     pub news_feeds: Option<String>,
+    pub reaction_features: Option<String>,
+    pub keyword_reactions: Option<String>,
+    pub quote_reaction_emoji: Option<String>,
+    pub quote_reaction_channel_id: Option<String>,
+    pub subtitles_page_size: Option<String>,
+    pub subtitles_max_pages: Option<String>,
+    pub command_cooldown_seconds: Option<String>,
+    pub command_cooldowns: Option<String>,
+    pub command_max_arg_length: Option<String>,
+    pub command_max_arg_lengths: Option<String>,
+    pub slash_commands: Option<String>,
+    pub unavailable_message_template: Option<String>,
+    pub unavailable_message_ai: Option<String>,
+    pub unavailable_message_history: Option<String>,
+    pub unavailable_message_quote_db: Option<String>,
+    pub unavailable_message_search: Option<String>,
+    pub unavailable_message_image: Option<String>,
+    pub rate_limit_reply_message: Option<String>,
 }
 
 pub fn load_config() -> Result<Config> {
@@ -147,27 +419,317 @@ pub fn preprocess_config_content(content: &str) -> String {
 #[derive(Debug, Clone)]
 pub struct ParsedConfig {
     pub bot_name: String,
+    /// Extra regexes that mean the bot is being addressed ({name} = the bot's name)
+    pub extra_address_patterns: Vec<String>,
+    /// Extra regexes that mean it isn't, overriding everything else
+    pub extra_negative_patterns: Vec<String>,
     pub message_history_limit: usize,
+    pub channel_history_limits: HashMap<String, usize>,
     pub db_trim_interval: u64,
+    /// Whether trims skip messages that quotes and reminders point at
+    pub trim_keep_protected: bool,
+    pub db_health_check_interval: u64,
+    pub info_count_cache_secs: u64,
+    /// How long !alive and !wiki reuse a Wikipedia lookup
+    pub wikipedia_cache_ttl: Duration,
+    /// Most names the Wikipedia cache remembers (0 turns it off)
+    pub wikipedia_cache_max_entries: usize,
+    /// Longest any outbound HTTP request may take
+    pub http_timeout: Duration,
+    /// Where message history is stored
+    pub message_store: MessageStoreSettings,
+    /// Longest a reply to a message may spend waiting on Gemini
+    pub gemini_reply_timeout: Duration,
     pub gemini_rate_limit_minute: u32,
     pub gemini_rate_limit_day: u32,
+    /// How many AI replies one user can get in a burst (0 = no per-user limit)
+    pub user_reply_burst: u32,
+    /// How many AI replies a user earns back per minute
+    pub user_reply_refill_per_minute: f64,
     pub gemini_image_rate_limit_minute: u32,
     pub gemini_image_rate_limit_day: u32,
     pub gateway_bot_ids: Vec<u64>,
     pub duckduckgo_search_enabled: bool,
     pub gemini_context_messages: usize,
+    pub gemini_uncertainty_mode: bool,
+    pub gemini_persona_examples: Vec<(String, String)>,
+    pub gemini_temperatures: GeminiTemperatures,
+    /// Top-p and output length limits sent with every request (unset: the API's defaults)
+    pub gemini_generation: GenerationSettings,
+    /// Retries after a Gemini rate limit or overload error
+    pub gemini_max_retries: u32,
+    /// Whether !rephrase edits the bot's message instead of posting a new one
+    pub rephrase_edit_original: bool,
+    /// Whether direct replies stream into a message that fills in as Gemini writes them
+    pub gemini_stream_replies: bool,
+    pub gemini_fallback_api_endpoint: Option<String>,
+    pub gemini_fallback_api_key: Option<String>,
+    /// Whether the AI endpoint speaks Gemini's API or OpenAI's chat completions
+    pub llm_api_flavor: ApiFlavor,
+    /// Model named in OpenAI-compatible requests
+    pub llm_model: Option<String>,
+    pub reply_chain_depth: usize,
+    /// How many of a user's recent exchanges with the bot go into its replies (0 disables)
+    pub conversation_memory_exchanges: usize,
+    /// Minutes without an exchange before a user's conversation is forgotten
+    pub conversation_memory_idle_mins: u64,
+    /// How many image/video attachments a reply looks at, and how large each may be
+    pub media_limits: MediaLimits,
+    pub context_collapse_duplicates: bool,
+    pub context_paste_line_threshold: usize,
+    pub context_exclude_pastes: bool,
     pub interjection_mst3k_probability: f64,
     pub interjection_memory_probability: f64,
     pub interjection_pondering_probability: f64,
     pub interjection_ai_probability: f64,
     pub imagine_channels: Vec<String>,
+    pub command_channel_rules: CommandChannelRules,
+    pub screenshot_alt_text: bool,
+    /// Have Gemini write !8ball answers instead of using the classic ones
+    pub eightball_use_ai: bool,
+    /// Words that keep an !urban definition out of the channel (None = no filter)
+    pub urban_blocked_terms: Option<Vec<String>>,
+    /// Longest GIF rendered for a !frinkiac/!morbotron result
+    pub screenshot_gif_max_duration: Duration,
+    /// Extra Frinkiac-family sites, each with its own command
+    pub screenshot_sources: Vec<ScreenshotSource>,
+    /// Call-and-response pairs, the built-in ones unless [[phrase_responses]] are configured
+    pub phrase_responses: Vec<crate::phrase_responses::PhraseResponse>,
+    pub image_retry_policy: ImageRetryPolicy,
+    pub image_backend: ImageBackendSettings,
+    pub birthday_channel_id: Option<u64>,
+    pub birthday_utc_offset: Option<FixedOffset>,
+    /// Timezone for !remindme times like 14:30 (None = system local)
+    pub reminder_utc_offset: Option<FixedOffset>,
+    pub daily_digest: Option<DailyDigestSettings>,
     pub interjection_news_probability: f64,
     pub interjection_minimum_messages: usize,
+    pub interjection_max_per_hour: usize,
+    pub interjection_similarity_threshold: f64,
+    pub skip_interjections_after_bot: bool,
+    pub interjection_warmup_secs: u64,
+    pub interjection_warmup_messages: usize,
+    /// Bounds for learning interjection probabilities from feedback (None = off)
+    pub adaptive_interjections: Option<AdaptiveSettings>,
+    /// Log every interjection decision to the interjection_metrics table
+    pub interjection_metrics: bool,
     pub fill_silence_enabled: bool,
     pub fill_silence_start_hours: f64,
     pub fill_silence_max_hours: f64,
+    /// Hours when spontaneous interjections are held off (None = any hour)
+    pub fill_silence_quiet_hours: Option<crate::fill_silence::QuietHours>,
+    /// Shortest and longest wait between spontaneous interjection checks
+    pub fill_silence_check_interval: (Duration, Duration),
+    /// Channels with their own fill silence settings; names are resolved after connecting
+    pub fill_silence_channels: Vec<(
+        crate::fill_silence::SilenceChannel,
+        crate::fill_silence::SilenceSettings,
+    )>,
     pub quiet_channels: Vec<String>,
+    pub owner_ids: Vec<u64>,
+    /// Users allowed to run admin commands like !interject (owners always are)
+    pub admin_user_ids: Vec<u64>,
+    pub fetch_uncached_members: bool,
+    pub quote_dud_min_length: usize,
+    /// Let !quote -dud and memory interjections pick messages from any server
+    pub all_guilds: bool,
+    pub channel_topic_context: bool,
+    pub mst3k_quotes_file: Option<String>,
     pub giphy_api_key: Option<String>,
+    pub reaction_features: Vec<String>,
+    pub keyword_reactions: KeywordReactions,
+    /// Emoji that has the bot repost a message as a quote (reaction_quote feature)
+    pub quote_reaction_emoji: String,
+    /// Channel quotes are posted in (None = the quoted message's channel)
+    pub quote_reaction_channel_id: Option<u64>,
+    pub unavailable_messages: FeatureUnavailableMessages,
+    pub rate_limit_reply_message: String,
+    pub subtitles_page_size: usize,
+    pub subtitles_max_pages: usize,
+    pub command_cooldown_seconds: u64,
+    /// Per-command overrides of the command cooldown
+    pub command_cooldowns: HashMap<String, Duration>,
+    pub argument_limits: ArgumentLimits,
+    pub slash_commands_enabled: bool,
+}
+
+/// Parse a UTC offset like "-05:00", "+5:30" or "-8" (whole hours)
+pub fn parse_utc_offset(value: &str) -> Option<FixedOffset> {
+    let value = value.trim();
+    let (sign, rest) = match value.strip_prefix('-') {
+        Some(rest) => (-1, rest),
+        None => (1, value.strip_prefix('+').unwrap_or(value)),
+    };
+    let (hours, minutes) = match rest.split_once(':') {
+        Some((hours, minutes)) => (hours.parse::<i32>().ok()?, minutes.parse::<i32>().ok()?),
+        None => (rest.parse::<i32>().ok()?, 0),
+    };
+    if !(0..60).contains(&minutes) {
+        return None;
+    }
+    FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60))
+}
+
+/// Parse a Gemini sampling temperature, falling back to `default` when it's missing
+/// or outside the range Gemini accepts
+pub fn parse_temperature(name: &str, value: Option<&str>, default: f64) -> f64 {
+    let Some(value) = value else {
+        return default;
+    };
+    match value.trim().parse::<f64>() {
+        Ok(temperature) if (0.0..=MAX_TEMPERATURE).contains(&temperature) => temperature,
+        _ => {
+            warn!(
+                "Invalid {} value: {} (must be between 0 and {}), using {}",
+                name, value, MAX_TEMPERATURE, default
+            );
+            default
+        }
+    }
+}
+
+/// Parse GEMINI_TOP_P, which must be above 0 and at most 1. Invalid values are logged and
+/// left unset.
+pub fn parse_top_p(value: Option<&str>) -> Option<f64> {
+    let value = value?;
+    match value.trim().parse::<f64>() {
+        Ok(top_p) if top_p > 0.0 && top_p <= 1.0 => Some(top_p),
+        _ => {
+            warn!(
+                "Invalid gemini_top_p value: {} (must be above 0 and at most 1), leaving it unset",
+                value
+            );
+            None
+        }
+    }
+}
+
+/// Parse GEMINI_MAX_OUTPUT_TOKENS, a positive token count. Invalid values are logged and
+/// left unset.
+pub fn parse_max_output_tokens(value: Option<&str>) -> Option<u32> {
+    let value = value?;
+    match value.trim().parse::<u32>() {
+        Ok(tokens) if tokens > 0 => Some(tokens),
+        _ => {
+            warn!(
+                "Invalid gemini_max_output_tokens value: {} (must be a positive number), leaving it unset",
+                value
+            );
+            None
+        }
+    }
+}
+
+/// Work out which image backend to use. Anything but a usable self-hosted setup falls back
+/// to the default Pollinations backend with a warning.
+pub fn parse_image_backend(
+    backend: Option<&str>,
+    url: Option<&str>,
+    api_key: Option<&str>,
+    basic_auth: Option<&str>,
+) -> ImageBackendSettings {
+    let backend = backend.map(|b| b.trim().to_lowercase().replace('-', "_"));
+    match backend.as_deref() {
+        None | Some("") | Some("pollinations") => ImageBackendSettings::Pollinations,
+        Some("stable_diffusion" | "automatic1111" | "a1111") => {
+            let Some(url) = url.map(str::trim).filter(|url| !url.is_empty()) else {
+                warn!("IMAGE_BACKEND is stable_diffusion but IMAGE_API_URL is not set, using pollinations");
+                return ImageBackendSettings::Pollinations;
+            };
+            let api_key = api_key.map(str::trim).filter(|key| !key.is_empty());
+            let basic_auth = basic_auth.and_then(|auth| auth.trim().split_once(':'));
+            let auth = match (api_key, basic_auth) {
+                (Some(key), _) => ImageApiAuth::Bearer(key.to_string()),
+                (None, Some((username, password))) => ImageApiAuth::Basic {
+                    username: username.to_string(),
+                    password: password.to_string(),
+                },
+                (None, None) => ImageApiAuth::None,
+            };
+            ImageBackendSettings::StableDiffusion {
+                url: url.to_string(),
+                auth,
+            }
+        }
+        Some(other) => {
+            warn!("Unknown IMAGE_BACKEND value: {}, using pollinations", other);
+            ImageBackendSettings::Pollinations
+        }
+    }
+}
+
+/// Work out where message history goes: a postgres:// (or postgresql://) URL selects the
+/// shared PostgreSQL store, anything else is a SQLite file path (an optional sqlite://
+/// prefix is dropped). Unset means the default local SQLite file.
+pub fn parse_message_store(url: Option<&str>) -> MessageStoreSettings {
+    let Some(url) = url.map(str::trim).filter(|url| !url.is_empty()) else {
+        return MessageStoreSettings::default();
+    };
+    let lower = url.to_lowercase();
+    if lower.starts_with("postgres://") || lower.starts_with("postgresql://") {
+        return MessageStoreSettings::Postgres {
+            url: url.to_string(),
+        };
+    }
+    let path = url
+        .strip_prefix("sqlite://")
+        .or_else(|| url.strip_prefix("sqlite:"))
+        .unwrap_or(url);
+    if path.is_empty() {
+        return MessageStoreSettings::default();
+    }
+    MessageStoreSettings::Sqlite {
+        path: path.to_string(),
+    }
+}
+
+/// Reaction-driven features that need the GUILD_MESSAGE_REACTIONS gateway intent
+pub const KNOWN_REACTION_FEATURES: &[&str] = &[
+    "starboard",
+    "reaction_roles",
+    "reaction_translate",
+    "poll_tally",
+    "cooldown_retry",
+    "interjection_feedback",
+    "reaction_quote",
+    "screenshot_pages",
+];
+
+/// Parse the comma-separated REACTION_FEATURES list, ignoring case and treating `-` as
+/// `_`. Unknown features are logged and left out.
+pub fn parse_reaction_features(features: Option<&str>) -> Vec<String> {
+    features
+        .unwrap_or_default()
+        .split(',')
+        .map(|feature| feature.trim().to_lowercase().replace('-', "_"))
+        .filter(|feature| !feature.is_empty())
+        .filter(|feature| {
+            let known = KNOWN_REACTION_FEATURES.contains(&feature.as_str());
+            if !known {
+                warn!("Ignoring unknown reaction feature: {}", feature);
+            }
+            known
+        })
+        .collect()
+}
+
+// Parse a comma-separated list of Discord user IDs, skipping (and logging) invalid ones
+fn parse_user_ids(name: &str, value: Option<&str>) -> Vec<u64> {
+    value
+        .map(|ids| {
+            ids.split(',')
+                .map(|id| id.trim())
+                .filter(|id| !id.is_empty())
+                .filter_map(|id| match id.parse::<u64>() {
+                    Ok(id) => Some(id),
+                    Err(_) => {
+                        warn!("Invalid user ID in {}: {}", name, id);
+                        None
+                    }
+                })
+                .collect()
+        })
+        .unwrap_or_default()
 }
 
 pub fn parse_config(config: &Config) -> ParsedConfig {
@@ -178,6 +740,25 @@ pub fn parse_config(config: &Config) -> ParsedConfig {
         .unwrap_or_else(|| "Crow".to_string());
     info!("Bot name set to: {}", bot_name);
 
+    // Get any extra patterns for telling when the bot is being addressed
+    let extra_address_patterns = config
+        .extra_address_patterns
+        .as_deref()
+        .map(parse_pattern_list)
+        .unwrap_or_default();
+    let extra_negative_patterns = config
+        .extra_negative_patterns
+        .as_deref()
+        .map(parse_pattern_list)
+        .unwrap_or_default();
+    if !extra_address_patterns.is_empty() || !extra_negative_patterns.is_empty() {
+        info!(
+            "Extra address patterns: {} positive, {} negative",
+            extra_address_patterns.len(),
+            extra_negative_patterns.len()
+        );
+    }
+
     // Get the message history limit
     let message_history_limit = config
         .message_history_limit
@@ -187,6 +768,33 @@ pub fn parse_config(config: &Config) -> ParsedConfig {
 
     info!("Message history limit set to {}", message_history_limit);
 
+    // Parse per-channel history limits ("channel_id:limit, channel_id:limit")
+    let mut channel_history_limits = HashMap::new();
+    if let Some(limits) = &config.channel_history_limits {
+        for entry in limits
+            .split(',')
+            .map(|e| e.trim())
+            .filter(|e| !e.is_empty())
+        {
+            match entry.split_once(':') {
+                Some((channel_id, limit)) => match limit.trim().parse::<usize>() {
+                    Ok(limit) => {
+                        channel_history_limits.insert(channel_id.trim().to_string(), limit);
+                    }
+                    Err(_) => warn!("Invalid history limit in channel_history_limits: {}", entry),
+                },
+                None => warn!(
+                    "Invalid channel_history_limits entry (expected id:limit): {}",
+                    entry
+                ),
+            }
+        }
+    }
+
+    if !channel_history_limits.is_empty() {
+        info!("Per-channel history limits: {:?}", channel_history_limits);
+    }
+
     // Get database trim interval (default: 1 hour)
     let db_trim_interval = config
         .db_trim_interval_secs
@@ -196,6 +804,95 @@ pub fn parse_config(config: &Config) -> ParsedConfig {
 
     info!("Database trim interval set to {} seconds", db_trim_interval);
 
+    // Parse whether trimming spares quoted/reminder messages (default: true)
+    let trim_keep_protected = config
+        .trim_keep_protected
+        .as_ref()
+        .map(|enabled| match enabled.to_lowercase().as_str() {
+            "false" | "0" | "no" | "disabled" | "off" => false,
+            "true" | "1" | "yes" | "enabled" | "on" => true,
+            _ => {
+                info!(
+                    "Invalid trim_keep_protected value: {}, defaulting to enabled",
+                    enabled
+                );
+                true
+            }
+        })
+        .unwrap_or(true);
+
+    // Get how often to check (and if needed reopen) the message database (default: 1 minute)
+    let db_health_check_interval = config
+        .db_health_check_interval_secs
+        .as_ref()
+        .and_then(|interval| interval.parse::<u64>().ok())
+        .filter(|interval| *interval > 0)
+        .unwrap_or(60);
+
+    info!(
+        "Database health check interval set to {} seconds",
+        db_health_check_interval
+    );
+
+    // Get how long !info may reuse a cached message count (default: 5 minutes)
+    let info_count_cache_secs = config
+        .info_count_cache_secs
+        .as_ref()
+        .and_then(|secs| secs.parse::<u64>().ok())
+        .unwrap_or(300);
+
+    info!(
+        "!info message count cache TTL set to {} seconds",
+        info_count_cache_secs
+    );
+
+    // Get how long Wikipedia lookups are reused, and how many are kept (default: 3 hours, 500)
+    let wikipedia_cache_ttl = config
+        .wikipedia_cache_secs
+        .as_ref()
+        .and_then(|secs| secs.parse::<u64>().ok())
+        .map(Duration::from_secs)
+        .unwrap_or(wikipedia::DEFAULT_CACHE_TTL);
+    let wikipedia_cache_max_entries = config
+        .wikipedia_cache_max_entries
+        .as_ref()
+        .and_then(|entries| entries.parse::<usize>().ok())
+        .unwrap_or(wikipedia::DEFAULT_CACHE_MAX_ENTRIES);
+
+    info!(
+        "Wikipedia cache keeps up to {} lookups for {} seconds",
+        wikipedia_cache_max_entries,
+        wikipedia_cache_ttl.as_secs()
+    );
+
+    let http_timeout = config
+        .http_timeout_seconds
+        .as_ref()
+        .and_then(|secs| secs.parse::<u64>().ok())
+        .filter(|secs| *secs > 0)
+        .map(Duration::from_secs)
+        .unwrap_or(http_client::DEFAULT_REQUEST_TIMEOUT);
+    let gemini_reply_timeout = config
+        .gemini_reply_timeout_seconds
+        .as_ref()
+        .and_then(|secs| secs.parse::<u64>().ok())
+        .filter(|secs| *secs > 0)
+        .map(Duration::from_secs)
+        .unwrap_or(gemini_api::DEFAULT_REPLY_TIMEOUT);
+
+    info!(
+        "HTTP requests time out after {} seconds, Gemini replies after {} seconds",
+        http_timeout.as_secs(),
+        gemini_reply_timeout.as_secs()
+    );
+
+    let message_store = parse_message_store(config.message_store_url.as_deref());
+    match &message_store {
+        MessageStoreSettings::Sqlite { path } => info!("Message history: SQLite at {}", path),
+        // The URL can hold a password, so it isn't logged
+        MessageStoreSettings::Postgres { .. } => info!("Message history: PostgreSQL"),
+    }
+
     // Get Gemini API rate limits
     let gemini_rate_limit_minute = config
         .gemini_rate_limit_minute
@@ -214,6 +911,28 @@ pub fn parse_config(config: &Config) -> ParsedConfig {
         gemini_rate_limit_minute, gemini_rate_limit_day
     );
 
+    // Parse the per-user reply budget, which applies on top of the limits above
+    let user_reply_burst = config
+        .user_reply_burst
+        .as_ref()
+        .and_then(|burst| burst.parse::<u32>().ok())
+        .unwrap_or(crate::rate_limiter::DEFAULT_USER_REPLY_BURST);
+    let user_reply_refill_per_minute = config
+        .user_reply_refill_per_minute
+        .as_ref()
+        .and_then(|rate| rate.parse::<f64>().ok())
+        .filter(|rate| rate.is_finite() && *rate > 0.0)
+        .unwrap_or(crate::rate_limiter::DEFAULT_USER_REPLY_REFILL_PER_MINUTE);
+
+    if user_reply_burst > 0 {
+        info!(
+            "Each user can get {} AI replies in a burst, earning back {} per minute",
+            user_reply_burst, user_reply_refill_per_minute
+        );
+    } else {
+        info!("No per-user AI reply limit");
+    }
+
     // Get Gemini Image API rate limits (separate from text API)
     let gemini_image_rate_limit_minute = config
         .gemini_image_rate_limit_minute
@@ -292,136 +1011,955 @@ pub fn parse_config(config: &Config) -> ParsedConfig {
         gemini_context_messages
     );
 
-    // Parse interjection probabilities
-    let interjection_mst3k_probability = config
-        .interjection_mst3k_probability
+    // Parse how many messages of a reply chain to walk back for context
+    let reply_chain_depth = config
+        .reply_chain_depth
         .as_ref()
-        .and_then(|prob| prob.parse::<f64>().ok())
-        .unwrap_or(0.005); // Default: 0.5% chance (1 in 200)
+        .and_then(|depth| depth.parse::<usize>().ok())
+        .unwrap_or(5)
+        .min(crate::reply_chain::MAX_REPLY_CHAIN_DEPTH);
 
-    let interjection_memory_probability = config
-        .interjection_memory_probability
-        .as_ref()
-        .and_then(|prob| prob.parse::<f64>().ok())
-        .unwrap_or(0.005); // Default: 0.5% chance (1 in 200)
+    info!("Reply chain context depth set to {}", reply_chain_depth);
 
-    let interjection_pondering_probability = config
-        .interjection_pondering_probability
+    // Parse per-user conversation memory (default: last 3 exchanges, forgotten after 30 idle
+    // minutes)
+    let conversation_memory_exchanges = config
+        .conversation_memory_exchanges
         .as_ref()
-        .and_then(|prob| prob.parse::<f64>().ok())
-        .unwrap_or(0.005); // Default: 0.5% chance (1 in 200)
-
-    let interjection_ai_probability = config
-        .interjection_ai_probability
+        .and_then(|count| count.parse::<usize>().ok())
+        .unwrap_or(3);
+    let conversation_memory_idle_mins = config
+        .conversation_memory_idle_mins
         .as_ref()
-        .and_then(|prob| prob.parse::<f64>().ok())
-        .unwrap_or(0.005); // Default: 0.5% chance (1 in 200)
+        .and_then(|mins| mins.parse::<u64>().ok())
+        .unwrap_or(30);
 
     info!(
-        "Interjection probabilities: MST3K: {}, Memory: {}, Pondering: {}, AI: {}",
-        interjection_mst3k_probability,
-        interjection_memory_probability,
-        interjection_pondering_probability,
-        interjection_ai_probability
+        "Conversation memory: {} exchanges per user, forgotten after {} idle minutes",
+        conversation_memory_exchanges, conversation_memory_idle_mins
     );
 
-    // Parse news interjection probability
-    let interjection_news_probability = config
-        .interjection_news_probability
-        .as_ref()
-        .and_then(|prob| prob.parse::<f64>().ok())
-        .unwrap_or(0.005); // Default: 0.5% chance (1 in 200)
-
-    // Parse minimum messages between interjections
-    let interjection_minimum_messages = config
-        .interjection_minimum_messages
-        .as_ref()
-        .and_then(|msgs| msgs.parse::<usize>().ok())
-        .unwrap_or(7); // Default: 7 messages from others before interjecting
+    // Parse how many attachments, and how large, are sent to Gemini with a message
+    let default_media_limits = MediaLimits::default();
+    let media_limits = MediaLimits {
+        max_items: config
+            .media_max_attachments
+            .as_ref()
+            .and_then(|count| count.trim().parse::<usize>().ok())
+            .unwrap_or(default_media_limits.max_items),
+        max_bytes: config
+            .media_max_size_mb
+            .as_ref()
+            .and_then(|mb| mb.trim().parse::<f64>().ok())
+            .filter(|mb| *mb > 0.0)
+            .map(|mb| (mb * 1_000_000.0) as usize)
+            .unwrap_or(default_media_limits.max_bytes),
+    };
+    info!(
+        "Media attachments: up to {} per message, {} bytes each",
+        media_limits.max_items, media_limits.max_bytes
+    );
 
-    // Parse fill silence configuration
-    let fill_silence_enabled = config
-        .fill_silence_enabled
+    // Parse whether repeated consecutive messages are collapsed in Gemini context
+    let context_collapse_duplicates = config
+        .context_collapse_duplicates
         .as_ref()
         .map(|enabled| match enabled.to_lowercase().as_str() {
-            "false" | "0" | "no" | "disabled" | "off" => false,
             "true" | "1" | "yes" | "enabled" | "on" => true,
+            "false" | "0" | "no" | "disabled" | "off" => false,
             _ => {
                 info!(
-                    "Invalid fill_silence_enabled value: {}, defaulting to enabled",
+                    "Invalid context_collapse_duplicates value: {}, defaulting to enabled",
                     enabled
                 );
                 true
             }
         })
-        .unwrap_or(true); // Default to enabled
+        .unwrap_or(true);
 
-    let fill_silence_start_hours = config
-        .fill_silence_start_hours
-        .as_ref()
-        .and_then(|hours| hours.parse::<f64>().ok())
-        .unwrap_or(1.0); // Default: 1 hour
+    info!(
+        "Collapsing repeated messages in context is {}",
+        if context_collapse_duplicates {
+            "enabled"
+        } else {
+            "disabled"
+        }
+    );
 
-    let fill_silence_max_hours = config
-        .fill_silence_max_hours
+    // Parse whether nicknames missing from the member cache are fetched from the API
+    let fetch_uncached_members = config
+        .fetch_uncached_members
         .as_ref()
-        .and_then(|hours| hours.parse::<f64>().ok())
-        .unwrap_or(12.0); // Default: 12 hours
+        .map(|enabled| match enabled.to_lowercase().as_str() {
+            "true" | "1" | "yes" | "enabled" | "on" => true,
+            "false" | "0" | "no" | "disabled" | "off" => false,
+            _ => {
+                info!(
+                    "Invalid fetch_uncached_members value: {}, defaulting to enabled",
+                    enabled
+                );
+                true
+            }
+        })
+        .unwrap_or(true);
 
     info!(
-        "Fill silence feature is {}",
-        if fill_silence_enabled {
+        "Fetching uncached guild members for display names is {}",
+        if fetch_uncached_members {
             "enabled"
         } else {
             "disabled"
         }
     );
-    if fill_silence_enabled {
-        info!(
-            "Fill silence configuration: Start increasing after {} hours, reach 100% at {} hours",
-            fill_silence_start_hours, fill_silence_max_hours
-        );
-    }
 
-    // Parse imagine channels
-    let imagine_channels = config
-        .imagine_channels
+    // Parse how large code/log pastes are handled in Gemini context
+    let context_paste_line_threshold = config
+        .context_paste_line_threshold
         .as_ref()
-        .map(|channels_str| {
-            channels_str
-                .split(',')
-                .map(|channel| channel.trim().to_string())
-                .filter(|channel| !channel.is_empty())
-                .collect::<Vec<String>>()
+        .and_then(|lines| lines.parse::<usize>().ok())
+        .unwrap_or(crate::db_utils::DEFAULT_PASTE_LINE_THRESHOLD);
+
+    let context_exclude_pastes = config
+        .context_paste_mode
+        .as_ref()
+        .map(|mode| match mode.to_lowercase().as_str() {
+            "exclude" | "drop" => true,
+            "summarize" | "summary" => false,
+            _ => {
+                info!(
+                    "Invalid context_paste_mode value: {}, defaulting to summarize",
+                    mode
+                );
+                false
+            }
         })
-        .unwrap_or_default();
+        .unwrap_or(false);
 
-    if !imagine_channels.is_empty() {
+    if context_paste_line_threshold == 0 {
+        info!("Code/log paste detection in context is disabled");
+    } else {
         info!(
-            "Image generation restricted to channels: {:?}",
-            imagine_channels
+            "Pastes of {}+ lines will be {} in context",
+            context_paste_line_threshold,
+            if context_exclude_pastes {
+                "excluded"
+            } else {
+                "summarized"
+            }
         );
-    } else {
-        info!("Image generation allowed in all channels");
     }
 
-    // Parse quiet channels configuration (names and IDs)
-    let mut quiet_channels = config
-        .quiet_channel_names
-        .as_ref()
-        .or(config.quiet_channel_name.as_ref())
-        .map(|names| {
-            names
-                .split(',')
-                .map(|channel| channel.trim().to_string())
-                .filter(|channel| !channel.is_empty())
-                .collect::<Vec<String>>()
-        })
-        .unwrap_or_default();
-
-    // Add quiet channel IDs to the list
-    let quiet_channel_ids = config
-        .quiet_channel_ids
+    // Parse uncertainty mode (asks Gemini to hedge answers it isn't confident about)
+    let gemini_uncertainty_mode = config
+        .gemini_uncertainty_mode
+        .as_ref()
+        .map(|enabled| match enabled.to_lowercase().as_str() {
+            "true" | "1" | "yes" | "enabled" | "on" => true,
+            "false" | "0" | "no" | "disabled" | "off" => false,
+            _ => {
+                info!(
+                    "Invalid gemini_uncertainty_mode value: {}, defaulting to disabled",
+                    enabled
+                );
+                false
+            }
+        })
+        .unwrap_or(false);
+
+    info!(
+        "Gemini uncertainty mode is {}",
+        if gemini_uncertainty_mode {
+            "enabled"
+        } else {
+            "disabled"
+        }
+    );
+
+    // Parse the few-shot persona examples (one "message => response" per line)
+    let gemini_persona_examples = config
+        .gemini_persona_examples
+        .as_deref()
+        .map(crate::prompt_templates::parse_persona_examples)
+        .unwrap_or_default();
+    if gemini_persona_examples.is_empty() {
+        info!("No Gemini persona examples configured");
+    } else {
+        info!(
+            "Loaded {} Gemini persona examples (at most {} are used)",
+            gemini_persona_examples.len(),
+            crate::prompt_templates::MAX_PERSONA_EXAMPLES
+        );
+    }
+
+    // Parse per-task sampling temperatures. GEMINI_TEMPERATURE sets the creative tasks'
+    // default; evaluations keep their low temperature unless set on their own.
+    let mut default_temperatures = GeminiTemperatures::default();
+    if let Some(value) = config.gemini_temperature.as_deref() {
+        let temperature = parse_temperature(
+            "gemini_temperature",
+            Some(value),
+            default_temperatures.reply,
+        );
+        default_temperatures.reply = temperature;
+        default_temperatures.interjection = temperature;
+        default_temperatures.image_prompt = temperature;
+    }
+    let gemini_temperatures = GeminiTemperatures {
+        reply: parse_temperature(
+            "gemini_temperature_reply",
+            config.gemini_temperature_reply.as_deref(),
+            default_temperatures.reply,
+        ),
+        interjection: parse_temperature(
+            "gemini_temperature_interjection",
+            config.gemini_temperature_interjection.as_deref(),
+            default_temperatures.interjection,
+        ),
+        image_prompt: parse_temperature(
+            "gemini_temperature_image_prompt",
+            config.gemini_temperature_image_prompt.as_deref(),
+            default_temperatures.image_prompt,
+        ),
+        evaluation: parse_temperature(
+            "gemini_temperature_evaluation",
+            config.gemini_temperature_evaluation.as_deref(),
+            default_temperatures.evaluation,
+        ),
+    };
+    info!(
+        "Gemini temperatures: reply {}, interjection {}, image prompt {}, evaluation {}",
+        gemini_temperatures.reply,
+        gemini_temperatures.interjection,
+        gemini_temperatures.image_prompt,
+        gemini_temperatures.evaluation
+    );
+
+    // Parse the optional top-p and output length limits
+    let gemini_generation = GenerationSettings {
+        top_p: parse_top_p(config.gemini_top_p.as_deref()),
+        max_output_tokens: parse_max_output_tokens(config.gemini_max_output_tokens.as_deref()),
+    };
+    if gemini_generation != GenerationSettings::default() {
+        info!("Gemini generation limits: {:?}", gemini_generation);
+    }
+
+    let gemini_max_retries = config
+        .gemini_max_retries
+        .as_ref()
+        .and_then(|count| count.trim().parse::<u32>().ok())
+        .unwrap_or(DEFAULT_MAX_RETRIES);
+
+    // Parse whether !rephrase edits the original reply (default: false, post a new one)
+    let rephrase_edit_original = config
+        .rephrase_edit_original
+        .as_ref()
+        .map(|enabled| match enabled.to_lowercase().as_str() {
+            "false" | "0" | "no" | "disabled" | "off" => false,
+            "true" | "1" | "yes" | "enabled" | "on" => true,
+            _ => {
+                info!(
+                    "Invalid rephrase_edit_original value: {}, defaulting to disabled",
+                    enabled
+                );
+                false
+            }
+        })
+        .unwrap_or(false);
+
+    // Parse whether direct replies are streamed (default: false)
+    let gemini_stream_replies = config
+        .gemini_stream_replies
+        .as_ref()
+        .map(|enabled| match enabled.to_lowercase().as_str() {
+            "false" | "0" | "no" | "disabled" | "off" => false,
+            "true" | "1" | "yes" | "enabled" | "on" => true,
+            _ => {
+                info!(
+                    "Invalid gemini_stream_replies value: {}, defaulting to disabled",
+                    enabled
+                );
+                false
+            }
+        })
+        .unwrap_or(false);
+
+    // Parse the optional fallback Gemini endpoint (full URL or bare model name)
+    let gemini_fallback_api_endpoint = config
+        .gemini_fallback_api_endpoint
+        .as_ref()
+        .map(|endpoint| endpoint.trim())
+        .filter(|endpoint| !endpoint.is_empty())
+        .map(crate::gemini_api::endpoint_for_model);
+    let gemini_fallback_api_key = config
+        .gemini_fallback_api_key
+        .as_ref()
+        .map(|key| key.trim().to_string())
+        .filter(|key| !key.is_empty());
+
+    match &gemini_fallback_api_endpoint {
+        Some(endpoint) => info!(
+            "Gemini fallback endpoint: {} ({})",
+            endpoint,
+            if gemini_fallback_api_key.is_some() {
+                "separate API key"
+            } else {
+                "primary API key"
+            }
+        ),
+        None => info!("No Gemini fallback endpoint configured"),
+    }
+
+    // Parse which API the AI endpoint speaks (default: Gemini)
+    let llm_api_flavor = config
+        .llm_api_flavor
+        .as_ref()
+        .map(|flavor| {
+            ApiFlavor::parse(flavor).unwrap_or_else(|| {
+                warn!(
+                    "Invalid llm_api_flavor value: {}, defaulting to gemini",
+                    flavor
+                );
+                ApiFlavor::Gemini
+            })
+        })
+        .unwrap_or_default();
+    let llm_model = config
+        .llm_model
+        .as_ref()
+        .map(|model| model.trim().to_string())
+        .filter(|model| !model.is_empty());
+    if llm_api_flavor == ApiFlavor::OpenAiCompatible {
+        info!(
+            "Using an OpenAI-compatible chat completions API (model: {})",
+            llm_model.as_deref().unwrap_or("server default")
+        );
+    }
+
+    // Parse interjection probabilities
+    let interjection_mst3k_probability = config
+        .interjection_mst3k_probability
+        .as_ref()
+        .and_then(|prob| prob.parse::<f64>().ok())
+        .unwrap_or(0.005); // Default: 0.5% chance (1 in 200)
+
+    let interjection_memory_probability = config
+        .interjection_memory_probability
+        .as_ref()
+        .and_then(|prob| prob.parse::<f64>().ok())
+        .unwrap_or(0.005); // Default: 0.5% chance (1 in 200)
+
+    let interjection_pondering_probability = config
+        .interjection_pondering_probability
+        .as_ref()
+        .and_then(|prob| prob.parse::<f64>().ok())
+        .unwrap_or(0.005); // Default: 0.5% chance (1 in 200)
+
+    let interjection_ai_probability = config
+        .interjection_ai_probability
+        .as_ref()
+        .and_then(|prob| prob.parse::<f64>().ok())
+        .unwrap_or(0.005); // Default: 0.5% chance (1 in 200)
+
+    info!(
+        "Interjection probabilities: MST3K: {}, Memory: {}, Pondering: {}, AI: {}",
+        interjection_mst3k_probability,
+        interjection_memory_probability,
+        interjection_pondering_probability,
+        interjection_ai_probability
+    );
+
+    // Parse news interjection probability
+    let interjection_news_probability = config
+        .interjection_news_probability
+        .as_ref()
+        .and_then(|prob| prob.parse::<f64>().ok())
+        .unwrap_or(0.005); // Default: 0.5% chance (1 in 200)
+
+    // Parse minimum messages between interjections
+    let interjection_minimum_messages = config
+        .interjection_minimum_messages
+        .as_ref()
+        .and_then(|msgs| msgs.parse::<usize>().ok())
+        .unwrap_or(7); // Default: 7 messages from others before interjecting
+
+    // Parse the global cap on interjections per hour (0 = no cap)
+    let interjection_max_per_hour = config
+        .interjection_max_per_hour
+        .as_ref()
+        .and_then(|max| max.parse::<usize>().ok())
+        .unwrap_or(0);
+
+    if interjection_max_per_hour > 0 {
+        info!(
+            "Interjections capped at {} per hour across all channels",
+            interjection_max_per_hour
+        );
+    }
+
+    // Parse how close an interjection may come to recent channel messages (0 disables the check)
+    let interjection_similarity_threshold = config
+        .interjection_similarity_threshold
+        .as_ref()
+        .and_then(|threshold| threshold.parse::<f64>().ok())
+        .map(|threshold| threshold.clamp(0.0, 1.0))
+        .unwrap_or(0.8);
+
+    if interjection_similarity_threshold > 0.0 {
+        info!(
+            "Interjections overlapping recent messages by {:.0}% or more are skipped",
+            interjection_similarity_threshold * 100.0
+        );
+    } else {
+        info!("Interjection echo filtering is disabled");
+    }
+
+    // Parse whether interjections wait for someone else to speak after the bot
+    let skip_interjections_after_bot = config
+        .skip_interjections_after_bot
+        .as_ref()
+        .map(|enabled| match enabled.to_lowercase().as_str() {
+            "true" | "1" | "yes" | "enabled" | "on" => true,
+            "false" | "0" | "no" | "disabled" | "off" => false,
+            _ => {
+                info!(
+                    "Invalid skip_interjections_after_bot value: {}, defaulting to enabled",
+                    enabled
+                );
+                true
+            }
+        })
+        .unwrap_or(true);
+
+    info!(
+        "Skipping interjections while the bot has the last word is {}",
+        if skip_interjections_after_bot {
+            "enabled"
+        } else {
+            "disabled"
+        }
+    );
+
+    // Parse the per-channel interjection warm-up (0 seconds disables it)
+    let interjection_warmup_secs = config
+        .interjection_warmup_secs
+        .as_ref()
+        .and_then(|secs| secs.parse::<u64>().ok())
+        .unwrap_or(300);
+    let interjection_warmup_messages = config
+        .interjection_warmup_messages
+        .as_ref()
+        .and_then(|count| count.parse::<usize>().ok())
+        .unwrap_or(20);
+
+    if interjection_warmup_secs > 0 {
+        info!(
+            "Interjections wait per channel for {} seconds or {} messages after startup",
+            interjection_warmup_secs, interjection_warmup_messages
+        );
+    } else {
+        info!("Interjection warm-up is disabled");
+    }
+
+    // Parse adaptive interjections (off by default)
+    let adaptive_interjections_enabled = config
+        .adaptive_interjections
+        .as_ref()
+        .map(|enabled| match enabled.to_lowercase().as_str() {
+            "true" | "1" | "yes" | "enabled" | "on" => true,
+            "false" | "0" | "no" | "disabled" | "off" => false,
+            _ => {
+                info!(
+                    "Invalid adaptive_interjections value: {}, defaulting to disabled",
+                    enabled
+                );
+                false
+            }
+        })
+        .unwrap_or(false);
+
+    let adaptive_interjections = adaptive_interjections_enabled.then(|| {
+        let defaults = AdaptiveSettings::default();
+        let parse = |value: &Option<String>, default: f64| {
+            value
+                .as_ref()
+                .and_then(|value| value.parse::<f64>().ok())
+                .unwrap_or(default)
+        };
+        let settings = AdaptiveSettings {
+            min_factor: parse(
+                &config.adaptive_interjection_min_factor,
+                defaults.min_factor,
+            ),
+            max_factor: parse(
+                &config.adaptive_interjection_max_factor,
+                defaults.max_factor,
+            ),
+            step: parse(&config.adaptive_interjection_step, defaults.step),
+        };
+
+        // The bounds must contain 1.0 so every type can start at its configured probability
+        if settings.min_factor <= 0.0
+            || settings.min_factor > 1.0
+            || settings.max_factor < 1.0
+            || settings.step <= 0.0
+        {
+            warn!(
+                "Invalid adaptive interjection bounds {:?}, using {:?}",
+                settings, defaults
+            );
+            defaults
+        } else {
+            settings
+        }
+    });
+
+    match &adaptive_interjections {
+        Some(settings) => info!(
+            "Adaptive interjections enabled: probabilities scale between {}x and {}x in steps of {}",
+            settings.min_factor, settings.max_factor, settings.step
+        ),
+        None => info!("Adaptive interjections are disabled"),
+    }
+
+    // Parse interjection metrics logging (off by default)
+    let interjection_metrics = config
+        .interjection_metrics
+        .as_ref()
+        .map(|enabled| match enabled.to_lowercase().as_str() {
+            "true" | "1" | "yes" | "enabled" | "on" => true,
+            "false" | "0" | "no" | "disabled" | "off" => false,
+            _ => {
+                info!(
+                    "Invalid interjection_metrics value: {}, defaulting to disabled",
+                    enabled
+                );
+                false
+            }
+        })
+        .unwrap_or(false);
+    info!(
+        "Interjection metrics logging is {}",
+        if interjection_metrics {
+            "enabled"
+        } else {
+            "disabled"
+        }
+    );
+
+    // Parse fill silence configuration
+    let fill_silence_enabled = config
+        .fill_silence_enabled
+        .as_ref()
+        .map(|enabled| match enabled.to_lowercase().as_str() {
+            "false" | "0" | "no" | "disabled" | "off" => false,
+            "true" | "1" | "yes" | "enabled" | "on" => true,
+            _ => {
+                info!(
+                    "Invalid fill_silence_enabled value: {}, defaulting to enabled",
+                    enabled
+                );
+                true
+            }
+        })
+        .unwrap_or(true); // Default to enabled
+
+    let fill_silence_start_hours = config
+        .fill_silence_start_hours
+        .as_ref()
+        .and_then(|hours| hours.parse::<f64>().ok())
+        .unwrap_or(1.0); // Default: 1 hour
+
+    let fill_silence_max_hours = config
+        .fill_silence_max_hours
+        .as_ref()
+        .and_then(|hours| hours.parse::<f64>().ok())
+        .unwrap_or(12.0); // Default: 12 hours
+
+    info!(
+        "Fill silence feature is {}",
+        if fill_silence_enabled {
+            "enabled"
+        } else {
+            "disabled"
+        }
+    );
+    if fill_silence_enabled {
+        info!(
+            "Fill silence configuration: Start increasing after {} hours, reach 100% at {} hours",
+            fill_silence_start_hours, fill_silence_max_hours
+        );
+    }
+
+    // Parse fill silence quiet hours (both ends are needed)
+    let quiet_hour = |value: &Option<String>, key: &str| {
+        value.as_ref().and_then(|hour| {
+            let parsed = crate::fill_silence::parse_quiet_hour(hour);
+            if parsed.is_none() {
+                warn!("Invalid {} value: {}, ignoring quiet hours", key, hour);
+            }
+            parsed
+        })
+    };
+    let fill_silence_quiet_hours = match (
+        quiet_hour(&config.fill_silence_quiet_start, "fill_silence_quiet_start"),
+        quiet_hour(&config.fill_silence_quiet_end, "fill_silence_quiet_end"),
+    ) {
+        (Some(start), Some(end)) if start != end => {
+            let utc_offset = config.fill_silence_utc_offset.as_ref().and_then(|offset| {
+                let parsed = parse_utc_offset(offset);
+                if parsed.is_none() {
+                    warn!(
+                        "Invalid fill_silence_utc_offset value: {}, using the system timezone",
+                        offset
+                    );
+                }
+                parsed
+            });
+            info!(
+                "Spontaneous interjections are quiet from {:02}:00 to {:02}:00",
+                start, end
+            );
+            Some(crate::fill_silence::QuietHours {
+                start,
+                end,
+                utc_offset,
+            })
+        }
+        (Some(_), Some(_)) => {
+            warn!("Fill silence quiet hours start and end at the same hour, ignoring them");
+            None
+        }
+        _ => None,
+    };
+
+    // Parse how often to check for spontaneous interjections
+    let check_secs = |value: &Option<String>, key: &str, default: u64| {
+        value
+            .as_ref()
+            .and_then(|secs| {
+                let parsed = secs.trim().parse::<u64>().ok().filter(|secs| *secs > 0);
+                if parsed.is_none() {
+                    warn!("Invalid {} value: {}, using {}", key, secs, default);
+                }
+                parsed
+            })
+            .unwrap_or(default)
+    };
+    let check_min_secs = check_secs(
+        &config.fill_silence_check_min_secs,
+        "fill_silence_check_min_secs",
+        crate::fill_silence::DEFAULT_CHECK_MIN_SECS,
+    );
+    let check_max_secs = check_secs(
+        &config.fill_silence_check_max_secs,
+        "fill_silence_check_max_secs",
+        crate::fill_silence::DEFAULT_CHECK_MAX_SECS,
+    );
+    let fill_silence_check_interval = if check_min_secs <= check_max_secs {
+        (
+            Duration::from_secs(check_min_secs),
+            Duration::from_secs(check_max_secs),
+        )
+    } else {
+        warn!(
+            "fill_silence_check_min_secs ({}) is more than fill_silence_check_max_secs ({}), swapping them",
+            check_min_secs, check_max_secs
+        );
+        (
+            Duration::from_secs(check_max_secs),
+            Duration::from_secs(check_min_secs),
+        )
+    };
+    info!(
+        "Checking for spontaneous interjections every {}-{} seconds",
+        fill_silence_check_interval.0.as_secs(),
+        fill_silence_check_interval.1.as_secs()
+    );
+
+    // Parse per-channel fill silence settings
+    let fill_silence_channels = crate::fill_silence::parse_fill_silence_channels(
+        config.fill_silence_channels.as_deref().unwrap_or_default(),
+        crate::fill_silence::SilenceSettings {
+            enabled: fill_silence_enabled,
+            start_hours: fill_silence_start_hours,
+            max_hours: fill_silence_max_hours,
+        },
+    );
+
+    // Parse imagine channels
+    let imagine_channels = config
+        .imagine_channels
+        .as_ref()
+        .map(|channels_str| {
+            channels_str
+                .split(',')
+                .map(|channel| channel.trim().to_string())
+                .filter(|channel| !channel.is_empty())
+                .collect::<Vec<String>>()
+        })
+        .unwrap_or_default();
+
+    if !imagine_channels.is_empty() {
+        info!(
+            "Image generation restricted to channels: {:?}",
+            imagine_channels
+        );
+    } else {
+        info!("Image generation allowed in all channels");
+    }
+
+    // Parse image generation retry behavior
+    let image_retry_attempts = config
+        .image_retry_attempts
+        .as_ref()
+        .and_then(|attempts| attempts.parse::<usize>().ok())
+        .unwrap_or(2)
+        .max(1);
+    let image_retry_rewrite_prompt = config
+        .image_retry_rewrite_prompt
+        .as_ref()
+        .map(|enabled| match enabled.to_lowercase().as_str() {
+            "true" | "1" | "yes" | "enabled" | "on" => true,
+            "false" | "0" | "no" | "disabled" | "off" => false,
+            _ => {
+                info!(
+                    "Invalid image_retry_rewrite_prompt value: {}, defaulting to enabled",
+                    enabled
+                );
+                true
+            }
+        })
+        .unwrap_or(true);
+    let image_retry_policy = ImageRetryPolicy {
+        max_attempts: image_retry_attempts,
+        rewrite_rejected_prompts: image_retry_rewrite_prompt,
+    };
+
+    info!(
+        "Image generation: up to {} attempts, rewording rejected prompts {}",
+        image_retry_policy.max_attempts,
+        if image_retry_policy.rewrite_rejected_prompts {
+            "enabled"
+        } else {
+            "disabled"
+        }
+    );
+
+    // Parse the image backend (hosted Pollinations unless a self-hosted endpoint is set)
+    let image_backend = parse_image_backend(
+        config.image_backend.as_deref(),
+        config.image_api_url.as_deref(),
+        config.image_api_key.as_deref(),
+        config.image_api_basic_auth.as_deref(),
+    );
+    match &image_backend {
+        ImageBackendSettings::Pollinations => info!("Image backend: pollinations"),
+        ImageBackendSettings::StableDiffusion { url, .. } => {
+            info!("Image backend: stable_diffusion at {}", url)
+        }
+    }
+
+    // Parse birthday announcements (channel to post in, and the timezone that decides "today")
+    let birthday_channel_id = config
+        .birthday_channel_id
+        .as_ref()
+        .and_then(|id| id.trim().parse::<u64>().ok());
+    let birthday_utc_offset = config.birthday_utc_offset.as_ref().and_then(|offset| {
+        let parsed = parse_utc_offset(offset);
+        if parsed.is_none() {
+            warn!(
+                "Invalid birthday_utc_offset value: {}, using the system timezone",
+                offset
+            );
+        }
+        parsed
+    });
+
+    match birthday_channel_id {
+        Some(channel_id) => info!(
+            "Birthday announcements enabled in channel {} (timezone: {})",
+            channel_id,
+            birthday_utc_offset
+                .map(|offset| offset.to_string())
+                .unwrap_or_else(|| "system local".to_string())
+        ),
+        None => info!("Birthday announcements disabled (no birthday_channel_id)"),
+    }
+
+    // Parse the timezone !remindme reads times of day and dates in
+    let reminder_utc_offset = config.reminder_utc_offset.as_ref().and_then(|offset| {
+        let parsed = parse_utc_offset(offset);
+        if parsed.is_none() {
+            warn!(
+                "Invalid reminder_utc_offset value: {}, using the system timezone",
+                offset
+            );
+        }
+        parsed
+    });
+
+    // Parse per-command channel rules (allowlists and NSFW-only commands)
+    let mut command_channel_rules = CommandChannelRules::default();
+    command_channel_rules.set_allowed_channels("imagine", &imagine_channels);
+
+    let screenshot_channels = config
+        .screenshot_channels
+        .as_ref()
+        .map(|channels_str| {
+            channels_str
+                .split(',')
+                .map(|channel| channel.trim().to_string())
+                .filter(|channel| !channel.is_empty())
+                .collect::<Vec<String>>()
+        })
+        .unwrap_or_default();
+
+    if !screenshot_channels.is_empty() {
+        info!(
+            "Screenshot commands restricted to channels: {:?}",
+            screenshot_channels
+        );
+        for command in SCREENSHOT_COMMANDS {
+            command_channel_rules.set_allowed_channels(command, &screenshot_channels);
+        }
+    }
+
+    // Parse whether screenshots are uploaded with their subtitle as alt text
+    let screenshot_alt_text = config
+        .screenshot_alt_text
+        .as_ref()
+        .map(|enabled| match enabled.to_lowercase().as_str() {
+            "true" | "1" | "yes" | "enabled" | "on" => true,
+            "false" | "0" | "no" | "disabled" | "off" => false,
+            _ => {
+                info!(
+                    "Invalid screenshot_alt_text value: {}, defaulting to enabled",
+                    enabled
+                );
+                true
+            }
+        })
+        .unwrap_or(true);
+
+    info!(
+        "Screenshot alt text is {}",
+        if screenshot_alt_text {
+            "enabled"
+        } else {
+            "disabled"
+        }
+    );
+
+    // Parse whether !8ball answers come from Gemini
+    let eightball_use_ai = config
+        .eightball_use_ai
+        .as_ref()
+        .map(|enabled| match enabled.to_lowercase().as_str() {
+            "true" | "1" | "yes" | "enabled" | "on" => true,
+            "false" | "0" | "no" | "disabled" | "off" => false,
+            _ => {
+                info!(
+                    "Invalid eightball_use_ai value: {}, defaulting to disabled",
+                    enabled
+                );
+                false
+            }
+        })
+        .unwrap_or(false);
+
+    // Parse the !urban filter; on by default, with any extra words added to the defaults
+    let urban_filter_nsfw = config
+        .urban_filter_nsfw
+        .as_ref()
+        .map(|enabled| match enabled.to_lowercase().as_str() {
+            "true" | "1" | "yes" | "enabled" | "on" => true,
+            "false" | "0" | "no" | "disabled" | "off" => false,
+            _ => {
+                info!(
+                    "Invalid urban_filter_nsfw value: {}, defaulting to enabled",
+                    enabled
+                );
+                true
+            }
+        })
+        .unwrap_or(true);
+    let urban_blocked_terms = urban_filter_nsfw.then(|| {
+        let mut terms: Vec<String> = crate::urban::DEFAULT_BLOCKED_TERMS
+            .iter()
+            .map(|term| term.to_string())
+            .collect();
+        if let Some(extra) = &config.urban_blocked_terms {
+            terms.extend(
+                extra
+                    .split(',')
+                    .map(|term| term.trim().to_lowercase())
+                    .filter(|term| !term.is_empty()),
+            );
+        }
+        terms
+    });
+    info!(
+        "!urban NSFW filter is {}",
+        if urban_filter_nsfw {
+            "enabled"
+        } else {
+            "disabled"
+        }
+    );
+
+    // Parse how long screenshot GIFs may run
+    let screenshot_gif_max_duration = config
+        .screenshot_gif_max_secs
+        .as_ref()
+        .and_then(|secs| secs.trim().parse::<u64>().ok())
+        .filter(|secs| *secs > 0)
+        .map(Duration::from_secs)
+        .unwrap_or(DEFAULT_GIF_MAX_DURATION);
+    info!(
+        "Screenshot GIFs run for at most {} seconds",
+        screenshot_gif_max_duration.as_secs()
+    );
+
+    // Parse the extra screenshot sites; they share the screenshot channel allowlist
+    let screenshot_sources =
+        parse_screenshot_sources(config.screenshot_sources.as_deref().unwrap_or_default());
+    for source in &screenshot_sources {
+        command_channel_rules.set_allowed_channels(&source.command, &screenshot_channels);
+    }
+
+    let phrase_responses = crate::phrase_responses::parse_phrase_responses(
+        config.phrase_responses.as_deref().unwrap_or_default(),
+    );
+    info!("{} phrase responses", phrase_responses.len());
+
+    if let Some(commands) = &config.nsfw_only_commands {
+        for command in commands
+            .split(',')
+            .map(|command| command.trim().trim_start_matches('!').to_lowercase())
+            .filter(|command| !command.is_empty())
+        {
+            info!("Command !{} restricted to NSFW channels", command);
+            command_channel_rules.require_nsfw(&command);
+        }
+    }
+
+    // Parse quiet channels configuration (names and IDs)
+    let mut quiet_channels = config
+        .quiet_channel_names
+        .as_ref()
+        .or(config.quiet_channel_name.as_ref())
+        .map(|names| {
+            names
+                .split(',')
+                .map(|channel| channel.trim().to_string())
+                .filter(|channel| !channel.is_empty())
+                .collect::<Vec<String>>()
+        })
+        .unwrap_or_default();
+
+    // Add quiet channel IDs to the list
+    let quiet_channel_ids = config
+        .quiet_channel_ids
         .as_ref()
         .or(config.quiet_channel_id.as_ref())
         .map(|ids| {
@@ -443,6 +1981,326 @@ pub fn parse_config(config: &Config) -> ParsedConfig {
         info!("No quiet channels configured - bot will respond normally in all channels");
     }
 
+    // Parse whether channel topics are included in reply and interjection prompts
+    let channel_topic_context = config
+        .channel_topic_context
+        .as_ref()
+        .map(|enabled| match enabled.to_lowercase().as_str() {
+            "true" | "1" | "yes" | "enabled" | "on" => true,
+            "false" | "0" | "no" | "disabled" | "off" => false,
+            _ => {
+                info!(
+                    "Invalid channel_topic_context value: {}, defaulting to enabled",
+                    enabled
+                );
+                true
+            }
+        })
+        .unwrap_or(true);
+
+    info!(
+        "Channel topic context is {}",
+        if channel_topic_context {
+            "enabled"
+        } else {
+            "disabled"
+        }
+    );
+
+    // Optional file of fallback MST3K quotes (one per line)
+    let mst3k_quotes_file = config
+        .mst3k_quotes_file
+        .as_ref()
+        .map(|path| path.trim().to_string())
+        .filter(|path| !path.is_empty());
+
+    if let Some(path) = &mst3k_quotes_file {
+        info!("Fallback MST3K quotes will be loaded from {}", path);
+    }
+
+    // Parse the shortest message !quote -dud will pick by default
+    let quote_dud_min_length = config
+        .quote_dud_min_length
+        .as_ref()
+        .and_then(|len| len.parse::<usize>().ok())
+        .unwrap_or(crate::db_utils::DEFAULT_DUD_QUOTE_MIN_LENGTH);
+
+    info!(
+        "!quote -dud minimum message length set to {}",
+        quote_dud_min_length
+    );
+
+    // Parse whether remembered messages can come from other servers (off by default)
+    let all_guilds = config
+        .all_guilds
+        .as_ref()
+        .map(|enabled| match enabled.to_lowercase().as_str() {
+            "true" | "1" | "yes" | "enabled" | "on" => true,
+            "false" | "0" | "no" | "disabled" | "off" => false,
+            _ => {
+                info!(
+                    "Invalid all_guilds value: {}, defaulting to disabled",
+                    enabled
+                );
+                false
+            }
+        })
+        .unwrap_or(false);
+
+    if all_guilds {
+        info!("!quote -dud and memory interjections will draw on every server");
+    } else {
+        info!("!quote -dud and memory interjections are limited to the current server");
+    }
+
+    // Parse the daily digest (off unless a channel is configured)
+    let daily_digest = config
+        .daily_digest_channel_id
+        .as_ref()
+        .and_then(|id| id.trim().parse::<u64>().ok())
+        .map(|channel_id| {
+            let post_time = config
+                .daily_digest_time
+                .as_ref()
+                .and_then(|time| {
+                    let parsed = NaiveTime::parse_from_str(time.trim(), "%H:%M").ok();
+                    if parsed.is_none() {
+                        warn!("Invalid daily_digest_time value: {}, using 21:00", time);
+                    }
+                    parsed
+                })
+                .unwrap_or_else(|| NaiveTime::from_hms_opt(21, 0, 0).unwrap());
+            let utc_offset = config.daily_digest_utc_offset.as_ref().and_then(|offset| {
+                let parsed = parse_utc_offset(offset);
+                if parsed.is_none() {
+                    warn!(
+                        "Invalid daily_digest_utc_offset value: {}, using the system timezone",
+                        offset
+                    );
+                }
+                parsed
+            });
+            let sections = match &config.daily_digest_sections {
+                Some(names) => names
+                    .split(',')
+                    .filter(|name| !name.trim().is_empty())
+                    .filter_map(|name| {
+                        let section = DigestSection::parse(name);
+                        if section.is_none() {
+                            warn!("Ignoring unknown daily digest section: {}", name.trim());
+                        }
+                        section
+                    })
+                    .collect(),
+                None => DigestSection::ALL.to_vec(),
+            };
+
+            DailyDigestSettings {
+                channel_id: ChannelId::new(channel_id),
+                post_time,
+                utc_offset,
+                sections,
+                quote_min_length: quote_dud_min_length,
+            }
+        });
+
+    match &daily_digest {
+        Some(digest) => info!(
+            "Daily digest enabled in channel {} at {} (timezone: {}, sections: {:?})",
+            digest.channel_id,
+            digest.post_time.format("%H:%M"),
+            digest
+                .utc_offset
+                .map(|offset| offset.to_string())
+                .unwrap_or_else(|| "system local".to_string()),
+            digest.sections
+        ),
+        None => info!("Daily digest disabled (no daily_digest_channel_id)"),
+    }
+
+    // Parse the user IDs allowed to run owner-only commands
+    let owner_ids = parse_user_ids("owner_ids", config.owner_ids.as_deref());
+
+    if owner_ids.is_empty() {
+        info!("No owner IDs configured - owner commands are limited to the application owner");
+    } else {
+        info!("Owner IDs configured: {:?}", owner_ids);
+    }
+
+    // Parse the user IDs allowed to run admin commands like !interject
+    let admin_user_ids = parse_user_ids("admin_user_ids", config.admin_user_ids.as_deref());
+    if !admin_user_ids.is_empty() {
+        info!("Admin user IDs configured: {:?}", admin_user_ids);
+    }
+
+    // Parse reaction features (opt-in, each one requires the reactions intent)
+    let reaction_features = parse_reaction_features(config.reaction_features.as_deref());
+
+    if !reaction_features.is_empty() {
+        info!("Reaction features enabled: {:?}", reaction_features);
+    } else {
+        info!("No reaction features enabled");
+    }
+
+    // Parse keyword → emoji reactions (off unless configured)
+    let keyword_reactions = config
+        .keyword_reactions
+        .as_deref()
+        .map(KeywordReactions::parse)
+        .unwrap_or_default();
+    if !keyword_reactions.is_empty() {
+        info!(
+            "Keyword reactions enabled for {} keywords",
+            keyword_reactions.len()
+        );
+    }
+
+    // Parse reaction quoting (which emoji triggers it and where quotes go)
+    let quote_reaction_emoji = config
+        .quote_reaction_emoji
+        .as_deref()
+        .map(str::trim)
+        .filter(|emoji| !emoji.is_empty())
+        .unwrap_or(DEFAULT_QUOTE_REACTION_EMOJI)
+        .to_string();
+    let quote_reaction_channel_id = config
+        .quote_reaction_channel_id
+        .as_ref()
+        .and_then(|id| id.trim().parse::<u64>().ok());
+    if reaction_features.iter().any(|f| f == "reaction_quote") {
+        info!(
+            "Reaction quotes enabled with {} (posting to {})",
+            quote_reaction_emoji,
+            quote_reaction_channel_id
+                .map(|id| format!("channel {}", id))
+                .unwrap_or_else(|| "the same channel".to_string())
+        );
+    }
+
+    // Parse !subtitles pagination (lines per message, messages per command)
+    let subtitles_page_size = config
+        .subtitles_page_size
+        .as_ref()
+        .and_then(|size| size.parse::<usize>().ok())
+        .unwrap_or(40);
+    let subtitles_max_pages = config
+        .subtitles_max_pages
+        .as_ref()
+        .and_then(|pages| pages.parse::<usize>().ok())
+        .unwrap_or(3);
+
+    info!(
+        "!subtitles pagination: {} lines per page, up to {} pages per command",
+        subtitles_page_size, subtitles_max_pages
+    );
+
+    // Parse the per-user command cooldown (0 disables it)
+    let command_cooldown_seconds = config
+        .command_cooldown_seconds
+        .as_ref()
+        .and_then(|secs| secs.parse::<u64>().ok())
+        .unwrap_or(0);
+    if command_cooldown_seconds > 0 {
+        info!(
+            "Each user can run a given command once every {} seconds",
+            command_cooldown_seconds
+        );
+    } else {
+        info!("No default per-user command cooldown");
+    }
+    let command_cooldowns = config
+        .command_cooldowns
+        .as_deref()
+        .map(parse_cooldowns)
+        .unwrap_or_default();
+    for (command, cooldown) in &command_cooldowns {
+        info!(
+            "Each user can run !{} once every {} seconds",
+            command,
+            cooldown.as_secs()
+        );
+    }
+
+    // Parse command argument length limits (0 = no limit)
+    let command_max_arg_length = config
+        .command_max_arg_length
+        .as_ref()
+        .and_then(|length| length.parse::<usize>().ok())
+        .unwrap_or(DEFAULT_MAX_ARG_LENGTH);
+    let argument_limits = ArgumentLimits::parse(
+        command_max_arg_length,
+        config.command_max_arg_lengths.as_deref().unwrap_or(""),
+    );
+    info!(
+        "Command arguments are limited to {} characters by default ({:?})",
+        command_max_arg_length, argument_limits
+    );
+
+    // Parse whether to register slash commands (on by default)
+    let slash_commands_enabled = config
+        .slash_commands
+        .as_ref()
+        .map(|enabled| match enabled.to_lowercase().as_str() {
+            "false" | "0" | "no" | "disabled" | "off" => false,
+            "true" | "1" | "yes" | "enabled" | "on" => true,
+            _ => {
+                info!(
+                    "Invalid slash_commands value: {}, defaulting to enabled",
+                    enabled
+                );
+                true
+            }
+        })
+        .unwrap_or(true);
+    info!(
+        "Slash commands are {}",
+        if slash_commands_enabled {
+            "enabled"
+        } else {
+            "disabled"
+        }
+    );
+
+    // Parse the reply sent when the Gemini rate limiter turns away a direct reply
+    // (an empty message keeps the bot quiet, as it always is for interjections)
+    let rate_limit_reply_message = config
+        .rate_limit_reply_message
+        .clone()
+        .unwrap_or_else(|| DEFAULT_RATE_LIMIT_REPLY.to_string());
+    if rate_limit_reply_message.trim().is_empty() {
+        info!("Rate-limited replies will be skipped silently");
+    }
+
+    // Parse "feature unavailable" wording overrides
+    let mut unavailable_messages = FeatureUnavailableMessages::default();
+    if let Some(template) = &config.unavailable_message_template {
+        unavailable_messages.set_template(template);
+    }
+    for (reason, message) in [
+        (FeatureUnavailable::Ai, &config.unavailable_message_ai),
+        (
+            FeatureUnavailable::MessageHistory,
+            &config.unavailable_message_history,
+        ),
+        (
+            FeatureUnavailable::QuoteDatabase,
+            &config.unavailable_message_quote_db,
+        ),
+        (
+            FeatureUnavailable::Search,
+            &config.unavailable_message_search,
+        ),
+        (
+            FeatureUnavailable::ImageGeneration,
+            &config.unavailable_message_image,
+        ),
+    ] {
+        if let Some(message) = message {
+            info!("Using custom unavailable message for {:?}", reason);
+            unavailable_messages.set_message(reason, message);
+        }
+    }
+
     info!(
         "DuckDuckGo search feature is {}",
         if duckduckgo_search_enabled {
@@ -454,26 +2312,99 @@ pub fn parse_config(config: &Config) -> ParsedConfig {
 
     ParsedConfig {
         bot_name,
+        extra_address_patterns,
+        extra_negative_patterns,
         message_history_limit,
+        channel_history_limits,
         db_trim_interval,
+        trim_keep_protected,
+        db_health_check_interval,
+        info_count_cache_secs,
+        wikipedia_cache_ttl,
+        wikipedia_cache_max_entries,
+        http_timeout,
+        message_store,
+        gemini_reply_timeout,
         gemini_rate_limit_minute,
         gemini_rate_limit_day,
+        user_reply_burst,
+        user_reply_refill_per_minute,
         gemini_image_rate_limit_minute,
         gemini_image_rate_limit_day,
         gateway_bot_ids,
         duckduckgo_search_enabled,
         gemini_context_messages,
+        gemini_uncertainty_mode,
+        gemini_persona_examples,
+        gemini_temperatures,
+        gemini_generation,
+        gemini_max_retries,
+        rephrase_edit_original,
+        gemini_stream_replies,
+        gemini_fallback_api_endpoint,
+        gemini_fallback_api_key,
+        llm_api_flavor,
+        llm_model,
+        reply_chain_depth,
+        conversation_memory_exchanges,
+        conversation_memory_idle_mins,
+        media_limits,
+        context_collapse_duplicates,
+        context_paste_line_threshold,
+        context_exclude_pastes,
         interjection_mst3k_probability,
         interjection_memory_probability,
         interjection_pondering_probability,
         interjection_ai_probability,
         imagine_channels,
+        command_channel_rules,
+        screenshot_alt_text,
+        eightball_use_ai,
+        urban_blocked_terms,
+        screenshot_gif_max_duration,
+        screenshot_sources,
+        phrase_responses,
+        image_retry_policy,
+        image_backend,
+        birthday_channel_id,
+        birthday_utc_offset,
+        reminder_utc_offset,
+        daily_digest,
         interjection_news_probability,
         interjection_minimum_messages,
+        interjection_max_per_hour,
+        interjection_similarity_threshold,
+        skip_interjections_after_bot,
+        interjection_warmup_secs,
+        interjection_warmup_messages,
+        adaptive_interjections,
+        interjection_metrics,
         fill_silence_enabled,
         fill_silence_start_hours,
         fill_silence_max_hours,
+        fill_silence_quiet_hours,
+        fill_silence_check_interval,
+        fill_silence_channels,
         quiet_channels,
+        owner_ids,
+        admin_user_ids,
+        fetch_uncached_members,
+        quote_dud_min_length,
+        all_guilds,
+        channel_topic_context,
+        mst3k_quotes_file,
         giphy_api_key: config.giphy_api_key.clone(),
+        reaction_features,
+        keyword_reactions,
+        quote_reaction_emoji,
+        quote_reaction_channel_id,
+        unavailable_messages,
+        rate_limit_reply_message,
+        subtitles_page_size,
+        subtitles_max_pages,
+        command_cooldown_seconds,
+        command_cooldowns,
+        argument_limits,
+        slash_commands_enabled,
     }
 }
@@ -0,0 +1,250 @@
+use serenity::model::id::{ChannelId, UserId};
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+// Longest user message or reply kept per exchange, so memory can't crowd out the prompt
+const MAX_EXCHANGE_CHARS: usize = 500;
+
+/// One back-and-forth between a user and the bot
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Exchange {
+    pub user_message: String,
+    pub bot_reply: String,
+}
+
+fn truncate(text: &str) -> String {
+    if text.chars().count() <= MAX_EXCHANGE_CHARS {
+        text.to_string()
+    } else {
+        let cut: String = text.chars().take(MAX_EXCHANGE_CHARS).collect();
+        format!("{cut}…")
+    }
+}
+
+// A user's exchanges in one channel and when the last one happened
+struct Conversation {
+    exchanges: VecDeque<Exchange>,
+    last_active: Instant,
+}
+
+/// Each user's last few exchanges with the bot per channel, so follow-ups like "what about
+/// the second one?" have something to refer to. A conversation is forgotten once it has
+/// been idle for the timeout.
+pub struct ConversationMemory {
+    max_exchanges: usize,
+    idle_timeout: Duration,
+    conversations: std::sync::Mutex<HashMap<(ChannelId, UserId), Conversation>>,
+}
+
+impl ConversationMemory {
+    pub fn new(max_exchanges: usize, idle_timeout: Duration) -> Self {
+        Self {
+            max_exchanges,
+            idle_timeout,
+            conversations: std::sync::Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Remember a reply to a user, dropping their oldest exchange in the channel past the limit
+    pub fn record(
+        &self,
+        channel_id: ChannelId,
+        user_id: UserId,
+        user_message: &str,
+        bot_reply: &str,
+    ) {
+        self.record_at(channel_id, user_id, user_message, bot_reply, Instant::now());
+    }
+
+    /// The user's exchanges with the bot in the channel, oldest first (empty once idle)
+    pub fn recent(&self, channel_id: ChannelId, user_id: UserId) -> Vec<Exchange> {
+        self.recent_at(channel_id, user_id, Instant::now())
+    }
+
+    /// Forget every conversation with a user, in all channels
+    pub fn forget_user(&self, user_id: UserId) {
+        self.conversations
+            .lock()
+            .unwrap()
+            .retain(|(_, user), _| *user != user_id);
+    }
+
+    fn record_at(
+        &self,
+        channel_id: ChannelId,
+        user_id: UserId,
+        user_message: &str,
+        bot_reply: &str,
+        now: Instant,
+    ) {
+        if self.max_exchanges == 0 {
+            return;
+        }
+        let mut conversations = self.conversations.lock().unwrap();
+        // Drop idle conversations so the map doesn't grow forever
+        conversations.retain(|_, conversation| {
+            now.duration_since(conversation.last_active) < self.idle_timeout
+        });
+
+        let conversation = conversations
+            .entry((channel_id, user_id))
+            .or_insert_with(|| Conversation {
+                exchanges: VecDeque::new(),
+                last_active: now,
+            });
+        conversation.exchanges.push_back(Exchange {
+            user_message: truncate(user_message),
+            bot_reply: truncate(bot_reply),
+        });
+        while conversation.exchanges.len() > self.max_exchanges {
+            conversation.exchanges.pop_front();
+        }
+        conversation.last_active = now;
+    }
+
+    fn recent_at(&self, channel_id: ChannelId, user_id: UserId, now: Instant) -> Vec<Exchange> {
+        let conversations = self.conversations.lock().unwrap();
+        conversations
+            .get(&(channel_id, user_id))
+            .filter(|conversation| now.duration_since(conversation.last_active) < self.idle_timeout)
+            .map(|conversation| conversation.exchanges.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+}
+
+/// The user's recent exchanges as a section to put before their message, kept apart from
+/// the channel context; None when there are none
+pub fn format_exchanges(user_name: &str, bot_name: &str, exchanges: &[Exchange]) -> Option<String> {
+    if exchanges.is_empty() {
+        return None;
+    }
+
+    let lines = exchanges
+        .iter()
+        .map(|exchange| {
+            format!(
+                "{user_name}: {}\n{bot_name}: {}",
+                exchange.user_message, exchange.bot_reply
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    Some(format!(
+        "[Your recent conversation with {user_name}, oldest first:]\n{lines}\n[End of conversation]\n\n"
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_conversation_memory_keeps_recent_exchanges_per_user() {
+        let memory = ConversationMemory::new(2, Duration::from_secs(60));
+        let (channel, other_channel) = (ChannelId::new(1), ChannelId::new(2));
+        let (user, other_user) = (UserId::new(10), UserId::new(11));
+        let start = Instant::now();
+
+        memory.record_at(
+            channel,
+            user,
+            "name three bad movies",
+            "Manos, Troll 2, Eegah",
+            start,
+        );
+        memory.record_at(
+            channel,
+            user,
+            "what about the second one?",
+            "Nilbog!",
+            start,
+        );
+        memory.record_at(channel, user, "who made it?", "Claudio Fragasso", start);
+        memory.record_at(other_channel, user, "hi", "hello", start);
+
+        // Only the last two exchanges are kept, and only for that user in that channel
+        let recent = memory.recent_at(channel, user, start);
+        assert_eq!(
+            recent,
+            vec![
+                Exchange {
+                    user_message: "what about the second one?".to_string(),
+                    bot_reply: "Nilbog!".to_string(),
+                },
+                Exchange {
+                    user_message: "who made it?".to_string(),
+                    bot_reply: "Claudio Fragasso".to_string(),
+                },
+            ]
+        );
+        assert!(memory.recent_at(channel, other_user, start).is_empty());
+        assert_eq!(memory.recent_at(other_channel, user, start).len(), 1);
+
+        // Forgetting a user clears them from every channel, leaving everyone else
+        let forgotten = ConversationMemory::new(2, Duration::from_secs(60));
+        forgotten.record_at(channel, user, "a", "b", start);
+        forgotten.record_at(other_channel, user, "c", "d", start);
+        forgotten.record_at(channel, other_user, "e", "f", start);
+        forgotten.forget_user(user);
+        assert!(forgotten.recent_at(channel, user, start).is_empty());
+        assert!(forgotten.recent_at(other_channel, user, start).is_empty());
+        assert_eq!(forgotten.recent_at(channel, other_user, start).len(), 1);
+
+        assert_eq!(
+            format_exchanges("Mike", "Crow", &recent).unwrap(),
+            "[Your recent conversation with Mike, oldest first:]\n\
+             Mike: what about the second one?\nCrow: Nilbog!\n\
+             Mike: who made it?\nCrow: Claudio Fragasso\n\
+             [End of conversation]\n\n"
+        );
+        assert_eq!(format_exchanges("Mike", "Crow", &[]), None);
+    }
+
+    #[test]
+    fn test_conversation_memory_expires_when_idle() {
+        let memory = ConversationMemory::new(3, Duration::from_secs(60));
+        let (channel, user) = (ChannelId::new(1), UserId::new(10));
+        let start = Instant::now();
+
+        memory.record_at(channel, user, "first", "one", start);
+        // Each exchange keeps the conversation alive
+        memory.record_at(
+            channel,
+            user,
+            "second",
+            "two",
+            start + Duration::from_secs(50),
+        );
+        assert_eq!(
+            memory
+                .recent_at(channel, user, start + Duration::from_secs(100))
+                .len(),
+            2
+        );
+        assert!(memory
+            .recent_at(channel, user, start + Duration::from_secs(110))
+            .is_empty());
+
+        // An expired conversation starts over
+        memory.record_at(
+            channel,
+            user,
+            "third",
+            "three",
+            start + Duration::from_secs(200),
+        );
+        assert_eq!(
+            memory.recent_at(channel, user, start + Duration::from_secs(200)),
+            vec![Exchange {
+                user_message: "third".to_string(),
+                bot_reply: "three".to_string(),
+            }]
+        );
+
+        // A limit of 0 turns memory off
+        let off = ConversationMemory::new(0, Duration::from_secs(60));
+        off.record_at(channel, user, "first", "one", start);
+        assert!(off.recent_at(channel, user, start).is_empty());
+    }
+}
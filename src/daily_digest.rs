@@ -0,0 +1,214 @@
+use crate::db_utils::{self, ActivitySummary};
+use chrono::{FixedOffset, NaiveDateTime, NaiveTime, Utc};
+use serenity::all::Http;
+use serenity::model::id::ChannelId;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio_rusqlite::Connection as SqliteConnection;
+use tracing::{error, info, warn};
+
+// The digest covers the 24 hours before it's posted
+const DIGEST_WINDOW_SECS: i64 = 24 * 60 * 60;
+// Quotes longer than this are cut so the digest stays short
+const MAX_QUOTE_CHARS: usize = 300;
+
+/// A part of the daily digest that can be turned on or off in config
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DigestSection {
+    MessageCount,
+    MostActive,
+    Quote,
+}
+
+impl DigestSection {
+    pub const ALL: [DigestSection; 3] = [
+        DigestSection::MessageCount,
+        DigestSection::MostActive,
+        DigestSection::Quote,
+    ];
+
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().replace('-', "_").as_str() {
+            "count" | "message_count" | "messages" => Some(DigestSection::MessageCount),
+            "active" | "most_active" => Some(DigestSection::MostActive),
+            "quote" => Some(DigestSection::Quote),
+            _ => None,
+        }
+    }
+}
+
+/// Where, when and what the daily digest posts
+#[derive(Clone, Debug)]
+pub struct DailyDigestSettings {
+    pub channel_id: ChannelId,
+    pub post_time: NaiveTime,
+    /// Timezone for `post_time` (None = system local)
+    pub utc_offset: Option<FixedOffset>,
+    pub sections: Vec<DigestSection>,
+    /// Shortest message worth quoting
+    pub quote_min_length: usize,
+}
+
+// The current time in the configured timezone, or the machine's local time if none is set
+fn now_in(offset: Option<FixedOffset>) -> NaiveDateTime {
+    match offset {
+        Some(offset) => Utc::now().with_timezone(&offset).naive_local(),
+        None => chrono::Local::now().naive_local(),
+    }
+}
+
+/// How long to wait from `now` until the next time the digest is due
+pub fn time_until_next_post(now: NaiveDateTime, post_time: NaiveTime) -> Duration {
+    let mut next = now.date().and_time(post_time);
+    if next <= now {
+        next += chrono::Duration::days(1);
+    }
+    (next - now).to_std().unwrap_or(Duration::from_secs(60))
+}
+
+/// The digest message, or None if nothing was said all day
+pub fn format_digest(summary: &ActivitySummary, sections: &[DigestSection]) -> Option<String> {
+    if summary.message_count == 0 {
+        return None;
+    }
+
+    let mut lines = vec!["📰 **Daily digest**".to_string()];
+    for section in sections {
+        match section {
+            DigestSection::MessageCount => {
+                let plural = if summary.message_count == 1 { "" } else { "s" };
+                lines.push(format!(
+                    "💬 {} message{} in the last 24 hours",
+                    summary.message_count, plural
+                ));
+            }
+            DigestSection::MostActive => {
+                if let Some((name, count)) = &summary.most_active {
+                    lines.push(format!("🏆 Most active: **{name}** ({count} messages)"));
+                }
+            }
+            DigestSection::Quote => {
+                if let Some((name, content)) = &summary.quote {
+                    let content = content.split_whitespace().collect::<Vec<_>>().join(" ");
+                    let content = if content.chars().count() > MAX_QUOTE_CHARS {
+                        let truncated: String = content.chars().take(MAX_QUOTE_CHARS - 1).collect();
+                        format!("{}…", truncated.trim_end())
+                    } else {
+                        content
+                    };
+                    lines.push(format!("💭 Quote of the day: \"{content}\" — {name}"));
+                }
+            }
+        }
+    }
+
+    Some(lines.join("\n"))
+}
+
+/// Build and post one digest covering the last 24 hours
+pub async fn post_daily_digest(
+    http: &Http,
+    conn: Arc<Mutex<SqliteConnection>>,
+    settings: &DailyDigestSettings,
+) -> anyhow::Result<()> {
+    // Leave the bot's own chatter out of the stats
+    let bot_id = match http.get_current_user().await {
+        Ok(user) => Some(user.id.to_string()),
+        Err(e) => {
+            warn!(
+                "Couldn't look up the bot user for the daily digest: {:?}",
+                e
+            );
+            None
+        }
+    };
+
+    let until = Utc::now().timestamp();
+    let summary = db_utils::activity_summary(
+        conn,
+        until - DIGEST_WINDOW_SECS,
+        until,
+        bot_id,
+        settings.quote_min_length,
+    )
+    .await
+    .map_err(|e| anyhow::anyhow!("Error summarizing the day's activity: {}", e))?;
+
+    let Some(digest) = format_digest(&summary, &settings.sections) else {
+        info!("No messages in the last 24 hours, skipping the daily digest");
+        return Ok(());
+    };
+
+    crate::utils::send_without_pings(http, settings.channel_id, digest).await?;
+    info!("Posted daily digest to channel {}", settings.channel_id);
+    Ok(())
+}
+
+/// Daily digest scheduler: sleeps until the configured time, posts, and repeats
+pub async fn run_daily_digest_scheduler(
+    http: Arc<Http>,
+    conn: Arc<Mutex<SqliteConnection>>,
+    settings: DailyDigestSettings,
+) {
+    loop {
+        let wait = time_until_next_post(now_in(settings.utc_offset), settings.post_time);
+        info!(
+            "Next daily digest in {} minutes",
+            wait.as_secs().div_ceil(60)
+        );
+        tokio::time::sleep(wait).await;
+
+        if let Err(e) = post_daily_digest(&http, conn.clone(), &settings).await {
+            error!("Error posting daily digest: {:?}", e);
+        }
+
+        // Don't post twice if the clock lands exactly on the post time again
+        tokio::time::sleep(Duration::from_secs(60)).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    #[test]
+    fn test_digest_schedule_and_sections() {
+        let post_time = NaiveTime::from_hms_opt(21, 0, 0).unwrap();
+        let morning = NaiveDate::from_ymd_opt(2024, 5, 1)
+            .unwrap()
+            .and_hms_opt(9, 30, 0)
+            .unwrap();
+        assert_eq!(
+            time_until_next_post(morning, post_time),
+            Duration::from_secs(11 * 3600 + 30 * 60)
+        );
+        // Right at (or past) the post time, the next one is tomorrow
+        let evening = morning.date().and_time(post_time);
+        assert_eq!(
+            time_until_next_post(evening, post_time),
+            Duration::from_secs(24 * 3600)
+        );
+
+        let summary = ActivitySummary {
+            message_count: 42,
+            most_active: Some(("Bob".to_string(), 17)),
+            quote: Some(("Carol".to_string(), "I   love\nthis show".to_string())),
+        };
+        assert_eq!(
+            format_digest(&summary, &DigestSection::ALL).unwrap(),
+            "📰 **Daily digest**\n💬 42 messages in the last 24 hours\n🏆 Most active: **Bob** (17 messages)\n💭 Quote of the day: \"I love this show\" — Carol"
+        );
+        assert_eq!(
+            format_digest(&summary, &[DigestSection::Quote]).unwrap(),
+            "📰 **Daily digest**\n💭 Quote of the day: \"I love this show\" — Carol"
+        );
+
+        // Nothing to report on a silent day
+        assert_eq!(
+            format_digest(&ActivitySummary::default(), &DigestSection::ALL),
+            None
+        );
+    }
+}
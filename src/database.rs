@@ -1,3 +1,5 @@
+use crate::feature_unavailable::{FeatureUnavailable, FeatureUnavailableMessages};
+use crate::streaming_reply::{pack_lines, DISCORD_MESSAGE_LIMIT};
 use anyhow::Result;
 use mysql::{prelude::*, OptsBuilder, Pool};
 use rand::RngExt;
@@ -110,6 +112,8 @@ impl DatabaseManager {
         }
     }
 
+    // Post a random quote or slogan matching the search. Up to `count` different quotes are
+    // posted at once; slogans are always one at a time.
     pub async fn query_random_entry(
         &self,
         http: &Http,
@@ -117,12 +121,18 @@ impl DatabaseManager {
         search_term: Option<String>,
         show_name: Option<String>,
         entry_type: &str,
+        count: usize,
     ) -> Result<()> {
         // Check if we have MySQL connection info
         if self.pool.is_none() {
             error!("❌ MySQL pool is None when handling {} command", entry_type);
+            // Callers normally check is_configured first and use the configured wording
             msg.channel_id
-                .say(http, "MySQL database is not configured.")
+                .say(
+                    http,
+                    FeatureUnavailableMessages::default()
+                        .message(FeatureUnavailable::QuoteDatabase),
+                )
                 .await?;
             return Ok(());
         }
@@ -236,11 +246,17 @@ impl DatabaseManager {
                     return Ok(());
                 }
 
-                // Get a random quote
-                let random_index = rand::rng().random_range(0..total_entries);
+                // Pick distinct random quotes, in the order they appear in the database
+                let mut indices = rand::seq::index::sample(
+                    &mut rand::rng(),
+                    total_entries as usize,
+                    count.clamp(1, total_entries as usize),
+                )
+                .into_vec();
+                indices.sort_unstable();
                 info!(
-                    "Selected random index {} of {} for quotes",
-                    random_index, total_entries
+                    "Selected random indices {:?} of {} for quotes",
+                    indices, total_entries
                 );
 
                 let select_query = "SELECT quote, show_title, masterlist_episodes.show_ep, title \
@@ -251,41 +267,46 @@ impl DatabaseManager {
                                    AND quote LIKE ? AND show_title LIKE ? \
                                    LIMIT ?, 1";
 
-                let quote_result = conn.exec_first::<(String, String, String, String), _, _>(
-                    select_query,
-                    (where_clause, show_clause, random_index),
-                );
+                let mut quotes = Vec::new();
+                for random_index in indices {
+                    let quote_result = conn.exec_first::<(String, String, String, String), _, _>(
+                        select_query,
+                        (where_clause.clone(), show_clause.clone(), random_index),
+                    );
 
-                // Format and send the quote
-                match quote_result {
-                    Ok(Some((quote_text, show_title, episode_num, episode_title))) => {
-                        // Clean up HTML entities
-                        let clean_quote = html_escape::decode_html_entities(&quote_text);
+                    match quote_result {
+                        Ok(Some((quote_text, show_title, episode_num, episode_title))) => {
+                            // Clean up HTML entities
+                            let clean_quote = html_escape::decode_html_entities(&quote_text);
 
-                        let quote_num = random_index + 1;
-                        msg.channel_id
-                            .say(
-                                http,
-                                format!(
-                                    "(Quote {quote_num} of {total_entries}) {clean_quote} -- {show_title} {episode_num}: {episode_title}"
-                                ),
-                            )
-                            .await?;
-                    }
-                    Ok(None) => {
-                        error!(
-                            "Query returned no results despite count being {}",
-                            total_entries
-                        );
-                        msg.channel_id.say(http, "No quotes found.").await?;
-                    }
-                    Err(e) => {
-                        error!("Failed to query quote: {:?}", e);
-                        msg.channel_id
-                            .say(http, "Failed to retrieve a quote from the database.")
-                            .await?;
+                            let quote_num = random_index + 1;
+                            quotes.push(format!(
+                                "(Quote {quote_num} of {total_entries}) {clean_quote} -- {show_title} {episode_num}: {episode_title}"
+                            ));
+                        }
+                        Ok(None) => {
+                            error!(
+                                "Query returned no results for index {} despite count being {}",
+                                random_index, total_entries
+                            );
+                        }
+                        Err(e) => {
+                            error!("Failed to query quote: {:?}", e);
+                            msg.channel_id
+                                .say(http, "Failed to retrieve a quote from the database.")
+                                .await?;
+                            return Ok(());
+                        }
                     }
                 }
+
+                // Format and send the quotes
+                if quotes.is_empty() {
+                    msg.channel_id.say(http, "No quotes found.").await?;
+                }
+                for message in pack_lines(&quotes, DISCORD_MESSAGE_LIMIT) {
+                    msg.channel_id.say(http, message).await?;
+                }
             }
             "slogan" => {
                 // For slogans, we use the simple query as before
@@ -1,142 +1,533 @@
+use crate::message_history::StoredMessage;
+use rusqlite::types::Value;
+use rusqlite::OptionalExtension;
 use serenity::model::channel::Message;
-use serenity::model::id::{ChannelId, GuildId, MessageId, UserId};
-use std::collections::HashSet;
+use serenity::model::id::{ChannelId, MessageId};
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::sync::Mutex;
 use tokio_rusqlite::Connection as SqliteConnection;
-use tracing::{error, info};
+use tracing::{error, info, warn};
 // Removed unused imports
 
-// Initialize the SQLite database with enhanced schema
-pub async fn initialize_database(
-    path: &str,
-) -> Result<Arc<Mutex<SqliteConnection>>, Box<dyn std::error::Error>> {
-    // Connect to the database
-    let conn = SqliteConnection::open(path).await?;
+// Whether context fetches collapse repeated messages; set once from config at startup
+static COLLAPSE_CONTEXT_DUPLICATES: AtomicBool = AtomicBool::new(true);
 
-    // First check if the table exists at all
-    let table_exists = conn
-        .call(|conn| {
-            let result: i64 = conn.query_row(
-                "SELECT count(*) FROM sqlite_master WHERE type='table' AND name='messages'",
-                [],
-                |row| row.get(0),
-            )?;
-            Ok::<_, rusqlite::Error>(result > 0)
-        })
-        .await?;
+/// Turn collapsing of consecutive duplicate messages in Gemini context on or off
+pub fn set_collapse_context_duplicates(enabled: bool) {
+    COLLAPSE_CONTEXT_DUPLICATES.store(enabled, Ordering::Relaxed);
+}
 
-    if !table_exists {
-        // Table doesn't exist, create it with the full schema
-        conn.call(|conn| {
-            conn.execute(
-                "CREATE TABLE messages (
-                    id INTEGER PRIMARY KEY,
-                    message_id TEXT NOT NULL,
-                    channel_id TEXT NOT NULL,
-                    guild_id TEXT,
-                    author_id TEXT NOT NULL,
-                    author TEXT NOT NULL,
-                    display_name TEXT,
-                    content TEXT NOT NULL,
-                    timestamp INTEGER NOT NULL,
-                    referenced_message_id TEXT
-                )",
-                [],
-            )?;
-            Ok::<_, rusqlite::Error>(())
-        })
-        .await?;
-    } else {
-        // Table exists, check if migration is needed
-        let needs_migration = conn
-            .call(|conn| {
-                let mut stmt = conn.prepare("PRAGMA table_info(messages)")?;
-                let columns = stmt.query_map([], |row| {
-                    let name: String = row.get(1)?;
-                    Ok(name)
-                })?;
+// Messages with at least this many lines are treated as pasted code/logs in context (0 = off)
+static CONTEXT_PASTE_LINE_THRESHOLD: AtomicUsize = AtomicUsize::new(DEFAULT_PASTE_LINE_THRESHOLD);
+// Whether pasted floods are dropped from context entirely instead of summarized
+static EXCLUDE_CONTEXT_PASTES: AtomicBool = AtomicBool::new(false);
 
-                let mut has_message_id = false;
-                let mut has_channel_id = false;
-                let mut has_author_id = false;
-
-                for column_name in columns.flatten() {
-                    if column_name == "message_id" {
-                        has_message_id = true;
-                    } else if column_name == "channel_id" {
-                        has_channel_id = true;
-                    } else if column_name == "author_id" {
-                        has_author_id = true;
-                    }
-                }
+pub const DEFAULT_PASTE_LINE_THRESHOLD: usize = 40;
 
-                Ok::<_, rusqlite::Error>(!has_message_id || !has_channel_id || !has_author_id)
-            })
-            .await?;
+/// Configure how large code/log pastes are handled in Gemini context
+pub fn set_context_paste_handling(line_threshold: usize, exclude: bool) {
+    CONTEXT_PASTE_LINE_THRESHOLD.store(line_threshold, Ordering::Relaxed);
+    EXCLUDE_CONTEXT_PASTES.store(exclude, Ordering::Relaxed);
+}
 
-        if needs_migration {
-            info!("Migrating messages database to enhanced schema...");
+// Lines that look like code or log output rather than chat
+fn is_code_like_line(line: &str) -> bool {
+    let trimmed = line.trim();
+    line.starts_with("    ")
+        || line.starts_with('\t')
+        || trimmed.starts_with("at ")
+        || trimmed.starts_with("```")
+        || trimmed.ends_with(';')
+        || trimmed.ends_with('{')
+        || trimmed == "}"
+        || [
+            "ERROR",
+            "WARN",
+            "INFO",
+            "DEBUG",
+            "TRACE",
+            "Traceback",
+            "Exception",
+        ]
+        .iter()
+        .any(|marker| trimmed.contains(marker))
+}
 
-            // Create a backup of the old table
-            conn.call(|conn| {
-                conn.execute("ALTER TABLE messages RENAME TO messages_backup", [])?;
-                Ok::<_, rusqlite::Error>(())
-            })
-            .await?;
+/// If `content` looks like a mass paste of code or logs, returns its line count.
+/// Anything at `line_threshold` lines counts; a fenced or mostly code-like message counts
+/// from half that.
+pub fn pasted_flood_lines(content: &str, line_threshold: usize) -> Option<usize> {
+    if line_threshold == 0 {
+        return None;
+    }
 
-            // Create the new table with the enhanced schema
-            conn.call(|conn| {
-                conn.execute(
-                    "CREATE TABLE messages (
-                        id INTEGER PRIMARY KEY,
-                        message_id TEXT NOT NULL,
-                        channel_id TEXT NOT NULL,
-                        guild_id TEXT,
-                        author_id TEXT NOT NULL,
-                        author TEXT NOT NULL,
-                        display_name TEXT,
-                        content TEXT NOT NULL,
-                        timestamp INTEGER NOT NULL,
-                        referenced_message_id TEXT
-                    )",
-                    [],
-                )?;
-                Ok::<_, rusqlite::Error>(())
-            })
-            .await?;
+    let lines: Vec<&str> = content.lines().collect();
+    if lines.len() >= line_threshold {
+        return Some(lines.len());
+    }
 
-            // Migrate data from backup with default values for new columns
-            conn.call(|conn| {
-                conn.execute(
-                    "INSERT INTO messages (id, author, display_name, content, timestamp, message_id, channel_id, author_id)
-                     SELECT id, author, display_name, content, timestamp, '0', '0', '0' FROM messages_backup",
-                    [],
-                )?;
-                Ok::<_, rusqlite::Error>(())
-            }).await?;
+    let half = line_threshold.div_ceil(2).max(2);
+    if lines.len() >= half {
+        let code_like = lines.iter().filter(|line| is_code_like_line(line)).count();
+        if content.contains("```") || code_like * 2 >= lines.len() {
+            return Some(lines.len());
         }
     }
 
-    // Create indexes for faster queries
-    let indexes = vec![
-        ("idx_message_timestamp", "messages (timestamp)"),
-        ("idx_message_author_id", "messages (author, id)"),
-    ];
+    None
+}
 
-    for (name, sql) in indexes {
-        let sql = format!("CREATE INDEX IF NOT EXISTS {name} ON {sql}");
-        conn.call(move |conn| {
-            conn.execute(&sql, []).map(|_| ())?;
-            Ok::<_, rusqlite::Error>(())
+fn paste_placeholder(lines: usize) -> String {
+    format!("[pasted {lines} lines of code/logs]")
+}
+
+/// Replace code/log floods in context with a short placeholder, or drop them when `exclude`
+/// is set. Replied-to pastes in the reply context are summarized the same way. Only the
+/// prompt context changes; the stored message keeps its full text.
+pub fn collapse_pasted_floods(
+    messages: Vec<ContextMessage>,
+    line_threshold: usize,
+    exclude: bool,
+) -> Vec<ContextMessage> {
+    messages
+        .into_iter()
+        .filter_map(|(author, display_name, pronouns, content, reply_context)| {
+            let content = match pasted_flood_lines(&content, line_threshold) {
+                Some(_) if exclude => return None,
+                Some(lines) => paste_placeholder(lines),
+                None => content,
+            };
+
+            let reply_context = reply_context.map(|reply| match reply.split_once(": ") {
+                Some((name, text)) => match pasted_flood_lines(text, line_threshold) {
+                    Some(lines) => format!("{name}: {}", paste_placeholder(lines)),
+                    None => reply,
+                },
+                None => reply,
+            });
+
+            Some((author, display_name, pronouns, content, reply_context))
         })
+        .collect()
+}
+
+/// A context message: (author, display_name, pronouns, content, reply_context)
+pub type ContextMessage = (String, String, Option<String>, String, Option<String>);
+
+/// Collapse runs of the same author saying the same thing (bridge echoes, stutters) into a
+/// single message. Only adjacent repeats are merged. This only shapes prompt context; stored
+/// duplicates are handled by `clean_up_duplicates`.
+pub fn collapse_consecutive_duplicates(messages: Vec<ContextMessage>) -> Vec<ContextMessage> {
+    let mut collapsed: Vec<ContextMessage> = Vec::with_capacity(messages.len());
+    for message in messages {
+        if let Some(previous) = collapsed.last() {
+            if previous.0 == message.0 && previous.3.trim() == message.3.trim() {
+                continue;
+            }
+        }
+        collapsed.push(message);
+    }
+    collapsed
+}
+
+/// Cached row count for the messages table, so `!info` doesn't scan the table
+/// every time. The count is adjusted on insert/trim and recounted after the TTL.
+pub struct MessageCountCache {
+    ttl: Duration,
+    cached: std::sync::Mutex<Option<(i64, Instant)>>,
+}
+
+impl MessageCountCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            cached: std::sync::Mutex::new(None),
+        }
+    }
+
+    // Get the message count, running `count` (e.g. [`count_messages`] or the message store's
+    // count) only if the cache is stale or a fresh count is requested
+    pub async fn get<E>(
+        &self,
+        count: impl std::future::Future<Output = Result<i64, E>>,
+        force_refresh: bool,
+    ) -> Result<i64, E> {
+        if !force_refresh {
+            if let Some((count, counted_at)) = *self.cached.lock().unwrap() {
+                if counted_at.elapsed() < self.ttl {
+                    return Ok(count);
+                }
+            }
+        }
+
+        let count = count.await?;
+        *self.cached.lock().unwrap() = Some((count, Instant::now()));
+        Ok(count)
+    }
+
+    // Account for a newly inserted message without recounting
+    pub fn record_insert(&self) {
+        if let Some((count, _)) = self.cached.lock().unwrap().as_mut() {
+            *count += 1;
+        }
+    }
+
+    // Account for messages removed by a trim without recounting
+    pub fn record_trim(&self, deleted: usize) {
+        if let Some((count, _)) = self.cached.lock().unwrap().as_mut() {
+            *count = count.saturating_sub(deleted as i64).max(0);
+        }
+    }
+}
+
+// How many messages are stored
+pub async fn count_messages(
+    conn: Arc<Mutex<SqliteConnection>>,
+) -> Result<i64, Box<dyn std::error::Error>> {
+    let count = conn
+        .lock()
+        .await
+        .call(|conn| conn.query_row("SELECT COUNT(*) FROM messages", [], |row| row.get(0)))
         .await?;
+    Ok(count)
+}
+
+// Schema changes in the order they were made. A database records how many of these it
+// has had applied, and `open_database` runs the rest in order, each in its own transaction.
+// Append new migrations to the end; never reorder or edit one that has shipped. Databases
+// from before versioning start at 0, so every step has to cope with what it may find.
+type Migration = fn(&rusqlite::Transaction) -> rusqlite::Result<()>;
+const MIGRATIONS: &[(&str, Migration)] = &[
+    ("create messages table", |tx| {
+        tx.execute_batch(
+            "CREATE TABLE IF NOT EXISTS messages (
+                id INTEGER PRIMARY KEY,
+                message_id TEXT NOT NULL,
+                channel_id TEXT NOT NULL,
+                guild_id TEXT,
+                author_id TEXT NOT NULL,
+                author TEXT NOT NULL,
+                display_name TEXT,
+                content TEXT NOT NULL,
+                timestamp INTEGER NOT NULL,
+                referenced_message_id TEXT
+            )",
+        )
+    }),
+    ("add display_name to messages", |tx| {
+        if !table_columns(tx, "messages")?.contains("display_name") {
+            tx.execute_batch("ALTER TABLE messages ADD COLUMN display_name TEXT")?;
+        }
+        Ok(())
+    }),
+    ("add message, channel and author ids to messages", |tx| {
+        let columns = table_columns(tx, "messages")?;
+        if ["message_id", "channel_id", "author_id"]
+            .iter()
+            .all(|column| columns.contains(*column))
+        {
+            return Ok(());
+        }
+
+        // Rebuild the table, keeping the old one as messages_backup
+        tx.execute_batch(
+            "ALTER TABLE messages RENAME TO messages_backup;
+             CREATE TABLE messages (
+                id INTEGER PRIMARY KEY,
+                message_id TEXT NOT NULL,
+                channel_id TEXT NOT NULL,
+                guild_id TEXT,
+                author_id TEXT NOT NULL,
+                author TEXT NOT NULL,
+                display_name TEXT,
+                content TEXT NOT NULL,
+                timestamp INTEGER NOT NULL,
+                referenced_message_id TEXT
+             );
+             INSERT INTO messages (id, author, display_name, content, timestamp, message_id, channel_id, author_id)
+             SELECT id, author, display_name, content, timestamp, '0', '0', '0' FROM messages_backup;",
+        )
+    }),
+    ("index messages", |tx| {
+        tx.execute_batch(
+            "CREATE INDEX IF NOT EXISTS idx_message_timestamp ON messages (timestamp);
+             CREATE INDEX IF NOT EXISTS idx_message_author_id ON messages (author, id);",
+        )
+    }),
+    // Set up by ensure_full_text_search at every startup instead, so a database opened
+    // without FTS5 gets its search table once SQLite has it
+    ("full-text search over messages", |_| Ok(())),
+    // Birthdays registered with !birthday set
+    ("create birthdays table", |tx| {
+        tx.execute_batch(
+            "CREATE TABLE IF NOT EXISTS birthdays (
+                user_id TEXT PRIMARY KEY,
+                guild_id TEXT NOT NULL,
+                display_name TEXT NOT NULL,
+                month INTEGER NOT NULL,
+                day INTEGER NOT NULL,
+                last_announced_year INTEGER NOT NULL DEFAULT 0
+            )",
+        )
+    }),
+    // Reminders set with !remindme, kept until they've been sent
+    ("create reminders table", |tx| {
+        tx.execute_batch(
+            "CREATE TABLE IF NOT EXISTS reminders (
+                id INTEGER PRIMARY KEY,
+                user_id TEXT NOT NULL,
+                channel_id TEXT NOT NULL,
+                remind_at INTEGER NOT NULL,
+                text TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_reminders_remind_at ON reminders (remind_at);",
+        )
+    }),
+    // Messages that quotes and reminders point at, which trimming leaves alone
+    ("create protected_messages table", |tx| {
+        tx.execute_batch(
+            "CREATE TABLE IF NOT EXISTS protected_messages (
+                message_id TEXT PRIMARY KEY,
+                reason TEXT NOT NULL,
+                protected_at INTEGER NOT NULL
+            )",
+        )
+    }),
+    // Last message seen in each channel, saved on shutdown for missed-message recovery
+    ("create last_seen table", |tx| {
+        tx.execute_batch(
+            "CREATE TABLE IF NOT EXISTS last_seen (
+                channel_id TEXT PRIMARY KEY,
+                message_id TEXT NOT NULL,
+                timestamp INTEGER NOT NULL
+            )",
+        )
+    }),
+    // Pronouns registered with !pronouns set
+    ("create user_pronouns table", |tx| {
+        tx.execute_batch(
+            "CREATE TABLE IF NOT EXISTS user_pronouns (
+                user_id TEXT PRIMARY KEY,
+                pronouns TEXT NOT NULL
+            )",
+        )
+    }),
+    // Interjection decisions, logged when INTERJECTION_METRICS is on
+    ("create interjection_metrics table", |tx| {
+        tx.execute_batch(
+            "CREATE TABLE IF NOT EXISTS interjection_metrics (
+                id INTEGER PRIMARY KEY,
+                timestamp INTEGER NOT NULL,
+                kind TEXT NOT NULL,
+                channel_id TEXT NOT NULL,
+                base_probability REAL NOT NULL,
+                silence_multiplier REAL NOT NULL,
+                adjusted_probability REAL NOT NULL,
+                outcome TEXT NOT NULL,
+                reason TEXT
+            );
+            CREATE INDEX IF NOT EXISTS idx_interjection_metrics_timestamp
+                ON interjection_metrics (timestamp);",
+        )
+    }),
+    // Daily AI token totals, so !info's figure for today survives a restart
+    ("create token_usage table", |tx| {
+        tx.execute_batch(
+            "CREATE TABLE IF NOT EXISTS token_usage (
+                day TEXT PRIMARY KEY,
+                prompt_tokens INTEGER NOT NULL DEFAULT 0,
+                candidates_tokens INTEGER NOT NULL DEFAULT 0,
+                total_tokens INTEGER NOT NULL DEFAULT 0
+            )",
+        )
+    }),
+    // Users who asked with !forgetme (or an owner's !forget) not to have messages stored
+    ("create no_store_users table", |tx| {
+        tx.execute_batch(
+            "CREATE TABLE IF NOT EXISTS no_store_users (
+                user_id TEXT PRIMARY KEY,
+                forgotten_at INTEGER NOT NULL
+            )",
+        )
+    }),
+    // Attachments and links as StoredMedia JSON
+    ("add media to messages", |tx| {
+        if !table_columns(tx, "messages")?.contains("media") {
+            tx.execute_batch("ALTER TABLE messages ADD COLUMN media TEXT")?;
+        }
+        Ok(())
+    }),
+];
+
+// Full-text search over messages, kept in sync with the messages table by triggers. Checked
+// at every startup rather than as a migration: SQLite builds without FTS5 still work
+// (!search falls back to LIKE matching), and pick it up when opened by one that has it.
+fn ensure_full_text_search(conn: &rusqlite::Connection) -> rusqlite::Result<()> {
+    let existed: bool = conn.query_row(
+        "SELECT count(*) > 0 FROM sqlite_master WHERE type='table' AND name='messages_fts'",
+        [],
+        |row| row.get(0),
+    )?;
+    let created = conn.execute_batch(
+            "CREATE VIRTUAL TABLE IF NOT EXISTS messages_fts
+                 USING fts5(content, content='messages', content_rowid='id');
+             CREATE TRIGGER IF NOT EXISTS messages_fts_insert AFTER INSERT ON messages BEGIN
+                 INSERT INTO messages_fts(rowid, content) VALUES (new.id, new.content);
+             END;
+             CREATE TRIGGER IF NOT EXISTS messages_fts_delete AFTER DELETE ON messages BEGIN
+                 INSERT INTO messages_fts(messages_fts, rowid, content) VALUES ('delete', old.id, old.content);
+             END;
+             CREATE TRIGGER IF NOT EXISTS messages_fts_update AFTER UPDATE OF content ON messages BEGIN
+                 INSERT INTO messages_fts(messages_fts, rowid, content) VALUES ('delete', old.id, old.content);
+                 INSERT INTO messages_fts(rowid, content) VALUES (new.id, new.content);
+             END;",
+        );
+    if let Err(e) = created {
+        warn!(
+            "Full-text search isn't available ({}); !search will use slower LIKE matching",
+            e
+        );
+        return Ok(());
     }
 
+    // Index any history saved before the search table existed
+    if !existed {
+        conn.execute(
+            "INSERT INTO messages_fts(messages_fts) VALUES ('rebuild')",
+            [],
+        )?;
+        info!("Built full-text search index for message history");
+    }
+    Ok(())
+}
+
+// Column names of a table, for migrations that only apply to older layouts
+fn table_columns(conn: &rusqlite::Connection, table: &str) -> rusqlite::Result<HashSet<String>> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({table})"))?;
+    let columns = stmt.query_map([], |row| row.get::<_, String>(1))?;
+    columns.collect()
+}
+
+// How many migrations have been applied to a database (0 for one from before versioning)
+fn schema_version(conn: &rusqlite::Connection) -> rusqlite::Result<i64> {
+    conn.execute_batch("CREATE TABLE IF NOT EXISTS schema_version (version INTEGER NOT NULL)")?;
+    let version = conn
+        .query_row("SELECT max(version) FROM schema_version", [], |row| {
+            row.get::<_, Option<i64>>(0)
+        })?
+        .unwrap_or(0);
+    Ok(version)
+}
+
+// Apply any migrations the database hasn't had yet
+fn migrate(conn: &mut rusqlite::Connection) -> rusqlite::Result<()> {
+    let mut version = schema_version(conn)?;
+    for (name, apply) in MIGRATIONS.iter().skip(version.max(0) as usize) {
+        let tx = conn.transaction()?;
+        apply(&tx)?;
+        version += 1;
+        tx.execute("DELETE FROM schema_version", [])?;
+        tx.execute(
+            "INSERT INTO schema_version (version) VALUES (?1)",
+            [version],
+        )?;
+        tx.commit()?;
+        info!("Applied database migration {}: {}", version, name);
+    }
+
+    let tx = conn.transaction()?;
+    ensure_full_text_search(&tx)?;
+    tx.commit()
+}
+
+// Initialize the SQLite database with enhanced schema
+pub async fn initialize_database(
+    path: &str,
+) -> Result<Arc<Mutex<SqliteConnection>>, Box<dyn std::error::Error>> {
+    let conn = open_database(path)
+        .await
+        .map_err(|e| -> Box<dyn std::error::Error> { e })?;
+
     // Return the connection wrapped in an Arc<Mutex>
     Ok(Arc::new(Mutex::new(conn)))
 }
 
+// Open the database and bring its schema up to date (also used to reconnect)
+pub async fn open_database(
+    path: &str,
+) -> Result<SqliteConnection, Box<dyn std::error::Error + Send + Sync>> {
+    // Connect to the database
+    let conn = SqliteConnection::open(path).await?;
+
+    conn.call(migrate).await?;
+
+    Ok(conn)
+}
+
+// Check the connection with a trivial query. If it fails, reopen it with `reopen`
+// (normally `open_database`) and swap the fresh connection in behind the shared
+// mutex so every holder of the Arc recovers. Returns Ok(true) if a reconnect happened.
+pub async fn ensure_connection_healthy<F, Fut>(
+    conn: &Arc<Mutex<SqliteConnection>>,
+    reopen: F,
+    max_attempts: u32,
+    retry_delay: Duration,
+) -> Result<bool, Box<dyn std::error::Error + Send + Sync>>
+where
+    F: Fn() -> Fut,
+    Fut: std::future::Future<
+        Output = Result<SqliteConnection, Box<dyn std::error::Error + Send + Sync>>,
+    >,
+{
+    let health = conn
+        .lock()
+        .await
+        .call(|conn| {
+            conn.query_row("SELECT 1", [], |_| Ok(()))?;
+            Ok::<_, rusqlite::Error>(())
+        })
+        .await;
+
+    let failure = match health {
+        Ok(()) => return Ok(false),
+        Err(e) => e.to_string(),
+    };
+
+    warn!("Message database health check failed: {}", failure);
+
+    let mut last_error = failure;
+    for attempt in 1..=max_attempts {
+        info!(
+            "Attempting to reopen message database (attempt {}/{})",
+            attempt, max_attempts
+        );
+
+        match reopen().await {
+            Ok(fresh) => {
+                *conn.lock().await = fresh;
+                info!("✅ Reconnected to message database");
+                return Ok(true);
+            }
+            Err(e) => {
+                last_error = e.to_string();
+                error!("Failed to reopen message database: {}", last_error);
+            }
+        }
+
+        if attempt < max_attempts {
+            tokio::time::sleep(retry_delay).await;
+        }
+    }
+
+    Err(
+        format!("Could not reopen message database after {max_attempts} attempts: {last_error}")
+            .into(),
+    )
+}
+
 // Save a message to the SQLite database with enhanced fields
 pub async fn save_message(
     conn: Arc<Mutex<SqliteConnection>>,
@@ -145,7 +536,7 @@ pub async fn save_message(
     content: &str,
     message: Option<&Message>, // Optional Message object for enhanced fields
     _operation_id: Option<String>, // Optional operation ID for tracking (no longer used)
-) -> Result<(), Box<dyn std::error::Error>> {
+) -> Result<bool, Box<dyn std::error::Error>> {
     let timestamp = std::time::SystemTime::now()
         .duration_since(std::time::UNIX_EPOCH)?
         .as_secs();
@@ -156,6 +547,8 @@ pub async fn save_message(
     let content = content.to_string();
 
     let conn_guard = conn.lock().await;
+    // Whether this call added a new row (as opposed to updating an existing one)
+    let inserted;
 
     // If we have a Message object, save all fields
     if let Some(msg) = message {
@@ -169,29 +562,45 @@ pub async fn save_message(
             .as_ref()
             .map(|m| m.id.to_string())
             .unwrap_or_default();
+        let media = crate::media_utils::StoredMedia::from_message(msg).to_json();
 
-        // Check if this message already exists in the database
-        let exists = conn_guard
+        // Check if this message already exists in the database, and whether its author
+        // opted out of having messages stored
+        let (exists, no_store) = conn_guard
             .call({
                 let message_id = message_id.clone();
+                let author_id = author_id.clone();
                 move |conn| {
                     let result: Result<i64, _> = conn.query_row(
                         "SELECT 1 FROM messages WHERE message_id = ?",
                         [&message_id],
                         |_| Ok(1),
                     );
-                    Ok::<_, rusqlite::Error>(result.is_ok())
+                    let no_store = conn
+                        .query_row(
+                            "SELECT 1 FROM no_store_users WHERE user_id = ?",
+                            [&author_id],
+                            |_| Ok(()),
+                        )
+                        .optional()?
+                        .is_some();
+                    Ok::<_, rusqlite::Error>((result.is_ok(), no_store))
                 }
             })
             .await?;
 
+        if no_store {
+            return Ok(false);
+        }
+
+        inserted = !exists;
         if exists {
             // Message already exists, update it instead of inserting a new record
             conn_guard
                 .call(move |conn| {
                     conn.execute(
-                        "UPDATE messages SET content = ? WHERE message_id = ?",
-                        [&content, &message_id],
+                        "UPDATE messages SET content = ?1, media = ?2 WHERE message_id = ?3",
+                        rusqlite::params![content, media, message_id],
                     )?;
                     Ok::<_, rusqlite::Error>(())
                 })
@@ -201,18 +610,19 @@ pub async fn save_message(
             conn_guard.call(move |conn| {
                 conn.execute(
                     "INSERT INTO messages (
-                        message_id, channel_id, guild_id, author_id, author, display_name, content, timestamp, referenced_message_id
-                    ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
-                    [
-                        &message_id,
-                        &channel_id,
-                        &guild_id,
-                        &author_id,
-                        &author,
-                        &clean_display_name,
-                        &content,
-                        &timestamp.to_string(),
-                        &referenced_message_id,
+                        message_id, channel_id, guild_id, author_id, author, display_name, content, timestamp, referenced_message_id, media
+                    ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+                    rusqlite::params![
+                        message_id,
+                        channel_id,
+                        guild_id,
+                        author_id,
+                        author,
+                        clean_display_name,
+                        content,
+                        timestamp.to_string(),
+                        referenced_message_id,
+                        media,
                     ],
                 )?;
                 Ok::<_, rusqlite::Error>(())
@@ -220,6 +630,7 @@ pub async fn save_message(
         }
     } else {
         // Fallback to basic fields if no Message object is provided
+        inserted = true;
         conn_guard
             .call(move |conn| {
                 conn.execute(
@@ -241,45 +652,248 @@ pub async fn save_message(
             .await?;
     }
 
-    Ok(())
+    Ok(inserted)
 }
 
-// Trim the database to keep only the most recent messages
-pub async fn trim_database(
+/// Delete every stored message by a user and stop storing their new ones, for !forgetme
+/// and !forget. Returns how many messages were deleted.
+pub async fn forget_user(
     conn: Arc<Mutex<SqliteConnection>>,
-    limit: usize,
+    user_id: String,
 ) -> Result<usize, Box<dyn std::error::Error>> {
+    let forgotten_at = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)?
+        .as_secs() as i64;
+
     let conn_guard = conn.lock().await;
+    let deleted = conn_guard
+        .call(move |conn| {
+            let tx = conn.transaction()?;
+            tx.execute(
+                "INSERT OR IGNORE INTO no_store_users (user_id, forgotten_at) VALUES (?1, ?2)",
+                rusqlite::params![user_id, forgotten_at],
+            )?;
+            let deleted = tx.execute("DELETE FROM messages WHERE author_id = ?1", [&user_id])?;
+            tx.commit()?;
+            Ok::<_, rusqlite::Error>(deleted)
+        })
+        .await?;
+
+    Ok(deleted)
+}
 
-    // First, count how many messages we have
-    let count = conn_guard
+// Delete every stored message posted under a username or display name (ignoring case), for
+// people the bot only knows by name, such as users relayed through a gateway bot. Returns
+// how many messages were deleted.
+pub async fn delete_messages_by_author(
+    conn: Arc<Mutex<SqliteConnection>>,
+    name: String,
+) -> Result<usize, Box<dyn std::error::Error>> {
+    let conn_guard = conn.lock().await;
+    let deleted = conn_guard
         .call(move |conn| {
-            let mut stmt = conn.prepare("SELECT COUNT(*) FROM messages")?;
-            let count: i64 = stmt.query_row([], |row| row.get(0))?;
-            Ok::<_, rusqlite::Error>(count)
+            conn.execute(
+                "DELETE FROM messages WHERE author = ?1 COLLATE NOCASE OR display_name = ?1 COLLATE NOCASE",
+                [&name],
+            )
         })
         .await?;
 
-    // If we have more messages than the limit, delete the oldest ones
-    if count as usize > limit {
-        let to_delete = count as usize - limit;
+    Ok(deleted)
+}
 
-        conn_guard
-            .call(move |conn| {
-                conn.execute(
-                    "DELETE FROM messages WHERE id IN (
-                    SELECT id FROM messages ORDER BY timestamp ASC LIMIT ?
-                )",
-                    [to_delete],
-                )?;
-                Ok::<_, rusqlite::Error>(())
+// The ID of the user who most recently posted under a username or display name. Messages
+// from `excluded_ids` (the bot and gateway bots, whose relayed messages carry other
+// people's names) don't count.
+pub async fn find_user_id_by_name(
+    conn: Arc<Mutex<SqliteConnection>>,
+    name: String,
+    excluded_ids: Vec<String>,
+) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    let conn_guard = conn.lock().await;
+    let user_id = conn_guard
+        .call(move |conn| {
+            let placeholders = (0..excluded_ids.len())
+                .map(|i| format!("?{}", i + 2))
+                .collect::<Vec<_>>()
+                .join(", ");
+            let query = format!(
+                "SELECT author_id FROM messages
+                 WHERE author_id != '0' AND author_id NOT IN ({placeholders})
+                   AND (author = ?1 COLLATE NOCASE OR display_name = ?1 COLLATE NOCASE)
+                 ORDER BY timestamp DESC LIMIT 1"
+            );
+            let params = std::iter::once(name).chain(excluded_ids);
+            conn.query_row(&query, rusqlite::params_from_iter(params), |row| {
+                row.get::<_, String>(0)
             })
-            .await?;
+            .optional()
+        })
+        .await?;
+    Ok(user_id)
+}
 
-        return Ok(to_delete);
-    }
+/// A user's newest message for `!lastseen`: (author, display_name, content, timestamp)
+pub type LastMessage = (String, String, String, u64);
+
+// The newest non-empty message from a user ID, or from anyone whose username or display
+// name contains `name` (ignoring case) when `author_id` is None
+pub async fn last_message(
+    conn: Arc<Mutex<SqliteConnection>>,
+    author_id: Option<String>,
+    name: String,
+) -> Result<Option<LastMessage>, Box<dyn std::error::Error>> {
+    let pattern = like_pattern(&name.to_lowercase());
+    let conn_guard = conn.lock().await;
+    let message = conn_guard
+        .call(move |conn| {
+            conn.query_row(
+                "SELECT author, display_name, content, timestamp FROM messages
+                 WHERE (author_id = ?1 OR (?1 IS NULL AND
+                        (LOWER(author) LIKE ?2 ESCAPE '\\' OR LOWER(display_name) LIKE ?2 ESCAPE '\\')))
+                   AND content != ''
+                 ORDER BY timestamp DESC LIMIT 1",
+                rusqlite::params![author_id, pattern],
+                |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        row.get::<_, Option<String>>(1)?.unwrap_or_default(),
+                        row.get::<_, String>(2)?,
+                        row.get::<_, u64>(3)?,
+                    ))
+                },
+            )
+            .optional()
+        })
+        .await?;
+    Ok(message)
+}
 
-    Ok(0)
+// Every distinct username and display name non-empty messages were stored under
+pub async fn author_names(
+    conn: Arc<Mutex<SqliteConnection>>,
+) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let conn_guard = conn.lock().await;
+    let names = conn_guard
+        .call(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT author FROM messages WHERE content != ''
+                 UNION
+                 SELECT display_name FROM messages WHERE content != '' AND display_name IS NOT NULL",
+            )?;
+            let names = stmt
+                .query_map([], |row| row.get::<_, String>(0))?
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok::<_, rusqlite::Error>(
+                names
+                    .into_iter()
+                    .filter(|name| !name.trim().is_empty())
+                    .collect::<Vec<_>>(),
+            )
+        })
+        .await?;
+    Ok(names)
+}
+
+/// A stored message for a memory interjection: (content, author, display_name, timestamp)
+pub type Memory = (String, String, String, i64);
+
+// A random stored message worth reminiscing about, weighted toward more recent ones, from
+// one guild when `guild_id` is given. Commands, bare links, media and anything posted as
+// `exclude_name` (the bot) are skipped.
+pub async fn random_memory(
+    conn: Arc<Mutex<SqliteConnection>>,
+    exclude_name: String,
+    guild_id: Option<String>,
+) -> Result<Option<Memory>, Box<dyn std::error::Error>> {
+    let conn_guard = conn.lock().await;
+    let memory = conn_guard
+        .call(move |conn| {
+            // A random fraction of each timestamp, so newer messages tend to sort first
+            conn.query_row(
+                "SELECT content, author, display_name, timestamp FROM messages
+                 WHERE length(content) >= 20 AND length(content) <= 300
+                   AND content NOT LIKE '!%'
+                   AND content NOT LIKE 'http://%'
+                   AND content NOT LIKE 'https://%'
+                   AND content NOT LIKE '%[Image:%'
+                   AND content NOT LIKE '%[Video:%'
+                   AND author != ?1 AND display_name != ?1
+                   AND (?2 IS NULL OR guild_id = ?2)
+                 ORDER BY (ABS(RANDOM()) / 9223372036854775807.0) * timestamp DESC
+                 LIMIT 1",
+                rusqlite::params![exclude_name, guild_id],
+                |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        row.get::<_, String>(1)?,
+                        row.get::<_, String>(2)?,
+                        row.get::<_, i64>(3)?,
+                    ))
+                },
+            )
+            .optional()
+        })
+        .await?;
+    Ok(memory)
+}
+
+// Every username and display name a user's stored messages were posted under
+pub async fn known_names(
+    conn: Arc<Mutex<SqliteConnection>>,
+    user_id: String,
+) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let conn_guard = conn.lock().await;
+    let names = conn_guard
+        .call(move |conn| {
+            let mut stmt = conn.prepare(
+                "SELECT DISTINCT author, display_name FROM messages WHERE author_id = ?1",
+            )?;
+            let rows = stmt.query_map([&user_id], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, Option<String>>(1)?))
+            })?;
+            let mut names = Vec::new();
+            for row in rows {
+                let (author, display_name) = row?;
+                names.push(author);
+                names.extend(display_name.filter(|name| !name.is_empty()));
+            }
+            Ok::<_, rusqlite::Error>(names)
+        })
+        .await?;
+    Ok(names)
+}
+
+/// Message IDs in protected_messages, which trimming leaves alone
+pub async fn protected_message_ids(
+    conn: Arc<Mutex<SqliteConnection>>,
+) -> Result<Vec<String>, Box<dyn std::error::Error + Send + Sync>> {
+    let ids = conn
+        .lock()
+        .await
+        .call(|conn| {
+            let mut stmt = conn.prepare("SELECT message_id FROM protected_messages")?;
+            let rows = stmt.query_map([], |row| row.get::<_, String>(0))?;
+            rows.collect::<Result<Vec<_>, _>>()
+        })
+        .await?;
+    Ok(ids)
+}
+
+/// Pronouns registered with !pronouns set, keyed by user ID
+pub async fn registered_pronouns_by_user(
+    conn: Arc<Mutex<SqliteConnection>>,
+) -> Result<HashMap<String, String>, Box<dyn std::error::Error + Send + Sync>> {
+    let pronouns = conn
+        .lock()
+        .await
+        .call(|conn| {
+            let mut stmt = conn.prepare("SELECT user_id, pronouns FROM user_pronouns")?;
+            let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+            rows.collect::<Result<HashMap<_, _>, _>>()
+        })
+        .await?;
+    Ok(pronouns)
 }
 
 // Get recent messages from the database in chronological order
@@ -293,9 +907,15 @@ pub async fn get_recent_messages_with_reply_context(
     let conn_guard = conn.lock().await;
 
     // If channel_id is provided, filter by it
-    let raw_messages: Vec<(String, String, String, String, Option<String>)> = if let Some(channel) =
-        channel_id
-    {
+    #[allow(clippy::type_complexity)]
+    let raw_messages: Vec<(
+        String,
+        String,
+        String,
+        String,
+        Option<String>,
+        Option<String>,
+    )> = if let Some(channel) = channel_id {
         let channel_str = channel.to_string();
 
         // Get the most recent messages with their referenced message content
@@ -306,9 +926,11 @@ pub async fn get_recent_messages_with_reply_context(
                     let mut stmt = conn.prepare(
                         "SELECT m.message_id, m.channel_id, m.guild_id, m.author_id, m.author,
                                 m.display_name, m.content, m.timestamp, m.referenced_message_id,
-                                ref.author as ref_author, ref.display_name as ref_display_name, ref.content as ref_content
+                                ref.author as ref_author, ref.display_name as ref_display_name, ref.content as ref_content,
+                                up.pronouns, m.media
                          FROM messages m
                          LEFT JOIN messages ref ON m.referenced_message_id = ref.message_id
+                         LEFT JOIN user_pronouns up ON up.user_id = m.author_id
                          WHERE m.channel_id = ?
                          ORDER BY m.timestamp DESC LIMIT ?"
                     )?;
@@ -327,9 +949,14 @@ pub async fn get_recent_messages_with_reply_context(
                         Ok((
                             row.get::<_, String>(4)?, // author
                             row.get::<_, String>(5)?, // display_name
-                            row.get::<_, String>(6)?, // content
+                            // content, marking any attachments and links
+                            crate::media_utils::with_media_markers(
+                                &row.get::<_, String>(6)?,
+                                row.get::<_, Option<String>>(13)?.as_deref(),
+                            ),
                             row.get::<_, i64>(7)?.to_string(), // timestamp
                             reply_context, // reply context
+                            row.get::<_, Option<String>>(12)?, // registered pronouns
                         ))
                     })?;
 
@@ -346,9 +973,11 @@ pub async fn get_recent_messages_with_reply_context(
             let mut stmt = conn.prepare(
                 "SELECT m.message_id, m.channel_id, m.guild_id, m.author_id, m.author,
                         m.display_name, m.content, m.timestamp, m.referenced_message_id,
-                        ref.author as ref_author, ref.display_name as ref_display_name, ref.content as ref_content
+                        ref.author as ref_author, ref.display_name as ref_display_name, ref.content as ref_content,
+                        up.pronouns, m.media
                  FROM messages m
                  LEFT JOIN messages ref ON m.referenced_message_id = ref.message_id
+                 LEFT JOIN user_pronouns up ON up.user_id = m.author_id
                  ORDER BY m.timestamp DESC LIMIT ?"
             )?;
 
@@ -366,9 +995,14 @@ pub async fn get_recent_messages_with_reply_context(
                 Ok((
                     row.get::<_, String>(4)?, // author
                     row.get::<_, String>(5)?, // display_name
-                    row.get::<_, String>(6)?, // content
+                    // content, marking any attachments and links
+                    crate::media_utils::with_media_markers(
+                        &row.get::<_, String>(6)?,
+                        row.get::<_, Option<String>>(13)?.as_deref(),
+                    ),
                     row.get::<_, i64>(7)?.to_string(), // timestamp
                     reply_context, // reply context
+                    row.get::<_, Option<String>>(12)?, // registered pronouns
                 ))
             })?;
 
@@ -377,14 +1011,35 @@ pub async fn get_recent_messages_with_reply_context(
         }).await?
     };
 
-    // Convert to the expected format: (author, display_name, pronouns, content, reply_context)
-    #[allow(clippy::type_complexity)]
-    let messages: Vec<(String, String, Option<String>, String, Option<String>)> = raw_messages
+    let messages = raw_messages
+        .into_iter()
+        .map(
+            |(author, display_name, content, _timestamp, reply_context, registered_pronouns)| {
+                (
+                    author,
+                    display_name,
+                    registered_pronouns,
+                    content,
+                    reply_context,
+                )
+            },
+        )
+        .collect();
+
+    Ok(finish_context_messages(messages))
+}
+
+/// Tidy messages read for context, whichever store they came from: work out pronouns
+/// (the third field holds the registered ones on the way in), clean display names, and
+/// collapse pasted floods and repeated messages as configured.
+pub fn finish_context_messages(messages: Vec<ContextMessage>) -> Vec<ContextMessage> {
+    let messages: Vec<ContextMessage> = messages
         .into_iter()
         .map(
-            |(author, display_name, content, _timestamp, reply_context)| {
-                // Extract pronouns from display name if present
-                let pronouns = crate::utils::extract_pronouns(&display_name);
+            |(author, display_name, registered_pronouns, content, reply_context)| {
+                // Registered pronouns win; otherwise extract them from the display name
+                let pronouns =
+                    crate::pronouns::preferred_pronouns(registered_pronouns, &display_name);
                 let clean_display_name = crate::display_name::clean_display_name(&display_name);
 
                 (author, clean_display_name, pronouns, content, reply_context)
@@ -392,7 +1047,30 @@ pub async fn get_recent_messages_with_reply_context(
         )
         .collect();
 
-    Ok(messages)
+    let line_threshold = CONTEXT_PASTE_LINE_THRESHOLD.load(Ordering::Relaxed);
+    let messages = if line_threshold > 0 {
+        collapse_pasted_floods(
+            messages,
+            line_threshold,
+            EXCLUDE_CONTEXT_PASTES.load(Ordering::Relaxed),
+        )
+    } else {
+        messages
+    };
+
+    if COLLAPSE_CONTEXT_DUPLICATES.load(Ordering::Relaxed) {
+        let before = messages.len();
+        let messages = collapse_consecutive_duplicates(messages);
+        if messages.len() < before {
+            info!(
+                "Collapsed {} repeated messages out of context",
+                before - messages.len()
+            );
+        }
+        return messages;
+    }
+
+    messages
 }
 
 // Get recent messages from the database in chronological order with pronouns
@@ -665,119 +1343,563 @@ pub async fn get_recent_messages_with_pronouns(
     Ok(deduplicated_messages)
 }
 
-// Trim the message history to keep only the most recent messages
-pub async fn trim_message_history(
+/// Keep a message through trims, e.g. because a quote or reminder points at it.
+/// Protecting a message twice keeps the first reason.
+pub async fn protect_message(
+    conn: Arc<Mutex<SqliteConnection>>,
+    message_id: String,
+    reason: &'static str,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let now = chrono::Utc::now().timestamp();
+    conn.lock()
+        .await
+        .call(move |conn| {
+            conn.execute(
+                "INSERT OR IGNORE INTO protected_messages (message_id, reason, protected_at)
+                 VALUES (?1, ?2, ?3)",
+                rusqlite::params![message_id, reason, now],
+            )?;
+            Ok::<_, rusqlite::Error>(())
+        })
+        .await?;
+    Ok(())
+}
+
+// Trim the message history, keeping the most recent messages. Channels listed in
+// `channel_limits` (keyed by channel ID) are trimmed to their own limit each; every other
+// channel shares `default_limit` between them, as the one global cap. With
+// `keep_protected`, messages in protected_messages are never deleted and don't count
+// towards a limit.
+pub async fn trim_message_history(
     conn: Arc<tokio::sync::Mutex<SqliteConnection>>,
-    limit: usize,
+    default_limit: usize,
+    channel_limits: &HashMap<String, usize>,
+    keep_protected: bool,
 ) -> Result<usize, Box<dyn std::error::Error>> {
-    // This is just an alias for trim_database for backward compatibility
-    trim_database(conn, limit).await
+    let channel_limits = channel_limits.clone();
+    let unprotected = if keep_protected {
+        "message_id NOT IN (SELECT message_id FROM protected_messages)"
+    } else {
+        "1"
+    };
+
+    let deleted = conn
+        .lock()
+        .await
+        .call(move |conn| {
+            let channel_counts: Vec<(String, i64)> = {
+                let mut stmt = conn.prepare(&format!(
+                    "SELECT channel_id, COUNT(*) FROM messages WHERE {unprotected}
+                     GROUP BY channel_id"
+                ))?;
+                let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+                rows.collect::<Result<Vec<_>, _>>()?
+            };
+
+            let mut total_deleted = 0;
+            let mut shared_count = 0;
+            for (channel_id, count) in channel_counts {
+                let count = count as usize;
+                let Some(&limit) = channel_limits.get(&channel_id) else {
+                    shared_count += count;
+                    continue;
+                };
+                if count <= limit {
+                    continue;
+                }
+
+                let to_delete = count - limit;
+                total_deleted += conn.execute(
+                    &format!(
+                        "DELETE FROM messages WHERE id IN (
+                            SELECT id FROM messages WHERE channel_id = ?1 AND {unprotected}
+                            ORDER BY timestamp ASC, id ASC LIMIT ?2
+                        )"
+                    ),
+                    rusqlite::params![channel_id, to_delete as i64],
+                )?;
+            }
+
+            // Everything else is trimmed together, oldest first
+            if shared_count > default_limit {
+                let placeholders = (0..channel_limits.len())
+                    .map(|i| format!("?{}", i + 2))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                let params = std::iter::once(Value::Integer((shared_count - default_limit) as i64))
+                    .chain(channel_limits.into_keys().map(Value::Text));
+                total_deleted += conn.execute(
+                    &format!(
+                        "DELETE FROM messages WHERE id IN (
+                            SELECT id FROM messages
+                            WHERE channel_id NOT IN ({placeholders}) AND {unprotected}
+                            ORDER BY timestamp ASC, id ASC LIMIT ?1
+                        )"
+                    ),
+                    rusqlite::params_from_iter(params),
+                )?;
+            }
+
+            Ok::<_, rusqlite::Error>(total_deleted)
+        })
+        .await?;
+
+    Ok(deleted)
 }
 
-// Load message history from the database
-pub async fn load_message_history(
-    conn: Arc<tokio::sync::Mutex<SqliteConnection>>,
-    history: &mut std::collections::VecDeque<serenity::model::channel::Message>,
+/// A stored message that matched a history search
+#[derive(Debug, Clone, PartialEq)]
+pub struct HistorySearchHit {
+    pub message_id: String,
+    pub channel_id: String,
+    pub guild_id: String,
+    pub author: String,
+    pub display_name: String,
+    pub content: String,
+    pub timestamp: i64,
+}
+
+// Turn user input into an FTS5 query: every word must appear, with no query syntax allowed
+fn fts_match_query(term: &str) -> String {
+    term.split_whitespace()
+        .map(|word| format!("\"{}\"", word.replace('"', "\"\"")))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// Columns and scope shared by both kinds of history search. ?1 is the guild, ?2 the
+// channel, ?3 the limit and ?4 the offset; the search terms follow from ?5.
+const HISTORY_SEARCH_SELECT: &str =
+    "SELECT m.message_id, m.channel_id, m.guild_id, m.author, m.display_name, m.content, m.timestamp";
+const HISTORY_SEARCH_SCOPE: &str =
+    "((?1 != '' AND m.guild_id = ?1) OR (?1 = '' AND m.channel_id = ?2))
+                   AND m.content NOT LIKE '!%'
+                 ORDER BY m.timestamp DESC, m.id DESC
+                 LIMIT ?3 OFFSET ?4";
+
+fn fts_search_sql() -> String {
+    format!(
+        "{HISTORY_SEARCH_SELECT}
+                 FROM messages_fts
+                 JOIN messages m ON m.id = messages_fts.rowid
+                 WHERE messages_fts MATCH ?5
+                   AND {HISTORY_SEARCH_SCOPE}"
+    )
+}
+
+// The search without FTS5: one case-insensitive LIKE per word, all of which must match
+fn like_search_sql(words: usize) -> String {
+    let conditions: String = (0..words)
+        .map(|i| format!("m.content LIKE ?{} ESCAPE '\\' AND ", i + 5))
+        .collect();
+    format!(
+        "{HISTORY_SEARCH_SELECT}
+                 FROM messages m
+                 WHERE {conditions}{HISTORY_SEARCH_SCOPE}"
+    )
+}
+
+// A LIKE pattern matching `word` anywhere, with LIKE's own wildcards taken literally
+pub(crate) fn like_pattern(word: &str) -> String {
+    let escaped = word
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+    format!("%{escaped}%")
+}
+
+// Full-text search of stored messages, newest first, using LIKE when FTS5 isn't available. Searches the whole guild, or just
+// the channel when `guild_id` is empty (DMs). Bot commands are left out of the results.
+pub async fn search_message_history(
+    conn: Arc<Mutex<SqliteConnection>>,
+    term: &str,
+    guild_id: &str,
+    channel_id: &str,
     limit: usize,
-    channel_id: Option<&str>,
-) -> Result<(), Box<dyn std::error::Error>> {
+    offset: usize,
+) -> Result<Vec<HistorySearchHit>, Box<dyn std::error::Error>> {
+    let match_query = fts_match_query(term);
+    if match_query.is_empty() {
+        return Ok(Vec::new());
+    }
+    let like_patterns: Vec<String> = term.split_whitespace().map(like_pattern).collect();
+
+    let guild_id = guild_id.to_string();
+    let channel_id = channel_id.to_string();
     let conn_guard = conn.lock().await;
 
-    // Get messages from the database, filtered by channel_id if provided
-    let db_messages = if let Some(channel) = channel_id {
-        let channel = channel.to_string();
-        conn_guard.call(move |conn| {
-            let mut stmt = conn.prepare(
-                "SELECT message_id, channel_id, guild_id, author_id, author, content, timestamp, referenced_message_id
-                 FROM messages
-                 WHERE channel_id = ?
-                 ORDER BY timestamp DESC LIMIT ?"
+    let hits = conn_guard
+        .call(move |conn| {
+            let has_fts: bool = conn.query_row(
+                "SELECT count(*) > 0 FROM sqlite_master WHERE type='table' AND name='messages_fts'",
+                [],
+                |row| row.get(0),
             )?;
 
-            let rows = stmt.query_map([&channel, &limit.to_string()], |row| {
-                Ok((
-                    row.get::<_, String>(0)?, // message_id
-                    row.get::<_, String>(1)?, // channel_id
-                    row.get::<_, Option<String>>(2)?, // guild_id
-                    row.get::<_, String>(3)?, // author_id
-                    row.get::<_, String>(4)?, // author
-                    row.get::<_, String>(5)?, // content
-                    row.get::<_, i64>(6)?, // timestamp
-                    row.get::<_, Option<String>>(7)?, // referenced_message_id
-                ))
+            let mut params: Vec<rusqlite::types::Value> = vec![
+                guild_id.into(),
+                channel_id.into(),
+                (limit as i64).into(),
+                (offset as i64).into(),
+            ];
+            let sql = if has_fts {
+                params.push(match_query.into());
+                fts_search_sql()
+            } else {
+                let sql = like_search_sql(like_patterns.len());
+                params.extend(like_patterns.into_iter().map(Into::into));
+                sql
+            };
+
+            let mut stmt = conn.prepare(&sql)?;
+            let rows = stmt.query_map(rusqlite::params_from_iter(params), |row| {
+                Ok(HistorySearchHit {
+                    message_id: row.get(0)?,
+                    channel_id: row.get(1)?,
+                    guild_id: row.get::<_, Option<String>>(2)?.unwrap_or_default(),
+                    author: row.get(3)?,
+                    display_name: row.get::<_, Option<String>>(4)?.unwrap_or_default(),
+                    content: row.get(5)?,
+                    timestamp: row.get(6)?,
+                })
             })?;
 
-            let result: Vec<_> = rows.flatten().collect();
-
+            let result: Vec<_> = rows.collect::<Result<Vec<_>, _>>()?;
             Ok::<_, rusqlite::Error>(result)
-        }).await?
-    } else {
-        // If no channel_id is provided, get messages from all channels
-        conn_guard.call(move |conn| {
-            let mut stmt = conn.prepare(
-                "SELECT message_id, channel_id, guild_id, author_id, author, content, timestamp, referenced_message_id
-                 FROM messages ORDER BY timestamp DESC LIMIT ?"
-            )?;
+        })
+        .await?;
 
-            let rows = stmt.query_map([limit], |row| {
-                Ok((
-                    row.get::<_, String>(0)?, // message_id
-                    row.get::<_, String>(1)?, // channel_id
-                    row.get::<_, Option<String>>(2)?, // guild_id
-                    row.get::<_, String>(3)?, // author_id
-                    row.get::<_, String>(4)?, // author
-                    row.get::<_, String>(5)?, // content
-                    row.get::<_, i64>(6)?, // timestamp
-                    row.get::<_, Option<String>>(7)?, // referenced_message_id
-                ))
-            })?;
+    Ok(hits)
+}
 
-            let result: Vec<_> = rows.flatten().collect();
+// Default shortest message `!quote -dud` will pick, so "lol" and "ok" don't come up
+pub const DEFAULT_DUD_QUOTE_MIN_LENGTH: usize = 20;
 
-            Ok::<_, rusqlite::Error>(result)
-        }).await?
-    };
+// The `!quote` flags that pick messages by when they were posted
+pub const DATE_FLAGS: &[&str] = &["-year", "-after", "-before"];
 
-    // Try to convert database records to Message objects
-    for (
-        msg_id_str,
-        channel_id_str,
-        guild_id_opt,
-        author_id_str,
-        author_name,
-        content,
-        _timestamp,
-        _ref_msg_id,
-    ) in db_messages
-    {
-        // Parse IDs - use default values if parsing fails
-        let msg_id = msg_id_str.parse::<u64>().unwrap_or(0);
-        let channel_id = channel_id_str.parse::<u64>().unwrap_or(0);
-        let author_id = author_id_str.parse::<u64>().unwrap_or(0);
-
-        // Skip records with invalid IDs (likely from old schema)
-        if msg_id == 0 || channel_id == 0 || author_id == 0 {
-            continue;
-        }
-
-        // Create a minimal Message object with the available data
-        let mut msg = Message::default();
-        msg.id = MessageId::new(msg_id);
-        msg.channel_id = ChannelId::new(channel_id);
-        if let Some(guild_id_str) = guild_id_opt {
-            if let Ok(guild_id) = guild_id_str.parse::<u64>() {
-                msg.guild_id = Some(GuildId::new(guild_id));
+/// A window of time to pick stored messages from, as unix timestamps: from `after` on,
+/// and before `before`. Either end may be open.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DateRange {
+    pub after: Option<i64>,
+    pub before: Option<i64>,
+}
+
+impl DateRange {
+    /// Read `-year YYYY`, `-after YYYY-MM-DD` and `-before YYYY-MM-DD` (all UTC) from
+    /// command arguments. Given more than one, messages have to fit all of them.
+    pub fn from_args(args: &[&str]) -> Result<DateRange, String> {
+        let mut range = DateRange::default();
+        for (i, flag) in args.iter().enumerate() {
+            if !DATE_FLAGS.contains(flag) {
+                continue;
             }
+            let value = args
+                .get(i + 1)
+                .ok_or(format!("{flag} needs a date after it"))?;
+
+            let (after, before) = if *flag == "-year" {
+                let year = value
+                    .parse::<i32>()
+                    .ok()
+                    .filter(|year| (1970..=9999).contains(year))
+                    .ok_or(format!("\"{value}\" isn't a year like 2021"))?;
+                (Some(year_start(year)), Some(year_start(year + 1)))
+            } else {
+                let day = chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                    .map_err(|_| format!("\"{value}\" isn't a date like 2021-06-30"))?
+                    .and_hms_opt(0, 0, 0)
+                    .map(|day| day.and_utc().timestamp());
+                if *flag == "-after" {
+                    (day, None)
+                } else {
+                    (None, day)
+                }
+            };
+
+            range.after = range.after.max(after);
+            range.before = match (range.before, before) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                (a, b) => a.or(b),
+            };
         }
-        msg.author.id = UserId::new(author_id);
-        msg.author.name = author_name;
-        msg.content = content;
 
-        // Add to history
-        history.push_back(msg);
+        if let (Some(after), Some(before)) = (range.after, range.before) {
+            if after >= before {
+                return Err("Nothing can be posted in that date range".to_string());
+            }
+        }
+        Ok(range)
     }
 
-    Ok(())
+    pub fn is_unbounded(&self) -> bool {
+        self.after.is_none() && self.before.is_none()
+    }
+
+    /// SQL that keeps rows whose `column` falls in the range, or TRUE when it's unbounded.
+    /// The bounds are integers, so they're written into the query as they are.
+    pub fn sql_predicate(&self, column: &str) -> String {
+        let mut clauses = Vec::new();
+        if let Some(after) = self.after {
+            clauses.push(format!("{column} >= {after}"));
+        }
+        if let Some(before) = self.before {
+            clauses.push(format!("{column} < {before}"));
+        }
+        if clauses.is_empty() {
+            "TRUE".to_string()
+        } else {
+            clauses.join(" AND ")
+        }
+    }
+}
+
+// Midnight UTC on the first of January
+fn year_start(year: i32) -> i64 {
+    chrono::NaiveDate::from_ymd_opt(year, 1, 1)
+        .and_then(|day| day.and_hms_opt(0, 0, 0))
+        .map_or(0, |day| day.and_utc().timestamp())
+}
+
+/// Who a name given to `!quote -dud` turned out to mean
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NameMatch {
+    /// Someone's username or display name is exactly this, ignoring case
+    Exact(String),
+    /// Nobody is called that, but it's part of this username or display name
+    Partial(String),
+}
+
+impl NameMatch {
+    pub fn name(&self) -> &str {
+        match self {
+            NameMatch::Exact(name) | NameMatch::Partial(name) => name,
+        }
+    }
+}
+
+// Resolve a name for `!quote -dud` within a guild (any guild if None). An exact,
+// case-insensitive username or display name wins; failing that, the name containing it
+// that was posted under most often.
+pub async fn resolve_dud_name(
+    conn: Arc<Mutex<SqliteConnection>>,
+    name: String,
+    guild_id: Option<String>,
+) -> Result<Option<NameMatch>, Box<dyn std::error::Error>> {
+    let conn_guard = conn.lock().await;
+    let resolved = conn_guard
+        .call(move |conn| {
+            let exact = conn
+                .query_row(
+                    "SELECT 1 FROM messages
+                     WHERE (author = ?1 COLLATE NOCASE OR display_name = ?1 COLLATE NOCASE)
+                       AND (?2 IS NULL OR guild_id = ?2)
+                     LIMIT 1",
+                    rusqlite::params![name, guild_id],
+                    |_| Ok(()),
+                )
+                .optional()?;
+            if exact.is_some() {
+                return Ok(Some(NameMatch::Exact(name)));
+            }
+
+            let partial = conn
+                .query_row(
+                    "SELECT min(name) FROM (
+                         SELECT author AS name FROM messages
+                         WHERE author LIKE ?1 ESCAPE '\\' AND (?2 IS NULL OR guild_id = ?2)
+                         UNION ALL
+                         SELECT display_name FROM messages
+                         WHERE display_name LIKE ?1 ESCAPE '\\' AND (?2 IS NULL OR guild_id = ?2)
+                     )
+                     GROUP BY lower(name) ORDER BY COUNT(*) DESC, min(name) LIMIT 1",
+                    rusqlite::params![like_pattern(&name), guild_id],
+                    |row| row.get::<_, String>(0),
+                )
+                .optional()?;
+            Ok::<_, rusqlite::Error>(partial.map(NameMatch::Partial))
+        })
+        .await?;
+    Ok(resolved)
+}
+
+// Pick up to `count` random stored messages for `!quote -dud` as (author, display_name,
+// content), optionally from one user (whose username or display name is `username`,
+// ignoring case; see [`resolve_dud_name`]), one guild and a window of time. Messages
+// shorter than `min_length` characters are skipped unless that leaves nothing to pick from.
+pub async fn random_dud_quotes(
+    conn: Arc<Mutex<SqliteConnection>>,
+    username: Option<String>,
+    guild_id: Option<String>,
+    dates: DateRange,
+    min_length: usize,
+    count: usize,
+) -> Result<Vec<(String, String, String)>, Box<dyn std::error::Error>> {
+    let conn_guard = conn.lock().await;
+    let quotes = conn_guard
+        .call(move |conn| {
+            let date_filter = dates.sql_predicate("timestamp");
+            let query = format!(
+                "SELECT author, COALESCE(display_name, author), content, media FROM messages
+                 WHERE (?1 IS NULL OR author = ?1 COLLATE NOCASE OR display_name = ?1 COLLATE NOCASE)
+                   AND length(trim(content)) >= ?2
+                   AND (?3 IS NULL OR guild_id = ?3)
+                   AND {date_filter}
+                 ORDER BY RANDOM() LIMIT ?4"
+            );
+
+            let thresholds = if min_length > 0 {
+                vec![min_length, 0]
+            } else {
+                vec![0]
+            };
+            let mut stmt = conn.prepare(&query)?;
+            for threshold in thresholds {
+                let quotes = stmt
+                    .query_map(
+                        rusqlite::params![username, threshold as i64, guild_id, count as i64],
+                        |row| {
+                            Ok((
+                                row.get::<_, String>(0)?,
+                                row.get::<_, String>(1)?,
+                                crate::media_utils::with_media_markers(
+                                    &row.get::<_, String>(2)?,
+                                    row.get::<_, Option<String>>(3)?.as_deref(),
+                                ),
+                            ))
+                        },
+                    )?
+                    .collect::<Result<Vec<_>, _>>()?;
+                if !quotes.is_empty() {
+                    return Ok(quotes);
+                }
+            }
+            Ok::<_, rusqlite::Error>(Vec::new())
+        })
+        .await?;
+    Ok(quotes)
+}
+
+/// What happened in the messages table between two unix timestamps, for the daily digest
+#[derive(Debug, Default, PartialEq)]
+pub struct ActivitySummary {
+    pub message_count: i64,
+    /// (display name, message count) of whoever posted the most
+    pub most_active: Option<(String, i64)>,
+    /// (display name, content) of a random message long enough to be worth quoting
+    pub quote: Option<(String, String)>,
+}
+
+// Summarize messages with `since <= timestamp < until`. Messages from `exclude_author_id`
+// (the bot itself) are left out, and commands are never picked as the quote.
+pub async fn activity_summary(
+    conn: Arc<Mutex<SqliteConnection>>,
+    since: i64,
+    until: i64,
+    exclude_author_id: Option<String>,
+    quote_min_length: usize,
+) -> Result<ActivitySummary, Box<dyn std::error::Error>> {
+    let conn_guard = conn.lock().await;
+    let summary = conn_guard
+        .call(move |conn| {
+            let window = "timestamp >= ?1 AND timestamp < ?2 AND author_id != ?3";
+            let excluded = exclude_author_id.unwrap_or_default();
+
+            let message_count: i64 = conn.query_row(
+                &format!("SELECT COUNT(*) FROM messages WHERE {window}"),
+                rusqlite::params![since, until, excluded],
+                |row| row.get(0),
+            )?;
+
+            let most_active = conn
+                .query_row(
+                    &format!(
+                        "SELECT COALESCE(NULLIF(MAX(display_name), ''), author), COUNT(*) AS posts
+                         FROM messages WHERE {window}
+                         GROUP BY author ORDER BY posts DESC, MAX(timestamp) DESC LIMIT 1"
+                    ),
+                    rusqlite::params![since, until, excluded],
+                    |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?)),
+                )
+                .optional()?;
+
+            let quote = conn
+                .query_row(
+                    &format!(
+                        "SELECT COALESCE(NULLIF(display_name, ''), author), content
+                         FROM messages WHERE {window}
+                         AND length(trim(content)) >= ?4 AND content NOT LIKE '!%'
+                         ORDER BY RANDOM() LIMIT 1"
+                    ),
+                    rusqlite::params![since, until, excluded, quote_min_length as i64],
+                    |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)),
+                )
+                .optional()?;
+
+            Ok::<_, rusqlite::Error>(ActivitySummary {
+                message_count,
+                most_active,
+                quote,
+            })
+        })
+        .await?;
+    Ok(summary)
+}
+
+/// Load the most recent messages across all channels, oldest first, to seed the
+/// in-memory history at startup
+pub async fn load_message_history(
+    conn: Arc<Mutex<SqliteConnection>>,
+    limit: usize,
+) -> Result<Vec<StoredMessage>, Box<dyn std::error::Error>> {
+    let conn_guard = conn.lock().await;
+
+    let mut messages = conn_guard
+        .call(move |conn| {
+            let mut stmt = conn.prepare(
+                "SELECT message_id, channel_id, author, COALESCE(display_name, author), content,
+                        timestamp, referenced_message_id, media
+                 FROM messages ORDER BY timestamp DESC, id DESC LIMIT ?",
+            )?;
+
+            let rows = stmt.query_map([limit], |row| {
+                let content: String = row.get(4)?;
+                let media: Option<String> = row.get(7)?;
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, String>(3)?,
+                    crate::media_utils::with_media_markers(&content, media.as_deref()),
+                    row.get::<_, i64>(5)?,
+                    row.get::<_, Option<String>>(6)?,
+                ))
+            })?;
+
+            rows.collect::<Result<Vec<_>, _>>()
+        })
+        .await?
+        .into_iter()
+        .filter_map(
+            |(message_id, channel_id, author, display_name, content, timestamp, reference)| {
+                // Rows without a usable channel (from very old schemas) can't be placed
+                let channel_id = channel_id.parse::<u64>().ok().filter(|id| *id != 0)?;
+                Some(StoredMessage {
+                    message_id: message_id.parse().unwrap_or(0),
+                    channel_id,
+                    author,
+                    display_name,
+                    content,
+                    timestamp,
+                    referenced_message_id: reference.and_then(|id| id.parse().ok()),
+                })
+            },
+        )
+        .collect::<Vec<_>>();
+
+    messages.reverse();
+    Ok(messages)
 }
 
 // Update an existing message in the database when it's edited
@@ -941,9 +2063,1298 @@ pub async fn get_last_messages_by_channel(
                 result.insert(channel_id, timestamp_and_message_id);
             }
 
+            // Positions saved at shutdown can be newer than any stored message (e.g. from
+            // users who opted out of storage), so the later of the two wins
+            let mut stmt =
+                conn.prepare("SELECT channel_id, message_id, timestamp FROM last_seen")?;
+            let rows = stmt.query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, i64>(2)?,
+                ))
+            })?;
+            for row in rows {
+                let (channel_id, message_id, timestamp) = row?;
+                merge_saved_position(&mut result, &channel_id, &message_id, timestamp);
+            }
+
             Ok::<_, rusqlite::Error>(result)
         })
         .await?;
 
     Ok(result)
 }
+
+/// Fold a position saved in the last_seen table into the per-channel last messages. The
+/// saved position can be newer than any stored message (e.g. from users who opted out of
+/// storage), so the later of the two wins.
+pub fn merge_saved_position(
+    positions: &mut HashMap<ChannelId, (serenity::model::Timestamp, MessageId)>,
+    channel_id: &str,
+    message_id: &str,
+    timestamp: i64,
+) {
+    let (Ok(channel_id), Ok(message_id)) = (channel_id.parse::<u64>(), message_id.parse::<u64>())
+    else {
+        return;
+    };
+    if channel_id == 0 || message_id == 0 {
+        return;
+    }
+    let saved = (
+        serenity::model::Timestamp::from_unix_timestamp(timestamp).unwrap_or_default(),
+        MessageId::new(message_id),
+    );
+    let entry = positions.entry(ChannelId::new(channel_id)).or_insert(saved);
+    if saved.1 > entry.1 {
+        *entry = saved;
+    }
+}
+
+/// Save the last message seen in each channel so the next start can pick up where this
+/// one left off. Returns how many channels were saved.
+pub async fn persist_last_seen(
+    conn: Arc<Mutex<SqliteConnection>>,
+    last_seen: &HashMap<ChannelId, (serenity::model::Timestamp, MessageId)>,
+) -> Result<usize, Box<dyn std::error::Error + Send + Sync>> {
+    let rows: Vec<(String, String, i64)> = last_seen
+        .iter()
+        .map(|(channel_id, (timestamp, message_id))| {
+            (
+                channel_id.to_string(),
+                message_id.to_string(),
+                timestamp.unix_timestamp(),
+            )
+        })
+        .collect();
+
+    let saved = conn
+        .lock()
+        .await
+        .call(move |conn| {
+            let tx = conn.transaction()?;
+            {
+                let mut stmt = tx.prepare(
+                    "INSERT INTO last_seen (channel_id, message_id, timestamp) VALUES (?1, ?2, ?3)
+                     ON CONFLICT(channel_id) DO UPDATE SET
+                        message_id = excluded.message_id,
+                        timestamp = excluded.timestamp",
+                )?;
+                for (channel_id, message_id, timestamp) in &rows {
+                    stmt.execute(rusqlite::params![channel_id, message_id, timestamp])?;
+                }
+            }
+            tx.commit()?;
+            Ok::<_, rusqlite::Error>(rows.len())
+        })
+        .await?;
+
+    Ok(saved)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serenity::model::id::UserId;
+
+    fn column_names(conn: &rusqlite::Connection, table: &str) -> Vec<String> {
+        let mut columns: Vec<String> = table_columns(conn, table).unwrap().into_iter().collect();
+        columns.sort();
+        columns
+    }
+
+    // A single dud quote, as the memory interjection asks for one
+    async fn random_dud_quote(
+        conn: Arc<Mutex<SqliteConnection>>,
+        username: Option<String>,
+        guild_id: Option<String>,
+        dates: DateRange,
+        min_length: usize,
+    ) -> Result<Option<(String, String, String)>, Box<dyn std::error::Error>> {
+        Ok(
+            random_dud_quotes(conn, username, guild_id, dates, min_length, 1)
+                .await?
+                .into_iter()
+                .next(),
+        )
+    }
+
+    #[test]
+    fn test_migrate_empty_database() {
+        let mut conn = rusqlite::Connection::open_in_memory().unwrap();
+        migrate(&mut conn).unwrap();
+        assert_eq!(schema_version(&conn).unwrap(), MIGRATIONS.len() as i64);
+        assert_eq!(
+            column_names(&conn, "messages"),
+            vec![
+                "author",
+                "author_id",
+                "channel_id",
+                "content",
+                "display_name",
+                "guild_id",
+                "id",
+                "media",
+                "message_id",
+                "referenced_message_id",
+                "timestamp",
+            ]
+        );
+        for table in ["birthdays", "reminders", "no_store_users", "token_usage"] {
+            assert!(!column_names(&conn, table).is_empty(), "{table} missing");
+        }
+
+        // Running again is a no-op
+        migrate(&mut conn).unwrap();
+        assert_eq!(schema_version(&conn).unwrap(), MIGRATIONS.len() as i64);
+    }
+
+    #[test]
+    fn test_migrate_legacy_database() {
+        // The original layout: no ids and no display_name
+        let mut conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE messages (
+                id INTEGER PRIMARY KEY,
+                author TEXT NOT NULL,
+                content TEXT NOT NULL,
+                timestamp INTEGER NOT NULL
+             );
+             INSERT INTO messages (author, content, timestamp) VALUES ('alice', 'hello crow', 1000);",
+        )
+        .unwrap();
+
+        migrate(&mut conn).unwrap();
+        assert_eq!(schema_version(&conn).unwrap(), MIGRATIONS.len() as i64);
+
+        let columns = column_names(&conn, "messages");
+        for column in ["display_name", "message_id", "channel_id", "author_id"] {
+            assert!(columns.contains(&column.to_string()), "{column} missing");
+        }
+        let row: (String, Option<String>, String, String) = conn
+            .query_row(
+                "SELECT author, display_name, content, message_id FROM messages",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+            )
+            .unwrap();
+        assert_eq!(
+            row,
+            (
+                "alice".to_string(),
+                None,
+                "hello crow".to_string(),
+                "0".to_string()
+            )
+        );
+
+        // Old history is searchable once migrated
+        let hits: i64 = conn
+            .query_row(
+                "SELECT count(*) FROM messages_fts WHERE messages_fts MATCH 'crow'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(hits, 1);
+    }
+
+    #[test]
+    fn test_full_text_search_added_on_later_startup() {
+        // A database whose migrations ran on a SQLite build without FTS5
+        let mut conn = rusqlite::Connection::open_in_memory().unwrap();
+        migrate(&mut conn).unwrap();
+        conn.execute_batch(
+            "DROP TRIGGER messages_fts_insert;
+             DROP TRIGGER messages_fts_delete;
+             DROP TRIGGER messages_fts_update;
+             DROP TABLE messages_fts;
+             INSERT INTO messages (message_id, channel_id, author_id, author, content, timestamp)
+             VALUES ('1', 'c1', 'u1', 'alice', 'hello crow', 1000);",
+        )
+        .unwrap();
+
+        // The next startup sets search up and indexes what's already there
+        migrate(&mut conn).unwrap();
+        assert_eq!(schema_version(&conn).unwrap(), MIGRATIONS.len() as i64);
+        let hits: i64 = conn
+            .query_row(
+                "SELECT count(*) FROM messages_fts WHERE messages_fts MATCH 'crow'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(hits, 1);
+    }
+
+    #[tokio::test]
+    async fn test_context_marks_media() {
+        let conn = initialize_database(":memory:").await.unwrap();
+
+        let mut msg = Message::default();
+        msg.id = MessageId::new(1);
+        msg.channel_id = ChannelId::new(10);
+        msg.author.id = UserId::new(42);
+        msg.attachments = serde_json::from_value(serde_json::json!([{
+            "id": "1",
+            "filename": "cat.png",
+            "size": 1024,
+            "url": "https://cdn.example/cat.png",
+            "proxy_url": "https://cdn.example/cat.png",
+            "content_type": "image/png",
+        }]))
+        .unwrap();
+        save_message(conn.clone(), "alice", "Alice", "", Some(&msg), None)
+            .await
+            .unwrap();
+
+        let mut msg = Message::default();
+        msg.id = MessageId::new(2);
+        msg.channel_id = ChannelId::new(10);
+        msg.author.id = UserId::new(42);
+        msg.content = "https://www.example.com/story".to_string();
+        save_message(
+            conn.clone(),
+            "alice",
+            "Alice",
+            &msg.content,
+            Some(&msg),
+            None,
+        )
+        .await
+        .unwrap();
+
+        let mut contents: Vec<String> =
+            get_recent_messages_with_reply_context(conn.clone(), 10, Some("10"))
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|(_, _, _, content, _)| content)
+                .collect();
+        contents.sort();
+        assert_eq!(
+            contents,
+            vec![
+                "[image]",
+                "https://www.example.com/story [link: example.com]"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_persisted_last_seen_round_trips() {
+        let conn = initialize_database(":memory:").await.unwrap();
+        let at = |secs: i64| serenity::model::Timestamp::from_unix_timestamp(secs).unwrap();
+
+        let mut last_seen = HashMap::new();
+        last_seen.insert(ChannelId::new(1), (at(1_000), MessageId::new(10)));
+        last_seen.insert(ChannelId::new(2), (at(2_000), MessageId::new(20)));
+        assert_eq!(
+            persist_last_seen(conn.clone(), &last_seen).await.unwrap(),
+            2
+        );
+
+        // Saving again replaces a channel's position rather than adding to it
+        last_seen.insert(ChannelId::new(1), (at(1_500), MessageId::new(15)));
+        persist_last_seen(conn.clone(), &last_seen).await.unwrap();
+
+        let loaded = get_last_messages_by_channel(conn.clone()).await.unwrap();
+        assert_eq!(loaded, last_seen);
+    }
+
+    #[tokio::test]
+    async fn test_message_count_cache_tracks_inserts_and_trims() {
+        let conn = initialize_database(":memory:").await.unwrap();
+        let cache = MessageCountCache::new(Duration::from_secs(3600));
+
+        assert_eq!(
+            cache
+                .get(count_messages(conn.clone()), false)
+                .await
+                .unwrap(),
+            0
+        );
+
+        for i in 0..3 {
+            let content = format!("message {i}");
+            let inserted = save_message(conn.clone(), "alice", "Alice", &content, None, None)
+                .await
+                .unwrap();
+            assert!(inserted);
+            cache.record_insert();
+        }
+        assert_eq!(
+            cache
+                .get(count_messages(conn.clone()), false)
+                .await
+                .unwrap(),
+            3
+        );
+
+        let deleted = trim_message_history(conn.clone(), 1, &HashMap::new(), false)
+            .await
+            .unwrap();
+        assert_eq!(deleted, 2);
+        cache.record_trim(deleted);
+        assert_eq!(
+            cache
+                .get(count_messages(conn.clone()), false)
+                .await
+                .unwrap(),
+            1
+        );
+
+        // A forced recount agrees with the running count
+        assert_eq!(
+            cache.get(count_messages(conn.clone()), true).await.unwrap(),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn test_connection_recovers_after_transient_open_failure() {
+        let conn = initialize_database(":memory:").await.unwrap();
+
+        // Nothing to do while the connection is healthy
+        let reopened = ensure_connection_healthy(
+            &conn,
+            || async { open_database(":memory:").await },
+            3,
+            Duration::from_millis(1),
+        )
+        .await
+        .unwrap();
+        assert!(!reopened);
+
+        // Kill the background connection so queries fail
+        let dead = conn.lock().await.clone();
+        dead.close().await.unwrap();
+
+        // First reopen attempt fails, the second succeeds
+        let attempts = std::sync::atomic::AtomicU32::new(0);
+        let reopened = ensure_connection_healthy(
+            &conn,
+            || async {
+                if attempts.fetch_add(1, std::sync::atomic::Ordering::SeqCst) == 0 {
+                    Err("simulated open failure".into())
+                } else {
+                    open_database(":memory:").await
+                }
+            },
+            3,
+            Duration::from_millis(1),
+        )
+        .await
+        .unwrap();
+        assert!(reopened);
+        assert_eq!(attempts.load(std::sync::atomic::Ordering::SeqCst), 2);
+
+        // The shared handle works again
+        let cache = MessageCountCache::new(Duration::from_secs(60));
+        assert_eq!(
+            cache.get(count_messages(conn.clone()), true).await.unwrap(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_collapse_consecutive_duplicates() {
+        let message = |author: &str, content: &str| -> ContextMessage {
+            (
+                author.to_string(),
+                author.to_string(),
+                None,
+                content.to_string(),
+                None,
+            )
+        };
+
+        let messages = vec![
+            message("bridge", "hello from irc"),
+            message("bridge", "hello from irc"),
+            message("bridge", "hello from irc "),
+            message("alice", "hello from irc"),
+            message("bridge", "something else"),
+            message("bridge", "hello from irc"),
+        ];
+
+        let contents: Vec<_> = collapse_consecutive_duplicates(messages)
+            .into_iter()
+            .map(|(author, _, _, content, _)| format!("{author}: {content}"))
+            .collect();
+        assert_eq!(
+            contents,
+            vec![
+                "bridge: hello from irc",
+                "alice: hello from irc",
+                "bridge: something else",
+                "bridge: hello from irc",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_collapse_pasted_floods() {
+        let message = |author: &str, content: &str, reply: Option<&str>| -> ContextMessage {
+            (
+                author.to_string(),
+                author.to_string(),
+                None,
+                content.to_string(),
+                reply.map(str::to_string),
+            )
+        };
+
+        let stack_trace = (0..500)
+            .map(|i| format!("    at com.example.Widget.frob(Widget.java:{i})"))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let short_code = "```\nfn main() {\n    println!(\"hi\");\n}\n```\nanyone know why?\nit fails\nsometimes\nbut not always\nweird\nright?\nhelp\nplease\nthanks\nok\nbye\nlol\nhmm\nhuh\nwell";
+        let chatty = "line one\nline two\nline three";
+        let reply = format!("bob: {stack_trace}");
+
+        let messages = vec![
+            message("alice", "did anyone see the build break?", None),
+            message("bob", &stack_trace, None),
+            message("carol", short_code, None),
+            message("dave", chatty, Some(&reply)),
+        ];
+
+        let summarized = collapse_pasted_floods(messages.clone(), 40, false);
+        assert_eq!(summarized.len(), 4);
+        assert_eq!(summarized[0].3, "did anyone see the build break?");
+        assert_eq!(summarized[1].3, "[pasted 500 lines of code/logs]");
+        assert_eq!(summarized[2].3, "[pasted 20 lines of code/logs]");
+        assert_eq!(summarized[3].3, chatty);
+        assert_eq!(
+            summarized[3].4.as_deref(),
+            Some("bob: [pasted 500 lines of code/logs]")
+        );
+
+        let excluded = collapse_pasted_floods(messages.clone(), 40, true);
+        let authors: Vec<_> = excluded.iter().map(|m| m.0.as_str()).collect();
+        assert_eq!(authors, vec!["alice", "dave"]);
+
+        // A threshold of 0 turns detection off
+        assert_eq!(collapse_pasted_floods(messages.clone(), 0, false), messages);
+    }
+
+    #[tokio::test]
+    async fn test_dud_quote_skips_short_messages() {
+        let conn = initialize_database(":memory:").await.unwrap();
+
+        conn.lock()
+            .await
+            .call(|conn| {
+                for (id, author, content) in [
+                    (1, "alice", "lol"),
+                    (2, "alice", "ok"),
+                    (3, "alice", "  k  "),
+                    (4, "bob", "I have never been so insulted by a movie about hands"),
+                    (5, "carol", "same"),
+                ] {
+                    conn.execute(
+                        "INSERT INTO messages (message_id, channel_id, guild_id, author_id, author, display_name, content, timestamp)
+                         VALUES (?1, 'c1', 'g1', ?2, ?2, ?2, ?3, ?4)",
+                        rusqlite::params![id.to_string(), author, content, 1000 + id],
+                    )?;
+                }
+                Ok::<_, rusqlite::Error>(())
+            })
+            .await
+            .unwrap();
+
+        // The default filter never picks the one-word messages
+        for _ in 0..20 {
+            let (author, _, content) = random_dud_quote(
+                conn.clone(),
+                None,
+                None,
+                DateRange::default(),
+                DEFAULT_DUD_QUOTE_MIN_LENGTH,
+            )
+            .await
+            .unwrap()
+            .unwrap();
+            assert_eq!(author, "bob");
+            assert!(content.contains("hands"));
+        }
+
+        // A user with only short messages still gets quoted
+        let (author, _, content) = random_dud_quote(
+            conn.clone(),
+            Some("carol".to_string()),
+            None,
+            DateRange::default(),
+            20,
+        )
+        .await
+        .unwrap()
+        .unwrap();
+        assert_eq!((author.as_str(), content.as_str()), ("carol", "same"));
+
+        // With no minimum, short messages are fair game
+        let mut seen_short = false;
+        for _ in 0..50 {
+            let (_, _, content) =
+                random_dud_quote(conn.clone(), None, None, DateRange::default(), 0)
+                    .await
+                    .unwrap()
+                    .unwrap();
+            seen_short |= content.trim().len() < 5;
+        }
+        assert!(seen_short);
+
+        assert!(random_dud_quote(
+            conn.clone(),
+            Some("nobody".to_string()),
+            None,
+            DateRange::default(),
+            20
+        )
+        .await
+        .unwrap()
+        .is_none());
+    }
+
+    #[test]
+    fn test_date_range_from_args() {
+        let args = |text: &str| DateRange::from_args(&text.split_whitespace().collect::<Vec<_>>());
+        // 2021-01-01 and 2022-01-01, midnight UTC
+        let (y2021, y2022) = (1_609_459_200, 1_640_995_200);
+
+        assert_eq!(args("-dud bob"), Ok(DateRange::default()));
+        assert_eq!(
+            args("-year 2021 deadline"),
+            Ok(DateRange {
+                after: Some(y2021),
+                before: Some(y2022)
+            })
+        );
+        assert_eq!(
+            args("-after 2021-01-01"),
+            Ok(DateRange {
+                after: Some(y2021),
+                before: None
+            })
+        );
+        assert_eq!(
+            args("-before 2022-01-01"),
+            Ok(DateRange {
+                after: None,
+                before: Some(y2022)
+            })
+        );
+        // Together they narrow each other down
+        assert_eq!(
+            args("-year 2021 -after 2021-06-01"),
+            Ok(DateRange {
+                after: Some(1_622_505_600),
+                before: Some(y2022)
+            })
+        );
+
+        assert!(args("-year").is_err());
+        assert!(args("-year twenty").is_err());
+        assert!(args("-year 1969").is_err());
+        assert!(args("-after 2021-02-30").is_err());
+        assert!(args("-before yesterday").is_err());
+        assert!(args("-after 2022-01-01 -before 2021-01-01").is_err());
+    }
+
+    #[test]
+    fn test_date_range_sql_predicate() {
+        assert_eq!(DateRange::default().sql_predicate("timestamp"), "TRUE");
+        assert_eq!(
+            DateRange {
+                after: Some(100),
+                before: Some(200)
+            }
+            .sql_predicate("timestamp"),
+            "timestamp >= 100 AND timestamp < 200"
+        );
+        assert_eq!(
+            DateRange {
+                after: None,
+                before: Some(200)
+            }
+            .sql_predicate("m.timestamp"),
+            "m.timestamp < 200"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_random_dud_quote_by_date() {
+        let conn = initialize_database(":memory:").await.unwrap();
+        conn.lock()
+            .await
+            .call(|conn| {
+                for (id, author, timestamp) in [(1, "alice", 100), (2, "bob", 200), (3, "carol", 300)] {
+                    conn.execute(
+                        "INSERT INTO messages (message_id, channel_id, author_id, author, display_name, content, timestamp)
+                         VALUES (?1, 'c1', ?2, ?2, ?2, 'something worth remembering', ?3)",
+                        rusqlite::params![id.to_string(), author, timestamp],
+                    )?;
+                }
+                Ok::<_, rusqlite::Error>(())
+            })
+            .await
+            .unwrap();
+
+        let middle = DateRange {
+            after: Some(150),
+            before: Some(300),
+        };
+        for _ in 0..20 {
+            let (author, _, _) = random_dud_quote(conn.clone(), None, None, middle, 20)
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(author, "bob");
+        }
+        assert!(
+            random_dud_quote(conn.clone(), Some("alice".to_string()), None, middle, 0)
+                .await
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_random_dud_quote_without_display_name() {
+        let conn = initialize_database(":memory:").await.unwrap();
+        conn.lock()
+            .await
+            .call(|conn| {
+                conn.execute(
+                    "INSERT INTO messages (message_id, channel_id, author_id, author, content, timestamp)
+                     VALUES ('1', 'c1', 'u1', 'alice', 'something worth remembering', 1000)",
+                    [],
+                )?;
+                Ok::<_, rusqlite::Error>(())
+            })
+            .await
+            .unwrap();
+
+        let (author, display_name, _) =
+            random_dud_quote(conn.clone(), None, None, DateRange::default(), 20)
+                .await
+                .unwrap()
+                .unwrap();
+        assert_eq!((author.as_str(), display_name.as_str()), ("alice", "alice"));
+    }
+
+    #[tokio::test]
+    async fn test_random_dud_quote_by_guild() {
+        let conn = initialize_database(":memory:").await.unwrap();
+        conn.lock()
+            .await
+            .call(|conn| {
+                for (id, guild, author) in [(1, Some("g1"), "alice"), (2, Some("g2"), "bob"), (3, None, "carol")] {
+                    conn.execute(
+                        "INSERT INTO messages (message_id, channel_id, guild_id, author_id, author, display_name, content, timestamp)
+                         VALUES (?1, 'c1', ?2, ?3, ?3, ?3, 'something worth remembering', ?4)",
+                        rusqlite::params![id.to_string(), guild, author, 1000 + id],
+                    )?;
+                }
+                Ok::<_, rusqlite::Error>(())
+            })
+            .await
+            .unwrap();
+
+        // Scoped to a guild, only that guild's messages come up
+        for _ in 0..20 {
+            let (author, _, _) = random_dud_quote(
+                conn.clone(),
+                None,
+                Some("g1".to_string()),
+                DateRange::default(),
+                20,
+            )
+            .await
+            .unwrap()
+            .unwrap();
+            assert_eq!(author, "alice");
+        }
+        assert!(random_dud_quote(
+            conn.clone(),
+            Some("bob".to_string()),
+            Some("g1".to_string()),
+            DateRange::default(),
+            0
+        )
+        .await
+        .unwrap()
+        .is_none());
+
+        // Unscoped, every guild (and DMs) are fair game
+        let mut authors = HashSet::new();
+        for _ in 0..100 {
+            let (author, _, _) =
+                random_dud_quote(conn.clone(), None, None, DateRange::default(), 20)
+                    .await
+                    .unwrap()
+                    .unwrap();
+            authors.insert(author);
+        }
+        assert_eq!(authors.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_random_memory_by_guild() {
+        let conn = initialize_database(":memory:").await.unwrap();
+        conn.lock()
+            .await
+            .call(|conn| {
+                for (id, guild, author) in [(1, Some("g1"), "alice"), (2, Some("g2"), "bob"), (3, None, "carol"), (4, Some("g1"), "Crow")] {
+                    conn.execute(
+                        "INSERT INTO messages (message_id, channel_id, guild_id, author_id, author, display_name, content, timestamp)
+                         VALUES (?1, 'c1', ?2, ?3, ?3, ?3, 'something worth remembering', ?4)",
+                        rusqlite::params![id.to_string(), guild, author, 1000 + id],
+                    )?;
+                }
+                Ok::<_, rusqlite::Error>(())
+            })
+            .await
+            .unwrap();
+
+        // Scoped to a guild, only that guild's messages come up, and never the bot's own
+        for _ in 0..20 {
+            let (_, author, _, _) =
+                random_memory(conn.clone(), "Crow".to_string(), Some("g1".to_string()))
+                    .await
+                    .unwrap()
+                    .unwrap();
+            assert_eq!(author, "alice");
+        }
+        assert!(
+            random_memory(conn.clone(), "Crow".to_string(), Some("g3".to_string()))
+                .await
+                .unwrap()
+                .is_none()
+        );
+
+        // Unscoped, every guild (and DMs) are fair game
+        let mut authors = HashSet::new();
+        for _ in 0..100 {
+            let (_, author, _, _) = random_memory(conn.clone(), "Crow".to_string(), None)
+                .await
+                .unwrap()
+                .unwrap();
+            authors.insert(author);
+        }
+        assert_eq!(authors.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_random_dud_quotes_count() {
+        let conn = initialize_database(":memory:").await.unwrap();
+        conn.lock()
+            .await
+            .call(|conn| {
+                for (id, author) in [(1, "alice"), (2, "bob"), (3, "carol"), (4, "dave")] {
+                    conn.execute(
+                        "INSERT INTO messages (message_id, channel_id, guild_id, author_id, author, display_name, content, timestamp)
+                         VALUES (?1, 'c1', 'g1', ?2, ?2, ?2, 'something worth remembering', ?3)",
+                        rusqlite::params![id.to_string(), author, 1000 + id],
+                    )?;
+                }
+                Ok::<_, rusqlite::Error>(())
+            })
+            .await
+            .unwrap();
+
+        // Several quotes are all different messages
+        let quotes = random_dud_quotes(conn.clone(), None, None, DateRange::default(), 20, 3)
+            .await
+            .unwrap();
+        assert_eq!(quotes.len(), 3);
+        let authors: HashSet<_> = quotes.iter().map(|(author, _, _)| author).collect();
+        assert_eq!(authors.len(), 3);
+
+        // Asking for more than there are returns what there is
+        let quotes = random_dud_quotes(conn.clone(), None, None, DateRange::default(), 20, 10)
+            .await
+            .unwrap();
+        assert_eq!(quotes.len(), 4);
+        let quotes = random_dud_quotes(
+            conn.clone(),
+            Some("dave".to_string()),
+            None,
+            DateRange::default(),
+            20,
+            5,
+        )
+        .await
+        .unwrap();
+        assert_eq!(quotes.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_resolve_dud_name() {
+        let conn = initialize_database(":memory:").await.unwrap();
+        conn.lock()
+            .await
+            .call(|conn| {
+                for (id, guild, author, display) in [
+                    (1, "g1", "alice", "Alice"),
+                    (2, "g1", "bert", "Albert"),
+                    (3, "g1", "randal", "Randal"),
+                    (4, "g1", "randal", "Randal"),
+                    (5, "g2", "alfred", "Alfred"),
+                    (6, "g2", "alfred", "Alfred"),
+                    (7, "g2", "alfred", "Alfred"),
+                    (8, "g3", "mr_bean", "Mr_Bean"),
+                    (9, "g3", "mrxbeanie", "Mrxbeanie"),
+                    (10, "g3", "mrxbeanie", "Mrxbeanie"),
+                ] {
+                    conn.execute(
+                        "INSERT INTO messages (message_id, channel_id, guild_id, author_id, author, display_name, content, timestamp)
+                         VALUES (?1, 'c1', ?2, ?3, ?3, ?4, 'something worth remembering', ?5)",
+                        rusqlite::params![id.to_string(), guild, author, display, 1000 + id],
+                    )?;
+                }
+                Ok::<_, rusqlite::Error>(())
+            })
+            .await
+            .unwrap();
+        let resolve = |name: &str, guild: Option<&str>| {
+            resolve_dud_name(conn.clone(), name.to_string(), guild.map(str::to_string))
+        };
+
+        // Exact usernames and display names win, ignoring case
+        assert_eq!(
+            resolve("ALICE", Some("g1")).await.unwrap(),
+            Some(NameMatch::Exact("ALICE".to_string()))
+        );
+        assert_eq!(
+            resolve("albert", Some("g1")).await.unwrap(),
+            Some(NameMatch::Exact("albert".to_string()))
+        );
+
+        // Otherwise the name containing it that was used most, within the guild
+        assert_eq!(
+            resolve("al", Some("g1")).await.unwrap(),
+            Some(NameMatch::Partial("Randal".to_string()))
+        );
+        assert_eq!(
+            resolve("al", None).await.unwrap(),
+            Some(NameMatch::Partial("Alfred".to_string()))
+        );
+        assert_eq!(resolve("zed", None).await.unwrap(), None);
+
+        // LIKE wildcards in the name are taken literally
+        assert_eq!(
+            resolve("r_b", Some("g3")).await.unwrap(),
+            Some(NameMatch::Partial("Mr_Bean".to_string()))
+        );
+        assert_eq!(resolve("%", Some("g3")).await.unwrap(), None);
+
+        // Quoting by name never picks up someone else whose name contains it
+        assert!(random_dud_quote(
+            conn.clone(),
+            Some("al".to_string()),
+            None,
+            DateRange::default(),
+            0
+        )
+        .await
+        .unwrap()
+        .is_none());
+        let (author, _, _) = random_dud_quote(
+            conn.clone(),
+            Some("ALBERT".to_string()),
+            None,
+            DateRange::default(),
+            0,
+        )
+        .await
+        .unwrap()
+        .unwrap();
+        assert_eq!(author, "bert");
+    }
+
+    #[tokio::test]
+    async fn test_search_message_history() {
+        let conn = initialize_database(":memory:").await.unwrap();
+
+        conn.lock()
+            .await
+            .call(|conn| {
+                for (id, guild, channel, content) in [
+                    (1, "g1", "c1", "Anyone watching the Mitchell and Hepburn movie tonight?"),
+                    (2, "g1", "c2", "MITCHELL is the best riffed movie"),
+                    (3, "g1", "c1", "!search mitchell"),
+                    (4, "g2", "c9", "Mitchell over in another server"),
+                    (5, "g1", "c1", "nothing to see here"),
+                ] {
+                    conn.execute(
+                        "INSERT INTO messages (message_id, channel_id, guild_id, author_id, author, display_name, content, timestamp)
+                         VALUES (?1, ?2, ?3, 'u1', 'alice', 'Alice', ?4, ?5)",
+                        rusqlite::params![id.to_string(), channel, guild, content, 1000 + id],
+                    )?;
+                }
+                Ok::<_, rusqlite::Error>(())
+            })
+            .await
+            .unwrap();
+
+        // Case-insensitive, guild-scoped, newest first, commands excluded
+        let hits = search_message_history(conn.clone(), "mitchell", "g1", "c1", 10, 0)
+            .await
+            .unwrap();
+        let ids: Vec<_> = hits.iter().map(|h| h.message_id.as_str()).collect();
+        assert_eq!(ids, vec!["2", "1"]);
+
+        // All words must match, and query syntax in user input is treated as text
+        let hits = search_message_history(conn.clone(), "hepburn MOVIE", "g1", "c1", 10, 0)
+            .await
+            .unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].message_id, "1");
+        assert!(
+            search_message_history(conn.clone(), "\"mitchell OR", "g1", "c1", 10, 0)
+                .await
+                .is_ok()
+        );
+
+        // Paging
+        let hits = search_message_history(conn.clone(), "mitchell", "g1", "c1", 1, 1)
+            .await
+            .unwrap();
+        assert_eq!(hits[0].message_id, "1");
+
+        // Edits and trims keep the index in sync
+        update_message(conn.clone(), "5".to_string(), "mitchell again".to_string())
+            .await
+            .unwrap();
+        trim_message_history(conn.clone(), 3, &HashMap::new(), false)
+            .await
+            .unwrap();
+        let hits = search_message_history(conn.clone(), "mitchell", "g1", "c1", 10, 0)
+            .await
+            .unwrap();
+        let ids: Vec<_> = hits.iter().map(|h| h.message_id.as_str()).collect();
+        assert_eq!(ids, vec!["5"]);
+    }
+
+    #[test]
+    fn test_history_search_query_builders() {
+        assert_eq!(fts_match_query("  hello   world "), "\"hello\" \"world\"");
+        assert_eq!(
+            fts_match_query("say \"hi\" OR"),
+            "\"say\" \"\"\"hi\"\"\" \"OR\""
+        );
+        assert_eq!(fts_match_query("   "), "");
+
+        assert_eq!(like_pattern("movie"), "%movie%");
+        assert_eq!(like_pattern("100%_\\"), "%100\\%\\_\\\\%");
+
+        let sql = like_search_sql(2);
+        assert!(sql.contains("m.content LIKE ?5 ESCAPE '\\' AND m.content LIKE ?6 ESCAPE '\\'"));
+        assert!(!sql.contains("?7"));
+        assert!(fts_search_sql().contains("MATCH ?5"));
+    }
+
+    #[tokio::test]
+    async fn test_delete_messages_by_author() {
+        let conn = initialize_database(":memory:").await.unwrap();
+
+        conn.lock()
+            .await
+            .call(|conn| {
+                for (id, author_id, author, display_name) in [
+                    (1, "42", "bob", "Bobby"),
+                    (2, "0", "Bob", ""),
+                    (3, "7", "relay", "bobby"),
+                    (4, "7", "alice", "Alice"),
+                    (5, "8", "bobcat", "Bob Cat"),
+                ] {
+                    conn.execute(
+                        "INSERT INTO messages (message_id, channel_id, guild_id, author_id, author, display_name, content, timestamp)
+                         VALUES (?1, 'c1', 'g1', ?2, ?3, ?4, 'hello', ?5)",
+                        rusqlite::params![id.to_string(), author_id, author, display_name, 1000 + id],
+                    )?;
+                }
+                Ok::<_, rusqlite::Error>(())
+            })
+            .await
+            .unwrap();
+
+        let remaining = |conn: Arc<Mutex<SqliteConnection>>| async move {
+            conn.lock()
+                .await
+                .call(|conn| {
+                    let mut stmt =
+                        conn.prepare("SELECT message_id FROM messages ORDER BY message_id")?;
+                    let ids = stmt
+                        .query_map([], |row| row.get::<_, String>(0))?
+                        .collect::<Result<Vec<_>, _>>()?;
+                    Ok::<_, rusqlite::Error>(ids)
+                })
+                .await
+                .unwrap()
+        };
+
+        // Username matches ignore case; partial names don't match
+        assert_eq!(
+            delete_messages_by_author(conn.clone(), "BOB".to_string())
+                .await
+                .unwrap(),
+            2
+        );
+        assert_eq!(remaining(conn.clone()).await, vec!["3", "4", "5"]);
+
+        // So do display names
+        assert_eq!(
+            delete_messages_by_author(conn.clone(), "Bobby".to_string())
+                .await
+                .unwrap(),
+            1
+        );
+        assert_eq!(remaining(conn.clone()).await, vec!["4", "5"]);
+
+        assert_eq!(
+            delete_messages_by_author(conn.clone(), "nobody".to_string())
+                .await
+                .unwrap(),
+            0
+        );
+    }
+
+    #[tokio::test]
+    async fn test_search_without_fts() {
+        let conn = initialize_database(":memory:").await.unwrap();
+
+        // What a database built without FTS5 looks like
+        conn.lock()
+            .await
+            .call(|conn| {
+                conn.execute_batch(
+                    "DROP TRIGGER messages_fts_insert;
+                     DROP TRIGGER messages_fts_delete;
+                     DROP TRIGGER messages_fts_update;
+                     DROP TABLE messages_fts;",
+                )?;
+                for (id, content) in [
+                    (1, "Mitchell is 100% the best movie"),
+                    (2, "mitchell_fan posted again"),
+                    (3, "!search mitchell"),
+                ] {
+                    conn.execute(
+                        "INSERT INTO messages (message_id, channel_id, guild_id, author_id, author, display_name, content, timestamp)
+                         VALUES (?1, 'c1', 'g1', 'u1', 'alice', 'Alice', ?2, ?3)",
+                        rusqlite::params![id.to_string(), content, 1000 + id],
+                    )?;
+                }
+                Ok::<_, rusqlite::Error>(())
+            })
+            .await
+            .unwrap();
+
+        let ids = |hits: Vec<HistorySearchHit>| -> Vec<String> {
+            hits.into_iter().map(|h| h.message_id).collect()
+        };
+
+        let hits = search_message_history(conn.clone(), "MITCHELL", "g1", "c1", 10, 0)
+            .await
+            .unwrap();
+        assert_eq!(ids(hits), vec!["2", "1"]);
+
+        // LIKE wildcards in the search are matched literally
+        let hits = search_message_history(conn.clone(), "100% movie", "g1", "c1", 10, 0)
+            .await
+            .unwrap();
+        assert_eq!(ids(hits), vec!["1"]);
+        let hits = search_message_history(conn.clone(), "l_fan", "g1", "c1", 10, 0)
+            .await
+            .unwrap();
+        assert_eq!(ids(hits), vec!["2"]);
+        let hits = search_message_history(conn.clone(), "%", "g1", "c1", 10, 0)
+            .await
+            .unwrap();
+        assert_eq!(ids(hits), vec!["1"]);
+    }
+
+    #[tokio::test]
+    async fn test_trim_keeps_protected_messages() {
+        let conn = initialize_database(":memory:").await.unwrap();
+        conn.lock()
+            .await
+            .call(|conn| {
+                for i in 0..6 {
+                    conn.execute(
+                        "INSERT INTO messages (message_id, channel_id, author_id, author, content, timestamp)
+                         VALUES (?1, '100', '1', 'alice', ?2, ?3)",
+                        rusqlite::params![format!("m{i}"), format!("msg {i}"), i],
+                    )?;
+                }
+                Ok::<_, rusqlite::Error>(())
+            })
+            .await
+            .unwrap();
+
+        // The two oldest messages were quoted; a trim to 2 would otherwise take them first
+        protect_message(conn.clone(), "m0".to_string(), "quote")
+            .await
+            .unwrap();
+        protect_message(conn.clone(), "m1".to_string(), "reminder")
+            .await
+            .unwrap();
+        let remaining_ids = || async {
+            conn.lock()
+                .await
+                .call(|conn| {
+                    let mut stmt =
+                        conn.prepare("SELECT message_id FROM messages ORDER BY timestamp")?;
+                    let rows = stmt.query_map([], |row| row.get::<_, String>(0))?;
+                    rows.collect::<Result<Vec<_>, _>>()
+                })
+                .await
+                .unwrap()
+        };
+
+        // Protected messages survive and don't use up the channel's limit
+        let deleted = trim_message_history(conn.clone(), 2, &HashMap::new(), true)
+            .await
+            .unwrap();
+        assert_eq!(deleted, 2);
+        assert_eq!(remaining_ids().await, vec!["m0", "m1", "m4", "m5"]);
+
+        // With protection off they're trimmed like anything else
+        let deleted = trim_message_history(conn.clone(), 2, &HashMap::new(), false)
+            .await
+            .unwrap();
+        assert_eq!(deleted, 2);
+        assert_eq!(remaining_ids().await, vec!["m4", "m5"]);
+    }
+
+    #[tokio::test]
+    async fn test_trim_message_history_per_channel() {
+        let conn = initialize_database(":memory:").await.unwrap();
+
+        // 5 messages in a busy channel, 5 in a quiet one, and 2 and 3 in unconfigured ones
+        conn.lock()
+            .await
+            .call(|conn| {
+                for (channel, n) in [("100", 5), ("200", 5), ("300", 2), ("400", 3)] {
+                    for i in 0..n {
+                        conn.execute(
+                            "INSERT INTO messages (message_id, channel_id, author_id, author, content, timestamp)
+                             VALUES (?1, ?2, '1', 'alice', ?3, ?4)",
+                            rusqlite::params![format!("{channel}-{i}"), channel, format!("msg {i}"), i],
+                        )?;
+                    }
+                }
+                Ok::<_, rusqlite::Error>(())
+            })
+            .await
+            .unwrap();
+
+        let mut limits = HashMap::new();
+        limits.insert("100".to_string(), 4);
+        limits.insert("200".to_string(), 1);
+
+        let deleted = trim_message_history(conn.clone(), 3, &limits, true)
+            .await
+            .unwrap();
+        assert_eq!(deleted, 7);
+
+        let remaining = conn
+            .lock()
+            .await
+            .call(|conn| {
+                let mut stmt = conn.prepare(
+                    "SELECT channel_id, COUNT(*), MIN(timestamp) FROM messages
+                     GROUP BY channel_id ORDER BY channel_id",
+                )?;
+                let rows = stmt.query_map([], |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        row.get::<_, i64>(1)?,
+                        row.get::<_, i64>(2)?,
+                    ))
+                })?;
+                rows.collect::<Result<Vec<_>, _>>()
+            })
+            .await
+            .unwrap();
+
+        // The oldest messages are the ones removed from each configured channel, while
+        // the unconfigured ones keep the newest 3 between them
+        assert_eq!(
+            remaining,
+            vec![
+                ("100".to_string(), 4, 1),
+                ("200".to_string(), 1, 4),
+                ("300".to_string(), 1, 1),
+                ("400".to_string(), 2, 1),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_activity_summary_only_counts_the_window() {
+        let conn = initialize_database(":memory:").await.unwrap();
+        let day_start = 1_700_000_000;
+        let day_end = day_start + 86_400;
+
+        conn.lock()
+            .await
+            .call(move |conn| {
+                for (id, author_id, author, content, timestamp) in [
+                    // Yesterday: alice was chatty, but that doesn't count today
+                    (1, "1", "alice", "yesterday's news is old news", day_start - 10),
+                    (2, "1", "alice", "still yesterday over here", day_start - 5),
+                    (3, "1", "alice", "and yet more from yesterday", day_start - 1),
+                    // Today
+                    (4, "2", "bob", "I have never been so insulted by a movie about hands", day_start),
+                    (5, "2", "bob", "lol", day_start + 100),
+                    (6, "3", "carol", "!quote -dud somebody with a long name", day_start + 200),
+                    (7, "99", "crow", "I am the bot and I talk far too much", day_start + 300),
+                    (8, "99", "crow", "I am the bot and I talk far too much", day_start + 301),
+                    (9, "99", "crow", "I am the bot and I talk far too much", day_start + 302),
+                    // Tomorrow
+                    (10, "3", "carol", "this one belongs to the next digest", day_end),
+                ] {
+                    conn.execute(
+                        "INSERT INTO messages (message_id, channel_id, author_id, author, display_name, content, timestamp)
+                         VALUES (?1, 'c1', ?2, ?3, ?3, ?4, ?5)",
+                        rusqlite::params![id.to_string(), author_id, author, content, timestamp],
+                    )?;
+                }
+                Ok::<_, rusqlite::Error>(())
+            })
+            .await
+            .unwrap();
+
+        let summary = activity_summary(
+            conn.clone(),
+            day_start,
+            day_end,
+            Some("99".to_string()),
+            DEFAULT_DUD_QUOTE_MIN_LENGTH,
+        )
+        .await
+        .unwrap();
+
+        // bob's two messages and carol's command; the bot and other days are left out
+        assert_eq!(summary.message_count, 3);
+        assert_eq!(summary.most_active, Some(("bob".to_string(), 2)));
+        // The only quotable message today: long enough and not a command
+        assert_eq!(
+            summary.quote,
+            Some((
+                "bob".to_string(),
+                "I have never been so insulted by a movie about hands".to_string()
+            ))
+        );
+
+        // A quiet window has nothing to report
+        let quiet = activity_summary(conn.clone(), day_end + 1, day_end + 86_400, None, 0)
+            .await
+            .unwrap();
+        assert_eq!(quiet, ActivitySummary::default());
+    }
+}
@@ -0,0 +1,416 @@
+use anyhow::Result;
+use rand::RngExt;
+use serenity::all::Http;
+use serenity::model::channel::Message;
+use std::fmt;
+
+// Most dice one roll may throw
+const MAX_DICE: u32 = 1000;
+// Most sides a die may have
+const MAX_SIDES: u32 = 10_000;
+// Largest flat modifier, so totals stay readable
+const MAX_MODIFIER: i64 = 1_000_000;
+// Longest notation we'll try to read
+const MAX_NOTATION_CHARS: usize = 50;
+// Past this many dice only the total is shown, to keep the reply short
+const MAX_SHOWN_DICE: usize = 20;
+
+/// Which dice count towards the total
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Keep {
+    Highest(u32),
+    Lowest(u32),
+}
+
+/// A parsed roll such as `4d6kh3+2`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiceRoll {
+    pub count: u32,
+    pub sides: u32,
+    pub keep: Option<Keep>,
+    /// The flat modifiers added together
+    pub modifier: i64,
+}
+
+/// Why some dice notation couldn't be rolled, worded for the channel
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiceError {
+    Empty,
+    TooLong,
+    Invalid(String),
+    TooManyDice,
+    TooManySides,
+    NoDice,
+    NoSides,
+    KeepTooMany,
+    ModifierTooBig,
+}
+
+impl fmt::Display for DiceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DiceError::Empty => write!(
+                f,
+                "Tell me what to roll, e.g. `!roll 2d6+3`, `!roll d20` or `!roll 4d6kh3`"
+            ),
+            DiceError::TooLong => write!(
+                f,
+                "That's too long to be a roll (max {MAX_NOTATION_CHARS} characters)"
+            ),
+            DiceError::Invalid(notation) => write!(
+                f,
+                "I don't know how to roll `{notation}`; try something like `2d6+3` or `4d6kh3`"
+            ),
+            DiceError::TooManyDice => write!(f, "I only have {MAX_DICE} dice"),
+            DiceError::TooManySides => write!(f, "Dice can have at most {MAX_SIDES} sides"),
+            DiceError::NoDice => write!(f, "Roll at least one die"),
+            DiceError::NoSides => write!(f, "A die needs at least one side"),
+            DiceError::KeepTooMany => write!(f, "You can't keep more dice than you roll"),
+            DiceError::ModifierTooBig => write!(f, "That modifier is too big"),
+        }
+    }
+}
+
+// Reads a roll left to right
+struct Parser<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+}
+
+impl Parser<'_> {
+    // A run of digits, if there is one; None also stands for a number too big to be sensible
+    fn number(&mut self) -> Option<Option<u64>> {
+        let mut digits = String::new();
+        while let Some(c) = self.chars.peek().copied().filter(char::is_ascii_digit) {
+            digits.push(c);
+            self.chars.next();
+        }
+        (!digits.is_empty()).then(|| digits.parse().ok())
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        self.chars.next_if_eq(&c).is_some()
+    }
+}
+
+/// Read standard dice notation: `[count]d<sides>`, optionally `kh<n>`/`k<n>` (keep the
+/// highest n) or `kl<n>` (keep the lowest n), then any `+n`/`-n` modifiers.
+/// Case and spaces don't matter.
+pub fn parse(notation: &str) -> Result<DiceRoll, DiceError> {
+    let notation = notation.trim();
+    if notation.is_empty() {
+        return Err(DiceError::Empty);
+    }
+    if notation.chars().count() > MAX_NOTATION_CHARS {
+        return Err(DiceError::TooLong);
+    }
+
+    let compact: String = notation
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect::<String>()
+        .to_lowercase();
+    let invalid = || DiceError::Invalid(notation.to_string());
+    let mut parser = Parser {
+        chars: compact.chars().peekable(),
+    };
+
+    let count = match parser.number() {
+        None => 1,
+        Some(count) => count.ok_or(DiceError::TooManyDice)?,
+    };
+    if !parser.eat('d') {
+        return Err(invalid());
+    }
+    let sides = parser
+        .number()
+        .ok_or_else(invalid)?
+        .ok_or(DiceError::TooManySides)?;
+
+    if count == 0 {
+        return Err(DiceError::NoDice);
+    }
+    if count > u64::from(MAX_DICE) {
+        return Err(DiceError::TooManyDice);
+    }
+    if sides == 0 {
+        return Err(DiceError::NoSides);
+    }
+    if sides > u64::from(MAX_SIDES) {
+        return Err(DiceError::TooManySides);
+    }
+    let (count, sides) = (count as u32, sides as u32);
+
+    let keep = if parser.eat('k') {
+        let lowest = if parser.eat('l') {
+            true
+        } else {
+            parser.eat('h');
+            false
+        };
+        let kept = parser
+            .number()
+            .ok_or_else(invalid)?
+            .filter(|kept| *kept <= u64::from(count))
+            .ok_or(DiceError::KeepTooMany)? as u32;
+        if kept == 0 {
+            return Err(DiceError::NoDice);
+        }
+        Some(if lowest {
+            Keep::Lowest(kept)
+        } else {
+            Keep::Highest(kept)
+        })
+    } else {
+        None
+    };
+
+    let mut modifier: i64 = 0;
+    while let Some(sign) = parser.chars.next() {
+        let sign = match sign {
+            '+' => 1,
+            '-' => -1,
+            _ => return Err(invalid()),
+        };
+        let amount = parser
+            .number()
+            .ok_or_else(invalid)?
+            .and_then(|amount| i64::try_from(amount).ok())
+            .ok_or(DiceError::ModifierTooBig)?;
+        modifier += sign * amount;
+        if modifier.abs() > MAX_MODIFIER {
+            return Err(DiceError::ModifierTooBig);
+        }
+    }
+
+    Ok(DiceRoll {
+        count,
+        sides,
+        keep,
+        modifier,
+    })
+}
+
+/// The dice as they landed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RollResult {
+    /// Each die in the order it was rolled, and whether it counts
+    pub dice: Vec<(u32, bool)>,
+    pub total: i64,
+}
+
+impl DiceRoll {
+    /// Throw the dice
+    pub fn roll(&self, rng: &mut impl RngExt) -> RollResult {
+        let faces: Vec<u32> = (0..self.count)
+            .map(|_| rng.random_range(1..=self.sides))
+            .collect();
+        self.score(faces)
+    }
+
+    // Work out which dice are kept and the total
+    fn score(&self, faces: Vec<u32>) -> RollResult {
+        let mut order: Vec<usize> = (0..faces.len()).collect();
+        let kept = match self.keep {
+            None => faces.len(),
+            Some(Keep::Highest(kept)) => {
+                order.sort_by(|a, b| faces[*b].cmp(&faces[*a]));
+                kept as usize
+            }
+            Some(Keep::Lowest(kept)) => {
+                order.sort_by_key(|i| faces[*i]);
+                kept as usize
+            }
+        };
+
+        let mut counts = vec![false; faces.len()];
+        for i in order.into_iter().take(kept) {
+            counts[i] = true;
+        }
+        let total = faces
+            .iter()
+            .zip(&counts)
+            .filter(|(_, counts)| **counts)
+            .map(|(face, _)| i64::from(*face))
+            .sum::<i64>()
+            + self.modifier;
+
+        RollResult {
+            dice: faces.into_iter().zip(counts).collect(),
+            total,
+        }
+    }
+}
+
+impl fmt::Display for DiceRoll {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}d{}", self.count, self.sides)?;
+        match self.keep {
+            Some(Keep::Highest(kept)) => write!(f, "kh{kept}")?,
+            Some(Keep::Lowest(kept)) => write!(f, "kl{kept}")?,
+            None => {}
+        }
+        match self.modifier {
+            0 => Ok(()),
+            modifier if modifier > 0 => write!(f, "+{modifier}"),
+            modifier => write!(f, "{modifier}"),
+        }
+    }
+}
+
+// One line for the channel: the dice (dropped ones struck out) and the total
+fn format_roll(roll: &DiceRoll, result: &RollResult) -> String {
+    if result.dice.len() > MAX_SHOWN_DICE {
+        return format!(
+            "🎲 `{}`: **{}** ({} dice)",
+            roll,
+            result.total,
+            result.dice.len()
+        );
+    }
+
+    let dice = result
+        .dice
+        .iter()
+        .map(|(face, counts)| {
+            if *counts {
+                face.to_string()
+            } else {
+                format!("~~{face}~~")
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+    let modifier = match roll.modifier {
+        0 => String::new(),
+        modifier if modifier > 0 => format!(" + {modifier}"),
+        modifier => format!(" - {}", -modifier),
+    };
+    format!(
+        "🎲 `{}`: [{}]{} = **{}**",
+        roll, dice, modifier, result.total
+    )
+}
+
+// This function will be called from main.rs to handle the !roll command
+pub async fn handle_roll_command(http: &Http, msg: &Message, notation: &str) -> Result<()> {
+    let reply = match parse(notation) {
+        Ok(roll) => {
+            let result = roll.roll(&mut rand::rng());
+            format_roll(&roll, &result)
+        }
+        Err(e) => e.to_string(),
+    };
+    msg.reply(http, reply).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn roll(count: u32, sides: u32, keep: Option<Keep>, modifier: i64) -> DiceRoll {
+        DiceRoll {
+            count,
+            sides,
+            keep,
+            modifier,
+        }
+    }
+
+    #[test]
+    fn test_parse_dice_notation() {
+        assert_eq!(parse("2d6"), Ok(roll(2, 6, None, 0)));
+        assert_eq!(parse("d20"), Ok(roll(1, 20, None, 0)));
+        assert_eq!(parse("2d6+3"), Ok(roll(2, 6, None, 3)));
+        assert_eq!(parse("1d8-1"), Ok(roll(1, 8, None, -1)));
+        assert_eq!(parse(" 3D4 + 2 - 5 "), Ok(roll(3, 4, None, -3)));
+        assert_eq!(parse("d100+0"), Ok(roll(1, 100, None, 0)));
+        assert_eq!(parse("1000d10000"), Ok(roll(1000, 10_000, None, 0)));
+    }
+
+    #[test]
+    fn test_parse_keep() {
+        assert_eq!(parse("4d6kh3"), Ok(roll(4, 6, Some(Keep::Highest(3)), 0)));
+        assert_eq!(parse("4d6k3"), Ok(roll(4, 6, Some(Keep::Highest(3)), 0)));
+        assert_eq!(
+            parse("2d20kl1+5"),
+            Ok(roll(2, 20, Some(Keep::Lowest(1)), 5))
+        );
+        assert_eq!(parse("2d20KH2"), Ok(roll(2, 20, Some(Keep::Highest(2)), 0)));
+        assert_eq!(parse("4d6kh5"), Err(DiceError::KeepTooMany));
+        assert_eq!(parse("4d6kh0"), Err(DiceError::NoDice));
+        assert!(matches!(parse("4d6kh"), Err(DiceError::Invalid(_))));
+        assert!(matches!(parse("4d6kx3"), Err(DiceError::Invalid(_))));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(parse(""), Err(DiceError::Empty));
+        assert_eq!(parse("   "), Err(DiceError::Empty));
+        assert_eq!(parse("1001d6"), Err(DiceError::TooManyDice));
+        assert_eq!(
+            parse("99999999999999999999999d6"),
+            Err(DiceError::TooManyDice)
+        );
+        assert_eq!(parse("d10001"), Err(DiceError::TooManySides));
+        assert_eq!(parse("0d6"), Err(DiceError::NoDice));
+        assert_eq!(parse("2d0"), Err(DiceError::NoSides));
+        assert_eq!(parse("d20+1000001"), Err(DiceError::ModifierTooBig));
+        assert_eq!(parse(&"1".repeat(51)), Err(DiceError::TooLong));
+        for bad in ["6", "d", "2d", "2x6", "2d6+", "2d6*2", "two d6", "2d6+-1"] {
+            assert!(
+                matches!(parse(bad), Err(DiceError::Invalid(_))),
+                "{bad} should be invalid"
+            );
+        }
+    }
+
+    #[test]
+    fn test_keep_scoring() {
+        let highest = roll(4, 6, Some(Keep::Highest(3)), 0).score(vec![3, 6, 1, 3]);
+        assert_eq!(
+            highest.dice,
+            vec![(3, true), (6, true), (1, false), (3, true)]
+        );
+        assert_eq!(highest.total, 12);
+
+        let lowest = roll(2, 20, Some(Keep::Lowest(1)), 2).score(vec![15, 4]);
+        assert_eq!(lowest.dice, vec![(15, false), (4, true)]);
+        assert_eq!(lowest.total, 6);
+
+        let all = roll(2, 6, None, -1).score(vec![2, 5]);
+        assert_eq!(all.total, 6);
+    }
+
+    #[test]
+    fn test_rolls_stay_on_the_dice() {
+        let mut rng = rand::rng();
+        let dice = roll(100, 6, None, 1);
+        let result = dice.roll(&mut rng);
+        assert_eq!(result.dice.len(), 100);
+        assert!(result.dice.iter().all(|(face, _)| (1..=6).contains(face)));
+        assert!((101..=601).contains(&result.total));
+    }
+
+    #[test]
+    fn test_format_roll() {
+        let dice = parse("4d6kh3+2").unwrap();
+        assert_eq!(
+            format_roll(&dice, &dice.score(vec![3, 6, 1, 3])),
+            "🎲 `4d6kh3+2`: [3, 6, ~~1~~, 3] + 2 = **14**"
+        );
+
+        let dice = parse("d20-1").unwrap();
+        assert_eq!(
+            format_roll(&dice, &dice.score(vec![12])),
+            "🎲 `1d20-1`: [12] - 1 = **11**"
+        );
+
+        // Lots of dice only show the total
+        let dice = parse("30d1").unwrap();
+        assert_eq!(
+            format_roll(&dice, &dice.score(vec![1; 30])),
+            "🎲 `30d1`: **30** (30 dice)"
+        );
+    }
+}
@@ -1,10 +1,26 @@
 use lazy_static::lazy_static;
 use regex::Regex;
 use serenity::model::channel::Message;
+use serenity::model::guild::Member;
 use serenity::model::id::{GuildId, UserId};
 use serenity::prelude::*;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
 use tracing::{debug, error};
 
+// How long a fetched member name is reused before asking Discord again
+const MEMBER_NAME_TTL: Duration = Duration::from_secs(30 * 60);
+
+// Whether members missing from our cache are fetched from the API (one call per user per TTL)
+static FETCH_UNCACHED_MEMBERS: AtomicBool = AtomicBool::new(true);
+
+/// Turn fetching of uncached guild members on or off
+pub fn set_fetch_uncached_members(enabled: bool) {
+    FETCH_UNCACHED_MEMBERS.store(enabled, Ordering::Relaxed);
+}
+
 // Regular expression for extracting gateway usernames from bot messages
 lazy_static! {
     // Match patterns like "[irc] <username>" in the message content
@@ -12,6 +28,93 @@ lazy_static! {
 
     // Match patterns like "<username>" in the author name
     static ref AUTHOR_USERNAME_REGEX: Regex = Regex::new(r"<([^>]+)>").unwrap();
+
+    static ref MEMBER_NAMES: MemberNameCache = MemberNameCache::new(MEMBER_NAME_TTL);
+}
+
+/// Best names of guild members we've looked up, so nicknames (and the pronouns in them)
+/// don't cost an API call on every message. The bot doesn't request the privileged
+/// GUILD_MEMBERS intent, so Discord never sends us a member list to fill this from.
+pub struct MemberNameCache {
+    ttl: Duration,
+    names: std::sync::Mutex<HashMap<(GuildId, UserId), (String, Instant)>>,
+}
+
+impl MemberNameCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            names: std::sync::Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn cached(&self, guild_id: GuildId, user_id: UserId, now: Instant) -> Option<String> {
+        let names = self.names.lock().unwrap();
+        names
+            .get(&(guild_id, user_id))
+            .filter(|(_, fetched_at)| now.duration_since(*fetched_at) < self.ttl)
+            .map(|(name, _)| name.clone())
+    }
+
+    /// The cached name, or the result of `fetch` on a miss (stored for next time).
+    /// Failed fetches aren't cached, so they're retried on the next message.
+    pub async fn get_or_fetch<F, Fut, E>(
+        &self,
+        guild_id: GuildId,
+        user_id: UserId,
+        fetch: F,
+    ) -> Result<String, E>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<String, E>>,
+    {
+        if let Some(name) = self.cached(guild_id, user_id, Instant::now()) {
+            return Ok(name);
+        }
+
+        let name = fetch().await?;
+        self.names
+            .lock()
+            .unwrap()
+            .insert((guild_id, user_id), (name.clone(), Instant::now()));
+        Ok(name)
+    }
+}
+
+// Nickname, then global name, then username
+fn member_display_name(member: &Member) -> String {
+    member
+        .nick
+        .clone()
+        .or_else(|| member.user.global_name.clone())
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| member.user.name.clone())
+}
+
+// Look up a member's best name through the cache, fetching it from the API on a miss.
+// Returns None when fetching is turned off and we haven't seen the member.
+async fn member_name(ctx: &Context, guild_id: GuildId, user_id: UserId) -> Option<String> {
+    if !FETCH_UNCACHED_MEMBERS.load(Ordering::Relaxed) {
+        return MEMBER_NAMES.cached(guild_id, user_id, Instant::now());
+    }
+
+    match MEMBER_NAMES
+        .get_or_fetch(guild_id, user_id, || async {
+            let member = guild_id.member(&ctx.http, user_id).await?;
+            debug!("Fetched member data for {} in guild {}", user_id, guild_id);
+            Ok::<_, serenity::Error>(member_display_name(&member))
+        })
+        .await
+    {
+        Ok(name) => Some(name),
+        Err(e) => {
+            error!(
+                "Failed to get member data for {} in guild {}: {:?}",
+                user_id, guild_id, e
+            );
+            None
+        }
+    }
 }
 
 // Helper function to check if a message is from a gateway bot and extract the real username
@@ -91,21 +194,15 @@ pub async fn get_best_display_name(ctx: &Context, msg: &Message) -> String {
 
     // Prioritize server nickname over global name over username
     if let Some(guild_id) = msg.guild_id {
-        // Get member data which includes the nickname
-        match guild_id.member(&ctx.http, user_id).await {
-            Ok(member) => {
-                // Use nickname if available, otherwise fall back to global name or username
-                if let Some(nick) = &member.nick {
-                    debug!("Using server nickname for {}: {}", user_id, nick);
-                    return nick.clone();
-                }
-            }
-            Err(e) => {
-                error!(
-                    "Failed to get member data for {} in guild {}: {:?}",
-                    user_id, guild_id, e
-                );
-            }
+        // Messages usually carry the author's nickname, which saves an API call
+        if let Some(nick) = msg.member.as_ref().and_then(|member| member.nick.as_ref()) {
+            debug!("Using server nickname for {}: {}", user_id, nick);
+            return nick.clone();
+        }
+
+        if let Some(name) = member_name(ctx, guild_id, user_id).await {
+            debug!("Using member name for {}: {}", user_id, name);
+            return name;
         }
     }
 
@@ -128,65 +225,35 @@ pub async fn get_best_display_name_with_guild(
     user_id: UserId,
     guild_id: GuildId,
 ) -> String {
-    // Get member data which includes the nickname
-    match guild_id.member(&ctx.http, user_id).await {
-        Ok(member) => {
-            // Use nickname if available
-            if let Some(nick) = &member.nick {
-                debug!(
-                    "Using server nickname for {} in guild {}: {}",
-                    user_id, guild_id, nick
-                );
-                return nick.clone();
-            }
+    if let Some(name) = member_name(ctx, guild_id, user_id).await {
+        debug!(
+            "Using member name for {} in guild {}: {}",
+            user_id, guild_id, name
+        );
+        return name;
+    }
 
-            // Fall back to global name if available
-            if let Some(global_name) = &member.user.global_name {
+    // Try to get user data directly
+    match ctx.http.get_user(user_id).await {
+        Ok(user) => {
+            // Try global name first
+            if let Some(global_name) = &user.global_name {
                 if !global_name.is_empty() {
-                    debug!(
-                        "Using global name for {} in guild {}: {}",
-                        user_id, guild_id, global_name
-                    );
+                    debug!("Using global name for {}: {}", user_id, global_name);
                     return global_name.clone();
                 }
             }
 
-            // Last resort: use username
-            debug!(
-                "Using username for {} in guild {}: {}",
-                user_id, guild_id, member.user.name
-            );
-            member.user.name
+            // Fall back to username
+            debug!("Using username for {}: {}", user_id, user.name);
+            user.name
         }
         Err(e) => {
-            error!(
-                "Failed to get member data for {} in guild {}: {:?}",
-                user_id, guild_id, e
-            );
+            error!("Failed to get user data for {}: {:?}", user_id, e);
 
-            // Try to get user data directly
-            match ctx.http.get_user(user_id).await {
-                Ok(user) => {
-                    // Try global name first
-                    if let Some(global_name) = &user.global_name {
-                        if !global_name.is_empty() {
-                            debug!("Using global name for {}: {}", user_id, global_name);
-                            return global_name.clone();
-                        }
-                    }
-
-                    // Fall back to username
-                    debug!("Using username for {}: {}", user_id, user.name);
-                    user.name
-                }
-                Err(e) => {
-                    error!("Failed to get user data for {}: {:?}", user_id, e);
-
-                    // Instead of returning just the user ID, use a more user-friendly fallback
-                    let user_id_short = user_id.to_string().chars().take(4).collect::<String>();
-                    format!("User-{user_id_short}")
-                }
-            }
+            // Instead of returning just the user ID, use a more user-friendly fallback
+            let user_id_short = user_id.to_string().chars().take(4).collect::<String>();
+            format!("User-{user_id_short}")
         }
     }
 }
@@ -212,19 +279,57 @@ pub fn clean_display_name(name: &str) -> String {
     clean_name
 }
 
-// Extract pronouns from a display name
-pub fn extract_pronouns(name: &str) -> Option<String> {
-    // Look for pronouns in parentheses at the end of the name
-    let pronouns_regex = Regex::new(r"\s*\(([^)]+)\)\s*$").unwrap();
-    if let Some(captures) = pronouns_regex.captures(name) {
-        if let Some(pronouns) = captures.get(1) {
-            return Some(pronouns.as_str().to_string());
-        }
-    }
-    None
-}
-
 // Check if a string looks like a user ID (all digits)
 pub fn is_user_id(s: &str) -> bool {
     s.chars().all(|c| c.is_ascii_digit())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+
+    #[tokio::test]
+    async fn test_member_name_fetched_once_on_cache_miss() {
+        let cache = MemberNameCache::new(Duration::from_secs(60));
+        let guild = GuildId::new(1);
+        let user = UserId::new(2);
+        let fetches = AtomicUsize::new(0);
+
+        // Stand-in for the API: counts calls and returns a nickname with pronouns
+        let fetch = || async {
+            fetches.fetch_add(1, Ordering::SeqCst);
+            Ok::<_, String>("Crow (he/him)".to_string())
+        };
+
+        assert_eq!(
+            cache.get_or_fetch(guild, user, fetch).await.unwrap(),
+            "Crow (he/him)"
+        );
+        assert_eq!(
+            cache.get_or_fetch(guild, user, fetch).await.unwrap(),
+            "Crow (he/him)"
+        );
+        assert_eq!(fetches.load(Ordering::SeqCst), 1);
+        assert_eq!(
+            crate::utils::extract_pronouns(&cache.cached(guild, user, Instant::now()).unwrap()),
+            Some("he/him".to_string())
+        );
+
+        // Failed fetches aren't cached
+        let other = UserId::new(3);
+        let failed = cache
+            .get_or_fetch(guild, other, || async {
+                Err::<String, _>("unknown member")
+            })
+            .await;
+        assert!(failed.is_err());
+        assert_eq!(cache.cached(guild, other, Instant::now()), None);
+
+        // Stale entries are fetched again
+        assert_eq!(
+            cache.cached(guild, user, Instant::now() + Duration::from_secs(61)),
+            None
+        );
+    }
+}
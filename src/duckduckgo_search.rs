@@ -1,8 +1,12 @@
+use crate::http_client;
 use anyhow::Result;
 use scraper::{Html, Selector};
 use std::time::Duration;
 use tracing::{error, info};
 
+// DuckDuckGo blocks requests that don't look like they come from a browser
+const BROWSER_USER_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/121.0.0.0 Safari/537.36";
+
 pub struct DuckDuckGoSearchClient {}
 
 pub struct SearchResult {
@@ -18,12 +22,6 @@ impl DuckDuckGoSearchClient {
 
     // Helper method to fetch raw HTML for debugging
     pub async fn fetch_raw_html(&self, query: &str) -> Result<String> {
-        // Create the client with custom user agent to avoid being blocked
-        let client = reqwest::Client::builder()
-            .user_agent("Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/121.0.0.0 Safari/537.36")
-            .timeout(Duration::from_secs(10))
-            .build()?;
-
         // Build the URL with query parameters
         let encoded_query = urlencoding::encode(query);
         let url = format!("https://duckduckgo.com/html/?q={encoded_query}");
@@ -31,7 +29,12 @@ impl DuckDuckGoSearchClient {
         info!("Fetching search results from: {}", url);
 
         // Make the request
-        let response = client.get(&url).send().await?;
+        let response = http_client::shared()
+            .get(&url)
+            .header(reqwest::header::USER_AGENT, BROWSER_USER_AGENT)
+            .timeout(Duration::from_secs(10))
+            .send()
+            .await?;
 
         // Check if the request was successful
         if !response.status().is_success() {
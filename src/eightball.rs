@@ -0,0 +1,200 @@
+use crate::gemini_api::GeminiClient;
+use crate::unknown_command::is_prompt_echo;
+use anyhow::Result;
+use rand::seq::IndexedRandom;
+use serenity::all::Http;
+use serenity::model::channel::Message;
+use tracing::error;
+
+// The twenty answers on the original Magic 8-Ball's die
+const CLASSIC_ANSWERS: &[&str] = &[
+    "It is certain.",
+    "It is decidedly so.",
+    "Without a doubt.",
+    "Yes, definitely.",
+    "You may rely on it.",
+    "As I see it, yes.",
+    "Most likely.",
+    "Outlook good.",
+    "Yes.",
+    "Signs point to yes.",
+    "Reply hazy, try again.",
+    "Ask again later.",
+    "Better not tell you now.",
+    "Cannot predict now.",
+    "Concentrate and ask again.",
+    "Don't count on it.",
+    "My reply is no.",
+    "My sources say no.",
+    "Outlook not so good.",
+    "Very doubtful.",
+];
+
+// Words a yes/no (or any) question tends to start with
+const QUESTION_WORDS: &[&str] = &[
+    "am",
+    "are",
+    "is",
+    "was",
+    "were",
+    "will",
+    "would",
+    "shall",
+    "should",
+    "can",
+    "could",
+    "may",
+    "might",
+    "must",
+    "do",
+    "does",
+    "did",
+    "has",
+    "have",
+    "had",
+    "isn't",
+    "aren't",
+    "won't",
+    "wouldn't",
+    "shouldn't",
+    "can't",
+    "couldn't",
+    "don't",
+    "doesn't",
+    "didn't",
+    "who",
+    "what",
+    "when",
+    "where",
+    "why",
+    "how",
+    "which",
+];
+
+// Longest AI answer we'll post
+const ANSWER_MAX_CHARS: usize = 200;
+
+/// Whether some text reads like a question: it ends with a `?` or starts with a word
+/// like "will" or "should"
+pub fn looks_like_question(text: &str) -> bool {
+    let text = text.trim();
+    if text.ends_with('?') {
+        return true;
+    }
+    text.split_whitespace().next().is_some_and(|first| {
+        let first = first.trim_end_matches([',', ':']).to_lowercase();
+        QUESTION_WORDS.contains(&first.as_str())
+    })
+}
+
+/// One of the classic 8-ball answers, at random
+pub fn classic_answer() -> &'static str {
+    CLASSIC_ANSWERS
+        .choose(&mut rand::rng())
+        .copied()
+        .unwrap_or("Ask again later.")
+}
+
+// Clean up a model response into a single short answer, or None if unusable
+fn sanitize_answer(response: &str) -> Option<String> {
+    let line = response
+        .lines()
+        .map(|l| l.trim())
+        .find(|l| !l.is_empty())?
+        .trim_matches(['"', '\'', '`'])
+        .trim();
+
+    if line.is_empty() || is_prompt_echo(line) || line.chars().count() > ANSWER_MAX_CHARS {
+        return None;
+    }
+
+    // Never let an answer ping anyone
+    Some(
+        line.replace("@everyone", "everyone")
+            .replace("@here", "here")
+            .replace("<@", "<"),
+    )
+}
+
+// This function will be called from main.rs to handle the !8ball command. With a Gemini
+// client the answer is written in character, falling back to a classic one.
+pub async fn handle_eightball_command(
+    http: &Http,
+    msg: &Message,
+    question: &str,
+    gemini_client: Option<&GeminiClient>,
+) -> Result<()> {
+    if !looks_like_question(question) {
+        msg.reply(
+            http,
+            "The 8-ball only answers questions. Try `!8ball Will it rain tomorrow?`",
+        )
+        .await?;
+        return Ok(());
+    }
+
+    let mut answer = None;
+    if let Some(gemini_client) = gemini_client {
+        let _ = msg.channel_id.broadcast_typing(http).await;
+        let prompt = gemini_client
+            .prompt_templates()
+            .format_eightball(question.trim());
+        match gemini_client.generate_content(&prompt).await {
+            Ok(response) => {
+                answer = sanitize_answer(&response);
+                if answer.is_none() {
+                    error!("8-ball returned an unusable response: {}", response);
+                }
+            }
+            Err(e) => error!("Error generating 8-ball answer: {:?}", e),
+        }
+    }
+
+    let answer = answer.unwrap_or_else(|| classic_answer().to_string());
+    msg.reply(http, format!("🎱 {answer}")).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_looks_like_question() {
+        assert!(looks_like_question("Will it rain tomorrow?"));
+        assert!(looks_like_question("pizza tonight?"));
+        assert!(looks_like_question("  should I quit my job  "));
+        assert!(looks_like_question("Is this the real life"));
+        assert!(looks_like_question("Won't somebody think of the children"));
+        assert!(looks_like_question("ok, but why?"));
+
+        assert!(!looks_like_question(""));
+        assert!(!looks_like_question("   "));
+        assert!(!looks_like_question("I like turtles"));
+        assert!(!looks_like_question("Islands are nice"));
+        assert!(!looks_like_question("tell me my future!"));
+    }
+
+    #[test]
+    fn test_classic_answer() {
+        for _ in 0..100 {
+            assert!(CLASSIC_ANSWERS.contains(&classic_answer()));
+        }
+        assert_eq!(CLASSIC_ANSWERS.len(), 20);
+    }
+
+    #[test]
+    fn test_sanitize_answer() {
+        assert_eq!(
+            sanitize_answer("\"Signs point to nope.\"\nextra"),
+            Some("Signs point to nope.".to_string())
+        );
+        assert_eq!(
+            sanitize_answer("Ask @everyone, <@123>"),
+            Some("Ask everyone, <123>".to_string())
+        );
+        assert_eq!(sanitize_answer(" \n "), None);
+        assert_eq!(sanitize_answer(&"no ".repeat(100)), None);
+        assert_eq!(sanitize_answer("You are {bot_name}, a Discord bot"), None);
+    }
+}
@@ -1,15 +1,14 @@
-use crate::db_utils;
 use crate::duckduckgo_search::DuckDuckGoSearchClient;
-use crate::gemini_api::GeminiClient;
+use crate::gemini_api::{GeminiClient, GeminiTask};
+use crate::message_store::MessageStore;
 use crate::multi_response_generator::MultiResponseGenerator;
 use crate::news_verification;
 use anyhow::Result;
 use serenity::http::Http;
 use serenity::model::channel::Message;
-use serenity::model::id::ChannelId;
+use serenity::model::id::{ChannelId, MessageId};
 use serenity::prelude::*;
 use std::sync::Arc;
-use tokio_rusqlite::Connection;
 use tracing::{error, info};
 
 /// Extract topic from response in "TOPIC: description ENDTOPIC" format
@@ -91,17 +90,17 @@ pub async fn handle_fact_interjection(
     msg: &Message,
     gemini_client: &GeminiClient,
     _multi_response_generator: &Option<MultiResponseGenerator>,
-    message_db: &Option<Arc<tokio::sync::Mutex<Connection>>>,
+    message_store: &Option<Arc<dyn MessageStore>>,
     bot_name: &str,
     gemini_context_messages: usize,
-) -> Result<bool> {
-    let context_messages = if let Some(db) = message_db {
-        match db_utils::get_recent_messages_with_reply_context(
-            db.clone(),
-            gemini_context_messages,
-            Some(msg.channel_id.to_string().as_str()),
-        )
-        .await
+) -> Result<Option<MessageId>> {
+    let context_messages = if let Some(db) = message_store {
+        match db
+            .get_recent(
+                gemini_context_messages,
+                Some(msg.channel_id.to_string().as_str()),
+            )
+            .await
         {
             Ok(messages) => messages,
             Err(e) => {
@@ -133,17 +132,14 @@ pub async fn handle_spontaneous_fact_interjection(
     channel_id: ChannelId,
     gemini_client: &GeminiClient,
     _multi_response_generator: &Option<MultiResponseGenerator>,
-    message_db: &Option<Arc<tokio::sync::Mutex<Connection>>>,
+    message_store: &Option<Arc<dyn MessageStore>>,
     bot_name: &str,
     gemini_context_messages: usize,
-) -> Result<bool> {
-    let context_messages = if let Some(db) = message_db {
-        match db_utils::get_recent_messages_with_reply_context(
-            db.clone(),
-            gemini_context_messages,
-            Some(&channel_id.to_string()),
-        )
-        .await
+) -> Result<Option<MessageId>> {
+    let context_messages = if let Some(db) = message_store {
+        match db
+            .get_recent(gemini_context_messages, Some(&channel_id.to_string()))
+            .await
         {
             Ok(messages) => messages,
             Err(e) => {
@@ -170,7 +166,11 @@ pub async fn handle_spontaneous_fact_interjection(
 }
 
 /// Send a fact response with typing delay
-async fn send_fact_response(http: &Http, channel_id: ChannelId, response: &str) {
+async fn send_fact_response(
+    http: &Http,
+    channel_id: ChannelId,
+    response: &str,
+) -> Option<MessageId> {
     if let Err(e) = channel_id.broadcast_typing(http).await {
         error!(
             "Failed to send typing indicator for fact interjection: {:?}",
@@ -182,10 +182,15 @@ async fn send_fact_response(http: &Http, channel_id: ChannelId, response: &str)
     let delay_secs = (words as f32 * 0.2).clamp(2.0, 5.0) as u64;
     tokio::time::sleep(std::time::Duration::from_secs(delay_secs)).await;
 
-    if let Err(e) = channel_id.say(http, response).await {
-        error!("Error sending fact interjection: {:?}", e);
-    } else {
-        info!("Fact interjection sent: {}", response);
+    match crate::utils::send_without_pings(http, channel_id, response).await {
+        Ok(sent) => {
+            info!("Fact interjection sent: {}", response);
+            Some(sent.id)
+        }
+        Err(e) => {
+            error!("Error sending fact interjection: {:?}", e);
+            None
+        }
     }
 }
 
@@ -197,7 +202,7 @@ async fn handle_fact_interjection_common(
     _multi_response_generator: &Option<MultiResponseGenerator>,
     context_messages: &[(String, String, Option<String>, String, Option<String>)],
     _bot_name: &str,
-) -> Result<bool> {
+) -> Result<Option<MessageId>> {
     // Format context for the prompt
     let context_text = if !context_messages.is_empty() {
         let mut chronological_messages = context_messages.to_owned();
@@ -230,7 +235,10 @@ async fn handle_fact_interjection_common(
 
     // fact_prompt is already fully formed (personality + context baked in).
     // Always use generate_content directly to avoid re-wrapping with personality.
-    let response_result = match gemini_client.generate_content(&fact_prompt).await {
+    let response_result = match gemini_client
+        .generate_content_for(&fact_prompt, GeminiTask::Interjection)
+        .await
+    {
         Ok(response) => {
             let trimmed = response.trim().to_string();
             if trimmed.to_lowercase() == "pass" {
@@ -250,7 +258,7 @@ async fn handle_fact_interjection_common(
                 || response.contains("Guidelines:")
             {
                 error!("Fact interjection: API returned prompt template instead of response");
-                return Ok(false);
+                return Ok(None);
             }
 
             // Extract topic and use search-first approach
@@ -268,7 +276,7 @@ async fn handle_fact_interjection_common(
                         "Fact interjection skipped: response is incomplete after stripping TOPIC: '{}'",
                         display_response
                     );
-                    return Ok(false);
+                    return Ok(None);
                 }
 
                 if let Some(url) = try_search_for_article(&topic).await {
@@ -284,30 +292,29 @@ async fn handle_fact_interjection_common(
                         Ok(true) => {
                             info!("Fact search result validated: {}", url);
                             let final_response = format!("{} Source: {}", display_response, url);
-                            send_fact_response(http, channel_id, &final_response).await;
+                            send_fact_response(http, channel_id, &final_response).await
                         }
                         _ => {
                             info!("Fact search result failed validation - sending without URL");
-                            send_fact_response(http, channel_id, &display_response).await;
+                            send_fact_response(http, channel_id, &display_response).await
                         }
                     }
                 } else {
                     info!("No search results for fact topic - sending without URL");
-                    send_fact_response(http, channel_id, &display_response).await;
+                    send_fact_response(http, channel_id, &display_response).await
                 }
             } else {
                 info!("No TOPIC found in fact response - sending as-is");
-                send_fact_response(http, channel_id, &response).await;
+                send_fact_response(http, channel_id, &response).await
             }
-            true
         }
         Ok(None) => {
             info!("Fact interjection evaluation: decided to PASS - no response sent");
-            false
+            None
         }
         Err(e) => {
             error!("Error generating fact interjection: {:?}", e);
-            false
+            None
         }
     };
 
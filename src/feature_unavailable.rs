@@ -0,0 +1,106 @@
+use std::collections::HashMap;
+
+/// Why a feature can't be used right now
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FeatureUnavailable {
+    /// No Gemini API key configured
+    Ai,
+    /// SQLite message history database failed to open
+    MessageHistory,
+    /// MySQL quote/slogan database not configured
+    QuoteDatabase,
+    /// DuckDuckGo search disabled in config
+    Search,
+    /// No image generation API key configured
+    ImageGeneration,
+}
+
+impl FeatureUnavailable {
+    #[cfg(test)]
+    pub const ALL: [FeatureUnavailable; 5] = [
+        FeatureUnavailable::Ai,
+        FeatureUnavailable::MessageHistory,
+        FeatureUnavailable::QuoteDatabase,
+        FeatureUnavailable::Search,
+        FeatureUnavailable::ImageGeneration,
+    ];
+
+    // Human-readable reason used to fill in the {reason} placeholder
+    fn default_reason(self) -> &'static str {
+        match self {
+            FeatureUnavailable::Ai => "the AI service isn't configured",
+            FeatureUnavailable::MessageHistory => "the message history database isn't available",
+            FeatureUnavailable::QuoteDatabase => "the quote database isn't configured",
+            FeatureUnavailable::Search => "search is turned off",
+            FeatureUnavailable::ImageGeneration => "image generation isn't configured",
+        }
+    }
+}
+
+/// Consistent "this feature isn't available" replies, with operator overrides from config
+#[derive(Debug, Clone)]
+pub struct FeatureUnavailableMessages {
+    template: String,
+    overrides: HashMap<FeatureUnavailable, String>,
+}
+
+impl Default for FeatureUnavailableMessages {
+    fn default() -> Self {
+        Self {
+            template: "Sorry, that isn't available right now because {reason}.".to_string(),
+            overrides: HashMap::new(),
+        }
+    }
+}
+
+impl FeatureUnavailableMessages {
+    /// Replace the shared template; `{reason}` is substituted with the reason text
+    pub fn set_template(&mut self, template: &str) {
+        self.template = template.to_string();
+    }
+
+    /// Use a fixed message for one reason instead of the template
+    pub fn set_message(&mut self, reason: FeatureUnavailable, message: &str) {
+        self.overrides.insert(reason, message.to_string());
+    }
+
+    /// Get the message to show users for this reason
+    pub fn message(&self, reason: FeatureUnavailable) -> String {
+        match self.overrides.get(&reason) {
+            Some(message) => message.clone(),
+            None => self.template.replace("{reason}", reason.default_reason()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_messages_mention_reason() {
+        let messages = FeatureUnavailableMessages::default();
+        for reason in FeatureUnavailable::ALL {
+            let message = messages.message(reason);
+            assert!(message.contains(reason.default_reason()));
+            assert!(!message.contains("{reason}"));
+        }
+    }
+
+    #[test]
+    fn test_configured_messages_per_reason() {
+        let mut messages = FeatureUnavailableMessages::default();
+        messages.set_template("Nope: {reason}");
+        assert_eq!(
+            messages.message(FeatureUnavailable::Search),
+            "Nope: search is turned off"
+        );
+
+        for (i, reason) in FeatureUnavailable::ALL.into_iter().enumerate() {
+            messages.set_message(reason, &format!("custom message {i}"));
+        }
+        for (i, reason) in FeatureUnavailable::ALL.into_iter().enumerate() {
+            assert_eq!(messages.message(reason), format!("custom message {i}"));
+        }
+    }
+}
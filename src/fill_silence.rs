@@ -1,22 +1,194 @@
+use chrono::{DateTime, FixedOffset, Local, Timelike, Utc};
 use rand::RngExt;
+use serde::Deserialize;
 use serenity::model::id::{ChannelId, UserId};
 use std::collections::HashMap;
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use tokio::sync::RwLock;
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
+
+/// Shortest wait between spontaneous interjection checks, in seconds
+pub const DEFAULT_CHECK_MIN_SECS: u64 = 30;
+
+/// Longest wait between spontaneous interjection checks, in seconds
+pub const DEFAULT_CHECK_MAX_SECS: u64 = 90;
+
+/// How long to wait before the next spontaneous interjection check: anywhere from the
+/// shortest to the longest wait, so the bot doesn't check in like clockwork
+pub fn next_check_delay(interval: (Duration, Duration), rng: &mut impl RngExt) -> Duration {
+    let (min, max) = interval;
+    rng.random_range(min..=max)
+}
+
+/// Hours of the day when spontaneous interjections stay quiet, from `start` up to (but
+/// not including) `end`. The window wraps past midnight when `end` is earlier than
+/// `start`, e.g. 22 to 7.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct QuietHours {
+    pub start: u32,
+    pub end: u32,
+    /// Timezone the hours are in (None = system local)
+    pub utc_offset: Option<FixedOffset>,
+}
+
+impl QuietHours {
+    /// Whether an hour of the day (0-23) falls in the window
+    pub fn contains(&self, hour: u32) -> bool {
+        if self.start <= self.end {
+            (self.start..self.end).contains(&hour)
+        } else {
+            hour >= self.start || hour < self.end
+        }
+    }
+
+    /// Whether `now` falls in the window, in the configured timezone
+    pub fn is_quiet_at(&self, now: DateTime<Utc>) -> bool {
+        let hour = match self.utc_offset {
+            Some(offset) => now.with_timezone(&offset).hour(),
+            None => now.with_timezone(&Local).hour(),
+        };
+        self.contains(hour)
+    }
+}
+
+/// Parse a quiet-hours boundary: an hour of the day like "22", or "22:00"
+pub fn parse_quiet_hour(value: &str) -> Option<u32> {
+    let value = value.trim();
+    let hour = match value.split_once(':') {
+        Some((hour, "00")) => hour,
+        Some(_) => return None,
+        None => value,
+    };
+    hour.parse::<u32>().ok().filter(|hour| *hour < 24)
+}
+
+/// One `[[fill_silence_channels]]` entry in CrowConfig.toml, as written
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct FillSilenceChannelConfig {
+    pub channel_id: Option<String>,
+    pub channel_name: Option<String>,
+    pub enabled: Option<String>,
+    pub start_hours: Option<String>,
+    pub max_hours: Option<String>,
+}
+
+/// How fill silence behaves in a channel
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SilenceSettings {
+    pub enabled: bool,
+    /// Start increasing probabilities after this many hours of silence
+    pub start_hours: f64,
+    /// Reach 100% probability after this many hours of silence
+    pub max_hours: f64,
+}
+
+/// Which channel a `[[fill_silence_channels]]` entry is for
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SilenceChannel {
+    Id(ChannelId),
+    /// Resolved to IDs once the bot has connected to Discord
+    Name(String),
+}
+
+// Check one configured channel, filling in anything it leaves out from the defaults
+fn validate_channel(
+    entry: &FillSilenceChannelConfig,
+    defaults: SilenceSettings,
+) -> Result<(SilenceChannel, SilenceSettings), String> {
+    let channel = match (
+        entry.channel_id.as_deref().map(str::trim),
+        entry.channel_name.as_deref().map(str::trim),
+    ) {
+        (Some(id), _) => id
+            .parse::<u64>()
+            .ok()
+            .filter(|id| *id != 0)
+            .map(|id| SilenceChannel::Id(ChannelId::new(id)))
+            .ok_or(format!("channel_id \"{id}\" is not a channel ID"))?,
+        (None, Some(name)) if !name.is_empty() => {
+            SilenceChannel::Name(name.trim_start_matches('#').to_string())
+        }
+        _ => return Err("missing channel_id or channel_name".to_string()),
+    };
+
+    let enabled = match entry.enabled.as_deref().map(|e| e.trim().to_lowercase()) {
+        None => defaults.enabled,
+        Some(enabled) => match enabled.as_str() {
+            "false" | "0" | "no" | "disabled" | "off" => false,
+            "true" | "1" | "yes" | "enabled" | "on" => true,
+            _ => return Err(format!("enabled \"{enabled}\" is not true or false")),
+        },
+    };
+
+    let hours = |value: &Option<String>, key: &str, default: f64| match value.as_deref() {
+        None => Ok(default),
+        Some(hours) => hours
+            .trim()
+            .parse::<f64>()
+            .ok()
+            .filter(|hours| hours.is_finite() && *hours >= 0.0)
+            .ok_or(format!("{key} \"{hours}\" is not a number of hours")),
+    };
+    let start_hours = hours(&entry.start_hours, "start_hours", defaults.start_hours)?;
+    let max_hours = hours(&entry.max_hours, "max_hours", defaults.max_hours)?;
+    if max_hours < start_hours {
+        return Err(format!(
+            "max_hours ({max_hours}) is less than start_hours ({start_hours})"
+        ));
+    }
+
+    Ok((
+        channel,
+        SilenceSettings {
+            enabled,
+            start_hours,
+            max_hours,
+        },
+    ))
+}
+
+/// Check the configured per-channel fill silence settings, logging and skipping any that
+/// are invalid. Settings an entry leaves out come from the global defaults.
+pub fn parse_fill_silence_channels(
+    entries: &[FillSilenceChannelConfig],
+    defaults: SilenceSettings,
+) -> Vec<(SilenceChannel, SilenceSettings)> {
+    let mut channels = Vec::new();
+
+    for (i, entry) in entries.iter().enumerate() {
+        match validate_channel(entry, defaults) {
+            Ok((channel, settings)) => {
+                info!(
+                    "Fill silence for {:?}: {}, start after {} hours, 100% at {} hours",
+                    channel,
+                    if settings.enabled {
+                        "enabled"
+                    } else {
+                        "disabled"
+                    },
+                    settings.start_hours,
+                    settings.max_hours
+                );
+                channels.push((channel, settings));
+            }
+            Err(problem) => {
+                warn!("Skipping fill silence channel {}: {}", i + 1, problem);
+            }
+        }
+    }
+
+    channels
+}
 
 /// Manages the "fill silence" feature, which increases interjection probabilities
 /// after periods of inactivity in a channel.
 pub struct FillSilenceManager {
-    /// Whether the fill silence feature is enabled
-    enabled: bool,
-
-    /// Start increasing probabilities after this many hours of silence
-    start_hours: f64,
+    /// Settings for channels without their own
+    defaults: SilenceSettings,
 
-    /// Reach 100% probability after this many hours of silence
-    max_hours: f64,
+    /// Per-channel settings from [[fill_silence_channels]]
+    channel_settings: HashMap<ChannelId, SilenceSettings>,
 
     /// Last activity time for each channel, keyed by channel ID
     last_activity: Arc<RwLock<HashMap<ChannelId, (Instant, UserId)>>>,
@@ -32,26 +204,56 @@ pub struct FillSilenceManager {
 
     /// Minimum number of messages from others before allowing interjections
     minimum_messages: usize,
+
+    /// When spontaneous interjections are held off (None = any hour)
+    quiet_hours: Option<QuietHours>,
 }
 
 impl FillSilenceManager {
     /// Create a new FillSilenceManager
     pub fn new(enabled: bool, start_hours: f64, max_hours: f64, minimum_messages: usize) -> Self {
         Self {
-            enabled,
-            start_hours,
-            max_hours,
+            defaults: SilenceSettings {
+                enabled,
+                start_hours,
+                max_hours,
+            },
+            channel_settings: HashMap::new(),
             last_activity: Arc::new(RwLock::new(HashMap::new())),
             last_check: Arc::new(RwLock::new(HashMap::new())),
             bot_was_last_speaker: Arc::new(RwLock::new(HashMap::new())),
             messages_since_bot_interjection: Arc::new(RwLock::new(HashMap::new())),
             minimum_messages,
+            quiet_hours: None,
         }
     }
 
+    /// Hold off spontaneous interjections during these hours
+    pub fn with_quiet_hours(mut self, quiet_hours: Option<QuietHours>) -> Self {
+        self.quiet_hours = quiet_hours;
+        self
+    }
+
+    /// Use these settings instead of the defaults in particular channels
+    pub fn with_channel_settings(
+        mut self,
+        channel_settings: HashMap<ChannelId, SilenceSettings>,
+    ) -> Self {
+        self.channel_settings = channel_settings;
+        self
+    }
+
+    /// The settings in effect for a channel: its own, or else the defaults
+    pub fn settings_for(&self, channel_id: ChannelId) -> SilenceSettings {
+        self.channel_settings
+            .get(&channel_id)
+            .copied()
+            .unwrap_or(self.defaults)
+    }
+
     /// Update the last activity time for a channel
     pub async fn update_activity(&self, channel_id: ChannelId, user_id: UserId) {
-        if !self.enabled {
+        if !self.settings_for(channel_id).enabled {
             return;
         }
 
@@ -71,7 +273,7 @@ impl FillSilenceManager {
         user_id: UserId,
         bot_id: UserId,
     ) {
-        if !self.enabled {
+        if !self.settings_for(channel_id).enabled {
             return;
         }
 
@@ -91,12 +293,9 @@ impl FillSilenceManager {
         }
     }
 
-    /// Mark that the bot was the last speaker in a channel
+    /// Mark that the bot was the last speaker in a channel. Tracked even when fill silence
+    /// is off, since reactive interjections also hold off while the bot has the last word.
     pub async fn mark_bot_as_last_speaker(&self, channel_id: ChannelId) {
-        if !self.enabled {
-            return;
-        }
-
         let mut bot_last = self.bot_was_last_speaker.write().await;
         bot_last.insert(channel_id, true);
 
@@ -112,21 +311,24 @@ impl FillSilenceManager {
 
     /// Mark that a user (not the bot) was the last speaker in a channel
     pub async fn mark_user_as_last_speaker(&self, channel_id: ChannelId) {
-        if !self.enabled {
-            return;
-        }
-
         let mut bot_last = self.bot_was_last_speaker.write().await;
         bot_last.insert(channel_id, false);
 
         debug!("Marked user as last speaker in channel {}", channel_id);
     }
 
+    /// Whether the bot spoke last in a channel, with nobody else speaking since
+    pub async fn bot_was_last_speaker(&self, channel_id: ChannelId) -> bool {
+        let bot_last = self.bot_was_last_speaker.read().await;
+        bot_last.get(&channel_id).copied().unwrap_or(false)
+    }
+
     /// Calculate the probability multiplier for a channel based on inactivity time
     /// Returns a multiplier between 1.0 (normal probability) and a value that would
     /// make the probability 100% (after max_hours of inactivity)
     pub async fn get_probability_multiplier(&self, channel_id: ChannelId, bot_id: UserId) -> f64 {
-        if !self.enabled {
+        let settings = self.settings_for(channel_id);
+        if !settings.enabled {
             return 1.0;
         }
 
@@ -151,7 +353,7 @@ impl FillSilenceManager {
         let hours_elapsed = elapsed.as_secs_f64() / 3600.0;
 
         // If less than start_hours have passed, use normal probability
-        if hours_elapsed < self.start_hours {
+        if hours_elapsed < settings.start_hours {
             return 1.0;
         }
 
@@ -166,7 +368,7 @@ impl FillSilenceManager {
         let capped_multiplier = hours_multiplier.min(max_multiplier);
 
         // If we've exceeded max_hours, add an additional boost to ensure high probability
-        let final_multiplier = if hours_elapsed >= self.max_hours {
+        let final_multiplier = if hours_elapsed >= settings.max_hours {
             // Add an extra boost to make very likely (but not 100% guaranteed)
             capped_multiplier * 2.0
         } else {
@@ -184,7 +386,8 @@ impl FillSilenceManager {
     /// Calculate the minimum quality threshold (1-10) for spontaneous interjections.
     /// Starts at 10 (very high bar) at start_hours and linearly decreases to 5 at max_hours.
     pub async fn get_quality_threshold(&self, channel_id: ChannelId, bot_id: UserId) -> u8 {
-        if !self.enabled {
+        let settings = self.settings_for(channel_id);
+        if !settings.enabled {
             return 10;
         }
 
@@ -199,12 +402,13 @@ impl FillSilenceManager {
             None => return 10,
         };
 
-        if hours_elapsed < self.start_hours {
+        if hours_elapsed < settings.start_hours {
             return 10;
         }
 
         // Linear interpolation: 10 at start_hours, 5 at max_hours
-        let progress = ((hours_elapsed - self.start_hours) / (self.max_hours - self.start_hours))
+        let progress = ((hours_elapsed - settings.start_hours)
+            / (settings.max_hours - settings.start_hours))
             .clamp(0.0, 1.0);
         let threshold = 10.0 - (progress * 5.0);
         threshold.round() as u8
@@ -217,13 +421,24 @@ impl FillSilenceManager {
         channel_id: ChannelId,
         bot_id: UserId,
     ) -> bool {
-        if !self.enabled {
+        if !self.settings_for(channel_id).enabled {
+            return false;
+        }
+
+        // Nobody wants the bot filling the silence at 4am
+        if self
+            .quiet_hours
+            .is_some_and(|quiet| quiet.is_quiet_at(Utc::now()))
+        {
+            debug!(
+                "Quiet hours, skipping spontaneous interjection check for channel {}",
+                channel_id
+            );
             return false;
         }
 
         // Check if the bot was the last speaker
-        let bot_last = self.bot_was_last_speaker.read().await;
-        if bot_last.get(&channel_id).unwrap_or(&false) == &true {
+        if self.bot_was_last_speaker(channel_id).await {
             // Bot was the last speaker, don't make another interjection until someone else speaks
             debug!(
                 "Bot was last speaker in channel {}, skipping spontaneous interjection check",
@@ -293,3 +508,224 @@ impl FillSilenceManager {
         false
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quiet_hours_window() {
+        let quiet = |start, end| QuietHours {
+            start,
+            end,
+            utc_offset: FixedOffset::east_opt(0),
+        };
+
+        // A daytime window
+        let afternoon = quiet(13, 15);
+        assert!(!afternoon.contains(12));
+        assert!(afternoon.contains(13));
+        assert!(afternoon.contains(14));
+        assert!(!afternoon.contains(15));
+
+        // Wrapping past midnight
+        let night = quiet(22, 7);
+        for hour in [22, 23, 0, 3, 6] {
+            assert!(night.contains(hour), "{hour} should be quiet");
+        }
+        for hour in [7, 12, 21] {
+            assert!(!night.contains(hour), "{hour} shouldn't be quiet");
+        }
+
+        // The same start and end is an empty window
+        assert!((0..24).all(|hour| !quiet(5, 5).contains(hour)));
+    }
+
+    #[test]
+    fn test_quiet_hours_timezone() {
+        let night = QuietHours {
+            start: 22,
+            end: 7,
+            utc_offset: FixedOffset::west_opt(5 * 3600),
+        };
+        let at = |time: &str| {
+            DateTime::parse_from_rfc3339(time)
+                .unwrap()
+                .with_timezone(&Utc)
+        };
+
+        // 04:00 UTC is 23:00 the evening before at UTC-5
+        assert!(night.is_quiet_at(at("2024-05-01T04:00:00Z")));
+        // 12:00 UTC is 07:00 there, the end of the window
+        assert!(!night.is_quiet_at(at("2024-05-01T12:00:00Z")));
+        assert!(night.is_quiet_at(at("2024-05-01T11:59:00Z")));
+    }
+
+    #[test]
+    fn test_next_check_delay_within_interval() {
+        let interval = (Duration::from_secs(30), Duration::from_secs(90));
+        let mut rng = rand::rng();
+        for _ in 0..1000 {
+            let delay = next_check_delay(interval, &mut rng);
+            assert!(
+                (interval.0..=interval.1).contains(&delay),
+                "{delay:?} is outside the interval"
+            );
+        }
+
+        // A fixed interval always waits the same time
+        let fixed = (Duration::from_secs(60), Duration::from_secs(60));
+        assert_eq!(next_check_delay(fixed, &mut rng), Duration::from_secs(60));
+    }
+
+    #[test]
+    fn test_parse_quiet_hour() {
+        assert_eq!(parse_quiet_hour("22"), Some(22));
+        assert_eq!(parse_quiet_hour(" 7 "), Some(7));
+        assert_eq!(parse_quiet_hour("07:00"), Some(7));
+        assert_eq!(parse_quiet_hour("0"), Some(0));
+        assert_eq!(parse_quiet_hour("24"), None);
+        assert_eq!(parse_quiet_hour("7:30"), None);
+        assert_eq!(parse_quiet_hour("late"), None);
+    }
+
+    #[test]
+    fn test_parse_fill_silence_channels() {
+        let config: crate::config::Config = toml::from_str(
+            r##"
+discord_token = "token"
+
+[[fill_silence_channels]]
+channel_id = "42"
+enabled = "off"
+
+[[fill_silence_channels]]
+channel_name = "#general"
+start_hours = "4"
+
+[[fill_silence_channels]]
+start_hours = "2"
+
+[[fill_silence_channels]]
+channel_id = "general"
+
+[[fill_silence_channels]]
+channel_id = "7"
+max_hours = "soon"
+
+[[fill_silence_channels]]
+channel_id = "7"
+start_hours = "20"
+"##,
+        )
+        .unwrap();
+        let defaults = SilenceSettings {
+            enabled: true,
+            start_hours: 1.0,
+            max_hours: 12.0,
+        };
+
+        // Entries without a channel, with a bad ID or hours, or starting past max_hours
+        // are skipped; the rest fill in the gaps from the defaults
+        let channels =
+            parse_fill_silence_channels(&config.fill_silence_channels.unwrap(), defaults);
+        assert_eq!(
+            channels,
+            vec![
+                (
+                    SilenceChannel::Id(ChannelId::new(42)),
+                    SilenceSettings {
+                        enabled: false,
+                        ..defaults
+                    }
+                ),
+                (
+                    SilenceChannel::Name("general".to_string()),
+                    SilenceSettings {
+                        start_hours: 4.0,
+                        ..defaults
+                    }
+                ),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_channel_settings_override_defaults() {
+        let busy = ChannelId::new(1);
+        let quiet = ChannelId::new(2);
+        let other = ChannelId::new(3);
+        let busy_settings = SilenceSettings {
+            enabled: true,
+            start_hours: 0.5,
+            max_hours: 2.0,
+        };
+        let manager =
+            FillSilenceManager::new(true, 1.0, 12.0, 0).with_channel_settings(HashMap::from([
+                (busy, busy_settings),
+                (
+                    quiet,
+                    SilenceSettings {
+                        enabled: false,
+                        ..busy_settings
+                    },
+                ),
+            ]));
+
+        assert_eq!(manager.settings_for(busy), busy_settings);
+        assert!(!manager.settings_for(quiet).enabled);
+        // Channels without their own settings use the defaults
+        assert_eq!(
+            manager.settings_for(other),
+            SilenceSettings {
+                enabled: true,
+                start_hours: 1.0,
+                max_hours: 12.0,
+            }
+        );
+
+        // Fill silence is off in the quiet channel, so its activity isn't tracked
+        let user = UserId::new(10);
+        manager.update_activity(quiet, user).await;
+        manager.update_activity(other, user).await;
+        let last_activity = manager.last_activity.read().await;
+        assert!(!last_activity.contains_key(&quiet));
+        assert!(last_activity.contains_key(&other));
+    }
+
+    #[tokio::test]
+    async fn test_quiet_hours_hold_interjections() {
+        let every_hour = QuietHours {
+            start: 0,
+            end: 24,
+            utc_offset: None,
+        };
+        let manager = FillSilenceManager::new(true, 0.0, 0.0, 0).with_quiet_hours(Some(every_hour));
+        assert!(
+            !manager
+                .should_check_spontaneous_interjection(ChannelId::new(1), UserId::new(2))
+                .await
+        );
+    }
+
+    #[tokio::test]
+    async fn test_bot_last_speaker_holds_interjections() {
+        // Tracked even with fill silence turned off
+        let manager = FillSilenceManager::new(false, 1.0, 6.0, 0);
+        let channel = ChannelId::new(1);
+        let other_channel = ChannelId::new(2);
+
+        assert!(!manager.bot_was_last_speaker(channel).await);
+
+        manager.mark_user_as_last_speaker(channel).await;
+        assert!(!manager.bot_was_last_speaker(channel).await);
+
+        // The bot just posted: hold off until a human speaks again
+        manager.mark_bot_as_last_speaker(channel).await;
+        assert!(manager.bot_was_last_speaker(channel).await);
+        assert!(!manager.bot_was_last_speaker(other_channel).await);
+
+        manager.mark_user_as_last_speaker(channel).await;
+        assert!(!manager.bot_was_last_speaker(channel).await);
+    }
+}
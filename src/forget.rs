@@ -0,0 +1,241 @@
+use crate::message_store::MessageStore;
+use anyhow::Result;
+use serenity::all::Http;
+use serenity::model::channel::Message;
+use serenity::model::id::UserId;
+use tracing::info;
+
+// The user ID in a mention like <@123> or <@!123>, or a bare ID
+fn parse_user_id(arg: &str) -> Option<String> {
+    let id = arg
+        .strip_prefix("<@")
+        .and_then(|rest| rest.strip_suffix('>'))
+        .map(|rest| rest.trim_start_matches('!'))
+        .unwrap_or(arg);
+    id.parse::<u64>().ok().map(|id| id.to_string())
+}
+
+fn plural_messages(count: usize) -> String {
+    format!("{count} message{}", if count == 1 { "" } else { "s" })
+}
+
+/// Who a forget command removed, so the bot's in-memory state (recent speakers,
+/// conversation memory) can drop them too
+#[derive(Debug, Default, PartialEq)]
+pub struct Forgotten {
+    /// How many stored messages were deleted
+    pub deleted: usize,
+    /// The user, when we know who they are
+    pub user_id: Option<UserId>,
+    /// Names they posted under
+    pub names: Vec<String>,
+}
+
+// Forget a user: delete their messages and stop storing new ones
+async fn forget(store: &dyn MessageStore, user_id: String) -> Result<Forgotten> {
+    let names = store.known_names(user_id.clone()).await?;
+    let deleted = store
+        .forget_user(user_id.clone())
+        .await
+        .map_err(|e| anyhow::anyhow!("Error forgetting user: {}", e))?;
+    Ok(Forgotten {
+        deleted,
+        user_id: user_id.parse::<u64>().ok().map(UserId::new),
+        names,
+    })
+}
+
+// Forget someone by name: the user who last posted under it, if there is one, and every
+// message posted under the name (which catches people relayed through gateway bots).
+// `excluded_ids` are never forgotten as the user: a gateway bot's ID is on every relayed
+// message, so forgetting it would delete and stop storing everyone it relays.
+async fn forget_by_name(
+    store: &dyn MessageStore,
+    name: String,
+    excluded_ids: &[String],
+) -> Result<Forgotten> {
+    let mut forgotten = match store
+        .find_user_id_by_name(name.clone(), excluded_ids.to_vec())
+        .await?
+    {
+        Some(user_id) => forget(store, user_id).await?,
+        None => Forgotten::default(),
+    };
+    forgotten.deleted += store
+        .delete_messages_by_author(name.clone())
+        .await
+        .map_err(|e| anyhow::anyhow!("Error deleting messages by name: {}", e))?;
+    forgotten.names.push(name);
+    Ok(forgotten)
+}
+
+// This function will be called from main.rs to handle the !forgetme command
+pub async fn handle_forgetme_command(
+    http: &Http,
+    msg: &Message,
+    message_store: &dyn MessageStore,
+) -> Result<Forgotten> {
+    let mut forgotten = forget(message_store, msg.author.id.to_string()).await?;
+    forgotten.names.push(msg.author.name.clone());
+    let deleted = forgotten.deleted;
+    info!(
+        "Forgot {} at their request ({} deleted)",
+        msg.author.name,
+        plural_messages(deleted)
+    );
+
+    msg.reply(
+        http,
+        format!(
+            "Done: I deleted {} of yours and won't store your messages from now on. I'll still reply to you.",
+            plural_messages(deleted)
+        ),
+    )
+    .await?;
+    Ok(forgotten)
+}
+
+// This function will be called from main.rs to handle the admin-only !forget command.
+// Names never resolve to `excluded_ids` (the bot and gateway bots).
+pub async fn handle_forget_command(
+    http: &Http,
+    msg: &Message,
+    args: &[&str],
+    message_store: &dyn MessageStore,
+    excluded_ids: &[String],
+) -> Result<Forgotten> {
+    let target = args.join(" ");
+    let target = target.trim().trim_start_matches('@');
+    if target.is_empty() {
+        msg.reply(http, "Usage: !forget <@user | user ID | name>")
+            .await?;
+        return Ok(Forgotten::default());
+    }
+
+    let forgotten = match parse_user_id(target) {
+        Some(user_id) => forget(message_store, user_id).await?,
+        None => forget_by_name(message_store, target.to_string(), excluded_ids).await?,
+    };
+    let deleted = plural_messages(forgotten.deleted);
+    info!(
+        "{} had {} forgotten ({} deleted)",
+        msg.author.name, target, deleted
+    );
+
+    let reply = match forgotten.user_id {
+        Some(user_id) => format!(
+            "Done: I deleted {deleted} from user {user_id} and won't store their messages from now on."
+        ),
+        None if forgotten.deleted == 0 => format!("I don't know anyone called {target}."),
+        None => format!("Done: I deleted {deleted} posted as {target}."),
+    };
+    msg.reply(http, reply).await?;
+    Ok(forgotten)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db_utils;
+    use crate::message_store::SqliteMessageStore;
+    use serenity::model::id::{ChannelId, MessageId};
+
+    #[tokio::test]
+    async fn test_forgotten_users_messages_are_deleted_and_not_stored() {
+        let conn = db_utils::initialize_database(":memory:").await.unwrap();
+        let store = SqliteMessageStore::new(conn.clone());
+
+        let message = |id: u64, author: u64| {
+            let mut msg = Message::default();
+            msg.id = MessageId::new(id);
+            msg.channel_id = ChannelId::new(1);
+            msg.author.id = UserId::new(author);
+            msg
+        };
+        for (id, author) in [(1, 42), (2, 42), (3, 7)] {
+            let msg = message(id, author);
+            store
+                .save("user", "User", "hello", Some(&msg))
+                .await
+                .unwrap();
+        }
+
+        // Only the forgotten user's messages go
+        let forgotten = forget(&store, "42".to_string()).await.unwrap();
+        assert_eq!(forgotten.deleted, 2);
+        assert_eq!(forgotten.user_id, Some(UserId::new(42)));
+        assert_eq!(forgotten.names, vec!["user", "User"]);
+        let count_by = |author: &'static str| {
+            let conn = conn.clone();
+            async move {
+                conn.lock()
+                    .await
+                    .call(move |conn| {
+                        conn.query_row(
+                            "SELECT COUNT(*) FROM messages WHERE author_id = ?1",
+                            [author],
+                            |row| row.get::<_, i64>(0),
+                        )
+                    })
+                    .await
+                    .unwrap()
+            }
+        };
+        assert_eq!(count_by("42").await, 0);
+        assert_eq!(count_by("7").await, 1);
+
+        // New messages from them aren't stored; everyone else's still are
+        let stored = store
+            .save("user", "User", "hi", Some(&message(4, 42)))
+            .await
+            .unwrap();
+        assert!(!stored);
+        assert_eq!(count_by("42").await, 0);
+        assert!(store
+            .save("user", "User", "hi", Some(&message(5, 7)))
+            .await
+            .unwrap());
+
+        // Forgetting twice is harmless
+        assert_eq!(forget(&store, "42".to_string()).await.unwrap().deleted, 0);
+
+        // By name, the known user is forgotten along with anything else posted as them
+        store.save("Relayed", "", "hi", None).await.unwrap();
+        let forgotten = forget_by_name(&store, "user".to_string(), &[])
+            .await
+            .unwrap();
+        assert_eq!(forgotten.user_id, Some(UserId::new(7)));
+        assert_eq!(forgotten.deleted, 2);
+        let forgotten = forget_by_name(&store, "relayed".to_string(), &[])
+            .await
+            .unwrap();
+        assert_eq!(forgotten.user_id, None);
+        assert_eq!(forgotten.deleted, 1);
+
+        // A gateway-relayed user is stored under the gateway bot's ID. Forgetting them by
+        // name takes only their messages and leaves the gateway's other users stored.
+        let gateway = 99;
+        for (id, name) in [(6, "Rita"), (7, "Sam")] {
+            store
+                .save("bridge", name, "relayed hello", Some(&message(id, gateway)))
+                .await
+                .unwrap();
+        }
+        let forgotten = forget_by_name(&store, "rita".to_string(), &["99".to_string()])
+            .await
+            .unwrap();
+        assert_eq!(forgotten.user_id, None);
+        assert_eq!(forgotten.deleted, 1);
+        assert_eq!(forgotten.names, vec!["rita"]);
+        assert_eq!(count_by("99").await, 1);
+        assert!(store
+            .save("bridge", "Sam", "still stored", Some(&message(8, gateway)))
+            .await
+            .unwrap());
+        assert_eq!(count_by("99").await, 2);
+
+        assert_eq!(parse_user_id("<@!42>"), Some("42".to_string()));
+        assert_eq!(parse_user_id("42"), Some("42".to_string()));
+        assert_eq!(parse_user_id("bob"), None);
+    }
+}
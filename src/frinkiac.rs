@@ -1,21 +1,23 @@
-use crate::gemini_api::GeminiClient;
+use crate::http_client;
+use crate::screenshot::{
+    self, format_episode_time, merge_subtitle_fragments, Frame, Screenshot, ScreenshotProvider,
+    ScreenshotSite, SearchState, TimedSubtitle,
+};
 use crate::text_formatting;
 use anyhow::{anyhow, Result};
-use rand::seq::IndexedRandom;
 use reqwest::Client as HttpClient;
 use serenity::all::Http;
 
-use serenity::builder::CreateMessage;
 use serenity::model::channel::Message;
-use std::time::Duration;
+use std::borrow::Cow;
 use tracing::{error, info};
 
 // API endpoints
 const FRINKIAC_BASE_URL: &str = "https://frinkiac.com/api/search";
 const FRINKIAC_CAPTION_URL: &str = "https://frinkiac.com/api/caption";
 const FRINKIAC_IMAGE_URL: &str = "https://frinkiac.com/img";
-const FRINKIAC_MEME_URL: &str = "https://frinkiac.com/meme";
 const FRINKIAC_RANDOM_URL: &str = "https://frinkiac.com/api/random";
+const FRINKIAC_EPISODE_URL: &str = "https://frinkiac.com/api/episode";
 
 // Common search terms for random screenshots when no query is provided
 const RANDOM_SEARCH_TERMS: &[&str] = &[
@@ -100,142 +102,191 @@ const RANDOM_SEARCH_TERMS: &[&str] = &[
     "i'm your worst nightmare",
 ];
 
-// Result struct for Frinkiac searches
-#[derive(Debug, Clone)]
-pub struct FrinkiacResult {
-    pub _episode: String,
-    pub episode_title: String,
-    pub season: u32,
-    pub episode_number: u32,
-    pub _timestamp: String,
-    pub image_url: String,
-    pub _meme_url: String,
-    pub caption: String,
-    pub start_timestamp: u64,
-    pub end_timestamp: u64,
-    pub subtitles: Vec<TimedSubtitle>,
-    pub gif_url: Option<String>,
+const FRINKIAC_SITE: ScreenshotSite = ScreenshotSite {
+    base_url: Cow::Borrowed("https://frinkiac.com"),
+    gif_font: "akbar",
+    upload_name: Cow::Borrowed("frinkiac"),
+    show: Cow::Borrowed("Simpsons"),
+    no_random_message: Cow::Borrowed("Couldn't find any Simpsons screenshots. D'oh!"),
+    random_error_message: Cow::Borrowed("Error getting Frinkiac screenshot. D'oh!"),
+    search_error_message: Cow::Borrowed("Error searching Frinkiac. D'oh!"),
+    options: true,
+    shut_down_notice: None,
+};
+
+// Frinkiac client struct
+pub struct FrinkiacClient {
+    http_client: HttpClient,
+    search_state: SearchState,
 }
 
 impl FrinkiacClient {
     pub fn new() -> Self {
         info!("Creating Frinkiac client");
 
-        // Create HTTP client with reasonable timeouts
-        let http_client = HttpClient::builder()
-            .timeout(Duration::from_secs(10))
-            .build()
-            .expect("Failed to create HTTP client");
-
         Self {
-            http_client,
-            last_query: std::sync::RwLock::new(None),
-            current_index: std::sync::RwLock::new(0),
+            http_client: http_client::shared(),
+            search_state: SearchState::default(),
         }
     }
 
-    // Get a random screenshot from Frinkiac
-    pub async fn random(&self) -> Result<Option<FrinkiacResult>> {
-        info!("Getting random Frinkiac screenshot");
+    // Get caption and details for a specific frame
+    pub async fn get_caption_for_frame(
+        &self,
+        episode: &str,
+        timestamp: u64,
+    ) -> Result<Option<Screenshot>> {
+        // Get the caption for this frame
+        let caption_url = self.caption_url(&Frame::at(episode, timestamp));
 
-        // Try the direct random API endpoint first
-        match self.get_random_direct().await {
-            Ok(Some(result)) => {
-                info!("Successfully got random screenshot from direct API");
-                return Ok(Some(result));
-            }
-            Ok(None) => {
-                info!("No results from direct random API, trying fallback method");
-            }
-            Err(e) => {
-                info!(
-                    "Error from direct random API: {}, trying fallback method",
-                    e
-                );
+        info!("Fetching caption from URL: {}", caption_url);
+
+        let caption_response = self
+            .http_client
+            .get(&caption_url)
+            .send()
+            .await
+            .map_err(|e| anyhow!("Failed to get caption from Frinkiac: {}", e))?;
+
+        let status = caption_response.status();
+        info!("Caption API response status: {}", status);
+
+        let (episode, caption_response) = if status.is_success() {
+            (episode.to_string(), caption_response)
+        } else if status.as_u16() == 404 {
+            // If we get a 404, try with a different URL format
+            info!("Got 404 for caption, trying alternative URL format");
+
+            // Try with a different format - some episodes might be formatted differently
+            let alt_episode = if episode.contains("E") || episode.contains("S") {
+                // If it's already in SxxExx format, try with just the episode number
+                let parts: Vec<&str> = episode.split(['E', 'S']).collect();
+                if parts.len() > 1 {
+                    parts[parts.len() - 1].to_string()
+                } else {
+                    episode.to_string()
+                }
+            } else {
+                // If it's not in SxxExx format, try with that format
+                let episode_num = episode.parse::<u32>().unwrap_or(1);
+                format!("S01E{episode_num:02}")
+            };
+
+            let alt_caption_url = self.caption_url(&Frame::at(&alt_episode, timestamp));
+            info!("Trying alternative caption URL: {}", alt_caption_url);
+
+            let alt_caption_response = self
+                .http_client
+                .get(&alt_caption_url)
+                .send()
+                .await
+                .map_err(|e| anyhow!("Failed to get caption with alternative URL: {}", e))?;
+
+            if !alt_caption_response.status().is_success() {
+                return Err(anyhow!(
+                    "Frinkiac caption request failed with both URL formats"
+                ));
             }
-        }
 
-        // Fallback: Use a random search term from our list
-        // Select a random term before the async operation to avoid Send issues
-        let random_term = {
-            let mut rng = rand::rng();
-            RANDOM_SEARCH_TERMS
-                .choose(&mut rng)
-                .ok_or_else(|| anyhow!("Failed to select random search term"))?
-                .to_string() // Convert to owned String to avoid lifetime issues
+            (alt_episode, alt_caption_response)
+        } else {
+            return Err(anyhow!(
+                "Frinkiac caption request failed with status: {}",
+                status
+            ));
         };
 
-        info!("Using random search term: {}", random_term);
+        // Parse the caption result as a generic JSON Value first
+        let caption_result: serde_json::Value = caption_response
+            .json()
+            .await
+            .map_err(|e| anyhow!("Failed to parse Frinkiac caption result: {}", e))?;
+
+        // Format the image URL
+        let image_url = format!("{FRINKIAC_IMAGE_URL}/{episode}/{timestamp}.jpg");
 
-        // Use search_with_strategy directly to avoid recursion
-        self.search_with_strategy(&random_term).await
+        let mut result =
+            screenshot::parse_caption(&caption_result, &episode, timestamp, image_url)?;
+        result.caption = format_caption(&result.subtitle_text(" "));
+        Ok(Some(result))
     }
 
-    // Try to get a random screenshot using Frinkiac's random API
-    async fn get_random_direct(&self) -> Result<Option<FrinkiacResult>> {
-        // Make the request to the random API
-        let random_response = self
+    /// Fetch every subtitle line for an episode (canonical SxxEyy ID).
+    /// Returns the episode title and lines, or None if the episode doesn't exist.
+    pub async fn get_episode_subtitles(
+        &self,
+        episode: &str,
+    ) -> Result<Option<(String, Vec<TimedSubtitle>)>> {
+        // The episode endpoint takes a millisecond range; ask for well past any episode's end
+        let url = format!("{FRINKIAC_EPISODE_URL}/{episode}/0/99999999");
+        info!("Fetching episode subtitles from URL: {}", url);
+
+        let response = self
             .http_client
-            .get(FRINKIAC_RANDOM_URL)
+            .get(&url)
             .send()
             .await
-            .map_err(|e| anyhow!("Failed to get random screenshot from Frinkiac: {}", e))?;
+            .map_err(|e| anyhow!("Failed to get episode subtitles from Frinkiac: {}", e))?;
 
-        if !random_response.status().is_success() {
+        let status = response.status();
+        if status.is_client_error() {
+            info!("Frinkiac has no episode {} (status {})", episode, status);
+            return Ok(None);
+        }
+        if !status.is_success() {
             return Err(anyhow!(
-                "Frinkiac random request failed with status: {}",
-                random_response.status()
+                "Frinkiac episode request failed with status: {}",
+                status
             ));
         }
 
-        // Parse the random result as a generic JSON Value first
-        let random_result: serde_json::Value = random_response
+        let episode_result: serde_json::Value = response
             .json()
             .await
-            .map_err(|e| anyhow!("Failed to parse Frinkiac random result: {}", e))?;
+            .map_err(|e| anyhow!("Failed to parse Frinkiac episode result: {}", e))?;
 
-        // Extract the episode and timestamp
-        let episode = random_result
+        let title = episode_result
             .get("Episode")
-            .and_then(|v| v.as_str())
-            .ok_or_else(|| anyhow!("Missing Episode in random result"))?;
-
-        let timestamp = random_result
-            .get("Timestamp")
-            .and_then(|v| v.as_u64())
-            .ok_or_else(|| anyhow!("Missing Timestamp in random result"))?;
-
-        // Get the caption for this frame
-        self.get_caption_for_frame(episode, timestamp).await
-    }
+            .and_then(|e| e.get("Title"))
+            .and_then(|t| t.as_str())
+            .unwrap_or("Unknown")
+            .to_string();
 
-    pub async fn search(&self, query: &str) -> Result<Option<FrinkiacResult>> {
-        info!("Frinkiac search for: {}", query);
+        let subtitles: Vec<TimedSubtitle> = episode_result
+            .get("Subtitles")
+            .and_then(|v| v.as_array())
+            .map(|subs| {
+                subs.iter()
+                    .filter_map(|s| {
+                        Some(TimedSubtitle {
+                            text: s.get("Content")?.as_str()?.to_string(),
+                            start: s.get("StartTimestamp")?.as_u64()?,
+                            end: s.get("EndTimestamp")?.as_u64()?,
+                            representative_ts: s.get("RepresentativeTimestamp")?.as_u64()?,
+                        })
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
 
-        // Try a direct search first
-        if let Some(result) = self.search_with_strategy(query).await? {
-            info!("Found result with direct search");
-            return Ok(Some(result));
+        if subtitles.is_empty() {
+            return Ok(None);
         }
 
-        // If direct search fails and it's a multi-word query, try with quotes
-        if query.contains(' ') {
-            if let Some(result) = self.search_with_strategy(&format!("\"{query}\"")).await? {
-                info!("Found result with quoted search");
-                return Ok(Some(result));
-            }
-        }
+        Ok(Some((title, subtitles)))
+    }
+}
 
-        // If all else fails, return a random result
-        info!(
-            "No results found for query: {}, returning random result",
-            query
-        );
-        self.get_random_direct().await
+impl ScreenshotProvider for FrinkiacClient {
+    fn name(&self) -> &str {
+        "Frinkiac"
     }
 
-    // Internal method to perform the actual API call with a specific search strategy
-    async fn search_with_strategy(&self, query: &str) -> Result<Option<FrinkiacResult>> {
+    fn site(&self) -> &ScreenshotSite {
+        &FRINKIAC_SITE
+    }
+
+    async fn search(&self, query: &str) -> Result<Vec<Frame>> {
         // URL encode the query
         let encoded_query = urlencoding::encode(query);
         let search_url = format!("{FRINKIAC_BASE_URL}?q={encoded_query}");
@@ -261,718 +312,355 @@ impl FrinkiacClient {
             .await
             .map_err(|e| anyhow!("Failed to parse Frinkiac search results: {}", e))?;
 
-        // If no results, return None
-        if search_results.is_empty() {
-            info!("No results found for query: {}", query);
-            return Ok(None);
-        }
-
-        // Deduplicate results by episode (API returns many frames from same scene)
-        let mut seen_episodes = std::collections::HashSet::new();
-        let unique_results: Vec<&serde_json::Value> = search_results
-            .iter()
-            .filter(|r| {
-                let ep = r.get("Episode").and_then(|v| v.as_str()).unwrap_or("");
-                seen_episodes.insert(ep.to_string())
-            })
-            .collect();
-
-        if unique_results.is_empty() {
-            return Ok(None);
-        }
-
-        // Bias toward classic seasons (1-11): show those first, then later seasons
-        let (classic, later): (Vec<&serde_json::Value>, Vec<&serde_json::Value>) =
-            unique_results.into_iter().partition(|r| {
-                let ep = r.get("Episode").and_then(|v| v.as_str()).unwrap_or("");
-                // Parse season number from "S01E02" format
-                let season: u32 = ep
-                    .strip_prefix('S')
-                    .and_then(|s| s.split('E').next())
-                    .and_then(|s| s.parse().ok())
-                    .unwrap_or(99);
-                season <= 11
-            });
-        let ordered_results: Vec<&serde_json::Value> = classic.into_iter().chain(later).collect();
-
-        // Pick the next result, rotating through results for repeated queries
-        let index = {
-            let mut last_q = self.last_query.write().unwrap();
-            let mut idx = self.current_index.write().unwrap();
-            if last_q.as_deref() == Some(query) {
-                *idx = (*idx + 1) % ordered_results.len();
-            } else {
-                *last_q = Some(query.to_string());
-                *idx = 0;
-            }
-            *idx
-        };
-
-        let result = ordered_results[index];
-
-        // Extract the episode and timestamp
-        let episode = result
-            .get("Episode")
-            .and_then(|v| v.as_str())
-            .ok_or_else(|| anyhow!("Missing Episode in search result"))?;
-
-        let timestamp = result
-            .get("Timestamp")
-            .and_then(|v| v.as_u64())
-            .ok_or_else(|| anyhow!("Missing Timestamp in search result"))?;
-
-        // Get the caption for this frame
-        self.get_caption_for_frame(episode, timestamp).await
+        Ok(rank_search_results(&search_results))
     }
 
-    // Get caption and details for a specific frame
-    pub async fn get_caption_for_frame(
-        &self,
-        episode: &str,
-        timestamp: u64,
-    ) -> Result<Option<FrinkiacResult>> {
-        // Get the caption for this frame
-        let caption_url = format!("{FRINKIAC_CAPTION_URL}?e={episode}&t={timestamp}");
-
-        info!("Fetching caption from URL: {}", caption_url);
-
-        let caption_response = self
-            .http_client
-            .get(&caption_url)
-            .send()
-            .await
-            .map_err(|e| anyhow!("Failed to get caption from Frinkiac: {}", e))?;
-
-        let status = caption_response.status();
-        info!("Caption API response status: {}", status);
-
-        if !status.is_success() {
-            // If we get a 404, try with a different URL format
-            if status.as_u16() == 404 {
-                info!("Got 404 for caption, trying alternative URL format");
-
-                // Try with a different format - some episodes might be formatted differently
-                let alt_episode = if episode.contains("E") || episode.contains("S") {
-                    // If it's already in SxxExx format, try with just the episode number
-                    let parts: Vec<&str> = episode.split(['E', 'S']).collect();
-                    if parts.len() > 1 {
-                        parts[parts.len() - 1].to_string()
-                    } else {
-                        episode.to_string()
-                    }
-                } else {
-                    // If it's not in SxxExx format, try with that format
-                    let episode_num = episode.parse::<u32>().unwrap_or(1);
-                    format!("S01E{episode_num:02}")
-                };
-
-                let alt_caption_url =
-                    format!("{FRINKIAC_CAPTION_URL}?e={alt_episode}&t={timestamp}");
-                info!("Trying alternative caption URL: {}", alt_caption_url);
-
-                let alt_caption_response = self
-                    .http_client
-                    .get(&alt_caption_url)
-                    .send()
-                    .await
-                    .map_err(|e| anyhow!("Failed to get caption with alternative URL: {}", e))?;
-
-                if !alt_caption_response.status().is_success() {
-                    return Err(anyhow!(
-                        "Frinkiac caption request failed with both URL formats"
-                    ));
-                }
-
-                // Parse the caption result as a generic JSON Value
-                let caption_result: serde_json::Value = alt_caption_response
-                    .json()
-                    .await
-                    .map_err(|e| anyhow!("Failed to parse Frinkiac caption result: {}", e))?;
-
-                // Extract episode information
-                let episode_info = caption_result
-                    .get("Episode")
-                    .ok_or_else(|| anyhow!("Missing Episode info in caption result"))?;
-
-                let episode_title = episode_info
-                    .get("Title")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("Unknown")
-                    .to_string();
-
-                let season = episode_info
-                    .get("Season")
-                    .and_then(|v| v.as_u64())
-                    .unwrap_or(0) as u32;
-
-                let episode_number = episode_info
-                    .get("EpisodeNumber")
-                    .and_then(|v| v.as_u64())
-                    .unwrap_or(0) as u32;
-
-                // Extract subtitles/caption
-                let subtitles = caption_result
-                    .get("Subtitles")
-                    .and_then(|v| v.as_array())
-                    .ok_or_else(|| anyhow!("Missing Subtitles in caption result"))?;
-
-                let caption = subtitles
-                    .iter()
-                    .filter_map(|s| s.get("Content").and_then(|c| c.as_str()))
-                    .collect::<Vec<&str>>()
-                    .join(" ");
-
-                let timed_subs: Vec<TimedSubtitle> = subtitles
-                    .iter()
-                    .filter_map(|s| {
-                        Some(TimedSubtitle {
-                            text: s.get("Content")?.as_str()?.to_string(),
-                            start: s.get("StartTimestamp")?.as_u64()?,
-                            end: s.get("EndTimestamp")?.as_u64()?,
-                            representative_ts: s.get("RepresentativeTimestamp")?.as_u64()?,
-                        })
-                    })
-                    .collect();
-
-                // Extract subtitle time range
-                let start_ts = timed_subs.first().map(|s| s.start).unwrap_or(timestamp);
-                let end_ts = timed_subs.last().map(|s| s.end).unwrap_or(timestamp + 4000);
-
-                // Format the image URL
-                let image_url = format!("{FRINKIAC_IMAGE_URL}/{alt_episode}/{timestamp}.jpg");
-
-                // Format the meme URL (for sharing)
-                let meme_url = format!("{FRINKIAC_MEME_URL}/{alt_episode}/{timestamp}.jpg");
-
-                return Ok(Some(FrinkiacResult {
-                    _episode: alt_episode,
-                    episode_title,
-                    season,
-                    episode_number,
-                    _timestamp: timestamp.to_string(),
-                    image_url,
-                    _meme_url: meme_url,
-                    caption: format_caption(&caption),
-                    start_timestamp: start_ts,
-                    end_timestamp: end_ts,
-                    subtitles: timed_subs,
-                    gif_url: None,
-                }));
-            }
-
-            return Err(anyhow!(
-                "Frinkiac caption request failed with status: {}",
-                caption_response.status()
-            ));
-        }
+    fn caption_url(&self, frame: &Frame) -> String {
+        format!(
+            "{FRINKIAC_CAPTION_URL}?e={}&t={}",
+            frame.episode, frame.timestamp
+        )
+    }
 
-        // Parse the caption result as a generic JSON Value first
-        let caption_result: serde_json::Value = caption_response
-            .json()
+    async fn caption(&self, frame: &Frame) -> Result<Option<Screenshot>> {
+        self.get_caption_for_frame(&frame.episode, frame.timestamp)
             .await
-            .map_err(|e| anyhow!("Failed to parse Frinkiac caption result: {}", e))?;
-
-        // Extract episode information
-        let episode_info = caption_result
-            .get("Episode")
-            .ok_or_else(|| anyhow!("Missing Episode info in caption result"))?;
-
-        let episode_title = episode_info
-            .get("Title")
-            .and_then(|v| v.as_str())
-            .unwrap_or("Unknown")
-            .to_string();
-
-        let season = episode_info
-            .get("Season")
-            .and_then(|v| v.as_u64())
-            .unwrap_or(0) as u32;
-
-        let episode_number = episode_info
-            .get("EpisodeNumber")
-            .and_then(|v| v.as_u64())
-            .unwrap_or(0) as u32;
-
-        // Extract subtitles/caption
-        let subtitles = caption_result
-            .get("Subtitles")
-            .and_then(|v| v.as_array())
-            .ok_or_else(|| anyhow!("Missing Subtitles in caption result"))?;
-
-        let caption = subtitles
-            .iter()
-            .filter_map(|s| s.get("Content").and_then(|c| c.as_str()))
-            .collect::<Vec<&str>>()
-            .join(" ");
-
-        let timed_subs: Vec<TimedSubtitle> = subtitles
-            .iter()
-            .filter_map(|s| {
-                Some(TimedSubtitle {
-                    text: s.get("Content")?.as_str()?.to_string(),
-                    start: s.get("StartTimestamp")?.as_u64()?,
-                    end: s.get("EndTimestamp")?.as_u64()?,
-                    representative_ts: s.get("RepresentativeTimestamp")?.as_u64()?,
-                })
-            })
-            .collect();
-
-        // Extract subtitle time range
-        let start_ts = timed_subs.first().map(|s| s.start).unwrap_or(timestamp);
-        let end_ts = timed_subs.last().map(|s| s.end).unwrap_or(timestamp + 4000);
-
-        // Format the image URL
-        let image_url = format!("{FRINKIAC_IMAGE_URL}/{episode}/{timestamp}.jpg");
-
-        // Format the meme URL (for sharing)
-        let meme_url = format!("{FRINKIAC_MEME_URL}/{episode}/{timestamp}.jpg");
-
-        Ok(Some(FrinkiacResult {
-            _episode: episode.to_string(),
-            episode_title,
-            season,
-            episode_number,
-            _timestamp: timestamp.to_string(),
-            image_url,
-            _meme_url: meme_url,
-            caption: format_caption(&caption),
-            start_timestamp: start_ts,
-            end_timestamp: end_ts,
-            subtitles: timed_subs,
-            gif_url: None,
-        }))
     }
 
-    /// Expand subtitles to sentence boundaries by fetching adjacent captions.
-    /// If the first subtitle starts mid-sentence, fetches earlier context.
-    /// If the last subtitle ends mid-sentence, fetches later context.
-    pub async fn expand_to_sentence_boundaries(&self, result: &mut FrinkiacResult) {
-        let episode = result._episode.clone();
-
-        // Check if first subtitle starts mid-sentence (lowercase first char)
-        let first_starts_mid = result
-            .subtitles
-            .first()
-            .is_some_and(|s| s.text.chars().next().is_some_and(|c| c.is_lowercase()));
-        let first_start = result.subtitles.first().map(|s| s.start).unwrap_or(0);
-
-        if first_starts_mid {
-            let earlier_ts = first_start.saturating_sub(2000);
-            let expanded =
-                if let Ok(Some(earlier)) = self.get_caption_for_frame(&episode, earlier_ts).await {
-                    let mut to_prepend = Vec::new();
-                    for sub in earlier.subtitles.iter().rev() {
-                        if sub.end <= first_start {
-                            to_prepend.push(sub.clone());
-                            if sub.text.chars().next().is_some_and(|c| c.is_uppercase()) {
-                                break;
-                            }
-                        }
-                    }
-                    to_prepend.reverse();
-                    if let Some(first_new) = to_prepend.first() {
-                        result.start_timestamp = first_new.start;
-                        for (i, sub) in to_prepend.into_iter().enumerate() {
-                            result.subtitles.insert(i, sub);
-                        }
-                        true
-                    } else {
-                        false
-                    }
-                } else {
-                    false
-                };
-
-            // If we couldn't find the sentence start, drop the dangling fragment
-            if !expanded && !result.subtitles.is_empty() {
-                let removed = result.subtitles.remove(0);
-                if let Some(new_first) = result.subtitles.first() {
-                    result.start_timestamp = new_first.start;
-                }
-                info!("Dropped dangling subtitle fragment: {:?}", removed.text);
-            }
-        }
+    fn random_terms(&self) -> &[&str] {
+        RANDOM_SEARCH_TERMS
+    }
 
-        // Check if last subtitle ends mid-sentence
-        let last_ends_mid = result.subtitles.last().is_some_and(|s| {
-            s.text.ends_with(',')
-                || (!s.text.ends_with('.')
-                    && !s.text.ends_with('!')
-                    && !s.text.ends_with('?')
-                    && !s.text.ends_with('"'))
-        });
-        let last_rep_ts = result
-            .subtitles
-            .last()
-            .map(|s| s.representative_ts)
-            .unwrap_or(0);
-        let last_end = result.subtitles.last().map(|s| s.end).unwrap_or(0);
-
-        if last_ends_mid {
-            let later_ts = last_rep_ts;
-            let mut expanded = false;
-
-            if let Ok(Some(later)) = self.get_caption_for_frame(&episode, later_ts).await {
-                for sub in &later.subtitles {
-                    if sub.start >= last_end {
-                        result.end_timestamp = sub.end;
-                        result.subtitles.push(sub.clone());
-                        expanded = true;
-                        if sub.text.ends_with('.')
-                            || sub.text.ends_with('!')
-                            || sub.text.ends_with('?')
-                            || sub.text.ends_with('"')
-                        {
-                            break;
-                        }
-                    }
-                }
+    // Try the direct random API endpoint first, then a random search term
+    async fn random_frame(&self) -> Result<Option<Frame>> {
+        match screenshot::random_api_frame(&self.http_client, FRINKIAC_RANDOM_URL).await {
+            Ok(frame) => {
+                info!("Successfully got random screenshot from direct API");
+                return Ok(Some(frame));
             }
-
-            // If we couldn't find the sentence end, drop the trailing fragment
-            if !expanded && result.subtitles.len() > 1 {
-                result.subtitles.pop();
-                if let Some(new_last) = result.subtitles.last() {
-                    result.end_timestamp = new_last.end;
-                }
+            Err(e) => {
+                info!(
+                    "Error from direct random API: {}, trying fallback method",
+                    e
+                );
             }
         }
-    }
-}
 
-// Format a caption to proper sentence case and separate different speakers
-fn format_caption(caption: &str) -> String {
-    text_formatting::format_caption(caption, text_formatting::SIMPSONS_PROPER_NOUNS)
-}
-
-/// A subtitle with timing for GIF overlay
-#[derive(Debug, Clone)]
-pub struct TimedSubtitle {
-    pub text: String,
-    pub start: u64,
-    pub end: u64,
-    /// A valid frame timestamp for this subtitle (always maps to an indexed frame)
-    pub representative_ts: u64,
-}
-
-/// Merge subtitle fragments that are continuations of the same sentence.
-/// If a subtitle starts with lowercase or continues a sentence ending with a comma,
-/// merge it with the previous one.
-pub fn merge_subtitle_fragments(subs: &[TimedSubtitle]) -> Vec<TimedSubtitle> {
-    if subs.is_empty() {
-        return Vec::new();
+        screenshot::random_term_frame(self).await
     }
 
-    let mut merged: Vec<TimedSubtitle> = Vec::new();
+    fn search_state(&self) -> &SearchState {
+        &self.search_state
+    }
 
-    for sub in subs {
-        let should_merge = if let Some(prev) = merged.last() {
-            // Merge if: previous ends with comma, or current starts with lowercase
-            prev.text.ends_with(',') || sub.text.chars().next().is_some_and(|c| c.is_lowercase())
-        } else {
-            false
-        };
+    fn title(&self, result: &Screenshot) -> String {
+        format!(
+            "{} (Season {}, Episode {})",
+            result.episode_title, result.season, result.episode_number
+        )
+    }
 
-        if should_merge {
-            let prev = merged.last_mut().unwrap();
-            prev.text = format!("{} {}", prev.text, sub.text);
-            prev.end = sub.end;
+    fn format_result(&self, result: &Screenshot) -> String {
+        let title = self.title(result);
+        let media_url = result.gif_url.as_deref().unwrap_or(&result.image_url);
+        if result.gif_url.is_some() {
+            // Caption is baked into the GIF
+            format!("{title}\n{media_url}")
         } else {
-            merged.push(sub.clone());
+            let caption = &result.caption;
+            format!("{title}\n{media_url}\n{caption}")
         }
     }
 
-    merged
+    fn format_still(&self, result: &Screenshot) -> String {
+        format!("{}\n{}", self.title(result), result.caption)
+    }
 }
 
-// Generate a GIF from a Frinkiac result using the render API
-pub async fn generate_gif(
-    base_url: &str,
-    episode: &str,
-    start: u64,
-    end: u64,
-    subtitles: &[TimedSubtitle],
-    font_size: u32,
-    font: &str,
-) -> Option<String> {
-    let url = format!("{base_url}/api/render/gif/stream");
-
-    let overlays: Vec<serde_json::Value> = subtitles
+// Format a caption to proper sentence case and separate different speakers
+/// Order raw search results the way we serve them: one frame per episode (the API returns
+/// many frames from the same scene), classic seasons (1-11) first, otherwise in the API's
+/// relevance order. Results missing an episode or timestamp are skipped.
+pub fn rank_search_results(results: &[serde_json::Value]) -> Vec<Frame> {
+    let mut seen_episodes = std::collections::HashSet::new();
+    let unique_results: Vec<Frame> = results
         .iter()
-        .map(|sub| {
-            serde_json::json!({
-                "text": sub.text,
-                "font": font,
-                "x": 50,
-                "y": 90,
-                "text_align": "c",
-                "all_caps": true,
-                "size": font_size,
-                "color": [255, 255, 255, 255],
-                "start": sub.start.saturating_sub(start),
-                "end": sub.end.saturating_sub(start)
+        .enumerate()
+        .filter_map(|(i, r)| {
+            let episode = r.get("Episode").and_then(|v| v.as_str())?;
+            let timestamp = r.get("Timestamp").and_then(|v| v.as_u64())?;
+            seen_episodes.insert(episode.to_string()).then(|| Frame {
+                episode: episode.to_string(),
+                timestamp,
+                api_rank: i + 1,
+                note: None,
             })
         })
         .collect();
 
-    let body = serde_json::json!([{
-        "episode": episode,
-        "start": start,
-        "end": end,
-        "overlays": overlays
-    }]);
-
-    let client = reqwest::Client::builder()
-        .timeout(Duration::from_secs(30))
-        .build()
-        .ok()?;
-
-    let response = client
-        .post(&url)
-        .header("Content-Type", "application/json")
-        .body(body.to_string())
-        .send()
-        .await
-        .ok()?;
-
-    if !response.status().is_success() {
-        error!("GIF generation failed with status: {}", response.status());
-        return None;
-    }
-
-    let text = response.text().await.ok()?;
-
-    // Parse newline-delimited JSON, find the line with "url"
-    for line in text.lines().rev() {
-        if let Ok(val) = serde_json::from_str::<serde_json::Value>(line) {
-            if let Some(gif_path) = val.get("url").and_then(|v| v.as_str()) {
-                let full_url = format!("{base_url}{gif_path}");
-                info!("Generated GIF: {}", full_url);
-                return Some(full_url);
-            }
+    // Bias toward classic seasons (1-11): show those first, then later seasons
+    let (mut classic, later): (Vec<Frame>, Vec<Frame>) =
+        unique_results.into_iter().partition(|candidate| {
+            // Parse season number from "S01E02" format
+            let season: u32 = candidate
+                .episode
+                .strip_prefix('S')
+                .and_then(|s| s.split('E').next())
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(99);
+            season <= 11
+        });
+    if !later.is_empty() {
+        for candidate in &mut classic {
+            candidate.note = Some("classic season");
         }
     }
-
-    error!("GIF generation did not return a URL");
-    None
+    classic.into_iter().chain(later).collect()
 }
 
-// Format a Frinkiac result for display
-pub fn format_frinkiac_result(result: &FrinkiacResult) -> String {
-    let media_url = result.gif_url.as_deref().unwrap_or(&result.image_url);
-    let episode_title = &result.episode_title;
-    let season = result.season;
-    let episode_number = result.episode_number;
-    if result.gif_url.is_some() {
-        // Caption is baked into the GIF
-        format!("{episode_title} (Season {season}, Episode {episode_number})\n{media_url}")
-    } else {
-        let caption = &result.caption;
-        format!(
-            "{episode_title} (Season {season}, Episode {episode_number})\n{media_url}\n{caption}"
-        )
-    }
+fn format_caption(caption: &str) -> String {
+    text_formatting::format_caption(caption, text_formatting::SIMPSONS_PROPER_NOUNS)
 }
 
-/// Send a frinkiac result as a Discord embed (GIF with clickable title) or plain text fallback
-async fn send_frinkiac_result(http: &Http, msg: &Message, result: &FrinkiacResult) {
-    if let Some(gif_url) = &result.gif_url {
-        let title = format!(
-            "{} (Season {}, Episode {})",
-            result.episode_title, result.season, result.episode_number
-        );
+/// Parse an episode identifier like "S07E21", "s7e21" or "7x21" into canonical "S07E21"
+pub fn parse_episode_id(input: &str) -> Option<String> {
+    let lower = input.trim().to_lowercase();
 
-        // Download the GIF and upload as attachment for reliable display
-        match reqwest::Client::new().get(gif_url.as_str()).send().await {
-            Ok(resp) if resp.status().is_success() => {
-                if let Ok(bytes) = resp.bytes().await {
-                    let attachment = serenity::builder::CreateAttachment::bytes(
-                        bytes.to_vec(),
-                        "frinkiac.gif".to_string(),
-                    );
-                    let message = CreateMessage::new().content(title).add_file(attachment);
-                    if let Err(e) = msg.channel_id.send_message(http, message).await {
-                        error!("Error sending Frinkiac GIF attachment: {:?}", e);
-                    }
-                }
-            }
-            _ => {
-                // Fallback: send the URL as text
-                let response = format!("{}\n{}", title, gif_url);
-                if let Err(e) = msg.channel_id.say(http, &response).await {
-                    error!("Error sending Frinkiac result: {:?}", e);
-                }
-            }
-        }
+    let (season, episode) = if let Some(rest) = lower.strip_prefix('s') {
+        rest.split_once('e')?
     } else {
-        let response = format_frinkiac_result(result);
-        if let Err(e) = msg.channel_id.say(http, &response).await {
-            error!("Error sending Frinkiac result: {:?}", e);
-        }
-    }
-}
-
-// Parse arguments for the frinkiac command
-fn parse_frinkiac_args(args: &str) -> (Option<String>, Option<u32>, Option<u32>) {
-    let mut search_term = None;
-    let mut season_filter = None;
-    let mut episode_filter = None;
+        lower.split_once('x')?
+    };
 
-    let mut current_arg = String::new();
-    let mut expecting_season = false;
-    let mut expecting_episode = false;
+    let season: u32 = season.parse().ok()?;
+    let episode: u32 = episode.parse().ok()?;
+    if !(1..=99).contains(&season) || !(1..=99).contains(&episode) {
+        return None;
+    }
 
-    for part in args.split_whitespace() {
-        if expecting_season {
-            if let Ok(season) = part.parse::<u32>() {
-                season_filter = Some(season);
-            }
-            expecting_season = false;
-            continue;
-        }
+    Some(format!("S{season:02}E{episode:02}"))
+}
 
-        if expecting_episode {
-            if let Ok(episode) = part.parse::<u32>() {
-                episode_filter = Some(episode);
-            }
-            expecting_episode = false;
-            continue;
+/// Split lines into message-sized pages of at most `page_size` lines and `max_chars` characters
+pub fn paginate_lines(lines: &[String], page_size: usize, max_chars: usize) -> Vec<String> {
+    let mut pages = Vec::new();
+    let mut current = String::new();
+    let mut current_lines = 0;
+
+    for line in lines {
+        let would_overflow = !current.is_empty() && current.len() + line.len() + 1 > max_chars;
+        if current_lines >= page_size.max(1) || would_overflow {
+            pages.push(std::mem::take(&mut current));
+            current_lines = 0;
         }
-
-        if part == "-s" || part == "--season" {
-            expecting_season = true;
-        } else if part == "-e" || part == "--episode" {
-            expecting_episode = true;
-        } else {
-            if !current_arg.is_empty() {
-                current_arg.push(' ');
-            }
-            current_arg.push_str(part);
+        if !current.is_empty() {
+            current.push('\n');
         }
+        current.push_str(line);
+        current_lines += 1;
     }
 
-    if !current_arg.is_empty() {
-        search_term = Some(current_arg);
+    if !current.is_empty() {
+        pages.push(current);
     }
 
-    (search_term, season_filter, episode_filter)
+    pages
 }
 
-// This function will be called from main.rs to handle the !frinkiac command
-pub async fn handle_frinkiac_command(
+// This function will be called from main.rs to handle the !subtitles command
+pub async fn handle_subtitles_command(
     http: &Http,
     msg: &Message,
     args: Option<String>,
     frinkiac_client: &FrinkiacClient,
-    _gemini_client: Option<&GeminiClient>,
+    page_size: usize,
+    max_pages: usize,
 ) -> Result<()> {
-    // Parse arguments to support filtering by season/episode
-    let (search_term, season_filter, episode_filter) = if let Some(args_str) = args {
-        parse_frinkiac_args(&args_str)
-    } else {
-        (None, None, None)
+    let usage =
+        "Usage: !subtitles <episode> [page] (e.g. `!subtitles S07E21` or `!subtitles 7x21 2`)";
+
+    let args = args.unwrap_or_default();
+    let mut parts = args.split_whitespace();
+    let Some(episode) = parts.next().and_then(parse_episode_id) else {
+        msg.channel_id.say(http, usage).await?;
+        return Ok(());
     };
+    let start_page = parts
+        .next()
+        .and_then(|p| p.parse::<usize>().ok())
+        .unwrap_or(1)
+        .max(1);
 
-    // Show typing indicator while we search
     let _ = msg.channel_id.broadcast_typing(http).await;
 
-    // If no search term is provided, get a random screenshot
-    if search_term.is_none() && season_filter.is_none() && episode_filter.is_none() {
-        info!("Frinkiac request for random screenshot");
-
-        match frinkiac_client.random().await {
-            Ok(Some(mut result)) => {
-                frinkiac_client
-                    .expand_to_sentence_boundaries(&mut result)
-                    .await;
-                result.gif_url = generate_gif(
-                    "https://frinkiac.com",
-                    &result._episode,
-                    result.start_timestamp,
-                    result.end_timestamp,
-                    &merge_subtitle_fragments(&result.subtitles),
-                    0,
-                    "akbar",
-                )
-                .await;
-                send_frinkiac_result(http, msg, &result).await;
-            }
-            Ok(None) => {
-                let _ = msg
-                    .channel_id
-                    .say(http, "Couldn't find any Simpsons screenshots. D'oh!")
-                    .await;
-            }
-            Err(e) => {
-                error!("Error getting random Frinkiac screenshot: {:?}", e);
-                let _ = msg
-                    .channel_id
-                    .say(http, "Error getting Frinkiac screenshot. D'oh!")
-                    .await;
-            }
-        };
+    let (title, subtitles) = match frinkiac_client.get_episode_subtitles(&episode).await {
+        Ok(Some(found)) => found,
+        Ok(None) => {
+            msg.channel_id
+                .say(http, format!("Couldn't find a Simpsons episode {episode}."))
+                .await?;
+            return Ok(());
+        }
+        Err(e) => {
+            error!("Error fetching episode subtitles: {:?}", e);
+            msg.channel_id
+                .say(http, "Error fetching subtitles from Frinkiac. D'oh!")
+                .await?;
+            return Ok(());
+        }
+    };
 
+    let lines: Vec<String> = merge_subtitle_fragments(&subtitles)
+        .iter()
+        .map(|s| format!("[{}] {}", format_episode_time(s.start), s.text))
+        .collect();
+
+    // Leave room for the code fence and header in Discord's 2000 character limit
+    let pages = paginate_lines(&lines, page_size, 1800);
+    let total_pages = pages.len();
+
+    if start_page > total_pages {
+        msg.channel_id
+            .say(
+                http,
+                format!("{episode} only has {total_pages} pages of subtitles."),
+            )
+            .await?;
         return Ok(());
     }
 
-    // If we have a search term, search for it
-    if let Some(term) = search_term {
-        info!("Frinkiac search for: {}", term);
-
-        match frinkiac_client.search(&term).await {
-            Ok(Some(mut result)) => {
-                let filtered_out = season_filter.is_some_and(|s| result.season != s)
-                    || episode_filter.is_some_and(|e| result.episode_number != e);
-
-                if filtered_out {
-                    let _ = msg.channel_id.say(http, format!("Couldn't find any Simpsons screenshots matching \"{term}\" in the specified season/episode.")).await;
-                } else {
-                    frinkiac_client
-                        .expand_to_sentence_boundaries(&mut result)
-                        .await;
-                    result.gif_url = generate_gif(
-                        "https://frinkiac.com",
-                        &result._episode,
-                        result.start_timestamp,
-                        result.end_timestamp,
-                        &merge_subtitle_fragments(&result.subtitles),
-                        0,
-                        "akbar",
-                    )
-                    .await;
-                    send_frinkiac_result(http, msg, &result).await;
-                }
-            }
-            Ok(None) => {
-                let _ = msg
-                    .channel_id
-                    .say(
-                        http,
-                        format!("Couldn't find any Simpsons screenshots matching \"{term}\"."),
-                    )
-                    .await;
-            }
-            Err(e) => {
-                error!("Error searching Frinkiac: {:?}", e);
-                let _ = msg
-                    .channel_id
-                    .say(http, "Error searching Frinkiac. D'oh!")
-                    .await;
-            }
-        }
-    } else {
-        let error_msg = "Please provide a search term with season/episode filters.";
-        if let Err(e) = msg.channel_id.say(http, error_msg).await {
-            error!("Error sending error message: {:?}", e);
+    let end_page = (start_page - 1 + max_pages.max(1)).min(total_pages);
+    for (i, page) in pages[start_page - 1..end_page].iter().enumerate() {
+        let page_number = start_page + i;
+        let content =
+            format!("**{episode} - {title}** (page {page_number}/{total_pages})\n```\n{page}\n```");
+        if let Err(e) = msg.channel_id.say(http, content).await {
+            error!("Error sending subtitles page: {:?}", e);
+            break;
         }
     }
 
+    if end_page < total_pages {
+        msg.channel_id
+            .say(
+                http,
+                format!(
+                    "More lines available - use `!subtitles {episode} {}` to continue.",
+                    end_page + 1
+                ),
+            )
+            .await?;
+    }
+
     Ok(())
 }
 
-// Frinkiac client struct
-pub struct FrinkiacClient {
-    http_client: HttpClient,
-    last_query: std::sync::RwLock<Option<String>>,
-    current_index: std::sync::RwLock<usize>,
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_result_format() {
+        let client = FrinkiacClient::new();
+        let mut result = screenshot::parse_caption(
+            &serde_json::json!({
+                "Episode": {"Title": "Homer's Enemy", "Season": 8, "EpisodeNumber": 23},
+                "Subtitles": []
+            }),
+            "S08E23",
+            61000,
+            "https://frinkiac.com/img/S08E23/61000.jpg".to_string(),
+        )
+        .unwrap();
+        result.caption = "\"I'm a nuclear safety inspector.\"".to_string();
+
+        assert_eq!(
+            client.format_result(&result),
+            "Homer's Enemy (Season 8, Episode 23)\nhttps://frinkiac.com/img/S08E23/61000.jpg\n\"I'm a nuclear safety inspector.\""
+        );
+        assert_eq!(
+            client.format_still(&result),
+            "Homer's Enemy (Season 8, Episode 23)\n\"I'm a nuclear safety inspector.\""
+        );
+
+        // The caption is baked into a GIF
+        result.gif_url = Some("https://frinkiac.com/gif/S08E23/61000/64000.gif".to_string());
+        assert_eq!(
+            client.format_result(&result),
+            "Homer's Enemy (Season 8, Episode 23)\nhttps://frinkiac.com/gif/S08E23/61000/64000.gif"
+        );
+    }
+
+    #[test]
+    fn test_parse_episode_id() {
+        assert_eq!(parse_episode_id("S07E21"), Some("S07E21".to_string()));
+        assert_eq!(parse_episode_id("s7e21"), Some("S07E21".to_string()));
+        assert_eq!(parse_episode_id("7x21"), Some("S07E21".to_string()));
+        assert_eq!(parse_episode_id(" S10E02 "), Some("S10E02".to_string()));
+
+        assert_eq!(parse_episode_id("S00E01"), None);
+        assert_eq!(parse_episode_id("S07E100"), None);
+        assert_eq!(parse_episode_id("S07"), None);
+        assert_eq!(parse_episode_id("homer"), None);
+        assert_eq!(parse_episode_id(""), None);
+    }
+
+    #[test]
+    fn test_paginate_lines() {
+        let lines: Vec<String> = (1..=5).map(|i| format!("line {i}")).collect();
+        let pages = paginate_lines(&lines, 2, 1000);
+        assert_eq!(pages, vec!["line 1\nline 2", "line 3\nline 4", "line 5"]);
+
+        // The character cap also starts a new page
+        let pages = paginate_lines(&lines, 10, 12);
+        assert_eq!(pages.len(), 5);
+    }
+
+    #[test]
+    fn test_search_debug_lists_ranked_candidates() {
+        let results: Vec<serde_json::Value> = serde_json::from_str(
+            r#"[
+                {"Episode": "S25E03", "Timestamp": 61000},
+                {"Episode": "S07E21", "Timestamp": 125500},
+                {"Episode": "S07E21", "Timestamp": 126000},
+                {"Episode": "S04E12", "Timestamp": 9000}
+            ]"#,
+        )
+        .unwrap();
+
+        // One frame per episode, classic seasons ahead of later ones
+        let candidates = rank_search_results(&results);
+        let order: Vec<(&str, usize)> = candidates
+            .iter()
+            .map(|c| (c.episode.as_str(), c.api_rank))
+            .collect();
+        assert_eq!(order, vec![("S07E21", 2), ("S04E12", 4), ("S25E03", 1)]);
+
+        let ranking = screenshot::SearchRanking {
+            query: "steamed hams".to_string(),
+            candidates,
+            chosen: 1,
+        };
+        assert_eq!(
+            ranking.format_debug(),
+            "🔍 **Search debug** for \"steamed hams\": 3 candidates (one per episode, by API relevance rank)\n\
+            • 1. S07E21 @ 2:05 — API rank #2, classic season\n\
+            ▶ 2. S04E12 @ 0:09 — API rank #4, classic season\n\
+            • 3. S25E03 @ 1:01 — API rank #1"
+        );
+
+        // The flag is pulled out of the search terms
+        assert_eq!(
+            screenshot::take_debug_flag(Some("steamed hams -debug".to_string())),
+            (Some("steamed hams".to_string()), true)
+        );
+        assert_eq!(
+            screenshot::take_debug_flag(Some("-debug".to_string())),
+            (None, true)
+        );
+        assert_eq!(
+            screenshot::take_debug_flag(Some("debug mode".to_string())),
+            (Some("debug mode".to_string()), false)
+        );
+    }
 }
@@ -1,18 +1,368 @@
 use crate::prompt_templates::PromptTemplates;
-use crate::rate_limiter::RateLimiter;
+use crate::rate_limiter::{is_rate_limited, RateLimitSnapshot, RateLimiter};
+use crate::token_usage::{TokenUsage, UsageTracker};
 use anyhow::Result;
 use chrono::{DateTime, Utc};
+use futures::stream::{self, Stream};
 use rand::RngExt;
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::Mutex;
-use tracing::{error, info};
+use tracing::{debug, error, info};
+
+/// Default for GEMINI_REPLY_TIMEOUT_SECONDS: how long a reply (retries and all) may take
+pub const DEFAULT_REPLY_TIMEOUT: Duration = Duration::from_secs(90);
+
+/// Returned by [`with_timeout`] when a call ran out of time, so the user can be told the
+/// reply took too long rather than that something broke
+#[derive(Debug)]
+pub struct TimedOut {
+    after: Duration,
+}
+
+impl std::fmt::Display for TimedOut {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Gemini call timed out after {} seconds",
+            self.after.as_secs()
+        )
+    }
+}
+
+impl std::error::Error for TimedOut {}
+
+/// Whether an error came from [`with_timeout`] giving up
+pub fn is_timed_out(error: &anyhow::Error) -> bool {
+    error.downcast_ref::<TimedOut>().is_some()
+}
+
+/// Run a Gemini call, giving up with [`TimedOut`] if it hasn't finished within `limit`
+pub async fn with_timeout<T>(limit: Duration, call: impl Future<Output = Result<T>>) -> Result<T> {
+    match tokio::time::timeout(limit, call).await {
+        Ok(result) => result,
+        Err(_) => Err(TimedOut { after: limit }.into()),
+    }
+}
+
+/// A Gemini model URL and the API key for the project that serves it
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeminiEndpoint {
+    pub label: &'static str,
+    pub url: String,
+    pub api_key: String,
+}
+
+/// Expand a bare model name like "gemini-2.5-flash" into a generateContent URL.
+/// Full URLs are returned unchanged.
+pub fn endpoint_for_model(model_or_url: &str) -> String {
+    if model_or_url.starts_with("http://") || model_or_url.starts_with("https://") {
+        model_or_url.to_string()
+    } else {
+        format!(
+            "https://generativelanguage.googleapis.com/v1beta/models/{}:generateContent",
+            model_or_url
+        )
+    }
+}
+
+// Where an OpenAI-compatible server is assumed to be when no endpoint is configured (Ollama)
+const DEFAULT_OPENAI_COMPATIBLE_URL: &str = "http://localhost:11434";
+
+/// Which request/response shape the configured endpoint speaks
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ApiFlavor {
+    /// Google's generateContent API
+    #[default]
+    Gemini,
+    /// `/v1/chat/completions`, as served by OpenAI and self-hosted servers like Ollama,
+    /// LM Studio and vLLM
+    OpenAiCompatible,
+}
+
+impl ApiFlavor {
+    /// Parse an `LLM_API_FLAVOR` value
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().replace('-', "_").as_str() {
+            "gemini" => Some(ApiFlavor::Gemini),
+            "openai" | "openai_compatible" => Some(ApiFlavor::OpenAiCompatible),
+            _ => None,
+        }
+    }
+}
+
+/// Point an OpenAI-compatible server's base URL at its chat completions route.
+/// URLs that already name the route are returned unchanged.
+pub fn chat_completions_url(base: &str) -> String {
+    let base = base.trim_end_matches('/');
+    if base.ends_with("/chat/completions") {
+        base.to_string()
+    } else if base.ends_with("/v1") {
+        format!("{base}/chat/completions")
+    } else {
+        format!("{base}/v1/chat/completions")
+    }
+}
+
+/// The streamGenerateContent URL (sending server-sent events) for a generateContent URL,
+/// or None when the URL doesn't name a generateContent endpoint
+pub fn stream_endpoint_url(url: &str) -> Option<String> {
+    let (base, query) = match url.split_once('?') {
+        Some((base, query)) => (base, Some(query)),
+        None => (url, None),
+    };
+    let model = base.strip_suffix(":generateContent")?;
+    Some(match query {
+        Some(query) => format!("{model}:streamGenerateContent?alt=sse&{query}"),
+        None => format!("{model}:streamGenerateContent?alt=sse"),
+    })
+}
+
+/// Take the payloads of the complete server-sent events (their `data:` lines) out of the
+/// bytes received so far, leaving a trailing incomplete event in the buffer
+pub fn take_sse_events(buffer: &mut Vec<u8>) -> Vec<String> {
+    let mut events = Vec::new();
+    loop {
+        let boundary = buffer
+            .windows(2)
+            .position(|pair| pair == b"\n\n")
+            .map(|at| (at, 2))
+            .into_iter()
+            .chain(
+                buffer
+                    .windows(4)
+                    .position(|quad| quad == b"\r\n\r\n")
+                    .map(|at| (at, 4)),
+            )
+            .min();
+        let Some((at, separator_len)) = boundary else {
+            return events;
+        };
+        let event: Vec<u8> = buffer.drain(..at + separator_len).collect();
+        let data = String::from_utf8_lossy(&event[..at])
+            .lines()
+            .filter_map(|line| line.strip_prefix("data:"))
+            .map(str::trim)
+            .collect::<Vec<_>>()
+            .join("\n");
+        if !data.is_empty() {
+            events.push(data);
+        }
+    }
+}
+
+// The text in one streamed response chunk; None when it carries none (e.g. the last one,
+// which may only hold usage counts)
+fn stream_chunk_text(json: &serde_json::Value) -> Result<Option<String>> {
+    if let Some(error) = json.get("error") {
+        let message = error
+            .get("message")
+            .and_then(|m| m.as_str())
+            .unwrap_or("Unknown API error");
+        return Err(anyhow::anyhow!("Gemini API error: {}", message));
+    }
+    if let Some(reason) = json
+        .pointer("/candidates/0/finishReason")
+        .and_then(|r| r.as_str())
+    {
+        if matches!(reason, "SAFETY" | "RECITATION" | "OTHER") {
+            return Err(anyhow::anyhow!(
+                "Gemini API stopped the streamed response: {}",
+                reason
+            ));
+        }
+    }
+    Ok(json
+        .pointer("/candidates/0/content/parts/0/text")
+        .and_then(|t| t.as_str())
+        .filter(|text| !text.is_empty())
+        .map(str::to_string))
+}
+
+/// Highest sampling temperature Gemini accepts
+pub const MAX_TEMPERATURE: f64 = 2.0;
+
+/// The kind of work a Gemini call is doing, which picks its sampling temperature
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GeminiTask {
+    /// Answering someone who addressed the bot
+    Reply,
+    /// Unprompted comments: pondering, facts, memories, news and the like
+    Interjection,
+    /// Rewording prompts for image generation
+    ImagePrompt,
+    /// Judging, rating or fact-checking text, where consistency beats creativity
+    Evaluation,
+}
+
+/// Sampling temperature for each kind of Gemini task
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GeminiTemperatures {
+    pub reply: f64,
+    pub interjection: f64,
+    pub image_prompt: f64,
+    pub evaluation: f64,
+}
+
+impl Default for GeminiTemperatures {
+    fn default() -> Self {
+        Self {
+            reply: 1.0,
+            interjection: 1.2,
+            image_prompt: 1.2,
+            evaluation: 0.2,
+        }
+    }
+}
+
+/// Optional sampling limits sent with every request; unset ones are left to the API's defaults
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct GenerationSettings {
+    /// Nucleus sampling cutoff, between 0 and 1
+    pub top_p: Option<f64>,
+    /// Longest response, in tokens
+    pub max_output_tokens: Option<u32>,
+}
+
+impl GeminiTemperatures {
+    pub fn for_task(&self, task: GeminiTask) -> f64 {
+        match task {
+            GeminiTask::Reply => self.reply,
+            GeminiTask::Interjection => self.interjection,
+            GeminiTask::ImagePrompt => self.image_prompt,
+            GeminiTask::Evaluation => self.evaluation,
+        }
+    }
+}
+
+/// The parts of a multimodal request: media first, then the prompt text
+pub fn media_request_parts(
+    prompt: &str,
+    media: &[crate::media_utils::MediaItem],
+    youtube_urls: &[crate::media_utils::YouTubeUrl],
+) -> Vec<serde_json::Value> {
+    let mut parts = Vec::new();
+
+    for item in media {
+        parts.push(serde_json::json!({
+            "inlineData": {
+                "mimeType": item.mime_type,
+                "data": item.data
+            }
+        }));
+    }
+
+    for yt in youtube_urls {
+        parts.push(serde_json::json!({
+            "fileData": {
+                "mimeType": "video/*",
+                "fileUri": yt.url
+            }
+        }));
+    }
+
+    parts.push(serde_json::json!({"text": prompt}));
+    parts
+}
+
+/// Retries after a retryable error when GEMINI_MAX_RETRIES isn't set
+pub const DEFAULT_MAX_RETRIES: u32 = 3;
+
+// Wait before the first retry when the server doesn't say; doubled for each retry after
+const RETRY_BASE_DELAY: Duration = Duration::from_secs(2);
+
+// Longest Retry-After the bot will honor, so a reply isn't held up for minutes
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(60);
+
+/// Statuses worth retrying: rate limiting and temporary server trouble. Anything else,
+/// like a 400, would fail the same way again.
+pub fn is_retryable_status(status: reqwest::StatusCode) -> bool {
+    matches!(status.as_u16(), 429 | 500 | 502 | 503 | 504)
+}
+
+/// How long to wait before retry number `retry` (from 1): the server's Retry-After when it
+/// sent one, otherwise exponential backoff plus up to half again, scaled by `jitter` (0-1)
+pub fn retry_delay(retry: u32, retry_after: Option<Duration>, jitter: f64) -> Duration {
+    if let Some(retry_after) = retry_after {
+        return retry_after.min(MAX_RETRY_AFTER);
+    }
+    let backoff = RETRY_BASE_DELAY * 2u32.saturating_pow(retry.saturating_sub(1));
+    backoff.mul_f64(1.0 + jitter.clamp(0.0, 1.0) / 2.0)
+}
+
+// A Retry-After header given in seconds
+fn retry_after(headers: &reqwest::header::HeaderMap) -> Option<Duration> {
+    headers
+        .get(reqwest::header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+        .map(Duration::from_secs)
+}
+
+/// Whether a failure on the primary endpoint is worth repeating on the fallback.
+/// Outages, quota/billing exhaustion and exhausted retries are; content problems
+/// (safety blocks, unreadable responses) and our own rate limiter are not.
+pub fn should_use_fallback(error: &anyhow::Error) -> bool {
+    if is_rate_limited(error) {
+        return false;
+    }
+
+    let message = error.to_string();
+    ![
+        "safety filters",
+        "content recitation",
+        "terminated the response",
+        "Failed to extract text",
+    ]
+    .iter()
+    .any(|marker| message.contains(marker))
+}
+
+/// Run a request against the primary endpoint and, if it fails persistently and a fallback
+/// is configured, once more against the fallback. Logs which endpoint served the request.
+pub async fn call_with_fallback<F, Fut>(
+    primary: &GeminiEndpoint,
+    fallback: Option<&GeminiEndpoint>,
+    mut call: F,
+) -> Result<String>
+where
+    F: FnMut(GeminiEndpoint) -> Fut,
+    Fut: Future<Output = Result<String>>,
+{
+    let primary_error = match call(primary.clone()).await {
+        Ok(text) => {
+            info!("Gemini request served by {} endpoint", primary.label);
+            return Ok(text);
+        }
+        Err(e) => e,
+    };
+
+    let Some(fallback) = fallback else {
+        return Err(primary_error);
+    };
+    if !should_use_fallback(&primary_error) {
+        return Err(primary_error);
+    }
+
+    error!(
+        "Gemini {} endpoint failed ({}), trying {} endpoint {}",
+        primary.label, primary_error, fallback.label, fallback.url
+    );
+    let text = call(fallback.clone()).await?;
+    info!("Gemini request served by {} endpoint", fallback.label);
+    Ok(text)
+}
 
 #[derive(Clone)]
 pub struct GeminiClient {
-    api_key: String,
-    api_endpoint: String,
+    primary: GeminiEndpoint,
+    // Optional secondary model/project used when the primary fails persistently
+    fallback: Option<GeminiEndpoint>,
     http_client: reqwest::Client,
     prompt_templates: PromptTemplates,
     rate_limiter: RateLimiter,
@@ -20,8 +370,16 @@ pub struct GeminiClient {
     #[allow(dead_code)]
     context_messages: usize,
     log_prompts: bool,
+    temperatures: GeminiTemperatures,
+    generation: GenerationSettings,
+    max_retries: u32,
+    api_flavor: ApiFlavor,
+    // Model name sent with OpenAI-compatible requests (Gemini URLs name the model themselves)
+    model: Option<String>,
     // Track when image generation quota was exhausted
     image_quota_exhausted_until: Arc<Mutex<Option<DateTime<Utc>>>>,
+    // Today's token totals, shared by every client given the same tracker
+    usage: Arc<UsageTracker>,
 }
 
 /// Configuration for creating a GeminiClient
@@ -29,6 +387,8 @@ pub struct GeminiClient {
 pub struct GeminiConfig {
     pub api_key: String,
     pub api_endpoint: Option<String>,
+    pub fallback_api_endpoint: Option<String>,
+    pub fallback_api_key: Option<String>,
     pub prompt_wrapper: Option<String>,
     pub bot_name: String,
     pub rate_limit_minute: u32,
@@ -38,6 +398,14 @@ pub struct GeminiConfig {
     pub context_messages: usize,
     pub log_prompts: bool,
     pub personality_description: Option<String>,
+    pub uncertainty_mode: bool,
+    pub persona_examples: Vec<(String, String)>,
+    pub temperatures: GeminiTemperatures,
+    pub generation: GenerationSettings,
+    /// Retries after a rate limit or overload error, with exponential backoff
+    pub max_retries: u32,
+    pub api_flavor: ApiFlavor,
+    pub model: Option<String>,
 }
 
 impl GeminiClient {
@@ -56,6 +424,18 @@ impl GeminiClient {
             prompt_templates.set_template("general_response", &wrapper);
         }
 
+        // Ask the model to hedge or flag answers it isn't sure about
+        if config.uncertainty_mode {
+            prompt_templates.set_uncertainty_instruction(Some(
+                crate::prompt_templates::UNCERTAINTY_INSTRUCTION,
+            ));
+        }
+
+        // Few-shot examples of the bot's voice
+        if !config.persona_examples.is_empty() {
+            prompt_templates.set_persona_examples(config.persona_examples);
+        }
+
         // Create rate limiter for text generation with persistence
         let rate_limiter = RateLimiter::new_with_persistence(
             config.rate_limit_minute,
@@ -70,36 +450,68 @@ impl GeminiClient {
             "gemini_image_quota.json".to_string(),
         );
 
+        // OpenAI-compatible endpoints are configured as the server's base URL
+        let api_flavor = config.api_flavor;
+        let endpoint_url = |url: String| match api_flavor {
+            ApiFlavor::Gemini => url,
+            ApiFlavor::OpenAiCompatible => chat_completions_url(&url),
+        };
+        let primary_url = match (api_flavor, config.api_endpoint) {
+            (_, Some(url)) => endpoint_url(url),
+            (ApiFlavor::Gemini, None) => default_endpoint,
+            (ApiFlavor::OpenAiCompatible, None) => {
+                chat_completions_url(DEFAULT_OPENAI_COMPATIBLE_URL)
+            }
+        };
+
+        let fallback = config.fallback_api_endpoint.map(|url| GeminiEndpoint {
+            label: "fallback",
+            url: endpoint_url(url),
+            api_key: config
+                .fallback_api_key
+                .unwrap_or_else(|| config.api_key.clone()),
+        });
+
         Self {
-            api_key: config.api_key,
-            api_endpoint: config.api_endpoint.unwrap_or(default_endpoint),
-            http_client: reqwest::Client::new(),
+            primary: GeminiEndpoint {
+                label: "primary",
+                url: primary_url,
+                api_key: config.api_key,
+            },
+            fallback,
+            http_client: crate::http_client::shared(),
             prompt_templates,
             rate_limiter,
             image_rate_limiter,
             context_messages: config.context_messages,
             log_prompts: config.log_prompts,
+            temperatures: config.temperatures,
+            generation: config.generation,
+            max_retries: config.max_retries,
+            api_flavor,
+            model: config.model,
             image_quota_exhausted_until: Arc::new(Mutex::new(None)),
+            usage: Arc::new(UsageTracker::new(None)),
         }
     }
 
-    /// Get API quota usage statistics
-    pub async fn get_quota_stats(&self) -> (String, String, String) {
-        // Get text API usage
-        let (text_min_used, text_min_limit, text_day_used, text_day_limit) =
-            self.rate_limiter.get_usage_stats().await;
-        let text_quota = format!(
-            "{}/{} per minute, {}/{} per day",
-            text_min_used, text_min_limit, text_day_used, text_day_limit
-        );
+    /// Count this client's token usage in a shared tracker instead of its own
+    pub fn with_usage_tracker(mut self, usage: Arc<UsageTracker>) -> Self {
+        self.usage = usage;
+        self
+    }
 
-        // Get image API usage
-        let (img_min_used, img_min_limit, img_day_used, img_day_limit) =
-            self.image_rate_limiter.get_usage_stats().await;
-        let image_quota = format!(
-            "{}/{} per minute, {}/{} per day",
-            img_min_used, img_min_limit, img_day_used, img_day_limit
-        );
+    /// Tokens used so far today (UTC)
+    pub fn token_usage_today(&self) -> TokenUsage {
+        self.usage.today()
+    }
+
+    /// Get API quota usage statistics: the text API's limits, the image API's, and
+    /// whether image generation is available
+    pub async fn get_quota_stats(&self) -> (RateLimitSnapshot, String, String) {
+        // Get text and image API usage
+        let text_quota = self.rate_limiter.snapshot();
+        let image_quota = self.image_rate_limiter.snapshot().to_string();
 
         // Get image quota exhaustion status
         let image_status = if self.is_image_quota_exhausted().await {
@@ -131,6 +543,14 @@ impl GeminiClient {
         false
     }
 
+    // A copy of this client that replies at a different temperature (clamped to 0-2). The
+    // copy shares the original's rate limits.
+    pub fn with_reply_temperature(&self, temperature: f64) -> Self {
+        let mut client = self.clone();
+        client.temperatures.reply = temperature.clamp(0.0, MAX_TEMPERATURE);
+        client
+    }
+
     // Get a reference to the prompt templates
     pub fn prompt_templates(&self) -> &PromptTemplates {
         &self.prompt_templates
@@ -169,6 +589,7 @@ impl GeminiClient {
             user_name,
             &context_with_pronouns,
             user_pronouns,
+            GeminiTask::Reply,
         )
         .await
     }
@@ -181,6 +602,7 @@ impl GeminiClient {
         context_messages: &[(String, String, Option<String>, String)],
         user_pronouns: Option<&str>,
         should_respond: bool,
+        task: GeminiTask,
     ) -> Result<Option<String>> {
         // Create a modified prompt that asks for multiple responses
         let multi_response_prompt = format!(
@@ -194,6 +616,7 @@ impl GeminiClient {
                 user_name,
                 context_messages,
                 user_pronouns,
+                task,
             )
             .await
         {
@@ -212,8 +635,9 @@ impl GeminiClient {
                         let line = line.trim();
                         if line.starts_with("OPTION:") {
                             let option = line.strip_prefix("OPTION:").unwrap_or(line).trim();
+                            let option = crate::prompt_templates::apply_confidence_marker(option);
                             if !option.is_empty() && option.to_lowercase() != "pass" {
-                                Some(option.to_string())
+                                Some(option)
                             } else {
                                 None
                             }
@@ -226,7 +650,9 @@ impl GeminiClient {
                 if options.is_empty() {
                     // Fallback: use the original response if no options were parsed
                     if should_respond {
-                        Ok(Some(trimmed.to_string()))
+                        Ok(Some(crate::prompt_templates::apply_confidence_marker(
+                            trimmed,
+                        )))
                     } else {
                         Ok(None)
                     }
@@ -269,8 +695,24 @@ impl GeminiClient {
         prompt: &str,
         user_name: &str,
         context_messages: &[(String, String, Option<String>, String)],
-        _user_pronouns: Option<&str>,
+        user_pronouns: Option<&str>,
+        task: GeminiTask,
     ) -> Result<String> {
+        let formatted_prompt =
+            self.format_response_prompt(prompt, user_name, context_messages, user_pronouns);
+        self.generate_content_for(&formatted_prompt, task).await
+    }
+
+    // Wrap a prompt with the conversation context, using the prompt itself as the template
+    // when it has a {context} placeholder and the general response template otherwise.
+    // The pronouns of the person being replied to, when known, are noted at the end.
+    fn format_response_prompt(
+        &self,
+        prompt: &str,
+        user_name: &str,
+        context_messages: &[(String, String, Option<String>, String)],
+        user_pronouns: Option<&str>,
+    ) -> String {
         // Check if the prompt already contains context placeholder (meaning it's a template
         // that needs format_custom) or if it's already fully formatted (preformatted=true
         // means it already has personality/context baked in and should not be re-wrapped)
@@ -323,25 +765,149 @@ impl GeminiClient {
         };
 
         // Format the prompt using the wrapper or custom template
-        let formatted_prompt = if has_context_placeholder {
+        let formatted = if has_context_placeholder {
             // If the prompt already contains {context}, use it as a custom template
             let mut values = HashMap::new();
             values.insert("context".to_string(), context);
-            self.prompt_templates.format_custom(prompt, &values)
+            self.prompt_templates
+                .with_persona_examples(&self.prompt_templates.format_custom(prompt, &values))
         } else {
             // Otherwise use the standard general response template
             self.prompt_templates
                 .format_general_response(prompt, user_name, &context)
         };
 
-        self.generate_content(&formatted_prompt).await
+        match user_pronouns {
+            Some(pronouns) => format!(
+                "{formatted}\n\n{user_name} ({pronouns}) is the person you're replying to; use {pronouns} pronouns when referring to them."
+            ),
+            None => formatted,
+        }
     }
 
-    // Generate content with a raw prompt and retry on overload errors.
-    // Automatically detects image/video URLs in context and upgrades to multimodal.
+    /// Generate a reply like `generate_response_with_context_and_pronouns`, but through
+    /// Gemini's streamGenerateContent endpoint, yielding the text in pieces as it's written.
+    /// The whole reply, body included, must arrive within `timeout`; past that the stream
+    /// ends with an error. Only the primary endpoint is used; callers fall back to the
+    /// regular path on errors.
+    pub async fn generate_response_stream(
+        &self,
+        prompt: &str,
+        user_name: &str,
+        context_messages: &[(String, String, Option<String>, String)],
+        user_pronouns: Option<&str>,
+        task: GeminiTask,
+        timeout: Duration,
+    ) -> Result<impl Stream<Item = Result<String>> + Send + 'static> {
+        if self.api_flavor != ApiFlavor::Gemini {
+            return Err(anyhow::anyhow!(
+                "Streaming is only supported with the Gemini API"
+            ));
+        }
+        let url = stream_endpoint_url(&self.primary.url)
+            .ok_or_else(|| anyhow::anyhow!("Can't stream from endpoint {}", self.primary.url))?;
+        let formatted_prompt =
+            self.format_response_prompt(prompt, user_name, context_messages, user_pronouns);
+
+        self.rate_limiter.check().await?;
+        if self.log_prompts {
+            info!("Gemini API Streaming Prompt: {}", formatted_prompt);
+        }
+
+        let response = self
+            .authorize(self.http_client.post(&url), &self.primary)
+            .json(&self.text_request_body(&formatted_prompt, task))
+            .timeout(timeout)
+            .send()
+            .await?;
+        self.rate_limiter.record_request().await;
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(anyhow::anyhow!(
+                "Gemini API streaming error ({}): {}",
+                status,
+                body
+            ));
+        }
+
+        // Read the body as it arrives, handing out the text of each complete event
+        let state = (
+            response,
+            Vec::new(),
+            VecDeque::new(),
+            false,
+            self.usage.clone(),
+        );
+        Ok(stream::unfold(
+            state,
+            |(mut response, mut buffer, mut pending, mut done, usage)| async move {
+                loop {
+                    if let Some(item) = pending.pop_front() {
+                        return Some((item, (response, buffer, pending, done, usage)));
+                    }
+                    if done {
+                        return None;
+                    }
+                    match response.chunk().await {
+                        Ok(Some(bytes)) => {
+                            buffer.extend_from_slice(&bytes);
+                            for event in take_sse_events(&mut buffer) {
+                                match serde_json::from_str::<serde_json::Value>(&event) {
+                                    Ok(json) => {
+                                        // The final event carries the whole reply's usage
+                                        if json.pointer("/candidates/0/finishReason").is_some() {
+                                            if let Some(tokens) = TokenUsage::from_response(&json) {
+                                                usage.record(tokens).await;
+                                            }
+                                        }
+                                        match stream_chunk_text(&json) {
+                                            Ok(Some(text)) => pending.push_back(Ok(text)),
+                                            Ok(None) => {}
+                                            Err(e) => {
+                                                pending.push_back(Err(e));
+                                                done = true;
+                                                break;
+                                            }
+                                        }
+                                    }
+                                    Err(e) => {
+                                        pending.push_back(Err(anyhow::anyhow!(
+                                            "Unreadable Gemini stream event: {}",
+                                            e
+                                        )));
+                                        done = true;
+                                        break;
+                                    }
+                                }
+                            }
+                        }
+                        Ok(None) => done = true,
+                        Err(e) => {
+                            pending.push_back(Err(e.into()));
+                            done = true;
+                        }
+                    }
+                }
+            },
+        ))
+    }
+
+    // Generate a reply from a raw prompt
     pub async fn generate_content(&self, prompt: &str) -> Result<String> {
-        // Check if the prompt contains embedded media URLs from context
-        let context_media = crate::media_utils::fetch_media_from_context(prompt, 3).await;
+        self.generate_content_for(prompt, GeminiTask::Reply).await
+    }
+
+    // Generate content with a raw prompt and retry on overload errors, at the task's temperature.
+    // Automatically detects image/video URLs in context and upgrades to multimodal.
+    pub async fn generate_content_for(&self, prompt: &str, task: GeminiTask) -> Result<String> {
+        // Check if the prompt contains embedded media URLs from context (only Gemini can
+        // look at them)
+        let context_media = match self.api_flavor {
+            ApiFlavor::Gemini => crate::media_utils::fetch_media_from_context(prompt, 3).await,
+            ApiFlavor::OpenAiCompatible => Vec::new(),
+        };
 
         if !context_media.is_empty() {
             info!(
@@ -350,23 +916,106 @@ impl GeminiClient {
             );
             let clean_prompt = crate::media_utils::strip_media_urls_from_context(prompt);
             return self
-                .generate_content_with_media(&clean_prompt, &context_media, &[])
+                .generate_media_for(&clean_prompt, &context_media, &[], task)
                 .await;
         }
 
-        self.generate_content_text_only(prompt).await
+        self.generate_content_text_only(prompt, task).await
+    }
+
+    // Gemini's generationConfig: the task's temperature plus any configured limits
+    fn generation_config(&self, task: GeminiTask) -> serde_json::Value {
+        let mut config = serde_json::json!({
+            "temperature": self.temperatures.for_task(task)
+        });
+        if let Some(top_p) = self.generation.top_p {
+            config["topP"] = serde_json::json!(top_p);
+        }
+        if let Some(max_output_tokens) = self.generation.max_output_tokens {
+            config["maxOutputTokens"] = serde_json::json!(max_output_tokens);
+        }
+        config
+    }
+
+    // Request body for a text-only prompt, with the task's temperature
+    fn text_request_body(&self, prompt: &str, task: GeminiTask) -> serde_json::Value {
+        match self.api_flavor {
+            ApiFlavor::Gemini => serde_json::json!({
+                "contents": [{
+                    "parts": [{
+                        "text": prompt
+                    }]
+                }],
+                "generationConfig": self.generation_config(task)
+            }),
+            ApiFlavor::OpenAiCompatible => {
+                let mut body = serde_json::json!({
+                    "messages": [{
+                        "role": "user",
+                        "content": prompt
+                    }],
+                    "temperature": self.temperatures.for_task(task),
+                    "stream": false
+                });
+                if let Some(model) = &self.model {
+                    body["model"] = serde_json::json!(model);
+                }
+                if let Some(top_p) = self.generation.top_p {
+                    body["top_p"] = serde_json::json!(top_p);
+                }
+                if let Some(max_output_tokens) = self.generation.max_output_tokens {
+                    body["max_tokens"] = serde_json::json!(max_output_tokens);
+                }
+                body
+            }
+        }
+    }
+
+    // Attach the endpoint's API key the way its flavor expects. OpenAI-compatible servers
+    // often don't need one, so an empty key sends no header.
+    fn authorize(
+        &self,
+        request: reqwest::RequestBuilder,
+        endpoint: &GeminiEndpoint,
+    ) -> reqwest::RequestBuilder {
+        match self.api_flavor {
+            ApiFlavor::Gemini => request.header("x-goog-api-key", &endpoint.api_key),
+            ApiFlavor::OpenAiCompatible if endpoint.api_key.is_empty() => request,
+            ApiFlavor::OpenAiCompatible => request.bearer_auth(&endpoint.api_key),
+        }
     }
 
-    // Text-only content generation with retry on overload errors
-    async fn generate_content_text_only(&self, prompt: &str) -> Result<String> {
-        // Maximum number of retries
-        const MAX_RETRIES: usize = 5;
+    // The generated text in a successful response
+    fn response_text<'a>(&self, response_json: &'a serde_json::Value) -> Option<&'a str> {
+        let pointer = match self.api_flavor {
+            ApiFlavor::Gemini => "/candidates/0/content/parts/0/text",
+            ApiFlavor::OpenAiCompatible => "/choices/0/message/content",
+        };
+        response_json.pointer(pointer).and_then(|t| t.as_str())
+    }
+
+    // Text-only content generation, falling back to the secondary endpoint if configured
+    async fn generate_content_text_only(&self, prompt: &str, task: GeminiTask) -> Result<String> {
+        call_with_fallback(
+            &self.primary,
+            self.fallback.as_ref(),
+            |endpoint| async move { self.generate_text_at(&endpoint, prompt, task).await },
+        )
+        .await
+    }
 
-        // Initial delay in seconds (will be doubled each retry - exponential backoff)
-        let mut delay_secs = 10;
+    // Text-only content generation against one endpoint, retrying rate limit and overload
+    // errors with exponential backoff
+    async fn generate_text_at(
+        &self,
+        endpoint: &GeminiEndpoint,
+        prompt: &str,
+        task: GeminiTask,
+    ) -> Result<String> {
+        let max_retries = self.max_retries;
 
-        // Try up to MAX_RETRIES times
-        for attempt in 1..=MAX_RETRIES {
+        // The first attempt plus up to max_retries more
+        for retry in 0..=max_retries {
             // Check rate limits but don't record yet — only record after successful send
             self.rate_limiter.check().await?;
 
@@ -376,29 +1025,26 @@ impl GeminiClient {
             }
 
             // Prepare the request body
-            let request_body = serde_json::json!({
-                "contents": [{
-                    "parts": [{
-                        "text": prompt
-                    }]
-                }]
-            });
+            let request_body = self.text_request_body(prompt, task);
 
-            // Make the API call
+            // Make the API call, within the shared client's HTTP_TIMEOUT_SECONDS
             let response = self
-                .http_client
-                .post(&self.api_endpoint)
-                .header("x-goog-api-key", &self.api_key)
+                .authorize(self.http_client.post(&endpoint.url), endpoint)
                 .json(&request_body)
-                .timeout(Duration::from_secs(30))
                 .send()
                 .await?;
 
             // Request was sent successfully — record it for rate limiting
             self.rate_limiter.record_request().await;
 
-            // Parse the response
-            let response_json: serde_json::Value = response.json().await?;
+            // Parse the response; error responses may not be JSON at all
+            let status = response.status();
+            let retry_after = retry_after(response.headers());
+            let response_json: serde_json::Value = if status.is_success() {
+                response.json().await?
+            } else {
+                response.json().await.unwrap_or_default()
+            };
 
             // Log the full raw response if logging is enabled
             if self.log_prompts {
@@ -409,14 +1055,29 @@ impl GeminiClient {
                 }
             }
 
-            // Check for error in response
-            if let Some(error) = response_json.get("error") {
-                let error_message = error
-                    .get("message")
-                    .and_then(|m| m.as_str())
-                    .unwrap_or("Unknown API error");
-                let error_code = error.get("code").and_then(|c| c.as_u64()).unwrap_or(0);
+            if let Some(usage) = TokenUsage::from_response(&response_json) {
+                self.usage.record(usage).await;
+            }
 
+            // Check for an error in the response, or an error status without one
+            let api_error = match response_json.get("error") {
+                Some(error) => Some((
+                    error
+                        .get("message")
+                        .and_then(|m| m.as_str())
+                        .unwrap_or("Unknown API error"),
+                    error
+                        .get("code")
+                        .and_then(|c| c.as_u64())
+                        .unwrap_or(status.as_u16().into()),
+                )),
+                None if !status.is_success() => Some((
+                    status.canonical_reason().unwrap_or("Unknown API error"),
+                    status.as_u16().into(),
+                )),
+                None => None,
+            };
+            if let Some((error_message, error_code)) = api_error {
                 // Check for billing/spending cap errors - return a friendly message
                 if error_message.contains("spending cap")
                     || error_message.contains("BillingHardLimit")
@@ -430,36 +1091,34 @@ impl GeminiClient {
                     ));
                 }
 
-                // Check if this is a retryable error
-                if error_message.contains("overloaded")
+                // Check if this is a retryable error (never a bad request)
+                if is_retryable_status(status)
+                    || error_message.contains("overloaded")
                     || error_message.contains("try again later")
                     || (error_code == 500 && error_message.contains("Internal error encountered"))
                 {
-                    if attempt < MAX_RETRIES {
-                        // Log that we're retrying
-                        info!(
-                            "Gemini API retryable error (attempt {}/{}): {} (code {}), retrying in {} seconds...",
-                            attempt, MAX_RETRIES, error_message, error_code, delay_secs
+                    if retry < max_retries {
+                        let delay = retry_delay(retry + 1, retry_after, rand::rng().random());
+                        debug!(
+                            "Gemini API retryable error (retry {}/{}): {} (code {}), retrying in {:?}",
+                            retry + 1,
+                            max_retries,
+                            error_message,
+                            error_code,
+                            delay
                         );
-
-                        // Wait before retrying
-                        tokio::time::sleep(Duration::from_secs(delay_secs)).await;
-
-                        // Double the delay for next attempt (exponential backoff)
-                        delay_secs *= 2;
-
-                        // Continue to the next retry attempt
+                        tokio::time::sleep(delay).await;
                         continue;
                     } else {
                         // If we've exhausted retries, return a special error
                         // that callers can check for to avoid showing error messages to users
                         error!(
                             "Gemini API retryable error, maximum retries ({}) exceeded: {} (code {})",
-                            MAX_RETRIES, error_message, error_code
+                            max_retries, error_message, error_code
                         );
                         return Err(anyhow::anyhow!(
                             "SILENT_ERROR: Gemini API retryable error after {} retries: {}",
-                            MAX_RETRIES,
+                            max_retries,
                             error_message
                         ));
                     }
@@ -492,15 +1151,7 @@ impl GeminiClient {
             }
 
             // Extract the generated text
-            if let Some(text) = response_json
-                .get("candidates")
-                .and_then(|c| c.get(0))
-                .and_then(|c| c.get("content"))
-                .and_then(|c| c.get("parts"))
-                .and_then(|p| p.get(0))
-                .and_then(|p| p.get("text"))
-                .and_then(|t| t.as_str())
-            {
+            if let Some(text) = self.response_text(&response_json) {
                 // Log the response if enabled
                 if self.log_prompts {
                     info!("Gemini API Response Text: {}", text);
@@ -548,17 +1199,57 @@ impl GeminiClient {
         Err(anyhow::anyhow!("Maximum retry attempts exceeded"))
     }
 
-    /// Generate content with text and media (images/video) using multimodal input
+    /// Generate a reply with text and media (images/video) using multimodal input
     pub async fn generate_content_with_media(
         &self,
         prompt: &str,
         media: &[crate::media_utils::MediaItem],
         youtube_urls: &[crate::media_utils::YouTubeUrl],
+    ) -> Result<String> {
+        self.generate_media_for(prompt, media, youtube_urls, GeminiTask::Reply)
+            .await
+    }
+
+    // Multimodal content generation at the task's temperature
+    async fn generate_media_for(
+        &self,
+        prompt: &str,
+        media: &[crate::media_utils::MediaItem],
+        youtube_urls: &[crate::media_utils::YouTubeUrl],
+        task: GeminiTask,
     ) -> Result<String> {
         if media.is_empty() && youtube_urls.is_empty() {
-            return self.generate_content_text_only(prompt).await;
+            return self.generate_content_text_only(prompt, task).await;
+        }
+        if self.api_flavor == ApiFlavor::OpenAiCompatible {
+            info!(
+                "Ignoring {} media items and {} YouTube URLs: the OpenAI-compatible API is used text-only",
+                media.len(),
+                youtube_urls.len()
+            );
+            return self.generate_content_text_only(prompt, task).await;
         }
 
+        call_with_fallback(
+            &self.primary,
+            self.fallback.as_ref(),
+            |endpoint| async move {
+                self.generate_media_at(&endpoint, prompt, media, youtube_urls, task)
+                    .await
+            },
+        )
+        .await
+    }
+
+    // Multimodal content generation against one endpoint
+    async fn generate_media_at(
+        &self,
+        endpoint: &GeminiEndpoint,
+        prompt: &str,
+        media: &[crate::media_utils::MediaItem],
+        youtube_urls: &[crate::media_utils::YouTubeUrl],
+        task: GeminiTask,
+    ) -> Result<String> {
         self.rate_limiter.acquire().await?;
 
         if self.log_prompts {
@@ -570,37 +1261,15 @@ impl GeminiClient {
             );
         }
 
-        // Build parts array: media first, then text
-        let mut parts = Vec::new();
-
-        for item in media {
-            parts.push(serde_json::json!({
-                "inline_data": {
-                    "mime_type": item.mime_type,
-                    "data": item.data
-                }
-            }));
-        }
-
-        for yt in youtube_urls {
-            parts.push(serde_json::json!({
-                "file_data": {
-                    "mime_type": "video/*",
-                    "file_uri": yt.url
-                }
-            }));
-        }
-
-        parts.push(serde_json::json!({"text": prompt}));
-
         let request_body = serde_json::json!({
-            "contents": [{"parts": parts}]
+            "contents": [{"parts": media_request_parts(prompt, media, youtube_urls)}],
+            "generationConfig": self.generation_config(task)
         });
 
         let response = self
             .http_client
-            .post(&self.api_endpoint)
-            .header("x-goog-api-key", &self.api_key)
+            .post(&endpoint.url)
+            .header("x-goog-api-key", &endpoint.api_key)
             .json(&request_body)
             .timeout(Duration::from_secs(60)) // longer timeout for media
             .send()
@@ -614,6 +1283,10 @@ impl GeminiClient {
             }
         }
 
+        if let Some(usage) = TokenUsage::from_response(&response_json) {
+            self.usage.record(usage).await;
+        }
+
         // Check for errors
         if let Some(error) = response_json.get("error") {
             let msg = error
@@ -654,6 +1327,8 @@ mod tests {
         let client = GeminiClient::new(GeminiConfig {
             api_key: "test_key".to_string(),
             api_endpoint: None,
+            fallback_api_endpoint: None,
+            fallback_api_key: None,
             prompt_wrapper: None,
             bot_name: "TestBot".to_string(),
             rate_limit_minute: 15,
@@ -663,6 +1338,13 @@ mod tests {
             context_messages: 5,
             log_prompts: false,
             personality_description: None,
+            uncertainty_mode: false,
+            persona_examples: Vec::new(),
+            temperatures: GeminiTemperatures::default(),
+            generation: GenerationSettings::default(),
+            max_retries: DEFAULT_MAX_RETRIES,
+            api_flavor: ApiFlavor::Gemini,
+            model: None,
         });
 
         // Initially, quota should not be exhausted
@@ -674,6 +1356,8 @@ mod tests {
         let client = GeminiClient::new(GeminiConfig {
             api_key: "test_key".to_string(),
             api_endpoint: None,
+            fallback_api_endpoint: None,
+            fallback_api_key: None,
             prompt_wrapper: None,
             bot_name: "TestBot".to_string(),
             rate_limit_minute: 15,
@@ -683,6 +1367,13 @@ mod tests {
             context_messages: 5,
             log_prompts: false,
             personality_description: None,
+            uncertainty_mode: false,
+            persona_examples: Vec::new(),
+            temperatures: GeminiTemperatures::default(),
+            generation: GenerationSettings::default(),
+            max_retries: DEFAULT_MAX_RETRIES,
+            api_flavor: ApiFlavor::Gemini,
+            model: None,
         });
 
         // Manually set the exhaustion time to yesterday (simulating time passage)
@@ -700,6 +1391,8 @@ mod tests {
         let client = GeminiClient::new(GeminiConfig {
             api_key: "test_key".to_string(),
             api_endpoint: None,
+            fallback_api_endpoint: None,
+            fallback_api_key: None,
             prompt_wrapper: None,
             bot_name: "TestBot".to_string(),
             rate_limit_minute: 10,
@@ -709,8 +1402,488 @@ mod tests {
             context_messages: 5,
             log_prompts: false,
             personality_description: None,
+            uncertainty_mode: false,
+            persona_examples: Vec::new(),
+            temperatures: GeminiTemperatures::default(),
+            generation: GenerationSettings::default(),
+            max_retries: DEFAULT_MAX_RETRIES,
+            api_flavor: ApiFlavor::Gemini,
+            model: None,
         });
 
         assert!(!client.is_image_quota_exhausted().await);
     }
+
+    #[test]
+    fn test_task_temperature_in_request_body() {
+        let client = GeminiClient::new(GeminiConfig {
+            api_key: "test_key".to_string(),
+            api_endpoint: None,
+            fallback_api_endpoint: None,
+            fallback_api_key: None,
+            prompt_wrapper: None,
+            bot_name: "TestBot".to_string(),
+            rate_limit_minute: 10,
+            rate_limit_day: 1000,
+            image_rate_limit_minute: 2,
+            image_rate_limit_day: 50,
+            context_messages: 5,
+            log_prompts: false,
+            personality_description: None,
+            uncertainty_mode: false,
+            persona_examples: Vec::new(),
+            temperatures: GeminiTemperatures {
+                reply: 0.7,
+                interjection: 1.5,
+                image_prompt: 1.25,
+                evaluation: 0.0,
+            },
+            generation: GenerationSettings::default(),
+            max_retries: DEFAULT_MAX_RETRIES,
+            api_flavor: ApiFlavor::Gemini,
+            model: None,
+        });
+
+        let body = client.text_request_body("rate these", GeminiTask::Evaluation);
+        assert_eq!(body["generationConfig"]["temperature"], 0.0);
+        assert_eq!(body["contents"][0]["parts"][0]["text"], "rate these");
+
+        let body = client.text_request_body("say something", GeminiTask::Interjection);
+        assert_eq!(body["generationConfig"]["temperature"], 1.5);
+        let body = client.text_request_body("hi", GeminiTask::Reply);
+        assert_eq!(body["generationConfig"]["temperature"], 0.7);
+    }
+
+    #[test]
+    fn test_response_prompt_includes_user_pronouns() {
+        let client = GeminiClient::new(GeminiConfig {
+            api_key: "test_key".to_string(),
+            api_endpoint: None,
+            fallback_api_endpoint: None,
+            fallback_api_key: None,
+            prompt_wrapper: None,
+            bot_name: "TestBot".to_string(),
+            rate_limit_minute: 10,
+            rate_limit_day: 1000,
+            image_rate_limit_minute: 2,
+            image_rate_limit_day: 50,
+            context_messages: 5,
+            log_prompts: false,
+            personality_description: None,
+            uncertainty_mode: false,
+            persona_examples: Vec::new(),
+            temperatures: GeminiTemperatures::default(),
+            generation: GenerationSettings::default(),
+            max_retries: DEFAULT_MAX_RETRIES,
+            api_flavor: ApiFlavor::Gemini,
+            model: None,
+        });
+
+        let prompt = client.format_response_prompt("hello there", "Alice", &[], Some("she/her"));
+        assert!(prompt.contains("hello there"));
+        assert!(prompt.contains("Alice (she/her)"));
+
+        // Custom templates get them too
+        let prompt =
+            client.format_response_prompt("Chat: {context}", "Alice", &[], Some("they/them"));
+        assert!(prompt.contains("Alice (they/them)"));
+
+        let prompt = client.format_response_prompt("hello there", "Alice", &[], None);
+        assert!(!prompt.contains("pronouns when referring"));
+    }
+
+    #[test]
+    fn test_request_body_per_api_flavor() {
+        let config = GeminiConfig {
+            api_key: String::new(),
+            api_endpoint: Some("http://localhost:1234/v1/".to_string()),
+            fallback_api_endpoint: None,
+            fallback_api_key: None,
+            prompt_wrapper: None,
+            bot_name: "TestBot".to_string(),
+            rate_limit_minute: 10,
+            rate_limit_day: 1000,
+            image_rate_limit_minute: 2,
+            image_rate_limit_day: 50,
+            context_messages: 5,
+            log_prompts: false,
+            personality_description: None,
+            uncertainty_mode: false,
+            persona_examples: Vec::new(),
+            temperatures: GeminiTemperatures::default(),
+            generation: GenerationSettings::default(),
+            max_retries: DEFAULT_MAX_RETRIES,
+            api_flavor: ApiFlavor::OpenAiCompatible,
+            model: Some("llama3.1".to_string()),
+        };
+
+        let openai = GeminiClient::new(config.clone());
+        assert_eq!(
+            openai.primary.url,
+            "http://localhost:1234/v1/chat/completions"
+        );
+        assert_eq!(
+            openai.text_request_body("hi", GeminiTask::Evaluation),
+            serde_json::json!({
+                "model": "llama3.1",
+                "messages": [{"role": "user", "content": "hi"}],
+                "temperature": 0.2,
+                "stream": false
+            })
+        );
+        let response = serde_json::json!({
+            "choices": [{"message": {"role": "assistant", "content": "hello"}}]
+        });
+        assert_eq!(openai.response_text(&response), Some("hello"));
+
+        let gemini = GeminiClient::new(GeminiConfig {
+            api_endpoint: None,
+            api_flavor: ApiFlavor::Gemini,
+            ..config
+        });
+        assert_eq!(
+            gemini.text_request_body("hi", GeminiTask::Evaluation),
+            serde_json::json!({
+                "contents": [{"parts": [{"text": "hi"}]}],
+                "generationConfig": {"temperature": 0.2}
+            })
+        );
+        assert_eq!(gemini.response_text(&response), None);
+        let response = serde_json::json!({
+            "candidates": [{"content": {"parts": [{"text": "hello"}]}}]
+        });
+        assert_eq!(gemini.response_text(&response), Some("hello"));
+
+        assert_eq!(
+            ApiFlavor::parse("OpenAI-Compatible"),
+            Some(ApiFlavor::OpenAiCompatible)
+        );
+        assert_eq!(ApiFlavor::parse("claude"), None);
+        assert_eq!(
+            chat_completions_url("http://localhost:11434"),
+            "http://localhost:11434/v1/chat/completions"
+        );
+        assert_eq!(
+            chat_completions_url("http://gpu/v1/chat/completions"),
+            "http://gpu/v1/chat/completions"
+        );
+    }
+
+    #[test]
+    fn test_generation_settings_only_sent_when_configured() {
+        let config = GeminiConfig {
+            api_key: String::new(),
+            api_endpoint: None,
+            fallback_api_endpoint: None,
+            fallback_api_key: None,
+            prompt_wrapper: None,
+            bot_name: "TestBot".to_string(),
+            rate_limit_minute: 10,
+            rate_limit_day: 1000,
+            image_rate_limit_minute: 2,
+            image_rate_limit_day: 50,
+            context_messages: 5,
+            log_prompts: false,
+            personality_description: None,
+            uncertainty_mode: false,
+            persona_examples: Vec::new(),
+            temperatures: GeminiTemperatures::default(),
+            generation: GenerationSettings::default(),
+            max_retries: DEFAULT_MAX_RETRIES,
+            api_flavor: ApiFlavor::Gemini,
+            model: None,
+        };
+
+        let unset = GeminiClient::new(config.clone());
+        assert_eq!(
+            unset.text_request_body("hi", GeminiTask::Reply)["generationConfig"],
+            serde_json::json!({"temperature": 1.0})
+        );
+
+        let generation = GenerationSettings {
+            top_p: Some(0.8),
+            max_output_tokens: Some(256),
+        };
+        let gemini = GeminiClient::new(GeminiConfig {
+            generation,
+            ..config.clone()
+        });
+        assert_eq!(
+            gemini.text_request_body("hi", GeminiTask::Reply)["generationConfig"],
+            serde_json::json!({"temperature": 1.0, "topP": 0.8, "maxOutputTokens": 256})
+        );
+
+        let openai = GeminiClient::new(GeminiConfig {
+            generation,
+            api_flavor: ApiFlavor::OpenAiCompatible,
+            ..config
+        });
+        let body = openai.text_request_body("hi", GeminiTask::Reply);
+        assert_eq!(body["top_p"], serde_json::json!(0.8));
+        assert_eq!(body["max_tokens"], serde_json::json!(256));
+    }
+
+    // Serve one HTTP response per connection, in order, on a local port. The thread returns
+    // how many requests it answered.
+    fn mock_server(responses: Vec<String>) -> (String, std::thread::JoinHandle<usize>) {
+        use std::io::{Read, Write};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        let handle = std::thread::spawn(move || {
+            for response in &responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                // Read the headers, then as much body as they announce
+                let mut request = Vec::new();
+                let mut buffer = [0; 4096];
+                let body_start = loop {
+                    let read = stream.read(&mut buffer).unwrap();
+                    request.extend_from_slice(&buffer[..read]);
+                    if let Some(at) = request.windows(4).position(|w| w == b"\r\n\r\n") {
+                        break at + 4;
+                    }
+                };
+                let headers = String::from_utf8_lossy(&request[..body_start]).to_lowercase();
+                let content_length = headers
+                    .lines()
+                    .find_map(|line| line.strip_prefix("content-length:"))
+                    .and_then(|length| length.trim().parse::<usize>().ok())
+                    .unwrap_or(0);
+                while request.len() < body_start + content_length {
+                    let read = stream.read(&mut buffer).unwrap();
+                    request.extend_from_slice(&buffer[..read]);
+                }
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+            responses.len()
+        });
+        (url, handle)
+    }
+
+    fn http_response(status: &str, headers: &str, body: &str) -> String {
+        format!(
+            "HTTP/1.1 {status}\r\n{headers}Content-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+            body.len()
+        )
+    }
+
+    #[tokio::test]
+    async fn test_retries_overloaded_responses() {
+        let overloaded = http_response("503 Service Unavailable", "Retry-After: 0\r\n", "");
+        let ok = http_response(
+            "200 OK",
+            "",
+            r#"{"candidates":[{"content":{"parts":[{"text":"Hello there"}]},"finishReason":"STOP"}]}"#,
+        );
+        let (url, server) = mock_server(vec![overloaded.clone(), overloaded, ok]);
+
+        let config = GeminiConfig {
+            api_key: "test_key".to_string(),
+            api_endpoint: Some(url),
+            fallback_api_endpoint: None,
+            fallback_api_key: None,
+            prompt_wrapper: None,
+            bot_name: "TestBot".to_string(),
+            rate_limit_minute: 10,
+            rate_limit_day: 1000,
+            image_rate_limit_minute: 2,
+            image_rate_limit_day: 50,
+            context_messages: 5,
+            log_prompts: false,
+            personality_description: None,
+            uncertainty_mode: false,
+            persona_examples: Vec::new(),
+            temperatures: GeminiTemperatures::default(),
+            generation: GenerationSettings::default(),
+            max_retries: DEFAULT_MAX_RETRIES,
+            api_flavor: ApiFlavor::Gemini,
+            model: None,
+        };
+
+        // Two 503s are retried and the third attempt succeeds
+        // Count requests in memory so the test doesn't touch the quota file
+        let mut client = GeminiClient::new(config.clone());
+        client.rate_limiter = RateLimiter::in_memory(10, 1000);
+        assert_eq!(client.generate_content("hi").await.unwrap(), "Hello there");
+        assert_eq!(server.join().unwrap(), 3);
+
+        // A bad request is not retried
+        let bad_request = http_response(
+            "400 Bad Request",
+            "",
+            r#"{"error":{"code":400,"message":"Invalid JSON payload"}}"#,
+        );
+        let (url, server) = mock_server(vec![bad_request]);
+        let mut client = GeminiClient::new(GeminiConfig {
+            api_endpoint: Some(url),
+            ..config
+        });
+        client.rate_limiter = RateLimiter::in_memory(10, 1000);
+        let error = client.generate_content("hi").await.unwrap_err();
+        assert!(error.to_string().contains("Invalid JSON payload"));
+        assert_eq!(server.join().unwrap(), 1);
+    }
+
+    #[test]
+    fn test_media_request_parts() {
+        use crate::media_utils::{MediaItem, YouTubeUrl};
+
+        let media = [
+            MediaItem::from_bytes("image/png", b"\x89PNG fake"),
+            MediaItem::from_bytes("image/jpeg", b"fake jpeg"),
+        ];
+        let youtube = [YouTubeUrl {
+            url: "https://youtu.be/abc123".to_string(),
+        }];
+
+        assert_eq!(
+            media_request_parts("what does this say?", &media, &youtube),
+            vec![
+                serde_json::json!({"inlineData": {"mimeType": "image/png", "data": "iVBORyBmYWtl"}}),
+                serde_json::json!({"inlineData": {"mimeType": "image/jpeg", "data": "ZmFrZSBqcGVn"}}),
+                serde_json::json!({"fileData": {"mimeType": "video/*", "fileUri": "https://youtu.be/abc123"}}),
+                serde_json::json!({"text": "what does this say?"}),
+            ]
+        );
+        assert_eq!(
+            media_request_parts("hi", &[], &[]),
+            vec![serde_json::json!({"text": "hi"})]
+        );
+    }
+
+    #[test]
+    fn test_retry_delay() {
+        assert!(is_retryable_status(reqwest::StatusCode::TOO_MANY_REQUESTS));
+        assert!(is_retryable_status(
+            reqwest::StatusCode::SERVICE_UNAVAILABLE
+        ));
+        assert!(!is_retryable_status(reqwest::StatusCode::BAD_REQUEST));
+
+        // Exponential backoff with up to 50% jitter, unless the server says how long
+        assert_eq!(retry_delay(1, None, 0.0), Duration::from_secs(2));
+        assert_eq!(retry_delay(3, None, 0.0), Duration::from_secs(8));
+        assert_eq!(retry_delay(3, None, 1.0), Duration::from_secs(12));
+        assert_eq!(
+            retry_delay(3, Some(Duration::from_secs(5)), 1.0),
+            Duration::from_secs(5)
+        );
+        assert_eq!(
+            retry_delay(1, Some(Duration::from_secs(3600)), 0.0),
+            MAX_RETRY_AFTER
+        );
+    }
+
+    #[test]
+    fn test_stream_events_and_urls() {
+        assert_eq!(
+            stream_endpoint_url(&endpoint_for_model("gemini-2.5-flash")).as_deref(),
+            Some("https://generativelanguage.googleapis.com/v1beta/models/gemini-2.5-flash:streamGenerateContent?alt=sse")
+        );
+        assert_eq!(
+            stream_endpoint_url("https://proxy/models/m:generateContent?key=abc").as_deref(),
+            Some("https://proxy/models/m:streamGenerateContent?alt=sse&key=abc")
+        );
+        assert_eq!(stream_endpoint_url("http://localhost:11434"), None);
+
+        // Events split across reads are held back until they're complete
+        let chunk = |text: &str| {
+            serde_json::json!({"candidates": [{"content": {"parts": [{"text": text}]}}]})
+                .to_string()
+        };
+        let mut buffer =
+            format!("data: {}\r\n\r\ndata: {}", chunk("Hel"), chunk("lo")).into_bytes();
+        let events = take_sse_events(&mut buffer);
+        assert_eq!(events.len(), 1);
+        let first: serde_json::Value = serde_json::from_str(&events[0]).unwrap();
+        assert_eq!(stream_chunk_text(&first).unwrap().as_deref(), Some("Hel"));
+        buffer.extend_from_slice(b"\n\n");
+        let events = take_sse_events(&mut buffer);
+        let second: serde_json::Value = serde_json::from_str(&events[0]).unwrap();
+        assert_eq!(stream_chunk_text(&second).unwrap().as_deref(), Some("lo"));
+        assert!(buffer.is_empty());
+
+        let usage_only = serde_json::json!({"usageMetadata": {"totalTokenCount": 12}});
+        assert_eq!(stream_chunk_text(&usage_only).unwrap(), None);
+        let blocked = serde_json::json!({"candidates": [{"finishReason": "SAFETY"}]});
+        assert!(stream_chunk_text(&blocked).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_primary_failure_routes_to_fallback() {
+        let primary = GeminiEndpoint {
+            label: "primary",
+            url: endpoint_for_model("gemini-3.1-flash-lite"),
+            api_key: "primary_key".to_string(),
+        };
+        let fallback = GeminiEndpoint {
+            label: "fallback",
+            url: endpoint_for_model("gemini-2.5-flash"),
+            api_key: "fallback_key".to_string(),
+        };
+        assert_eq!(
+            fallback.url,
+            "https://generativelanguage.googleapis.com/v1beta/models/gemini-2.5-flash:generateContent"
+        );
+
+        // Primary is down: the fallback serves the request
+        let calls = std::sync::Mutex::new(Vec::new());
+        let result = call_with_fallback(&primary, Some(&fallback), |endpoint| {
+            calls.lock().unwrap().push(endpoint.api_key.clone());
+            async move {
+                if endpoint.label == "primary" {
+                    Err(anyhow::anyhow!(
+                        "SILENT_ERROR: Gemini API retryable error after 5 retries: The model is overloaded"
+                    ))
+                } else {
+                    Ok("hello from the fallback".to_string())
+                }
+            }
+        })
+        .await;
+        assert_eq!(result.unwrap(), "hello from the fallback");
+        assert_eq!(*calls.lock().unwrap(), vec!["primary_key", "fallback_key"]);
+
+        // Content problems aren't retried elsewhere
+        let calls = std::sync::Mutex::new(0);
+        let result = call_with_fallback(&primary, Some(&fallback), |_| {
+            *calls.lock().unwrap() += 1;
+            async { Err(anyhow::anyhow!("Gemini API safety filters triggered.")) }
+        })
+        .await;
+        assert!(result.is_err());
+        assert_eq!(*calls.lock().unwrap(), 1);
+
+        // Without a fallback the primary error is returned as-is
+        let result = call_with_fallback(&primary, None, |_| async {
+            Err(anyhow::anyhow!("Gemini API error: quota exceeded"))
+        })
+        .await;
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Gemini API error: quota exceeded"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_with_timeout() {
+        // A call that would hang well past the limit is cut off with TimedOut
+        let stuck = async {
+            tokio::time::sleep(Duration::from_secs(5)).await;
+            Ok("too late")
+        };
+        let error = with_timeout(Duration::from_millis(50), stuck)
+            .await
+            .unwrap_err();
+        assert!(is_timed_out(&error));
+        assert!(!is_rate_limited(&error));
+
+        // Quick calls and their own errors pass straight through
+        let quick = with_timeout(Duration::from_secs(5), async { Ok(7) }).await;
+        assert_eq!(quick.unwrap(), 7);
+        let failed: Result<()> = with_timeout(Duration::from_secs(5), async {
+            Err(anyhow::anyhow!("boom"))
+        })
+        .await;
+        assert!(!is_timed_out(&failed.unwrap_err()));
+    }
 }
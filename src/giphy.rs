@@ -1,3 +1,4 @@
+use crate::http_client;
 use anyhow::Result;
 use tracing::{error, info};
 
@@ -20,8 +21,7 @@ impl GiphyClient {
             urlencoding::encode(query),
         );
 
-        let client = reqwest::Client::new();
-        let response = client
+        let response = http_client::shared()
             .get(&url)
             .timeout(std::time::Duration::from_secs(10))
             .send()
@@ -0,0 +1,175 @@
+use crate::db_utils::HistorySearchHit;
+use crate::message_store::MessageStore;
+use crate::utils::no_ping_message;
+use anyhow::Result;
+use serenity::all::Http;
+use serenity::model::channel::Message;
+use tracing::error;
+
+// Results shown per page
+const RESULTS_PER_PAGE: usize = 5;
+// Never page further back than this many results
+const MAX_RESULTS: usize = 50;
+// Longest snippet of a matching message we'll show
+const SNIPPET_CHARS: usize = 150;
+
+/// Split `!search` arguments into the search term and page number (`-p N` / `--page N`)
+pub fn parse_search_args(args: &str) -> (String, usize) {
+    let mut words: Vec<&str> = args.split_whitespace().collect();
+    let mut page = 1;
+
+    if words.len() >= 2 {
+        let flag = words[words.len() - 2];
+        if flag == "-p" || flag == "--page" {
+            if let Ok(p) = words[words.len() - 1].parse::<usize>() {
+                page = p.max(1);
+                words.truncate(words.len() - 2);
+            }
+        }
+    }
+
+    (words.join(" "), page)
+}
+
+/// Link that jumps to a stored message, if we know enough to build one
+fn jump_link(hit: &HistorySearchHit) -> Option<String> {
+    if hit.message_id == "0" || hit.channel_id == "0" {
+        return None;
+    }
+    let guild = if hit.guild_id.is_empty() {
+        "@me"
+    } else {
+        hit.guild_id.as_str()
+    };
+    Some(format!(
+        "https://discord.com/channels/{}/{}/{}",
+        guild, hit.channel_id, hit.message_id
+    ))
+}
+
+fn format_hit(hit: &HistorySearchHit) -> String {
+    let name = if hit.display_name.is_empty() {
+        &hit.author
+    } else {
+        &hit.display_name
+    };
+    let content = hit.content.replace('\n', " ");
+    let snippet = if content.chars().count() > SNIPPET_CHARS {
+        let truncated: String = content.chars().take(SNIPPET_CHARS).collect();
+        format!("{}…", truncated.trim_end())
+    } else {
+        content
+    };
+
+    match jump_link(hit) {
+        Some(link) => format!("**{name}** <t:{}:R>: {snippet}\n<{link}>", hit.timestamp),
+        None => format!("**{name}** <t:{}:R>: {snippet}", hit.timestamp),
+    }
+}
+
+// This function will be called from main.rs to handle the !search command
+pub async fn handle_search_command(
+    http: &Http,
+    msg: &Message,
+    args: &str,
+    store: &dyn MessageStore,
+) -> Result<()> {
+    let (term, page) = parse_search_args(args);
+    if term.is_empty() {
+        msg.channel_id
+            .say(http, "Usage: !search <term> [-p page]")
+            .await?;
+        return Ok(());
+    }
+
+    let offset = (page - 1) * RESULTS_PER_PAGE;
+    if offset >= MAX_RESULTS {
+        msg.channel_id
+            .say(
+                http,
+                format!(
+                    "I only show the {MAX_RESULTS} most recent matches. Try a more specific search."
+                ),
+            )
+            .await?;
+        return Ok(());
+    }
+
+    let guild_id = msg.guild_id.map(|id| id.to_string()).unwrap_or_default();
+    let channel_id = msg.channel_id.to_string();
+
+    // Fetch one extra to know whether there's another page
+    let hits = match store
+        .search_history(
+            term.clone(),
+            guild_id,
+            channel_id,
+            RESULTS_PER_PAGE + 1,
+            offset,
+        )
+        .await
+    {
+        Ok(hits) => Some(hits),
+        Err(e) => {
+            error!("Error searching message history: {:?}", e);
+            None
+        }
+    };
+    let Some(mut hits) = hits else {
+        msg.channel_id
+            .say(http, "Error searching message history")
+            .await?;
+        return Ok(());
+    };
+
+    if hits.is_empty() {
+        let reply = if page == 1 {
+            format!("No messages found matching \"{term}\".")
+        } else {
+            format!("No more messages matching \"{term}\".")
+        };
+        msg.channel_id
+            .send_message(http, no_ping_message(reply))
+            .await?;
+        return Ok(());
+    }
+
+    let has_more = hits.len() > RESULTS_PER_PAGE && offset + RESULTS_PER_PAGE < MAX_RESULTS;
+    hits.truncate(RESULTS_PER_PAGE);
+
+    let mut response = format!("Messages matching \"{term}\" (page {page}):\n");
+    for hit in &hits {
+        response.push_str(&format_hit(hit));
+        response.push('\n');
+    }
+    if has_more {
+        response.push_str(&format!("More results: `!search {term} -p {}`", page + 1));
+    }
+
+    // The term and quoted messages may contain mentions; don't re-ping anyone
+    msg.channel_id
+        .send_message(http, no_ping_message(response))
+        .await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_search_args() {
+        assert_eq!(parse_search_args("mitchell"), ("mitchell".to_string(), 1));
+        assert_eq!(
+            parse_search_args("  torgo  hands -p 3"),
+            ("torgo hands".to_string(), 3)
+        );
+        assert_eq!(
+            parse_search_args("torgo --page 0"),
+            ("torgo".to_string(), 1)
+        );
+        assert_eq!(parse_search_args("-p"), ("-p".to_string(), 1));
+        assert_eq!(parse_search_args(""), (String::new(), 1));
+    }
+}
@@ -0,0 +1,93 @@
+use lazy_static::lazy_static;
+use reqwest::Client;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::time::Duration;
+use tracing::warn;
+
+/// How the bot identifies itself; Wikipedia in particular asks API clients to
+pub const USER_AGENT: &str = "CrowBot/1.0 (https://github.com/mwstowe/crowtdiscordbot)";
+
+/// Give up on a host that won't even accept the connection after this long
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Default for HTTP_TIMEOUT_SECONDS. Long enough for the screenshot sites to render a GIF.
+pub const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+// Give up on any request after this many seconds, so a hung endpoint can't stall a handler
+static REQUEST_TIMEOUT_SECS: AtomicU64 = AtomicU64::new(DEFAULT_REQUEST_TIMEOUT.as_secs());
+
+// How many times the shared client has been built; should only ever be once
+static BUILDS: AtomicUsize = AtomicUsize::new(0);
+
+lazy_static! {
+    static ref SHARED: Client = {
+        BUILDS.fetch_add(1, Ordering::SeqCst);
+        build(Duration::from_secs(
+            REQUEST_TIMEOUT_SECS.load(Ordering::Relaxed),
+        ))
+    };
+}
+
+fn build(request_timeout: Duration) -> Client {
+    Client::builder()
+        .user_agent(USER_AGENT)
+        .connect_timeout(CONNECT_TIMEOUT.min(request_timeout))
+        .timeout(request_timeout)
+        .build()
+        .expect("Failed to create HTTP client")
+}
+
+/// Set the overall request timeout. Only takes effect before the first call to [`shared`].
+pub fn set_request_timeout(timeout: Duration) {
+    if BUILDS.load(Ordering::SeqCst) > 0 {
+        warn!("HTTP client already built; request timeout change ignored");
+    }
+    REQUEST_TIMEOUT_SECS.store(timeout.as_secs().max(1), Ordering::Relaxed);
+}
+
+/// The bot's HTTP client. Every caller gets a handle to the same connection pool, so
+/// repeat requests to a site reuse its connections instead of a fresh TLS handshake.
+pub fn shared() -> Client {
+    SHARED.clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+    use std::net::TcpListener;
+    use std::time::Instant;
+
+    #[test]
+    fn test_shared_client_is_reused() {
+        let first = shared();
+        let second = shared();
+        assert_eq!(BUILDS.load(Ordering::SeqCst), 1);
+        drop((first, second));
+        let _third = shared();
+        assert_eq!(BUILDS.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_request_timeout() {
+        // A server that takes the request and never answers
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            if let Ok((mut stream, _)) = listener.accept() {
+                let mut buf = [0u8; 1024];
+                let _ = stream.read(&mut buf);
+                std::thread::sleep(Duration::from_secs(5));
+            }
+        });
+
+        let started = Instant::now();
+        let error = build(Duration::from_secs(1))
+            .get(&url)
+            .send()
+            .await
+            .unwrap_err();
+        assert!(error.is_timeout());
+        assert!(started.elapsed() < Duration::from_secs(4));
+    }
+}
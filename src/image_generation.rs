@@ -1,107 +1,255 @@
+use crate::feature_unavailable::{FeatureUnavailable, FeatureUnavailableMessages};
+use crate::gemini_api::{GeminiClient, GeminiTask};
 use crate::rate_limiter::RateLimiter;
 use anyhow::Result;
-use serenity::all::{Channel, CreateMessage};
+use base64::Engine;
+use serenity::all::CreateMessage;
+use serenity::async_trait;
 use serenity::builder::CreateAttachment;
 use serenity::model::channel::Message;
 use serenity::prelude::*;
+use std::future::Future;
 use std::time::Duration;
 use tokio_util::sync::CancellationToken;
 use tracing::{error, info};
 
-pub async fn handle_imagine_command(
-    ctx: &Context,
-    msg: &Message,
+/// Why a single image generation attempt failed
+#[derive(Debug, Clone, PartialEq)]
+pub enum ImageAttemptError {
+    /// The model refused the prompt (content or safety filter)
+    Rejected(String),
+    /// Timeout, rate limit or server error that may go away if we just ask again
+    Transient(String),
+    /// Something retrying won't fix, like a bad key or no usable models
+    Fatal(String),
+}
+
+/// What to do after a failed attempt
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RetryAction {
+    /// Try the same prompt again
+    Retry,
+    /// Ask Gemini to reword the prompt, then try again
+    RewriteAndRetry,
+    GiveUp,
+}
+
+/// How hard to try before giving up on an image
+#[derive(Debug, Clone, Copy)]
+pub struct ImageRetryPolicy {
+    /// Total attempts, including the first
+    pub max_attempts: usize,
+    /// Reword rejected prompts via Gemini before retrying
+    pub rewrite_rejected_prompts: bool,
+}
+
+impl Default for ImageRetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 2,
+            rewrite_rejected_prompts: true,
+        }
+    }
+}
+
+impl ImageRetryPolicy {
+    /// Decide what to do after `attempts_made` attempts, the last of which failed with `error`
+    pub fn next_action(&self, error: &ImageAttemptError, attempts_made: usize) -> RetryAction {
+        if attempts_made >= self.max_attempts {
+            return RetryAction::GiveUp;
+        }
+
+        match error {
+            ImageAttemptError::Transient(_) => RetryAction::Retry,
+            // Sending the exact prompt that was just refused would only be refused again
+            ImageAttemptError::Rejected(_) if self.rewrite_rejected_prompts => {
+                RetryAction::RewriteAndRetry
+            }
+            ImageAttemptError::Rejected(_) | ImageAttemptError::Fatal(_) => RetryAction::GiveUp,
+        }
+    }
+}
+
+// Sort a failed HTTP response into rejected / transient / fatal
+fn classify_failure(status: u16, body: &str) -> ImageAttemptError {
+    let body_lower = body.to_lowercase();
+    let mentions_filter = ["safety", "content policy", "moderation", "nsfw", "flagged"]
+        .iter()
+        .any(|word| body_lower.contains(word));
+
+    if mentions_filter || matches!(status, 400 | 403 | 451) {
+        ImageAttemptError::Rejected(format!("HTTP {status}"))
+    } else if matches!(status, 408 | 429) || status >= 500 {
+        ImageAttemptError::Transient(format!("HTTP {status}"))
+    } else {
+        ImageAttemptError::Fatal(format!("HTTP {status}"))
+    }
+}
+
+/// Run `generate` until it succeeds or `policy` says to stop, rewording the prompt with
+/// `rewrite` after rejections. Returns the result and the prompt that produced it.
+pub async fn generate_with_retry<T, G, GFut, R, RFut>(
     prompt: &str,
-    imagine_channels: &[String],
-    pollinations_api_key: Option<&str>,
-    rate_limiter: &RateLimiter,
-    http_client: &reqwest::Client,
-) -> Result<()> {
-    // Check if the command is being used in an allowed channel
-    let channel_name = match msg.channel_id.to_channel(&ctx.http).await {
-        Ok(channel) => match channel {
-            Channel::Guild(guild_channel) => guild_channel.name,
-            _ => String::new(),
-        },
-        Err(_) => String::new(),
-    };
+    policy: ImageRetryPolicy,
+    mut generate: G,
+    mut rewrite: R,
+) -> Result<(T, String), ImageAttemptError>
+where
+    G: FnMut(String) -> GFut,
+    GFut: Future<Output = Result<T, ImageAttemptError>>,
+    R: FnMut(String) -> RFut,
+    RFut: Future<Output = Option<String>>,
+{
+    let mut current_prompt = prompt.to_string();
+    let mut attempts_made = 0;
 
-    if !imagine_channels.is_empty() && !imagine_channels.contains(&channel_name) {
-        let channel_list = if imagine_channels.len() == 1 {
-            format!("the #{} channel", imagine_channels[0])
-        } else {
-            let channels = imagine_channels
-                .iter()
-                .map(|c| format!("#{c}"))
-                .collect::<Vec<_>>()
-                .join(", ");
-            format!("one of these channels: {channels}")
+    loop {
+        attempts_made += 1;
+        let error = match generate(current_prompt.clone()).await {
+            Ok(result) => return Ok((result, current_prompt)),
+            Err(e) => e,
         };
-        msg.reply(
-            &ctx.http,
-            format!("Image generation is only available in {channel_list}. Please try your command there."),
-        )
-        .await?;
-        return Ok(());
-    }
 
-    // Start typing indicator and keep refreshing it until generation completes
-    let typing_channel_id = msg.channel_id;
-    let typing_http = ctx.http.clone();
-    let typing_cancel = CancellationToken::new();
-    let typing_cancel_clone = typing_cancel.clone();
-    tokio::spawn(async move {
-        loop {
-            if let Err(e) = typing_channel_id.broadcast_typing(&typing_http).await {
-                error!(
-                    "Failed to send typing indicator for image generation: {:?}",
-                    e
+        match policy.next_action(&error, attempts_made) {
+            RetryAction::GiveUp => return Err(error),
+            RetryAction::Retry => {
+                info!(
+                    "Image attempt {} failed ({:?}), retrying",
+                    attempts_made, error
                 );
             }
-            tokio::select! {
-                _ = tokio::time::sleep(Duration::from_secs(8)) => {}
-                _ = typing_cancel_clone.cancelled() => break,
-            }
+            RetryAction::RewriteAndRetry => match rewrite(current_prompt.clone()).await {
+                Some(rewritten) => {
+                    info!("Image prompt rejected, retrying as: {}", rewritten);
+                    current_prompt = rewritten;
+                }
+                None => return Err(error),
+            },
         }
-    });
+    }
+}
 
-    info!("Generating image via Pollinations for prompt: {}", prompt);
+/// Raw bytes of one generated image
+pub type ImageBytes = Vec<u8>;
 
-    // Check rate limits before making the request
-    if let Err(e) = rate_limiter.acquire().await {
-        error!("Image generation rate limited: {:?}", e);
-        msg.reply(
-            &ctx.http,
-            "Image generation is currently rate limited. Please try again in a moment.",
-        )
-        .await?;
-        return Ok(());
+/// Size of the image to ask for
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ImageOptions {
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Default for ImageOptions {
+    fn default() -> Self {
+        Self {
+            width: 1024,
+            height: 1024,
+        }
     }
+}
 
-    // Truncate very long prompts — image models don't benefit from extremely detailed text
-    // and long URL-encoded prompts can cause timeouts
-    let truncated_prompt = if prompt.len() > 500 {
-        info!("Truncating image prompt from {} to 500 chars", prompt.len());
-        &prompt[..prompt.rfind(' ').unwrap_or(500).min(500)]
-    } else {
-        prompt
-    };
+/// Something that turns a prompt into images. One attempt per call; retrying and prompt
+/// rewording are handled by `generate_with_retry`.
+#[async_trait]
+pub trait ImageBackend: Send + Sync {
+    /// Short name for logs, matching the IMAGE_BACKEND setting
+    fn name(&self) -> &'static str;
+
+    async fn generate(
+        &self,
+        prompt: &str,
+        opts: ImageOptions,
+    ) -> Result<Vec<ImageBytes>, ImageAttemptError>;
+}
+
+/// How to authenticate with a self-hosted image endpoint
+#[derive(Debug, Clone, PartialEq)]
+pub enum ImageApiAuth {
+    None,
+    /// Sent as `Authorization: Bearer <token>`
+    Bearer(String),
+    /// HTTP basic auth, as set with Automatic1111's `--api-auth user:password`
+    Basic {
+        username: String,
+        password: String,
+    },
+}
 
-    let encoded_prompt = urlencoding::encode(truncated_prompt);
-    let timeout = Duration::from_secs(90);
+/// Which image backend IMAGE_BACKEND selected
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum ImageBackendSettings {
+    /// The hosted Pollinations API (needs POLLINATIONS_API_KEY)
+    #[default]
+    Pollinations,
+    /// A self-hosted Automatic1111-compatible `/sdapi/v1/txt2img` endpoint
+    StableDiffusion { url: String, auth: ImageApiAuth },
+}
+
+/// Build the configured backend; None when it's missing what it needs to run
+pub fn build_image_backend(
+    settings: &ImageBackendSettings,
+    pollinations_api_key: Option<&str>,
+    http_client: reqwest::Client,
+) -> Option<Box<dyn ImageBackend>> {
+    match settings {
+        ImageBackendSettings::Pollinations => pollinations_api_key.map(|key| {
+            Box::new(PollinationsBackend {
+                http_client,
+                key: key.to_string(),
+            }) as Box<dyn ImageBackend>
+        }),
+        ImageBackendSettings::StableDiffusion { url, auth } => {
+            Some(Box::new(StableDiffusionBackend {
+                http_client,
+                url: url.trim_end_matches('/').to_string(),
+                auth: auth.clone(),
+            }))
+        }
+    }
+}
+
+/// Images from the hosted Pollinations API
+pub struct PollinationsBackend {
+    http_client: reqwest::Client,
+    key: String,
+}
+
+#[async_trait]
+impl ImageBackend for PollinationsBackend {
+    fn name(&self) -> &'static str {
+        "pollinations"
+    }
+
+    // One pass over the Pollinations models for a prompt
+    async fn generate(
+        &self,
+        prompt: &str,
+        opts: ImageOptions,
+    ) -> Result<Vec<ImageBytes>, ImageAttemptError> {
+        // Truncate very long prompts — image models don't benefit from extremely detailed text
+        // and long URL-encoded prompts can cause timeouts
+        let truncated_prompt = if prompt.len() > 500 {
+            info!("Truncating image prompt from {} to 500 chars", prompt.len());
+            &prompt[..prompt.rfind(' ').unwrap_or(500).min(500)]
+        } else {
+            prompt
+        };
+
+        let encoded_prompt = urlencoding::encode(truncated_prompt);
+        let timeout = Duration::from_secs(90);
 
-    let image_bytes = if let Some(key) = pollinations_api_key {
         // Try models in order of quality, falling back on 402 (payment required)
         let models = ["gptimage", "flux"];
-        let mut result = None;
 
         for model in models {
             let url = format!(
-                "https://gen.pollinations.ai/image/{encoded_prompt}?model={model}&width=1024&height=1024&nologo=true"
+                "https://gen.pollinations.ai/image/{encoded_prompt}?model={model}&width={}&height={}&nologo=true",
+                opts.width, opts.height
             );
-            let resp = http_client
+            let resp = self
+                .http_client
                 .get(&url)
-                .header("Authorization", format!("Bearer {key}"))
+                .header("Authorization", format!("Bearer {}", self.key))
                 .timeout(timeout)
                 .send()
                 .await;
@@ -109,50 +257,263 @@ pub async fn handle_imagine_command(
             match resp {
                 Ok(r) if r.status().is_success() => {
                     info!("Image generated successfully with model: {}", model);
-                    result = Some(r.bytes().await?);
-                    break;
+                    return match r.bytes().await {
+                        Ok(bytes) if !bytes.is_empty() => Ok(vec![bytes.to_vec()]),
+                        Ok(_) => Err(ImageAttemptError::Transient("empty image".to_string())),
+                        Err(e) => Err(ImageAttemptError::Transient(e.to_string())),
+                    };
                 }
                 Ok(r) if r.status().as_u16() == 402 => {
                     info!("Model {} returned 402, trying next model", model);
                     continue;
                 }
                 Ok(r) => {
+                    let status = r.status().as_u16();
+                    let body = r.text().await.unwrap_or_default();
                     error!(
-                        "Pollinations API error with model {}: HTTP {}",
-                        model,
-                        r.status()
+                        "Pollinations API error with model {}: HTTP {} {}",
+                        model, status, body
                     );
-                    break;
+                    return Err(classify_failure(status, &body));
                 }
                 Err(e) => {
                     error!("Pollinations API request failed: {:?}", e);
-                    break;
+                    return Err(ImageAttemptError::Transient(e.to_string()));
                 }
             }
         }
 
-        result
-    } else {
-        error!("No Pollinations API key configured - image generation requires a key");
+        Err(ImageAttemptError::Fatal(
+            "no image models available".to_string(),
+        ))
+    }
+}
+
+/// Images from a self-hosted Stable Diffusion server speaking the Automatic1111 API
+/// (also served by Forge, SD.Next and ComfyUI bridges)
+pub struct StableDiffusionBackend {
+    http_client: reqwest::Client,
+    url: String,
+    auth: ImageApiAuth,
+}
+
+#[derive(serde::Deserialize)]
+struct Txt2ImgResponse {
+    #[serde(default)]
+    images: Vec<String>,
+}
+
+// Pull the images out of a txt2img response; they come back base64-encoded
+fn decode_txt2img_images(body: &str) -> Result<Vec<ImageBytes>, ImageAttemptError> {
+    let response: Txt2ImgResponse = serde_json::from_str(body)
+        .map_err(|e| ImageAttemptError::Fatal(format!("unexpected response: {e}")))?;
+    let images = response
+        .images
+        .iter()
+        .map(|image| {
+            // Some servers prefix a data URL header
+            let data = image
+                .split_once(',')
+                .map_or(image.as_str(), |(_, data)| data);
+            base64::engine::general_purpose::STANDARD
+                .decode(data)
+                .map_err(|e| ImageAttemptError::Fatal(format!("bad image data: {e}")))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    if images.is_empty() {
+        return Err(ImageAttemptError::Transient(
+            "no images returned".to_string(),
+        ));
+    }
+    Ok(images)
+}
+
+#[async_trait]
+impl ImageBackend for StableDiffusionBackend {
+    fn name(&self) -> &'static str {
+        "stable_diffusion"
+    }
+
+    async fn generate(
+        &self,
+        prompt: &str,
+        opts: ImageOptions,
+    ) -> Result<Vec<ImageBytes>, ImageAttemptError> {
+        let request = self
+            .http_client
+            .post(format!("{}/sdapi/v1/txt2img", self.url))
+            .json(&serde_json::json!({
+                "prompt": prompt,
+                "width": opts.width,
+                "height": opts.height,
+                "batch_size": 1,
+            }))
+            // Local GPUs can be slow, especially on the first request after loading a model
+            .timeout(Duration::from_secs(180));
+        let request = match &self.auth {
+            ImageApiAuth::None => request,
+            ImageApiAuth::Bearer(token) => request.bearer_auth(token),
+            ImageApiAuth::Basic { username, password } => {
+                request.basic_auth(username, Some(password))
+            }
+        };
+
+        match request.send().await {
+            Ok(r) if r.status().is_success() => {
+                let body = r
+                    .text()
+                    .await
+                    .map_err(|e| ImageAttemptError::Transient(e.to_string()))?;
+                let images = decode_txt2img_images(&body)?;
+                info!("Generated {} images with Stable Diffusion", images.len());
+                Ok(images)
+            }
+            Ok(r) => {
+                let status = r.status().as_u16();
+                let body = r.text().await.unwrap_or_default();
+                error!("Stable Diffusion API error: HTTP {} {}", status, body);
+                Err(classify_failure(status, &body))
+            }
+            Err(e) => {
+                error!("Stable Diffusion API request failed: {:?}", e);
+                Err(ImageAttemptError::Transient(e.to_string()))
+            }
+        }
+    }
+}
+
+// Ask Gemini to reword a refused prompt; None if it can't or won't
+async fn rewrite_rejected_prompt(gemini_client: &GeminiClient, prompt: &str) -> Option<String> {
+    let rewrite_prompt = gemini_client
+        .prompt_templates()
+        .format_image_prompt_rewrite(prompt);
+
+    match gemini_client
+        .generate_content_for(&rewrite_prompt, GeminiTask::ImagePrompt)
+        .await
+    {
+        Ok(response) => {
+            let rewritten = response.trim().trim_matches(['"', '\'', '`']).trim();
+            if rewritten.is_empty()
+                || rewritten.eq_ignore_ascii_case("pass")
+                || rewritten.eq_ignore_ascii_case(prompt)
+                || crate::unknown_command::is_prompt_echo(rewritten)
+            {
+                None
+            } else {
+                Some(rewritten.to_string())
+            }
+        }
+        Err(e) => {
+            error!("Error rewriting image prompt: {:?}", e);
+            None
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub async fn handle_imagine_command(
+    ctx: &Context,
+    msg: &Message,
+    prompt: &str,
+    backend: Option<&dyn ImageBackend>,
+    rate_limiter: &RateLimiter,
+    gemini_client: Option<&GeminiClient>,
+    retry_policy: ImageRetryPolicy,
+) -> Result<()> {
+    // Start typing indicator and keep refreshing it until generation completes
+    let typing_channel_id = msg.channel_id;
+    let typing_http = ctx.http.clone();
+    let typing_cancel = CancellationToken::new();
+    let typing_cancel_clone = typing_cancel.clone();
+    tokio::spawn(async move {
+        loop {
+            if let Err(e) = typing_channel_id.broadcast_typing(&typing_http).await {
+                error!(
+                    "Failed to send typing indicator for image generation: {:?}",
+                    e
+                );
+            }
+            tokio::select! {
+                _ = tokio::time::sleep(Duration::from_secs(8)) => {}
+                _ = typing_cancel_clone.cancelled() => break,
+            }
+        }
+    });
+
+    let Some(backend) = backend else {
+        error!("No image backend configured - the default Pollinations backend requires a key");
         typing_cancel.cancel();
+        // Callers normally check for a backend first and use the configured wording
         msg.reply(
             &ctx.http,
-            "Image generation is not configured. A Pollinations API key is required.",
+            FeatureUnavailableMessages::default().message(FeatureUnavailable::ImageGeneration),
         )
         .await?;
         return Ok(());
     };
 
+    info!(
+        "Generating image via {} for prompt: {}",
+        backend.name(),
+        prompt
+    );
+
+    // Check rate limits before making the request
+    if let Err(e) = rate_limiter.acquire().await {
+        error!("Image generation rate limited: {:?}", e);
+        msg.reply(
+            &ctx.http,
+            "Image generation is currently rate limited. Please try again in a moment.",
+        )
+        .await?;
+        return Ok(());
+    }
+
+    // The first attempt was already counted against the rate limit above
+    let mut first_attempt = true;
+    let result = generate_with_retry(
+        prompt,
+        retry_policy,
+        |attempt_prompt| {
+            let is_retry = !std::mem::replace(&mut first_attempt, false);
+            async move {
+                if is_retry && rate_limiter.acquire().await.is_err() {
+                    return Err(ImageAttemptError::Fatal("rate limited".to_string()));
+                }
+                backend
+                    .generate(&attempt_prompt, ImageOptions::default())
+                    .await
+            }
+        },
+        |rejected_prompt| async move {
+            match gemini_client {
+                Some(gemini_client) => {
+                    rewrite_rejected_prompt(gemini_client, &rejected_prompt).await
+                }
+                None => None,
+            }
+        },
+    )
+    .await;
+
     // Stop the typing indicator
     typing_cancel.cancel();
 
-    match image_bytes {
-        Some(bytes) => {
-            let attachment = CreateAttachment::bytes(bytes, "imagine.jpg");
-            let message_content = format!("Here's what I imagine for: {prompt}");
+    match result {
+        Ok((images, final_prompt)) => {
+            let message_content = if final_prompt == prompt {
+                format!("Here's what I imagine for: {prompt}")
+            } else {
+                format!("Here's what I imagine for: {prompt}\n(reworded to: {final_prompt})")
+            };
+            let attachments = images.into_iter().enumerate().map(|(i, bytes)| match i {
+                0 => CreateAttachment::bytes(bytes, "imagine.jpg"),
+                _ => CreateAttachment::bytes(bytes, format!("imagine_{}.jpg", i + 1)),
+            });
             let builder = CreateMessage::default()
                 .content(message_content)
-                .add_file(attachment);
+                .add_files(attachments);
 
             if let Err(e) = msg.channel_id.send_message(&ctx.http, builder).await {
                 error!("Failed to send generated image: {:?}", e);
@@ -160,14 +521,201 @@ pub async fn handle_imagine_command(
                     .await?;
             }
         }
-        None => {
-            msg.reply(
-                &ctx.http,
-                "Sorry, I couldn't generate that image. Please try again.",
-            )
-            .await?;
+        Err(e) => {
+            error!("Image generation failed: {:?}", e);
+            let reply = match e {
+                ImageAttemptError::Rejected(_) => {
+                    "Sorry, the image generator refused that prompt. Try describing it differently."
+                }
+                ImageAttemptError::Transient(_) => {
+                    "Sorry, the image service isn't responding right now. Please try again later."
+                }
+                ImageAttemptError::Fatal(_) => {
+                    "Sorry, I couldn't generate that image. Please try again."
+                }
+            };
+            msg.reply(&ctx.http, reply).await?;
         }
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    #[test]
+    fn test_next_action() {
+        let policy = ImageRetryPolicy::default();
+        let rejected = ImageAttemptError::Rejected("HTTP 400".to_string());
+        let transient = ImageAttemptError::Transient("timeout".to_string());
+        let fatal = ImageAttemptError::Fatal("HTTP 401".to_string());
+
+        assert_eq!(policy.next_action(&transient, 1), RetryAction::Retry);
+        assert_eq!(
+            policy.next_action(&rejected, 1),
+            RetryAction::RewriteAndRetry
+        );
+        assert_eq!(policy.next_action(&fatal, 1), RetryAction::GiveUp);
+        assert_eq!(policy.next_action(&transient, 2), RetryAction::GiveUp);
+
+        let no_rewrite = ImageRetryPolicy {
+            max_attempts: 3,
+            rewrite_rejected_prompts: false,
+        };
+        assert_eq!(no_rewrite.next_action(&rejected, 1), RetryAction::GiveUp);
+        assert_eq!(no_rewrite.next_action(&transient, 2), RetryAction::Retry);
+
+        assert_eq!(
+            classify_failure(400, ""),
+            ImageAttemptError::Rejected("HTTP 400".to_string())
+        );
+        assert_eq!(
+            classify_failure(500, "flagged by moderation"),
+            ImageAttemptError::Rejected("HTTP 500".to_string())
+        );
+        assert_eq!(
+            classify_failure(503, ""),
+            ImageAttemptError::Transient("HTTP 503".to_string())
+        );
+        assert_eq!(
+            classify_failure(401, ""),
+            ImageAttemptError::Fatal("HTTP 401".to_string())
+        );
+    }
+
+    // Returns one image per prompt word, refusing anything that mentions "gore"
+    struct MockBackend;
+
+    #[async_trait]
+    impl ImageBackend for MockBackend {
+        fn name(&self) -> &'static str {
+            "mock"
+        }
+
+        async fn generate(
+            &self,
+            prompt: &str,
+            _opts: ImageOptions,
+        ) -> Result<Vec<ImageBytes>, ImageAttemptError> {
+            if prompt.contains("gore") {
+                return Err(ImageAttemptError::Rejected("HTTP 400".to_string()));
+            }
+            Ok(prompt
+                .split_whitespace()
+                .map(|word| word.as_bytes().to_vec())
+                .collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_image_backends() {
+        let http_client = crate::http_client::shared();
+        let backend = |settings: &ImageBackendSettings, key: Option<&str>| {
+            build_image_backend(settings, key, http_client.clone()).map(|b| b.name())
+        };
+        assert_eq!(
+            backend(&ImageBackendSettings::Pollinations, Some("key")),
+            Some("pollinations")
+        );
+        assert_eq!(backend(&ImageBackendSettings::Pollinations, None), None);
+        let self_hosted = ImageBackendSettings::StableDiffusion {
+            url: "http://gpu:7860/".to_string(),
+            auth: ImageApiAuth::None,
+        };
+        assert_eq!(backend(&self_hosted, None), Some("stable_diffusion"));
+
+        // Any backend plugs into the retry loop
+        let mock: Box<dyn ImageBackend> = Box::new(MockBackend);
+        let (images, final_prompt) = generate_with_retry(
+            "zombie gore",
+            ImageRetryPolicy::default(),
+            |p| {
+                let mock = &mock;
+                async move { mock.generate(&p, ImageOptions::default()).await }
+            },
+            |p| async move { Some(p.replace("gore", "ketchup")) },
+        )
+        .await
+        .unwrap();
+        assert_eq!(final_prompt, "zombie ketchup");
+        assert_eq!(images, vec![b"zombie".to_vec(), b"ketchup".to_vec()]);
+
+        // Automatic1111 sends images back base64-encoded, sometimes as data URLs
+        assert_eq!(
+            decode_txt2img_images(
+                r#"{"images": ["aGVsbG8=", "data:image/png;base64,d29ybGQ="], "info": "{}"}"#
+            ),
+            Ok(vec![b"hello".to_vec(), b"world".to_vec()])
+        );
+        assert!(matches!(
+            decode_txt2img_images(r#"{"images": []}"#),
+            Err(ImageAttemptError::Transient(_))
+        ));
+        assert!(matches!(
+            decode_txt2img_images("<html>"),
+            Err(ImageAttemptError::Fatal(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_generate_with_retry_rewrites_rejected_prompts() {
+        let policy = ImageRetryPolicy::default();
+
+        // Mock generator refuses anything mentioning "gore"; mock rewriter tones it down
+        let seen_prompts = RefCell::new(Vec::new());
+        let generate = |p: String| {
+            seen_prompts.borrow_mut().push(p.clone());
+            async move {
+                if p.contains("gore") {
+                    Err(ImageAttemptError::Rejected("HTTP 400".to_string()))
+                } else {
+                    Ok(p.len())
+                }
+            }
+        };
+        let rewrite = |p: String| async move { Some(p.replace("gore", "ketchup")) };
+
+        let (_, final_prompt) = generate_with_retry("zombie gore", policy, generate, rewrite)
+            .await
+            .unwrap();
+        assert_eq!(final_prompt, "zombie ketchup");
+        assert_eq!(
+            *seen_prompts.borrow(),
+            vec!["zombie gore".to_string(), "zombie ketchup".to_string()]
+        );
+
+        // Rewriter gives up: the rejection is reported after one attempt
+        let attempts = RefCell::new(0);
+        let result = generate_with_retry(
+            "zombie gore",
+            policy,
+            |_| {
+                *attempts.borrow_mut() += 1;
+                async { Err::<usize, _>(ImageAttemptError::Rejected("HTTP 400".to_string())) }
+            },
+            |_| async { None },
+        )
+        .await;
+        assert!(matches!(result, Err(ImageAttemptError::Rejected(_))));
+        assert_eq!(*attempts.borrow(), 1);
+
+        // Transient errors retry the same prompt until attempts run out
+        let attempts = RefCell::new(0);
+        let result = generate_with_retry(
+            "a cat",
+            policy,
+            |p| {
+                *attempts.borrow_mut() += 1;
+                assert_eq!(p, "a cat");
+                async { Err::<usize, _>(ImageAttemptError::Transient("timeout".to_string())) }
+            },
+            |_| async { panic!("transient errors shouldn't rewrite the prompt") },
+        )
+        .await;
+        assert!(matches!(result, Err(ImageAttemptError::Transient(_))));
+        assert_eq!(*attempts.borrow(), policy.max_attempts);
+    }
+}
@@ -0,0 +1,88 @@
+use crate::adaptive::InterjectionKind;
+use rand::RngExt;
+use serenity::model::id::ChannelId;
+use tracing::info;
+
+/// What scales every interjection roll for one message
+#[derive(Clone, Copy, Debug)]
+pub struct RollContext {
+    pub channel_id: ChannelId,
+    pub silence_multiplier: f64,
+    /// Damping after a recent interjection (0 while interjections are held off)
+    pub recency_multiplier: f64,
+    /// Why interjections are held off right now, if they are
+    pub held_off: Option<&'static str>,
+    /// Set by !interject: this type fires regardless of its chance and no other type does
+    pub forced: Option<InterjectionKind>,
+}
+
+impl RollContext {
+    /// A context where only `kind` fires, for testing it on demand
+    pub fn forced(channel_id: ChannelId, kind: InterjectionKind) -> Self {
+        Self {
+            channel_id,
+            silence_multiplier: 1.0,
+            recency_multiplier: 1.0,
+            held_off: None,
+            forced: Some(kind),
+        }
+    }
+}
+
+/// The chance actually rolled: `base` scaled by `multiplier`, kept between 0 and 1
+pub fn adjusted_probability(base: f64, multiplier: f64) -> f64 {
+    let adjusted = base * multiplier;
+    if adjusted.is_nan() {
+        return 0.0;
+    }
+    adjusted.clamp(0.0, 1.0)
+}
+
+// "1 in N" for the configured chance, for the trigger log line
+fn odds(base: f64) -> String {
+    if base > 0.0 {
+        format!("1 in {:.0}", 1.0 / base)
+    } else {
+        "disabled".to_string()
+    }
+}
+
+/// Roll for the `name` interjection at `base` probability scaled by `multiplier` (silence,
+/// recency and anything learned), logging the standard line when it triggers
+pub fn should_interject(base: f64, multiplier: f64, name: &str) -> bool {
+    let adjusted = adjusted_probability(base, multiplier);
+    let hit = rand::rng().random_bool(adjusted);
+    if hit {
+        info!(
+            "Triggered {} interjection (base: {:.2}% chance, adjusted: {:.2}%, multiplier: {:.2}x, {})",
+            name,
+            base * 100.0,
+            adjusted * 100.0,
+            multiplier,
+            odds(base)
+        );
+    }
+    hit
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_should_interject_extremes() {
+        for _ in 0..200 {
+            assert!(!should_interject(0.0, 1.0, "test"));
+            assert!(!should_interject(0.5, 0.0, "test"));
+            assert!(should_interject(1.0, 1.0, "test"));
+            // Multipliers can't push the chance past certainty
+            assert!(should_interject(0.6, 2.0, "test"));
+        }
+
+        assert!((adjusted_probability(0.1, 3.0) - 0.3).abs() < 1e-9);
+        assert_eq!(adjusted_probability(0.8, 2.0), 1.0);
+        assert_eq!(adjusted_probability(0.5, -1.0), 0.0);
+        assert_eq!(odds(0.04), "1 in 25");
+        assert_eq!(odds(0.0), "disabled");
+    }
+}
@@ -0,0 +1,320 @@
+use crate::adaptive::InterjectionKind;
+use anyhow::Result;
+use serenity::all::Http;
+use serenity::model::channel::Message;
+use serenity::model::id::ChannelId;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tokio_rusqlite::Connection as SqliteConnection;
+
+// How far back !interject-stats looks unless given a number of days
+const DEFAULT_STATS_DAYS: i64 = 7;
+
+/// What became of an interjection roll
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    /// The roll failed, so nothing was attempted
+    Missed,
+    /// The roll succeeded and the interjection is being worked on
+    Rolled,
+    Fired,
+    /// The roll succeeded but nothing was sent (the AI passed, it echoed the channel, ...)
+    Suppressed,
+}
+
+impl Outcome {
+    fn as_str(self) -> &'static str {
+        match self {
+            Outcome::Missed => "missed",
+            Outcome::Rolled => "rolled",
+            Outcome::Fired => "fired",
+            Outcome::Suppressed => "suppressed",
+        }
+    }
+}
+
+/// The odds behind one interjection roll
+#[derive(Clone, Debug)]
+pub struct Roll {
+    pub kind: InterjectionKind,
+    pub channel_id: ChannelId,
+    pub base_probability: f64,
+    pub silence_multiplier: f64,
+    pub adjusted_probability: f64,
+    /// Why interjections are held off right now (hourly cap, warm-up, ...), if they are
+    pub held_off: Option<&'static str>,
+}
+
+fn now() -> i64 {
+    chrono::Utc::now().timestamp()
+}
+
+/// Log a roll to the interjection_metrics table. Successful rolls start out as `Rolled`
+/// until `record_outcome` says what became of them.
+pub async fn record_roll(conn: Arc<Mutex<SqliteConnection>>, roll: &Roll, hit: bool) -> Result<()> {
+    let outcome = if hit {
+        Outcome::Rolled
+    } else {
+        Outcome::Missed
+    };
+    let reason = if hit { None } else { roll.held_off };
+    let params = (
+        now(),
+        roll.kind.name(),
+        roll.channel_id.to_string(),
+        roll.base_probability,
+        roll.silence_multiplier,
+        roll.adjusted_probability,
+        outcome.as_str(),
+        reason,
+    );
+
+    let conn_guard = conn.lock().await;
+    conn_guard
+        .call(move |conn| {
+            conn.execute(
+                "INSERT INTO interjection_metrics (
+                    timestamp, kind, channel_id, base_probability, silence_multiplier,
+                    adjusted_probability, outcome, reason
+                ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                params,
+            )?;
+            Ok::<_, rusqlite::Error>(())
+        })
+        .await?;
+    Ok(())
+}
+
+/// Record what became of the latest successful roll of `kind` in a channel
+pub async fn record_outcome(
+    conn: Arc<Mutex<SqliteConnection>>,
+    kind: InterjectionKind,
+    channel_id: ChannelId,
+    outcome: Outcome,
+    reason: Option<&str>,
+) -> Result<()> {
+    let params = (
+        outcome.as_str(),
+        reason.map(str::to_string),
+        kind.name(),
+        channel_id.to_string(),
+    );
+
+    let conn_guard = conn.lock().await;
+    conn_guard
+        .call(move |conn| {
+            conn.execute(
+                "UPDATE interjection_metrics SET outcome = ?1, reason = ?2
+                 WHERE id = (
+                     SELECT MAX(id) FROM interjection_metrics
+                     WHERE kind = ?3 AND channel_id = ?4 AND outcome = 'rolled'
+                 )",
+                params,
+            )?;
+            Ok::<_, rusqlite::Error>(())
+        })
+        .await?;
+    Ok(())
+}
+
+/// How one interjection type has fared
+#[derive(Debug, Default, PartialEq)]
+pub struct KindStats {
+    pub kind: String,
+    pub rolls: i64,
+    pub hits: i64,
+    pub fired: i64,
+    /// Successful rolls that sent nothing, including any still in progress
+    pub suppressed: i64,
+    /// Rolls made while interjections were held off
+    pub held_off: i64,
+}
+
+/// Per-type totals and the commonest reasons interjections didn't go out, since `since`
+pub async fn summarize(
+    conn: Arc<Mutex<SqliteConnection>>,
+    since: i64,
+) -> Result<(Vec<KindStats>, Vec<(String, i64)>)> {
+    let conn_guard = conn.lock().await;
+    let summary = conn_guard
+        .call(move |conn| {
+            let mut stmt = conn.prepare(
+                "SELECT kind, COUNT(*),
+                        SUM(outcome != 'missed'),
+                        SUM(outcome = 'fired'),
+                        SUM(outcome IN ('suppressed', 'rolled')),
+                        SUM(outcome = 'missed' AND reason IS NOT NULL)
+                 FROM interjection_metrics
+                 WHERE timestamp >= ?1
+                 GROUP BY kind ORDER BY kind",
+            )?;
+            let stats = stmt
+                .query_map([since], |row| {
+                    Ok(KindStats {
+                        kind: row.get(0)?,
+                        rolls: row.get(1)?,
+                        hits: row.get(2)?,
+                        fired: row.get(3)?,
+                        suppressed: row.get(4)?,
+                        held_off: row.get(5)?,
+                    })
+                })?
+                .collect::<Result<Vec<_>, _>>()?;
+
+            let mut stmt = conn.prepare(
+                "SELECT kind || ': ' || COALESCE(reason, 'no reason recorded'), COUNT(*)
+                 FROM interjection_metrics
+                 WHERE timestamp >= ?1 AND (outcome IN ('suppressed', 'rolled') OR reason IS NOT NULL)
+                 GROUP BY 1 ORDER BY 2 DESC LIMIT 10",
+            )?;
+            let reasons = stmt
+                .query_map([since], |row| Ok((row.get(0)?, row.get(1)?)))?
+                .collect::<Result<Vec<_>, _>>()?;
+
+            Ok::<_, rusqlite::Error>((stats, reasons))
+        })
+        .await?;
+    Ok(summary)
+}
+
+/// The !interject-stats report
+pub fn format_stats(days: i64, stats: &[KindStats], reasons: &[(String, i64)]) -> String {
+    let plural = if days == 1 { "" } else { "s" };
+    if stats.is_empty() {
+        return format!("No interjection decisions logged in the last {days} day{plural}.");
+    }
+
+    let mut lines = vec![format!("**Interjection stats (last {days} day{plural})**")];
+    for kind in stats {
+        let fire_rate = if kind.rolls > 0 {
+            kind.fired as f64 * 100.0 / kind.rolls as f64
+        } else {
+            0.0
+        };
+        lines.push(format!(
+            "**{}**: {} rolls, {} hit, {} fired ({:.2}%), {} suppressed, {} held off",
+            kind.kind, kind.rolls, kind.hits, kind.fired, fire_rate, kind.suppressed, kind.held_off
+        ));
+    }
+
+    if !reasons.is_empty() {
+        lines.push("**Why they didn't go out:**".to_string());
+        for (reason, count) in reasons {
+            lines.push(format!("- {reason} ×{count}"));
+        }
+    }
+
+    lines.join("\n")
+}
+
+// This function will be called from main.rs to handle the owner-only !interject-stats
+// command, after the owner check
+pub async fn handle_interject_stats_command(
+    http: &Http,
+    msg: &Message,
+    args: &[&str],
+    message_db: Arc<Mutex<SqliteConnection>>,
+) -> Result<()> {
+    let days = args
+        .first()
+        .and_then(|days| days.parse::<i64>().ok())
+        .filter(|days| *days > 0)
+        .unwrap_or(DEFAULT_STATS_DAYS);
+
+    let (stats, reasons) = summarize(message_db, now() - days * 24 * 60 * 60).await?;
+    crate::utils::send_without_pings(http, msg.channel_id, format_stats(days, &stats, &reasons))
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_fired_interjection_records_a_row() {
+        let conn = crate::db_utils::initialize_database(":memory:")
+            .await
+            .unwrap();
+        let channel_id = ChannelId::new(5);
+        let roll = |kind, held_off| Roll {
+            kind,
+            channel_id,
+            base_probability: 0.01,
+            silence_multiplier: 1.5,
+            adjusted_probability: 0.015,
+            held_off,
+        };
+
+        record_roll(conn.clone(), &roll(InterjectionKind::Ai, None), true)
+            .await
+            .unwrap();
+        record_outcome(
+            conn.clone(),
+            InterjectionKind::Ai,
+            channel_id,
+            Outcome::Fired,
+            None,
+        )
+        .await
+        .unwrap();
+        record_roll(conn.clone(), &roll(InterjectionKind::Ai, None), true)
+            .await
+            .unwrap();
+        record_outcome(
+            conn.clone(),
+            InterjectionKind::Ai,
+            channel_id,
+            Outcome::Suppressed,
+            Some("declined"),
+        )
+        .await
+        .unwrap();
+        record_roll(
+            conn.clone(),
+            &roll(InterjectionKind::Ai, Some("hourly cap")),
+            false,
+        )
+        .await
+        .unwrap();
+
+        let (stats, reasons) = summarize(conn.clone(), 0).await.unwrap();
+        assert_eq!(
+            stats,
+            vec![KindStats {
+                kind: "ai".to_string(),
+                rolls: 3,
+                hits: 2,
+                fired: 1,
+                suppressed: 1,
+                held_off: 1,
+            }]
+        );
+        assert!(reasons.contains(&("ai: declined".to_string(), 1)));
+        assert!(reasons.contains(&("ai: hourly cap".to_string(), 1)));
+
+        // The fired row keeps the odds it was rolled with
+        let (base, silence, outcome) = conn
+            .lock()
+            .await
+            .call(|conn| {
+                conn.query_row(
+                    "SELECT base_probability, silence_multiplier, outcome
+                     FROM interjection_metrics ORDER BY id LIMIT 1",
+                    [],
+                    |row| {
+                        Ok((
+                            row.get::<_, f64>(0)?,
+                            row.get::<_, f64>(1)?,
+                            row.get::<_, String>(2)?,
+                        ))
+                    },
+                )
+            })
+            .await
+            .unwrap();
+        assert_eq!((base, silence, outcome.as_str()), (0.01, 1.5, "fired"));
+
+        assert!(format_stats(7, &stats, &reasons).contains("**ai**: 3 rolls, 2 hit, 1 fired"));
+    }
+}
@@ -0,0 +1,117 @@
+use crate::adaptive::InterjectionKind;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+// Suppression reasons that mean something went wrong, rather than the bot choosing to stay
+// quiet (the AI passing, echoing the channel, having nothing to say, ...)
+const ERROR_REASONS: &[&str] = &[
+    "api error",
+    "database error",
+    "not configured",
+    "prompt leak",
+    "rate limited",
+    "send failed",
+];
+
+#[derive(Default)]
+struct KindCounters {
+    fired: AtomicU64,
+    passed: AtomicU64,
+    errored: AtomicU64,
+}
+
+/// How each interjection type has fared since startup, for `!stats`. Kept in memory only,
+/// so the counts reset on restart; `!interject-stats` has the persisted history.
+#[derive(Default)]
+pub struct InterjectionStats {
+    counters: [KindCounters; InterjectionKind::ALL.len()],
+}
+
+impl InterjectionStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn counters(&self, kind: InterjectionKind) -> &KindCounters {
+        let index = InterjectionKind::ALL
+            .iter()
+            .position(|k| *k == kind)
+            .unwrap_or_default();
+        &self.counters[index]
+    }
+
+    /// An interjection went out
+    pub fn record_fired(&self, kind: InterjectionKind) {
+        self.counters(kind).fired.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// An interjection that was rolled for didn't go out, for the given reason
+    pub fn record_suppressed(&self, kind: InterjectionKind, reason: &str) {
+        let counters = self.counters(kind);
+        if ERROR_REASONS.contains(&reason) {
+            counters.errored.fetch_add(1, Ordering::Relaxed);
+        } else {
+            counters.passed.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// (fired, passed, errored) for one interjection type
+    pub fn counts(&self, kind: InterjectionKind) -> (u64, u64, u64) {
+        let counters = self.counters(kind);
+        (
+            counters.fired.load(Ordering::Relaxed),
+            counters.passed.load(Ordering::Relaxed),
+            counters.errored.load(Ordering::Relaxed),
+        )
+    }
+
+    /// The `!stats` message: each type's counts and the totals, with the bot's uptime
+    pub fn format(&self, uptime: &str) -> String {
+        let mut lines = vec![format!(
+            "📊 **Interjection stats** (up {uptime}, counted since startup)"
+        )];
+        let mut totals = (0, 0, 0);
+        for kind in InterjectionKind::ALL {
+            let (fired, passed, errored) = self.counts(kind);
+            totals = (totals.0 + fired, totals.1 + passed, totals.2 + errored);
+            lines.push(format!(
+                "**{}**: {fired} fired, {passed} passed, {errored} errored",
+                kind.name()
+            ));
+        }
+        lines.push(format!(
+            "**Total**: {} fired, {} passed, {} errored",
+            totals.0, totals.1, totals.2
+        ));
+        lines.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stats_counting_and_formatting() {
+        let stats = InterjectionStats::new();
+        stats.record_fired(InterjectionKind::Mst3k);
+        stats.record_fired(InterjectionKind::Mst3k);
+        stats.record_suppressed(InterjectionKind::Ai, "declined");
+        stats.record_suppressed(InterjectionKind::Ai, "echo or bot spoke last");
+        stats.record_suppressed(InterjectionKind::News, "api error");
+
+        assert_eq!(stats.counts(InterjectionKind::Mst3k), (2, 0, 0));
+        assert_eq!(stats.counts(InterjectionKind::Ai), (0, 2, 0));
+        assert_eq!(stats.counts(InterjectionKind::News), (0, 0, 1));
+        assert_eq!(
+            stats.format("1h 2m 3s"),
+            "📊 **Interjection stats** (up 1h 2m 3s, counted since startup)\n\
+             **mst3k**: 2 fired, 0 passed, 0 errored\n\
+             **memory**: 0 fired, 0 passed, 0 errored\n\
+             **pondering**: 0 fired, 0 passed, 0 errored\n\
+             **ai**: 0 fired, 2 passed, 0 errored\n\
+             **fact**: 0 fired, 0 passed, 0 errored\n\
+             **news**: 0 fired, 0 passed, 1 errored\n\
+             **Total**: 2 fired, 2 passed, 1 errored"
+        );
+    }
+}
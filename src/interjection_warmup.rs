@@ -0,0 +1,126 @@
+use serenity::model::id::ChannelId;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::info;
+
+// How long we've watched a channel and how many messages we've seen there
+struct ChannelWarmup {
+    first_seen: Instant,
+    messages: usize,
+    warm: bool,
+}
+
+/// Keeps interjections off in each channel until the bot has built up some context there:
+/// it must have watched the channel for `min_duration`, or seen `min_messages` messages
+/// in it, whichever comes first. A `min_duration` of zero disables the warm-up, and a
+/// `min_messages` of zero means only time counts. Shared by everything holding a clone.
+#[derive(Clone)]
+pub struct InterjectionWarmup {
+    min_duration: Duration,
+    min_messages: usize,
+    channels: Arc<std::sync::Mutex<HashMap<ChannelId, ChannelWarmup>>>,
+}
+
+impl InterjectionWarmup {
+    pub fn new(min_duration: Duration, min_messages: usize) -> Self {
+        Self {
+            min_duration,
+            min_messages,
+            channels: Arc::new(std::sync::Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Count a message seen in a channel
+    pub fn observe(&self, channel_id: ChannelId) {
+        self.observe_at(channel_id, Instant::now());
+    }
+
+    /// Whether interjections are allowed in the channel yet. Starts the warm-up clock for
+    /// channels we haven't seen before.
+    pub fn is_warm(&self, channel_id: ChannelId) -> bool {
+        self.is_warm_at(channel_id, Instant::now())
+    }
+
+    fn observe_at(&self, channel_id: ChannelId, now: Instant) {
+        if self.min_duration.is_zero() {
+            return;
+        }
+
+        let mut channels = self.channels.lock().unwrap();
+        channels
+            .entry(channel_id)
+            .or_insert_with(|| ChannelWarmup {
+                first_seen: now,
+                messages: 0,
+                warm: false,
+            })
+            .messages += 1;
+    }
+
+    fn is_warm_at(&self, channel_id: ChannelId, now: Instant) -> bool {
+        if self.min_duration.is_zero() {
+            return true;
+        }
+
+        let mut channels = self.channels.lock().unwrap();
+        let channel = channels.entry(channel_id).or_insert_with(|| ChannelWarmup {
+            first_seen: now,
+            messages: 0,
+            warm: false,
+        });
+        if channel.warm {
+            return true;
+        }
+
+        let watched = now.duration_since(channel.first_seen);
+        if watched >= self.min_duration
+            || (self.min_messages > 0 && channel.messages >= self.min_messages)
+        {
+            channel.warm = true;
+            info!(
+                "Channel {} finished interjection warm-up ({} messages over {} seconds)",
+                channel_id,
+                channel.messages,
+                watched.as_secs()
+            );
+        }
+        channel.warm
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_warmup_gates_by_time_and_message_count() {
+        let warmup = InterjectionWarmup::new(Duration::from_secs(300), 3);
+        let quiet = ChannelId::new(1);
+        let busy = ChannelId::new(2);
+        let start = Instant::now();
+
+        // Time: a quiet channel warms up once five minutes have passed
+        assert!(!warmup.is_warm_at(quiet, start));
+        assert!(!warmup.is_warm_at(quiet, start + Duration::from_secs(299)));
+        assert!(warmup.is_warm_at(quiet, start + Duration::from_secs(300)));
+
+        // Message count: a busy channel warms up after enough messages
+        warmup.observe_at(busy, start);
+        warmup.observe_at(busy, start);
+        assert!(!warmup.is_warm_at(busy, start + Duration::from_secs(1)));
+        warmup.observe_at(busy, start + Duration::from_secs(2));
+        assert!(warmup.is_warm_at(busy, start + Duration::from_secs(2)));
+
+        // Only time counts when the message threshold is 0
+        let time_only = InterjectionWarmup::new(Duration::from_secs(60), 0);
+        for _ in 0..100 {
+            time_only.observe_at(busy, start);
+        }
+        assert!(!time_only.is_warm_at(busy, start));
+
+        // A zero duration disables the warm-up
+        let disabled = InterjectionWarmup::new(Duration::ZERO, 10);
+        assert!(disabled.is_warm_at(quiet, start));
+    }
+}
@@ -0,0 +1,109 @@
+use serenity::all::{Http, ReactionType};
+use serenity::model::channel::Message;
+use tracing::{error, warn};
+
+/// Keywords that get an emoji reaction instead of a reply, e.g. "pizza" → 🍕.
+/// Matching is case-insensitive on whole words, and the first keyword that matches wins,
+/// so a message never gets more than one of these reactions.
+#[derive(Clone, Debug, Default)]
+pub struct KeywordReactions {
+    reactions: Vec<(String, String)>,
+}
+
+impl KeywordReactions {
+    /// Parse a comma-separated list of `keyword=emoji` pairs, skipping malformed entries
+    pub fn parse(spec: &str) -> Self {
+        let reactions = spec
+            .split(',')
+            .filter(|entry| !entry.trim().is_empty())
+            .filter_map(|entry| {
+                let parsed = entry.split_once('=').and_then(|(keyword, emoji)| {
+                    let keyword = keyword.trim().to_lowercase();
+                    let emoji = emoji.trim().to_string();
+                    (!keyword.is_empty() && !emoji.is_empty()).then_some((keyword, emoji))
+                });
+                if parsed.is_none() {
+                    warn!("Ignoring malformed keyword reaction: {}", entry.trim());
+                }
+                parsed
+            })
+            .collect();
+
+        Self { reactions }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.reactions.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.reactions.len()
+    }
+
+    /// The emoji for the first configured keyword that appears in the message
+    pub fn emoji_for(&self, content: &str) -> Option<&str> {
+        let content = content.to_lowercase();
+        let words: Vec<&str> = content
+            .split(|c: char| !c.is_alphanumeric() && c != '\'')
+            .map(|word| word.trim_matches('\''))
+            .filter(|word| !word.is_empty())
+            .collect();
+
+        self.reactions
+            .iter()
+            .find(|(keyword, _)| {
+                // Multi-word keywords match as a run of consecutive words
+                let keyword_words: Vec<&str> = keyword.split_whitespace().collect();
+                !keyword_words.is_empty()
+                    && words
+                        .windows(keyword_words.len())
+                        .any(|window| window == keyword_words.as_slice())
+            })
+            .map(|(_, emoji)| emoji.as_str())
+    }
+
+    /// React to the message if it mentions one of the keywords; returns whether we reacted
+    pub async fn react(&self, http: &Http, msg: &Message) -> bool {
+        let Some(emoji) = self.emoji_for(&msg.content) else {
+            return false;
+        };
+
+        let reaction = match ReactionType::try_from(emoji) {
+            Ok(reaction) => reaction,
+            Err(e) => {
+                warn!("Invalid keyword reaction emoji {}: {:?}", emoji, e);
+                return false;
+            }
+        };
+
+        if let Err(e) = msg.react(http, reaction).await {
+            error!("Error adding keyword reaction: {:?}", e);
+            return false;
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keyword_to_emoji_matching() {
+        let reactions = KeywordReactions::parse("pizza=🍕, Birthday = 🎉, taco tuesday=🌮, broken");
+        assert_eq!(reactions.len(), 3);
+
+        assert_eq!(reactions.emoji_for("Who wants PIZZA tonight?"), Some("🍕"));
+        assert_eq!(reactions.emoji_for("happy birthday!!"), Some("🎉"));
+        assert_eq!(reactions.emoji_for("it's Taco Tuesday"), Some("🌮"));
+
+        // Whole words only
+        assert_eq!(reactions.emoji_for("pizzazz"), None);
+        assert_eq!(reactions.emoji_for("taco on a tuesday"), None);
+
+        // Only one reaction per message: the first configured keyword wins
+        assert_eq!(reactions.emoji_for("birthday pizza"), Some("🍕"));
+
+        assert!(KeywordReactions::parse("").is_empty());
+    }
+}
@@ -1,9 +1,9 @@
+use crate::feature_unavailable::{FeatureUnavailable, FeatureUnavailableMessages};
+use crate::message_store::MessageStore;
 use anyhow::Result;
 use serenity::all::Message;
 use std::sync::Arc;
 use std::time::{SystemTime, UNIX_EPOCH};
-use tokio::sync::Mutex;
-use tokio_rusqlite::Connection as SqliteConnection;
 use tracing::error;
 
 pub struct LastSeenFinder;
@@ -13,78 +13,6 @@ impl LastSeenFinder {
         Self {}
     }
 
-    // Find the last message from a user by author_id
-    pub async fn find_last_message_by_id(
-        &self,
-        conn: Arc<Mutex<SqliteConnection>>,
-        author_id: &str,
-    ) -> Result<Option<(String, String, String, u64)>, anyhow::Error> {
-        let author_id = author_id.to_string();
-        let conn_guard = conn.lock().await;
-
-        let result = conn_guard
-            .call(move |conn| {
-                let mut stmt = conn.prepare(
-                    "SELECT author, display_name, content, timestamp FROM messages
-                 WHERE author_id = ?1 AND content != ''
-                 ORDER BY timestamp DESC LIMIT 1",
-                )?;
-
-                let rows = stmt.query_map([&author_id], |row| {
-                    Ok((
-                        row.get::<_, String>(0)?,
-                        row.get::<_, String>(1).unwrap_or_else(|_| "".to_string()),
-                        row.get::<_, String>(2)?,
-                        row.get::<_, u64>(3)?,
-                    ))
-                })?;
-
-                let result = rows.flatten().next();
-
-                Ok::<_, rusqlite::Error>(result)
-            })
-            .await?;
-
-        Ok(result)
-    }
-
-    // Find the last message from a user by name (nickname, display name, or username)
-    pub async fn find_last_message(
-        &self,
-        conn: Arc<Mutex<SqliteConnection>>,
-        name: &str,
-    ) -> Result<Option<(String, String, String, u64)>, anyhow::Error> {
-        let name_lower = name.to_lowercase();
-        let name_pattern = format!("%{name_lower}%");
-        let conn_guard = conn.lock().await;
-
-        // Query the database for the most recent message from a user matching the name pattern
-        let result = conn_guard
-            .call(move |conn| {
-                let mut stmt = conn.prepare(
-                    "SELECT author, display_name, content, timestamp FROM messages
-                 WHERE (LOWER(author) LIKE ?1 OR LOWER(display_name) LIKE ?1) AND content != ''
-                 ORDER BY timestamp DESC LIMIT 1",
-                )?;
-
-                let rows = stmt.query_map([&name_pattern], |row| {
-                    Ok((
-                        row.get::<_, String>(0)?,                                   // author
-                        row.get::<_, String>(1).unwrap_or_else(|_| "".to_string()), // display_name
-                        row.get::<_, String>(2)?,                                   // content
-                        row.get::<_, u64>(3)?,                                      // timestamp
-                    ))
-                })?;
-
-                let result = rows.flatten().next();
-
-                Ok::<_, rusqlite::Error>(result)
-            })
-            .await?;
-
-        Ok(result)
-    }
-
     // Format the time difference between now and the timestamp
     pub fn format_time_ago(&self, timestamp: u64) -> String {
         let now = SystemTime::now()
@@ -157,12 +85,55 @@ impl LastSeenFinder {
     }
 }
 
+// Most names offered when nobody matches
+const MAX_SUGGESTIONS: usize = 3;
+
+/// The candidates closest to a name nobody matched, by edit distance ignoring case (a swapped
+/// pair of letters counts as one edit), closest first. Names too far off to be a typo are left out, as are case-insensitive repeats.
+pub fn suggest_names(name: &str, candidates: &[String], limit: usize) -> Vec<String> {
+    let name = name.to_lowercase();
+    // Allow about one edit in three, and at least two for short names
+    let max_distance = (name.chars().count() / 3).max(2);
+
+    let mut scored: Vec<(usize, String, &String)> = Vec::new();
+    for candidate in candidates {
+        let lower = candidate.to_lowercase();
+        if scored.iter().any(|(_, seen, _)| *seen == lower) {
+            continue;
+        }
+        let distance = strsim::osa_distance(&name, &lower);
+        if distance <= max_distance {
+            scored.push((distance, lower, candidate));
+        }
+    }
+
+    scored.sort();
+    scored
+        .into_iter()
+        .take(limit)
+        .map(|(_, _, candidate)| candidate.clone())
+        .collect()
+}
+
+// What to say when nobody matches a name, suggesting near misses if there are any
+fn no_match_message(name: &str, suggestions: &[String]) -> String {
+    if suggestions.is_empty() {
+        format!("I haven't seen anyone matching \"{name}\"")
+    } else {
+        format!(
+            "I haven't seen anyone matching \"{name}\". Did you mean {}?",
+            suggestions.join(", ")
+        )
+    }
+}
+
 pub async fn handle_lastseen_command(
     http: &serenity::http::Http,
     msg: &Message,
     name: &str,
     user_id: Option<&str>,
-    db_conn: &Option<Arc<Mutex<SqliteConnection>>>,
+    store: &Option<Arc<dyn MessageStore>>,
+    unavailable_messages: &FeatureUnavailableMessages,
 ) -> Result<()> {
     if name.is_empty() && user_id.is_none() {
         if let Err(e) = msg.channel_id.say(http, "Usage: !lastseen [name]").await {
@@ -218,15 +189,14 @@ pub async fn handle_lastseen_command(
         return Ok(());
     }
 
-    // Check if we have a database connection
-    if let Some(conn) = db_conn {
+    // Check if we have a message store
+    if let Some(store) = store {
         let finder = LastSeenFinder::new();
 
-        let result = if let Some(uid) = user_id {
-            finder.find_last_message_by_id(conn.clone(), uid).await
-        } else {
-            finder.find_last_message(conn.clone(), name).await
-        };
+        // A mention is looked up by user ID, anything else by name
+        let result = store
+            .last_message(user_id.map(str::to_string), name.to_string())
+            .await;
 
         match result {
             Ok(Some((author, display_name, content, timestamp))) => {
@@ -246,9 +216,22 @@ pub async fn handle_lastseen_command(
                 }
             }
             Ok(None) => {
+                // Nobody's name contains it, so it may be a typo of a name we know
+                let suggestions = if user_id.is_none() {
+                    match store.author_names().await {
+                        Ok(names) => suggest_names(name, &names, MAX_SUGGESTIONS),
+                        Err(e) => {
+                            error!("Error loading names to suggest: {:?}", e);
+                            Vec::new()
+                        }
+                    }
+                } else {
+                    Vec::new()
+                };
+
                 if let Err(e) = msg
                     .channel_id
-                    .say(http, format!("I haven't seen anyone matching \"{name}\""))
+                    .say(http, no_match_message(name, &suggestions))
                     .await
                 {
                     error!("Error sending no match message: {:?}", e);
@@ -267,7 +250,10 @@ pub async fn handle_lastseen_command(
         }
     } else if let Err(e) = msg
         .channel_id
-        .say(http, "Message history database is not available")
+        .say(
+            http,
+            unavailable_messages.message(FeatureUnavailable::MessageHistory),
+        )
         .await
     {
         error!("Error sending database unavailable message: {:?}", e);
@@ -275,3 +261,56 @@ pub async fn handle_lastseen_command(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(items: &[&str]) -> Vec<String> {
+        items.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_suggest_names_ranking() {
+        let candidates = names(&["tom", "Robert", "rob", "bob", "alice", "Bobby"]);
+
+        // Closest first, ties alphabetical
+        assert_eq!(suggest_names("bbo", &candidates, 3), vec!["bob", "rob"]);
+        assert_eq!(suggest_names("rbo", &candidates, 1), vec!["rob"]);
+        assert_eq!(
+            suggest_names("BOBY", &candidates, 3),
+            vec!["bob", "Bobby", "rob"]
+        );
+
+        assert_eq!(
+            suggest_names("robrt", &names(&["Robert", "rob"]), 3),
+            vec!["Robert", "rob"]
+        );
+        assert_eq!(suggest_names("alcie", &candidates, 3), vec!["alice"]);
+    }
+
+    #[test]
+    fn test_suggest_names_filters() {
+        // Nothing close enough to be a typo
+        assert!(suggest_names("zzzzzz", &names(&["bob", "alice"]), 3).is_empty());
+        assert!(suggest_names("bob", &[], 3).is_empty());
+
+        // The same name in different cases is only offered once
+        assert_eq!(
+            suggest_names("bbo", &names(&["Bob", "bob", "BOB"]), 3),
+            vec!["Bob"]
+        );
+    }
+
+    #[test]
+    fn test_no_match_message() {
+        assert_eq!(
+            no_match_message("zed", &[]),
+            "I haven't seen anyone matching \"zed\""
+        );
+        assert_eq!(
+            no_match_message("bbo", &names(&["bob", "rob", "tom"])),
+            "I haven't seen anyone matching \"bbo\". Did you mean bob, rob, tom?"
+        );
+    }
+}
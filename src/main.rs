@@ -1,4 +1,5 @@
 use anyhow::Result;
+use futures::StreamExt;
 use rand::RngExt;
 use serenity::all::*;
 use serenity::async_trait;
@@ -13,36 +14,78 @@ use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::sync::RwLock;
 use tokio_rusqlite::Connection;
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
 
 // Import modules
+mod adaptive;
+mod address_detection;
+mod argument_limits;
+mod birthday;
 mod buzz;
+mod calc;
+mod channel_gating;
+mod channel_topic;
+mod choose;
+mod command_cooldown;
 mod config;
+mod conversation_memory;
 mod crime_fighting;
+mod daily_digest;
 mod database;
 mod db_utils;
+mod dice;
 mod display_name;
 mod duckduckgo_search;
+mod eightball;
 mod fact_interjection;
+mod feature_unavailable;
 mod fill_silence;
+mod forget;
 mod frinkiac;
 mod gemini_api;
 mod giphy;
+mod history_search;
+mod http_client;
 mod image_generation;
+mod interjection;
+mod interjection_metrics;
+mod interjection_stats;
+mod interjection_warmup;
+mod keyword_reactions;
 mod lastseen;
 mod masterofallscience;
 mod media_utils;
+mod message_history;
+mod message_store;
 mod morbotron;
 mod multi_response_generator;
 mod news_feed;
 mod news_interjection;
 mod news_verification;
+mod phrase_responses;
+mod postgres_store;
 mod prompt_templates;
+mod pronouns;
 mod rate_limiter;
+mod reaction_quote;
+mod recent_speakers;
+mod reminder;
+mod rephrase;
+mod reply_chain;
 mod response_timing;
+mod screenshot;
+mod screenshot_sources;
+mod shutdown;
+mod slash_commands;
+mod streaming_reply;
+mod summarize;
 mod text_formatting;
+mod token_usage;
+mod translate;
 mod trump_insult;
 mod utils;
+mod vibecheck;
+mod wikipedia;
 
 // Helper function to check if a response looks like a prompt
 mod bandname;
@@ -50,130 +93,80 @@ mod celebrity_status;
 mod mst3k_quotes;
 mod regex_substitution;
 mod unknown_command;
+mod urban;
 
 // Use our modules
+use adaptive::{Feedback, InterjectionKind};
+use address_detection::AddressDetector;
+use birthday::handle_birthday_command;
 use buzz::handle_buzz_command;
+use calc::handle_calc_command;
 use celebrity_status::handle_aliveordead_command;
+use channel_gating::{ChannelInfo, CommandChannelRules};
+use choose::handle_choose_command;
 use config::{load_config, parse_config, ParsedConfig};
 use crime_fighting::CrimeFightingGenerator;
 use database::DatabaseManager;
+use db_utils::ContextMessage;
+use dice::handle_roll_command;
 use display_name::{clean_display_name, get_best_display_name};
 use duckduckgo_search::DuckDuckGoSearchClient;
-use frinkiac::{handle_frinkiac_command, FrinkiacClient};
-use gemini_api::{GeminiClient, GeminiConfig};
-use image_generation::handle_imagine_command;
+use eightball::handle_eightball_command;
+use feature_unavailable::{FeatureUnavailable, FeatureUnavailableMessages};
+use forget::{handle_forget_command, handle_forgetme_command, Forgotten};
+use frinkiac::{handle_subtitles_command, FrinkiacClient};
+use gemini_api::{GeminiClient, GeminiConfig, GeminiTask};
+use history_search::handle_search_command;
+use image_generation::{
+    build_image_backend, handle_imagine_command, ImageBackend, ImageRetryPolicy,
+};
+use interjection_metrics::{handle_interject_stats_command, Outcome, Roll};
 use lastseen::handle_lastseen_command;
-use masterofallscience::{handle_masterofallscience_command, MasterOfAllScienceClient};
-use morbotron::{handle_morbotron_command, MorbotronClient};
+use masterofallscience::MasterOfAllScienceClient;
+use message_history::{MessageHistory, StoredMessage};
+use message_store::{MessageStore, MessageStoreSettings, SqliteMessageStore};
+use morbotron::MorbotronClient;
 use multi_response_generator::{MultiResponseConfig, MultiResponseGenerator};
 use news_interjection::handle_news_interjection;
+use postgres_store::PostgresMessageStore;
+use pronouns::handle_pronouns_command;
+use recent_speakers::{forget_speaker, format_speakers_dump, record_speaker, RecentSpeakers};
 use regex_substitution::handle_regex_substitution;
+use reminder::handle_remindme_command;
 use response_timing::apply_realistic_delay;
+use screenshot::{
+    handle_screenshot_command, show_screenshot_page, LastSearch, LastSearches, ScreenshotPage,
+    ScreenshotPages, ScreenshotProvider, ScreenshotService,
+};
+use screenshot_sources::GenericFrinkiacLikeClient;
+use summarize::handle_summarize_command;
+use translate::handle_translate_command;
 use unknown_command::handle_unknown_command;
+use urban::handle_urban_command;
+use utils::send_without_pings;
+use vibecheck::handle_vibecheck_command;
+use wikipedia::handle_wiki_command;
 
 // Define keys for the client data
 struct RecentSpeakersKey;
 impl TypeMapKey for RecentSpeakersKey {
-    type Value = Arc<RwLock<VecDeque<(String, String)>>>; // (username, display_name)
+    type Value = Arc<RwLock<RecentSpeakers>>; // (username, display_name)
 }
 
 struct MessageHistoryKey;
 impl TypeMapKey for MessageHistoryKey {
-    type Value = Arc<RwLock<VecDeque<Message>>>;
+    type Value = Arc<RwLock<MessageHistory>>;
 }
 
-/// Pre-compiled regex patterns for is_bot_addressed
-struct BotAddressPatterns {
-    name_boundary: regex::Regex,
-    negative: Vec<regex::Regex>,
-    positive: Vec<regex::Regex>,
-}
-
-impl BotAddressPatterns {
-    fn new(bot_name: &str) -> Self {
-        let name = bot_name.to_lowercase();
-        let escaped = regex::escape(&name);
-
-        let name_boundary = regex::Regex::new(&format!(r"\b{escaped}\b")).unwrap();
-
-        let neg_strs = [
-            format!(r"than {escaped}\b"),
-            format!(r"like {escaped}\b"),
-            format!(r"about {escaped}\b"),
-            format!(r"with {escaped}\b"),
-            format!(r"and {escaped}\b"),
-            format!(r"or {escaped}\b"),
-            format!(r"for {escaped}\b"),
-            format!(r"the {escaped}\b"),
-            format!(r"a {escaped}\b"),
-            format!(r"an {escaped}\b"),
-            format!(r"this {escaped}\b"),
-            format!(r"that {escaped}\b"),
-            format!(r"my {escaped}\b"),
-            format!(r"your {escaped}\b"),
-            format!(r"our {escaped}\b"),
-            format!(r"their {escaped}\b"),
-            format!(r"his {escaped}\b"),
-            format!(r"her {escaped}\b"),
-            format!(r"its {escaped}\b"),
-            format!(r"picked {escaped}\b"),
-            format!(r"chose {escaped}\b"),
-            format!(r"selected {escaped}\b"),
-            format!(r"named {escaped}\b"),
-            format!(r"called {escaped}\b"),
-            format!(r"{escaped} is\b"),
-            format!(r"{escaped} was\b"),
-            format!(r"{escaped} has\b"),
-            format!(r"{escaped} isn't\b"),
-            format!(r"{escaped} doesn't\b"),
-            format!(r"{escaped} didn't\b"),
-            format!(r"{escaped} won't\b"),
-            format!(r"{escaped} can't\b"),
-            format!(r"{escaped} rhymes"),
-            format!(r"rhymes with {escaped}"),
-            format!(r"{escaped} and"),
-            format!(r"more of a {escaped}"),
-            format!(r"less of a {escaped}"),
-            format!(r"kind of {escaped}"),
-            format!(r"sort of {escaped}"),
-            format!(r"type of {escaped}"),
-        ];
-        let negative = neg_strs
-            .iter()
-            .filter_map(|p| regex::Regex::new(p).ok())
-            .collect();
-
-        let pos_strs = [
-            format!(r"{escaped}\?"),
-            format!(r"{escaped}!"),
-            format!(r"{escaped},"),
-            format!(r"{escaped}:"),
-            format!(r"{escaped} can you"),
-            format!(r"{escaped} could you"),
-            format!(r"{escaped} will you"),
-            format!(r"{escaped} would you"),
-            format!(r"{escaped} please"),
-            format!(r"ask {escaped}"),
-            format!(r"tell {escaped}"),
-            format!(r", {escaped}"),
-            format!(r" {escaped}\."),
-        ];
-        let positive = pos_strs
-            .iter()
-            .filter_map(|p| regex::Regex::new(&format!(r"\b{p}\b")).ok())
-            .collect();
-
-        Self {
-            name_boundary,
-            negative,
-            positive,
-        }
-    }
+struct CommandCooldownsKey;
+impl TypeMapKey for CommandCooldownsKey {
+    type Value = Arc<command_cooldown::CommandCooldowns>;
 }
 
 struct Bot {
     bot_user_id: Arc<RwLock<Option<UserId>>>,
-    address_patterns: BotAddressPatterns,
+    /// Decides whether a message mentioning the bot's name is talking to it
+    address_detector: AddressDetector,
     followed_channels: Vec<ChannelId>,
     db_manager: DatabaseManager,
     search_client: Option<DuckDuckGoSearchClient>,
@@ -184,7 +177,8 @@ struct Bot {
     masterofallscience_client: MasterOfAllScienceClient,
     bot_name: String,
     message_db: Option<Arc<tokio::sync::Mutex<Connection>>>,
-    message_history_limit: usize,
+    /// Where message history is saved and read back (SQLite or a shared Postgres)
+    message_store: Option<Arc<dyn MessageStore>>,
     commands: HashMap<String, String>,
     keyword_triggers: Vec<(Vec<String>, String)>,
     crime_generator: CrimeFightingGenerator,
@@ -194,9 +188,12 @@ struct Bot {
     duckduckgo_search_enabled: bool,
     gemini_interjection_prompt: Option<String>,
     imagine_channels: Vec<String>,
-    pollinations_api_key: Option<String>,
+    /// Per-command channel allowlists and NSFW requirements
+    command_channel_rules: CommandChannelRules,
+    /// Where !imagine images come from (None when the backend isn't configured)
+    image_backend: Option<Box<dyn ImageBackend>>,
     image_rate_limiter: rate_limiter::RateLimiter,
-    http_client: reqwest::Client,
+    image_retry_policy: ImageRetryPolicy,
     start_time: Instant,
     gemini_context_messages: usize,
     interjection_mst3k_probability: f64,
@@ -208,14 +205,102 @@ struct Bot {
     fill_silence_manager: Arc<fill_silence::FillSilenceManager>,
     // Track the last seen message timestamp for each channel
     last_seen_message: Arc<RwLock<HashMap<ChannelId, (serenity::model::Timestamp, MessageId)>>>,
+    /// Set on Ctrl+C/SIGTERM so new messages are turned away while in-flight ones finish
+    shutdown: Arc<shutdown::ShutdownState>,
     // Track processed message IDs to prevent duplicate processing
     processed_messages: Arc<RwLock<VecDeque<MessageId>>>,
     quiet_channels: Vec<String>,
+    /// Cached channel topics for prompts; None when CHANNEL_TOPIC_CONTEXT is off
+    channel_topics: Option<Arc<channel_topic::ChannelTopicCache>>,
+    /// Quotes used when the MST3K quote database can't supply one
+    mst3k_fallback_quotes: Vec<String>,
+    /// Shortest message !quote -dud picks unless -all is given
+    quote_dud_min_length: usize,
+    /// Draw !quote -dud messages from every server rather than the current one
+    all_guilds: bool,
+    /// Users allowed to run owner-only commands; empty means the application owner
+    owner_ids: Vec<UserId>,
+    /// Users allowed to run admin commands like !interject, besides the owners
+    admin_user_ids: Vec<UserId>,
     giphy_client: Option<giphy::GiphyClient>,
     headline_cache: news_feed::HeadlineCache,
     news_feeds_config: Option<String>,
     /// Tracks when the last spontaneous interjection was sent
     last_interjection_time: Arc<RwLock<Option<Instant>>>,
+    /// Global cap on interjections per hour, shared with the spontaneous interjection task
+    interjection_cap: rate_limiter::SlidingWindowCap,
+    /// Skip interjections while the bot has the last word in a channel
+    skip_interjections_after_bot: bool,
+    /// Overlap with recent channel messages at which an interjection is dropped (0 disables)
+    interjection_similarity_threshold: f64,
+    /// Per-channel warm-up before interjections start, shared with the spontaneous task
+    interjection_warmup: interjection_warmup::InterjectionWarmup,
+    /// Learns per-type interjection probabilities from feedback (None when off)
+    adaptive_interjections: Option<adaptive::AdaptiveInterjections>,
+    /// How each interjection type has fared since startup, for !stats
+    interjection_stats: interjection_stats::InterjectionStats,
+    /// Log interjection decisions to the message database for !interject-stats
+    interjection_metrics: bool,
+    /// Reaction features enabled in config; empty means reaction events are ignored
+    reaction_features: Vec<String>,
+    /// Keywords that get an emoji reaction (empty when the feature is off)
+    keyword_reactions: keyword_reactions::KeywordReactions,
+    /// Reposts messages as quotes when reacted to (None when reaction_quote is off)
+    reaction_quotes: Option<reaction_quote::ReactionQuotes>,
+    /// Cached message count shown by !info
+    message_count_cache: Arc<db_utils::MessageCountCache>,
+    /// Wikipedia lookups for !alive and !wiki, cached
+    wikipedia: wikipedia::Wikipedia,
+    /// Each user's last !frinkiac/!morbotron search per channel, for !again
+    last_screenshot_searches: LastSearches,
+    /// Screenshot searches that can be paged with ◀️/▶️ (None when screenshot_pages is off)
+    screenshot_pages: Option<ScreenshotPages>,
+    /// What the bot's recent AI replies were generated from, for !rephrase
+    recent_replies: rephrase::RecentReplies,
+    /// Whether !rephrase edits the original reply instead of posting a new one
+    rephrase_edit_original: bool,
+    /// Whether direct replies stream into a message as Gemini writes them
+    stream_replies: bool,
+    /// Each user's recent exchanges with the bot per channel
+    conversation_memory: conversation_memory::ConversationMemory,
+    /// How many image/video attachments a reply looks at, and how large each may be
+    media_limits: media_utils::MediaLimits,
+    /// Wording for replies when a feature's backing service isn't set up
+    unavailable_messages: FeatureUnavailableMessages,
+    /// What to say when a direct reply hits the Gemini rate limit (empty stays quiet)
+    rate_limit_reply_message: String,
+    /// Longest a reply may wait on Gemini before giving up
+    gemini_reply_timeout: Duration,
+    subtitles_page_size: usize,
+    subtitles_max_pages: usize,
+    /// How many messages of a reply chain to include as context (0 disables)
+    reply_chain_depth: usize,
+    /// Upload screenshots with their subtitle as alt text
+    screenshot_alt_text: bool,
+    /// Have Gemini write !8ball answers
+    eightball_use_ai: bool,
+    /// Words that keep an !urban definition out of the channel (None = no filter)
+    urban_blocked_terms: Option<Vec<String>>,
+    /// Call-and-response pairs like "lisa needs braces" / "DENTAL PLAN!"
+    phrase_responses: Vec<phrase_responses::PhraseResponse>,
+    /// Longest GIF rendered for a !frinkiac/!morbotron result
+    screenshot_gif_max_duration: Duration,
+    /// Extra Frinkiac-family sites from [[screenshot_sources]], each on its own command
+    screenshot_sources: Vec<GenericFrinkiacLikeClient>,
+    /// Timezone that decides which day it is for birthdays (None = system local)
+    birthday_utc_offset: Option<chrono::FixedOffset>,
+    /// Timezone !remindme reads times of day in (None = system local)
+    reminder_utc_offset: Option<chrono::FixedOffset>,
+    /// When each channel last ran !vibecheck
+    vibe_check_cooldowns: Arc<RwLock<HashMap<ChannelId, Instant>>>,
+    /// Per-user budget for AI replies, so one user can't use up the shared rate limit
+    user_reply_buckets: rate_limiter::UserTokenBuckets,
+    /// Cooldown-blocked commands their authors can re-run by reacting to the notice
+    pending_command_retries: command_cooldown::PendingRetries,
+    /// How long each command's arguments may be
+    argument_limits: argument_limits::ArgumentLimits,
+    /// Register slash commands alongside the `!` commands
+    slash_commands_enabled: bool,
 }
 
 /// Configuration for creating a Bot instance
@@ -231,11 +316,33 @@ pub struct BotConfig {
     pub gemini_prompt_wrapper: Option<String>,
     pub gemini_interjection_prompt: Option<String>,
     pub message_db: Option<Arc<tokio::sync::Mutex<Connection>>>,
+    pub message_store: Option<Arc<dyn MessageStore>>,
+    /// Today's AI token totals, shared by every Gemini client
+    pub token_usage: Arc<token_usage::UsageTracker>,
     pub log_prompts: bool,
     pub interjection_fact_probability: f64,
     pub gemini_personality_description: Option<String>,
     pub pollinations_api_key: Option<String>,
     pub news_feeds: Option<String>,
+    /// Per-channel fill silence settings, with channel names already resolved
+    pub fill_silence_channels: HashMap<ChannelId, fill_silence::SilenceSettings>,
+}
+
+// Discord's largest page of channel messages
+const MISSED_MESSAGES_PAGE_SIZE: u8 = 100;
+
+// How many pages a channel's missed-message recovery fetches before giving up
+const MAX_MISSED_MESSAGE_PAGES: usize = 50;
+
+// Where the next page of missed messages starts: after the newest message in this page.
+// None once a short page shows we've caught up, or if the cursor wouldn't move.
+fn next_missed_messages_cursor(
+    cursor: MessageId,
+    page: &[Message],
+    page_size: usize,
+) -> Option<MessageId> {
+    let newest = page.iter().map(|msg| msg.id).max()?;
+    (page.len() >= page_size && newest > cursor).then_some(newest)
 }
 
 impl Bot {
@@ -255,53 +362,80 @@ impl Bot {
                     channel_id, last_message_id
                 );
 
-                // Get messages after the last seen message
-                let retriever = GetMessages::default();
-                let retriever = retriever.after(*last_message_id).limit(50);
+                // Page forward from the last seen message until we've caught up
+                let bot_id = self.get_bot_user_id(ctx).await;
+                let mut cursor = *last_message_id;
+                let mut recovered = 0;
+                let mut pages = 0;
+                loop {
+                    if pages == MAX_MISSED_MESSAGE_PAGES {
+                        warn!(
+                            "Stopped recovering missed messages in channel {} after {} pages",
+                            channel_id, pages
+                        );
+                        break;
+                    }
+                    pages += 1;
 
-                match channel_id.messages(&ctx.http, retriever).await {
-                    Ok(messages) => {
-                        if !messages.is_empty() {
-                            info!(
-                                "Found {} missed messages in channel {}",
-                                messages.len(),
-                                channel_id
+                    let retriever = GetMessages::default()
+                        .after(cursor)
+                        .limit(MISSED_MESSAGES_PAGE_SIZE);
+                    let mut messages = match channel_id.messages(&ctx.http, retriever).await {
+                        Ok(messages) => messages,
+                        Err(e) => {
+                            error!(
+                                "Error retrieving missed messages for channel {}: {:?}",
+                                channel_id, e
                             );
+                            break;
+                        }
+                    };
 
-                            // Process each missed message in chronological order (oldest first)
-                            for msg in messages.iter().rev() {
-                                // Skip our own messages
-                                if msg.author.id == self.get_bot_user_id(ctx).await {
-                                    continue;
-                                }
-
-                                info!(
-                                    "Processing missed message from {}: {}",
-                                    msg.author.name, msg.content
-                                );
+                    // Process each missed message in chronological order (oldest first)
+                    messages.sort_by_key(|msg| msg.id);
+                    for msg in &messages {
+                        // Store everything, our own messages included, as message() would
+                        self.record_message(ctx, msg).await;
 
-                                // Process the message
-                                if let Err(e) = self.process_message(ctx, msg).await {
-                                    error!("Error processing missed message: {:?}", e);
-                                }
+                        // Skip our own messages
+                        if msg.author.id != bot_id {
+                            info!(
+                                "Processing missed message from {}: {}",
+                                msg.author.name, msg.content
+                            );
 
-                                // Update the last seen message
-                                {
-                                    let mut last_seen = self.last_seen_message.write().await;
-                                    last_seen.insert(*channel_id, (msg.timestamp, msg.id));
-                                }
+                            // Process the message
+                            if let Err(e) = self.process_message(ctx, msg).await {
+                                error!("Error processing missed message: {:?}", e);
                             }
-                        } else {
-                            info!("No missed messages in channel {}", channel_id);
+                        }
+
+                        // Update the last seen message
+                        {
+                            let mut last_seen = self.last_seen_message.write().await;
+                            last_seen.insert(*channel_id, (msg.timestamp, msg.id));
                         }
                     }
-                    Err(e) => {
-                        error!(
-                            "Error retrieving missed messages for channel {}: {:?}",
-                            channel_id, e
-                        );
+                    recovered += messages.len();
+
+                    match next_missed_messages_cursor(
+                        cursor,
+                        &messages,
+                        usize::from(MISSED_MESSAGES_PAGE_SIZE),
+                    ) {
+                        Some(next) => cursor = next,
+                        None => break,
                     }
                 }
+
+                if recovered > 0 {
+                    info!(
+                        "Recovered {} missed messages in channel {}",
+                        recovered, channel_id
+                    );
+                } else {
+                    info!("No missed messages in channel {}", channel_id);
+                }
             } else {
                 info!(
                     "No last seen message for channel {}, skipping missed message check",
@@ -335,13 +469,21 @@ impl Bot {
         // Generate a comprehensive help message with all commands
         let help_message = if !parsed_config.imagine_channels.is_empty() {
             // Include the imagine command if channels are configured
-            "Available commands:\n!help - Show help\n!hello - Say hello\n!buzz - Generate corporate buzzwords\n!fightcrime - Generate a crime fighting duo\n!trump - Generate a Trump insult\n!bandname [name] - Generate music genre for a band\n!lastseen [name] - Find when a user was last active\n!quote [term] - Get a random quote\n!quote -show [show] - Get quote from specific show\n!quote -dud [user] - Get random message from a user\n!slogan [term] - Get a random advertising slogan\n!frinkiac [term] [-s season] [-e episode] - Get a Simpsons screenshot\n!morbotron [term] - Get a Futurama screenshot\n!masterofallscience [term] - Get a Rick and Morty screenshot\n!imagine [text] - Generate an image\n!alive [name] - Check if a celebrity is alive or dead\n!info - Show bot statistics"
+            "Available commands:\n!help - Show help\n!hello - Say hello\n!buzz - Generate corporate buzzwords\n!fightcrime - Generate a crime fighting duo\n!trump - Generate a Trump insult\n!bandname [name] - Generate music genre for a band\n!lastseen [name] - Find when a user was last active\n!search <term> [-p page] - Search message history\n!calc <expression> - Calculate something\n!roll <dice> - Roll dice, e.g. !roll 2d6+3 or !roll 4d6kh3\n!choose <a, b or c> - Pick one for you (also !pick)\n!8ball <question> - Ask the Magic 8-Ball\n!birthday set MM-DD | list | remove - Track birthdays\n!remindme <when> <what> - Get a reminder later, e.g. !remindme 2h check the oven\n!pronouns set they/them | clear - Tell the bot your pronouns\n!forgetme - Delete your stored messages and stop storing new ones\n!quote [term] [-count N] - Get a random quote (up to 5 with -count)\n!quote -show [show] - Get quote from specific show\n!quote -dud [user] [-all] [-count N] [-year YYYY] [-after/-before YYYY-MM-DD] - Get random message from a user\n!slogan [term] - Get a random advertising slogan\n!frinkiac [term] [-s season] [-e episode] [-context] [-caption] - Get a Simpsons screenshot\n!again - Show a different frame for your last screenshot search\n!subtitles <episode> [page] - Show a Simpsons episode's subtitles\n!morbotron [term] [-context] [-caption] - Get a Futurama screenshot\n!masterofallscience [term] - Get a Rick and Morty screenshot\n!rephrase [style] [-t temperature] - Reply to one of my messages to get a different take\n!imagine [text] - Generate an image\n!alive [name] - Check if a celebrity is alive or dead\n!wiki <topic> - Summarize a Wikipedia article\n!urban <term> - Look up slang on Urban Dictionary\n!translate [language] <text> - Translate text (into English by default)\n!vibecheck - Read the room\n!summarize [count] [-nobot] - Summarize the channel's recent messages\n!info [fresh] - Show bot statistics\n!uptime - Show how long the bot has been running\n!stats - Show how often each interjection type has fired since startup"
         } else {
             // Exclude the imagine command if no channels are configured
-            "Available commands:\n!help - Show help\n!hello - Say hello\n!buzz - Generate corporate buzzwords\n!fightcrime - Generate a crime fighting duo\n!trump - Generate a Trump insult\n!bandname [name] - Generate music genre for a band\n!lastseen [name] - Find when a user was last active\n!quote [term] - Get a random quote\n!quote -show [show] - Get quote from specific show\n!quote -dud [user] - Get random message from a user\n!slogan [term] - Get a random advertising slogan\n!frinkiac [term] [-s season] [-e episode] - Get a Simpsons screenshot\n!morbotron [term] - Get a Futurama screenshot\n!masterofallscience [term] - Get a Rick and Morty screenshot\n!alive [name] - Check if a celebrity is alive or dead\n!info - Show bot statistics"
+            "Available commands:\n!help - Show help\n!hello - Say hello\n!buzz - Generate corporate buzzwords\n!fightcrime - Generate a crime fighting duo\n!trump - Generate a Trump insult\n!bandname [name] - Generate music genre for a band\n!lastseen [name] - Find when a user was last active\n!search <term> [-p page] - Search message history\n!calc <expression> - Calculate something\n!roll <dice> - Roll dice, e.g. !roll 2d6+3 or !roll 4d6kh3\n!choose <a, b or c> - Pick one for you (also !pick)\n!8ball <question> - Ask the Magic 8-Ball\n!birthday set MM-DD | list | remove - Track birthdays\n!remindme <when> <what> - Get a reminder later, e.g. !remindme 2h check the oven\n!pronouns set they/them | clear - Tell the bot your pronouns\n!forgetme - Delete your stored messages and stop storing new ones\n!quote [term] [-count N] - Get a random quote (up to 5 with -count)\n!quote -show [show] - Get quote from specific show\n!quote -dud [user] [-all] [-count N] [-year YYYY] [-after/-before YYYY-MM-DD] - Get random message from a user\n!slogan [term] - Get a random advertising slogan\n!frinkiac [term] [-s season] [-e episode] [-context] [-caption] - Get a Simpsons screenshot\n!again - Show a different frame for your last screenshot search\n!subtitles <episode> [page] - Show a Simpsons episode's subtitles\n!morbotron [term] [-context] [-caption] - Get a Futurama screenshot\n!masterofallscience [term] - Get a Rick and Morty screenshot\n!rephrase [style] [-t temperature] - Reply to one of my messages to get a different take\n!alive [name] - Check if a celebrity is alive or dead\n!wiki <topic> - Summarize a Wikipedia article\n!urban <term> - Look up slang on Urban Dictionary\n!translate [language] <text> - Translate text (into English by default)\n!vibecheck - Read the room\n!summarize [count] [-nobot] - Summarize the channel's recent messages\n!info [fresh] - Show bot statistics\n!uptime - Show how long the bot has been running\n!stats - Show how often each interjection type has fired since startup"
         };
 
-        commands.insert("help".to_string(), help_message.to_string());
+        // Configured screenshot sources get a line each
+        let mut help_message = help_message.to_string();
+        for source in &parsed_config.screenshot_sources {
+            help_message.push_str(&format!(
+                "\n!{} [term] [-s season] [-e episode] [-context] [-caption] - Get a {} screenshot",
+                source.command, source.name
+            ));
+        }
+        commands.insert("help".to_string(), help_message);
 
         // Define keyword triggers - empty but we keep the structure for future additions
         let keyword_triggers = Vec::new();
@@ -381,19 +523,31 @@ impl Bot {
                     parsed_config.gemini_image_rate_limit_minute,
                     parsed_config.gemini_image_rate_limit_day
                 );
-                Some(GeminiClient::new(GeminiConfig {
-                    api_key,
-                    api_endpoint: config.gemini_api_endpoint,
-                    prompt_wrapper: config.gemini_prompt_wrapper,
-                    bot_name: parsed_config.bot_name.clone(),
-                    rate_limit_minute: parsed_config.gemini_rate_limit_minute,
-                    rate_limit_day: parsed_config.gemini_rate_limit_day,
-                    image_rate_limit_minute: parsed_config.gemini_image_rate_limit_minute,
-                    image_rate_limit_day: parsed_config.gemini_image_rate_limit_day,
-                    context_messages: parsed_config.gemini_context_messages,
-                    log_prompts: config.log_prompts,
-                    personality_description: config.gemini_personality_description,
-                }))
+                Some(
+                    GeminiClient::new(GeminiConfig {
+                        api_key,
+                        api_endpoint: config.gemini_api_endpoint,
+                        fallback_api_endpoint: parsed_config.gemini_fallback_api_endpoint.clone(),
+                        fallback_api_key: parsed_config.gemini_fallback_api_key.clone(),
+                        prompt_wrapper: config.gemini_prompt_wrapper,
+                        bot_name: parsed_config.bot_name.clone(),
+                        rate_limit_minute: parsed_config.gemini_rate_limit_minute,
+                        rate_limit_day: parsed_config.gemini_rate_limit_day,
+                        image_rate_limit_minute: parsed_config.gemini_image_rate_limit_minute,
+                        image_rate_limit_day: parsed_config.gemini_image_rate_limit_day,
+                        context_messages: parsed_config.gemini_context_messages,
+                        log_prompts: config.log_prompts,
+                        personality_description: config.gemini_personality_description,
+                        uncertainty_mode: parsed_config.gemini_uncertainty_mode,
+                        persona_examples: parsed_config.gemini_persona_examples.clone(),
+                        temperatures: parsed_config.gemini_temperatures,
+                        generation: parsed_config.gemini_generation,
+                        max_retries: parsed_config.gemini_max_retries,
+                        api_flavor: parsed_config.llm_api_flavor,
+                        model: parsed_config.llm_model.clone(),
+                    })
+                    .with_usage_tracker(config.token_usage.clone()),
+                )
             }
             None => {
                 info!("Gemini client not created - missing API key");
@@ -426,16 +580,43 @@ impl Bot {
         let band_genre_generator = bandname::BandGenreGenerator::new();
 
         // Initialize the fill silence manager
-        let fill_silence_manager = Arc::new(fill_silence::FillSilenceManager::new(
-            parsed_config.fill_silence_enabled,
-            parsed_config.fill_silence_start_hours,
-            parsed_config.fill_silence_max_hours,
-            parsed_config.interjection_minimum_messages,
-        ));
+        let fill_silence_manager = Arc::new(
+            fill_silence::FillSilenceManager::new(
+                parsed_config.fill_silence_enabled,
+                parsed_config.fill_silence_start_hours,
+                parsed_config.fill_silence_max_hours,
+                parsed_config.interjection_minimum_messages,
+            )
+            .with_quiet_hours(parsed_config.fill_silence_quiet_hours)
+            .with_channel_settings(config.fill_silence_channels.clone()),
+        );
+
+        // Reposting messages as quotes on a reaction is a reaction feature
+        let reaction_quotes = parsed_config
+            .reaction_features
+            .iter()
+            .any(|f| f == "reaction_quote")
+            .then(|| {
+                reaction_quote::ReactionQuotes::new(
+                    parsed_config.quote_reaction_emoji.clone(),
+                    parsed_config.quote_reaction_channel_id.map(ChannelId::new),
+                )
+            });
+
+        // Paging through a screenshot search's frames is a reaction feature
+        let screenshot_pages = parsed_config
+            .reaction_features
+            .iter()
+            .any(|f| f == "screenshot_pages")
+            .then(|| ScreenshotPages::new(screenshot::SCREENSHOT_PAGE_TTL));
 
         Self {
             bot_user_id: Arc::new(RwLock::new(None)),
-            address_patterns: BotAddressPatterns::new(&parsed_config.bot_name),
+            address_detector: AddressDetector::new(
+                &parsed_config.bot_name,
+                &parsed_config.extra_address_patterns,
+                &parsed_config.extra_negative_patterns,
+            ),
             followed_channels: config.followed_channels,
             db_manager,
             search_client,
@@ -446,7 +627,7 @@ impl Bot {
             masterofallscience_client,
             bot_name: parsed_config.bot_name,
             message_db: config.message_db,
-            message_history_limit: parsed_config.message_history_limit,
+            message_store: config.message_store,
             commands,
             keyword_triggers,
             crime_generator,
@@ -456,13 +637,18 @@ impl Bot {
             duckduckgo_search_enabled: parsed_config.duckduckgo_search_enabled,
             gemini_interjection_prompt: config.gemini_interjection_prompt,
             imagine_channels: parsed_config.imagine_channels,
-            pollinations_api_key: config.pollinations_api_key,
+            command_channel_rules: parsed_config.command_channel_rules,
+            image_retry_policy: parsed_config.image_retry_policy,
+            image_backend: build_image_backend(
+                &parsed_config.image_backend,
+                config.pollinations_api_key.as_deref(),
+                http_client::shared(),
+            ),
             image_rate_limiter: rate_limiter::RateLimiter::new_with_persistence(
                 parsed_config.gemini_image_rate_limit_minute,
                 parsed_config.gemini_image_rate_limit_day,
                 "pollinations_image_quota.json".to_string(),
             ),
-            http_client: reqwest::Client::new(),
             start_time: Instant::now(),
             gemini_context_messages: parsed_config.gemini_context_messages,
             interjection_mst3k_probability: parsed_config.interjection_mst3k_probability,
@@ -473,12 +659,101 @@ impl Bot {
             interjection_news_probability: parsed_config.interjection_news_probability,
             fill_silence_manager,
             last_seen_message: Arc::new(RwLock::new(HashMap::new())),
+            shutdown: shutdown::ShutdownState::new(),
             processed_messages: Arc::new(RwLock::new(VecDeque::new())),
             quiet_channels: parsed_config.quiet_channels,
+            quote_dud_min_length: parsed_config.quote_dud_min_length,
+            all_guilds: parsed_config.all_guilds,
+            mst3k_fallback_quotes: mst3k_quotes::load_fallback_quotes(
+                parsed_config.mst3k_quotes_file.as_deref(),
+            ),
+            channel_topics: parsed_config.channel_topic_context.then(|| {
+                Arc::new(channel_topic::ChannelTopicCache::new(
+                    channel_topic::CHANNEL_TOPIC_TTL,
+                ))
+            }),
+            owner_ids: parsed_config
+                .owner_ids
+                .iter()
+                .map(|id| UserId::new(*id))
+                .collect(),
+            admin_user_ids: parsed_config
+                .admin_user_ids
+                .iter()
+                .map(|id| UserId::new(*id))
+                .collect(),
             giphy_client: parsed_config.giphy_api_key.map(giphy::GiphyClient::new),
             headline_cache: news_feed::new_cache(),
             news_feeds_config: config.news_feeds,
             last_interjection_time: Arc::new(RwLock::new(None)),
+            interjection_cap: rate_limiter::SlidingWindowCap::new(
+                "Interjection",
+                parsed_config.interjection_max_per_hour,
+                Duration::from_secs(3600),
+            ),
+            interjection_similarity_threshold: parsed_config.interjection_similarity_threshold,
+            skip_interjections_after_bot: parsed_config.skip_interjections_after_bot,
+            interjection_warmup: interjection_warmup::InterjectionWarmup::new(
+                Duration::from_secs(parsed_config.interjection_warmup_secs),
+                parsed_config.interjection_warmup_messages,
+            ),
+            adaptive_interjections: parsed_config.adaptive_interjections.map(|settings| {
+                adaptive::AdaptiveInterjections::new_with_persistence(
+                    settings,
+                    "adaptive_interjections.json".to_string(),
+                )
+            }),
+            interjection_stats: interjection_stats::InterjectionStats::new(),
+            interjection_metrics: parsed_config.interjection_metrics,
+            reaction_features: parsed_config.reaction_features,
+            keyword_reactions: parsed_config.keyword_reactions,
+            reaction_quotes,
+            message_count_cache: Arc::new(db_utils::MessageCountCache::new(Duration::from_secs(
+                parsed_config.info_count_cache_secs,
+            ))),
+            wikipedia: wikipedia::Wikipedia::new(
+                parsed_config.wikipedia_cache_ttl,
+                parsed_config.wikipedia_cache_max_entries,
+            ),
+            last_screenshot_searches: LastSearches::new(screenshot::LAST_SEARCH_TTL),
+            screenshot_pages,
+            recent_replies: rephrase::RecentReplies::new(
+                rephrase::REPHRASE_TTL,
+                rephrase::MAX_REMEMBERED_REPLIES,
+            ),
+            rephrase_edit_original: parsed_config.rephrase_edit_original,
+            stream_replies: parsed_config.gemini_stream_replies,
+            conversation_memory: conversation_memory::ConversationMemory::new(
+                parsed_config.conversation_memory_exchanges,
+                Duration::from_secs(parsed_config.conversation_memory_idle_mins * 60),
+            ),
+            media_limits: parsed_config.media_limits,
+            unavailable_messages: parsed_config.unavailable_messages,
+            rate_limit_reply_message: parsed_config.rate_limit_reply_message,
+            gemini_reply_timeout: parsed_config.gemini_reply_timeout,
+            subtitles_page_size: parsed_config.subtitles_page_size,
+            subtitles_max_pages: parsed_config.subtitles_max_pages,
+            reply_chain_depth: parsed_config.reply_chain_depth,
+            screenshot_alt_text: parsed_config.screenshot_alt_text,
+            eightball_use_ai: parsed_config.eightball_use_ai,
+            urban_blocked_terms: parsed_config.urban_blocked_terms.clone(),
+            phrase_responses: parsed_config.phrase_responses.clone(),
+            screenshot_gif_max_duration: parsed_config.screenshot_gif_max_duration,
+            screenshot_sources: parsed_config
+                .screenshot_sources
+                .iter()
+                .map(GenericFrinkiacLikeClient::new)
+                .collect(),
+            birthday_utc_offset: parsed_config.birthday_utc_offset,
+            reminder_utc_offset: parsed_config.reminder_utc_offset,
+            vibe_check_cooldowns: Arc::new(RwLock::new(HashMap::new())),
+            user_reply_buckets: rate_limiter::UserTokenBuckets::new(
+                parsed_config.user_reply_burst,
+                parsed_config.user_reply_refill_per_minute,
+            ),
+            pending_command_retries: command_cooldown::PendingRetries::new(),
+            argument_limits: parsed_config.argument_limits,
+            slash_commands_enabled: parsed_config.slash_commands_enabled,
         }
     }
 
@@ -521,167 +796,991 @@ impl Bot {
         }
     }
 
-    // Handle the !info command
-    async fn handle_info_command(&self, ctx: &Context, msg: &Message) -> Result<()> {
-        // Calculate uptime
-        let uptime = self.start_time.elapsed();
-        let uptime_str = Self::format_duration(uptime);
+    // Format the !uptime reply; the start time is a Discord timestamp so it shows in local time
+    /// What to post when AI generation fails, or None to stay quiet. Interjections are
+    /// optional, so they never say anything; direct replies get a friendly notice.
+    fn ai_error_notice(
+        error: &anyhow::Error,
+        directly_addressed: bool,
+        rate_limit_reply_message: &str,
+    ) -> Option<String> {
+        if !directly_addressed {
+            return None;
+        }
 
-        // Get message history count
-        let message_count = if let Some(db) = &self.message_db {
-            match db
-                .lock()
-                .await
-                .call(|conn| {
-                    let mut stmt = conn.prepare("SELECT COUNT(*) FROM messages")?;
-                    let count: i64 = stmt.query_row([], |row| row.get(0))?;
-                    Ok::<_, rusqlite::Error>(count)
-                })
-                .await
-            {
-                Ok(count) => count.to_string(),
-                Err(_) => "Unknown".to_string(),
-            }
+        if rate_limiter::is_rate_limited(error) {
+            let message = rate_limit_reply_message.trim();
+            return (!message.is_empty()).then(|| message.to_string());
+        }
+
+        if gemini_api::is_timed_out(error) {
+            return Some("That took too long to think about. Try asking again!".to_string());
+        }
+
+        let error_string = error.to_string();
+
+        // Don't show silent errors to users
+        if error_string.contains("SILENT_ERROR") {
+            return None;
+        }
+
+        // Show a friendly message without raw error details
+        if error_string.contains("BILLING_ERROR") {
+            Some("The Gemini API quota or billing limit has been reached. I'll be back once the limit resets!".to_string())
         } else {
-            "Database not available".to_string()
-        };
+            Some("I'm having trouble thinking right now. Try again in a bit!".to_string())
+        }
+    }
 
-        // Get memory usage (approximate)
-        let memory_usage = match std::process::Command::new("ps")
-            .args(["-o", "rss=", "-p", &std::process::id().to_string()])
-            .output()
-        {
-            Ok(output) => {
-                let rss = String::from_utf8_lossy(&output.stdout).trim().to_string();
-                if rss.is_empty() {
-                    "Unknown".to_string()
-                } else {
-                    // Convert from KB to MB
-                    match rss.parse::<f64>() {
-                        Ok(kb) => format!("{:.2} MB", kb / 1024.0),
-                        Err(_) => "Unknown".to_string(),
-                    }
-                }
-            }
-            Err(_) => "Unknown".to_string(),
-        };
+    fn format_uptime_reply(uptime: Duration, started_at_unix: u64) -> String {
+        format!(
+            "Up for **{}** (since <t:{started_at_unix}:F>)",
+            Self::format_duration(uptime)
+        )
+    }
 
-        // Count followed channels
-        let channel_count = self.followed_channels.len();
+    // Run a screenshot site's command with the bot's screenshot settings
+    async fn run_screenshot_command<P: ScreenshotProvider>(
+        &self,
+        ctx: &Context,
+        msg: &Message,
+        args: Option<String>,
+        provider: &P,
+    ) -> Result<()> {
+        handle_screenshot_command(
+            &ctx.http,
+            msg,
+            args,
+            provider,
+            self.screenshot_alt_text,
+            self.screenshot_gif_max_duration,
+            self.screenshot_pages.as_ref(),
+        )
+        .await
+    }
 
-        // Build the info message
-        let mut info = format!("**{} Bot Info**\n\n", self.bot_name);
+    // Flick a paged screenshot search to its previous or next frame when the person who
+    // searched reacts ◀️/▶️
+    async fn turn_screenshot_page(&self, ctx: &Context, reaction: &Reaction) {
+        let Some(pages) = &self.screenshot_pages else {
+            return;
+        };
+        let forward = if reaction.emoji.unicode_eq(screenshot::NEXT_PAGE_EMOJI) {
+            true
+        } else if reaction.emoji.unicode_eq(screenshot::PREVIOUS_PAGE_EMOJI) {
+            false
+        } else {
+            return;
+        };
+        let Some(user_id) = reaction.user_id else {
+            return;
+        };
+        let Some(page) = pages.turn(reaction.message_id, user_id, forward) else {
+            return;
+        };
 
-        info.push_str(&format!("**Uptime:** {uptime_str}\n"));
-        info.push_str(&format!("**Messages in database:** {message_count}\n"));
-        info.push_str(&format!("**Memory usage:** {memory_usage}\n"));
-        info.push_str(&format!("**Following {channel_count} channels**\n"));
+        // Take the reaction back off so the same arrow can be pressed again (needs
+        // Manage Messages; without it, people unreact and react again)
+        if let Err(e) = reaction.delete(&ctx.http).await {
+            debug!("Couldn't remove screenshot page reaction: {:?}", e);
+        }
 
-        // Add feature status
-        info.push_str("\n**Features:**\n");
-        info.push_str(&format!(
-            "- DuckDuckGo search: {}\n",
-            if self.duckduckgo_search_enabled {
-                "Enabled"
-            } else {
-                "Disabled"
+        let result = match page.command.as_str() {
+            "frinkiac" => {
+                self.show_page(ctx, reaction, &self.frinkiac_client, &page)
+                    .await
             }
-        ));
-        info.push_str(&format!(
-            "- AI responses: {}\n",
-            if self.gemini_client.is_some() {
-                "Enabled"
-            } else {
-                "Disabled"
+            "morbotron" => {
+                self.show_page(ctx, reaction, &self.morbotron_client, &page)
+                    .await
             }
-        ));
-        info.push_str(&format!(
-            "- Image generation: {}\n",
-            if !self.imagine_channels.is_empty() {
-                format!("Enabled in {} channels", self.imagine_channels.len())
-            } else {
-                "Disabled".to_string()
+            "masterofallscience" => {
+                self.show_page(ctx, reaction, &self.masterofallscience_client, &page)
+                    .await
             }
-        ));
+            command => match self
+                .screenshot_sources
+                .iter()
+                .find(|source| source.command() == command)
+            {
+                Some(source) => self.show_page(ctx, reaction, source, &page).await,
+                None => return,
+            },
+        };
+        if let Err(e) = result {
+            error!("Error turning {} screenshot page: {:?}", page.command, e);
+        }
+    }
 
-        // Add API quota information if Gemini client is available
-        if let Some(gemini_client) = &self.gemini_client {
-            let (text_quota, _image_quota, _image_status) = gemini_client.get_quota_stats().await;
-            info.push_str("\n**API Quotas:**\n");
-            info.push_str(&format!("- Text API: {}\n", text_quota));
+    // Show a paged screenshot search's current frame with the bot's screenshot settings
+    async fn show_page<P: ScreenshotProvider>(
+        &self,
+        ctx: &Context,
+        reaction: &Reaction,
+        provider: &P,
+        page: &ScreenshotPage,
+    ) -> Result<()> {
+        show_screenshot_page(
+            &ctx.http,
+            reaction.channel_id,
+            reaction.message_id,
+            provider,
+            &page.frames[page.index],
+            page.caption,
+            self.screenshot_alt_text,
+            self.screenshot_gif_max_duration,
+        )
+        .await
+    }
+
+    // Run a !frinkiac or !morbotron search and remember it for !again
+    async fn run_screenshot_search(
+        &self,
+        ctx: &Context,
+        msg: &Message,
+        service: ScreenshotService,
+        args: Option<String>,
+    ) {
+        // -debug (owners only) also reports how the search ranked its candidates
+        let (args, debug) = screenshot::take_debug_flag(args);
+        let debug = debug && self.is_owner(ctx, msg.author.id).await;
+
+        let (result, name, ranking) = match service {
+            ScreenshotService::Frinkiac => (
+                self.run_screenshot_command(ctx, msg, args.clone(), &self.frinkiac_client)
+                    .await,
+                self.frinkiac_client.name(),
+                self.frinkiac_client.last_ranking(),
+            ),
+            ScreenshotService::Morbotron => (
+                self.run_screenshot_command(ctx, msg, args.clone(), &self.morbotron_client)
+                    .await,
+                self.morbotron_client.name(),
+                self.morbotron_client.last_ranking(),
+            ),
+        };
+
+        if let Err(e) = result {
+            error!("Error handling {} command: {:?}", service.command(), e);
+            if let Err(e) = msg
+                .channel_id
+                .say(&ctx.http, format!("Error searching {name}"))
+                .await
+            {
+                error!("Error sending error message: {:?}", e);
+            }
+            return;
         }
 
-        // Add Pollinations image quota
-        if !self.imagine_channels.is_empty() {
-            let (img_min_used, img_min_limit, img_day_used, img_day_limit) =
-                self.image_rate_limiter.get_usage_stats().await;
-            info.push_str(&format!(
-                "- Image API (Pollinations): {}/{} per minute, {}/{} per day\n",
-                img_min_used, img_min_limit, img_day_used, img_day_limit
-            ));
+        if debug {
+            let report = ranking
+                .map(|ranking| ranking.format_debug())
+                .unwrap_or_else(|| {
+                    "🔍 No ranked candidates for that search (it fell back to a random frame)."
+                        .to_string()
+                });
+            if let Err(e) = utils::send_without_pings(&ctx.http, msg.channel_id, report).await {
+                error!("Error sending screenshot search debug: {:?}", e);
+            }
         }
 
-        // Send the info message
-        if let Err(e) = msg.channel_id.say(&ctx.http, info).await {
-            error!("Error sending info message: {:?}", e);
+        self.last_screenshot_searches.record(
+            msg.author.id,
+            msg.channel_id,
+            LastSearch { service, args },
+        );
+    }
+
+    // Handle !again (and !frinkiac/!morbotron -next): repeat the user's last screenshot
+    // search in this channel to get a different frame
+    async fn handle_again_command(
+        &self,
+        ctx: &Context,
+        msg: &Message,
+        service: Option<ScreenshotService>,
+    ) -> Result<()> {
+        let last = self
+            .last_screenshot_searches
+            .last(msg.author.id, msg.channel_id)
+            .filter(|search| service.is_none_or(|service| search.service == service));
+
+        let Some(search) = last else {
+            msg.reply(
+                &ctx.http,
+                "Nothing to repeat. Search with `!frinkiac <term>` or `!morbotron <term>` first, then use `!again` to see a different frame.",
+            )
+            .await?;
+            return Ok(());
+        };
+
+        if !self
+            .command_allowed_here(ctx, msg, search.service.command())
+            .await
+        {
+            return Ok(());
         }
 
+        info!(
+            "Repeating {} search {:?} for {}",
+            search.service.command(),
+            search.args,
+            msg.author.name
+        );
+        self.run_screenshot_search(ctx, msg, search.service, search.args)
+            .await;
         Ok(())
     }
 
-    // Generate a crime fighting duo description
-    async fn generate_crime_fighting_duo(&self, ctx: &Context, msg: &Message) -> Result<String> {
-        // Try to get the list of recent speakers, but use defaults if anything fails
-        let data = ctx.data.read().await;
+    // Reply to a message that addressed the bot, by name or by mention, with an AI response.
+    // `content` is what the user asked, which is sent on with the channel's context.
+    async fn reply_with_ai(&self, ctx: &Context, msg: &Message, content: String) -> Result<()> {
+        if let Some(gemini_client) = &self.gemini_client {
+            // Keep one user from using up the shared AI budget
+            if !self.take_reply_token(ctx, msg).await {
+                return Ok(());
+            }
 
-        // Default names to use if we can't get real speakers
-        let default_speaker1 = "The Mysterious Stranger".to_string();
-        let default_speaker2 = "The Unknown Vigilante".to_string();
+            // Get and clean the display name
+            let display_name = get_best_display_name(ctx, msg).await;
+            let clean_display_name = clean_display_name(&display_name);
 
-        // Get the username of the person who invoked the command
-        let invoker_username = msg.author.name.clone();
+            // Registered pronouns, or the ones in the display name
+            let user_pronouns = self.user_pronouns(msg, &display_name).await;
 
-        // Try to get real speaker names, but fall back to defaults at any error
-        let (speaker1, speaker2) = match data.get::<RecentSpeakersKey>() {
-            Some(recent_speakers_lock) => {
-                match recent_speakers_lock.try_read() {
-                    Ok(recent_speakers) => {
-                        // Only log speakers at debug level
-                        if tracing::level_enabled!(tracing::Level::DEBUG) {
-                            let all_speakers: Vec<String> = recent_speakers
-                                .iter()
-                                .map(|(_, display)| display.clone())
-                                .collect();
-                            debug!("All speakers before filtering: {:?}", all_speakers);
-                        }
+            // Start typing indicator before making API call
+            if let Err(e) = msg.channel_id.broadcast_typing(&ctx.http).await {
+                error!("Failed to send typing indicator: {:?}", e);
+            }
 
-                        // Log the raw speakers list to help debug
-                        info!(
-                            "Raw speakers list: {:?}",
-                            recent_speakers
-                                .iter()
-                                .map(|(name, display)| format!("{name}:{display}"))
-                                .collect::<Vec<String>>()
-                        );
+            // Get recent messages for context
+            let context_messages = self
+                .recent_context(ctx, msg.channel_id, self.gemini_context_messages)
+                .await;
 
-                        // Filter out the invoker from the list of potential speakers
-                        let filtered_speakers: Vec<(String, String)> = recent_speakers
-                            .iter()
-                            .filter(|(username, _)| username != &invoker_username)
-                            .cloned()
-                            .collect();
+            // Convert to the format expected by generate_response_with_context_and_pronouns
+            let context_for_api: Vec<(String, String, Option<String>, String)> = context_messages
+                .iter()
+                .map(
+                    |(author, display_name, pronouns, content, _reply_context)| {
+                        (
+                            author.clone(),
+                            display_name.clone(),
+                            pronouns.clone(),
+                            content.clone(),
+                        )
+                    },
+                )
+                .collect();
+
+            // Extract media (images/video) from the message and any replied-to message
+            let media_items = media_utils::extract_media_from_message(msg, self.media_limits).await;
+            let youtube_urls = media_utils::extract_youtube_urls(&content);
+            let has_media = !media_items.is_empty() || !youtube_urls.is_empty();
+
+            // What the user said, for conversation memory
+            let user_message = content.clone();
+
+            // Walk the reply chain so long back-and-forths keep their thread
+            let reply_thread =
+                reply_chain::collect_reply_thread(&ctx.http, msg, self.reply_chain_depth).await;
+            let content = match reply_chain::format_reply_thread(&reply_thread) {
+                Some(thread) => format!("{thread}{content}"),
+                None => content,
+            };
 
-                        // Only log filtered speakers at debug level
-                        if tracing::level_enabled!(tracing::Level::DEBUG) {
-                            let filtered_names: Vec<String> = filtered_speakers
-                                .iter()
-                                .map(|(_, display)| display.clone())
-                                .collect();
-                            debug!(
-                                "Filtered speakers (excluding invoker): {:?}",
-                                filtered_names
-                            );
-                        }
+            // Remind the bot of its recent exchanges with this user
+            let exchanges = self
+                .conversation_memory
+                .recent(msg.channel_id, msg.author.id);
+            let content = match conversation_memory::format_exchanges(
+                &clean_display_name,
+                &self.bot_name,
+                &exchanges,
+            ) {
+                Some(conversation) => format!("{conversation}{content}"),
+                None => content,
+            };
+
+            // Keep replies on-theme for the channel
+            let topic = self.channel_topic(ctx, msg.channel_id).await;
+            let content = prompt_templates::with_channel_topic(&content, topic.as_deref());
+
+            // Append GIF instruction if Giphy is configured
+            let gif_suffix = if self.giphy_client.is_some() {
+                giphy::GIF_INSTRUCTION
+            } else {
+                ""
+            };
+
+            // With streaming on, plain text replies fill in as Gemini writes them.
+            // The GIF instruction is left out since a GIF can't be streamed.
+            if self.stream_replies && !has_media {
+                match self
+                    .stream_direct_reply(
+                        ctx,
+                        msg,
+                        gemini_client,
+                        &content,
+                        &clean_display_name,
+                        &context_for_api,
+                        user_pronouns.as_deref(),
+                    )
+                    .await
+                {
+                    Ok((sent_id, response)) => {
+                        self.conversation_memory.record(
+                            msg.channel_id,
+                            msg.author.id,
+                            &user_message,
+                            &response,
+                        );
+                        self.recent_replies.record(
+                            sent_id,
+                            rephrase::ReplyContext {
+                                prompt: content,
+                                user_name: clean_display_name,
+                                user_pronouns,
+                                context: context_for_api,
+                                response,
+                            },
+                        );
+                        return Ok(());
+                    }
+                    Err(e) => {
+                        warn!("Streaming reply failed, sending it whole instead: {:?}", e)
+                    }
+                }
+            }
+
+            // Use multimodal path if media is present, otherwise standard text path
+            let content_with_gif = format!("{}{}", content, gif_suffix);
+            let response_result = if has_media {
+                info!(
+                    "Using multimodal path: {} media items, {} YouTube URLs",
+                    media_items.len(),
+                    youtube_urls.len()
+                );
+                let prompt = format!(
+                    "{}{}",
+                    gemini_client.prompt_templates().format_general_response(
+                        &content,
+                        &clean_display_name,
+                        "",
+                    ),
+                    gif_suffix
+                );
+                gemini_api::with_timeout(
+                    self.gemini_reply_timeout,
+                    gemini_client.generate_content_with_media(&prompt, &media_items, &youtube_urls),
+                )
+                .await
+                .map(Some)
+            } else {
+                gemini_api::with_timeout(
+                    self.gemini_reply_timeout,
+                    gemini_client.generate_best_response_with_context_and_pronouns(
+                        &content_with_gif,
+                        &clean_display_name,
+                        &context_for_api,
+                        user_pronouns.as_deref(),
+                        true, // Always respond when directly addressed
+                        GeminiTask::Reply,
+                    ),
+                )
+                .await
+            };
+
+            match response_result {
+                Ok(Some(response)) => {
+                    // Check if the response looks like the prompt itself (API error)
+                    if response.contains("{bot_name}")
+                        || response.contains("{context}")
+                        || response.contains("Guidelines:")
+                    {
+                        error!("Reply error: API returned prompt text");
+                        return Ok(());
+                    }
+
+                    // Check if the response is a GIF request
+                    if let Some(giphy_client) = &self.giphy_client {
+                        if let Some(gif_url) = giphy_client.try_resolve_gif(&response).await {
+                            let message_reference = MessageReference::from(msg);
+                            let create_message = CreateMessage::new()
+                                .content(gif_url)
+                                .reference_message(message_reference);
+                            if let Err(e) =
+                                msg.channel_id.send_message(&ctx.http, create_message).await
+                            {
+                                error!("Error sending GIF reply: {:?}", e);
+                            }
+                            return Ok(());
+                        }
+                        // Also check for embedded GIF (text + GIF: at the end)
+                        if let Some((text, gif_url)) =
+                            giphy_client.try_resolve_embedded_gif(&response).await
+                        {
+                            if !text.is_empty() {
+                                apply_realistic_delay(&text, ctx, msg.channel_id).await;
+                                let message_reference = MessageReference::from(msg);
+                                let create_message = CreateMessage::new()
+                                    .content(&text)
+                                    .reference_message(message_reference);
+                                if let Err(e) =
+                                    msg.channel_id.send_message(&ctx.http, create_message).await
+                                {
+                                    error!("Error sending text before GIF: {:?}", e);
+                                }
+                            }
+                            if let Err(e) = msg.channel_id.say(&ctx.http, &gif_url).await {
+                                error!("Error sending GIF reply: {:?}", e);
+                            }
+                            return Ok(());
+                        }
+                    }
+
+                    // Apply realistic typing delay based on response length
+                    apply_realistic_delay(&response, ctx, msg.channel_id).await;
+
+                    // Create a message reference for replying
+                    let message_reference = MessageReference::from(msg);
+                    let create_message = CreateMessage::new()
+                        .content(response.clone())
+                        .reference_message(message_reference);
+
+                    let sent = match msg.channel_id.send_message(&ctx.http, create_message).await {
+                        Ok(sent) => Some(sent),
+                        Err(e) => {
+                            error!("Error sending Gemini response as reply: {:?}", e);
+                            // Fallback to regular message if reply fails
+                            msg.channel_id
+                                .say(&ctx.http, &response)
+                                .await
+                                .map_err(|e| {
+                                    error!("Error sending fallback Gemini response: {:?}", e)
+                                })
+                                .ok()
+                        }
+                    };
+
+                    if sent.is_some() {
+                        self.conversation_memory.record(
+                            msg.channel_id,
+                            msg.author.id,
+                            &user_message,
+                            &response,
+                        );
+                    }
+
+                    // Remember how text replies were made so !rephrase can redo them
+                    if let Some(sent) = sent.filter(|_| !has_media) {
+                        self.recent_replies.record(
+                            sent.id,
+                            rephrase::ReplyContext {
+                                prompt: content_with_gif,
+                                user_name: clean_display_name,
+                                user_pronouns,
+                                context: context_for_api,
+                                response,
+                            },
+                        );
+                    }
+                }
+                Ok(None) => {
+                    // No response generated (all were "pass")
+                    return Ok(());
+                }
+                Err(e) => {
+                    error!("Error calling Gemini API: {:?}", e);
+
+                    let Some(user_message) =
+                        Self::ai_error_notice(&e, true, &self.rate_limit_reply_message)
+                    else {
+                        return Ok(());
+                    };
+
+                    // Create a message reference for replying
+                    let message_reference = MessageReference::from(msg);
+                    let create_message = CreateMessage::new()
+                        .content(&user_message)
+                        .reference_message(message_reference);
+
+                    if let Err(e) = msg.channel_id.send_message(&ctx.http, create_message).await {
+                        error!("Error sending error message as reply: {:?}", e);
+                        // Fallback to regular message if reply fails
+                        if let Err(e) = msg.channel_id.say(&ctx.http, &user_message).await {
+                            error!("Error sending fallback error message: {:?}", e);
+                        }
+                    }
+                }
+            }
+        } else {
+            // No Gemini API configured, use a simple response
+            if let Err(e) = msg
+                .reply(
+                    &ctx.http,
+                    self.unavailable_messages.message(FeatureUnavailable::Ai),
+                )
+                .await
+            {
+                error!("Error sending simple response: {:?}", e);
+            }
+        }
+        Ok(())
+    }
+
+    // Stream a reply to a direct address into a message that fills in as Gemini writes it.
+    // Returns the reply's first message and its text; fails without leaving anything posted
+    // if the stream can't get started or the reply turns out to echo the prompt, so the
+    // caller can send the reply the regular way.
+    #[allow(clippy::too_many_arguments)]
+    async fn stream_direct_reply(
+        &self,
+        ctx: &Context,
+        msg: &Message,
+        gemini_client: &GeminiClient,
+        prompt: &str,
+        user_name: &str,
+        context: &[(String, String, Option<String>, String)],
+        user_pronouns: Option<&str>,
+    ) -> Result<(MessageId, String)> {
+        let chunks = gemini_client
+            .generate_response_stream(
+                prompt,
+                user_name,
+                context,
+                user_pronouns,
+                GeminiTask::Reply,
+                self.gemini_reply_timeout,
+            )
+            .await?;
+        let (messages, text) = streaming_reply::stream_reply(&ctx.http, msg, chunks, |text| {
+            if unknown_command::is_prompt_echo(text) {
+                error!("Streamed reply echoed the prompt, removing it");
+                return String::new();
+            }
+            prompt_templates::apply_confidence_marker(text)
+        })
+        .await?;
+        let first = messages
+            .first()
+            .ok_or_else(|| anyhow::anyhow!("The streamed reply was never posted"))?;
+        Ok((first.id, text))
+    }
+
+    // Handle !rephrase, sent as a reply to one of the bot's AI replies: generate it again
+    // with an optional style hint and temperature, then edit the original or post a new reply
+    async fn handle_rephrase_command(
+        &self,
+        ctx: &Context,
+        msg: &Message,
+        args: &[&str],
+    ) -> Result<()> {
+        let Some(gemini_client) = &self.gemini_client else {
+            msg.reply(
+                &ctx.http,
+                self.unavailable_messages.message(FeatureUnavailable::Ai),
+            )
+            .await?;
+            return Ok(());
+        };
+
+        let request = match rephrase::parse_rephrase_args(args) {
+            Ok(request) => request,
+            Err(e) => {
+                msg.reply(
+                    &ctx.http,
+                    format!("{e}. Usage: `!rephrase [style] [-t temperature]`"),
+                )
+                .await?;
+                return Ok(());
+            }
+        };
+
+        let bot_user_id = self.get_bot_user_id(ctx).await;
+        let replied_to = msg
+            .referenced_message
+            .as_deref()
+            .map(|original| (original.id, original.author.id));
+        let (target_id, original) =
+            match self.recent_replies.rephrase_target(replied_to, bot_user_id) {
+                Ok(target) => target,
+                Err(notice) => {
+                    msg.reply(&ctx.http, notice).await?;
+                    return Ok(());
+                }
+            };
+
+        info!(
+            "Rephrasing reply {} for {} (style: {:?}, temperature: {:?})",
+            target_id, msg.author.name, request.style, request.temperature
+        );
+
+        if let Err(e) = msg.channel_id.broadcast_typing(&ctx.http).await {
+            error!("Failed to send typing indicator: {:?}", e);
+        }
+
+        let adjusted_client;
+        let client = match request.temperature {
+            Some(temperature) => {
+                adjusted_client = gemini_client.with_reply_temperature(temperature);
+                &adjusted_client
+            }
+            None => gemini_client,
+        };
+
+        let prompt = rephrase::rephrase_prompt(&original, request.style.as_deref());
+        let response = match client
+            .generate_best_response_with_context_and_pronouns(
+                &prompt,
+                &original.user_name,
+                &original.context,
+                original.user_pronouns.as_deref(),
+                true,
+                GeminiTask::Reply,
+            )
+            .await
+        {
+            Ok(Some(response)) => response,
+            Ok(None) => {
+                msg.reply(&ctx.http, "I've got nothing better than that.")
+                    .await?;
+                return Ok(());
+            }
+            Err(e) => {
+                error!("Error rephrasing reply: {:?}", e);
+                if let Some(notice) =
+                    Self::ai_error_notice(&e, true, &self.rate_limit_reply_message)
+                {
+                    msg.reply(&ctx.http, notice).await?;
+                }
+                return Ok(());
+            }
+        };
+
+        // The original prompt stays, so rephrasing a rephrase starts from the same question
+        let rephrased = rephrase::ReplyContext {
+            response: response.clone(),
+            ..original
+        };
+        if self.rephrase_edit_original {
+            msg.channel_id
+                .edit_message(&ctx.http, target_id, EditMessage::new().content(&response))
+                .await?;
+            self.recent_replies.record(target_id, rephrased);
+        } else {
+            let sent = msg.reply(&ctx.http, &response).await?;
+            self.recent_replies.record(sent.id, rephrased);
+        }
+        Ok(())
+    }
+
+    // Spend one of the author's AI reply tokens. When they're out, the first refusal gets
+    // a short notice and later ones are skipped silently until tokens come back.
+    async fn take_reply_token(&self, ctx: &Context, msg: &Message) -> bool {
+        match self.user_reply_buckets.try_take(msg.author.id) {
+            rate_limiter::UserBudget::Allowed => true,
+            rate_limiter::UserBudget::Limited {
+                retry_after,
+                notify,
+            } => {
+                info!(
+                    "Skipping AI reply to {}: out of reply tokens for {}s",
+                    msg.author.name,
+                    retry_after.as_secs()
+                );
+                if notify {
+                    let notice = format!(
+                        "Give me a moment to catch up, try again in {}.",
+                        command_cooldown::format_wait(retry_after)
+                    );
+                    if let Err(e) = msg.reply(&ctx.http, notice).await {
+                        error!("Error sending reply cooldown notice: {:?}", e);
+                    }
+                }
+                false
+            }
+        }
+    }
+
+    // Recent messages in a channel for context, newest first
+    async fn recent_context(
+        &self,
+        ctx: &Context,
+        channel_id: ChannelId,
+        limit: usize,
+    ) -> Vec<ContextMessage> {
+        let history = ctx.data.read().await.get::<MessageHistoryKey>().cloned();
+        recent_context(
+            self.message_store.as_ref(),
+            history.as_ref(),
+            channel_id,
+            limit,
+        )
+        .await
+    }
+
+    // Save a message to the message store and the in-memory history
+    async fn record_message(&self, ctx: &Context, msg: &Message) {
+        // Get the display name
+        let display_name = get_best_display_name(ctx, msg).await;
+
+        // Check if this is a gateway bot message and extract the real username
+        let (author_name, final_display_name) = if msg.author.bot {
+            let bot_id = msg.author.id;
+
+            // Check if this is a gateway bot
+            if self.gateway_bot_ids.contains(&bot_id.get()) {
+                // Try to extract the gateway username from the message content or author name
+                if let Some(gateway_username) = crate::display_name::extract_gateway_username(msg) {
+                    // Log the extraction for debugging
+                    info!(
+                        "📝 Extracted gateway username for bot {}: {}",
+                        bot_id, gateway_username
+                    );
+
+                    // Use the gateway username as both author and display name
+                    (gateway_username.clone(), gateway_username)
+                } else {
+                    // Fallback to the display name we got earlier
+                    info!(
+                        "📝 Could not extract gateway username for bot {}, using fallback",
+                        bot_id
+                    );
+                    (msg.author.name.clone(), display_name)
+                }
+            } else {
+                // Regular bot, use the display name we got earlier
+                (msg.author.name.clone(), display_name)
+            }
+        } else {
+            // Regular user, use the display name we got earlier
+            (msg.author.name.clone(), display_name)
+        };
+
+        // Save the message to the database (include attachment metadata)
+        let attachment_tags = media_utils::describe_attachments(msg);
+        let stored_content = if attachment_tags.is_empty() {
+            msg.content.clone()
+        } else {
+            format!("{} {}", msg.content, attachment_tags)
+        };
+
+        if let Some(db) = &self.message_store {
+            match db
+                .save(
+                    &author_name,
+                    &final_display_name,
+                    &stored_content,
+                    Some(msg),
+                )
+                .await
+            {
+                Ok(true) => self.message_count_cache.record_insert(),
+                Ok(false) => {}
+                Err(e) => error!("Error saving message to database: {:?}", e),
+            }
+        }
+
+        remember_message(
+            ctx,
+            StoredMessage::from_message(&author_name, &final_display_name, &stored_content, msg),
+        )
+        .await;
+    }
+
+    // Pronouns for the author of a message: the ones they registered with !pronouns set,
+    // otherwise whatever their display name carries
+    async fn user_pronouns(&self, msg: &Message, display_name: &str) -> Option<String> {
+        let registered = match &self.message_db {
+            Some(db) => pronouns::registered_pronouns(db.clone(), msg.author.id.to_string())
+                .await
+                .unwrap_or_else(|e| {
+                    error!("Error looking up registered pronouns: {:?}", e);
+                    None
+                }),
+            None => None,
+        };
+        pronouns::preferred_pronouns(registered, display_name)
+    }
+
+    // Handle the !uptime command
+    async fn handle_uptime_command(&self, ctx: &Context, msg: &Message) -> Result<()> {
+        let uptime = self.start_time.elapsed();
+        let started_at_unix = std::time::SystemTime::now()
+            .checked_sub(uptime)
+            .and_then(|start| start.duration_since(std::time::UNIX_EPOCH).ok())
+            .map(|since_epoch| since_epoch.as_secs())
+            .unwrap_or(0);
+
+        msg.channel_id
+            .say(
+                &ctx.http,
+                Self::format_uptime_reply(uptime, started_at_unix),
+            )
+            .await?;
+        Ok(())
+    }
+
+    // Handle the !info command
+    async fn handle_info_command(
+        &self,
+        ctx: &Context,
+        msg: &Message,
+        force_recount: bool,
+    ) -> Result<()> {
+        // Calculate uptime
+        let uptime = self.start_time.elapsed();
+        let uptime_str = Self::format_duration(uptime);
+
+        // Get message history count
+        let message_count = if let Some(store) = &self.message_store {
+            match self
+                .message_count_cache
+                .get(store.count_messages(), force_recount)
+                .await
+            {
+                Ok(count) => count.to_string(),
+                Err(_) => "Unknown".to_string(),
+            }
+        } else {
+            "Database not available".to_string()
+        };
+
+        // Get memory usage (resident set size)
+        let memory_usage = match utils::current_rss_bytes() {
+            Some(bytes) => format!("{:.2} MB", bytes as f64 / (1024.0 * 1024.0)),
+            None => "Unknown".to_string(),
+        };
+
+        // Count followed channels
+        let channel_count = self.followed_channels.len();
+
+        // Build the info message
+        let mut info = format!("**{} Bot Info**\n\n", self.bot_name);
+
+        info.push_str(&format!("**Uptime:** {uptime_str}\n"));
+        info.push_str(&format!("**Messages in database:** {message_count}\n"));
+        info.push_str(&format!("**Memory usage:** {memory_usage}\n"));
+        info.push_str(&format!("**Following {channel_count} channels**\n"));
+
+        // Add feature status
+        info.push_str("\n**Features:**\n");
+        info.push_str(&format!(
+            "- DuckDuckGo search: {}\n",
+            if self.duckduckgo_search_enabled {
+                "Enabled"
+            } else {
+                "Disabled"
+            }
+        ));
+        info.push_str(&format!(
+            "- AI responses: {}\n",
+            if self.gemini_client.is_some() {
+                "Enabled"
+            } else {
+                "Disabled"
+            }
+        ));
+        info.push_str(&format!(
+            "- Image generation: {}\n",
+            if !self.imagine_channels.is_empty() {
+                format!("Enabled in {} channels", self.imagine_channels.len())
+            } else {
+                "Disabled".to_string()
+            }
+        ));
+
+        // Add API quota information if Gemini client is available
+        if let Some(gemini_client) = &self.gemini_client {
+            let (limits, _image_quota, _image_status) = gemini_client.get_quota_stats().await;
+            let tokens = gemini_client.token_usage_today();
+            info.push_str("\n**API Quotas:**\n");
+            info.push_str(&format!("- Gemini: {limits}"));
+            if let Some(resets_at) = limits.minute_resets_at {
+                info.push_str(&format!(
+                    " (next slot frees up <t:{}:R>)",
+                    resets_at.timestamp()
+                ));
+            }
+            info.push_str(&format!(
+                "\n- Daily limit resets <t:{}:R>\n",
+                limits.day_resets_at.timestamp()
+            ));
+            info.push_str(&format!(
+                "- Tokens today: {} ({} prompt, {} response)\n",
+                tokens.total_tokens, tokens.prompt_tokens, tokens.candidates_tokens
+            ));
+        }
+
+        // Add Pollinations image quota
+        if !self.imagine_channels.is_empty() {
+            info.push_str(&format!(
+                "- Image API (Pollinations): {}\n",
+                self.image_rate_limiter.snapshot()
+            ));
+        }
+
+        // Send the info message
+        if let Err(e) = msg.channel_id.say(&ctx.http, info).await {
+            error!("Error sending info message: {:?}", e);
+        }
+
+        Ok(())
+    }
+
+    // Generate a crime fighting duo description
+    async fn generate_crime_fighting_duo(&self, ctx: &Context, msg: &Message) -> Result<String> {
+        // Try to get the list of recent speakers, but use defaults if anything fails
+        let data = ctx.data.read().await;
+
+        // Default names to use if we can't get real speakers
+        let default_speaker1 = "The Mysterious Stranger".to_string();
+        let default_speaker2 = "The Unknown Vigilante".to_string();
+
+        // Get the username of the person who invoked the command
+        let invoker_username = msg.author.name.clone();
+
+        // Try to get real speaker names, but fall back to defaults at any error
+        let (speaker1, speaker2) = match data.get::<RecentSpeakersKey>() {
+            Some(recent_speakers_lock) => {
+                match recent_speakers_lock.try_read() {
+                    Ok(recent_speakers) => {
+                        // Only log speakers at debug level
+                        if tracing::level_enabled!(tracing::Level::DEBUG) {
+                            let all_speakers: Vec<String> = recent_speakers
+                                .iter()
+                                .map(|(_, display)| display.clone())
+                                .collect();
+                            debug!("All speakers before filtering: {:?}", all_speakers);
+                        }
+
+                        // Log the raw speakers list to help debug
+                        info!(
+                            "Raw speakers list: {:?}",
+                            recent_speakers
+                                .iter()
+                                .map(|(name, display)| format!("{name}:{display}"))
+                                .collect::<Vec<String>>()
+                        );
+
+                        // Filter out the invoker from the list of potential speakers
+                        let filtered_speakers: Vec<(String, String)> = recent_speakers
+                            .iter()
+                            .filter(|(username, _)| username != &invoker_username)
+                            .cloned()
+                            .collect();
+
+                        // Only log filtered speakers at debug level
+                        if tracing::level_enabled!(tracing::Level::DEBUG) {
+                            let filtered_names: Vec<String> = filtered_speakers
+                                .iter()
+                                .map(|(_, display)| display.clone())
+                                .collect();
+                            debug!(
+                                "Filtered speakers (excluding invoker): {:?}",
+                                filtered_names
+                            );
+                        }
 
                         if filtered_speakers.len() >= 2 {
                             // Get the last two speakers (most recent first)
@@ -749,8 +1848,19 @@ impl Bot {
             info!("Slogan request with no search term");
         }
 
+        if !self.db_manager.is_configured() {
+            msg.channel_id
+                .say(
+                    http,
+                    self.unavailable_messages
+                        .message(FeatureUnavailable::QuoteDatabase),
+                )
+                .await?;
+            return Ok(());
+        }
+
         self.db_manager
-            .query_random_entry(http, msg, search_term, None, "slogan")
+            .query_random_entry(http, msg, search_term, None, "slogan", 1)
             .await
     }
 
@@ -761,37 +1871,18 @@ impl Bot {
         msg: &Message,
         args: Vec<&str>,
     ) -> Result<()> {
-        // Parse arguments
-        let mut search_term = None;
-        let mut show_name = None;
-
-        let mut i = 0;
-        while i < args.len() {
-            if args[i] == "-show" && i + 1 < args.len() {
-                // Collect all words after -show until the next flag or end
-                let mut show = Vec::new();
-                i += 1;
-                while i < args.len() && !args[i].starts_with('-') {
-                    show.push(args[i]);
-                    i += 1;
-                }
-                show_name = Some(show.join(" "));
-            } else if !args[i].starts_with('-') {
-                // If not a flag, treat as search term
-                if search_term.is_none() {
-                    let mut terms = Vec::new();
-                    while i < args.len() && !args[i].starts_with('-') {
-                        terms.push(args[i]);
-                        i += 1;
-                    }
-                    search_term = Some(terms.join(" "));
-                } else {
-                    i += 1;
-                }
-            } else {
-                // Skip unknown flags
-                i += 1;
-            }
+        let (search_term, show_name) = quote_args(&args);
+        let count = quote_count(&args);
+
+        // The quote database doesn't know when anything was said
+        if args.iter().any(|arg| db_utils::DATE_FLAGS.contains(arg)) {
+            msg.channel_id
+                .say(
+                    http,
+                    "Show quotes don't have dates. -year, -after and -before work with !quote -dud.",
+                )
+                .await?;
+            return Ok(());
         }
 
         // Log the quote request
@@ -805,253 +1896,562 @@ impl Bot {
             info!("Quote request filtered by show: {}", show);
         }
 
+        if !self.db_manager.is_configured() {
+            msg.channel_id
+                .say(
+                    http,
+                    self.unavailable_messages
+                        .message(FeatureUnavailable::QuoteDatabase),
+                )
+                .await?;
+            return Ok(());
+        }
+
         // Pass both search term and show name to the database manager
         self.db_manager
-            .query_random_entry(http, msg, search_term, show_name, "quote")
+            .query_random_entry(http, msg, search_term, show_name, "quote", count)
             .await
     }
 
-    // Handle the !quote -dud command (quote a user)
+    // Handle the !quote -dud command (quote a user), posting up to `count` messages. Short
+    // messages are skipped unless `truly_random` is set.
     async fn handle_quote_dud_command(
         &self,
         http: &Http,
         msg: &Message,
         username: Option<String>,
+        truly_random: bool,
+        dates: db_utils::DateRange,
+        count: usize,
     ) -> Result<()> {
         // Check if we have a database connection
-        if let Some(db) = &self.message_db {
-            let db_clone = db.clone();
-
-            // Build the query based on whether a username was provided
-            let messages = if let Some(user) = &username {
-                let user_clone = user.clone();
-                info!("Quote -dud request for user: {}", user_clone);
-
-                // Query the database for messages from this user
-                db_clone.lock().await.call(move |conn| {
-                    // First check if display_name column exists
-                    let has_display_name = conn
-                        .prepare("PRAGMA table_info(messages)")
-                        .and_then(|mut stmt| {
-                            let rows = stmt.query_map([], |row| {
-                                let name: String = row.get(1)?;
-                                Ok(name)
-                            })?;
-
-                            let has_display_name = rows.flatten().any(|name| name == "display_name");
-                            Ok(has_display_name)
-                        })
-                        .unwrap_or(false);
-
-                    let mut result = Vec::new();
-
-                    if has_display_name {
-                        let query = "SELECT author, display_name, content FROM messages WHERE author = ? OR display_name LIKE ? ORDER BY RANDOM() LIMIT 1";
-                        let mut stmt = conn.prepare(query)?;
-                        let search_pattern = format!("%{}%", &user_clone);
-                        let rows = stmt.query_map([&user_clone, &search_pattern], |row| {
-                            Ok((
-                                row.get::<_, String>(0)?,
-                                row.get::<_, String>(1)?,
-                                row.get::<_, String>(2)?
-                            ))
-                        })?;
-
-                        for row in rows {
-                            result.push(row?);
-                        }
-                    } else {
-                        let query = "SELECT author, author as display_name, content FROM messages WHERE author = ? ORDER BY RANDOM() LIMIT 1";
-                        let mut stmt = conn.prepare(query)?;
-                        let rows = stmt.query_map([&user_clone], |row| {
-                            Ok((
-                                row.get::<_, String>(0)?,
-                                row.get::<_, String>(1)?,
-                                row.get::<_, String>(2)?
-                            ))
-                        })?;
-
-                        for row in rows {
-                            result.push(row?);
-                        }
-                    }
+        if let Some(db) = &self.message_store {
+            match &username {
+                Some(user) => info!("Quote -dud request for user: {}", user),
+                None => info!("Quote -dud request for random user"),
+            }
 
-                    Ok::<_, rusqlite::Error>(result)
-                }).await?
+            let min_length = if truly_random {
+                0
             } else {
-                info!("Quote -dud request for random user");
-
-                // Query the database for a random message from any user
-                db_clone.lock().await.call(move |conn| {
-                    // First check if display_name column exists
-                    let has_display_name = conn
-                        .prepare("PRAGMA table_info(messages)")
-                        .and_then(|mut stmt| {
-                            let rows = stmt.query_map([], |row| {
-                                let name: String = row.get(1)?;
-                                Ok(name)
-                            })?;
-
-                            let has_display_name = rows.flatten().any(|name| name == "display_name");
-                            Ok(has_display_name)
-                        })
-                        .unwrap_or(false);
-
-                    let query = if has_display_name {
-                        "SELECT author, display_name, content FROM messages ORDER BY RANDOM() LIMIT 1"
-                    } else {
-                        "SELECT author, author as display_name, content FROM messages ORDER BY RANDOM() LIMIT 1"
-                    };
-
-                    let mut stmt = conn.prepare(query)?;
-
-                    let rows = stmt.query_map([], |row| {
-                        Ok((
-                            row.get::<_, String>(0)?,
-                            row.get::<_, String>(1)?,
-                            row.get::<_, String>(2)?
-                        ))
-                    })?;
-
-                    let mut result = Vec::new();
-                    for row in rows {
-                        result.push(row?);
+                self.quote_dud_min_length
+            };
+            // Stay within this server unless configured otherwise
+            let guild_id = msg
+                .guild_id
+                .filter(|_| !self.all_guilds)
+                .map(|id| id.to_string());
+
+            // Prefer whoever has exactly that name, so "al" doesn't quote Randal
+            let name_match = match &username {
+                Some(user) => {
+                    let name_match = db
+                        .resolve_dud_name(user.clone(), guild_id.clone())
+                        .await
+                        .map_err(|e| anyhow::anyhow!("Error looking up user: {}", e))?;
+                    if name_match.is_none() {
+                        msg.channel_id
+                            .say(http, format!("No messages found from user {user}"))
+                            .await?;
+                        return Ok(());
                     }
-
-                    Ok::<_, rusqlite::Error>(result)
-                }).await?
+                    name_match
+                }
+                None => None,
             };
 
-            // If we found a message, send it
-            if let Some((author, display_name, content)) = messages.first() {
-                // Use the display name if available, otherwise fall back to author name
-                let name_to_use = if !display_name.is_empty() {
-                    display_name
-                } else {
-                    author
-                };
-
-                // Use the display_name::clean_display_name function for consistency
-                // This will also strip angle brackets if the name is in gateway format
-                let clean_display_name = display_name::clean_display_name(name_to_use);
+            let quotes = db
+                .random_dud_quotes(
+                    name_match.as_ref().map(|found| found.name().to_string()),
+                    guild_id,
+                    dates,
+                    min_length,
+                    count,
+                )
+                .await
+                .map_err(|e| anyhow::anyhow!("Error fetching quote: {}", e))?;
 
-                msg.channel_id
-                    .say(http, format!("<{clean_display_name}> {content}"))
-                    .await?;
+            // If we found messages, send them
+            if !quotes.is_empty() {
+                let mut lines = format_dud_quotes(&quotes);
+                if let (Some(user), Some(found)) = (&username, &name_match) {
+                    lines.splice(0..0, dud_name_note(user, found));
+                }
+                for message in
+                    streaming_reply::pack_lines(&lines, streaming_reply::DISCORD_MESSAGE_LIMIT)
+                {
+                    msg.channel_id.say(http, message).await?;
+                }
             } else {
                 // No messages found
                 if let Some(user) = username {
                     msg.channel_id
                         .say(http, format!("No messages found from user {user}"))
                         .await?;
+                } else if !dates.is_unbounded() {
+                    msg.channel_id
+                        .say(http, "No messages found from then")
+                        .await?;
                 } else {
                     msg.channel_id
                         .say(http, "No messages found in the database")
                         .await?;
                 }
             }
-        } else {
-            // No database connection
-            msg.channel_id
-                .say(http, "Message history database is not available")
+        } else {
+            // No database connection
+            msg.channel_id
+                .say(
+                    http,
+                    self.unavailable_messages
+                        .message(FeatureUnavailable::MessageHistory),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+}
+impl Bot {
+    /// Send a quote from the fallback pool (MST3K_QUOTES_FILE or the built-in list)
+    async fn send_fallback_mst3k_quote(&self, ctx: &Context, channel_id: ChannelId) {
+        let Some(quote) = mst3k_quotes::fallback_mst3k_quote(&self.mst3k_fallback_quotes) else {
+            self.mark_interjection_suppressed(InterjectionKind::Mst3k, channel_id, "no quotes")
+                .await;
+            return;
+        };
+        match send_without_pings(&ctx.http, channel_id, &quote).await {
+            Ok(sent) => {
+                info!("Fallback MST3K quote interjection sent: {}", quote);
+                self.mark_interjection_sent(InterjectionKind::Mst3k, channel_id, Some(sent.id))
+                    .await;
+            }
+            Err(e) => {
+                error!("Error sending fallback MST3K quote: {:?}", e);
+                self.mark_interjection_suppressed(
+                    InterjectionKind::Mst3k,
+                    channel_id,
+                    "send failed",
+                )
+                .await;
+            }
+        }
+    }
+
+    /// Whether interjections should hold off because the bot spoke last in the channel and
+    /// nobody has spoken since, so it doesn't end up talking to itself
+    async fn bot_has_last_word(&self, channel_id: ChannelId) -> bool {
+        if !self.skip_interjections_after_bot
+            || !self
+                .fill_silence_manager
+                .bot_was_last_speaker(channel_id)
+                .await
+        {
+            return false;
+        }
+        info!(
+            "Bot spoke last in channel {}, holding off on interjections",
+            channel_id
+        );
+        true
+    }
+
+    /// Record that a spontaneous interjection was sent (for recency dampening and the hourly cap)
+    async fn mark_interjection_sent(
+        &self,
+        kind: InterjectionKind,
+        channel_id: ChannelId,
+        sent: Option<MessageId>,
+    ) {
+        {
+            let mut last = self.last_interjection_time.write().await;
+            *last = Some(Instant::now());
+        }
+        self.interjection_cap.record();
+        if let (Some(adaptive), Some(message_id)) = (&self.adaptive_interjections, sent) {
+            adaptive.record_sent(message_id, kind);
+        }
+        match sent {
+            Some(_) => {
+                self.interjection_stats.record_fired(kind);
+                self.record_interjection_outcome(kind, channel_id, Outcome::Fired, None)
+                    .await
+            }
+            None => {
+                self.interjection_stats
+                    .record_suppressed(kind, "send failed");
+                self.record_interjection_outcome(
+                    kind,
+                    channel_id,
+                    Outcome::Suppressed,
+                    Some("send failed"),
+                )
+                .await
+            }
+        }
+    }
+
+    /// Record why an interjection that was rolled for didn't go out
+    async fn mark_interjection_suppressed(
+        &self,
+        kind: InterjectionKind,
+        channel_id: ChannelId,
+        reason: &str,
+    ) {
+        self.interjection_stats.record_suppressed(kind, reason);
+        self.record_interjection_outcome(kind, channel_id, Outcome::Suppressed, Some(reason))
+            .await;
+    }
+
+    /// Roll for an interjection type configured at `base`, scaled by silence, recency and what
+    /// adaptive mode has learned. Logs the decision when INTERJECTION_METRICS is on.
+    async fn roll_interjection(
+        &self,
+        kind: InterjectionKind,
+        base: f64,
+        context: &interjection::RollContext,
+    ) -> bool {
+        // Forced interjections skip the roll (and the metrics, since nothing was rolled)
+        if let Some(forced) = context.forced {
+            if forced == kind {
+                info!("Forcing {} interjection", kind.name());
+            }
+            return forced == kind;
+        }
+
+        let learned = self.interjection_probability(kind, base);
+        let multiplier = context.silence_multiplier * context.recency_multiplier;
+        let hit = interjection::should_interject(learned, multiplier, kind.name());
+        if let (true, Some(db)) = (self.interjection_metrics, &self.message_db) {
+            // What each roll is logged with
+            let roll = Roll {
+                kind,
+                channel_id: context.channel_id,
+                base_probability: base,
+                silence_multiplier: context.silence_multiplier,
+                adjusted_probability: interjection::adjusted_probability(learned, multiplier),
+                held_off: context.held_off,
+            };
+            if let Err(e) = interjection_metrics::record_roll(db.clone(), &roll, hit).await {
+                warn!("Failed to log interjection roll: {:?}", e);
+            }
+        }
+        hit
+    }
+
+    async fn record_interjection_outcome(
+        &self,
+        kind: InterjectionKind,
+        channel_id: ChannelId,
+        outcome: Outcome,
+        reason: Option<&str>,
+    ) {
+        if let (true, Some(db)) = (self.interjection_metrics, &self.message_db) {
+            if let Err(e) =
+                interjection_metrics::record_outcome(db.clone(), kind, channel_id, outcome, reason)
+                    .await
+            {
+                warn!("Failed to log interjection outcome: {:?}", e);
+            }
+        }
+    }
+
+    /// An interjection type's probability, scaled by what adaptive mode has learned
+    fn interjection_probability(&self, kind: InterjectionKind, base: f64) -> f64 {
+        match &self.adaptive_interjections {
+            Some(adaptive) => adaptive.probability(kind, base),
+            None => base,
+        }
+    }
+
+    // Function to check if the bot is being addressed
+    fn is_bot_addressed(&self, content: &str) -> bool {
+        self.address_detector.is_addressed(content)
+    }
+
+    // Check per-command channel rules, telling the user where the command is allowed if not here
+    async fn command_allowed_here(&self, ctx: &Context, msg: &Message, command: &str) -> bool {
+        if !self.command_channel_rules.is_gated(command) {
+            return true;
+        }
+
+        let channel = ChannelInfo::fetch(&ctx.http, msg.channel_id).await;
+        if self
+            .command_channel_rules
+            .command_allowed_in(&channel, command)
+        {
+            return true;
+        }
+
+        info!(
+            "Command !{} not allowed in channel {} ({})",
+            command, channel.name, channel.id
+        );
+        if let Err(e) = msg
+            .reply(
+                &ctx.http,
+                self.command_channel_rules.denial_message(command),
+            )
+            .await
+        {
+            error!("Error sending channel restriction message: {:?}", e);
+        }
+        false
+    }
+
+    // Tell a user how long until they can run a command again, offering to run it for them
+    // then if the cooldown_retry reaction feature is on
+    async fn send_cooldown_notice(
+        &self,
+        ctx: &Context,
+        msg: &Message,
+        command: &str,
+        wait: Duration,
+    ) {
+        info!(
+            "{} is on cooldown for !{} for another {:?}",
+            msg.author.name, command, wait
+        );
+
+        let retry = self.reaction_features.iter().any(|f| f == "cooldown_retry");
+        let mut notice = format!(
+            "Slow down! You can use !{command} again in {}.",
+            command_cooldown::format_wait(wait)
+        );
+        if retry {
+            notice.push_str(&format!(
+                " React with {} and I'll run it for you then.",
+                command_cooldown::RETRY_EMOJI
+            ));
+        }
+
+        let sent = match msg.reply(&ctx.http, notice).await {
+            Ok(sent) => sent,
+            Err(e) => {
+                error!("Error sending cooldown notice: {:?}", e);
+                return;
+            }
+        };
+
+        if retry {
+            self.pending_command_retries.add(sent.id, msg.clone(), wait);
+            if let Err(e) = sent
+                .react(
+                    &ctx.http,
+                    ReactionType::Unicode(command_cooldown::RETRY_EMOJI.to_string()),
+                )
+                .await
+            {
+                error!("Error adding retry reaction to cooldown notice: {:?}", e);
+            }
+        }
+    }
+
+    // Re-run a cooldown-blocked command once its author reacts to the notice and the
+    // cooldown has run out
+    async fn retry_after_cooldown(&self, ctx: &Context, reaction: &Reaction) {
+        let Some(user_id) = reaction.user_id else {
+            return;
+        };
+        if !reaction.emoji.unicode_eq(command_cooldown::RETRY_EMOJI) {
+            return;
+        }
+        let Some((command, wait)) = self
+            .pending_command_retries
+            .take(reaction.message_id, user_id)
+        else {
+            return;
+        };
+
+        info!(
+            "Re-running {:?} for {} in {:?}, after its cooldown",
+            command.content, command.author.name, wait
+        );
+        tokio::time::sleep(wait).await;
+
+        // The original message was already processed once, so let it through again
+        self.processed_messages
+            .write()
+            .await
+            .retain(|id| *id != command.id);
+        if let Err(e) = self.process_message(ctx, &command).await {
+            error!("Error re-running command after cooldown: {:?}", e);
+        }
+    }
+
+    // Count a 👍 or 👎 on one of our interjections towards adaptive interjection probabilities
+    async fn record_interjection_feedback(&self, reaction: &Reaction) {
+        let (Some(adaptive), Some(user_id)) = (&self.adaptive_interjections, reaction.user_id)
+        else {
+            return;
+        };
+        // Ignore the bot's own reactions
+        if *self.bot_user_id.read().await == Some(user_id) {
+            return;
+        }
+        let ReactionType::Unicode(emoji) = &reaction.emoji else {
+            return;
+        };
+        if let Some(feedback) = Feedback::from_emoji(emoji) {
+            adaptive
+                .record_reaction(reaction.message_id, user_id, feedback)
+                .await;
+        }
+    }
+
+    /// The channel's topic for prompt context, if it has one and the feature is enabled
+    async fn channel_topic(&self, ctx: &Context, channel_id: ChannelId) -> Option<String> {
+        match &self.channel_topics {
+            Some(cache) => cache.get(&ctx.http, channel_id).await,
+            None => None,
+        }
+    }
+
+    /// Whether a user may run owner-only commands. Uses OWNER_IDS when configured, otherwise
+    /// the owner (or team members) of the bot's Discord application.
+    async fn is_owner(&self, ctx: &Context, user_id: UserId) -> bool {
+        if !self.owner_ids.is_empty() {
+            return self.owner_ids.contains(&user_id);
+        }
+
+        match ctx.http.get_current_application_info().await {
+            Ok(info) => {
+                info.owner.as_ref().is_some_and(|owner| owner.id == user_id)
+                    || info
+                        .team
+                        .as_ref()
+                        .is_some_and(|team| team.members.iter().any(|m| m.user.id == user_id))
+            }
+            Err(e) => {
+                error!("Error fetching application info for owner check: {:?}", e);
+                false
+            }
+        }
+    }
+
+    // Whether a user may run admin commands: listed in ADMIN_USER_IDS, or an owner
+    async fn is_admin(&self, ctx: &Context, user_id: UserId) -> bool {
+        self.admin_user_ids.contains(&user_id) || self.is_owner(ctx, user_id).await
+    }
+
+    // Drop a forgotten user from what the bot remembers outside the database
+    async fn forget_in_memory(&self, ctx: &Context, forgotten: &Forgotten) {
+        if let Some(user_id) = forgotten.user_id {
+            self.conversation_memory.forget_user(user_id);
+        }
+
+        let data = ctx.data.read().await;
+        if let Some(history) = data.get::<MessageHistoryKey>() {
+            let removed = history.write().await.forget_names(&forgotten.names);
+            if removed > 0 {
+                info!(
+                    "Removed {} forgotten message(s) from the in-memory history",
+                    removed
+                );
+            }
+        }
+        if let Some(recent_speakers) = data.get::<RecentSpeakersKey>() {
+            let removed = forget_speaker(&mut *recent_speakers.write().await, &forgotten.names);
+            if removed > 0 {
+                info!(
+                    "Removed {} forgotten speaker(s) from the recent speakers list",
+                    removed
+                );
+            }
+        }
+    }
+
+    // Handle the admin-only !interject <type>: run that interjection in this channel now,
+    // skipping its roll, so a config change can be checked without waiting on the odds
+    async fn handle_interject_command(
+        &self,
+        ctx: &Context,
+        msg: &Message,
+        args: &[&str],
+    ) -> Result<()> {
+        if !self.is_admin(ctx, msg.author.id).await {
+            info!("Ignoring !interject from non-admin {}", msg.author.name);
+            msg.reply(&ctx.http, "You're not authorized to use !interject.")
                 .await?;
+            return Ok(());
         }
 
+        let Some(kind) = args
+            .first()
+            .and_then(|name| InterjectionKind::from_name(name))
+        else {
+            msg.reply(
+                &ctx.http,
+                "Usage: `!interject <mst3k|memory|pondering|ai|fact|news>`",
+            )
+            .await?;
+            return Ok(());
+        };
+
+        info!(
+            "{} forced a {} interjection in {}",
+            msg.author.name,
+            kind.name(),
+            msg.channel_id
+        );
+        let roll_context = interjection::RollContext::forced(msg.channel_id, kind);
+        self.run_interjections(ctx, msg, &roll_context).await?;
         Ok(())
     }
-}
-impl Bot {
-    /// Record that a spontaneous interjection was sent (for recency dampening)
-    async fn mark_interjection_sent(&self) {
-        let mut last = self.last_interjection_time.write().await;
-        *last = Some(Instant::now());
-    }
 
-    // Function to check if the bot is being addressed
-    fn is_bot_addressed(&self, content: &str) -> bool {
-        let bot_name = &self.bot_name.to_lowercase();
-        let content_lower = content.to_lowercase();
-
-        // Direct mention at the start - the message must start with the bot's name
-        // followed by a space, punctuation, or end of string
-        if content_lower.starts_with(bot_name) {
-            // Check what comes after the bot name
-            let remainder = &content_lower[bot_name.len()..];
-            if remainder.is_empty()
-                || remainder.starts_with(' ')
-                || remainder.starts_with('?')
-                || remainder.starts_with('!')
-                || remainder.starts_with(',')
-                || remainder.starts_with(':')
+    // Handle the owner-only !speakers command (dump or clear the recent speakers list)
+    async fn handle_speakers_command(&self, ctx: &Context, msg: &Message, args: &[&str]) {
+        if !self.is_owner(ctx, msg.author.id).await {
+            info!("Ignoring !speakers from non-owner {}", msg.author.name);
+            if let Err(e) = msg
+                .reply(&ctx.http, "Only the bot's owners can use !speakers.")
+                .await
             {
-                info!("Bot addressed: name at beginning of message");
-                return true;
-            }
-        }
-
-        // Common address patterns - these are explicit ways to address the bot
-        let address_patterns = [
-            format!("hey {bot_name}"),
-            format!("hi {bot_name}"),
-            format!("hello {bot_name}"),
-            format!("ok {bot_name}"),
-            format!("hey, {bot_name}"),
-            format!("hi, {bot_name}"),
-            format!("hello, {bot_name}"),
-            format!("ok, {bot_name}"),
-            format!("{bot_name}, "), // When name is used with a comma
-            format!("@{bot_name}"),  // Informal mention
-            format!("excuse me, {bot_name}"),
-            format!("by the way, {bot_name}"),
-            format!("btw, {bot_name}"),
-        ];
-
-        for pattern in &address_patterns {
-            if content_lower.contains(pattern) {
-                info!("Bot addressed: matched pattern '{}'", pattern);
-                return true;
-            }
-        }
-
-        // We don't have direct access to the bot's ID here, so we'll rely on other methods
-        // to detect mentions. The actual mention detection happens in the message handler
-        // where we check if the bot is mentioned in the message.
-
-        // Use regex with word boundaries to avoid false positives
-        if self.address_patterns.name_boundary.is_match(&content_lower) {
-            // Check for negative patterns first
-            for re in &self.address_patterns.negative {
-                if re.is_match(&content_lower) {
-                    info!(
-                        "Bot NOT addressed: matched negative pattern '{}'",
-                        re.as_str()
-                    );
-                    return false;
-                }
-            }
-
-            // Check for positive patterns
-            for re in &self.address_patterns.positive {
-                if re.is_match(&content_lower) {
-                    info!("Bot addressed: matched positive pattern '{}'", re.as_str());
-                    return true;
-                }
+                error!("Error sending owner-only message: {:?}", e);
             }
+            return;
+        }
 
-            // If the bot name is at the beginning or end of the message, it's likely being addressed
-            if content_lower.trim().starts_with(bot_name)
-                || content_lower.trim().ends_with(bot_name)
+        let speakers_lock = {
+            let data = ctx.data.read().await;
+            data.get::<RecentSpeakersKey>().cloned()
+        };
+        let Some(speakers_lock) = speakers_lock else {
+            error!("RecentSpeakersKey not found in context data");
+            if let Err(e) = msg
+                .channel_id
+                .say(&ctx.http, "Recent speakers list isn't initialized")
+                .await
             {
-                info!("Bot addressed: name at beginning or end of trimmed message");
-                return true;
+                error!("Error sending speakers message: {:?}", e);
             }
+            return;
+        };
 
-            // Bot name found but not clearly addressed
-            info!("Bot name found as standalone word, but not clearly addressed");
-            return false;
-        }
+        let response = if args
+            .first()
+            .is_some_and(|a| a.eq_ignore_ascii_case("clear"))
+        {
+            let mut speakers = speakers_lock.write().await;
+            let cleared = speakers.len();
+            speakers.clear();
+            info!(
+                "{} cleared the recent speakers list ({} entries)",
+                msg.author.name, cleared
+            );
+            format!("Cleared {cleared} recent speakers.")
+        } else {
+            format_speakers_dump(&*speakers_lock.read().await)
+        };
 
-        false
+        // Display names may contain mentions; don't ping anyone from a debug dump
+        if let Err(e) = msg
+            .channel_id
+            .send_message(&ctx.http, utils::no_ping_message(response))
+            .await
+        {
+            error!("Error sending speakers list: {:?}", e);
+        }
     }
 
     // Helper function to check if the bot should respond in a quiet channel
@@ -1097,735 +2497,887 @@ impl Bot {
         false
     }
 
-    // Process a message
-    async fn process_message(&self, ctx: &Context, msg: &Message) -> Result<()> {
-        // Prevent duplicate processing of the same message
-        {
-            let mut processed = self.processed_messages.write().await;
-            if processed.contains(&msg.id) {
-                return Ok(());
+    // Answer a slash command by running the `!` command it stands for, so both go through
+    // the same checks and handlers
+    async fn handle_slash_command(&self, ctx: &Context, interaction: &CommandInteraction) {
+        let Some(text) =
+            slash_commands::text_command(&interaction.data.name, &interaction.data.options)
+        else {
+            warn!("Unknown slash command /{}", interaction.data.name);
+            return;
+        };
+
+        if !self.followed_channels.contains(&interaction.channel_id) {
+            let response = CreateInteractionResponse::Message(
+                CreateInteractionResponseMessage::new()
+                    .content("I'm not following this channel.")
+                    .ephemeral(true),
+            );
+            if let Err(e) = interaction.create_response(&ctx.http, response).await {
+                error!("Error responding to slash command: {:?}", e);
             }
-            processed.push_back(msg.id);
-            // Keep only last 1000 message IDs
-            while processed.len() > 1000 {
-                processed.pop_front();
+            return;
+        }
+
+        info!("Slash command from {}: {}", interaction.user.name, text);
+
+        // Answer with the equivalent `!` command; the command's own messages follow it
+        let response = CreateInteractionResponse::Message(
+            CreateInteractionResponseMessage::new()
+                .content(format!("`{text}`"))
+                .allowed_mentions(CreateAllowedMentions::new()),
+        );
+        if let Err(e) = interaction.create_response(&ctx.http, response).await {
+            error!("Error responding to slash command: {:?}", e);
+            return;
+        }
+
+        // Handlers expect a message, so stand the response in for one from the user
+        let mut msg = match interaction.get_response(&ctx.http).await {
+            Ok(msg) => msg,
+            Err(e) => {
+                error!("Error fetching slash command response: {:?}", e);
+                return;
             }
+        };
+        msg.author = interaction.user.clone();
+        msg.member = None;
+        msg.guild_id = interaction.guild_id;
+        msg.content = text;
+
+        if let Err(e) = self.run_command(ctx, &msg).await {
+            error!("Error running slash command: {:?}", e);
         }
+    }
 
-        // Check if we should respond in quiet channels
-        if !self.should_respond_in_quiet_channel(ctx, msg).await {
-            // In a quiet channel and not directly addressed - skip all processing except random interjections
+    /// Run a `!` command message (or a slash command standing in for one): check it's
+    /// allowed here, within the argument limits and off cooldown, then dispatch it
+    async fn run_command(&self, ctx: &Context, msg: &Message) -> Result<()> {
+        let Some((command, parts)) = parse_command(&msg.content) else {
             return Ok(());
-        }
+        };
 
-        // Note: Message is already stored in the database in the message() event handler
-        // No need to store it again here
+        if !self.command_allowed_here(ctx, msg, &command).await {
+            return Ok(());
+        }
 
-        // Update the in-memory message history
-        let data = ctx.data.read().await;
-        if let Some(message_history) = data.get::<MessageHistoryKey>() {
-            let mut history = message_history.write().await;
-            if history.len() >= self.message_history_limit {
-                history.pop_front();
+        // Turn away oversized arguments before they can reach a paid API
+        if let Err(limit) = self.argument_limits.check(&command, &parts[1..]) {
+            info!(
+                "Rejected !{} from {}: arguments over {} characters",
+                command, msg.author.name, limit
+            );
+            if let Err(e) = msg
+                .reply(
+                    &ctx.http,
+                    argument_limits::too_long_message(&command, limit),
+                )
+                .await
+            {
+                error!("Error sending argument length notice: {:?}", e);
             }
-            history.push_back(msg.clone());
+            return Ok(());
         }
 
-        // IMPORTANT: Process all explicit triggers first, before any random interjections
+        let cooldowns = ctx.data.read().await.get::<CommandCooldownsKey>().cloned();
+        if let Some(Err(wait)) = cooldowns.map(|cooldowns| cooldowns.check(msg.author.id, &command))
+        {
+            self.send_cooldown_notice(ctx, msg, &command, wait).await;
+            return Ok(());
+        }
 
-        // Check for commands (messages starting with !)
-        if msg.content.starts_with('!') {
-            let parts: Vec<&str> = msg.content[1..].split_whitespace().collect();
-            if !parts.is_empty() {
-                let command = parts[0].to_lowercase();
-
-                if command == "hello" {
-                    // Simple hello command
-                    if let Err(e) = msg.channel_id.say(&ctx.http, "world!").await {
-                        error!("Error sending hello response: {:?}", e);
+        if !self.dispatch_command(ctx, msg, &command, &parts).await? {
+            debug!("Nothing handled command !{}", command);
+        }
+        Ok(())
+    }
+
+    /// Run a `!` command. `parts` holds the message's words with the command first.
+    /// Returns whether anything handled the command.
+    async fn dispatch_command(
+        &self,
+        ctx: &Context,
+        msg: &Message,
+        command: &str,
+        parts: &[&str],
+    ) -> Result<bool> {
+        let route = route_command(
+            command,
+            !self.imagine_channels.is_empty(),
+            self.screenshot_sources
+                .iter()
+                .map(|source| source.command()),
+            &self.commands,
+        );
+        match route {
+            CommandRoute::Hello => {
+                // Simple hello command
+                if let Err(e) = msg.channel_id.say(&ctx.http, "world!").await {
+                    error!("Error sending hello response: {:?}", e);
+                }
+            }
+            CommandRoute::Trump => {
+                // Generate a Trump insult
+                let insult = self.trump_insult_generator.generate_insult();
+                if let Err(e) = msg.channel_id.say(&ctx.http, insult).await {
+                    error!("Error sending Trump insult: {:?}", e);
+                }
+            }
+            CommandRoute::BandName => {
+                // Generate a band genre
+                if parts.len() > 1 {
+                    let band_name = parts[1..].join(" ");
+                    let genre = self.band_genre_generator.generate_genre(&band_name);
+                    if let Err(e) = msg.channel_id.say(&ctx.http, genre).await {
+                        error!("Error sending band genre: {:?}", e);
+                    }
+                } else if let Err(e) = msg.reply(&ctx.http, "Please provide a band name.").await {
+                    error!("Error sending usage message: {:?}", e);
+                }
+            }
+            CommandRoute::Imagine => {
+                // Extract the image prompt
+                if self.image_backend.is_none() {
+                    if let Err(e) = msg
+                        .reply(
+                            &ctx.http,
+                            self.unavailable_messages
+                                .message(FeatureUnavailable::ImageGeneration),
+                        )
+                        .await
+                    {
+                        error!("Error sending unavailable message: {:?}", e);
                     }
-                } else if command == "trump" {
-                    // Generate a Trump insult
-                    let insult = self.trump_insult_generator.generate_insult();
-                    if let Err(e) = msg.channel_id.say(&ctx.http, insult).await {
-                        error!("Error sending Trump insult: {:?}", e);
+                } else if parts.len() > 1 {
+                    let prompt = parts[1..].join(" ");
+                    if let Err(e) = handle_imagine_command(
+                        ctx,
+                        msg,
+                        &prompt,
+                        self.image_backend.as_deref(),
+                        &self.image_rate_limiter,
+                        self.gemini_client.as_ref(),
+                        self.image_retry_policy,
+                    )
+                    .await
+                    {
+                        error!("Error handling imagine command: {:?}", e);
                     }
-                } else if command == "bandname" {
-                    // Generate a band genre
-                    if parts.len() > 1 {
-                        let band_name = parts[1..].join(" ");
-                        let genre = self.band_genre_generator.generate_genre(&band_name);
-                        if let Err(e) = msg.channel_id.say(&ctx.http, genre).await {
-                            error!("Error sending band genre: {:?}", e);
+                } else if let Err(e) = msg
+                    .reply(
+                        &ctx.http,
+                        "Please provide a description of what you want me to show you.",
+                    )
+                    .await
+                {
+                    error!("Error sending usage message: {:?}", e);
+                }
+            }
+            CommandRoute::AliveOrDead => {
+                // Check if a celebrity name was provided
+                if parts.len() > 1 {
+                    let celebrity_name = parts[1..].join(" ");
+                    if let Err(e) =
+                        handle_aliveordead_command(&ctx.http, msg, &self.wikipedia, &celebrity_name)
+                            .await
+                    {
+                        error!("Error handling alive command: {:?}", e);
+                        if let Err(e) = msg
+                            .channel_id
+                            .say(&ctx.http, "Error checking celebrity status")
+                            .await
+                        {
+                            error!("Error sending error message: {:?}", e);
                         }
-                    } else if let Err(e) = msg.reply(&ctx.http, "Please provide a band name.").await
+                    }
+                } else if let Err(e) = msg
+                    .reply(&ctx.http, "Please provide a celebrity name.")
+                    .await
+                {
+                    error!("Error sending usage message: {:?}", e);
+                }
+            }
+            CommandRoute::Wiki => {
+                let topic = parts[1..].join(" ");
+                if let Err(e) = handle_wiki_command(&ctx.http, msg, &self.wikipedia, &topic).await {
+                    error!("Error handling wiki command: {:?}", e);
+                }
+            }
+            CommandRoute::Translate => {
+                if let Some(gemini_client) = &self.gemini_client {
+                    if let Err(e) =
+                        handle_translate_command(&ctx.http, msg, gemini_client, &parts[1..]).await
                     {
-                        error!("Error sending usage message: {:?}", e);
+                        error!("Error handling translate command: {:?}", e);
                     }
-                } else if command == "imagine" && !self.imagine_channels.is_empty() {
-                    // Extract the image prompt
-                    if parts.len() > 1 {
-                        let prompt = parts[1..].join(" ");
-                        if let Err(e) = handle_imagine_command(
-                            ctx,
-                            msg,
-                            &prompt,
-                            &self.imagine_channels,
-                            self.pollinations_api_key.as_deref(),
-                            &self.image_rate_limiter,
-                            &self.http_client,
-                        )
+                } else if let Err(e) = msg
+                    .channel_id
+                    .say(
+                        &ctx.http,
+                        self.unavailable_messages.message(FeatureUnavailable::Ai),
+                    )
+                    .await
+                {
+                    error!("Error sending unavailable message: {:?}", e);
+                }
+            }
+            CommandRoute::Help => {
+                // Help command - use the help message from our commands HashMap
+                if let Some(help_text) = self.commands.get("help") {
+                    if let Err(e) = msg.channel_id.say(&ctx.http, help_text).await {
+                        error!("Error sending help message: {:?}", e);
+                    }
+                }
+            }
+            CommandRoute::Info => {
+                // Handle the info command ("!info fresh" forces a database recount)
+                let force_recount = parts.get(1).is_some_and(|arg| *arg == "fresh");
+                if let Err(e) = self.handle_info_command(ctx, msg, force_recount).await {
+                    error!("Error handling info command: {:?}", e);
+                }
+            }
+            CommandRoute::Uptime => {
+                if let Err(e) = self.handle_uptime_command(ctx, msg).await {
+                    error!("Error handling uptime command: {:?}", e);
+                }
+            }
+            CommandRoute::Stats => {
+                let uptime = Self::format_duration(self.start_time.elapsed());
+                if let Err(e) = msg
+                    .channel_id
+                    .say(&ctx.http, self.interjection_stats.format(&uptime))
+                    .await
+                {
+                    error!("Error sending interjection stats: {:?}", e);
+                }
+            }
+            CommandRoute::Slogan => {
+                // Extract search term if provided
+                let search_term = if parts.len() > 1 {
+                    Some(parts[1..].join(" "))
+                } else {
+                    None
+                };
+
+                // Generate a slogan response
+                if let Err(e) = self
+                    .handle_slogan_command(&ctx.http, msg, search_term)
+                    .await
+                {
+                    error!("Error handling slogan command: {:?}", e);
+                    if let Err(e) = msg
+                        .channel_id
+                        .say(&ctx.http, "Error accessing slogan database")
                         .await
+                    {
+                        error!("Error sending error message: {:?}", e);
+                    }
+                }
+            }
+            CommandRoute::Quote => {
+                // Extract all arguments after the command
+                let args: Vec<&str> = if parts.len() > 1 {
+                    parts[1..].to_vec()
+                } else {
+                    Vec::new()
+                };
+
+                // Check if this is a -dud request (quote a user)
+                if let Some((username, truly_random)) = quote_dud_args(&args) {
+                    let result = match db_utils::DateRange::from_args(&args) {
+                        Ok(dates) => {
+                            self.handle_quote_dud_command(
+                                &ctx.http,
+                                msg,
+                                username,
+                                truly_random,
+                                dates,
+                                quote_count(&args),
+                            )
+                            .await
+                        }
+                        // A date that doesn't parse; say what's wrong with it
+                        Err(problem) => msg
+                            .reply(&ctx.http, problem)
+                            .await
+                            .map(|_| ())
+                            .map_err(Into::into),
+                    };
+                    if let Err(e) = result {
+                        error!("Error handling quote -dud command: {:?}", e);
+                        if let Err(e) = msg
+                            .channel_id
+                            .say(&ctx.http, "Error retrieving user quotes")
+                            .await
                         {
-                            error!("Error handling imagine command: {:?}", e);
+                            error!("Error sending error message: {:?}", e);
                         }
-                    } else if let Err(e) = msg
-                        .reply(
-                            &ctx.http,
-                            "Please provide a description of what you want me to show you.",
-                        )
+                    }
+                } else {
+                    // Regular quote command with possible -show flag
+                    if let Err(e) = self.handle_quote_command(&ctx.http, msg, args).await {
+                        error!("Error handling quote command: {:?}", e);
+                        if let Err(e) = msg
+                            .channel_id
+                            .say(&ctx.http, "Error accessing quote database")
+                            .await
+                        {
+                            error!("Error sending error message: {:?}", e);
+                        }
+                    }
+                }
+            }
+            CommandRoute::FightCrime => {
+                match self.generate_crime_fighting_duo(ctx, msg).await {
+                    Ok(duo) => {
+                        if let Err(e) = msg.channel_id.say(&ctx.http, duo).await {
+                            error!("Error sending crime fighting duo: {:?}", e);
+                        }
+                    }
+                    Err(e) => {
+                        error!("Error handling fightcrime command: {:?}", e);
+                        if let Err(e) = msg
+                            .channel_id
+                            .say(&ctx.http, "Error generating crime fighting duo")
+                            .await
+                        {
+                            error!("Error sending error message: {:?}", e);
+                        }
+                    }
+                }
+            }
+            CommandRoute::Buzz => {
+                // Handle the buzz command
+                if let Err(e) = handle_buzz_command(&ctx.http, msg).await {
+                    error!("Error handling buzz command: {:?}", e);
+                    if let Err(e) = msg
+                        .channel_id
+                        .say(&ctx.http, "Error generating buzzword")
                         .await
                     {
-                        error!("Error sending usage message: {:?}", e);
+                        error!("Error sending error message: {:?}", e);
                     }
-                } else if command == "alive" || command == "dead" {
-                    // Check if a celebrity name was provided
-                    if parts.len() > 1 {
-                        let celebrity_name = parts[1..].join(" ");
-                        if let Err(e) =
-                            handle_aliveordead_command(&ctx.http, msg, &celebrity_name).await
-                        {
-                            error!("Error handling alive command: {:?}", e);
-                            if let Err(e) = msg
-                                .channel_id
-                                .say(&ctx.http, "Error checking celebrity status")
-                                .await
-                            {
-                                error!("Error sending error message: {:?}", e);
-                            }
+                }
+            }
+            CommandRoute::LastSeen => {
+                // Extract name or user ID to search for
+                let (name, user_id) = if parts.len() > 1 {
+                    let raw = parts[1..].join(" ");
+                    // Handle Discord mention format <@123456> or <@!123456>
+                    if raw.starts_with("<@") && raw.ends_with('>') {
+                        let id_str = raw
+                            .trim_start_matches("<@")
+                            .trim_start_matches('!')
+                            .trim_end_matches('>');
+                        if let Ok(uid) = id_str.parse::<u64>() {
+                            (String::new(), Some(uid.to_string()))
+                        } else {
+                            (raw, None)
                         }
-                    } else if let Err(e) = msg
-                        .reply(&ctx.http, "Please provide a celebrity name.")
+                    } else {
+                        (raw.trim_start_matches('@').to_string(), None)
+                    }
+                } else {
+                    (String::new(), None)
+                };
+
+                // Determine search term: use author_id if we have a mention, otherwise name
+                let search_name = if let Some(ref uid) = user_id {
+                    uid.clone()
+                } else {
+                    name.clone()
+                };
+
+                // Handle the lastseen command
+                if let Err(e) = handle_lastseen_command(
+                    &ctx.http,
+                    msg,
+                    &search_name,
+                    user_id.as_deref(),
+                    &self.message_store,
+                    &self.unavailable_messages,
+                )
+                .await
+                {
+                    error!("Error handling lastseen command: {:?}", e);
+                    if let Err(e) = msg
+                        .channel_id
+                        .say(&ctx.http, "Error searching message history")
                         .await
                     {
-                        error!("Error sending usage message: {:?}", e);
-                    }
-                } else if command == "help" {
-                    // Help command - use the help message from our commands HashMap
-                    if let Some(help_text) = self.commands.get("help") {
-                        if let Err(e) = msg.channel_id.say(&ctx.http, help_text).await {
-                            error!("Error sending help message: {:?}", e);
-                        }
-                    }
-                } else if command == "info" {
-                    // Handle the info command
-                    if let Err(e) = self.handle_info_command(ctx, msg).await {
-                        error!("Error handling info command: {:?}", e);
+                        error!("Error sending error message: {:?}", e);
                     }
-                } else if command == "slogan" {
-                    // Extract search term if provided
-                    let search_term = if parts.len() > 1 {
-                        Some(parts[1..].join(" "))
-                    } else {
-                        None
-                    };
-
-                    // Generate a slogan response
-                    if let Err(e) = self
-                        .handle_slogan_command(&ctx.http, msg, search_term)
+                }
+            }
+            CommandRoute::Forget => {
+                if let Some(db) = &self.message_store {
+                    let result = if command == "forgetme" {
+                        handle_forgetme_command(&ctx.http, msg, db.as_ref()).await
+                    } else if self.is_admin(ctx, msg.author.id).await {
+                        // Names never resolve to the bot or a gateway bot relaying other people
+                        let excluded_ids: Vec<String> = self
+                            .gateway_bot_ids
+                            .iter()
+                            .copied()
+                            .chain([self.get_bot_user_id(ctx).await.get()])
+                            .map(|id| id.to_string())
+                            .collect();
+                        handle_forget_command(
+                            &ctx.http,
+                            msg,
+                            &parts[1..],
+                            db.as_ref(),
+                            &excluded_ids,
+                        )
                         .await
-                    {
-                        error!("Error handling slogan command: {:?}", e);
+                    } else {
+                        info!("Ignoring !forget from non-admin {}", msg.author.name);
                         if let Err(e) = msg
-                            .channel_id
-                            .say(&ctx.http, "Error accessing slogan database")
+                            .reply(
+                                &ctx.http,
+                                "Only the bot's admins can use !forget. Use !forgetme to forget yourself.",
+                            )
                             .await
                         {
-                            error!("Error sending error message: {:?}", e);
+                            error!("Error sending admin-only message: {:?}", e);
                         }
-                    }
-                } else if command == "quote" {
-                    // Extract all arguments after the command
-                    let args: Vec<&str> = if parts.len() > 1 {
-                        parts[1..].to_vec()
-                    } else {
-                        Vec::new()
+                        Ok(Forgotten::default())
                     };
-
-                    // Check if this is a -dud request (quote a user)
-                    if args.contains(&"-dud") {
-                        let username_index = args.iter().position(|&r| r == "-dud").unwrap() + 1;
-                        let username = if username_index < args.len() {
-                            Some(args[username_index].to_string())
-                        } else {
-                            None
-                        };
-
-                        if let Err(e) = self
-                            .handle_quote_dud_command(&ctx.http, msg, username)
-                            .await
-                        {
-                            error!("Error handling quote -dud command: {:?}", e);
-                            if let Err(e) = msg
-                                .channel_id
-                                .say(&ctx.http, "Error retrieving user quotes")
-                                .await
-                            {
-                                error!("Error sending error message: {:?}", e);
-                            }
-                        }
-                    } else {
-                        // Regular quote command with possible -show flag
-                        if let Err(e) = self.handle_quote_command(&ctx.http, msg, args).await {
-                            error!("Error handling quote command: {:?}", e);
-                            if let Err(e) = msg
-                                .channel_id
-                                .say(&ctx.http, "Error accessing quote database")
-                                .await
-                            {
-                                error!("Error sending error message: {:?}", e);
-                            }
-                        }
-                    }
-                } else if command == "fightcrime" {
-                    match self.generate_crime_fighting_duo(ctx, msg).await {
-                        Ok(duo) => {
-                            if let Err(e) = msg.channel_id.say(&ctx.http, duo).await {
-                                error!("Error sending crime fighting duo: {:?}", e);
-                            }
+                    match result {
+                        Ok(forgotten) => {
+                            self.message_count_cache.record_trim(forgotten.deleted);
+                            self.forget_in_memory(ctx, &forgotten).await;
                         }
                         Err(e) => {
-                            error!("Error handling fightcrime command: {:?}", e);
+                            error!("Error handling {} command: {:?}", command, e);
                             if let Err(e) = msg
                                 .channel_id
-                                .say(&ctx.http, "Error generating crime fighting duo")
+                                .say(&ctx.http, "Error deleting stored messages")
                                 .await
                             {
                                 error!("Error sending error message: {:?}", e);
                             }
                         }
                     }
-                } else if command == "buzz" {
-                    // Handle the buzz command
-                    if let Err(e) = handle_buzz_command(&ctx.http, msg).await {
-                        error!("Error handling buzz command: {:?}", e);
-                        if let Err(e) = msg
-                            .channel_id
-                            .say(&ctx.http, "Error generating buzzword")
-                            .await
-                        {
-                            error!("Error sending error message: {:?}", e);
-                        }
-                    }
-                } else if command == "lastseen" || command == "seen" {
-                    // Extract name or user ID to search for
-                    let (name, user_id) = if parts.len() > 1 {
-                        let raw = parts[1..].join(" ");
-                        // Handle Discord mention format <@123456> or <@!123456>
-                        if raw.starts_with("<@") && raw.ends_with('>') {
-                            let id_str = raw
-                                .trim_start_matches("<@")
-                                .trim_start_matches('!')
-                                .trim_end_matches('>');
-                            if let Ok(uid) = id_str.parse::<u64>() {
-                                (String::new(), Some(uid.to_string()))
-                            } else {
-                                (raw, None)
-                            }
-                        } else {
-                            (raw.trim_start_matches('@').to_string(), None)
-                        }
-                    } else {
-                        (String::new(), None)
-                    };
-
-                    // Determine search term: use author_id if we have a mention, otherwise name
-                    let search_name = if let Some(ref uid) = user_id {
-                        uid.clone()
-                    } else {
-                        name.clone()
-                    };
-
-                    // Handle the lastseen command
-                    if let Err(e) = handle_lastseen_command(
+                } else if let Err(e) = msg
+                    .channel_id
+                    .say(
+                        &ctx.http,
+                        self.unavailable_messages
+                            .message(FeatureUnavailable::MessageHistory),
+                    )
+                    .await
+                {
+                    error!("Error sending unavailable message: {:?}", e);
+                }
+            }
+            CommandRoute::RemindMe => {
+                if let Some(db) = &self.message_db {
+                    if let Err(e) = handle_remindme_command(
                         &ctx.http,
                         msg,
-                        &search_name,
-                        user_id.as_deref(),
-                        &self.message_db,
+                        &parts[1..],
+                        db.clone(),
+                        self.reminder_utc_offset,
                     )
                     .await
                     {
-                        error!("Error handling lastseen command: {:?}", e);
+                        error!("Error handling remindme command: {:?}", e);
                         if let Err(e) = msg
                             .channel_id
-                            .say(&ctx.http, "Error searching message history")
+                            .say(&ctx.http, "Error setting the reminder")
                             .await
                         {
                             error!("Error sending error message: {:?}", e);
                         }
                     }
-                } else if command == "frinkiac" {
-                    // Extract search term if provided
-                    let args = if parts.len() > 1 {
-                        Some(parts[1..].join(" "))
-                    } else {
-                        None
-                    };
-
-                    // Handle the frinkiac command
-                    if let Err(e) = handle_frinkiac_command(
+                } else if let Err(e) = msg
+                    .channel_id
+                    .say(
+                        &ctx.http,
+                        self.unavailable_messages
+                            .message(FeatureUnavailable::MessageHistory),
+                    )
+                    .await
+                {
+                    error!("Error sending unavailable message: {:?}", e);
+                }
+            }
+            CommandRoute::Birthday => {
+                if let Some(db) = &self.message_db {
+                    if let Err(e) = handle_birthday_command(
                         &ctx.http,
                         msg,
-                        args,
-                        &self.frinkiac_client,
-                        self.gemini_client.as_ref(),
+                        &parts[1..],
+                        db.clone(),
+                        self.birthday_utc_offset,
                     )
                     .await
                     {
-                        error!("Error handling frinkiac command: {:?}", e);
+                        error!("Error handling birthday command: {:?}", e);
                         if let Err(e) = msg
                             .channel_id
-                            .say(&ctx.http, "Error searching Frinkiac")
+                            .say(&ctx.http, "Error updating birthdays")
                             .await
                         {
                             error!("Error sending error message: {:?}", e);
                         }
                     }
-                } else if command == "morbotron" {
-                    // Extract search term if provided
-                    let search_term = if parts.len() > 1 {
-                        Some(parts[1..].join(" "))
-                    } else {
-                        None
-                    };
-
-                    // Handle the morbotron command
-                    if let Err(e) = handle_morbotron_command(
+                } else if let Err(e) = msg
+                    .channel_id
+                    .say(
                         &ctx.http,
-                        msg,
-                        search_term,
-                        &self.morbotron_client,
-                        self.gemini_client.as_ref(),
+                        self.unavailable_messages
+                            .message(FeatureUnavailable::MessageHistory),
                     )
                     .await
+                {
+                    error!("Error sending unavailable message: {:?}", e);
+                }
+            }
+            CommandRoute::Pronouns => {
+                if let Some(db) = &self.message_db {
+                    if let Err(e) =
+                        handle_pronouns_command(&ctx.http, msg, &parts[1..], db.clone()).await
                     {
-                        error!("Error handling morbotron command: {:?}", e);
+                        error!("Error handling pronouns command: {:?}", e);
                         if let Err(e) = msg
                             .channel_id
-                            .say(&ctx.http, "Error searching Morbotron")
+                            .say(&ctx.http, "Error updating pronouns")
                             .await
                         {
                             error!("Error sending error message: {:?}", e);
                         }
                     }
-                } else if command == "masterofallscience" {
-                    // Extract search term if provided
-                    let search_term = if parts.len() > 1 {
-                        Some(parts[1..].join(" "))
-                    } else {
-                        None
-                    };
-
-                    // Handle the masterofallscience command
-                    if let Err(e) = handle_masterofallscience_command(
+                } else if let Err(e) = msg
+                    .channel_id
+                    .say(
                         &ctx.http,
-                        msg,
-                        search_term,
-                        &self.masterofallscience_client,
-                        self.gemini_client.as_ref(),
+                        self.unavailable_messages
+                            .message(FeatureUnavailable::MessageHistory),
                     )
                     .await
+                {
+                    error!("Error sending unavailable message: {:?}", e);
+                }
+            }
+            CommandRoute::Speakers => {
+                self.handle_speakers_command(ctx, msg, &parts[1..]).await;
+            }
+            CommandRoute::InterjectStats => {
+                if !self.is_owner(ctx, msg.author.id).await {
+                    info!("Ignoring !interject-stats from non-owner {}", msg.author.name);
+                    if let Err(e) = msg
+                        .reply(&ctx.http, "Only the bot's owners can use !interject-stats.")
+                        .await
                     {
-                        error!("Error handling masterofallscience command: {:?}", e);
-                        if let Err(e) = msg
-                            .channel_id
-                            .say(&ctx.http, "Error searching Master of All Science")
-                            .await
-                        {
-                            error!("Error sending error message: {:?}", e);
-                        }
+                        error!("Error sending owner-only message: {:?}", e);
                     }
-                } else if let Some(response) = self.commands.get(&command) {
-                    if let Err(e) = msg.channel_id.say(&ctx.http, response).await {
-                        error!("Error sending command response: {:?}", e);
-                    }
-                } else if let Some(gemini_client) = &self.gemini_client {
-                    // Handle unknown command with Gemini API
-                    if let Err(e) =
-                        handle_unknown_command(&ctx.http, msg, &command, gemini_client, ctx).await
+                } else if let (true, Some(db)) = (self.interjection_metrics, &self.message_db) {
+                    if let Err(e) = handle_interject_stats_command(
+                        &ctx.http,
+                        msg,
+                        &parts[1..],
+                        db.clone(),
+                    )
+                    .await
                     {
-                        error!("Error handling unknown command: {:?}", e);
+                        error!("Error handling interject-stats command: {:?}", e);
                     }
+                } else if let Err(e) = msg
+                    .reply(
+                        &ctx.http,
+                        "Interjection metrics are off. Set INTERJECTION_METRICS = \"true\" to start logging them.",
+                    )
+                    .await
+                {
+                    error!("Error sending interject-stats message: {:?}", e);
                 }
             }
-            return Ok(());
-        }
-
-        // Check for DuckDuckGo search (messages starting with "search" or "google")
-        if self.duckduckgo_search_enabled
-            && msg.content.to_lowercase().starts_with("search ")
-            && msg.content.len() > 7
-        {
-            let query = &msg.content[7..];
-
-            if let Some(search_client) = &self.search_client {
-                if let Err(e) = msg
+            CommandRoute::Search => {
+                if let Some(store) = &self.message_store {
+                    let args = parts[1..].join(" ");
+                    if let Err(e) =
+                        handle_search_command(&ctx.http, msg, &args, store.as_ref()).await
+                    {
+                        error!("Error handling search command: {:?}", e);
+                    }
+                } else if let Err(e) = msg
                     .channel_id
-                    .say(&ctx.http, format!("Searching for: {query}"))
+                    .say(
+                        &ctx.http,
+                        self.unavailable_messages
+                            .message(FeatureUnavailable::MessageHistory),
+                    )
                     .await
                 {
-                    error!("Error sending search confirmation: {:?}", e);
+                    error!("Error sending unavailable message: {:?}", e);
                 }
-
-                // Perform the search
-                match search_client.search(query).await {
-                    Ok(Some(result)) => {
-                        // Clean up the title by removing extra whitespace
-                        let title = result.title.trim().replace("\n", " ").replace("  ", " ");
-
-                        // Format and send the result
-                        let response = format!("**{}**\n{}\n{}", title, result.url, result.snippet);
-                        if let Err(e) = msg.channel_id.say(&ctx.http, response).await {
-                            error!("Error sending search result: {:?}", e);
+            }
+            CommandRoute::Calc => {
+                let expression = parts[1..].join(" ");
+                if let Err(e) = handle_calc_command(&ctx.http, msg, &expression).await {
+                    error!("Error handling calc command: {:?}", e);
+                }
+            }
+            CommandRoute::Roll => {
+                let notation = parts[1..].join(" ");
+                if let Err(e) = handle_roll_command(&ctx.http, msg, &notation).await {
+                    error!("Error handling roll command: {:?}", e);
+                }
+            }
+            CommandRoute::Choose => {
+                let options = parts[1..].join(" ");
+                if let Err(e) = handle_choose_command(&ctx.http, msg, &options).await {
+                    error!("Error handling choose command: {:?}", e);
+                }
+            }
+            CommandRoute::EightBall => {
+                let question = parts[1..].join(" ");
+                let gemini_client = self
+                    .gemini_client
+                    .as_ref()
+                    .filter(|_| self.eightball_use_ai);
+                if let Err(e) =
+                    handle_eightball_command(&ctx.http, msg, &question, gemini_client).await
+                {
+                    error!("Error handling 8ball command: {:?}", e);
+                }
+            }
+            CommandRoute::Urban => {
+                let term = parts[1..].join(" ");
+                if let Err(e) =
+                    handle_urban_command(&ctx.http, msg, &term, self.urban_blocked_terms.as_deref())
+                        .await
+                {
+                    error!("Error handling urban command: {:?}", e);
+                }
+            }
+            CommandRoute::VibeCheck => {
+                match (&self.gemini_client, &self.message_store) {
+                    (Some(gemini_client), Some(db)) => {
+                        if let Err(e) = handle_vibecheck_command(
+                            &ctx.http,
+                            msg,
+                            gemini_client,
+                            db.as_ref(),
+                            &self.vibe_check_cooldowns,
+                        )
+                        .await
+                        {
+                            error!("Error handling vibecheck command: {:?}", e);
                         }
                     }
-                    Ok(None) => {
+                    (None, _) => {
                         if let Err(e) = msg
                             .channel_id
-                            .say(&ctx.http, "No search results found.")
+                            .say(
+                                &ctx.http,
+                                self.unavailable_messages.message(FeatureUnavailable::Ai),
+                            )
                             .await
                         {
-                            error!("Error sending no results message: {:?}", e);
+                            error!("Error sending unavailable message: {:?}", e);
                         }
                     }
-                    Err(e) => {
-                        error!("Error performing search: {:?}", e);
+                    (_, None) => {
                         if let Err(e) = msg
                             .channel_id
-                            .say(&ctx.http, "Error performing search.")
+                            .say(
+                                &ctx.http,
+                                self.unavailable_messages
+                                    .message(FeatureUnavailable::MessageHistory),
+                            )
                             .await
                         {
-                            error!("Error sending error message: {:?}", e);
-                        }
-                    }
-                }
-            } else if let Err(e) = msg
-                .channel_id
-                .say(&ctx.http, "Search is not configured.")
-                .await
-            {
-                error!("Error sending search error: {:?}", e);
-            }
-            return Ok(());
-        }
-
-        // Check if the bot is being addressed using our new function
-        if self.is_bot_addressed(&msg.content) {
-            // Use the full message content including the bot's name
-            let content = msg.content.trim().to_string();
-            let content_lower = content.to_lowercase();
-
-            // Check if the message contains "who fights crime" when the bot is addressed
-            if content_lower.contains("who fights crime") {
-                info!("Bot addressed with 'who fights crime' question");
-                match self.generate_crime_fighting_duo(ctx, msg).await {
-                    Ok(duo) => {
-                        if let Err(e) = msg.channel_id.say(&ctx.http, duo).await {
-                            error!("Error sending crime fighting duo: {:?}", e);
+                            error!("Error sending unavailable message: {:?}", e);
                         }
-                        return Ok(());
-                    }
-                    Err(e) => {
-                        error!("Error generating crime fighting duo: {:?}", e);
-                        // Continue with normal response if crime fighting duo generation fails
                     }
                 }
             }
-
-            if !content.is_empty() {
-                if let Some(gemini_client) = &self.gemini_client {
-                    // Get and clean the display name
-                    let display_name = get_best_display_name(ctx, msg).await;
-                    let clean_display_name = clean_display_name(&display_name);
-
-                    // Extract pronouns from the same display name
-                    let user_pronouns = crate::display_name::extract_pronouns(&display_name);
-
-                    // Start typing indicator before making API call
-                    if let Err(e) = msg.channel_id.broadcast_typing(&ctx.http).await {
-                        error!("Failed to send typing indicator: {:?}", e);
-                    }
-
-                    // Get recent messages for context
-                    let context_messages = if let Some(db) = &self.message_db {
-                        // Get the last self.gemini_context_messages messages from the database
-                        match db_utils::get_recent_messages_with_reply_context(
-                            db.clone(),
-                            self.gemini_context_messages,
-                            Some(msg.channel_id.to_string().as_str()),
+            CommandRoute::Summarize => {
+                match (&self.gemini_client, &self.message_store) {
+                    (Some(gemini_client), Some(db)) => {
+                        if let Err(e) = handle_summarize_command(
+                            &ctx.http,
+                            msg,
+                            gemini_client,
+                            db.as_ref(),
+                            &parts[1..],
                         )
                         .await
                         {
-                            Ok(messages) => messages,
-                            Err(e) => {
-                                error!("Error retrieving recent messages: {:?}", e);
-                                Vec::new()
-                            }
+                            error!("Error handling summarize command: {:?}", e);
                         }
-                    } else {
-                        Vec::new()
-                    };
-
-                    // Convert to the format expected by generate_response_with_context_and_pronouns
-                    let context_for_api: Vec<(String, String, Option<String>, String)> =
-                        context_messages
-                            .iter()
-                            .map(
-                                |(author, display_name, pronouns, content, _reply_context)| {
-                                    (
-                                        author.clone(),
-                                        display_name.clone(),
-                                        pronouns.clone(),
-                                        content.clone(),
-                                    )
-                                },
-                            )
-                            .collect();
-
-                    // Extract media (images/video) from the message and any replied-to message
-                    let media_items = media_utils::extract_media_from_message(msg).await;
-                    let youtube_urls = media_utils::extract_youtube_urls(&content);
-                    let has_media = !media_items.is_empty() || !youtube_urls.is_empty();
-
-                    // Append GIF instruction if Giphy is configured
-                    let gif_suffix = if self.giphy_client.is_some() {
-                        giphy::GIF_INSTRUCTION
-                    } else {
-                        ""
-                    };
-
-                    // Use multimodal path if media is present, otherwise standard text path
-                    let response_result = if has_media {
-                        info!(
-                            "Using multimodal path: {} media items, {} YouTube URLs",
-                            media_items.len(),
-                            youtube_urls.len()
-                        );
-                        let prompt = format!(
-                            "{}{}",
-                            gemini_client.prompt_templates().format_general_response(
-                                &content,
-                                &clean_display_name,
-                                "",
-                            ),
-                            gif_suffix
-                        );
-                        gemini_client
-                            .generate_content_with_media(&prompt, &media_items, &youtube_urls)
-                            .await
-                            .map(Some)
-                    } else {
-                        let content_with_gif = format!("{}{}", content, gif_suffix);
-                        gemini_client
-                            .generate_best_response_with_context_and_pronouns(
-                                &content_with_gif,
-                                &clean_display_name,
-                                &context_for_api,
-                                user_pronouns.as_deref(),
-                                true, // Always respond when directly addressed by name
+                    }
+                    (None, _) => {
+                        if let Err(e) = msg
+                            .channel_id
+                            .say(
+                                &ctx.http,
+                                self.unavailable_messages.message(FeatureUnavailable::Ai),
                             )
                             .await
-                    };
-
-                    match response_result {
-                        Ok(Some(response)) => {
-                            // Check if the response is a GIF request
-                            if let Some(giphy_client) = &self.giphy_client {
-                                if let Some(gif_url) = giphy_client.try_resolve_gif(&response).await
-                                {
-                                    let message_reference = MessageReference::from(msg);
-                                    let create_message = CreateMessage::new()
-                                        .content(gif_url)
-                                        .reference_message(message_reference);
-                                    if let Err(e) =
-                                        msg.channel_id.send_message(&ctx.http, create_message).await
-                                    {
-                                        error!("Error sending GIF reply: {:?}", e);
-                                    }
-                                    return Ok(());
-                                }
-                                // Also check for embedded GIF (text + GIF: at the end)
-                                if let Some((text, gif_url)) =
-                                    giphy_client.try_resolve_embedded_gif(&response).await
-                                {
-                                    if !text.is_empty() {
-                                        apply_realistic_delay(&text, ctx, msg.channel_id).await;
-                                        let message_reference = MessageReference::from(msg);
-                                        let create_message = CreateMessage::new()
-                                            .content(&text)
-                                            .reference_message(message_reference);
-                                        if let Err(e) = msg
-                                            .channel_id
-                                            .send_message(&ctx.http, create_message)
-                                            .await
-                                        {
-                                            error!("Error sending text before GIF: {:?}", e);
-                                        }
-                                    }
-                                    if let Err(e) = msg.channel_id.say(&ctx.http, &gif_url).await {
-                                        error!("Error sending GIF reply: {:?}", e);
-                                    }
-                                    return Ok(());
-                                }
-                            }
-
-                            // Apply realistic typing delay based on response length
-                            apply_realistic_delay(&response, ctx, msg.channel_id).await;
-
-                            // Create a message reference for replying
-                            let message_reference = MessageReference::from(msg);
-                            let create_message = CreateMessage::new()
-                                .content(response.clone())
-                                .reference_message(message_reference);
-
-                            if let Err(e) =
-                                msg.channel_id.send_message(&ctx.http, create_message).await
-                            {
-                                error!("Error sending Gemini response as reply: {:?}", e);
-                                // Fallback to regular message if reply fails
-                                if let Err(e) = msg.channel_id.say(&ctx.http, response).await {
-                                    error!("Error sending fallback Gemini response: {:?}", e);
-                                }
-                            }
+                        {
+                            error!("Error sending unavailable message: {:?}", e);
                         }
-                        Ok(None) => {
-                            // No response generated (all were "pass")
-                            return Ok(());
+                    }
+                    (_, None) => {
+                        if let Err(e) = msg
+                            .channel_id
+                            .say(
+                                &ctx.http,
+                                self.unavailable_messages
+                                    .message(FeatureUnavailable::MessageHistory),
+                            )
+                            .await
+                        {
+                            error!("Error sending unavailable message: {:?}", e);
                         }
-                        Err(e) => {
-                            error!("Error calling Gemini API: {:?}", e);
-
-                            let error_string = e.to_string();
-
-                            // Don't show silent errors to users
-                            if error_string.contains("SILENT_ERROR") {
-                                return Ok(());
-                            }
-
-                            // Show a friendly message without raw error details
-                            let user_message = if error_string.contains("BILLING_ERROR") {
-                                "The Gemini API quota or billing limit has been reached. I'll be back once the limit resets!".to_string()
-                            } else {
-                                "I'm having trouble thinking right now. Try again in a bit!"
-                                    .to_string()
-                            };
+                    }
+                }
+            }
+            CommandRoute::Subtitles => {
+                let args = if parts.len() > 1 {
+                    Some(parts[1..].join(" "))
+                } else {
+                    None
+                };
 
-                            // Create a message reference for replying
-                            let message_reference = MessageReference::from(msg);
-                            let create_message = CreateMessage::new()
-                                .content(&user_message)
-                                .reference_message(message_reference);
+                if let Err(e) = handle_subtitles_command(
+                    &ctx.http,
+                    msg,
+                    args,
+                    &self.frinkiac_client,
+                    self.subtitles_page_size,
+                    self.subtitles_max_pages,
+                )
+                .await
+                {
+                    error!("Error handling subtitles command: {:?}", e);
+                }
+            }
+            CommandRoute::Screenshot => {
+                let service = if command == "frinkiac" {
+                    ScreenshotService::Frinkiac
+                } else {
+                    ScreenshotService::Morbotron
+                };
+                // Extract search term if provided
+                let args = if parts.len() > 1 {
+                    Some(parts[1..].join(" "))
+                } else {
+                    None
+                };
 
-                            if let Err(e) =
-                                msg.channel_id.send_message(&ctx.http, create_message).await
-                            {
-                                error!("Error sending error message as reply: {:?}", e);
-                                // Fallback to regular message if reply fails
-                                if let Err(e) = msg.channel_id.say(&ctx.http, &user_message).await {
-                                    error!("Error sending fallback error message: {:?}", e);
-                                }
-                            }
-                        }
+                if args.as_deref() == Some("-next") {
+                    if let Err(e) = self.handle_again_command(ctx, msg, Some(service)).await {
+                        error!("Error handling {} -next: {:?}", command, e);
                     }
                 } else {
-                    // No Gemini API configured, use a simple response
+                    self.run_screenshot_search(ctx, msg, service, args).await;
+                }
+            }
+            CommandRoute::Again => {
+                if let Err(e) = self.handle_again_command(ctx, msg, None).await {
+                    error!("Error handling again command: {:?}", e);
+                }
+            }
+            CommandRoute::Interject => {
+                if let Err(e) = self.handle_interject_command(ctx, msg, &parts[1..]).await {
+                    error!("Error handling interject command: {:?}", e);
+                }
+            }
+            CommandRoute::Rephrase => {
+                if let Err(e) = self.handle_rephrase_command(ctx, msg, &parts[1..]).await {
+                    error!("Error handling rephrase command: {:?}", e);
+                }
+            }
+            CommandRoute::MasterOfAllScience => {
+                // Extract search term if provided
+                let search_term = if parts.len() > 1 {
+                    Some(parts[1..].join(" "))
+                } else {
+                    None
+                };
+
+                // Handle the masterofallscience command
+                if let Err(e) = self
+                    .run_screenshot_command(ctx, msg, search_term, &self.masterofallscience_client)
+                    .await
+                {
+                    error!("Error handling masterofallscience command: {:?}", e);
+                    let name = self.masterofallscience_client.name();
                     if let Err(e) = msg
-                        .reply(
-                            &ctx.http,
-                            "Sorry, I'm not configured to respond to messages yet.",
-                        )
+                        .channel_id
+                        .say(&ctx.http, format!("Error searching {name}"))
                         .await
                     {
-                        error!("Error sending simple response: {:?}", e);
+                        error!("Error sending error message: {:?}", e);
                     }
                 }
-                return Ok(());
             }
-        }
-
-        // Now process random interjections only if no explicit triggers were matched
-
-        // Update recent speakers list
-        {
-            let data = ctx.data.read().await;
-            if let Some(recent_speakers) = data.get::<RecentSpeakersKey>() {
-                let mut speakers = recent_speakers.write().await;
-                let username = msg.author.name.clone();
-                let display_name = get_best_display_name(ctx, msg).await;
-                let display_name = display_name::clean_display_name(&display_name);
+            CommandRoute::ScreenshotSource(index) => {
+                let source = &self.screenshot_sources[index];
+                let search_term = if parts.len() > 1 {
+                    Some(parts[1..].join(" "))
+                } else {
+                    None
+                };
 
-                if let Some(pos) = speakers.iter().position(|(name, _)| name == &username) {
-                    speakers.remove(pos);
+                if let Err(e) = self
+                    .run_screenshot_command(ctx, msg, search_term, source)
+                    .await
+                {
+                    error!("Error handling {} command: {:?}", command, e);
+                    if let Err(e) = msg
+                        .channel_id
+                        .say(&ctx.http, format!("Error searching {}", source.name()))
+                        .await
+                    {
+                        error!("Error sending error message: {:?}", e);
+                    }
                 }
-
-                if speakers.len() >= 5 {
-                    speakers.pop_front();
+            }
+            CommandRoute::Custom(response) => {
+                if let Err(e) = msg.channel_id.say(&ctx.http, response).await {
+                    error!("Error sending command response: {:?}", e);
                 }
-                speakers.push_back((username, display_name));
-
-                if tracing::level_enabled!(tracing::Level::DEBUG) {
-                    let speakers_list: Vec<String> = speakers
-                        .iter()
-                        .map(|(_, display)| display.clone())
-                        .collect();
-                    debug!("Current speakers list: {:?}", speakers_list);
+            }
+            CommandRoute::Unknown => {
+                // Handle unknown command with Gemini API, if there is one
+                let Some(gemini_client) = &self.gemini_client else {
+                    return Ok(false);
+                };
+                if let Err(e) =
+                    handle_unknown_command(&ctx.http, msg, command, gemini_client, ctx).await
+                {
+                    error!("Error handling unknown command: {:?}", e);
                 }
             }
         }
 
-        // Get the current user (bot) ID
-        let current_user_id = self.get_bot_user_id(ctx).await;
-
-        // Don't trigger interjections on bot commands - they're already handled
-        if msg.content.starts_with('!') {
-            return Ok(());
-        }
-
-        // Get the probability multiplier based on channel inactivity
-        let silence_multiplier = self
-            .fill_silence_manager
-            .get_probability_multiplier(msg.channel_id, current_user_id)
-            .await;
-
-        // Recency dampener: reduce interjection probability based on how recently
-        // the last one fired. Ramps from 0% at t=0 to 100% at t=10 minutes.
-        let recency_multiplier = {
-            let last = self.last_interjection_time.read().await;
-            match *last {
-                Some(t) => {
-                    let elapsed_secs = t.elapsed().as_secs_f64();
-                    (elapsed_secs / 600.0).min(1.0) // linear ramp over 10 minutes
-                }
-                None => 1.0, // no previous interjection
-            }
-        };
+        Ok(true)
+    }
 
+    /// Run each interjection type's roll and, for the ones that hit, its interjection in the
+    /// message's channel. Returns true when an interjection path has dealt with the message
+    /// and nothing else should respond to it.
+    async fn run_interjections(
+        &self,
+        ctx: &Context,
+        msg: &Message,
+        roll_context: &interjection::RollContext,
+    ) -> Result<bool> {
         // MST3K Quote interjection
-        let adjusted_mst3k_probability =
-            self.interjection_mst3k_probability * silence_multiplier * recency_multiplier;
-        if rand::rng().random_bool(adjusted_mst3k_probability) {
-            let probability_percent = self.interjection_mst3k_probability * 100.0;
-            let adjusted_percent = adjusted_mst3k_probability * 100.0;
-            let odds = if self.interjection_mst3k_probability > 0.0 {
-                format!("1 in {:.0}", 1.0 / self.interjection_mst3k_probability)
-            } else {
-                "disabled".to_string()
-            };
-
-            info!("Triggered MST3K quote interjection (base: {:.2}% chance, adjusted: {:.2}%, silence multiplier: {:.2}x, {})",
-                  probability_percent, adjusted_percent, silence_multiplier, odds);
-
+        if self
+            .roll_interjection(
+                InterjectionKind::Mst3k,
+                self.interjection_mst3k_probability,
+                roll_context,
+            )
+            .await
+        {
             // Try to get a quote from the database
             if self.db_manager.is_configured() {
                 if let Some(pool) = &self.db_manager.pool {
@@ -1833,105 +3385,62 @@ impl Bot {
                     match mst3k_quotes::process_mst3k_quote(pool).await {
                         Some(quote) => {
                             // Send the quote
-                            if let Err(e) = msg.channel_id.say(&ctx.http, &quote).await {
-                                error!("Error sending MST3K quote: {:?}", e);
+                            match send_without_pings(&ctx.http, msg.channel_id, &quote).await {
+                                Ok(sent) => {
+                                    info!("MST3K quote interjection sent: {}", quote);
+                                    self.mark_interjection_sent(
+                                        InterjectionKind::Mst3k,
+                                        msg.channel_id,
+                                        Some(sent.id),
+                                    )
+                                    .await;
+                                }
                                 // Silently fail - no fallback
-                            } else {
-                                info!("MST3K quote interjection sent: {}", quote);
-                                self.mark_interjection_sent().await;
+                                Err(e) => {
+                                    error!("Error sending MST3K quote: {:?}", e);
+                                    self.mark_interjection_suppressed(
+                                        InterjectionKind::Mst3k,
+                                        msg.channel_id,
+                                        "send failed",
+                                    )
+                                    .await;
+                                }
                             }
                         }
                         None => {
-                            // Silently fail - no fallback
-                            error!("Failed to process MST3K quote");
+                            error!("Failed to process MST3K quote, using a fallback quote");
+                            self.send_fallback_mst3k_quote(ctx, msg.channel_id).await;
                         }
                     }
                 } else {
-                    // Silently fail - no fallback
-                    error!("Database pool is None for MST3K quotes");
+                    error!("Database pool is None for MST3K quotes, using a fallback quote");
+                    self.send_fallback_mst3k_quote(ctx, msg.channel_id).await;
                 }
             } else {
-                // Silently fail - no fallback
-                error!("Database not configured for MST3K quotes");
+                info!("Database not configured for MST3K quotes, using a fallback quote");
+                self.send_fallback_mst3k_quote(ctx, msg.channel_id).await;
             }
         }
         // Memory interjection
-        let adjusted_memory_probability =
-            self.interjection_memory_probability * silence_multiplier * recency_multiplier;
-        if rand::rng().random_bool(adjusted_memory_probability) {
-            let probability_percent = self.interjection_memory_probability * 100.0;
-            let adjusted_percent = adjusted_memory_probability * 100.0;
-            let odds = if self.interjection_memory_probability > 0.0 {
-                format!("1 in {:.0}", 1.0 / self.interjection_memory_probability)
-            } else {
-                "disabled".to_string()
-            };
-
-            info!("Triggered memory interjection (base: {:.2}% chance, adjusted: {:.2}%, silence multiplier: {:.2}x, {})",
-                  probability_percent, adjusted_percent, silence_multiplier, odds);
-
-            if let (Some(db), Some(gemini_client)) = (&self.message_db, &self.gemini_client) {
-                let db_clone = Arc::clone(db);
-
-                // Query for a random message, weighted toward more recent ones
-                // Uses sqrt(RANDOM()) * timestamp to bias toward newer messages
-                let bot_name_for_query = self.bot_name.clone();
-                let result = db_clone
-                    .lock()
-                    .await
-                    .call(move |conn| {
-                        let query =
-                            "SELECT content, author, display_name, timestamp FROM messages \
-                        WHERE length(content) >= 20 AND length(content) <= 300 \
-                        AND content NOT LIKE '!%' \
-                        AND content NOT LIKE 'http://%' \
-                        AND content NOT LIKE 'https://%' \
-                        AND content NOT LIKE '%[Image:%' \
-                        AND content NOT LIKE '%[Video:%' \
-                        AND author != ?1 AND display_name != ?1 \
-                        ORDER BY (ABS(RANDOM()) / 9223372036854775807.0) * timestamp DESC \
-                        LIMIT 1";
-                        let mut stmt = conn.prepare(query)?;
-
-                        let rows = stmt.query_map([&bot_name_for_query], |row| {
-                            Ok((
-                                row.get::<_, String>(0)?,
-                                row.get::<_, String>(1)?,
-                                row.get::<_, String>(2)?,
-                                row.get::<_, i64>(3)?,
-                            ))
-                        })?;
-
-                        let mut result = Vec::new();
-                        for row in rows {
-                            result.push(row?);
-                        }
-
-                        Ok::<_, rusqlite::Error>(result)
-                    })
-                    .await;
+        if self
+            .roll_interjection(
+                InterjectionKind::Memory,
+                self.interjection_memory_probability,
+                roll_context,
+            )
+            .await
+        {
+            if let (Some(store), Some(gemini_client)) = (&self.message_store, &self.gemini_client) {
+                // A random stored message, weighted toward more recent ones, from this server
+                // unless configured otherwise
+                let guild_id = msg
+                    .guild_id
+                    .filter(|_| !self.all_guilds)
+                    .map(|id| id.to_string());
+                let result = store.random_memory(self.bot_name.clone(), guild_id).await;
 
                 // Get recent context from the channel (10 messages for better context)
-                let context_messages = if let Some(db2) = &self.message_db {
-                    match db_utils::get_recent_messages_with_reply_context(
-                        db2.clone(),
-                        10,
-                        Some(msg.channel_id.to_string().as_str()),
-                    )
-                    .await
-                    {
-                        Ok(messages) => messages,
-                        Err(e) => {
-                            error!(
-                                "Error retrieving recent messages for memory context: {:?}",
-                                e
-                            );
-                            Vec::new()
-                        }
-                    }
-                } else {
-                    Vec::new()
-                };
+                let context_messages = self.recent_context(ctx, msg.channel_id, 10).await;
 
                 let context_text = if !context_messages.is_empty() {
                     let mut chronological = context_messages.clone();
@@ -1948,9 +3457,8 @@ impl Bot {
                 };
 
                 match result {
-                    Ok(messages) => {
-                        if let Some((content, _author, display_name, timestamp)) = messages.first()
-                        {
+                    Ok(memory) => {
+                        if let Some((content, _author, display_name, timestamp)) = memory.as_ref() {
                             // Format the timestamp with relative time for recent, absolute for older
                             let date_str = {
                                 let dt = chrono::DateTime::from_timestamp(*timestamp, 0)
@@ -1999,13 +3507,22 @@ impl Bot {
                                 date_str, display_name, content
                             );
 
-                            match gemini_client.generate_content(&memory_prompt).await {
+                            match gemini_client
+                                .generate_content_for(&memory_prompt, GeminiTask::Interjection)
+                                .await
+                            {
                                 Ok(response) => {
                                     let response = response.trim();
 
                                     if response.to_lowercase() == "pass" {
                                         info!("Memory interjection evaluation: decided to PASS");
-                                        return Ok(());
+                                        self.mark_interjection_suppressed(
+                                            InterjectionKind::Memory,
+                                            msg.channel_id,
+                                            "declined",
+                                        )
+                                        .await;
+                                        return Ok(true);
                                     }
 
                                     // Check for prompt leak
@@ -2017,7 +3534,13 @@ impl Bot {
                                         error!(
                                             "Memory interjection error: API returned prompt text"
                                         );
-                                        return Ok(());
+                                        self.mark_interjection_suppressed(
+                                            InterjectionKind::Memory,
+                                            msg.channel_id,
+                                            "prompt leak",
+                                        )
+                                        .await;
+                                        return Ok(true);
                                     }
 
                                     // Check if the response is a GIF request
@@ -2025,15 +3548,19 @@ impl Bot {
                                         if let Some(gif_url) =
                                             giphy_client.try_resolve_gif(response).await
                                         {
-                                            if let Err(e) =
-                                                msg.channel_id.say(&ctx.http, &gif_url).await
+                                            if let Err(e) = send_without_pings(
+                                                &ctx.http,
+                                                msg.channel_id,
+                                                &gif_url,
+                                            )
+                                            .await
                                             {
                                                 error!(
                                                     "Error sending GIF memory interjection: {:?}",
                                                     e
                                                 );
                                             }
-                                            return Ok(());
+                                            return Ok(true);
                                         }
                                         if let Some((text, gif_url)) =
                                             giphy_client.try_resolve_embedded_gif(response).await
@@ -2041,8 +3568,12 @@ impl Bot {
                                             if !text.is_empty() {
                                                 apply_realistic_delay(&text, ctx, msg.channel_id)
                                                     .await;
-                                                if let Err(e) =
-                                                    msg.channel_id.say(&ctx.http, &text).await
+                                                if let Err(e) = send_without_pings(
+                                                    &ctx.http,
+                                                    msg.channel_id,
+                                                    &text,
+                                                )
+                                                .await
                                                 {
                                                     error!(
                                                         "Error sending text before GIF: {:?}",
@@ -2050,18 +3581,40 @@ impl Bot {
                                                     );
                                                 }
                                             }
-                                            if let Err(e) =
-                                                msg.channel_id.say(&ctx.http, &gif_url).await
+                                            if let Err(e) = send_without_pings(
+                                                &ctx.http,
+                                                msg.channel_id,
+                                                &gif_url,
+                                            )
+                                            .await
                                             {
                                                 error!(
                                                     "Error sending GIF memory interjection: {:?}",
                                                     e
                                                 );
                                             }
-                                            return Ok(());
+                                            return Ok(true);
                                         }
                                     }
 
+                                    if self.bot_has_last_word(msg.channel_id).await
+                                        || interjection_echoes_channel(
+                                            self.message_store.as_ref(),
+                                            msg.channel_id,
+                                            response,
+                                            self.interjection_similarity_threshold,
+                                        )
+                                        .await
+                                    {
+                                        self.mark_interjection_suppressed(
+                                            InterjectionKind::Memory,
+                                            msg.channel_id,
+                                            "echo or bot spoke last",
+                                        )
+                                        .await;
+                                        return Ok(true);
+                                    }
+
                                     if let Err(e) = msg.channel_id.broadcast_typing(&ctx.http).await
                                     {
                                         error!("Failed to send typing indicator for memory interjection: {:?}", e);
@@ -2070,64 +3623,81 @@ impl Bot {
                                     // Apply realistic typing delay
                                     apply_realistic_delay(response, ctx, msg.channel_id).await;
 
-                                    if let Err(e) = msg.channel_id.say(&ctx.http, response).await {
-                                        error!("Error sending memory interjection: {:?}", e);
-                                    } else {
-                                        info!("Memory interjection sent: {}", response);
-                                        self.mark_interjection_sent().await;
+                                    match send_without_pings(&ctx.http, msg.channel_id, response)
+                                        .await
+                                    {
+                                        Ok(sent) => {
+                                            info!("Memory interjection sent: {}", response);
+                                            self.mark_interjection_sent(
+                                                InterjectionKind::Memory,
+                                                msg.channel_id,
+                                                Some(sent.id),
+                                            )
+                                            .await;
+                                        }
+                                        Err(e) => {
+                                            error!("Error sending memory interjection: {:?}", e);
+                                            self.mark_interjection_suppressed(
+                                                InterjectionKind::Memory,
+                                                msg.channel_id,
+                                                "send failed",
+                                            )
+                                            .await;
+                                        }
                                     }
                                 }
                                 Err(e) => {
                                     error!("Error processing memory with Gemini API: {:?}", e);
+                                    self.mark_interjection_suppressed(
+                                        InterjectionKind::Memory,
+                                        msg.channel_id,
+                                        "api error",
+                                    )
+                                    .await;
                                 }
                             }
+                        } else {
+                            self.mark_interjection_suppressed(
+                                InterjectionKind::Memory,
+                                msg.channel_id,
+                                "no memories",
+                            )
+                            .await;
                         }
                     }
                     Err(e) => {
                         error!("Error querying database for random message: {:?}", e);
+                        self.mark_interjection_suppressed(
+                            InterjectionKind::Memory,
+                            msg.channel_id,
+                            "database error",
+                        )
+                        .await;
                     }
                 }
+            } else {
+                self.mark_interjection_suppressed(
+                    InterjectionKind::Memory,
+                    msg.channel_id,
+                    "not configured",
+                )
+                .await;
             }
         }
 
         // Pondering interjection
-        let adjusted_pondering_probability =
-            self.interjection_pondering_probability * silence_multiplier * recency_multiplier;
-        if rand::rng().random_bool(adjusted_pondering_probability) {
-            let probability_percent = self.interjection_pondering_probability * 100.0;
-            let adjusted_percent = adjusted_pondering_probability * 100.0;
-            let odds = if self.interjection_pondering_probability > 0.0 {
-                format!("1 in {:.0}", 1.0 / self.interjection_pondering_probability)
-            } else {
-                "disabled".to_string()
-            };
-
-            info!("Triggered pondering interjection (base: {:.2}% chance, adjusted: {:.2}%, silence multiplier: {:.2}x, {})",
-                  probability_percent, adjusted_percent, silence_multiplier, odds);
-
+        if self
+            .roll_interjection(
+                InterjectionKind::Pondering,
+                self.interjection_pondering_probability,
+                roll_context,
+            )
+            .await
+        {
             // Use Gemini API for pondering if available
             if let Some(gemini_client) = &self.gemini_client {
                 // Get recent messages for context
-                let recent_messages = if let Some(db) = &self.message_db {
-                    match db_utils::get_recent_messages_with_reply_context(
-                        db.clone(),
-                        5, // Get last 5 messages for context
-                        Some(&msg.channel_id.to_string()),
-                    )
-                    .await
-                    {
-                        Ok(messages) => messages,
-                        Err(e) => {
-                            error!(
-                                "Error retrieving recent messages for pondering interjection: {:?}",
-                                e
-                            );
-                            Vec::new()
-                        }
-                    }
-                } else {
-                    Vec::new()
-                };
+                let recent_messages = self.recent_context(ctx, msg.channel_id, 5).await;
 
                 // Format messages for context
                 let context = if !recent_messages.is_empty() {
@@ -2180,13 +3750,22 @@ Example bad responses:
 Keep it extremely brief and natural, as if you're just briefly pondering the conversation."#,
                     self.bot_name, personality, context
                 );
+                let topic = self.channel_topic(ctx, msg.channel_id).await;
+                let pondering_prompt =
+                    prompt_templates::with_channel_topic(&pondering_prompt, topic.as_deref());
+                let pondering_prompt = gemini_client
+                    .prompt_templates()
+                    .with_persona_examples(&pondering_prompt);
 
                 // Call multi-response generator if available, otherwise fall back to single response
                 let response_result = if let Some(multi_gen) = &self.multi_response_generator {
                     multi_gen.generate_best_response(&pondering_prompt).await
                 } else {
                     // Fallback to single response
-                    match gemini_client.generate_content(&pondering_prompt).await {
+                    match gemini_client
+                        .generate_content_for(&pondering_prompt, GeminiTask::Interjection)
+                        .await
+                    {
                         Ok(response) => {
                             let response = response.trim();
                             if response.to_lowercase().starts_with("pass") {
@@ -2209,30 +3788,52 @@ Keep it extremely brief and natural, as if you're just briefly pondering the con
                             error!("Pondering interjection error: API returned the prompt instead of a response");
                             // Log the issue but don't send any message to the channel
                             error!("Suppressing fallback pondering message as configured");
-                            return Ok(());
+                            self.mark_interjection_suppressed(
+                                InterjectionKind::Pondering,
+                                msg.channel_id,
+                                "prompt leak",
+                            )
+                            .await;
+                            return Ok(true);
+                        }
+
+                        if self.bot_has_last_word(msg.channel_id).await {
+                            self.mark_interjection_suppressed(
+                                InterjectionKind::Pondering,
+                                msg.channel_id,
+                                "bot spoke last",
+                            )
+                            .await;
+                            return Ok(true);
                         }
 
                         // Check if the response is a GIF request
                         if let Some(giphy_client) = &self.giphy_client {
                             if let Some(gif_url) = giphy_client.try_resolve_gif(&response).await {
-                                if let Err(e) = msg.channel_id.say(&ctx.http, &gif_url).await {
+                                if let Err(e) =
+                                    send_without_pings(&ctx.http, msg.channel_id, &gif_url).await
+                                {
                                     error!("Error sending GIF pondering interjection: {:?}", e);
                                 }
-                                return Ok(());
+                                return Ok(true);
                             }
                             if let Some((text, gif_url)) =
                                 giphy_client.try_resolve_embedded_gif(&response).await
                             {
                                 if !text.is_empty() {
                                     apply_realistic_delay(&text, ctx, msg.channel_id).await;
-                                    if let Err(e) = msg.channel_id.say(&ctx.http, &text).await {
+                                    if let Err(e) =
+                                        send_without_pings(&ctx.http, msg.channel_id, &text).await
+                                    {
                                         error!("Error sending text before GIF: {:?}", e);
                                     }
                                 }
-                                if let Err(e) = msg.channel_id.say(&ctx.http, &gif_url).await {
+                                if let Err(e) =
+                                    send_without_pings(&ctx.http, msg.channel_id, &gif_url).await
+                                {
                                     error!("Error sending GIF pondering interjection: {:?}", e);
                                 }
-                                return Ok(());
+                                return Ok(true);
                             }
                         }
 
@@ -2251,21 +3852,54 @@ Keep it extremely brief and natural, as if you're just briefly pondering the con
 
                         // Send the response
                         let response_text = response.clone(); // Clone for logging
-                        if let Err(e) = msg.channel_id.say(&ctx.http, response).await {
-                            error!("Error sending pondering interjection: {:?}", e);
-                        } else {
-                            info!("Pondering interjection sent: {}", response_text);
-                        }
-                        self.mark_interjection_sent().await;
+                        let sent =
+                            match send_without_pings(&ctx.http, msg.channel_id, response).await {
+                                Ok(sent) => {
+                                    info!("Pondering interjection sent: {}", response_text);
+                                    Some(sent.id)
+                                }
+                                Err(e) => {
+                                    error!("Error sending pondering interjection: {:?}", e);
+                                    None
+                                }
+                            };
+                        self.mark_interjection_sent(
+                            InterjectionKind::Pondering,
+                            msg.channel_id,
+                            sent,
+                        )
+                        .await;
                     }
                     Ok(None) => {
                         info!(
                             "Pondering interjection evaluation: decided to PASS - no response sent"
                         );
-                        return Ok(());
+                        self.mark_interjection_suppressed(
+                            InterjectionKind::Pondering,
+                            msg.channel_id,
+                            "declined",
+                        )
+                        .await;
+                        return Ok(true);
+                    }
+                    Err(e) if rate_limiter::is_rate_limited(&e) => {
+                        // Interjections are optional; just skip this one
+                        info!("Skipping pondering interjection: {}", e);
+                        self.mark_interjection_suppressed(
+                            InterjectionKind::Pondering,
+                            msg.channel_id,
+                            "rate limited",
+                        )
+                        .await;
                     }
                     Err(e) => {
                         error!("Pondering interjection error: {:?}", e);
+                        self.mark_interjection_suppressed(
+                            InterjectionKind::Pondering,
+                            msg.channel_id,
+                            "api error",
+                        )
+                        .await;
                         // Log the issue but don't send any message to the channel
                         error!("Suppressing fallback pondering message as configured");
                     }
@@ -2273,25 +3907,25 @@ Keep it extremely brief and natural, as if you're just briefly pondering the con
             } else {
                 // If Gemini API is not configured, just log and don't send anything
                 error!("Pondering interjection not available (Gemini API not configured) - no response sent");
-                return Ok(());
+                self.mark_interjection_suppressed(
+                    InterjectionKind::Pondering,
+                    msg.channel_id,
+                    "not configured",
+                )
+                .await;
+                return Ok(true);
             }
         }
 
         // AI interjection
-        let adjusted_ai_probability =
-            self.interjection_ai_probability * silence_multiplier * recency_multiplier;
-        if rand::rng().random_bool(adjusted_ai_probability) {
-            let probability_percent = self.interjection_ai_probability * 100.0;
-            let adjusted_percent = adjusted_ai_probability * 100.0;
-            let odds = if self.interjection_ai_probability > 0.0 {
-                format!("1 in {:.0}", 1.0 / self.interjection_ai_probability)
-            } else {
-                "disabled".to_string()
-            };
-
-            info!("Triggered AI interjection (base: {:.2}% chance, adjusted: {:.2}%, silence multiplier: {:.2}x, {})",
-                  probability_percent, adjusted_percent, silence_multiplier, odds);
-
+        if self
+            .roll_interjection(
+                InterjectionKind::Ai,
+                self.interjection_ai_probability,
+                roll_context,
+            )
+            .await
+        {
             if let Some(gemini_client) = &self.gemini_client {
                 if let Some(interjection_prompt) = &self.gemini_interjection_prompt {
                     info!("Processing AI interjection");
@@ -2299,29 +3933,14 @@ Keep it extremely brief and natural, as if you're just briefly pondering the con
                     // We'll start typing indicator only after we decide to send a message
 
                     // Get recent messages for context - use more messages for better context
-                    let context_messages = if let Some(db) = &self.message_db {
-                        match db_utils::get_recent_messages_with_reply_context(
-                            db.clone(),
-                            self.gemini_context_messages,
-                            Some(msg.channel_id.to_string().as_str()),
-                        )
-                        .await
-                        {
-                            Ok(messages) => messages,
-                            Err(e) => {
-                                error!(
-                                    "Error retrieving recent messages for AI interjection: {:?}",
-                                    e
-                                );
-                                Vec::new()
-                            }
-                        }
-                    } else {
-                        Vec::new()
-                    };
+                    let context_messages = self
+                        .recent_context(ctx, msg.channel_id, self.gemini_context_messages)
+                        .await;
 
                     // Replace bot_name but leave {context} for the API layer to handle
                     let prompt = interjection_prompt.replace("{bot_name}", &self.bot_name);
+                    let topic = self.channel_topic(ctx, msg.channel_id).await;
+                    let prompt = prompt_templates::with_channel_topic(&prompt, topic.as_deref());
 
                     // Convert to the format expected by generate_response_with_context_and_pronouns
                     let context_for_api: Vec<(String, String, Option<String>, String)> =
@@ -2353,6 +3972,7 @@ Keep it extremely brief and natural, as if you're just briefly pondering the con
                                 &context_for_api,
                                 None,
                                 false, // Let it decide whether to respond for interjections
+                                GeminiTask::Interjection,
                             )
                             .await
                     };
@@ -2368,7 +3988,31 @@ Keep it extremely brief and natural, as if you're just briefly pondering the con
                                 || response.contains("If none of these criteria are met")
                             {
                                 error!("AI interjection error: API returned the prompt instead of a response");
-                                return Ok(());
+                                self.mark_interjection_suppressed(
+                                    InterjectionKind::Ai,
+                                    msg.channel_id,
+                                    "prompt leak",
+                                )
+                                .await;
+                                return Ok(true);
+                            }
+
+                            if self.bot_has_last_word(msg.channel_id).await
+                                || interjection_echoes_channel(
+                                    self.message_store.as_ref(),
+                                    msg.channel_id,
+                                    &response,
+                                    self.interjection_similarity_threshold,
+                                )
+                                .await
+                            {
+                                self.mark_interjection_suppressed(
+                                    InterjectionKind::Ai,
+                                    msg.channel_id,
+                                    "echo or bot spoke last",
+                                )
+                                .await;
+                                return Ok(true);
                             }
 
                             // Start typing indicator now that we've decided to send a message
@@ -2383,13 +4027,23 @@ Keep it extremely brief and natural, as if you're just briefly pondering the con
                             if let Some(giphy_client) = &self.giphy_client {
                                 if let Some(gif_url) = giphy_client.try_resolve_gif(&response).await
                                 {
-                                    if let Err(e) = msg.channel_id.say(&ctx.http, &gif_url).await {
-                                        error!("Error sending GIF interjection: {:?}", e);
-                                    } else {
-                                        info!("GIF interjection sent: {}", gif_url);
-                                        self.mark_interjection_sent().await;
+                                    match send_without_pings(&ctx.http, msg.channel_id, &gif_url)
+                                        .await
+                                    {
+                                        Ok(sent) => {
+                                            info!("GIF interjection sent: {}", gif_url);
+                                            self.mark_interjection_sent(
+                                                InterjectionKind::Ai,
+                                                msg.channel_id,
+                                                Some(sent.id),
+                                            )
+                                            .await;
+                                        }
+                                        Err(e) => {
+                                            error!("Error sending GIF interjection: {:?}", e)
+                                        }
                                     }
-                                    return Ok(());
+                                    return Ok(true);
                                 }
                                 // Also check for embedded GIF (text + GIF: at the end)
                                 if let Some((text, gif_url)) =
@@ -2397,126 +4051,431 @@ Keep it extremely brief and natural, as if you're just briefly pondering the con
                                 {
                                     if !text.is_empty() {
                                         apply_realistic_delay(&text, ctx, msg.channel_id).await;
-                                        if let Err(e) = msg.channel_id.say(&ctx.http, &text).await {
+                                        if let Err(e) =
+                                            send_without_pings(&ctx.http, msg.channel_id, &text)
+                                                .await
+                                        {
                                             error!("Error sending text before GIF: {:?}", e);
                                         }
                                     }
-                                    if let Err(e) = msg.channel_id.say(&ctx.http, &gif_url).await {
-                                        error!("Error sending GIF interjection: {:?}", e);
-                                    } else {
-                                        info!("Embedded GIF interjection sent: {}", gif_url);
-                                    }
-                                    self.mark_interjection_sent().await;
-                                    return Ok(());
+                                    let sent = match send_without_pings(
+                                        &ctx.http,
+                                        msg.channel_id,
+                                        &gif_url,
+                                    )
+                                    .await
+                                    {
+                                        Ok(sent) => {
+                                            info!("Embedded GIF interjection sent: {}", gif_url);
+                                            Some(sent.id)
+                                        }
+                                        Err(e) => {
+                                            error!("Error sending GIF interjection: {:?}", e);
+                                            None
+                                        }
+                                    };
+                                    self.mark_interjection_sent(
+                                        InterjectionKind::Ai,
+                                        msg.channel_id,
+                                        sent,
+                                    )
+                                    .await;
+                                    return Ok(true);
                                 }
                             }
 
-                            // Apply realistic typing delay
-                            apply_realistic_delay(&response, ctx, msg.channel_id).await;
+                            // Apply realistic typing delay
+                            apply_realistic_delay(&response, ctx, msg.channel_id).await;
+
+                            // Send the response
+                            let response_text = response.clone(); // Clone for logging
+                            let sent = match send_without_pings(&ctx.http, msg.channel_id, response)
+                                .await
+                            {
+                                Ok(sent) => {
+                                    info!("AI interjection sent: {}", response_text);
+                                    Some(sent.id)
+                                }
+                                Err(e) => {
+                                    error!("Error sending AI interjection: {:?}", e);
+                                    None
+                                }
+                            };
+                            self.mark_interjection_sent(InterjectionKind::Ai, msg.channel_id, sent)
+                                .await;
+                        }
+                        Ok(None) => {
+                            info!("AI interjection evaluation: decided to PASS - no response sent");
+                            self.mark_interjection_suppressed(
+                                InterjectionKind::Ai,
+                                msg.channel_id,
+                                "declined",
+                            )
+                            .await;
+                            return Ok(true);
+                        }
+                        Err(e) if rate_limiter::is_rate_limited(&e) => {
+                            info!("Skipping AI interjection: {}", e);
+                            self.mark_interjection_suppressed(
+                                InterjectionKind::Ai,
+                                msg.channel_id,
+                                "rate limited",
+                            )
+                            .await;
+                        }
+                        Err(e) => {
+                            error!("AI interjection evaluation: ERROR - {:?}", e);
+                            self.mark_interjection_suppressed(
+                                InterjectionKind::Ai,
+                                msg.channel_id,
+                                "api error",
+                            )
+                            .await;
+                        }
+                    }
+                } else {
+                    // If Gemini API is configured but interjection prompt is missing
+                    info!("AI Interjection not available (GEMINI_INTERJECTION_PROMPT not configured) - no response sent");
+                    self.mark_interjection_suppressed(
+                        InterjectionKind::Ai,
+                        msg.channel_id,
+                        "not configured",
+                    )
+                    .await;
+                }
+            } else {
+                // If Gemini API is not configured
+                info!(
+                    "AI Interjection not available (Gemini API not configured) - no response sent"
+                );
+                self.mark_interjection_suppressed(
+                    InterjectionKind::Ai,
+                    msg.channel_id,
+                    "not configured",
+                )
+                .await;
+            }
+        }
+
+        // Fact interjection
+        if self
+            .roll_interjection(
+                InterjectionKind::Fact,
+                self.interjection_fact_probability,
+                roll_context,
+            )
+            .await
+        {
+            if let Some(gemini_client) = &self.gemini_client {
+                // We'll use our dedicated fact interjection module
+                match fact_interjection::handle_fact_interjection(
+                    ctx,
+                    msg,
+                    gemini_client,
+                    &self.multi_response_generator,
+                    &self.message_store,
+                    &self.bot_name,
+                    self.gemini_context_messages,
+                )
+                .await
+                {
+                    Ok(Some(sent)) => {
+                        self.mark_interjection_sent(
+                            InterjectionKind::Fact,
+                            msg.channel_id,
+                            Some(sent),
+                        )
+                        .await
+                    }
+                    Ok(None) => {
+                        self.mark_interjection_suppressed(
+                            InterjectionKind::Fact,
+                            msg.channel_id,
+                            "declined",
+                        )
+                        .await
+                    }
+                    Err(e) => {
+                        error!("Error handling fact interjection: {:?}", e);
+                        self.mark_interjection_suppressed(
+                            InterjectionKind::Fact,
+                            msg.channel_id,
+                            "api error",
+                        )
+                        .await;
+                    }
+                }
+            } else {
+                // If Gemini API is not configured
+                info!("Fact Interjection not available (Gemini API not configured) - no response sent");
+                self.mark_interjection_suppressed(
+                    InterjectionKind::Fact,
+                    msg.channel_id,
+                    "not configured",
+                )
+                .await;
+            }
+        }
+
+        // News interjection
+        if self
+            .roll_interjection(
+                InterjectionKind::News,
+                self.interjection_news_probability,
+                roll_context,
+            )
+            .await
+        {
+            if let Some(gemini_client) = &self.gemini_client {
+                // Call the news interjection handler
+                match handle_news_interjection(
+                    ctx,
+                    msg,
+                    gemini_client,
+                    &self.message_store,
+                    &self.bot_name,
+                    self.gemini_context_messages,
+                    &self.headline_cache,
+                )
+                .await
+                {
+                    Ok(Some(sent)) => {
+                        self.mark_interjection_sent(
+                            InterjectionKind::News,
+                            msg.channel_id,
+                            Some(sent),
+                        )
+                        .await
+                    }
+                    Ok(None) => {
+                        self.mark_interjection_suppressed(
+                            InterjectionKind::News,
+                            msg.channel_id,
+                            "declined",
+                        )
+                        .await
+                    }
+                    Err(e) => {
+                        error!("Error in news interjection: {:?}", e);
+                        self.mark_interjection_suppressed(
+                            InterjectionKind::News,
+                            msg.channel_id,
+                            "api error",
+                        )
+                        .await;
+                    }
+                }
+            } else {
+                // If Gemini API is not configured
+                info!("News Interjection not available (Gemini API not configured) - no response sent");
+                self.mark_interjection_suppressed(
+                    InterjectionKind::News,
+                    msg.channel_id,
+                    "not configured",
+                )
+                .await;
+            }
+        }
+
+        Ok(false)
+    }
+
+    async fn process_message(&self, ctx: &Context, msg: &Message) -> Result<()> {
+        // Prevent duplicate processing of the same message
+        {
+            let mut processed = self.processed_messages.write().await;
+            if processed.contains(&msg.id) {
+                return Ok(());
+            }
+            processed.push_back(msg.id);
+            // Keep only last 1000 message IDs
+            while processed.len() > 1000 {
+                processed.pop_front();
+            }
+        }
+
+        // Count the message toward this channel's interjection warm-up
+        self.interjection_warmup.observe(msg.channel_id);
+
+        // Check if we should respond in quiet channels
+        if !self.should_respond_in_quiet_channel(ctx, msg).await {
+            // In a quiet channel and not directly addressed - skip all processing except random interjections
+            return Ok(());
+        }
+
+        // Note: Message is already stored in the database and the in-memory history in the
+        // message() event handler. No need to store it again here
+
+        // IMPORTANT: Process all explicit triggers first, before any random interjections
+
+        // Check for commands (messages starting with !)
+        if msg.content.starts_with('!') {
+            return self.run_command(ctx, msg).await;
+        }
+
+        // Check for DuckDuckGo search (messages starting with "search" or "google")
+        if self.duckduckgo_search_enabled
+            && msg.content.to_lowercase().starts_with("search ")
+            && msg.content.len() > 7
+        {
+            let query = &msg.content[7..];
+
+            if let Some(search_client) = &self.search_client {
+                if let Err(e) = msg
+                    .channel_id
+                    .say(&ctx.http, format!("Searching for: {query}"))
+                    .await
+                {
+                    error!("Error sending search confirmation: {:?}", e);
+                }
+
+                // Perform the search
+                match search_client.search(query).await {
+                    Ok(Some(result)) => {
+                        // Clean up the title by removing extra whitespace
+                        let title = result.title.trim().replace("\n", " ").replace("  ", " ");
+
+                        // Format and send the result
+                        let response = format!("**{}**\n{}\n{}", title, result.url, result.snippet);
+                        if let Err(e) = msg.channel_id.say(&ctx.http, response).await {
+                            error!("Error sending search result: {:?}", e);
+                        }
+                    }
+                    Ok(None) => {
+                        if let Err(e) = msg
+                            .channel_id
+                            .say(&ctx.http, "No search results found.")
+                            .await
+                        {
+                            error!("Error sending no results message: {:?}", e);
+                        }
+                    }
+                    Err(e) => {
+                        error!("Error performing search: {:?}", e);
+                        if let Err(e) = msg
+                            .channel_id
+                            .say(&ctx.http, "Error performing search.")
+                            .await
+                        {
+                            error!("Error sending error message: {:?}", e);
+                        }
+                    }
+                }
+            } else if let Err(e) = msg
+                .channel_id
+                .say(
+                    &ctx.http,
+                    self.unavailable_messages
+                        .message(FeatureUnavailable::Search),
+                )
+                .await
+            {
+                error!("Error sending search error: {:?}", e);
+            }
+            return Ok(());
+        }
+
+        // Check if the bot is being addressed using our new function
+        if self.is_bot_addressed(&msg.content) {
+            // Use the full message content including the bot's name
+            let content = msg.content.trim().to_string();
+            let content_lower = content.to_lowercase();
 
-                            // Send the response
-                            let response_text = response.clone(); // Clone for logging
-                            if let Err(e) = msg.channel_id.say(&ctx.http, response).await {
-                                error!("Error sending AI interjection: {:?}", e);
-                            } else {
-                                info!("AI interjection sent: {}", response_text);
-                            }
-                            self.mark_interjection_sent().await;
-                        }
-                        Ok(None) => {
-                            info!("AI interjection evaluation: decided to PASS - no response sent");
-                            return Ok(());
-                        }
-                        Err(e) => {
-                            error!("AI interjection evaluation: ERROR - {:?}", e);
+            // Check if the message contains "who fights crime" when the bot is addressed
+            if content_lower.contains("who fights crime") {
+                info!("Bot addressed with 'who fights crime' question");
+                match self.generate_crime_fighting_duo(ctx, msg).await {
+                    Ok(duo) => {
+                        if let Err(e) = msg.channel_id.say(&ctx.http, duo).await {
+                            error!("Error sending crime fighting duo: {:?}", e);
                         }
+                        return Ok(());
+                    }
+                    Err(e) => {
+                        error!("Error generating crime fighting duo: {:?}", e);
+                        // Continue with normal response if crime fighting duo generation fails
                     }
-                } else {
-                    // If Gemini API is configured but interjection prompt is missing
-                    info!("AI Interjection not available (GEMINI_INTERJECTION_PROMPT not configured) - no response sent");
                 }
-            } else {
-                // If Gemini API is not configured
-                info!(
-                    "AI Interjection not available (Gemini API not configured) - no response sent"
-                );
+            }
+
+            if !content.is_empty() {
+                self.reply_with_ai(ctx, msg, content).await?;
+                return Ok(());
             }
         }
 
-        // Fact interjection
-        let adjusted_fact_probability =
-            self.interjection_fact_probability * silence_multiplier * recency_multiplier;
-        if rand::rng().random_bool(adjusted_fact_probability) {
-            let probability_percent = self.interjection_fact_probability * 100.0;
-            let adjusted_percent = adjusted_fact_probability * 100.0;
-            let odds = if self.interjection_fact_probability > 0.0 {
-                format!("1 in {:.0}", 1.0 / self.interjection_fact_probability)
-            } else {
-                "disabled".to_string()
-            };
+        // Now process random interjections only if no explicit triggers were matched
 
-            info!("Triggered fact interjection (base: {:.2}% chance, adjusted: {:.2}%, silence multiplier: {:.2}x, {})",
-                  probability_percent, adjusted_percent, silence_multiplier, odds);
+        // Update recent speakers list
+        {
+            let data = ctx.data.read().await;
+            if let Some(recent_speakers) = data.get::<RecentSpeakersKey>() {
+                let mut speakers = recent_speakers.write().await;
+                let username = msg.author.name.clone();
+                let display_name = get_best_display_name(ctx, msg).await;
+                let display_name = display_name::clean_display_name(&display_name);
 
-            if let Some(gemini_client) = &self.gemini_client {
-                // We'll use our dedicated fact interjection module
-                match fact_interjection::handle_fact_interjection(
-                    ctx,
-                    msg,
-                    gemini_client,
-                    &self.multi_response_generator,
-                    &self.message_db,
-                    &self.bot_name,
-                    self.gemini_context_messages,
-                )
-                .await
-                {
-                    Ok(true) => self.mark_interjection_sent().await,
-                    Err(e) => error!("Error handling fact interjection: {:?}", e),
-                    _ => {}
+                record_speaker(&mut speakers, username, display_name);
+
+                if tracing::level_enabled!(tracing::Level::DEBUG) {
+                    let speakers_list: Vec<String> = speakers
+                        .iter()
+                        .map(|(_, display)| display.clone())
+                        .collect();
+                    debug!("Current speakers list: {:?}", speakers_list);
                 }
-            } else {
-                // If Gemini API is not configured
-                info!("Fact Interjection not available (Gemini API not configured) - no response sent");
             }
         }
 
-        // News interjection
-        let adjusted_news_probability =
-            self.interjection_news_probability * silence_multiplier * recency_multiplier;
-        if rand::rng().random_bool(adjusted_news_probability) {
-            let probability_percent = self.interjection_news_probability * 100.0;
-            let adjusted_percent = adjusted_news_probability * 100.0;
-            let odds = if self.interjection_news_probability > 0.0 {
-                format!("1 in {:.0}", 1.0 / self.interjection_news_probability)
-            } else {
-                "disabled".to_string()
-            };
+        // Get the current user (bot) ID
+        let current_user_id = self.get_bot_user_id(ctx).await;
 
-            info!("Triggered news interjection (base: {:.2}% chance, adjusted: {:.2}%, silence multiplier: {:.2}x, {})",
-                  probability_percent, adjusted_percent, silence_multiplier, odds);
+        // Keyword reactions are low priority: after commands, before text interjections
+        if !self.keyword_reactions.is_empty() {
+            self.keyword_reactions.react(&ctx.http, msg).await;
+        }
 
-            if let Some(gemini_client) = &self.gemini_client {
-                // Call the news interjection handler
-                match handle_news_interjection(
-                    ctx,
-                    msg,
-                    gemini_client,
-                    &self.message_db,
-                    &self.bot_name,
-                    self.gemini_context_messages,
-                    &self.headline_cache,
-                )
-                .await
-                {
-                    Ok(true) => self.mark_interjection_sent().await,
-                    Err(e) => error!("Error in news interjection: {:?}", e),
-                    _ => {}
+        // Get the probability multiplier based on channel inactivity
+        let silence_multiplier = self
+            .fill_silence_manager
+            .get_probability_multiplier(msg.channel_id, current_user_id)
+            .await;
+
+        // Recency dampener: reduce interjection probability based on how recently
+        // the last one fired. Ramps from 0% at t=0 to 100% at t=10 minutes.
+        // The global hourly cap, the channel's warm-up and the bot having the last word
+        // zero it out entirely.
+        let held_off = if !self.interjection_cap.has_capacity() {
+            Some("hourly cap")
+        } else if !self.interjection_warmup.is_warm(msg.channel_id) {
+            Some("warming up")
+        } else if self.bot_has_last_word(msg.channel_id).await {
+            Some("bot has last word")
+        } else {
+            None
+        };
+        let recency_multiplier = if held_off.is_some() {
+            0.0
+        } else {
+            let last = self.last_interjection_time.read().await;
+            match *last {
+                Some(t) => {
+                    let elapsed_secs = t.elapsed().as_secs_f64();
+                    (elapsed_secs / 600.0).min(1.0) // linear ramp over 10 minutes
                 }
-            } else {
-                // If Gemini API is not configured
-                info!("News Interjection not available (Gemini API not configured) - no response sent");
+                None => 1.0, // no previous interjection
             }
+        };
+        let roll_context = interjection::RollContext {
+            channel_id: msg.channel_id,
+            silence_multiplier,
+            recency_multiplier,
+            held_off,
+            forced: None,
+        };
+
+        if self.run_interjections(ctx, msg, &roll_context).await? {
+            return Ok(());
         }
 
         // Check for keyword triggers
@@ -2544,24 +4503,11 @@ Keep it extremely brief and natural, as if you're just briefly pondering the con
             return Ok(());
         }
 
-        if content_lower.contains("lisa needs braces") {
-            if let Err(e) = msg.channel_id.say(&ctx.http, "DENTAL PLAN!").await {
-                error!("Error sending response: {:?}", e);
-            }
-            return Ok(());
-        }
-
-        if content_lower.trim_matches(|c: char| c.is_whitespace() || c.is_ascii_punctuation())
-            == "stop"
-        {
-            if let Err(e) = msg.channel_id.say(&ctx.http, "Hammer time!").await {
-                error!("Error sending response: {:?}", e);
-            }
-            return Ok(());
-        }
-
-        if content_lower.contains("my spoon is too big") {
-            if let Err(e) = msg.channel_id.say(&ctx.http, "I am a banana!").await {
+        // Call-and-response phrases from [[phrase_responses]]
+        let phrase_response =
+            phrase_responses::find_response(&self.phrase_responses, &msg.content, &mut rand::rng());
+        if let Some(response) = phrase_response {
+            if let Err(e) = msg.channel_id.say(&ctx.http, response).await {
                 error!("Error sending response: {:?}", e);
             }
             return Ok(());
@@ -2575,231 +4521,28 @@ Keep it extremely brief and natural, as if you're just briefly pondering the con
             {
                 if let Err(e) = msg.channel_id.say(&ctx.http, response).await {
                     error!("Error sending keyword response: {:?}", e);
-                }
-                return Ok(());
-            }
-        }
-
-        // Check for direct mentions of the bot
-        let current_user_id = self.get_bot_user_id(ctx).await;
-        if msg.mentions_user_id(current_user_id) {
-            // Extract the message content without the mention
-            let content = msg
-                .content
-                .replace(&format!("<@{current_user_id}>"), "")
-                .trim()
-                .to_string();
-
-            if !content.is_empty() {
-                if let Some(gemini_client) = &self.gemini_client {
-                    // Get and clean the display name
-                    let display_name = get_best_display_name(ctx, msg).await;
-                    let clean_display_name = clean_display_name(&display_name);
-
-                    // Extract pronouns from the same display name
-                    let user_pronouns = crate::display_name::extract_pronouns(&display_name);
-
-                    // Start typing indicator before making API call
-                    if let Err(e) = msg.channel_id.broadcast_typing(&ctx.http).await {
-                        error!("Failed to send typing indicator: {:?}", e);
-                    }
-
-                    // Get recent messages for context
-                    let context_messages = if let Some(db) = &self.message_db {
-                        match db_utils::get_recent_messages_with_reply_context(
-                            db.clone(),
-                            self.gemini_context_messages,
-                            Some(msg.channel_id.to_string().as_str()),
-                        )
-                        .await
-                        {
-                            Ok(messages) => messages,
-                            Err(e) => {
-                                error!("Error retrieving recent messages: {:?}", e);
-                                Vec::new()
-                            }
-                        }
-                    } else {
-                        Vec::new()
-                    };
-
-                    // Convert to the format expected by generate_response_with_context_and_pronouns
-                    let context_for_api: Vec<(String, String, Option<String>, String)> =
-                        context_messages
-                            .iter()
-                            .map(
-                                |(author, display_name, pronouns, content, _reply_context)| {
-                                    (
-                                        author.clone(),
-                                        display_name.clone(),
-                                        pronouns.clone(),
-                                        content.clone(),
-                                    )
-                                },
-                            )
-                            .collect();
-
-                    // Extract media (images/video) from the message and any replied-to message
-                    let media_items = media_utils::extract_media_from_message(msg).await;
-                    let youtube_urls = media_utils::extract_youtube_urls(&content);
-                    let has_media = !media_items.is_empty() || !youtube_urls.is_empty();
-
-                    // Append GIF instruction if Giphy is configured
-                    let gif_suffix = if self.giphy_client.is_some() {
-                        giphy::GIF_INSTRUCTION
-                    } else {
-                        ""
-                    };
-
-                    // Use multimodal path if media is present, otherwise standard text path
-                    let response_result = if has_media {
-                        info!(
-                            "Using multimodal path for mention: {} media items, {} YouTube URLs",
-                            media_items.len(),
-                            youtube_urls.len()
-                        );
-                        let prompt = format!(
-                            "{}{}",
-                            gemini_client.prompt_templates().format_general_response(
-                                &content,
-                                &clean_display_name,
-                                "",
-                            ),
-                            gif_suffix
-                        );
-                        gemini_client
-                            .generate_content_with_media(&prompt, &media_items, &youtube_urls)
-                            .await
-                            .map(Some)
-                    } else {
-                        let content_with_gif = format!("{}{}", content, gif_suffix);
-                        gemini_client
-                            .generate_best_response_with_context_and_pronouns(
-                                &content_with_gif,
-                                &clean_display_name,
-                                &context_for_api,
-                                user_pronouns.as_deref(),
-                                true, // Always respond when directly mentioned
-                            )
-                            .await
-                    };
-
-                    match response_result {
-                        Ok(Some(response)) => {
-                            // Check if the response looks like the prompt itself (API error)
-                            if response.contains("{bot_name}")
-                                || response.contains("{context}")
-                                || response.contains("Guidelines:")
-                            {
-                                error!("Mention response error: API returned prompt text");
-                                return Ok(());
-                            }
-
-                            // Check for GIF resolution
-                            if let Some(giphy_client) = &self.giphy_client {
-                                if let Some(gif_url) = giphy_client.try_resolve_gif(&response).await
-                                {
-                                    let message_reference = MessageReference::from(msg);
-                                    let create_message = CreateMessage::new()
-                                        .content(gif_url)
-                                        .reference_message(message_reference);
-                                    if let Err(e) =
-                                        msg.channel_id.send_message(&ctx.http, create_message).await
-                                    {
-                                        error!("Error sending GIF reply: {:?}", e);
-                                    }
-                                    return Ok(());
-                                }
-                                if let Some((text, gif_url)) =
-                                    giphy_client.try_resolve_embedded_gif(&response).await
-                                {
-                                    if !text.is_empty() {
-                                        apply_realistic_delay(&text, ctx, msg.channel_id).await;
-                                        let message_reference = MessageReference::from(msg);
-                                        let create_message = CreateMessage::new()
-                                            .content(&text)
-                                            .reference_message(message_reference);
-                                        if let Err(e) = msg
-                                            .channel_id
-                                            .send_message(&ctx.http, create_message)
-                                            .await
-                                        {
-                                            error!("Error sending text before GIF: {:?}", e);
-                                        }
-                                    }
-                                    if let Err(e) = msg.channel_id.say(&ctx.http, &gif_url).await {
-                                        error!("Error sending GIF reply: {:?}", e);
-                                    }
-                                    return Ok(());
-                                }
-                            }
-
-                            // Apply realistic typing delay based on response length
-                            apply_realistic_delay(&response, ctx, msg.channel_id).await;
-
-                            // Create a message reference for replying
-                            let message_reference = MessageReference::from(msg);
-                            let create_message = CreateMessage::new()
-                                .content(response.clone())
-                                .reference_message(message_reference);
-
-                            if let Err(e) =
-                                msg.channel_id.send_message(&ctx.http, create_message).await
-                            {
-                                error!("Error sending Gemini response as reply: {:?}", e);
-                                // Fallback to regular message if reply fails
-                                if let Err(e) = msg.channel_id.say(&ctx.http, response).await {
-                                    error!("Error sending fallback Gemini response: {:?}", e);
-                                }
-                            }
-                        }
-                        Ok(None) => {
-                            // No response generated (all were "pass")
-                            return Ok(());
-                        }
-                        Err(e) => {
-                            error!("Error calling Gemini API: {:?}", e);
-
-                            let error_string = e.to_string();
-
-                            // Don't show silent errors to users
-                            if error_string.contains("SILENT_ERROR") {
-                                return Ok(());
-                            }
-
-                            // Show a friendly message without raw error details
-                            let user_message = if error_string.contains("BILLING_ERROR") {
-                                "The Gemini API quota or billing limit has been reached. I'll be back once the limit resets!".to_string()
-                            } else {
-                                "I'm having trouble thinking right now. Try again in a bit!"
-                                    .to_string()
-                            };
-
-                            // Create a message reference for replying
-                            let message_reference = MessageReference::from(msg);
-                            let create_message = CreateMessage::new()
-                                .content(&user_message)
-                                .reference_message(message_reference);
-
-                            if let Err(e) =
-                                msg.channel_id.send_message(&ctx.http, create_message).await
-                            {
-                                error!("Error sending error message as reply: {:?}", e);
-                                // Fallback to regular message if reply fails
-                                if let Err(e) = msg.channel_id.say(&ctx.http, &user_message).await {
-                                    error!("Error sending fallback error message: {:?}", e);
-                                }
-                            }
-                        }
-                    }
-                } else {
-                    // Fallback if Gemini API is not configured
-                    let display_name = get_best_display_name(ctx, msg).await;
-                    let clean_display_name = clean_display_name(&display_name);
-                    if let Err(e) = msg.channel_id.say(&ctx.http, format!("Hello {}, you mentioned me! I'm {}! (Gemini API is not configured)", clean_display_name, self.bot_name)).await {
-                        error!("Error sending mention response: {:?}", e);
+                }
+                return Ok(());
+            }
+        }
+
+        // Check for direct mentions of the bot
+        let current_user_id = self.get_bot_user_id(ctx).await;
+        if msg.mentions_user_id(current_user_id) {
+            // Extract the message content without the mention
+            let content = match mention_action(&msg.content, current_user_id) {
+                MentionAction::Ask(content) => content,
+                MentionAction::Prompt => {
+                    if let Err(e) = msg.reply(&ctx.http, BARE_MENTION_REPLY).await {
+                        error!("Error replying to bare mention: {:?}", e);
                     }
+                    return Ok(());
                 }
+                MentionAction::Ignore => String::new(),
+            };
+
+            if !content.is_empty() {
+                self.reply_with_ai(ctx, msg, content).await?;
             }
         }
 
@@ -2809,6 +4552,11 @@ Keep it extremely brief and natural, as if you're just briefly pondering the con
 #[async_trait]
 impl EventHandler for Bot {
     async fn message(&self, ctx: Context, msg: Message) {
+        // Once shutdown has begun, leave new messages for missed-message recovery
+        let Some(_work) = self.shutdown.begin_work() else {
+            return;
+        };
+
         // Get the bot ID (cached from ready handler)
         let bot_id = self.get_bot_user_id(&ctx).await;
 
@@ -2817,11 +4565,15 @@ impl EventHandler for Bot {
             .update_activity_and_count(msg.channel_id, msg.author.id, bot_id)
             .await;
 
-        // Mark that a user (not the bot) was the last speaker
+        // Track whether the bot or someone else spoke last
         if msg.author.id != bot_id {
             self.fill_silence_manager
                 .mark_user_as_last_speaker(msg.channel_id)
                 .await;
+        } else {
+            self.fill_silence_manager
+                .mark_bot_as_last_speaker(msg.channel_id)
+                .await;
         }
 
         // Update the last seen message for this channel
@@ -2830,67 +4582,8 @@ impl EventHandler for Bot {
             last_seen.insert(msg.channel_id, (msg.timestamp, msg.id));
         }
 
-        // Store all messages in the database, including our own
-        if let Some(db) = &self.message_db {
-            // Get the display name
-            let display_name = get_best_display_name(&ctx, &msg).await;
-
-            // Check if this is a gateway bot message and extract the real username
-            let (author_name, final_display_name) = if msg.author.bot {
-                let bot_id = msg.author.id;
-
-                // Check if this is a gateway bot
-                if self.gateway_bot_ids.contains(&bot_id.get()) {
-                    // Try to extract the gateway username from the message content or author name
-                    if let Some(gateway_username) =
-                        crate::display_name::extract_gateway_username(&msg)
-                    {
-                        // Log the extraction for debugging
-                        info!(
-                            "📝 Extracted gateway username for bot {}: {}",
-                            bot_id, gateway_username
-                        );
-
-                        // Use the gateway username as both author and display name
-                        (gateway_username.clone(), gateway_username)
-                    } else {
-                        // Fallback to the display name we got earlier
-                        info!(
-                            "📝 Could not extract gateway username for bot {}, using fallback",
-                            bot_id
-                        );
-                        (msg.author.name.clone(), display_name)
-                    }
-                } else {
-                    // Regular bot, use the display name we got earlier
-                    (msg.author.name.clone(), display_name)
-                }
-            } else {
-                // Regular user, use the display name we got earlier
-                (msg.author.name.clone(), display_name)
-            };
-
-            // Save the message to the database (include attachment metadata)
-            let attachment_tags = media_utils::describe_attachments(&msg);
-            let stored_content = if attachment_tags.is_empty() {
-                msg.content.clone()
-            } else {
-                format!("{} {}", msg.content, attachment_tags)
-            };
-
-            if let Err(e) = db_utils::save_message(
-                db.clone(),
-                &author_name,
-                &final_display_name,
-                &stored_content,
-                Some(&msg),
-                None,
-            )
-            .await
-            {
-                error!("Error saving message to database: {:?}", e);
-            }
-        }
+        // Store all messages in the database and the in-memory history, including our own
+        self.record_message(&ctx, &msg).await;
 
         // Check if the message is from a bot
         if msg.author.bot {
@@ -2939,22 +4632,12 @@ impl EventHandler for Bot {
             return;
         }
 
-        // Check if we should respond in quiet channels (for special responses like "whoa")
+        // Check if we should respond in quiet channels
         if !self.should_respond_in_quiet_channel(&ctx, &msg).await {
             // In a quiet channel and not directly addressed - skip special responses
             return;
         }
 
-        // Special case: respond with "I know kung fu!" when someone says exactly "whoa"
-        let trimmed_content = msg.content.trim().to_lowercase();
-        if trimmed_content == "whoa" || trimmed_content == "woah" {
-            info!("Responding to 'whoa' with Matrix reference");
-            if let Err(e) = msg.channel_id.say(&ctx.http, "I know kung fu!").await {
-                error!("Error sending kung fu response: {:?}", e);
-            }
-            return;
-        }
-
         // Check for regex substitution (!s/, .s/, !/, ./)
         if msg.content.starts_with("!s/")
             || msg.content.starts_with(".s/")
@@ -2983,25 +4666,22 @@ impl EventHandler for Bot {
     ) {
         // Only process if we have the new message content
         if let Some(msg) = new {
-            // Store the updated message in the database
-            if let Some(db) = &self.message_db {
-                // Get the display name
-                let display_name = get_best_display_name(&ctx, &msg).await;
-
+            // Store the updated message in the database and the in-memory history
+            let display_name = get_best_display_name(&ctx, &msg).await;
+            if let Some(db) = &self.message_store {
                 // Save the message to the database (will update if it already exists)
-                if let Err(e) = db_utils::save_message(
-                    db.clone(),
-                    &msg.author.name,
-                    &display_name,
-                    &msg.content,
-                    Some(&msg),
-                    None,
-                )
-                .await
+                if let Err(e) = db
+                    .save(&msg.author.name, &display_name, &msg.content, Some(&msg))
+                    .await
                 {
                     error!("Error saving updated message to database: {:?}", e);
                 }
             }
+            remember_message(
+                &ctx,
+                StoredMessage::from_message(&msg.author.name, &display_name, &msg.content, &msg),
+            )
+            .await;
 
             // Only process messages in the followed channels
             if !self.followed_channels.contains(&msg.channel_id) {
@@ -3015,6 +4695,76 @@ impl EventHandler for Bot {
         }
     }
 
+    // Handle reactions being added (only delivered when a reaction feature is enabled)
+    async fn reaction_add(&self, ctx: Context, reaction: Reaction) {
+        if self.reaction_features.is_empty()
+            || !self.followed_channels.contains(&reaction.channel_id)
+        {
+            return;
+        }
+
+        debug!(
+            "Reaction {} added to message {} in channel {}",
+            reaction.emoji, reaction.message_id, reaction.channel_id
+        );
+
+        if self.reaction_features.iter().any(|f| f == "cooldown_retry") {
+            self.retry_after_cooldown(&ctx, &reaction).await;
+        }
+        if self.screenshot_pages.is_some() {
+            self.turn_screenshot_page(&ctx, &reaction).await;
+        }
+        if self
+            .reaction_features
+            .iter()
+            .any(|f| f == "interjection_feedback")
+        {
+            self.record_interjection_feedback(&reaction).await;
+        }
+        if let Some(reaction_quotes) = &self.reaction_quotes {
+            let bot_user_id = *self.bot_user_id.read().await;
+            if let Err(e) = reaction_quotes
+                .handle_reaction(&ctx, &reaction, bot_user_id, self.message_db.clone())
+                .await
+            {
+                error!("Error quoting reacted message: {:?}", e);
+            }
+        }
+    }
+
+    async fn interaction_create(&self, ctx: Context, interaction: Interaction) {
+        if let Interaction::Command(command) = interaction {
+            self.handle_slash_command(&ctx, &command).await;
+        }
+    }
+
+    // In adaptive mode, an interjection being deleted counts against its type
+    async fn message_delete(
+        &self,
+        _ctx: Context,
+        _channel_id: ChannelId,
+        deleted_message_id: MessageId,
+        _guild_id: Option<GuildId>,
+    ) {
+        if let Some(adaptive) = &self.adaptive_interjections {
+            adaptive.record_deletion(deleted_message_id).await;
+        }
+    }
+
+    // Handle reactions being removed
+    async fn reaction_remove(&self, _ctx: Context, reaction: Reaction) {
+        if self.reaction_features.is_empty()
+            || !self.followed_channels.contains(&reaction.channel_id)
+        {
+            return;
+        }
+
+        debug!(
+            "Reaction {} removed from message {} in channel {}",
+            reaction.emoji, reaction.message_id, reaction.channel_id
+        );
+    }
+
     async fn ready(&self, ctx: Context, ready: Ready) {
         // Cache the bot's user ID for use throughout the session
         {
@@ -3036,6 +4786,15 @@ impl EventHandler for Bot {
 
         info!("Bot is ready to respond to messages in the configured channels");
 
+        // Register slash commands (replaces whatever was registered before)
+        if self.slash_commands_enabled {
+            let commands = slash_commands::slash_commands(!self.imagine_channels.is_empty());
+            match Command::set_global_commands(&ctx.http, commands).await {
+                Ok(registered) => info!("Registered {} slash commands", registered.len()),
+                Err(e) => error!("Error registering slash commands: {:?}", e),
+            }
+        }
+
         // Start background news feed fetcher (refreshes every 15 minutes)
         news_feed::spawn_fetcher(
             self.headline_cache.clone(),
@@ -3044,8 +4803,8 @@ impl EventHandler for Bot {
         );
 
         // Load last seen messages from the database
-        if let Some(db) = &self.message_db {
-            match db_utils::get_last_messages_by_channel(db.clone()).await {
+        if let Some(db) = &self.message_store {
+            match db.last_seen_by_channel().await {
                 Ok(last_seen_db) => {
                     info!(
                         "Loaded {} last seen messages from database",
@@ -3088,6 +4847,402 @@ impl EventHandler for Bot {
     }
 }
 
+// The gateway intents to connect with. Reaction events are only requested when a reaction
+// feature needs them.
+fn gateway_intents(reaction_features: &[String]) -> GatewayIntents {
+    let intents =
+        GatewayIntents::GUILD_MESSAGES | GatewayIntents::MESSAGE_CONTENT | GatewayIntents::GUILDS;
+    if reaction_features.is_empty() {
+        intents
+    } else {
+        intents | GatewayIntents::GUILD_MESSAGE_REACTIONS
+    }
+}
+
+/// Where a `!` command goes, decided before any of it runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CommandRoute<'a> {
+    Hello,
+    Trump,
+    BandName,
+    Imagine,
+    AliveOrDead,
+    Wiki,
+    Translate,
+    Help,
+    Info,
+    Uptime,
+    Stats,
+    Slogan,
+    /// `!quote`, including `!quote -dud`
+    Quote,
+    FightCrime,
+    Buzz,
+    LastSeen,
+    /// `!forget` and `!forgetme`
+    Forget,
+    RemindMe,
+    Birthday,
+    Pronouns,
+    Speakers,
+    InterjectStats,
+    Search,
+    Calc,
+    Roll,
+    Choose,
+    EightBall,
+    Urban,
+    VibeCheck,
+    Summarize,
+    Subtitles,
+    /// `!frinkiac` and `!morbotron`
+    Screenshot,
+    Again,
+    Interject,
+    Rephrase,
+    MasterOfAllScience,
+    /// The `[[screenshot_sources]]` entry at this index
+    ScreenshotSource(usize),
+    /// A fixed response from the `[commands]` table
+    Custom(&'a str),
+    /// Anything else, which Gemini answers when it's configured
+    Unknown,
+}
+
+// Route a lowercased command. Built-in commands win over configured screenshot sources,
+// which win over custom commands; `!imagine` only counts when some channel allows it.
+fn route_command<'a, 'b>(
+    command: &str,
+    imagine_enabled: bool,
+    source_commands: impl IntoIterator<Item = &'b str>,
+    custom_commands: &'a HashMap<String, String>,
+) -> CommandRoute<'a> {
+    match command {
+        "hello" => CommandRoute::Hello,
+        "trump" => CommandRoute::Trump,
+        "bandname" => CommandRoute::BandName,
+        "imagine" if imagine_enabled => CommandRoute::Imagine,
+        "alive" | "dead" => CommandRoute::AliveOrDead,
+        "wiki" => CommandRoute::Wiki,
+        "translate" => CommandRoute::Translate,
+        "help" => CommandRoute::Help,
+        "info" => CommandRoute::Info,
+        "uptime" => CommandRoute::Uptime,
+        "stats" => CommandRoute::Stats,
+        "slogan" => CommandRoute::Slogan,
+        "quote" => CommandRoute::Quote,
+        "fightcrime" => CommandRoute::FightCrime,
+        "buzz" => CommandRoute::Buzz,
+        "lastseen" | "seen" => CommandRoute::LastSeen,
+        "forgetme" | "forget" => CommandRoute::Forget,
+        "remindme" => CommandRoute::RemindMe,
+        "birthday" => CommandRoute::Birthday,
+        "pronouns" => CommandRoute::Pronouns,
+        "speakers" => CommandRoute::Speakers,
+        "interject-stats" => CommandRoute::InterjectStats,
+        "search" => CommandRoute::Search,
+        "calc" => CommandRoute::Calc,
+        "roll" => CommandRoute::Roll,
+        "choose" | "pick" => CommandRoute::Choose,
+        "8ball" => CommandRoute::EightBall,
+        "urban" => CommandRoute::Urban,
+        "vibecheck" => CommandRoute::VibeCheck,
+        "summarize" => CommandRoute::Summarize,
+        "subtitles" => CommandRoute::Subtitles,
+        "frinkiac" | "morbotron" => CommandRoute::Screenshot,
+        "again" => CommandRoute::Again,
+        "interject" => CommandRoute::Interject,
+        "rephrase" => CommandRoute::Rephrase,
+        "masterofallscience" => CommandRoute::MasterOfAllScience,
+        _ => {
+            if let Some(index) = source_commands
+                .into_iter()
+                .position(|source| source == command)
+            {
+                CommandRoute::ScreenshotSource(index)
+            } else if let Some(response) = custom_commands.get(command) {
+                CommandRoute::Custom(response)
+            } else {
+                CommandRoute::Unknown
+            }
+        }
+    }
+}
+
+// Split a `!command args...` message into the lowercased command and the message's words
+// (command first), or None if there's no command
+fn parse_command(content: &str) -> Option<(String, Vec<&str>)> {
+    let parts: Vec<&str> = content.strip_prefix('!')?.split_whitespace().collect();
+    let command = parts.first()?.to_lowercase();
+    Some((command, parts))
+}
+
+// The search term and show for `!quote [term] [-show show]`. Words after the first run of
+// search words are ignored, as are unknown flags and the values after date flags and -count.
+fn quote_args(args: &[&str]) -> (Option<String>, Option<String>) {
+    let mut search_term = None;
+    let mut show_name = None;
+
+    let mut i = 0;
+    while i < args.len() {
+        if args[i] == "-show" && i + 1 < args.len() {
+            // Collect all words after -show until the next flag or end
+            let mut show = Vec::new();
+            i += 1;
+            while i < args.len() && !args[i].starts_with('-') {
+                show.push(args[i]);
+                i += 1;
+            }
+            show_name = Some(show.join(" "));
+        } else if db_utils::DATE_FLAGS.contains(&args[i]) || args[i] == "-count" {
+            // Skip the flag and its value
+            i += 2;
+        } else if !args[i].starts_with('-') {
+            // If not a flag, treat as search term
+            if search_term.is_none() {
+                let mut terms = Vec::new();
+                while i < args.len() && !args[i].starts_with('-') {
+                    terms.push(args[i]);
+                    i += 1;
+                }
+                search_term = Some(terms.join(" "));
+            } else {
+                i += 1;
+            }
+        } else {
+            // Skip unknown flags
+            i += 1;
+        }
+    }
+
+    (search_term, show_name)
+}
+
+// Most quotes one !quote can post, so -count can't flood the channel
+const MAX_QUOTE_COUNT: usize = 5;
+
+// How many quotes `-count N` asks for, clamped to 1..=MAX_QUOTE_COUNT. Without the flag,
+// or with a value that isn't a number, it's one.
+fn quote_count(args: &[&str]) -> usize {
+    args.iter()
+        .position(|&arg| arg == "-count")
+        .and_then(|i| args.get(i + 1))
+        .and_then(|value| value.parse::<u64>().ok())
+        .map_or(1, |count| count.clamp(1, MAX_QUOTE_COUNT as u64) as usize)
+}
+
+// One `<name> message` line per dud quote, named by display name when there is one
+fn format_dud_quotes(quotes: &[(String, String, String)]) -> Vec<String> {
+    quotes
+        .iter()
+        .map(|(author, display_name, content)| {
+            // Use the display name if available, otherwise fall back to author name
+            let name_to_use = if !display_name.is_empty() {
+                display_name
+            } else {
+                author
+            };
+
+            // Use the display_name::clean_display_name function for consistency
+            // This will also strip angle brackets if the name is in gateway format
+            let clean_display_name = display_name::clean_display_name(name_to_use);
+            format!("<{clean_display_name}> {content}")
+        })
+        .collect()
+}
+
+// Say who a name that only partly matched was taken to mean
+fn dud_name_note(asked: &str, name_match: &db_utils::NameMatch) -> Option<String> {
+    match name_match {
+        db_utils::NameMatch::Exact(_) => None,
+        db_utils::NameMatch::Partial(name) => Some(format!(
+            "(Nobody goes by {asked}, so here's {})",
+            display_name::clean_display_name(name)
+        )),
+    }
+}
+
+// The user to quote and whether short messages count for `!quote -dud [user] [-all]`,
+// or None for a regular !quote
+fn quote_dud_args(args: &[&str]) -> Option<(Option<String>, bool)> {
+    let dud_index = args.iter().position(|&arg| arg == "-dud")?;
+    let username = args
+        .get(dud_index + 1)
+        .filter(|arg| !arg.starts_with('-'))
+        .map(|arg| arg.to_string());
+    // -all includes short messages like "lol"
+    let truly_random = args.contains(&"-all");
+    Some((username, truly_random))
+}
+
+// Reply to an @mention with nothing else in it, so the user isn't met with silence
+const BARE_MENTION_REPLY: &str = "You rang? Ask me something or try !help.";
+
+// What to do with a message that mentions the bot
+#[derive(Debug, PartialEq)]
+enum MentionAction {
+    // Just the mention and nothing else: prompt the user to ask something
+    Prompt,
+    // Pass the text (with the mention stripped) on to Gemini
+    Ask(String),
+    // Mentioned without a tag in the text, e.g. an attachment-only reply to the bot
+    Ignore,
+}
+
+fn mention_action(content: &str, bot_id: UserId) -> MentionAction {
+    let tags = [format!("<@{bot_id}>"), format!("<@!{bot_id}>")];
+    let mut text = content.to_string();
+    for tag in &tags {
+        text = text.replace(tag.as_str(), "");
+    }
+    let text = text.trim();
+
+    if !text.is_empty() {
+        MentionAction::Ask(text.to_string())
+    } else if tags.iter().any(|tag| content.contains(tag.as_str())) {
+        MentionAction::Prompt
+    } else {
+        MentionAction::Ignore
+    }
+}
+
+// Add a message to the in-memory history, replacing it if it was edited
+async fn remember_message(ctx: &Context, message: StoredMessage) {
+    let history = ctx.data.read().await.get::<MessageHistoryKey>().cloned();
+    if let Some(history) = history {
+        history.write().await.push(message);
+    }
+}
+
+// Recent messages for context, newest first. They come from the message store, or from
+// the in-memory history when there's no store or it fails.
+async fn recent_context(
+    store: Option<&Arc<dyn MessageStore>>,
+    history: Option<&Arc<RwLock<MessageHistory>>>,
+    channel_id: ChannelId,
+    limit: usize,
+) -> Vec<ContextMessage> {
+    if let Some(store) = store {
+        match store
+            .get_recent(limit, Some(channel_id.to_string().as_str()))
+            .await
+        {
+            Ok(messages) => return messages,
+            Err(e) => error!(
+                "Error retrieving recent messages, using in-memory history: {:?}",
+                e
+            ),
+        }
+    }
+
+    match history {
+        Some(history) => history
+            .read()
+            .await
+            .recent_context(limit, Some(channel_id.get())),
+        None => Vec::new(),
+    }
+}
+
+// How many recent channel messages an interjection is compared against
+const INTERJECTION_ECHO_WINDOW: usize = 10;
+
+// Whether an interjection would just repeat something recently said in the channel.
+// The bot's own messages are stored too, so this also catches it repeating itself.
+async fn interjection_echoes_channel(
+    db: Option<&Arc<dyn MessageStore>>,
+    channel_id: ChannelId,
+    text: &str,
+    threshold: f64,
+) -> bool {
+    let Some(db) = db.filter(|_| threshold > 0.0) else {
+        return false;
+    };
+
+    match db
+        .get_recent(
+            INTERJECTION_ECHO_WINDOW,
+            Some(channel_id.to_string().as_str()),
+        )
+        .await
+    {
+        Ok(messages) => {
+            let echo = utils::is_echo_of_recent(
+                text,
+                messages
+                    .iter()
+                    .map(|(_, _, _, content, _)| content.as_str()),
+                threshold,
+            );
+            if echo {
+                info!(
+                    "Skipping interjection that echoes recent channel content: {}",
+                    text
+                );
+            }
+            echo
+        }
+        Err(e) => {
+            error!("Error retrieving recent messages for echo check: {:?}", e);
+            false
+        }
+    }
+}
+
+// How long a shutdown waits for in-flight messages before disconnecting anyway
+const SHUTDOWN_GRACE_PERIOD: Duration = Duration::from_secs(10);
+
+// How many servers are searched for a followed channel at once
+const CHANNEL_SEARCH_CONCURRENCY: usize = 8;
+
+/// What searching a set of servers for a channel turned up
+#[derive(Debug, Default, PartialEq)]
+struct ChannelSearchSummary {
+    channels: Vec<ChannelId>,
+    searched: usize,
+    errored: usize,
+}
+
+// Look up matching channels in each server with bounded concurrency. A server whose lookup
+// fails is counted and skipped; results keep the order the servers were given in.
+async fn search_guilds_for_channels<F, Fut>(
+    guild_ids: Vec<GuildId>,
+    lookup: F,
+) -> ChannelSearchSummary
+where
+    F: Fn(GuildId) -> Fut,
+    Fut: std::future::Future<Output = Result<Vec<ChannelId>>>,
+{
+    let mut results: Vec<(usize, GuildId, Result<Vec<ChannelId>>)> =
+        futures::stream::iter(guild_ids.into_iter().enumerate())
+            .map(|(index, guild_id)| {
+                let lookup = lookup(guild_id);
+                async move { (index, guild_id, lookup.await) }
+            })
+            .buffer_unordered(CHANNEL_SEARCH_CONCURRENCY)
+            .collect()
+            .await;
+    results.sort_by_key(|(index, _, _)| *index);
+
+    let mut summary = ChannelSearchSummary {
+        searched: results.len(),
+        ..Default::default()
+    };
+    for (_, guild_id, result) in results {
+        match result {
+            Ok(channels) => summary.channels.extend(channels),
+            Err(e) => {
+                warn!(
+                    "Could not retrieve channels for server {}: {:?}",
+                    guild_id, e
+                );
+                summary.errored += 1;
+            }
+        }
+    }
+    summary
+}
+
 // Helper function to find channels by name
 async fn find_channels_by_name(
     http: &Http,
@@ -3097,7 +5252,10 @@ async fn find_channels_by_name(
     // Get all the guilds (servers) the bot is in
     let guilds = match http.get_guilds(None, None).await {
         Ok(guilds) => guilds,
-        Err(_) => return Vec::new(),
+        Err(e) => {
+            error!("Could not list servers to search for '{}': {:?}", name, e);
+            return Vec::new();
+        }
     };
 
     info!(
@@ -3106,62 +5264,53 @@ async fn find_channels_by_name(
         guilds.len()
     );
 
-    let mut found_channels = Vec::new();
-
-    // For each guild, try to find the channel
-    for guild_info in guilds {
-        let guild_id = guild_info.id;
-
-        // If server_name is specified, check if this is the right server
-        if let Some(server) = server_name {
-            if let Ok(guild) = http.get_guild(guild_id).await {
-                if guild.name != server {
-                    info!(
-                        "Skipping server '{}' as it doesn't match the specified server name '{}'",
-                        guild.name, server
-                    );
-                    continue; // Skip this server if name doesn't match
-                }
-                info!("Checking server '{}' for channel '{}'", guild.name, name);
-            }
-        } else if let Ok(guild) = http.get_guild(guild_id).await {
-            info!("Checking server '{}' for channel '{}'", guild.name, name);
-        }
-
-        // Get all channels in this guild
-        if let Ok(channels) = http.get_channels(guild_id).await {
-            info!("Found {} channels in this server", channels.len());
-
-            // Find the channel with the matching name
-            for channel in channels {
-                if channel.name == name {
-                    info!(
-                        "✅ Found matching channel '{}' (ID: {}) in server",
-                        channel.name, channel.id
-                    );
-                    found_channels.push(channel.id);
-                }
-            }
-
-            if found_channels.is_empty() {
-                info!("No matching channel found in this server");
+    // If server_name is specified, only search servers with that name
+    let guild_ids = guilds
+        .into_iter()
+        .filter(|guild| match server_name {
+            Some(server) if guild.name != server => {
+                info!(
+                    "Skipping server '{}' as it doesn't match the specified server name '{}'",
+                    guild.name, server
+                );
+                false
             }
-        } else {
-            info!("Could not retrieve channels for this server");
-        }
-    }
+            _ => true,
+        })
+        .map(|guild| guild.id)
+        .collect();
+
+    let summary = search_guilds_for_channels(guild_ids, |guild_id| async move {
+        let channels = http.get_channels(guild_id).await?;
+        Ok(channels
+            .into_iter()
+            .filter(|channel| channel.name == name)
+            .map(|channel| {
+                info!(
+                    "✅ Found matching channel '{}' (ID: {}) in server {}",
+                    channel.name, channel.id, guild_id
+                );
+                channel.id
+            })
+            .collect())
+    })
+    .await;
 
-    if found_channels.is_empty() {
+    info!(
+        "Searched {} servers for '{}' ({} errored)",
+        summary.searched, name, summary.errored
+    );
+    if summary.channels.is_empty() {
         info!("❌ Channel '{}' not found in any server", name);
     } else {
         info!(
             "Found {} channels matching '{}'",
-            found_channels.len(),
+            summary.channels.len(),
             name
         );
     }
 
-    found_channels
+    summary.channels
 }
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -3176,6 +5325,13 @@ async fn main() -> Result<()> {
 
     // Parse config values
     let parsed_config = parse_config(&config);
+    http_client::set_request_timeout(parsed_config.http_timeout);
+    db_utils::set_collapse_context_duplicates(parsed_config.context_collapse_duplicates);
+    display_name::set_fetch_uncached_members(parsed_config.fetch_uncached_members);
+    db_utils::set_context_paste_handling(
+        parsed_config.context_paste_line_threshold,
+        parsed_config.context_exclude_pastes,
+    );
 
     info!(
         "News interjection probability: {}%",
@@ -3433,12 +5589,17 @@ Keep it brief and natural, as if you're just another participant in the conversa
     );
 
     // Set gateway intents, which decides what events the bot will be notified about
-    let intents =
-        GatewayIntents::GUILD_MESSAGES | GatewayIntents::MESSAGE_CONTENT | GatewayIntents::GUILDS;
+    let intents = gateway_intents(&parsed_config.reaction_features);
+    if intents.contains(GatewayIntents::GUILD_MESSAGE_REACTIONS) {
+        info!(
+            "Added GUILD_MESSAGE_REACTIONS intent for reaction features: {:?}",
+            parsed_config.reaction_features
+        );
+    }
 
-    // Initialize SQLite database for message history
-    let db_path = "message_history.db";
-    let message_db = match db_utils::initialize_database(db_path).await {
+    // Initialize the SQLite database (message history too, unless that's in Postgres)
+    let db_path = parsed_config.message_store.sqlite_path().to_string();
+    let message_db = match db_utils::initialize_database(&db_path).await {
         Ok(conn) => {
             info!("Successfully connected to message history database");
 
@@ -3464,6 +5625,29 @@ Keep it brief and natural, as if you're just another participant in the conversa
         }
     };
 
+    let message_store: Option<Arc<dyn MessageStore>> =
+        match (&parsed_config.message_store, &message_db) {
+            (_, None) => None,
+            (MessageStoreSettings::Sqlite { .. }, Some(db)) => {
+                Some(Arc::new(SqliteMessageStore::new(db.clone())))
+            }
+            (MessageStoreSettings::Postgres { url }, Some(db)) => {
+                match PostgresMessageStore::connect(url, db.clone()).await {
+                    Ok(store) => Some(Arc::new(store)),
+                    Err(e) => {
+                        error!("Failed to open the PostgreSQL message store: {:?}", e);
+                        None
+                    }
+                }
+            }
+        };
+
+    // Today's AI token totals, picking up where the last run left off
+    let token_usage = Arc::new(token_usage::UsageTracker::new(message_db.clone()));
+    if let Err(e) = token_usage.load().await {
+        error!("Failed to load today's token usage: {:?}", e);
+    }
+
     // Find the channel ID first
     let client = Client::builder(token, intents).await?;
 
@@ -3575,6 +5759,30 @@ Keep it brief and natural, as if you're just another participant in the conversa
 
     info!("✅ Found {} channels to follow", channel_ids.len());
 
+    // Resolve the channels with their own fill silence settings
+    let mut fill_silence_channels = HashMap::new();
+    for (channel, settings) in &parsed_config.fill_silence_channels {
+        match channel {
+            fill_silence::SilenceChannel::Id(channel_id) => {
+                fill_silence_channels.insert(*channel_id, *settings);
+            }
+            fill_silence::SilenceChannel::Name(name) => {
+                let found_channels = find_channels_by_name(
+                    &client.http,
+                    name,
+                    config.followed_server_name.as_deref(),
+                )
+                .await;
+                if found_channels.is_empty() {
+                    warn!("No channel named '{}' for its fill silence settings", name);
+                }
+                for channel_id in found_channels {
+                    fill_silence_channels.entry(channel_id).or_insert(*settings);
+                }
+            }
+        }
+    }
+
     // Clone values for the Bot struct
     let gemini_api_key_for_bot = gemini_api_key.clone();
     let gemini_api_endpoint_for_bot = gemini_api_endpoint.clone();
@@ -3594,11 +5802,14 @@ Keep it brief and natural, as if you're just another participant in the conversa
             gemini_prompt_wrapper: gemini_prompt_wrapper_for_bot,
             gemini_interjection_prompt: Some(gemini_interjection_prompt),
             message_db: message_db.clone(),
+            message_store: message_store.clone(),
+            token_usage: token_usage.clone(),
             log_prompts: gemini_log_prompts,
             interjection_fact_probability,
             gemini_personality_description: gemini_personality_description_for_bot,
             pollinations_api_key: config.pollinations_api_key.clone(),
             news_feeds: config.news_feeds.clone(),
+            fill_silence_channels,
         },
         parsed_config.clone(),
     );
@@ -3609,29 +5820,63 @@ Keep it brief and natural, as if you're just another participant in the conversa
     }
 
     // Start the database trimming task
-    if let Some(db) = &message_db {
-        let db_clone = db.clone();
+    if let Some(store) = &message_store {
+        let store = store.clone();
+        let count_cache = bot.message_count_cache.clone();
         let limit = parsed_config.message_history_limit;
+        let channel_limits = parsed_config.channel_history_limits.clone();
         let trim_interval = parsed_config.db_trim_interval;
+        let keep_protected = parsed_config.trim_keep_protected;
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(Duration::from_secs(trim_interval)).await;
+                info!("Running scheduled database trim task");
+                match store.trim(limit, &channel_limits, keep_protected).await {
+                    Ok(deleted) => {
+                        if deleted > 0 {
+                            count_cache.record_trim(deleted);
+                            info!("Trimmed database: removed {} old messages", deleted);
+                        }
+                    }
+                    Err(e) => {
+                        error!("Error trimming database: {:?}", e);
+                    }
+                }
+            }
+        });
+        info!(
+            "Started database trimming task (interval: {} seconds, limit: {} messages shared, {} channels with their own limit)",
+            parsed_config.db_trim_interval,
+            parsed_config.message_history_limit,
+            parsed_config.channel_history_limits.len()
+        );
+    }
+
+    if let Some(db) = &message_db {
+        // Periodically verify the connection and reopen it if it has died
+        let db_clone = db.clone();
+        let health_interval = parsed_config.db_health_check_interval;
         tokio::spawn(async move {
             loop {
-                tokio::time::sleep(Duration::from_secs(trim_interval)).await;
-                info!("Running scheduled database trim task");
-                match db_utils::trim_message_history(db_clone.clone(), limit).await {
-                    Ok(deleted) => {
-                        if deleted > 0 {
-                            info!("Trimmed database: removed {} old messages", deleted);
-                        }
-                    }
-                    Err(e) => {
-                        error!("Error trimming database: {:?}", e);
-                    }
+                tokio::time::sleep(Duration::from_secs(health_interval)).await;
+                if let Err(e) = db_utils::ensure_connection_healthy(
+                    &db_clone,
+                    || {
+                        let db_path = db_path.clone();
+                        async move { db_utils::open_database(&db_path).await }
+                    },
+                    3,
+                    Duration::from_secs(5),
+                )
+                .await
+                {
+                    error!("Message database is unavailable: {}", e);
                 }
             }
         });
         info!(
-            "Started database trimming task (interval: {} seconds, limit: {} messages)",
-            parsed_config.db_trim_interval, parsed_config.message_history_limit
+            "Started database health check task (interval: {} seconds)",
+            health_interval
         );
     }
 
@@ -3640,45 +5885,66 @@ Keep it brief and natural, as if you're just another participant in the conversa
 
     // Clone what we need for the spontaneous interjection task
     let fill_silence_manager = bot.fill_silence_manager.clone();
+    let interjection_cap = bot.interjection_cap.clone();
+    let headline_cache = bot.headline_cache.clone();
+    let interjection_warmup = bot.interjection_warmup.clone();
+    let interjection_similarity_threshold = bot.interjection_similarity_threshold;
+
+    // Clone what the shutdown handler needs to flush state
+    let shutdown_state = bot.shutdown.clone();
+    let last_seen_message = bot.last_seen_message.clone();
 
     let mut client = Client::builder(token, intents).event_handler(bot).await?;
 
+    let command_cooldowns = Arc::new(
+        command_cooldown::CommandCooldowns::new(Duration::from_secs(
+            parsed_config.command_cooldown_seconds,
+        ))
+        .with_overrides(parsed_config.command_cooldowns.clone()),
+    );
+
     // Initialize the data structures in the client data
     {
         let mut data = client.data.write().await;
-        let recent_speakers = Arc::new(RwLock::new(VecDeque::<(String, String)>::with_capacity(5)));
-        let message_history = Arc::new(RwLock::new(VecDeque::with_capacity(
-            parsed_config.message_history_limit,
+        let recent_speakers = Arc::new(RwLock::new(RecentSpeakers::with_capacity(
+            recent_speakers::MAX_RECENT_SPEAKERS,
         )));
+        let mut message_history = MessageHistory::new(parsed_config.message_history_limit);
 
-        // Load existing messages if database is available
-        if let Some(db) = &message_db {
-            // Create a temporary VecDeque to hold the loaded messages
-            let mut temp_history = VecDeque::new();
-            let db_clone = db.clone();
-
-            if let Err(e) = db_utils::load_message_history(
-                db_clone,
-                &mut temp_history,
-                parsed_config.message_history_limit,
-                None,
-            )
-            .await
+        // Seed the in-memory history so context is available before new messages arrive
+        if let Some(store) = &message_store {
+            match store
+                .load_history(parsed_config.message_history_limit)
+                .await
             {
-                error!("Failed to load message history: {:?}", e);
-            } else {
-                info!("Loaded {} messages from database", temp_history.len());
-
-                // For now, we can't directly convert the loaded messages to serenity Message objects
-                // In a real implementation, you would need to create Message objects from the stored data
-                // or modify the database schema to store all necessary fields
+                Ok(messages) => {
+                    info!("Loaded {} messages from database", messages.len());
+                    message_history.extend(messages);
+                }
+                Err(e) => error!("Failed to load message history: {:?}", e),
             }
         }
+        let message_history = Arc::new(RwLock::new(message_history));
 
         info!("Initializing RecentSpeakersKey in client data");
         data.insert::<RecentSpeakersKey>(recent_speakers);
         info!("Initializing MessageHistoryKey in client data");
         data.insert::<MessageHistoryKey>(message_history);
+        data.insert::<CommandCooldownsKey>(command_cooldowns.clone());
+    }
+
+    // Drop command cooldowns that have run out every so often
+    {
+        let command_cooldowns = command_cooldowns.clone();
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(command_cooldown::PRUNE_INTERVAL).await;
+                let pruned = command_cooldowns.prune();
+                if pruned > 0 {
+                    debug!("Pruned {} expired command cooldowns", pruned);
+                }
+            }
+        });
     }
 
     // Start the client
@@ -3703,6 +5969,51 @@ Keep it brief and natural, as if you're just another participant in the conversa
         }
     );
 
+    // Start the reminder scheduler; reminders are stored in the message database
+    if let Some(db) = &message_db {
+        tokio::spawn(reminder::run_reminder_scheduler(
+            client.http.clone(),
+            db.clone(),
+        ));
+        info!("Started reminder scheduler");
+    }
+
+    // Start the birthday announcement scheduler if a channel is configured
+    if let Some(channel_id) = parsed_config.birthday_channel_id {
+        if let Some(db) = &message_db {
+            tokio::spawn(birthday::run_birthday_scheduler(
+                client.http.clone(),
+                db.clone(),
+                ChannelId::new(channel_id),
+                parsed_config.birthday_utc_offset,
+            ));
+            info!("Started birthday scheduler for channel {}", channel_id);
+        } else {
+            warn!("Birthday announcements need the message database, which isn't available");
+        }
+    }
+
+    // Start the daily digest scheduler if a channel is configured
+    if let Some(digest) = parsed_config.daily_digest.clone() {
+        if let Some(db) = &message_db {
+            let channel_id = digest.channel_id;
+            tokio::spawn(daily_digest::run_daily_digest_scheduler(
+                client.http.clone(),
+                db.clone(),
+                digest,
+            ));
+            info!("Started daily digest scheduler for channel {}", channel_id);
+            if matches!(
+                parsed_config.message_store,
+                MessageStoreSettings::Postgres { .. }
+            ) {
+                warn!("The daily digest only counts messages in the local database, not the shared PostgreSQL history");
+            }
+        } else {
+            warn!("The daily digest needs the message database, which isn't available");
+        }
+    }
+
     // Start the spontaneous interjection task if fill silence is enabled
     if parsed_config.fill_silence_enabled {
         info!("Starting spontaneous interjection task for fill silence feature");
@@ -3711,7 +6022,8 @@ Keep it brief and natural, as if you're just another participant in the conversa
         let http = client.http.clone();
         let interjection_channels = interjection_channel_ids.clone();
         let bot_id = client.http.get_current_user().await?.id;
-        let message_db_clone = message_db.clone();
+        let message_store_clone = message_store.clone();
+        let message_history_clone = client.data.read().await.get::<MessageHistoryKey>().cloned();
         let bot_name_clone = parsed_config.bot_name.clone();
 
         // Log interjection channels
@@ -3726,19 +6038,31 @@ Keep it brief and natural, as if you're just another participant in the conversa
         // Create a new Gemini client for the task if we have an API key
         let task_gemini_client = if let Some(api_key) = &gemini_api_key {
             info!("Creating Gemini client for spontaneous interjection task");
-            Some(GeminiClient::new(GeminiConfig {
-                api_key: api_key.clone(),
-                api_endpoint: gemini_api_endpoint.clone(),
-                prompt_wrapper: gemini_prompt_wrapper.clone(),
-                bot_name: parsed_config.bot_name.clone(),
-                rate_limit_minute: parsed_config.gemini_rate_limit_minute,
-                rate_limit_day: parsed_config.gemini_rate_limit_day,
-                image_rate_limit_minute: parsed_config.gemini_image_rate_limit_minute,
-                image_rate_limit_day: parsed_config.gemini_image_rate_limit_day,
-                context_messages: parsed_config.gemini_context_messages,
-                log_prompts: gemini_log_prompts,
-                personality_description: gemini_personality_description.clone(),
-            }))
+            Some(
+                GeminiClient::new(GeminiConfig {
+                    api_key: api_key.clone(),
+                    api_endpoint: gemini_api_endpoint.clone(),
+                    fallback_api_endpoint: parsed_config.gemini_fallback_api_endpoint.clone(),
+                    fallback_api_key: parsed_config.gemini_fallback_api_key.clone(),
+                    prompt_wrapper: gemini_prompt_wrapper.clone(),
+                    bot_name: parsed_config.bot_name.clone(),
+                    rate_limit_minute: parsed_config.gemini_rate_limit_minute,
+                    rate_limit_day: parsed_config.gemini_rate_limit_day,
+                    image_rate_limit_minute: parsed_config.gemini_image_rate_limit_minute,
+                    image_rate_limit_day: parsed_config.gemini_image_rate_limit_day,
+                    context_messages: parsed_config.gemini_context_messages,
+                    log_prompts: gemini_log_prompts,
+                    personality_description: gemini_personality_description.clone(),
+                    uncertainty_mode: parsed_config.gemini_uncertainty_mode,
+                    persona_examples: parsed_config.gemini_persona_examples.clone(),
+                    temperatures: parsed_config.gemini_temperatures,
+                    generation: parsed_config.gemini_generation,
+                    max_retries: parsed_config.gemini_max_retries,
+                    api_flavor: parsed_config.llm_api_flavor,
+                    model: parsed_config.llm_model.clone(),
+                })
+                .with_usage_tracker(token_usage.clone()),
+            )
         } else {
             None
         };
@@ -3749,21 +6073,24 @@ Keep it brief and natural, as if you're just another participant in the conversa
             MultiResponseGenerator::new(client.clone(), MultiResponseConfig::default())
         });
 
-        // Compile regexes used in the interjection loop
-        let via_regex = regex::Regex::new(r"\s*\(via\s+[^)]+\)\s*").unwrap();
-        let url_regex = regex::Regex::new(r"https?://[^\s]+").unwrap();
+        let check_interval = parsed_config.fill_silence_check_interval;
 
         // Spawn the task
         tokio::spawn(async move {
+            // ThreadRng can't be held across an await, so the task keeps its own
+            let mut rng: rand::rngs::StdRng = rand::make_rng();
+
             loop {
                 // Check each channel for spontaneous interjections
                 for channel_id in &interjection_channels {
                     if fill_silence_manager
                         .should_check_spontaneous_interjection(*channel_id, bot_id)
                         .await
+                        && interjection_cap.has_capacity()
+                        && interjection_warmup.is_warm(*channel_id)
                     {
                         // Get a random interjection type (skipping type 2 - Message Pondering)
-                        let mut interjection_type = rand::rng().random_range(0..=4);
+                        let mut interjection_type = rng.random_range(0..=4);
 
                         // Adjust the type number to skip over type 2
                         if interjection_type >= 2 {
@@ -3775,14 +6102,11 @@ Keep it brief and natural, as if you're just another participant in the conversa
                             channel_id, interjection_type
                         );
 
-                        // Send a typing indicator
+                        // Send a typing indicator while the message is put together
                         if let Err(e) = channel_id.broadcast_typing(&http).await {
                             error!("Failed to send typing indicator for spontaneous interjection: {:?}", e);
                         }
 
-                        // Wait a bit to simulate typing
-                        tokio::time::sleep(Duration::from_secs(2)).await;
-
                         // Send a message based on the interjection type
                         let message = match interjection_type {
                             0 => {
@@ -3792,48 +6116,44 @@ Keep it brief and natural, as if you're just another participant in the conversa
                             }
                             1 => {
                                 // Memory interjection - get a random message from the database and process it
-                                if let Some(db) = &message_db_clone {
+                                if let Some(store) = &message_store_clone {
                                     // Get recent messages for context
-                                    let context_messages =
-                                        match db_utils::get_recent_messages_with_reply_context(
-                                            db.clone(),
-                                            parsed_config.gemini_context_messages,
-                                            Some(&channel_id.to_string()),
-                                        )
-                                        .await
-                                        {
-                                            Ok(messages) => messages,
-                                            Err(e) => {
-                                                error!("Error retrieving recent messages for memory interjection: {:?}", e);
-                                                Vec::new()
-                                            }
-                                        };
-
-                                    // Context is already in correct format: (author, display_name, pronouns, content)
-                                    // Query the database for a random message with minimum length of 20 characters
-                                    let query_result = db.lock().await.call(|conn| {
-                                        let query = "SELECT content, author, display_name FROM messages WHERE length(content) >= 20 ORDER BY RANDOM() LIMIT 1";
-                                        let mut stmt = conn.prepare(query)?;
-
-                                        let rows = stmt.query_map([], |row| {
-                                            Ok((
-                                                row.get::<_, String>(0)?,
-                                                row.get::<_, String>(1)?,
-                                                row.get::<_, Option<String>>(2)?.unwrap_or_default()
-                                            ))
-                                        })?;
-
-                                        let mut result = Vec::new();
-                                        for row in rows {
-                                            result.push(row?);
-                                        }
+                                    let context_messages = recent_context(
+                                        Some(store),
+                                        message_history_clone.as_ref(),
+                                        *channel_id,
+                                        parsed_config.gemini_context_messages,
+                                    )
+                                    .await;
 
-                                        Ok::<_, rusqlite::Error>(result)
-                                    }).await;
+                                    // Pick a random remembered message, staying within the
+                                    // channel's server unless configured otherwise
+                                    let guild_id = if parsed_config.all_guilds {
+                                        Ok(None)
+                                    } else {
+                                        channel_id.to_channel(&http).await.map(|channel| {
+                                            channel.guild().map(|c| c.guild_id.to_string())
+                                        })
+                                    };
+                                    let query_result = match guild_id {
+                                        Ok(guild_id) => store
+                                            .random_dud_quotes(
+                                                None,
+                                                guild_id,
+                                                db_utils::DateRange::default(),
+                                                db_utils::DEFAULT_DUD_QUOTE_MIN_LENGTH,
+                                                1,
+                                            )
+                                            .await
+                                            .map(|quotes| quotes.into_iter().next()),
+                                        Err(e) => Err(anyhow::anyhow!(
+                                            "Couldn't look up the channel's server: {e}"
+                                        )),
+                                    };
 
                                     match query_result {
-                                        Ok(messages) => {
-                                            if let Some((content, _, _)) = messages.first() {
+                                        Ok(quote) => {
+                                            if let Some((_, _, content)) = &quote {
                                                 // If we have a Gemini client, process the message
                                                 if let Some(gemini) = &task_gemini_client {
                                                     let personality =
@@ -3862,7 +6182,10 @@ Keep it brief and natural, as if you're just another participant in the conversa
                                                         .collect();
 
                                                     match gemini
-                                                        .generate_content(&memory_prompt)
+                                                        .generate_content_for(
+                                                            &memory_prompt,
+                                                            GeminiTask::Interjection,
+                                                        )
                                                         .await
                                                     {
                                                         Ok(response) => {
@@ -3875,6 +6198,17 @@ Keep it brief and natural, as if you're just another participant in the conversa
                                                                 response
                                                             }
                                                         }
+                                                        Err(e)
+                                                            if rate_limiter::is_rate_limited(
+                                                                &e,
+                                                            ) =>
+                                                        {
+                                                            info!(
+                                                                "Skipping memory interjection: {}",
+                                                                e
+                                                            );
+                                                            String::new()
+                                                        }
                                                         Err(e) => {
                                                             error!("Error generating memory interjection: {:?}", e);
                                                             String::new() // Return empty string to skip the interjection
@@ -3908,23 +6242,13 @@ Keep it brief and natural, as if you're just another participant in the conversa
                                 // AI-like interjection using Gemini API
                                 if let Some(gemini_client) = &task_gemini_client {
                                     // Get recent messages for context
-                                    let context_messages = if let Some(db) = &message_db_clone {
-                                        match db_utils::get_recent_messages_with_reply_context(
-                                            db.clone(),
-                                            parsed_config.gemini_context_messages,
-                                            Some(&channel_id.to_string()),
-                                        )
-                                        .await
-                                        {
-                                            Ok(messages) => messages,
-                                            Err(e) => {
-                                                error!("Error retrieving recent messages for AI interjection: {:?}", e);
-                                                Vec::new()
-                                            }
-                                        }
-                                    } else {
-                                        Vec::new()
-                                    };
+                                    let context_messages = recent_context(
+                                        message_store_clone.as_ref(),
+                                        message_history_clone.as_ref(),
+                                        *channel_id,
+                                        parsed_config.gemini_context_messages,
+                                    )
+                                    .await;
 
                                     // Format context for the prompt
                                     let context_text = if !context_messages.is_empty() {
@@ -3990,7 +6314,10 @@ Keep it brief and natural, as if you're just another participant in the conversa
                                         )
                                         .collect();
 
-                                    match gemini_client.generate_content(&ai_prompt).await {
+                                    match gemini_client
+                                        .generate_content_for(&ai_prompt, GeminiTask::Interjection)
+                                        .await
+                                    {
                                         Ok(response) => {
                                             // Check if the response is "pass" - if so, don't send anything
                                             if response.trim().to_lowercase() == "pass" {
@@ -4000,6 +6327,10 @@ Keep it brief and natural, as if you're just another participant in the conversa
                                                 response
                                             }
                                         }
+                                        Err(e) if rate_limiter::is_rate_limited(&e) => {
+                                            info!("Skipping AI interjection: {}", e);
+                                            String::new()
+                                        }
                                         Err(e) => {
                                             error!("Error generating AI interjection: {:?}", e);
                                             String::new() // Return empty string to skip the interjection
@@ -4020,13 +6351,16 @@ Keep it brief and natural, as if you're just another participant in the conversa
                                         *channel_id,
                                         gemini_client,
                                         &task_multi_response_generator,
-                                        &message_db_clone,
+                                        &message_store_clone,
                                         &bot_name_clone,
                                         parsed_config.gemini_context_messages,
                                     )
                                     .await
                                     {
-                                        Ok(_) => {
+                                        Ok(sent) => {
+                                            if sent.is_some() {
+                                                interjection_cap.record();
+                                            }
                                             // The fact was sent directly by the module, so return empty string
                                             // to prevent the spontaneous interjection task from sending another message
                                             String::new()
@@ -4043,160 +6377,26 @@ Keep it brief and natural, as if you're just another participant in the conversa
                                 }
                             }
                             _ => {
-                                // Use the AI-generated news interjection
+                                // News interjection using real headlines from the feeds
                                 if let Some(gemini_client) = &task_gemini_client {
-                                    // Get recent messages for context
-                                    let context_messages = if let Some(db) = &message_db_clone {
-                                        match db_utils::get_recent_messages_with_reply_context(
-                                            db.clone(),
-                                            parsed_config.gemini_context_messages,
-                                            Some(&channel_id.to_string()),
-                                        )
-                                        .await
-                                        {
-                                            Ok(messages) => messages,
-                                            Err(e) => {
-                                                error!("Error retrieving recent messages for news interjection: {:?}", e);
-                                                Vec::new()
-                                            }
-                                        }
-                                    } else {
-                                        Vec::new()
-                                    };
-
-                                    // Format context for the prompt
-                                    let context_text = if !context_messages.is_empty() {
-                                        // Reverse the messages to get chronological order (oldest first)
-                                        let mut chronological_messages = context_messages.clone();
-                                        chronological_messages.reverse();
-
-                                        let formatted_messages: Vec<String> =
-                                            chronological_messages
-                                                .iter()
-                                                .map(|(_author, display_name, _pronouns, content, _)| {
-                                                    format!("{display_name}: {content}")
-                                                })
-                                                .collect();
-                                        formatted_messages.join("\n")
-                                    } else {
-                                        "".to_string()
-                                    };
-
-                                    // Create the news prompt
-                                    let news_prompt = String::from(r#"You are {bot_name}, a Discord bot. Share an interesting technology or weird news article link with a brief comment about why it's interesting.
-
-{context}
-
-Guidelines:
-1. Create a fictional but plausible news article link about technology or weird news (NO sports)
-2. Format as: "Article title: https://example.com/article-path"
-3. The URL must be specific and detailed (e.g., https://arstechnica.com/tech-policy/2025/06/new-ai-regulations-impact-open-source/)
-4. Never use generic URLs like https://arstechnica.com/ or https://techcrunch.com/
-5. Always include year, month, and a descriptive path in the URL
-6. Then add a brief comment (1-2 sentences) on why it's interesting or relevant to the conversation
-7. If possible, relate it to the conversation, but don't force it
-8. Don't use phrases like "Check out this article" or "You might find this interesting"
-9. NEVER include tags like "(via search)", "(via Google)", or any other source attribution
-10. If you can't think of a relevant article, respond with "pass"
-
-Example good response: "AI Creates Perfect Pizza Recipe Through Taste Simulation: https://techcrunch.com/2025/06/ai-taste-simulation-pizza This shows how AI sensory processing is advancing beyond visual and audio into taste simulation."
-
-Example bad response: "Check out this interesting article about AI and food: https://techcrunch.com/ai-food-article (via search) I thought you might find this interesting given our conversation about technology."
-
-Be creative but realistic with your article title and URL."#)
-                                        .replace("{bot_name}", &bot_name_clone)
-                                        .replace("{context}", &context_text);
-
-                                    // Prompt is already fully formed — send directly
-                                    match gemini_client.generate_content(&news_prompt).await {
-                                        Ok(response) => {
-                                            // Check if the response is "pass" - if so, don't send anything
-                                            if response.trim().to_lowercase() == "pass" {
-                                                info!("News interjection evaluation: decided to PASS - no response sent");
-                                                String::new() // Return empty string to skip the interjection
-                                            } else {
-                                                // Remove any "(via search)" or similar tags using regex
-                                                let cleaned_response = via_regex
-                                                    .replace_all(&response, "")
-                                                    .to_string();
-
-                                                // Validate the URL
-                                                if let Some(url_match) =
-                                                    url_regex.find(&cleaned_response)
-                                                {
-                                                    let url_str = url_match.as_str();
-
-                                                    // Try to parse the URL
-                                                    if let Ok(url) = url::Url::parse(url_str) {
-                                                        // Check if the URL has a proper path (not just "/")
-                                                        let path = url.path();
-                                                        if path.len() <= 1 {
-                                                            // URL doesn't have a proper path
-                                                            info!("News interjection URL validation failed: URL has no proper path: {}", url_str);
-                                                            String::new()
-                                                        } else {
-                                                            // Validate that the URL actually exists
-                                                            match news_verification::validate_url_exists(url_str).await {
-                                                                Ok((true, Some(final_url))) => {
-                                                                    // URL exists, return the cleaned response with the final URL
-                                                                    info!("URL validation successful: {} exists", final_url);
-
-                                                                    // Extract title and summary for content verification
-                                                                    if let Some((title, _)) = news_verification::extract_article_info(&cleaned_response) {
-                                                                        // Get the summary (everything after the URL)
-                                                                        let url_pos = cleaned_response.find(url_str).unwrap_or(0);
-                                                                        let summary = if url_pos + url_str.len() < cleaned_response.len() {
-                                                                            cleaned_response[(url_pos + url_str.len())..].trim().to_string()
-                                                                        } else {
-                                                                            String::new()
-                                                                        };
-
-                                                                        // Verify that the title and summary match the content at the URL
-                                                                        match news_verification::verify_news_article(gemini_client, &title, &final_url, &summary).await {
-                                                                            Ok(true) => {
-                                                                                // Title and summary match the URL content
-                                                                                info!("News verification successful: Title and summary match URL content");
-                                                                                if url_str != final_url {
-                                                                                    cleaned_response.replace(url_str, &final_url).trim().to_string()
-                                                                                } else {
-                                                                                    cleaned_response.trim().to_string()
-                                                                                }
-                                                                            },
-                                                                            _ => {
-                                                                                // Title and summary don't match the URL content or verification failed
-                                                                                info!("News interjection skipped: Title/summary mismatch with URL content");
-                                                                                String::new()
-                                                                            }
-                                                                        }
-                                                                    } else {
-                                                                        // Couldn't extract title and summary
-                                                                        info!("News interjection skipped: Couldn't extract article title and URL");
-                                                                        String::new()
-                                                                    }
-                                                                },
-                                                                Ok(_) => {
-                                                                    // URL doesn't exist or isn't HTML
-                                                                    info!("News interjection skipped: URL doesn't exist or isn't HTML: {}", url_str);
-                                                                    String::new()
-                                                                },
-                                                                Err(e) => {
-                                                                    // Error validating URL
-                                                                    error!("Error validating URL {}: {:?}", url_str, e);
-                                                                    String::new()
-                                                                }
-                                                            }
-                                                        }
-                                                    } else {
-                                                        // Invalid URL
-                                                        info!("News interjection URL validation failed: Invalid URL: {}", url_str);
-                                                        String::new()
-                                                    }
-                                                } else {
-                                                    // No URL found
-                                                    info!("News interjection URL validation failed: No URL found in response");
-                                                    String::new()
-                                                }
-                                            }
+                                    let context_text = news_interjection::recent_context(
+                                        &message_store_clone,
+                                        parsed_config.gemini_context_messages,
+                                        *channel_id,
+                                    )
+                                    .await;
+                                    match news_interjection::choose_news_interjection(
+                                        gemini_client,
+                                        &bot_name_clone,
+                                        &context_text,
+                                        &headline_cache,
+                                    )
+                                    .await
+                                    {
+                                        Ok(message) => message.unwrap_or_default(),
+                                        Err(e) if rate_limiter::is_rate_limited(&e) => {
+                                            info!("Skipping news interjection: {}", e);
+                                            String::new()
                                         }
                                         Err(e) => {
                                             error!("Error generating news interjection: {:?}", e);
@@ -4227,7 +6427,10 @@ Be creative but realistic with your article title and URL."#)
                                     Respond with ONLY a single number from 1 to 10.",
                                     message.trim()
                                 );
-                                match gemini.generate_content(&rating_prompt).await {
+                                match gemini
+                                    .generate_content_for(&rating_prompt, GeminiTask::Evaluation)
+                                    .await
+                                {
                                     Ok(rating_response) => {
                                         let rating =
                                             rating_response.trim().parse::<u8>().unwrap_or(5);
@@ -4251,23 +6454,40 @@ Be creative but realistic with your article title and URL."#)
                                     "Spontaneous interjection suppressed (below threshold {}): {}",
                                     threshold, message
                                 );
-                            } else if let Err(e) = channel_id.say(&http, message.clone()).await {
-                                error!("Failed to send spontaneous interjection: {:?}", e);
+                            } else if interjection_echoes_channel(
+                                message_store_clone.as_ref(),
+                                *channel_id,
+                                &message,
+                                interjection_similarity_threshold,
+                            )
+                            .await
+                            {
+                                // Too close to something just said; stay quiet
                             } else {
-                                info!(
-                                    "Sent spontaneous interjection (type: {}): {}",
-                                    interjection_type, message
-                                );
+                                // Take about as long as it would take to type
+                                apply_realistic_delay(&message, &http, *channel_id).await;
 
-                                // Mark the bot as the last speaker in this channel
-                                fill_silence_manager
-                                    .mark_bot_as_last_speaker(*channel_id)
-                                    .await;
+                                if let Err(e) =
+                                    send_without_pings(&http, *channel_id, message.clone()).await
+                                {
+                                    error!("Failed to send spontaneous interjection: {:?}", e);
+                                } else {
+                                    info!(
+                                        "Sent spontaneous interjection (type: {}): {}",
+                                        interjection_type, message
+                                    );
+                                    interjection_cap.record();
 
-                                // Update the last activity time for this channel
-                                fill_silence_manager
-                                    .update_activity(*channel_id, bot_id)
-                                    .await;
+                                    // Mark the bot as the last speaker in this channel
+                                    fill_silence_manager
+                                        .mark_bot_as_last_speaker(*channel_id)
+                                        .await;
+
+                                    // Update the last activity time for this channel
+                                    fill_silence_manager
+                                        .update_activity(*channel_id, bot_id)
+                                        .await;
+                                }
                             }
                         } else {
                             info!("Skipping empty spontaneous interjection");
@@ -4275,20 +6495,368 @@ Be creative but realistic with your article title and URL."#)
                     }
                 }
 
-                // Sleep for a minute before checking again
-                tokio::time::sleep(Duration::from_secs(60)).await;
+                // Wait a while before checking again, not always the same while
+                let delay = fill_silence::next_check_delay(check_interval, &mut rng);
+                debug!(
+                    "Next spontaneous interjection check in {}s",
+                    delay.as_secs()
+                );
+                tokio::time::sleep(delay).await;
             }
         });
     }
 
+    // On Ctrl+C or SIGTERM: stop taking messages, let in-flight ones finish, save where each
+    // channel was up to, then disconnect cleanly
+    let shard_manager = client.shard_manager.clone();
+    let shutdown_db = message_store.clone();
+    tokio::spawn(async move {
+        shutdown::wait_for_signal().await;
+        info!("Shutting down gracefully...");
+        shutdown_state.begin_shutdown(SHUTDOWN_GRACE_PERIOD).await;
+
+        if let Some(db) = shutdown_db {
+            let last_seen = last_seen_message.read().await.clone();
+            match db.persist_last_seen(&last_seen).await {
+                Ok(saved) => info!("Saved last seen messages for {} channels", saved),
+                Err(e) => error!("Failed to save last seen messages: {}", e),
+            }
+        }
+
+        shard_manager.shutdown_all().await;
+    });
+
     info!("Press Ctrl+C to stop the bot");
     client.start().await?;
+    info!("Bot stopped");
 
     Ok(())
 }
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+
+    #[test]
+    fn test_next_missed_messages_cursor() {
+        let page = |ids: &[u64]| -> Vec<Message> {
+            ids.iter()
+                .map(|id| {
+                    let mut msg = Message::default();
+                    msg.id = MessageId::new(*id);
+                    msg
+                })
+                .collect()
+        };
+        let cursor = MessageId::new(10);
+
+        // A full page moves the cursor to its newest message, whatever order it came in
+        assert_eq!(
+            next_missed_messages_cursor(cursor, &page(&[13, 11, 12]), 3),
+            Some(MessageId::new(13))
+        );
+        // A short or empty page means we've caught up
+        assert_eq!(
+            next_missed_messages_cursor(cursor, &page(&[11, 12]), 3),
+            None
+        );
+        assert_eq!(next_missed_messages_cursor(cursor, &[], 3), None);
+        // A page that doesn't get past the cursor can't loop forever
+        assert_eq!(
+            next_missed_messages_cursor(cursor, &page(&[8, 9, 10]), 3),
+            None
+        );
+    }
+
+    #[test]
+    fn test_format_uptime_reply() {
+        let uptime = Duration::from_secs(2 * 86400 + 3 * 3600 + 4 * 60 + 5);
+        assert_eq!(
+            Bot::format_uptime_reply(uptime, 1_700_000_000),
+            "Up for **2d 3h 4m 5s** (since <t:1700000000:F>)"
+        );
+        assert_eq!(
+            Bot::format_uptime_reply(Duration::from_secs(42), 1_700_000_000),
+            "Up for **42s** (since <t:1700000000:F>)"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_channel_search_skips_failing_guilds() {
+        let guilds = (1..=20).map(GuildId::new).collect();
+        let summary = search_guilds_for_channels(guilds, |guild_id| async move {
+            let id = guild_id.get();
+            if id % 5 == 0 {
+                Err(anyhow::anyhow!("guild {id} unavailable"))
+            } else if id % 3 == 0 {
+                Ok(vec![ChannelId::new(id * 100)])
+            } else {
+                Ok(Vec::new())
+            }
+        })
+        .await;
+
+        // Guilds 5, 10, 15 and 20 fail; 15 would have matched but the rest still come back in order
+        assert_eq!(
+            summary,
+            ChannelSearchSummary {
+                channels: [3, 6, 9, 12, 18]
+                    .into_iter()
+                    .map(|id| ChannelId::new(id * 100))
+                    .collect(),
+                searched: 20,
+                errored: 4,
+            }
+        );
+
+        let empty = search_guilds_for_channels(Vec::new(), |_| async { Ok(Vec::new()) }).await;
+        assert_eq!(empty, ChannelSearchSummary::default());
+    }
+
+    #[test]
+    fn test_command_parsing_and_quote_dud_routing() {
+        let (command, parts) = parse_command("!Quote -dud Bob -all").unwrap();
+        assert_eq!(command, "quote");
+        assert_eq!(parts, vec!["Quote", "-dud", "Bob", "-all"]);
+        assert_eq!(
+            quote_dud_args(&parts[1..]),
+            Some((Some("Bob".to_string()), true))
+        );
+
+        // -dud without a user quotes someone at random
+        let (_, parts) = parse_command("!quote -dud").unwrap();
+        assert_eq!(quote_dud_args(&parts[1..]), Some((None, false)));
+        let (_, parts) = parse_command("!quote -dud -all").unwrap();
+        assert_eq!(quote_dud_args(&parts[1..]), Some((None, true)));
+
+        // Anything else is a regular !quote
+        let (_, parts) = parse_command("!quote -show spoon").unwrap();
+        assert_eq!(quote_dud_args(&parts[1..]), None);
+
+        assert_eq!(parse_command("!"), None);
+        assert_eq!(parse_command("hello !there"), None);
+    }
+
+    #[test]
+    fn test_gateway_intents() {
+        let base = GatewayIntents::GUILD_MESSAGES
+            | GatewayIntents::MESSAGE_CONTENT
+            | GatewayIntents::GUILDS;
+        assert_eq!(gateway_intents(&[]), base);
+        let features = config::parse_reaction_features(Some("reaction_quote"));
+        assert_eq!(
+            gateway_intents(&features),
+            base | GatewayIntents::GUILD_MESSAGE_REACTIONS
+        );
+        // Only unknown features configured: nothing needs reactions
+        let features = config::parse_reaction_features(Some("made_up"));
+        assert_eq!(gateway_intents(&features), base);
+    }
+
+    #[test]
+    fn test_route_command() {
+        let mut custom = HashMap::new();
+        custom.insert("imagine".to_string(), "No imagining here".to_string());
+        custom.insert("tng".to_string(), "Engage!".to_string());
+        custom.insert("quote".to_string(), "Shadowed".to_string());
+        let sources = ["ds9", "tng"];
+        let route = |text: &str, imagine_enabled: bool| {
+            let (command, _) = parse_command(text).unwrap();
+            match route_command(&command, imagine_enabled, sources, &custom) {
+                CommandRoute::Custom(response) => Err(response.to_string()),
+                route => Ok(route),
+            }
+        };
+
+        // Aliases and mixed case land on the same command
+        assert_eq!(route("!Hello", false), Ok(CommandRoute::Hello));
+        assert_eq!(route("!seen bob", false), Ok(CommandRoute::LastSeen));
+        assert_eq!(route("!lastseen bob", false), Ok(CommandRoute::LastSeen));
+        assert_eq!(route("!pick a b", false), Ok(CommandRoute::Choose));
+        assert_eq!(route("!DEAD someone", false), Ok(CommandRoute::AliveOrDead));
+        assert_eq!(route("!forgetme", false), Ok(CommandRoute::Forget));
+        assert_eq!(route("!morbotron", false), Ok(CommandRoute::Screenshot));
+        assert_eq!(
+            route("!interject-stats", false),
+            Ok(CommandRoute::InterjectStats)
+        );
+
+        // -dud is handled inside !quote, which a custom command can't shadow
+        assert_eq!(route("!quote -dud bob", false), Ok(CommandRoute::Quote));
+
+        // !imagine only routes to image generation when some channel allows it
+        assert_eq!(route("!imagine a cat", true), Ok(CommandRoute::Imagine));
+        assert_eq!(
+            route("!imagine a cat", false),
+            Err("No imagining here".to_string())
+        );
+
+        // Screenshot sources come before custom commands with the same name
+        assert_eq!(
+            route("!TNG picard", false),
+            Ok(CommandRoute::ScreenshotSource(1))
+        );
+        assert_eq!(route("!ds9", false), Ok(CommandRoute::ScreenshotSource(0)));
+
+        assert_eq!(route("!whatever", false), Ok(CommandRoute::Unknown));
+    }
+
+    #[test]
+    fn test_quote_args() {
+        fn args(text: &str) -> Vec<&str> {
+            text.split_whitespace().collect()
+        }
+        let pair = |term: Option<&str>, show: Option<&str>| {
+            (term.map(str::to_string), show.map(str::to_string))
+        };
+
+        assert_eq!(quote_args(&args("")), pair(None, None));
+        assert_eq!(
+            quote_args(&args("deadline looming -show west wing")),
+            pair(Some("deadline looming"), Some("west wing"))
+        );
+        // Date flags take their date with them, wherever they go
+        assert_eq!(
+            quote_args(&args("-year 2021 deadline -before 2021-06-01")),
+            pair(Some("deadline"), None)
+        );
+        assert_eq!(
+            quote_args(&args("-show simpsons -after 2020-01-01 donut")),
+            pair(Some("donut"), Some("simpsons"))
+        );
+        let (_, parts) = parse_command("!quote -dud bob -year 2021").unwrap();
+        assert_eq!(
+            quote_dud_args(&parts[1..]),
+            Some((Some("bob".to_string()), false))
+        );
+        // -count takes its number with it too
+        assert_eq!(
+            quote_args(&args("donut -count 3 -show simpsons")),
+            pair(Some("donut"), Some("simpsons"))
+        );
+    }
+
+    #[test]
+    fn test_quote_count() {
+        let count = |text: &str| quote_count(&text.split_whitespace().collect::<Vec<_>>());
+        assert_eq!(count(""), 1);
+        assert_eq!(count("-dud bob"), 1);
+        assert_eq!(count("-count 3"), 3);
+        assert_eq!(count("-dud -count 2 -all"), 2);
+        // Clamped to what one command may post
+        assert_eq!(count("-count 0"), 1);
+        assert_eq!(count("-count 50"), MAX_QUOTE_COUNT);
+        assert_eq!(count("-count 99999999999999999999"), 1);
+        // Missing or nonsense values fall back to one
+        assert_eq!(count("-count"), 1);
+        assert_eq!(count("-count lots"), 1);
+        assert_eq!(count("-count -3"), 1);
+    }
+
+    #[test]
+    fn test_dud_name_note() {
+        assert_eq!(
+            dud_name_note("alice", &db_utils::NameMatch::Exact("alice".to_string())),
+            None
+        );
+        assert_eq!(
+            dud_name_note("al", &db_utils::NameMatch::Partial("Randal".to_string())),
+            Some("(Nobody goes by al, so here's Randal)".to_string())
+        );
+    }
+
+    #[test]
+    fn test_format_dud_quotes() {
+        let quote = |author: &str, display: &str, content: &str| {
+            (author.to_string(), display.to_string(), content.to_string())
+        };
+        let lines = format_dud_quotes(&[
+            quote("bob", "Bobby", "first thing"),
+            quote("carol", "", "second thing"),
+        ]);
+        assert_eq!(lines, vec!["<Bobby> first thing", "<carol> second thing"]);
+
+        // Quotes that would go over Discord's limit together are posted separately
+        let long = "word ".repeat(300);
+        let lines = format_dud_quotes(&[
+            quote("a", "", &long),
+            quote("b", "", &long),
+            quote("c", "", "short"),
+        ]);
+        let messages = streaming_reply::pack_lines(&lines, streaming_reply::DISCORD_MESSAGE_LIMIT);
+        assert_eq!(messages.len(), 2);
+        assert!(messages[0].starts_with("<a>"));
+        assert!(messages[1].starts_with("<b>") && messages[1].ends_with("<c> short"));
+        assert!(messages
+            .iter()
+            .all(|m| m.chars().count() <= streaming_reply::DISCORD_MESSAGE_LIMIT));
+    }
+
+    #[test]
+    fn test_bare_mention_gets_a_prompt() {
+        let bot_id = UserId::new(1234);
+        assert_eq!(mention_action("<@1234>", bot_id), MentionAction::Prompt);
+        assert_eq!(
+            mention_action("  <@!1234>  ", bot_id),
+            MentionAction::Prompt
+        );
+
+        // A mention with text goes to Gemini, minus the mention
+        assert_eq!(
+            mention_action("<@1234> what's the best MST3K episode?", bot_id),
+            MentionAction::Ask("what's the best MST3K episode?".to_string())
+        );
+        assert_eq!(
+            mention_action("hey <@!1234>", bot_id),
+            MentionAction::Ask("hey".to_string())
+        );
+
+        // Pinged by a reply with no text of its own
+        assert_eq!(mention_action("", bot_id), MentionAction::Ignore);
+    }
+
+    #[tokio::test]
+    async fn test_rate_limited_interjection_is_silent_but_reply_is_not() {
+        let persistence = std::env::temp_dir().join("crow_rate_limit_notice_test.json");
+        let limiter = rate_limiter::RateLimiter::new_with_persistence(
+            0,
+            0,
+            persistence.display().to_string(),
+        );
+        let error = limiter.check().await.unwrap_err();
+        assert!(rate_limiter::is_rate_limited(&error));
+
+        let message = rate_limiter::DEFAULT_RATE_LIMIT_REPLY;
+        assert_eq!(Bot::ai_error_notice(&error, false, message), None);
+        assert_eq!(
+            Bot::ai_error_notice(&error, true, message).as_deref(),
+            Some("I'm a bit overloaded right now, try again in a moment.")
+        );
+
+        // An empty configured message keeps direct replies quiet too
+        assert_eq!(Bot::ai_error_notice(&error, true, ""), None);
+
+        // Other failures keep their existing handling
+        let silent = anyhow::anyhow!("SILENT_ERROR: safety filters");
+        assert_eq!(Bot::ai_error_notice(&silent, true, message), None);
+        let other = anyhow::anyhow!("Gemini API error: boom");
+        assert!(Bot::ai_error_notice(&other, true, message)
+            .unwrap()
+            .contains("trouble thinking"));
+
+        // A stuck call says it took too long, but only to someone waiting on a reply
+        let stuck = async {
+            tokio::time::sleep(Duration::from_secs(5)).await;
+            Ok(())
+        };
+        let timed_out = gemini_api::with_timeout(Duration::from_millis(10), stuck)
+            .await
+            .unwrap_err();
+        assert!(Bot::ai_error_notice(&timed_out, true, message)
+            .unwrap()
+            .contains("too long"));
+        assert_eq!(Bot::ai_error_notice(&timed_out, false, message), None);
+    }
 
     #[test]
     fn test_quiet_channels_configuration() {
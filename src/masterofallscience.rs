@@ -1,14 +1,10 @@
-#![allow(dead_code)] // masterofallscience.com has been shut down; keeping code in case it returns
-use crate::gemini_api::GeminiClient;
+use crate::http_client;
+use crate::screenshot::{self, Frame, Screenshot, ScreenshotProvider, ScreenshotSite, SearchState};
 use crate::text_formatting;
 use anyhow::{anyhow, Result};
-use rand::seq::IndexedRandom;
 use reqwest::Client as HttpClient;
 use serde::Deserialize;
-use serenity::all::Http;
-use serenity::model::channel::Message;
-use std::sync::RwLock;
-use std::time::Duration;
+use std::borrow::Cow;
 use tracing::{error, info};
 
 // API endpoints
@@ -16,6 +12,19 @@ const MASTEROFALLSCIENCE_BASE_URL: &str = "https://masterofallscience.com/api/se
 const MASTEROFALLSCIENCE_CAPTION_URL: &str = "https://masterofallscience.com/api/caption";
 const MASTEROFALLSCIENCE_IMAGE_URL: &str = "https://masterofallscience.com/img";
 
+const MASTEROFALLSCIENCE_SITE: ScreenshotSite = ScreenshotSite {
+    base_url: Cow::Borrowed("https://masterofallscience.com"),
+    gif_font: "fr",
+    upload_name: Cow::Borrowed("masterofallscience"),
+    show: Cow::Borrowed("Rick and Morty"),
+    no_random_message: Cow::Borrowed("Couldn't find any Rick and Morty screenshots. Wubba lubba dub dub!"),
+    random_error_message: Cow::Borrowed("Error getting Rick and Morty screenshot. Wubba lubba dub dub!"),
+    search_error_message: Cow::Borrowed("Error searching Master of All Science. Wubba lubba dub dub!"),
+    options: false,
+    // masterofallscience.com now redirects to frinkiac.com - the service is gone
+    shut_down_notice: Some("The Master of All Science (Rick and Morty) service has been shut down. Try `!frinkiac` for Simpsons or `!morbotron` for Futurama instead."),
+};
+
 // Common search terms for random screenshots when no query is provided
 const RANDOM_SEARCH_TERMS: &[&str] = &[
     "wubba lubba",
@@ -172,326 +181,19 @@ struct MasterOfAllScienceSearchResult {
     timestamp: u64,
 }
 
-#[derive(Debug, Deserialize)]
-struct MasterOfAllScienceCaptionResult {
-    #[serde(rename = "Episode")]
-    episode: MasterOfAllScienceEpisode,
-    #[serde(rename = "Frame")]
-    _frame: MasterOfAllScienceFrame,
-    #[serde(rename = "Subtitles")]
-    subtitles: Vec<MasterOfAllScienceSubtitle>,
-    #[serde(rename = "Nearby")]
-    _nearby: Vec<MasterOfAllScienceNearbyFrame>,
-}
-
-#[derive(Debug, Deserialize)]
-struct MasterOfAllScienceFrame {
-    #[serde(rename = "Id")]
-    _id: u64,
-    #[serde(rename = "Episode")]
-    _episode: String,
-    #[serde(rename = "Timestamp")]
-    _timestamp: u64,
-}
-
-#[derive(Debug, Deserialize)]
-struct MasterOfAllScienceNearbyFrame {
-    #[serde(rename = "Id")]
-    _id: u64,
-    #[serde(rename = "Episode")]
-    _episode: String,
-    #[serde(rename = "Timestamp")]
-    _timestamp: u64,
-}
-
-#[derive(Debug, Deserialize)]
-struct MasterOfAllScienceSubtitle {
-    #[serde(rename = "Id")]
-    _id: u64,
-    #[serde(rename = "RepresentativeTimestamp")]
-    representative_timestamp: u64,
-    #[serde(rename = "Episode")]
-    _episode: String,
-    #[serde(rename = "StartTimestamp")]
-    start_timestamp: u64,
-    #[serde(rename = "EndTimestamp")]
-    end_timestamp: u64,
-    #[serde(rename = "Content")]
-    content: String,
-    #[serde(rename = "Language")]
-    _language: String,
-}
-
-#[derive(Debug, Deserialize)]
-struct MasterOfAllScienceEpisode {
-    #[serde(rename = "Id")]
-    _id: u64,
-    #[serde(rename = "Key")]
-    _key: String,
-    #[serde(rename = "Season")]
-    season: u32,
-    #[serde(rename = "EpisodeNumber")]
-    episode_number: u32,
-    #[serde(rename = "Title")]
-    title: String,
-    #[serde(rename = "Director")]
-    _director: String,
-    #[serde(rename = "Writer")]
-    _writer: String,
-    #[serde(rename = "OriginalAirDate")]
-    _original_air_date: String,
-    #[serde(rename = "WikiLink")]
-    _wiki_link: String,
-}
-
-// Result struct for MasterOfAllScience searches
-#[derive(Debug, Clone)]
-pub struct MasterOfAllScienceResult {
-    pub _episode: String,
-    pub season: u32,
-    pub episode_number: u32,
-    pub episode_title: String,
-    pub _timestamp: String,
-    pub image_url: String,
-    pub caption: String,
-    pub start_timestamp: u64,
-    pub end_timestamp: u64,
-    pub subtitles: Vec<crate::frinkiac::TimedSubtitle>,
-    pub gif_url: Option<String>,
-}
-
 pub struct MasterOfAllScienceClient {
     http_client: HttpClient,
-    last_query: RwLock<Option<String>>,
-    last_results: RwLock<Vec<MasterOfAllScienceSearchResult>>,
-    current_index: RwLock<usize>,
+    search_state: SearchState,
 }
 
 impl MasterOfAllScienceClient {
     pub fn new() -> Self {
         info!("Creating MasterOfAllScience client");
 
-        // Create HTTP client with reasonable timeouts
-        let http_client = HttpClient::builder()
-            .timeout(Duration::from_secs(10))
-            .build()
-            .expect("Failed to create HTTP client");
-
         Self {
-            http_client,
-            last_query: RwLock::new(None),
-            last_results: RwLock::new(Vec::new()),
-            current_index: RwLock::new(0),
-        }
-    }
-
-    // Get a random screenshot
-    pub async fn random(&self) -> Result<Option<MasterOfAllScienceResult>> {
-        info!("Getting random MasterOfAllScience screenshot");
-
-        // Choose a random search term
-        let random_term = RANDOM_SEARCH_TERMS
-            .choose(&mut rand::rng())
-            .ok_or_else(|| anyhow!("Failed to choose random search term"))?;
-
-        info!("Using random search term: {}", random_term);
-
-        // Search for the random term
-        let results = self.search_api(random_term).await?;
-        if !results.is_empty() {
-            // Choose a random result
-            let random_result = results
-                .choose(&mut rand::rng())
-                .ok_or_else(|| anyhow!("Failed to choose random result"))?;
-
-            return self
-                .get_caption_for_frame(&random_result.episode, random_result.timestamp)
-                .await;
-        }
-
-        // If no results, return None
-        Ok(None)
-    }
-
-    // Search for a screenshot matching the query
-    pub async fn search(&self, query: &str) -> Result<Option<MasterOfAllScienceResult>> {
-        info!("MasterOfAllScience search for: {}", query);
-
-        // Check if this is the same query as last time
-        let same_query;
-        let result_to_use;
-
-        {
-            let last_query = self.last_query.read().unwrap();
-            let last_results = self.last_results.read().unwrap();
-            let last_results_len = last_results.len();
-
-            if let Some(last_q) = last_query.as_ref() {
-                if last_q == query && last_results_len > 0 {
-                    // Same query, increment the index to cycle through results
-                    let mut index = *self.current_index.read().unwrap() + 1;
-                    if index >= last_results_len {
-                        index = 0; // Wrap around to the beginning
-                    }
-
-                    // Update the current index
-                    *self.current_index.write().unwrap() = index;
-                    info!(
-                        "Same query as last time, using result {} of {}",
-                        index + 1,
-                        last_results_len
-                    );
-
-                    // Get the result at the current index
-                    result_to_use = Some((
-                        last_results[index].episode.clone(),
-                        last_results[index].timestamp,
-                    ));
-                    same_query = true;
-                } else {
-                    same_query = false;
-                    result_to_use = None;
-                }
-            } else {
-                same_query = false;
-                result_to_use = None;
-            }
-        }
-
-        // If it's the same query, use the result we found
-        if same_query {
-            if let Some((episode, timestamp)) = result_to_use {
-                return self.get_caption_for_frame(&episode, timestamp).await;
-            }
-        }
-
-        // New query, reset the index and fetch new results
-        *self.current_index.write().unwrap() = 0;
-
-        // Try a direct search first
-        let results = self.search_api(query).await?;
-        if !results.is_empty() {
-            // Store the query and results for next time
-            *self.last_query.write().unwrap() = Some(query.to_string());
-            *self.last_results.write().unwrap() = results.clone();
-
-            info!("Found {} results with direct search", results.len());
-            let first_result = &results[0];
-            return self
-                .get_caption_for_frame(&first_result.episode, first_result.timestamp)
-                .await;
-        }
-
-        // If direct search fails and it's a multi-word query, try with quotes
-        if query.contains(' ') {
-            let quoted_query = format!("\"{query}\"");
-            let results = self.search_api(&quoted_query).await?;
-            if !results.is_empty() {
-                // Store the query and results for next time
-                *self.last_query.write().unwrap() = Some(query.to_string());
-                *self.last_results.write().unwrap() = results.clone();
-
-                info!("Found {} results with quoted search", results.len());
-                let first_result = &results[0];
-                return self
-                    .get_caption_for_frame(&first_result.episode, first_result.timestamp)
-                    .await;
-            }
-        }
-
-        // If all else fails, return a random result
-        info!(
-            "No results found for query: {}, returning random result",
-            query
-        );
-        // Use Box::pin to avoid recursion issues
-        Box::pin(self.random()).await
-    }
-
-    async fn get_caption_for_frame(
-        &self,
-        episode: &str,
-        timestamp: u64,
-    ) -> Result<Option<MasterOfAllScienceResult>> {
-        // Use the correct URL format: /api/caption?e=S01E02&t=242434
-        let caption_url = format!("{MASTEROFALLSCIENCE_CAPTION_URL}?e={episode}&t={timestamp}");
-        info!("Using caption URL: {}", caption_url);
-
-        // Make the caption request
-        let caption_response = self
-            .http_client
-            .get(&caption_url)
-            .send()
-            .await
-            .map_err(|e| anyhow!("Failed to get MasterOfAllScience caption: {}", e))?;
-
-        let status = caption_response.status();
-        info!("Caption API response status: {}", status);
-
-        if !status.is_success() {
-            // If the request failed, return a random result instead of an error
-            info!(
-                "Caption request failed with status: {}, returning random result",
-                status
-            );
-            return Box::pin(self.random()).await;
+            http_client: http_client::shared(),
+            search_state: SearchState::default(),
         }
-
-        // Parse the caption result
-        let caption_result: MasterOfAllScienceCaptionResult = caption_response
-            .json()
-            .await
-            .map_err(|e| anyhow!("Failed to parse MasterOfAllScience caption: {}", e))?;
-
-        // If no subtitles, return None
-        if caption_result.subtitles.is_empty() {
-            return Ok(None);
-        }
-
-        // Extract the caption text
-        let caption = caption_result
-            .subtitles
-            .iter()
-            .map(|s| s.content.clone())
-            .collect::<Vec<String>>()
-            .join("\n");
-
-        let timed_subs: Vec<crate::frinkiac::TimedSubtitle> = caption_result
-            .subtitles
-            .iter()
-            .map(|s| crate::frinkiac::TimedSubtitle {
-                text: s.content.clone(),
-                start: s.start_timestamp,
-                end: s.end_timestamp,
-                representative_ts: s.representative_timestamp,
-            })
-            .collect();
-
-        // Build the image URL
-        let image_url = format!("{MASTEROFALLSCIENCE_IMAGE_URL}/{episode}/{timestamp}.jpg");
-
-        // Extract episode information
-        let episode_title = caption_result.episode.title.clone();
-        let season = caption_result.episode.season;
-        let episode_number = caption_result.episode.episode_number;
-
-        let start_ts = timed_subs.first().map(|s| s.start).unwrap_or(timestamp);
-        let end_ts = timed_subs.last().map(|s| s.end).unwrap_or(timestamp + 4000);
-
-        // Return the result
-        Ok(Some(MasterOfAllScienceResult {
-            _episode: episode.to_string(),
-            season,
-            episode_number,
-            episode_title,
-            _timestamp: timestamp.to_string(),
-            image_url,
-            caption: format_caption(&caption),
-            start_timestamp: start_ts,
-            end_timestamp: end_ts,
-            subtitles: timed_subs,
-            gif_url: None,
-        }))
     }
 
     // Internal method to search the API and return the raw results
@@ -554,34 +256,92 @@ impl MasterOfAllScienceClient {
     }
 }
 
-// Format a caption to proper sentence case and separate different speakers
-fn format_caption(caption: &str) -> String {
-    text_formatting::format_caption(caption, text_formatting::RICK_AND_MORTY_PROPER_NOUNS)
-}
+impl ScreenshotProvider for MasterOfAllScienceClient {
+    fn name(&self) -> &str {
+        "Master of All Science"
+    }
+
+    fn site(&self) -> &ScreenshotSite {
+        &MASTEROFALLSCIENCE_SITE
+    }
+
+    // Frames in the API's own order
+    async fn search(&self, query: &str) -> Result<Vec<Frame>> {
+        let results = self.search_api(query).await?;
+        Ok(results
+            .into_iter()
+            .enumerate()
+            .map(|(i, result)| Frame {
+                episode: result.episode,
+                timestamp: result.timestamp,
+                api_rank: i + 1,
+                note: None,
+            })
+            .collect())
+    }
+
+    fn caption_url(&self, frame: &Frame) -> String {
+        // Use the correct URL format: /api/caption?e=S01E02&t=242434
+        format!(
+            "{MASTEROFALLSCIENCE_CAPTION_URL}?e={}&t={}",
+            frame.episode, frame.timestamp
+        )
+    }
 
-// Format a MasterOfAllScience result for display
-pub fn format_masterofallscience_result(result: &MasterOfAllScienceResult) -> String {
-    let season = result.season;
-    let episode_number = result.episode_number;
-    let episode_title = &result.episode_title;
-    let media_url = result.gif_url.as_deref().unwrap_or(&result.image_url);
-    if result.gif_url.is_some() {
-        format!("**S{season:02}E{episode_number:02} - {episode_title}**\n{media_url}")
-    } else {
-        let caption = &result.caption;
-        format!("**S{season:02}E{episode_number:02} - {episode_title}**\n{media_url}\n\n{caption}")
+    async fn caption(&self, frame: &Frame) -> Result<Option<Screenshot>> {
+        let caption_url = self.caption_url(frame);
+        info!("Using caption URL: {}", caption_url);
+
+        // Make the caption request
+        let caption_response = self
+            .http_client
+            .get(&caption_url)
+            .send()
+            .await
+            .map_err(|e| anyhow!("Failed to get MasterOfAllScience caption: {}", e))?;
+
+        let status = caption_response.status();
+        info!("Caption API response status: {}", status);
+
+        if !status.is_success() {
+            // The search falls back to a random frame rather than failing
+            info!("Caption request failed with status: {}", status);
+            return Ok(None);
+        }
+
+        // Parse the caption result
+        let caption_result: serde_json::Value = caption_response
+            .json()
+            .await
+            .map_err(|e| anyhow!("Failed to parse MasterOfAllScience caption: {}", e))?;
+
+        // Build the image URL
+        let image_url = format!(
+            "{MASTEROFALLSCIENCE_IMAGE_URL}/{}/{}.jpg",
+            frame.episode, frame.timestamp
+        );
+        let mut result =
+            screenshot::parse_caption(&caption_result, &frame.episode, frame.timestamp, image_url)?;
+
+        // If no subtitles, return None
+        if result.subtitles.is_empty() {
+            return Ok(None);
+        }
+
+        result.caption = format_caption(&result.subtitle_text("\n"));
+        Ok(Some(result))
+    }
+
+    fn random_terms(&self) -> &[&str] {
+        RANDOM_SEARCH_TERMS
+    }
+
+    fn search_state(&self) -> &SearchState {
+        &self.search_state
     }
 }
 
-// This function will be called from main.rs to handle the !masterofallscience command
-pub async fn handle_masterofallscience_command(
-    http: &Http,
-    msg: &Message,
-    _args: Option<String>,
-    _masterofallscience_client: &MasterOfAllScienceClient,
-    _gemini_client: Option<&GeminiClient>,
-) -> Result<()> {
-    // masterofallscience.com now redirects to frinkiac.com - the service is gone
-    let _ = msg.channel_id.say(http, "The Master of All Science (Rick and Morty) service has been shut down. Try `!frinkiac` for Simpsons or `!morbotron` for Futurama instead.").await;
-    Ok(())
+// Format a caption to proper sentence case and separate different speakers
+fn format_caption(caption: &str) -> String {
+    text_formatting::format_caption(caption, text_formatting::RICK_AND_MORTY_PROPER_NOUNS)
 }
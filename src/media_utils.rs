@@ -1,6 +1,7 @@
 use anyhow::Result;
 use base64::Engine;
 use regex::Regex;
+use serde::{Deserialize, Serialize};
 use serenity::model::channel::Message;
 use tracing::{error, info};
 
@@ -11,6 +12,16 @@ pub struct MediaItem {
     pub data: String, // base64-encoded
 }
 
+impl MediaItem {
+    /// A media item holding the given raw bytes
+    pub fn from_bytes(mime_type: &str, bytes: &[u8]) -> Self {
+        Self {
+            mime_type: mime_type.to_string(),
+            data: base64::engine::general_purpose::STANDARD.encode(bytes),
+        }
+    }
+}
+
 /// A YouTube URL found in message text
 #[derive(Debug, Clone)]
 pub struct YouTubeUrl {
@@ -19,6 +30,9 @@ pub struct YouTubeUrl {
 
 const MAX_INLINE_SIZE: usize = 15_000_000; // ~15MB before base64
 
+// Attachments sent with a single message unless MEDIA_MAX_ATTACHMENTS says otherwise
+const DEFAULT_MAX_ATTACHMENTS: usize = 4;
+
 const IMAGE_TYPES: &[&str] = &["image/png", "image/jpeg", "image/webp", "image/gif"];
 const VIDEO_TYPES: &[&str] = &[
     "video/mp4",
@@ -28,85 +42,80 @@ const VIDEO_TYPES: &[&str] = &[
     "video/x-flv",
 ];
 
-/// Extract downloadable image/video attachments from a message
-pub async fn extract_media_from_message(msg: &Message) -> Vec<MediaItem> {
+/// How many attachments a message can pass to the AI, and how large each may be
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MediaLimits {
+    pub max_items: usize,
+    pub max_bytes: usize,
+}
+
+impl Default for MediaLimits {
+    fn default() -> Self {
+        Self {
+            max_items: DEFAULT_MAX_ATTACHMENTS,
+            max_bytes: MAX_INLINE_SIZE,
+        }
+    }
+}
+
+// Whether an attachment can go to the AI inline: an image or video no larger than the limit
+fn is_inline_media(content_type: &str, size: usize, max_bytes: usize) -> bool {
+    let is_image = IMAGE_TYPES.iter().any(|t| content_type.starts_with(t));
+    let is_video = VIDEO_TYPES.iter().any(|t| content_type.starts_with(t));
+    (is_image || is_video) && size <= max_bytes
+}
+
+/// Download the image/video attachments of a message and of the message it replies to,
+/// up to the limits. Other attachments are skipped.
+pub async fn extract_media_from_message(msg: &Message, limits: MediaLimits) -> Vec<MediaItem> {
     let mut items = Vec::new();
 
+    // The message's own attachments, then those of the message it replies to
+    let mut attachments = Vec::new();
     for attachment in &msg.attachments {
-        let content_type = attachment.content_type.as_deref().unwrap_or("");
-
-        let is_image = IMAGE_TYPES.iter().any(|t| content_type.starts_with(t));
-        let is_video = VIDEO_TYPES.iter().any(|t| content_type.starts_with(t));
+        attachments.push((attachment, "message"));
+    }
+    if let Some(ref referenced) = msg.referenced_message {
+        for attachment in &referenced.attachments {
+            attachments.push((attachment, "referenced message"));
+        }
+    }
 
-        if !is_image && !is_video {
-            continue;
+    for (attachment, source) in attachments {
+        if items.len() >= limits.max_items {
+            info!(
+                "Skipping remaining attachments - limit of {} reached",
+                limits.max_items
+            );
+            break;
         }
 
-        if attachment.size as usize > MAX_INLINE_SIZE {
+        let content_type = attachment.content_type.as_deref().unwrap_or("");
+        if !is_inline_media(content_type, attachment.size as usize, limits.max_bytes) {
             info!(
-                "Skipping attachment {} ({} bytes) - too large for inline",
-                attachment.filename, attachment.size
+                "Skipping attachment {} ({}, {} bytes) - not an image or video, or too large",
+                attachment.filename, content_type, attachment.size
             );
             continue;
         }
 
-        match download_and_encode(&attachment.url).await {
-            Ok(data) => {
-                items.push(MediaItem {
-                    mime_type: content_type.to_string(),
-                    data,
-                });
+        match download(&attachment.url, limits.max_bytes).await {
+            Ok(bytes) => {
+                items.push(MediaItem::from_bytes(content_type, &bytes));
                 info!(
-                    "Extracted media: {} ({}, {} bytes)",
-                    attachment.filename, content_type, attachment.size
+                    "Extracted media from {}: {} ({}, {} bytes)",
+                    source, attachment.filename, content_type, attachment.size
                 );
             }
             Err(e) => {
                 error!(
-                    "Failed to download attachment {}: {:?}",
-                    attachment.filename, e
+                    "Failed to download attachment {} from {}: {:?}",
+                    attachment.filename, source, e
                 );
             }
         }
     }
 
-    // Also check referenced message (reply-to) for attachments
-    if let Some(ref referenced) = msg.referenced_message {
-        for attachment in &referenced.attachments {
-            let content_type = attachment.content_type.as_deref().unwrap_or("");
-
-            let is_image = IMAGE_TYPES.iter().any(|t| content_type.starts_with(t));
-            let is_video = VIDEO_TYPES.iter().any(|t| content_type.starts_with(t));
-
-            if !is_image && !is_video {
-                continue;
-            }
-
-            if attachment.size as usize > MAX_INLINE_SIZE {
-                continue;
-            }
-
-            match download_and_encode(&attachment.url).await {
-                Ok(data) => {
-                    items.push(MediaItem {
-                        mime_type: content_type.to_string(),
-                        data,
-                    });
-                    info!(
-                        "Extracted media from referenced message: {} ({})",
-                        attachment.filename, content_type
-                    );
-                }
-                Err(e) => {
-                    error!(
-                        "Failed to download referenced attachment {}: {:?}",
-                        attachment.filename, e
-                    );
-                }
-            }
-        }
-    }
-
     items
 }
 
@@ -145,6 +154,125 @@ pub fn describe_attachments(msg: &Message) -> String {
     tags.join(" ")
 }
 
+// Links in message text, stopping at whitespace and the brackets of attachment tags
+const LINK_PATTERN: &str = r"https?://[^\s<>\[\]]+";
+// Most links a single message keeps
+const MAX_STORED_LINKS: usize = 5;
+
+/// An attachment as stored with a message
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StoredAttachment {
+    pub filename: String,
+    pub url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+}
+
+/// Attachments and links from a message, saved as JSON next to its text so context can
+/// show that media was shared even when the text is empty
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct StoredMedia {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attachments: Vec<StoredAttachment>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub links: Vec<String>,
+}
+
+impl StoredMedia {
+    /// The attachments of a message, plus links in its text and embeds
+    pub fn from_message(msg: &Message) -> Self {
+        let attachments: Vec<StoredAttachment> = msg
+            .attachments
+            .iter()
+            .map(|attachment| StoredAttachment {
+                filename: attachment.filename.clone(),
+                url: attachment.url.clone(),
+                content_type: attachment.content_type.clone(),
+            })
+            .collect();
+
+        let mut links = Vec::new();
+        let embed_urls = msg.embeds.iter().filter_map(|embed| embed.url.clone());
+        for link in extract_links(&msg.content).into_iter().chain(embed_urls) {
+            let is_attachment = attachments.iter().any(|a| a.url == link);
+            if !is_attachment && !links.contains(&link) && links.len() < MAX_STORED_LINKS {
+                links.push(link);
+            }
+        }
+
+        StoredMedia { attachments, links }
+    }
+
+    /// JSON for the messages table, or None when there's nothing to store
+    pub fn to_json(&self) -> Option<String> {
+        if self.attachments.is_empty() && self.links.is_empty() {
+            return None;
+        }
+        serde_json::to_string(self).ok()
+    }
+
+    /// Read the JSON stored with a message; anything unreadable counts as no media
+    pub fn from_json(json: Option<&str>) -> Self {
+        json.and_then(|json| serde_json::from_str(json).ok())
+            .unwrap_or_default()
+    }
+
+    /// Short markers for context, like "[image]" or "[link: example.com]". Attachments
+    /// already tagged in the text (see [`describe_attachments`]) aren't marked again.
+    pub fn markers(&self, content: &str) -> Vec<String> {
+        let mut markers = Vec::new();
+        for attachment in &self.attachments {
+            if content.contains(&attachment.url) {
+                continue;
+            }
+            let content_type = attachment.content_type.as_deref().unwrap_or("unknown");
+            if IMAGE_TYPES.iter().any(|t| content_type.starts_with(t)) {
+                markers.push("[image]".to_string());
+            } else if VIDEO_TYPES.iter().any(|t| content_type.starts_with(t)) {
+                markers.push("[video]".to_string());
+            } else {
+                markers.push(format!("[file: {}]", attachment.filename));
+            }
+        }
+        for link in &self.links {
+            let host = url::Url::parse(link).ok().and_then(|url| {
+                url.host_str()
+                    .map(|host| host.trim_start_matches("www.").to_string())
+            });
+            if let Some(host) = host {
+                markers.push(format!("[link: {host}]"));
+            }
+        }
+        markers
+    }
+}
+
+/// Every http(s) link in some text, in order, with trailing punctuation dropped
+pub fn extract_links(text: &str) -> Vec<String> {
+    let re = Regex::new(LINK_PATTERN).unwrap();
+    re.find_iter(text)
+        .map(|m| {
+            m.as_str()
+                .trim_end_matches(['.', ',', '!', '?', ')', '\'', '"', ':', ';'])
+                .to_string()
+        })
+        .collect()
+}
+
+/// A stored message's text with markers for its media appended, for conversation context
+pub fn with_media_markers(content: &str, media_json: Option<&str>) -> String {
+    let markers = StoredMedia::from_json(media_json).markers(content);
+    if markers.is_empty() {
+        return content.to_string();
+    }
+    let markers = markers.join(" ");
+    if content.trim().is_empty() {
+        markers
+    } else {
+        format!("{content} {markers}")
+    }
+}
+
 /// Extract image/video URLs from context text, returning media metadata.
 /// Returns up to `max_items` most recent items (from end of text).
 pub fn extract_media_urls_from_context(text: &str, max_items: usize) -> Vec<(String, String)> {
@@ -182,13 +310,10 @@ pub async fn fetch_media_from_context(text: &str, max_items: usize) -> Vec<Media
     let urls = extract_media_urls_from_context(text, max_items);
     let mut items = Vec::new();
     for (mime, url) in urls {
-        match download_and_encode(&url).await {
-            Ok(data) => {
+        match download(&url, MAX_INLINE_SIZE).await {
+            Ok(bytes) => {
                 info!("Fetched context media: {} ({})", url, mime);
-                items.push(MediaItem {
-                    mime_type: mime,
-                    data,
-                });
+                items.push(MediaItem::from_bytes(&mime, &bytes));
             }
             Err(e) => {
                 info!(
@@ -201,8 +326,8 @@ pub async fn fetch_media_from_context(text: &str, max_items: usize) -> Vec<Media
     items
 }
 
-/// Download a URL and return base64-encoded content
-async fn download_and_encode(url: &str) -> Result<String> {
+/// Download a URL, refusing anything over `max_bytes`
+async fn download(url: &str, max_bytes: usize) -> Result<Vec<u8>> {
     let client = reqwest::Client::builder()
         .timeout(std::time::Duration::from_secs(30))
         .build()?;
@@ -212,8 +337,103 @@ async fn download_and_encode(url: &str) -> Result<String> {
         return Err(anyhow::anyhow!("HTTP {}", response.status()));
     }
     let bytes = response.bytes().await?;
-    if bytes.len() > MAX_INLINE_SIZE {
+    if bytes.len() > max_bytes {
         return Err(anyhow::anyhow!("Too large: {} bytes", bytes.len()));
     }
-    Ok(base64::engine::general_purpose::STANDARD.encode(&bytes))
+    Ok(bytes.to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn attachment(filename: &str, url: &str, content_type: Option<&str>) -> serde_json::Value {
+        serde_json::json!({
+            "id": "1",
+            "filename": filename,
+            "size": 1024,
+            "url": url,
+            "proxy_url": url,
+            "content_type": content_type,
+        })
+    }
+
+    #[test]
+    fn test_stored_media_from_message() {
+        let mut msg = Message::default();
+        msg.content = "look (https://example.com/a?b=1), and www.nope.com or <https://docs.rs/x>."
+            .to_string();
+        msg.attachments = serde_json::from_value(serde_json::json!([
+            attachment("cat.png", "https://cdn.example/cat.png", Some("image/png")),
+            attachment("notes.txt", "https://cdn.example/notes.txt", None),
+        ]))
+        .unwrap();
+
+        let media = StoredMedia::from_message(&msg);
+        assert_eq!(media.attachments.len(), 2);
+        assert_eq!(media.attachments[0].filename, "cat.png");
+        assert_eq!(
+            media.links,
+            vec!["https://example.com/a?b=1", "https://docs.rs/x"]
+        );
+
+        // Round trips through the stored JSON; nothing to store means no JSON
+        let json = media.to_json().unwrap();
+        assert_eq!(StoredMedia::from_json(Some(&json)), media);
+        assert_eq!(StoredMedia::default().to_json(), None);
+        assert_eq!(
+            StoredMedia::from_json(Some("not json")),
+            StoredMedia::default()
+        );
+
+        // Links inside attachment tags aren't picked up twice
+        let tagged = format!("hi {}", describe_attachments(&msg));
+        assert!(extract_links(&tagged)
+            .iter()
+            .all(|link| !link.ends_with(']')));
+    }
+
+    #[test]
+    fn test_with_media_markers() {
+        let media = StoredMedia {
+            attachments: vec![
+                StoredAttachment {
+                    filename: "cat.png".to_string(),
+                    url: "https://cdn.example/cat.png".to_string(),
+                    content_type: Some("image/png".to_string()),
+                },
+                StoredAttachment {
+                    filename: "clip.mp4".to_string(),
+                    url: "https://cdn.example/clip.mp4".to_string(),
+                    content_type: Some("video/mp4".to_string()),
+                },
+                StoredAttachment {
+                    filename: "notes.txt".to_string(),
+                    url: "https://cdn.example/notes.txt".to_string(),
+                    content_type: None,
+                },
+            ],
+            links: vec!["https://www.youtube.com/watch?v=abc".to_string()],
+        };
+        let json = media.to_json();
+
+        // An image-only message no longer comes out blank
+        assert_eq!(
+            with_media_markers("", json.as_deref()),
+            "[image] [video] [file: notes.txt] [link: youtube.com]"
+        );
+        assert_eq!(
+            with_media_markers("check these out", json.as_deref()),
+            "check these out [image] [video] [file: notes.txt] [link: youtube.com]"
+        );
+
+        // Attachments already tagged in the text aren't marked again
+        let tagged = "[Image: cat.png | image/png | https://cdn.example/cat.png]";
+        assert_eq!(
+            with_media_markers(tagged, json.as_deref()),
+            format!("{tagged} [video] [file: notes.txt] [link: youtube.com]")
+        );
+
+        assert_eq!(with_media_markers("plain", None), "plain");
+    }
 }
@@ -0,0 +1,202 @@
+use crate::db_utils::{finish_context_messages, ContextMessage};
+use crate::display_name::clean_display_name;
+use crate::media_utils::{with_media_markers, StoredMedia};
+use serenity::model::channel::Message;
+use std::collections::VecDeque;
+
+/// A message as the context pipeline needs it, so history loaded from the database can
+/// sit alongside messages that arrive over the gateway
+#[derive(Debug, Clone, PartialEq)]
+pub struct StoredMessage {
+    /// Discord message ID, or 0 for messages saved without one
+    pub message_id: u64,
+    pub channel_id: u64,
+    pub author: String,
+    /// The raw display name, pronouns and all; it's cleaned when context is built
+    pub display_name: String,
+    /// Content with any attachment and link markers already applied
+    pub content: String,
+    /// Unix timestamp in seconds
+    pub timestamp: i64,
+    pub referenced_message_id: Option<u64>,
+}
+
+impl StoredMessage {
+    /// Build from a gateway message, using the same author, display name and content
+    /// that get saved to the message store
+    pub fn from_message(author: &str, display_name: &str, content: &str, msg: &Message) -> Self {
+        let media = StoredMedia::from_message(msg).to_json();
+        StoredMessage {
+            message_id: msg.id.get(),
+            channel_id: msg.channel_id.get(),
+            author: author.to_string(),
+            display_name: display_name.to_string(),
+            content: with_media_markers(content, media.as_deref()),
+            timestamp: msg.timestamp.unix_timestamp(),
+            referenced_message_id: msg
+                .message_reference
+                .as_ref()
+                .and_then(|reference| reference.message_id)
+                .map(|id| id.get()),
+        }
+    }
+}
+
+/// The most recent messages across all channels, oldest first. Conversation context
+/// falls back to this when the message store is missing or failing.
+#[derive(Debug)]
+pub struct MessageHistory {
+    messages: VecDeque<StoredMessage>,
+    limit: usize,
+}
+
+impl MessageHistory {
+    pub fn new(limit: usize) -> Self {
+        MessageHistory {
+            messages: VecDeque::with_capacity(limit),
+            limit,
+        }
+    }
+
+    /// Add a message, replacing the earlier copy if it was edited, and drop the oldest
+    /// messages once over the limit
+    pub fn push(&mut self, message: StoredMessage) {
+        if message.message_id != 0 {
+            if let Some(existing) = self
+                .messages
+                .iter_mut()
+                .find(|m| m.message_id == message.message_id)
+            {
+                *existing = message;
+                return;
+            }
+        }
+
+        self.messages.push_back(message);
+        while self.messages.len() > self.limit {
+            self.messages.pop_front();
+        }
+    }
+
+    /// Seed with messages loaded from the database, oldest first
+    pub fn extend(&mut self, messages: impl IntoIterator<Item = StoredMessage>) {
+        for message in messages {
+            self.push(message);
+        }
+    }
+
+    /// Drop every message posted under any of `names` (username or display name, ignoring
+    /// case and pronouns), returning how many went
+    pub fn forget_names(&mut self, names: &[String]) -> usize {
+        let before = self.messages.len();
+        self.messages.retain(|m| {
+            let display_name = clean_display_name(&m.display_name);
+            !names.iter().any(|name| {
+                m.author.eq_ignore_ascii_case(name)
+                    || m.display_name.eq_ignore_ascii_case(name)
+                    || display_name.eq_ignore_ascii_case(name)
+            })
+        });
+        before - self.messages.len()
+    }
+
+    /// The most recent messages, newest first, optionally from one channel, in the same
+    /// shape [`crate::message_store::MessageStore::get_recent`] returns
+    pub fn recent_context(&self, limit: usize, channel_id: Option<u64>) -> Vec<ContextMessage> {
+        let messages = self
+            .messages
+            .iter()
+            .rev()
+            .filter(|m| channel_id.is_none_or(|channel| m.channel_id == channel))
+            .take(limit)
+            .map(|m| {
+                let reply_context = m
+                    .referenced_message_id
+                    .and_then(|id| self.messages.iter().find(|r| r.message_id == id))
+                    .map(|r| format!("{}: {}", r.display_name, r.content));
+                (
+                    m.author.clone(),
+                    m.display_name.clone(),
+                    None,
+                    m.content.clone(),
+                    reply_context,
+                )
+            })
+            .collect();
+
+        finish_context_messages(messages)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stored(message_id: u64, channel_id: u64, content: &str) -> StoredMessage {
+        StoredMessage {
+            message_id,
+            channel_id,
+            author: "bob".to_string(),
+            display_name: "Bob (he/him)".to_string(),
+            content: content.to_string(),
+            timestamp: message_id as i64,
+            referenced_message_id: None,
+        }
+    }
+
+    #[test]
+    fn test_message_history_limit_and_edits() {
+        let mut history = MessageHistory::new(2);
+        history.extend([stored(1, 10, "one"), stored(2, 10, "two")]);
+        history.push(stored(2, 10, "two, edited"));
+        assert_eq!(history.recent_context(10, None).len(), 2);
+
+        history.push(stored(3, 10, "three"));
+        let contents: Vec<String> = history
+            .recent_context(10, None)
+            .into_iter()
+            .map(|(_, _, _, content, _)| content)
+            .collect();
+        assert_eq!(contents, vec!["three", "two, edited"]);
+    }
+
+    #[test]
+    fn test_recent_context() {
+        let mut history = MessageHistory::new(10);
+        let mut reply = stored(3, 10, "it was great");
+        reply.referenced_message_id = Some(1);
+        history.extend([
+            stored(1, 10, "how was the movie?"),
+            stored(2, 20, "elsewhere"),
+            reply,
+        ]);
+
+        let context = history.recent_context(10, Some(10));
+        assert_eq!(context.len(), 2);
+        let (author, display_name, pronouns, content, reply_context) = &context[0];
+        assert_eq!(author, "bob");
+        assert_eq!(display_name, "Bob");
+        assert_eq!(pronouns.as_deref(), Some("he/him"));
+        assert_eq!(content, "it was great");
+        assert_eq!(
+            reply_context.as_deref(),
+            Some("Bob (he/him): how was the movie?")
+        );
+        assert_eq!(history.recent_context(1, None).len(), 1);
+    }
+
+    #[test]
+    fn test_forget_names() {
+        let mut history = MessageHistory::new(10);
+        let mut relayed = stored(3, 10, "relayed");
+        relayed.author = "bridge".to_string();
+        relayed.display_name = "Rita".to_string();
+        history.extend([stored(1, 10, "one"), stored(2, 10, "two"), relayed]);
+
+        assert_eq!(history.forget_names(&["rita".to_string()]), 1);
+        assert_eq!(history.forget_names(&["nobody".to_string()]), 0);
+        // Pronouns in the stored display name don't hide it
+        assert_eq!(history.forget_names(&["BOB".to_string()]), 2);
+        assert!(history.recent_context(10, None).is_empty());
+    }
+}
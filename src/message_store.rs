@@ -0,0 +1,605 @@
+use crate::db_utils::{
+    self, ContextMessage, DateRange, HistorySearchHit, LastMessage, Memory, NameMatch,
+};
+use crate::message_history::StoredMessage;
+use anyhow::{anyhow, Result};
+use serenity::async_trait;
+use serenity::model::channel::Message;
+use serenity::model::id::{ChannelId, MessageId};
+use serenity::model::Timestamp;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tokio_rusqlite::Connection as SqliteConnection;
+
+/// Where the message history lives when MESSAGE_STORE_URL isn't set
+pub const DEFAULT_SQLITE_PATH: &str = "message_history.db";
+
+/// The last message seen in each channel
+pub type LastSeen = HashMap<ChannelId, (Timestamp, MessageId)>;
+
+/// Which message store MESSAGE_STORE_URL selected
+#[derive(Debug, Clone, PartialEq)]
+pub enum MessageStoreSettings {
+    /// A local SQLite file
+    Sqlite { path: String },
+    /// A PostgreSQL database shared between bot instances
+    Postgres { url: String },
+}
+
+impl Default for MessageStoreSettings {
+    fn default() -> Self {
+        MessageStoreSettings::Sqlite {
+            path: DEFAULT_SQLITE_PATH.to_string(),
+        }
+    }
+}
+
+impl MessageStoreSettings {
+    /// The SQLite file for everything else the bot stores (birthdays, reminders, pronouns
+    /// and so on), which stays local even when message history is in Postgres
+    pub fn sqlite_path(&self) -> &str {
+        match self {
+            MessageStoreSettings::Sqlite { path } => path,
+            MessageStoreSettings::Postgres { .. } => DEFAULT_SQLITE_PATH,
+        }
+    }
+}
+
+/// Where stored message history is kept. The SQLite store is the default; the Postgres
+/// store lets several bot instances share one history.
+#[async_trait]
+pub trait MessageStore: Send + Sync {
+    /// Short name for logs
+    fn name(&self) -> &'static str;
+
+    /// Store a message, or update its content if it's already stored. Returns whether a
+    /// new message was added (false for updates and for users who asked not to be stored).
+    async fn save(
+        &self,
+        author: &str,
+        display_name: &str,
+        content: &str,
+        message: Option<&Message>,
+    ) -> Result<bool>;
+
+    /// The most recent messages, newest first, optionally from one channel
+    async fn get_recent(
+        &self,
+        limit: usize,
+        channel_id: Option<&str>,
+    ) -> Result<Vec<ContextMessage>>;
+
+    /// The most recent messages across all channels, oldest first, for seeding the
+    /// in-memory history at startup
+    async fn load_history(&self, limit: usize) -> Result<Vec<StoredMessage>>;
+
+    /// Trim the history to its most recent messages, per channel where a channel has its
+    /// own limit; see [`db_utils::trim_message_history`]. Returns how many messages were
+    /// deleted.
+    async fn trim(
+        &self,
+        default_limit: usize,
+        channel_limits: &HashMap<String, usize>,
+        keep_protected: bool,
+    ) -> Result<usize>;
+
+    /// The last message seen in each channel, from stored messages and saved positions
+    async fn last_seen_by_channel(&self) -> Result<LastSeen>;
+
+    /// Save the last message seen in each channel. Returns how many channels were saved.
+    async fn persist_last_seen(&self, last_seen: &LastSeen) -> Result<usize>;
+
+    /// Who a `!quote -dud` name means in a guild (any guild if None): an exact username or
+    /// display name, or else the most used one containing it; see [`db_utils::resolve_dud_name`]
+    async fn resolve_dud_name(
+        &self,
+        name: String,
+        guild_id: Option<String>,
+    ) -> Result<Option<NameMatch>>;
+
+    /// Up to `count` random stored messages for `!quote -dud` and memory interjections, from
+    /// one guild unless `guild_id` is None and posted within `dates`; see
+    /// [`db_utils::random_dud_quotes`]
+    async fn random_dud_quotes(
+        &self,
+        username: Option<String>,
+        guild_id: Option<String>,
+        dates: DateRange,
+        min_length: usize,
+        count: usize,
+    ) -> Result<Vec<(String, String, String)>>;
+
+    /// The ID of the user who most recently posted under a username or display name,
+    /// ignoring messages from `excluded_ids` (the bot and gateway bots)
+    async fn find_user_id_by_name(
+        &self,
+        name: String,
+        excluded_ids: Vec<String>,
+    ) -> Result<Option<String>>;
+
+    /// Every username and display name a user's stored messages were posted under
+    async fn known_names(&self, user_id: String) -> Result<Vec<String>>;
+
+    /// Delete a user's messages and stop storing new ones. Returns how many were deleted.
+    async fn forget_user(&self, user_id: String) -> Result<usize>;
+
+    /// Delete every message posted under a username or display name, ignoring case
+    async fn delete_messages_by_author(&self, name: String) -> Result<usize>;
+
+    /// How many messages are stored, for `!info`
+    async fn count_messages(&self) -> Result<i64>;
+
+    /// Stored messages containing every word of `term`, newest first, for `!search`; see
+    /// [`db_utils::search_message_history`]
+    async fn search_history(
+        &self,
+        term: String,
+        guild_id: String,
+        channel_id: String,
+        limit: usize,
+        offset: usize,
+    ) -> Result<Vec<HistorySearchHit>>;
+
+    /// The newest message for `!lastseen` from a user ID, or else from anyone whose
+    /// username or display name contains `name`
+    async fn last_message(
+        &self,
+        author_id: Option<String>,
+        name: String,
+    ) -> Result<Option<LastMessage>>;
+
+    /// Every username and display name messages were stored under, for `!lastseen`
+    /// suggestions
+    async fn author_names(&self) -> Result<Vec<String>>;
+
+    /// A random message for a memory interjection, favouring recent ones, never one posted
+    /// as `exclude_name`, and only from `guild_id` when given; see [`db_utils::random_memory`]
+    async fn random_memory(
+        &self,
+        exclude_name: String,
+        guild_id: Option<String>,
+    ) -> Result<Option<Memory>>;
+}
+
+impl std::fmt::Debug for dyn MessageStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "MessageStore({})", self.name())
+    }
+}
+
+// db_utils errors aren't Send, so they're flattened to their message
+fn store_error(e: impl std::fmt::Display) -> anyhow::Error {
+    anyhow!("{}", e)
+}
+
+/// Message history in the bot's SQLite database
+pub struct SqliteMessageStore {
+    conn: Arc<Mutex<SqliteConnection>>,
+}
+
+impl SqliteMessageStore {
+    pub fn new(conn: Arc<Mutex<SqliteConnection>>) -> Self {
+        SqliteMessageStore { conn }
+    }
+}
+
+#[async_trait]
+impl MessageStore for SqliteMessageStore {
+    fn name(&self) -> &'static str {
+        "sqlite"
+    }
+
+    async fn save(
+        &self,
+        author: &str,
+        display_name: &str,
+        content: &str,
+        message: Option<&Message>,
+    ) -> Result<bool> {
+        db_utils::save_message(
+            self.conn.clone(),
+            author,
+            display_name,
+            content,
+            message,
+            None,
+        )
+        .await
+        .map_err(store_error)
+    }
+
+    async fn get_recent(
+        &self,
+        limit: usize,
+        channel_id: Option<&str>,
+    ) -> Result<Vec<ContextMessage>> {
+        db_utils::get_recent_messages_with_reply_context(self.conn.clone(), limit, channel_id)
+            .await
+            .map_err(store_error)
+    }
+
+    async fn load_history(&self, limit: usize) -> Result<Vec<StoredMessage>> {
+        db_utils::load_message_history(self.conn.clone(), limit)
+            .await
+            .map_err(store_error)
+    }
+
+    async fn trim(
+        &self,
+        default_limit: usize,
+        channel_limits: &HashMap<String, usize>,
+        keep_protected: bool,
+    ) -> Result<usize> {
+        db_utils::trim_message_history(
+            self.conn.clone(),
+            default_limit,
+            channel_limits,
+            keep_protected,
+        )
+        .await
+        .map_err(store_error)
+    }
+
+    async fn last_seen_by_channel(&self) -> Result<LastSeen> {
+        db_utils::get_last_messages_by_channel(self.conn.clone())
+            .await
+            .map_err(store_error)
+    }
+
+    async fn persist_last_seen(&self, last_seen: &LastSeen) -> Result<usize> {
+        db_utils::persist_last_seen(self.conn.clone(), last_seen)
+            .await
+            .map_err(store_error)
+    }
+
+    async fn random_dud_quotes(
+        &self,
+        username: Option<String>,
+        guild_id: Option<String>,
+        dates: DateRange,
+        min_length: usize,
+        count: usize,
+    ) -> Result<Vec<(String, String, String)>> {
+        db_utils::random_dud_quotes(
+            self.conn.clone(),
+            username,
+            guild_id,
+            dates,
+            min_length,
+            count,
+        )
+        .await
+        .map_err(store_error)
+    }
+
+    async fn resolve_dud_name(
+        &self,
+        name: String,
+        guild_id: Option<String>,
+    ) -> Result<Option<NameMatch>> {
+        db_utils::resolve_dud_name(self.conn.clone(), name, guild_id)
+            .await
+            .map_err(store_error)
+    }
+
+    async fn find_user_id_by_name(
+        &self,
+        name: String,
+        excluded_ids: Vec<String>,
+    ) -> Result<Option<String>> {
+        db_utils::find_user_id_by_name(self.conn.clone(), name, excluded_ids)
+            .await
+            .map_err(store_error)
+    }
+
+    async fn known_names(&self, user_id: String) -> Result<Vec<String>> {
+        db_utils::known_names(self.conn.clone(), user_id)
+            .await
+            .map_err(store_error)
+    }
+
+    async fn forget_user(&self, user_id: String) -> Result<usize> {
+        db_utils::forget_user(self.conn.clone(), user_id)
+            .await
+            .map_err(store_error)
+    }
+
+    async fn delete_messages_by_author(&self, name: String) -> Result<usize> {
+        db_utils::delete_messages_by_author(self.conn.clone(), name)
+            .await
+            .map_err(store_error)
+    }
+
+    async fn count_messages(&self) -> Result<i64> {
+        db_utils::count_messages(self.conn.clone())
+            .await
+            .map_err(store_error)
+    }
+
+    async fn search_history(
+        &self,
+        term: String,
+        guild_id: String,
+        channel_id: String,
+        limit: usize,
+        offset: usize,
+    ) -> Result<Vec<HistorySearchHit>> {
+        db_utils::search_message_history(
+            self.conn.clone(),
+            &term,
+            &guild_id,
+            &channel_id,
+            limit,
+            offset,
+        )
+        .await
+        .map_err(store_error)
+    }
+
+    async fn last_message(
+        &self,
+        author_id: Option<String>,
+        name: String,
+    ) -> Result<Option<LastMessage>> {
+        db_utils::last_message(self.conn.clone(), author_id, name)
+            .await
+            .map_err(store_error)
+    }
+
+    async fn author_names(&self) -> Result<Vec<String>> {
+        db_utils::author_names(self.conn.clone())
+            .await
+            .map_err(store_error)
+    }
+
+    async fn random_memory(
+        &self,
+        exclude_name: String,
+        guild_id: Option<String>,
+    ) -> Result<Option<Memory>> {
+        db_utils::random_memory(self.conn.clone(), exclude_name, guild_id)
+            .await
+            .map_err(store_error)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::message_history::MessageHistory;
+    use serenity::model::id::UserId;
+
+    fn message(id: u64, channel: u64, author: u64) -> Message {
+        let mut msg = Message::default();
+        msg.id = MessageId::new(id);
+        msg.channel_id = ChannelId::new(channel);
+        msg.author.id = UserId::new(author);
+        msg
+    }
+
+    // The behaviour every MessageStore backend has to share. Expects an empty store.
+    pub(crate) async fn check_store_contract(store: &dyn MessageStore) {
+        // Saving twice updates rather than duplicates
+        let first = message(1, 10, 42);
+        assert!(store
+            .save("alice", "Alice", "hello there", Some(&first))
+            .await
+            .unwrap());
+        assert!(!store
+            .save("alice", "Alice", "hello there, everyone", Some(&first))
+            .await
+            .unwrap());
+        for (id, channel, author, content) in [
+            (2, 10, 7, "the quick brown fox jumps over the lazy dog"),
+            (
+                3,
+                20,
+                42,
+                "somewhere else entirely, with a long enough message",
+            ),
+            (4, 10, 7, "ok"),
+        ] {
+            let (name, display_name) = if author == 42 {
+                ("alice", "Alice")
+            } else {
+                ("bob", "Bob (he/him)")
+            };
+            let msg = message(id, channel, author);
+            store
+                .save(name, display_name, content, Some(&msg))
+                .await
+                .unwrap();
+        }
+
+        // Messages saved in the same second can come back in either order
+        let recent = store.get_recent(10, Some("10")).await.unwrap();
+        let mut contents: Vec<&str> = recent.iter().map(|m| m.3.as_str()).collect();
+        contents.sort();
+        assert_eq!(
+            contents,
+            vec![
+                "hello there, everyone",
+                "ok",
+                "the quick brown fox jumps over the lazy dog",
+            ]
+        );
+        // Display names are cleaned up
+        let bob = recent.iter().find(|m| m.0 == "bob").unwrap();
+        assert_eq!(bob.1, "Bob");
+        assert_eq!(store.get_recent(10, None).await.unwrap().len(), 4);
+
+        // Loaded history comes back oldest first and can build context on its own
+        let loaded = store.load_history(3).await.unwrap();
+        assert_eq!(loaded.len(), 3);
+        assert!(loaded.windows(2).all(|w| w[0].timestamp <= w[1].timestamp));
+        let mut history = MessageHistory::new(10);
+        history.extend(store.load_history(10).await.unwrap());
+        let mut contents: Vec<String> = history
+            .recent_context(10, Some(10))
+            .into_iter()
+            .map(|m| m.3)
+            .collect();
+        contents.sort();
+        assert_eq!(
+            contents,
+            vec![
+                "hello there, everyone",
+                "ok",
+                "the quick brown fox jumps over the lazy dog",
+            ]
+        );
+
+        // Dud quote names prefer an exact match, then fall back to a partial one
+        assert_eq!(
+            store
+                .resolve_dud_name("BOB".to_string(), None)
+                .await
+                .unwrap(),
+            Some(NameMatch::Exact("BOB".to_string()))
+        );
+        assert_eq!(
+            store
+                .resolve_dud_name("lic".to_string(), None)
+                .await
+                .unwrap(),
+            Some(NameMatch::Partial("Alice".to_string()))
+        );
+        assert_eq!(
+            store
+                .resolve_dud_name("carol".to_string(), None)
+                .await
+                .unwrap(),
+            None
+        );
+
+        // Dud quotes skip short messages and can be limited to one user
+        let quotes = store
+            .random_dud_quotes(Some("bob".to_string()), None, DateRange::default(), 20, 3)
+            .await
+            .unwrap();
+        assert_eq!(quotes.len(), 1);
+        let (author, _, content) = &quotes[0];
+        assert_eq!(author, "bob");
+        assert_eq!(content, "the quick brown fox jumps over the lazy dog");
+        // Test messages have no guild, so scoping to one finds nothing
+        assert!(store
+            .random_dud_quotes(None, Some("1".to_string()), DateRange::default(), 0, 1)
+            .await
+            .unwrap()
+            .is_empty());
+
+        // The newest message per channel, unless a later position was saved
+        let last_seen = store.last_seen_by_channel().await.unwrap();
+        assert_eq!(last_seen[&ChannelId::new(20)].1, MessageId::new(3));
+        let mut saved = LastSeen::new();
+        saved.insert(
+            ChannelId::new(20),
+            (
+                Timestamp::from_unix_timestamp(5_000).unwrap(),
+                MessageId::new(9),
+            ),
+        );
+        assert_eq!(store.persist_last_seen(&saved).await.unwrap(), 1);
+        let last_seen = store.last_seen_by_channel().await.unwrap();
+        assert_eq!(last_seen[&ChannelId::new(20)].1, MessageId::new(9));
+        assert!(last_seen.contains_key(&ChannelId::new(10)));
+
+        // Reads behind !info, !search, !lastseen and memory interjections
+        assert_eq!(store.count_messages().await.unwrap(), 4);
+        let hits = store
+            .search_history("FOX dog".to_string(), String::new(), "10".to_string(), 5, 0)
+            .await
+            .unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].author, "bob");
+        assert!(store
+            .search_history("fox".to_string(), String::new(), "20".to_string(), 5, 0)
+            .await
+            .unwrap()
+            .is_empty());
+        let (author, display_name, _, _) = store
+            .last_message(Some("7".to_string()), String::new())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!((author.as_str(), display_name.as_str()), ("bob", "Bob"));
+        let (author, _, _, _) = store
+            .last_message(None, "LIC".to_string())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(author, "alice");
+        assert!(store
+            .last_message(None, "a_i".to_string())
+            .await
+            .unwrap()
+            .is_none());
+        let mut names = store.author_names().await.unwrap();
+        names.sort();
+        assert_eq!(names, vec!["Alice", "Bob", "alice", "bob"]);
+        let (_, author, _, _) = store
+            .random_memory("bob".to_string(), None)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(author, "alice");
+
+        // Trimming keeps each channel's newest messages
+        let mut channel_limits = HashMap::new();
+        channel_limits.insert("20".to_string(), 5);
+        assert_eq!(store.trim(2, &channel_limits, false).await.unwrap(), 1);
+        assert_eq!(store.get_recent(10, Some("10")).await.unwrap().len(), 2);
+        assert_eq!(store.get_recent(10, Some("20")).await.unwrap().len(), 1);
+
+        // Forgetting
+        assert_eq!(
+            store
+                .find_user_id_by_name("ALICE".to_string(), Vec::new())
+                .await
+                .unwrap()
+                .as_deref(),
+            Some("42")
+        );
+        assert_eq!(
+            store.known_names("42".to_string()).await.unwrap(),
+            vec!["alice", "Alice"]
+        );
+        assert_eq!(store.forget_user("42".to_string()).await.unwrap(), 1);
+        let later = message(5, 10, 42);
+        assert!(!store
+            .save("alice", "Alice", "remember me?", Some(&later))
+            .await
+            .unwrap());
+        assert_eq!(
+            store
+                .delete_messages_by_author("BOB".to_string())
+                .await
+                .unwrap(),
+            2
+        );
+        assert!(store.get_recent(10, None).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_sqlite_store_contract() {
+        let conn = db_utils::initialize_database(":memory:").await.unwrap();
+        check_store_contract(&SqliteMessageStore::new(conn)).await;
+    }
+
+    #[test]
+    fn test_sqlite_path() {
+        assert_eq!(
+            MessageStoreSettings::default().sqlite_path(),
+            DEFAULT_SQLITE_PATH
+        );
+        let postgres = MessageStoreSettings::Postgres {
+            url: "postgres://crow@db/crow".to_string(),
+        };
+        assert_eq!(postgres.sqlite_path(), DEFAULT_SQLITE_PATH);
+        let sqlite = MessageStoreSettings::Sqlite {
+            path: "/data/crow.db".to_string(),
+        };
+        assert_eq!(sqlite.sqlite_path(), "/data/crow.db");
+    }
+}
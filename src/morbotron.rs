@@ -1,14 +1,10 @@
-use crate::gemini_api::GeminiClient;
+use crate::http_client;
+use crate::screenshot::{self, Frame, Screenshot, ScreenshotProvider, ScreenshotSite, SearchState};
 use crate::text_formatting;
 use anyhow::{anyhow, Result};
-use rand::seq::IndexedRandom;
 use reqwest::Client as HttpClient;
 use serde::Deserialize;
-use serenity::all::Http;
-use serenity::builder::CreateMessage;
-use serenity::model::channel::Message;
-use std::sync::RwLock;
-use std::time::Duration;
+use std::borrow::Cow;
 use tracing::{error, info};
 
 // API endpoints
@@ -16,6 +12,22 @@ const MORBOTRON_BASE_URL: &str = "https://morbotron.com/api/search";
 const MORBOTRON_CAPTION_URL: &str = "https://morbotron.com/api/caption";
 const MORBOTRON_IMAGE_URL: &str = "https://morbotron.com/img";
 
+const MORBOTRON_SITE: ScreenshotSite = ScreenshotSite {
+    base_url: Cow::Borrowed("https://morbotron.com"),
+    gif_font: "fr",
+    upload_name: Cow::Borrowed("morbotron"),
+    show: Cow::Borrowed("Futurama"),
+    no_random_message: Cow::Borrowed(
+        "Couldn't find any Futurama screenshots. Bite my shiny metal...",
+    ),
+    random_error_message: Cow::Borrowed(
+        "Error getting Futurama screenshot. Bite my shiny metal...",
+    ),
+    search_error_message: Cow::Borrowed("Error searching Futurama quotes. Bite my shiny metal..."),
+    options: false,
+    shut_down_notice: None,
+};
+
 // Common search terms for random screenshots when no query is provided
 const RANDOM_SEARCH_TERMS: &[&str] = &[
     "good news everyone",
@@ -65,302 +77,19 @@ struct MorbotronSearchResult {
     timestamp: u64,
 }
 
-#[derive(Debug, Deserialize)]
-struct MorbotronCaptionResult {
-    #[serde(rename = "Episode")]
-    episode: MorbotronEpisode,
-    #[serde(rename = "Subtitles")]
-    subtitles: Vec<MorbotronSubtitle>,
-}
-
-#[derive(Debug, Deserialize)]
-struct MorbotronSubtitle {
-    #[serde(rename = "Id")]
-    _id: u64,
-    #[serde(rename = "RepresentativeTimestamp")]
-    representative_timestamp: u64,
-    #[serde(rename = "Episode")]
-    _episode: String,
-    #[serde(rename = "StartTimestamp")]
-    start_timestamp: u64,
-    #[serde(rename = "EndTimestamp")]
-    end_timestamp: u64,
-    #[serde(rename = "Content")]
-    content: String,
-    #[serde(rename = "Language")]
-    _language: String,
-}
-
-#[derive(Debug, Deserialize)]
-struct MorbotronEpisode {
-    #[serde(rename = "Id")]
-    _id: u64,
-    #[serde(rename = "Key")]
-    _key: String,
-    #[serde(rename = "Season")]
-    season: u32,
-    #[serde(rename = "EpisodeNumber")]
-    episode_number: u32,
-    #[serde(rename = "Title")]
-    title: String,
-    #[serde(rename = "Director")]
-    _director: String,
-    #[serde(rename = "Writer")]
-    _writer: String,
-    #[serde(rename = "OriginalAirDate")]
-    _original_air_date: String,
-    #[serde(rename = "WikiLink")]
-    _wiki_link: String,
-}
-
-// Result struct for Morbotron searches
-#[derive(Debug, Clone)]
-pub struct MorbotronResult {
-    pub _episode: String,
-    pub season: u32,
-    pub episode_number: u32,
-    pub episode_title: String,
-    pub _timestamp: String,
-    pub image_url: String,
-    pub caption: String,
-    pub start_timestamp: u64,
-    pub end_timestamp: u64,
-    pub subtitles: Vec<crate::frinkiac::TimedSubtitle>,
-    pub gif_url: Option<String>,
-}
-
 pub struct MorbotronClient {
     http_client: HttpClient,
-    last_query: RwLock<Option<String>>,
-    last_results: RwLock<Vec<MorbotronSearchResult>>,
-    current_index: RwLock<usize>,
+    search_state: SearchState,
 }
 
 impl MorbotronClient {
     pub fn new() -> Self {
         info!("Creating Morbotron client");
 
-        // Create HTTP client with reasonable timeouts
-        let http_client = HttpClient::builder()
-            .timeout(Duration::from_secs(10))
-            .build()
-            .expect("Failed to create HTTP client");
-
         Self {
-            http_client,
-            last_query: RwLock::new(None),
-            last_results: RwLock::new(Vec::new()),
-            current_index: RwLock::new(0),
-        }
-    }
-
-    // Get a random screenshot
-    pub async fn random(&self) -> Result<Option<MorbotronResult>> {
-        info!("Getting random Morbotron screenshot");
-
-        // Choose a random search term
-        let random_term = RANDOM_SEARCH_TERMS
-            .choose(&mut rand::rng())
-            .ok_or_else(|| anyhow!("Failed to choose random search term"))?;
-
-        info!("Using random search term: {}", random_term);
-
-        // Search for the random term
-        let results = self.search_api(random_term).await?;
-        if !results.is_empty() {
-            // Choose a random result
-            let random_result = results
-                .choose(&mut rand::rng())
-                .ok_or_else(|| anyhow!("Failed to choose random result"))?;
-
-            return self
-                .get_caption_for_frame(&random_result.episode, random_result.timestamp)
-                .await;
-        }
-
-        // If no results, return None
-        Ok(None)
-    }
-
-    // Search for a screenshot matching the query
-    pub async fn search(&self, query: &str) -> Result<Option<MorbotronResult>> {
-        info!("Morbotron search for: {}", query);
-
-        // Check if this is the same query as last time
-        let same_query;
-        let result_to_use;
-
-        {
-            let last_query = self.last_query.read().unwrap();
-            let last_results = self.last_results.read().unwrap();
-            let last_results_len = last_results.len();
-
-            if let Some(last_q) = last_query.as_ref() {
-                if last_q == query && last_results_len > 0 {
-                    // Same query, increment the index to cycle through results
-                    let mut index = *self.current_index.read().unwrap() + 1;
-                    if index >= last_results_len {
-                        index = 0; // Wrap around to the beginning
-                    }
-
-                    // Update the current index
-                    *self.current_index.write().unwrap() = index;
-                    info!(
-                        "Same query as last time, using result {} of {}",
-                        index + 1,
-                        last_results_len
-                    );
-
-                    // Get the result at the current index
-                    result_to_use = Some((
-                        last_results[index].episode.clone(),
-                        last_results[index].timestamp,
-                    ));
-                    same_query = true;
-                } else {
-                    same_query = false;
-                    result_to_use = None;
-                }
-            } else {
-                same_query = false;
-                result_to_use = None;
-            }
+            http_client: http_client::shared(),
+            search_state: SearchState::default(),
         }
-
-        // If it's the same query, use the result we found
-        if same_query {
-            if let Some((episode, timestamp)) = result_to_use {
-                return self.get_caption_for_frame(&episode, timestamp).await;
-            }
-        }
-
-        // New query, reset the index and fetch new results
-        *self.current_index.write().unwrap() = 0;
-
-        // Try a direct search first
-        let results = self.search_api(query).await?;
-        if !results.is_empty() {
-            // Store the query and results for next time
-            *self.last_query.write().unwrap() = Some(query.to_string());
-            *self.last_results.write().unwrap() = results.clone();
-
-            info!("Found {} results with direct search", results.len());
-            let first_result = &results[0];
-            return self
-                .get_caption_for_frame(&first_result.episode, first_result.timestamp)
-                .await;
-        }
-
-        // If direct search fails and it's a multi-word query, try with quotes
-        if query.contains(' ') {
-            let quoted_query = format!("\"{query}\"");
-            let results = self.search_api(&quoted_query).await?;
-            if !results.is_empty() {
-                // Store the query and results for next time
-                *self.last_query.write().unwrap() = Some(query.to_string());
-                *self.last_results.write().unwrap() = results.clone();
-
-                info!("Found {} results with quoted search", results.len());
-                let first_result = &results[0];
-                return self
-                    .get_caption_for_frame(&first_result.episode, first_result.timestamp)
-                    .await;
-            }
-        }
-
-        // If all else fails, return a random result
-        info!(
-            "No results found for query: {}, returning random result",
-            query
-        );
-        // Use Box::pin to avoid recursion issues
-        Box::pin(self.random()).await
-    }
-
-    async fn get_caption_for_frame(
-        &self,
-        episode: &str,
-        timestamp: u64,
-    ) -> Result<Option<MorbotronResult>> {
-        // Use the correct URL format: /api/caption?e=S01E02&t=242434
-        let caption_url = format!("{MORBOTRON_CAPTION_URL}?e={episode}&t={timestamp}");
-        info!("Using caption URL: {}", caption_url);
-
-        // Make the caption request
-        let caption_response = self
-            .http_client
-            .get(&caption_url)
-            .send()
-            .await
-            .map_err(|e| anyhow!("Failed to get Morbotron caption: {}", e))?;
-
-        let status = caption_response.status();
-        info!("Caption API response status: {}", status);
-
-        if !status.is_success() {
-            // If the request failed, return a random result instead of an error
-            info!(
-                "Caption request failed with status: {}, returning random result",
-                status
-            );
-            return Box::pin(self.random()).await;
-        }
-
-        // Parse the caption result
-        let caption_result: MorbotronCaptionResult = caption_response
-            .json()
-            .await
-            .map_err(|e| anyhow!("Failed to parse Morbotron caption: {}", e))?;
-
-        // If no subtitles, return None
-        if caption_result.subtitles.is_empty() {
-            return Ok(None);
-        }
-
-        // Extract the caption text
-        let caption = caption_result
-            .subtitles
-            .iter()
-            .map(|s| s.content.clone())
-            .collect::<Vec<String>>()
-            .join("\n");
-
-        let timed_subs: Vec<crate::frinkiac::TimedSubtitle> = caption_result
-            .subtitles
-            .iter()
-            .map(|s| crate::frinkiac::TimedSubtitle {
-                text: s.content.clone(),
-                start: s.start_timestamp,
-                end: s.end_timestamp,
-                representative_ts: s.representative_timestamp,
-            })
-            .collect();
-
-        // Build the image URL
-        let image_url = format!("{MORBOTRON_IMAGE_URL}/{episode}/{timestamp}.jpg");
-
-        // Extract episode information
-        let episode_title = caption_result.episode.title.clone();
-        let season = caption_result.episode.season;
-        let episode_number = caption_result.episode.episode_number;
-
-        let start_ts = timed_subs.first().map(|s| s.start).unwrap_or(timestamp);
-        let end_ts = timed_subs.last().map(|s| s.end).unwrap_or(timestamp + 4000);
-
-        // Return the result
-        Ok(Some(MorbotronResult {
-            _episode: episode.to_string(),
-            season,
-            episode_number,
-            episode_title,
-            _timestamp: timestamp.to_string(),
-            image_url,
-            caption: format_caption(&caption),
-            start_timestamp: start_ts,
-            end_timestamp: end_ts,
-            subtitles: timed_subs,
-            gif_url: None,
-        }))
     }
 
     // Internal method to search the API and return the raw results
@@ -415,242 +144,94 @@ impl MorbotronClient {
 
         Ok(search_results)
     }
+}
 
-    /// Expand subtitles to sentence boundaries by fetching adjacent captions.
-    pub async fn expand_to_sentence_boundaries(&self, result: &mut MorbotronResult) {
-        let episode = result._episode.clone();
-
-        let first_starts_mid = result
-            .subtitles
-            .first()
-            .is_some_and(|s| s.text.chars().next().is_some_and(|c| c.is_lowercase()));
-        let first_start = result.subtitles.first().map(|s| s.start).unwrap_or(0);
-
-        if first_starts_mid {
-            let earlier_ts = first_start.saturating_sub(2000);
-            let expanded =
-                if let Ok(Some(earlier)) = self.get_caption_for_frame(&episode, earlier_ts).await {
-                    let mut to_prepend = Vec::new();
-                    for sub in earlier.subtitles.iter().rev() {
-                        if sub.end <= first_start {
-                            to_prepend.push(sub.clone());
-                            if sub.text.chars().next().is_some_and(|c| c.is_uppercase()) {
-                                break;
-                            }
-                        }
-                    }
-                    to_prepend.reverse();
-                    if let Some(first_new) = to_prepend.first() {
-                        result.start_timestamp = first_new.start;
-                        for (i, sub) in to_prepend.into_iter().enumerate() {
-                            result.subtitles.insert(i, sub);
-                        }
-                        true
-                    } else {
-                        false
-                    }
-                } else {
-                    false
-                };
-
-            if !expanded && !result.subtitles.is_empty() {
-                result.subtitles.remove(0);
-                if let Some(new_first) = result.subtitles.first() {
-                    result.start_timestamp = new_first.start;
-                }
-            }
-        }
+impl ScreenshotProvider for MorbotronClient {
+    fn name(&self) -> &str {
+        "Morbotron"
+    }
 
-        let last_ends_mid = result.subtitles.last().is_some_and(|s| {
-            s.text.ends_with(',')
-                || (!s.text.ends_with('.')
-                    && !s.text.ends_with('!')
-                    && !s.text.ends_with('?')
-                    && !s.text.ends_with('"'))
-        });
-        let last_rep_ts = result
-            .subtitles
-            .last()
-            .map(|s| s.representative_ts)
-            .unwrap_or(0);
-        let last_end = result.subtitles.last().map(|s| s.end).unwrap_or(0);
+    fn site(&self) -> &ScreenshotSite {
+        &MORBOTRON_SITE
+    }
 
-        if last_ends_mid {
-            let later_ts = last_rep_ts;
-            let mut expanded = false;
-            if let Ok(Some(later)) = self.get_caption_for_frame(&episode, later_ts).await {
-                for sub in &later.subtitles {
-                    if sub.start >= last_end {
-                        result.end_timestamp = sub.end;
-                        result.subtitles.push(sub.clone());
-                        expanded = true;
-                        if sub.text.ends_with('.')
-                            || sub.text.ends_with('!')
-                            || sub.text.ends_with('?')
-                            || sub.text.ends_with('"')
-                        {
-                            break;
-                        }
-                    }
-                }
-            }
+    // Frames in the API's own order
+    async fn search(&self, query: &str) -> Result<Vec<Frame>> {
+        let results = self.search_api(query).await?;
+        Ok(results
+            .into_iter()
+            .enumerate()
+            .map(|(i, result)| Frame {
+                episode: result.episode,
+                timestamp: result.timestamp,
+                api_rank: i + 1,
+                note: None,
+            })
+            .collect())
+    }
 
-            if !expanded && result.subtitles.len() > 1 {
-                result.subtitles.pop();
-                if let Some(new_last) = result.subtitles.last() {
-                    result.end_timestamp = new_last.end;
-                }
-            }
-        }
+    fn caption_url(&self, frame: &Frame) -> String {
+        // Use the correct URL format: /api/caption?e=S01E02&t=242434
+        format!(
+            "{MORBOTRON_CAPTION_URL}?e={}&t={}",
+            frame.episode, frame.timestamp
+        )
     }
-}
 
-// Format a caption to proper sentence case and separate different speakers
-fn format_caption(caption: &str) -> String {
-    text_formatting::format_caption(caption, text_formatting::FUTURAMA_PROPER_NOUNS)
-}
+    async fn caption(&self, frame: &Frame) -> Result<Option<Screenshot>> {
+        let caption_url = self.caption_url(frame);
+        info!("Using caption URL: {}", caption_url);
 
-// Format a Morbotron result for display
-fn format_morbotron_result(result: &MorbotronResult) -> String {
-    let season = result.season;
-    let episode_number = result.episode_number;
-    let episode_title = &result.episode_title;
-    let media_url = result.gif_url.as_deref().unwrap_or(&result.image_url);
-    if result.gif_url.is_some() {
-        format!("**S{season:02}E{episode_number:02} - {episode_title}**\n{media_url}")
-    } else {
-        let caption = &result.caption;
-        format!("**S{season:02}E{episode_number:02} - {episode_title}**\n{media_url}\n\n{caption}")
-    }
-}
+        // Make the caption request
+        let caption_response = self
+            .http_client
+            .get(&caption_url)
+            .send()
+            .await
+            .map_err(|e| anyhow!("Failed to get Morbotron caption: {}", e))?;
 
-async fn send_morbotron_result(http: &Http, msg: &Message, result: &MorbotronResult) {
-    if let Some(gif_url) = &result.gif_url {
-        let title = format!(
-            "S{:02}E{:02} - {}",
-            result.season, result.episode_number, result.episode_title
-        );
+        let status = caption_response.status();
+        info!("Caption API response status: {}", status);
 
-        // Download the GIF and upload as attachment for reliable display
-        match reqwest::Client::new().get(gif_url.as_str()).send().await {
-            Ok(resp) if resp.status().is_success() => {
-                if let Ok(bytes) = resp.bytes().await {
-                    let attachment = serenity::builder::CreateAttachment::bytes(
-                        bytes.to_vec(),
-                        "morbotron.gif".to_string(),
-                    );
-                    let message = CreateMessage::new().content(title).add_file(attachment);
-                    if let Err(e) = msg.channel_id.send_message(http, message).await {
-                        error!("Error sending Morbotron GIF attachment: {:?}", e);
-                    }
-                }
-            }
-            _ => {
-                // Fallback: send the URL as text
-                let response = format!("{}\n{}", title, gif_url);
-                if let Err(e) = msg.channel_id.say(http, &response).await {
-                    error!("Error sending Morbotron result: {:?}", e);
-                }
-            }
-        }
-    } else {
-        let response = format_morbotron_result(result);
-        if let Err(e) = msg.channel_id.say(http, &response).await {
-            error!("Error sending Morbotron result: {:?}", e);
+        if !status.is_success() {
+            // The search falls back to a random frame rather than failing
+            info!("Caption request failed with status: {}", status);
+            return Ok(None);
         }
-    }
-}
-
-// This function will be called from main.rs to handle the !morbotron command
-pub async fn handle_morbotron_command(
-    http: &Http,
-    msg: &Message,
-    args: Option<String>,
-    morbotron_client: &MorbotronClient,
-    _gemini_client: Option<&GeminiClient>,
-) -> Result<()> {
-    // Show typing indicator while we search
-    let _ = msg.channel_id.broadcast_typing(http).await;
 
-    // If no search term is provided, get a random screenshot
-    if args.is_none() {
-        info!("Morbotron request for random screenshot");
+        // Parse the caption result
+        let caption_result: serde_json::Value = caption_response
+            .json()
+            .await
+            .map_err(|e| anyhow!("Failed to parse Morbotron caption: {}", e))?;
 
-        let response = match morbotron_client.random().await {
-            Ok(Some(mut result)) => {
-                morbotron_client
-                    .expand_to_sentence_boundaries(&mut result)
-                    .await;
-                result.gif_url = crate::frinkiac::generate_gif(
-                    "https://morbotron.com",
-                    &result._episode,
-                    result.start_timestamp,
-                    result.end_timestamp,
-                    &crate::frinkiac::merge_subtitle_fragments(&result.subtitles),
-                    0,
-                    "fr",
-                )
-                .await;
-                send_morbotron_result(http, msg, &result).await;
-                return Ok(());
-            }
-            Ok(None) => {
-                "Couldn't find any Futurama screenshots. Bite my shiny metal...".to_string()
-            }
-            Err(e) => {
-                error!("Error getting random Morbotron screenshot: {:?}", e);
-                "Error getting Futurama screenshot. Bite my shiny metal...".to_string()
-            }
-        };
+        // Build the image URL
+        let image_url = format!(
+            "{MORBOTRON_IMAGE_URL}/{}/{}.jpg",
+            frame.episode, frame.timestamp
+        );
+        let mut result =
+            screenshot::parse_caption(&caption_result, &frame.episode, frame.timestamp, image_url)?;
 
-        if let Err(e) = msg.channel_id.say(http, &response).await {
-            error!("Error sending Morbotron result: {:?}", e);
+        // If no subtitles, return None
+        if result.subtitles.is_empty() {
+            return Ok(None);
         }
-        return Ok(());
+
+        result.caption = format_caption(&result.subtitle_text("\n"));
+        Ok(Some(result))
     }
 
-    // If we have a search term, search for it
-    if let Some(term) = args {
-        info!("Morbotron search for: {}", term);
+    fn random_terms(&self) -> &[&str] {
+        RANDOM_SEARCH_TERMS
+    }
 
-        match morbotron_client.search(&term).await {
-            Ok(Some(mut result)) => {
-                morbotron_client
-                    .expand_to_sentence_boundaries(&mut result)
-                    .await;
-                result.gif_url = crate::frinkiac::generate_gif(
-                    "https://morbotron.com",
-                    &result._episode,
-                    result.start_timestamp,
-                    result.end_timestamp,
-                    &crate::frinkiac::merge_subtitle_fragments(&result.subtitles),
-                    0,
-                    "fr",
-                )
-                .await;
-                send_morbotron_result(http, msg, &result).await;
-            }
-            Ok(None) => {
-                let _ = msg
-                    .channel_id
-                    .say(
-                        http,
-                        format!("Couldn't find any Futurama screenshots matching \"{term}\"."),
-                    )
-                    .await;
-            }
-            Err(e) => {
-                error!("Error searching Morbotron: {:?}", e);
-                let _ = msg
-                    .channel_id
-                    .say(
-                        http,
-                        "Error searching Futurama quotes. Bite my shiny metal...",
-                    )
-                    .await;
-            }
-        }
+    fn search_state(&self) -> &SearchState {
+        &self.search_state
     }
+}
 
-    Ok(())
+// Format a caption to proper sentence case and separate different speakers
+fn format_caption(caption: &str) -> String {
+    text_formatting::format_caption(caption, text_formatting::FUTURAMA_PROPER_NOUNS)
 }
@@ -1,7 +1,95 @@
 use mysql::prelude::Queryable;
 use rand::seq::IndexedRandom;
 use regex::Regex;
-use tracing::{error, info};
+use tracing::{error, info, warn};
+
+/// Built-in quotes used when the quote database can't supply one and no quote file is set
+const BUILTIN_FALLBACK_QUOTES: &[&str] = &[
+    "Push the button, Frank!",
+    "Watch out for snakes!",
+    "It stinks!",
+    "Rowsdower!",
+    "Hi-keeba!",
+    "Torgo's theme is playing again.",
+    "I'm cold and there are wolves after me.",
+    "Mitchell!",
+    "This is the worst movie I've ever seen. Again.",
+    "Deep hurting.",
+];
+
+// Lines longer than this in a quote file are skipped as probably not a quote
+const MAX_QUOTE_LINE_CHARS: usize = 500;
+
+/// Parse a quote file: one quote per line. Blank lines and lines starting with `#`
+/// are skipped, as are overlong lines.
+pub fn parse_quote_lines(contents: &str) -> Vec<String> {
+    contents
+        .lines()
+        .enumerate()
+        .filter_map(|(index, line)| {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                return None;
+            }
+            if line.chars().count() > MAX_QUOTE_LINE_CHARS {
+                warn!(
+                    "Skipping MST3K quote file line {}: longer than {} characters",
+                    index + 1,
+                    MAX_QUOTE_LINE_CHARS
+                );
+                return None;
+            }
+            Some(line.to_string())
+        })
+        .collect()
+}
+
+/// Load the fallback quote pool from the configured file, or the built-in list if no file
+/// is configured or it's missing or has no usable quotes
+pub fn load_fallback_quotes(path: Option<&str>) -> Vec<String> {
+    let builtin = || {
+        BUILTIN_FALLBACK_QUOTES
+            .iter()
+            .map(|q| q.to_string())
+            .collect()
+    };
+
+    let Some(path) = path else {
+        return builtin();
+    };
+
+    match std::fs::read_to_string(path) {
+        Ok(contents) => {
+            let quotes = parse_quote_lines(&contents);
+            if quotes.is_empty() {
+                warn!(
+                    "MST3K quote file {} has no quotes, using the built-in list",
+                    path
+                );
+                builtin()
+            } else {
+                info!(
+                    "Loaded {} fallback MST3K quotes from {}",
+                    quotes.len(),
+                    path
+                );
+                quotes
+            }
+        }
+        Err(e) => {
+            warn!(
+                "Couldn't read MST3K quote file {} ({}), using the built-in list",
+                path, e
+            );
+            builtin()
+        }
+    }
+}
+
+/// Pick a random quote from the fallback pool
+pub fn fallback_mst3k_quote(quotes: &[String]) -> Option<String> {
+    quotes.choose(&mut rand::rng()).cloned()
+}
 
 /// Process an MST3K quote from the database
 ///
@@ -130,3 +218,33 @@ fn extract_speaker_lines(quote: &str) -> Option<String> {
         None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fallback_quotes_from_file() {
+        let path =
+            std::env::temp_dir().join(format!("crow_mst3k_quotes_{}.txt", std::process::id()));
+        std::fs::write(
+            &path,
+            "# Operator quotes\n\nHe's just a normal guy!\n  Rowsdower!  \n",
+        )
+        .unwrap();
+
+        let quotes = load_fallback_quotes(path.to_str());
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(quotes, vec!["He's just a normal guy!", "Rowsdower!"]);
+        for _ in 0..10 {
+            let quote = fallback_mst3k_quote(&quotes).unwrap();
+            assert!(quotes.contains(&quote));
+        }
+
+        // Missing or empty files fall back to the built-in list
+        let builtin = load_fallback_quotes(None);
+        assert_eq!(builtin.len(), BUILTIN_FALLBACK_QUOTES.len());
+        assert_eq!(load_fallback_quotes(path.to_str()), builtin);
+        assert!(parse_quote_lines("# only comments\n\n").is_empty());
+    }
+}
@@ -1,4 +1,4 @@
-use crate::gemini_api::GeminiClient;
+use crate::gemini_api::{GeminiClient, GeminiTask};
 use anyhow::Result;
 use serde_json::Value;
 use tracing::{error, info};
@@ -68,7 +68,7 @@ impl MultiResponseGenerator {
 
         match self
             .gemini_client
-            .generate_content(&generation_prompt)
+            .generate_content_for(&generation_prompt, GeminiTask::Interjection)
             .await
         {
             Ok(response) => {
@@ -144,7 +144,11 @@ impl MultiResponseGenerator {
         // Suppress unused variable warning - we keep the parameter for API compatibility
         let _ = context_preview;
 
-        match self.gemini_client.generate_content(&rating_prompt).await {
+        match self
+            .gemini_client
+            .generate_content_for(&rating_prompt, GeminiTask::Evaluation)
+            .await
+        {
             Ok(rating_response) => self.parse_ratings(&rating_response, responses).await,
             Err(e) => {
                 error!("Failed to rate responses: {:?}", e);
@@ -267,7 +271,13 @@ impl MultiResponseGenerator {
         // Generate the response normally first, then evaluate it
         match self
             .gemini_client
-            .generate_response_with_context_and_pronouns(prompt, "", context, None)
+            .generate_response_with_context_and_pronouns(
+                prompt,
+                "",
+                context,
+                None,
+                GeminiTask::Interjection,
+            )
             .await
         {
             Ok(response) => {
@@ -318,7 +328,7 @@ impl MultiResponseGenerator {
 
         match self
             .gemini_client
-            .generate_content(&evaluation_prompt)
+            .generate_content_for(&evaluation_prompt, GeminiTask::Evaluation)
             .await
         {
             Ok(rating_text) => {
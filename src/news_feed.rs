@@ -1,3 +1,5 @@
+use crate::http_client;
+use std::collections::HashSet;
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::RwLock;
@@ -122,12 +124,7 @@ pub fn spawn_fetcher(cache: HeadlineCache, interval_secs: u64, feed_config: Opti
 async fn fetch_feeds(feeds: &[(String, String)]) -> Vec<Headline> {
     let mut all = Vec::new();
 
-    let client = reqwest::Client::builder()
-        .timeout(Duration::from_secs(15))
-        .user_agent("Mozilla/5.0 (compatible; CrowBot/1.0)")
-        .redirect(reqwest::redirect::Policy::limited(3))
-        .build()
-        .unwrap_or_default();
+    let client = http_client::shared();
 
     for (url, name) in feeds {
         if let Some(mut items) = fetch_rss(&client, url, name).await {
@@ -139,26 +136,129 @@ async fn fetch_feeds(feeds: &[(String, String)]) -> Vec<Headline> {
     all
 }
 
-/// Parse an RSS feed and extract headlines
+/// Fetch an RSS or Atom feed and extract headlines
 async fn fetch_rss(client: &reqwest::Client, url: &str, source: &str) -> Option<Vec<Headline>> {
     let body = client.get(url).send().await.ok()?.text().await.ok()?;
+    Some(parse_feed(&body, source))
+}
+
+/// Pull the headlines out of an RSS (`<item>`) or Atom (`<entry>`) document, in feed order.
+/// Entries without a title or an http(s) link are skipped.
+pub fn parse_feed(body: &str, source: &str) -> Vec<Headline> {
     let mut headlines = Vec::new();
 
     // Simple XML parsing for <item><title>...</title><link>...</link></item>
-    for item in body.split("<item>").skip(1) {
-        let title = extract_xml_tag(item, "title")?;
-        let link = extract_xml_tag(item, "link").or_else(|| extract_xml_tag(item, "guid"))?;
-
-        if !title.is_empty() && link.starts_with("http") {
-            headlines.push(Headline {
-                title: clean_cdata(&title),
-                url: link,
-                source: source.to_string(),
-            });
+    for item in split_elements(body, "item") {
+        let title = extract_xml_tag(item, "title").unwrap_or_default();
+        let link = extract_xml_tag(item, "link")
+            .or_else(|| extract_xml_tag(item, "guid"))
+            .map(|link| clean_cdata(&link))
+            .unwrap_or_default();
+        push_headline(&mut headlines, &title, link, source);
+    }
+
+    // Atom puts the URL in <link href="..."/>, preferring rel="alternate"
+    for entry in split_elements(body, "entry") {
+        let title = extract_xml_tag(entry, "title").unwrap_or_default();
+        let link = atom_link(entry).unwrap_or_default();
+        push_headline(&mut headlines, &title, link, source);
+    }
+
+    headlines
+}
+
+fn push_headline(headlines: &mut Vec<Headline>, title: &str, url: String, source: &str) {
+    let title = clean_cdata(title);
+    if !title.is_empty() && url.starts_with("http") {
+        headlines.push(Headline {
+            title,
+            url,
+            source: source.to_string(),
+        });
+    }
+}
+
+/// The bodies of every `<tag>` or `<tag ...>` element, so `<item>` doesn't match `<itemref>`
+fn split_elements<'a>(xml: &'a str, tag: &str) -> Vec<&'a str> {
+    let open = format!("<{tag}");
+    let close = format!("</{tag}>");
+    let mut elements = Vec::new();
+    let mut rest = xml;
+
+    while let Some(start) = rest.find(&open) {
+        let after = &rest[start + open.len()..];
+        if !after.starts_with(['>', ' ', '\t', '\n', '\r']) {
+            rest = after;
+            continue;
+        }
+        let end = after.find(&close).unwrap_or(after.len());
+        elements.push(&after[..end]);
+        rest = &after[end..];
+    }
+
+    elements
+}
+
+/// The article link of an Atom entry
+fn atom_link(entry: &str) -> Option<String> {
+    let mut fallback = None;
+    for tag in entry.split("<link").skip(1) {
+        let tag = &tag[..tag.find('>')?];
+        let Some(href) = xml_attribute(tag, "href") else {
+            continue;
+        };
+        match xml_attribute(tag, "rel").as_deref() {
+            None | Some("alternate") => return Some(href),
+            _ => {
+                fallback.get_or_insert(href);
+            }
         }
     }
+    fallback
+}
+
+/// The value of `name="..."` (or single-quoted) inside a tag
+fn xml_attribute(tag: &str, name: &str) -> Option<String> {
+    let pattern = format!(" {name}=");
+    let start = tag.find(&pattern)? + pattern.len();
+    let quoted = &tag[start..];
+    let quote = quoted.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+    let value = &quoted[1..];
+    let end = value.find(quote)?;
+    Some(clean_cdata(&value[..end]))
+}
+
+/// Words too common to say anything about what a conversation is about
+const STOP_WORDS: &[&str] = &[
+    "about", "after", "again", "also", "been", "being", "could", "does", "from", "have", "just",
+    "like", "make", "more", "much", "only", "really", "said", "some", "than", "that", "their",
+    "them", "then", "there", "they", "thing", "think", "this", "what", "when", "where", "which",
+    "will", "with", "would", "your",
+];
 
-    Some(headlines)
+fn keywords(text: &str) -> HashSet<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| word.chars().count() >= 4)
+        .map(str::to_lowercase)
+        .filter(|word| !STOP_WORDS.contains(&word.as_str()))
+        .collect()
+}
+
+/// Order headlines by how many words they share with the recent conversation, keeping feed
+/// order (newest first) among equally relevant ones
+pub fn rank_headlines<'a>(headlines: &'a [Headline], context: &str) -> Vec<&'a Headline> {
+    let context_words = keywords(context);
+    let mut ranked: Vec<(usize, &Headline)> = headlines
+        .iter()
+        .map(|headline| {
+            let overlap = keywords(&headline.title)
+                .intersection(&context_words)
+                .count();
+            (overlap, headline)
+        })
+        .collect();
+    ranked.sort_by_key(|(overlap, _)| std::cmp::Reverse(*overlap));
+    ranked.into_iter().map(|(_, headline)| headline).collect()
 }
 
 /// Extract content between XML tags (handles CDATA)
@@ -187,3 +287,86 @@ fn clean_cdata(s: &str) -> String {
         .trim()
         .to_string()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_rss_feed() {
+        let rss = r#"<?xml version="1.0" encoding="UTF-8"?>
+<rss version="2.0">
+<channel>
+  <title>Example News</title>
+  <link>https://example.com/</link>
+  <item>
+    <title><![CDATA[Robot vacuum maps owner&#8217;s house &amp; sells it]]></title>
+    <link>https://example.com/2025/06/robot-vacuum</link>
+    <pubDate>Mon, 02 Jun 2025 10:00:00 GMT</pubDate>
+  </item>
+  <item>
+    <title>Guid-only story</title>
+    <guid isPermaLink="true">https://example.com/guid-story</guid>
+  </item>
+  <item>
+    <title>No link at all</title>
+  </item>
+</channel>
+</rss>"#;
+
+        let headlines = parse_feed(rss, "Example");
+        assert_eq!(headlines.len(), 2);
+        assert_eq!(
+            headlines[0].title,
+            "Robot vacuum maps owner's house & sells it"
+        );
+        assert_eq!(headlines[0].url, "https://example.com/2025/06/robot-vacuum");
+        assert_eq!(headlines[0].source, "Example");
+        assert_eq!(headlines[1].url, "https://example.com/guid-story");
+    }
+
+    #[test]
+    fn test_parse_atom_feed() {
+        let atom = r#"<feed xmlns="http://www.w3.org/2005/Atom">
+  <title>Atom Example</title>
+  <link href="https://example.org/"/>
+  <entry>
+    <title type="html">Tiny &amp; fast</title>
+    <link rel="replies" href="https://example.org/tiny/comments"/>
+    <link rel="alternate" type="text/html" href="https://example.org/tiny"/>
+  </entry>
+  <entry>
+    <title>Bare link</title>
+    <link href='https://example.org/bare'/>
+  </entry>
+</feed>"#;
+
+        let headlines = parse_feed(atom, "Atom");
+        let urls: Vec<&str> = headlines.iter().map(|h| h.url.as_str()).collect();
+        assert_eq!(
+            urls,
+            ["https://example.org/tiny", "https://example.org/bare"]
+        );
+        assert_eq!(headlines[0].title, "Tiny & fast");
+    }
+
+    #[test]
+    fn test_rank_headlines() {
+        let headline = |title: &str| Headline {
+            title: title.to_string(),
+            url: format!("https://example.com/{}", title.len()),
+            source: "Example".to_string(),
+        };
+        let headlines = [
+            headline("Stock markets close flat"),
+            headline("New volcano erupts in Iceland"),
+            headline("Scientists train crows to pick up litter"),
+        ];
+
+        let ranked = rank_headlines(&headlines, "alice: did you know crows can recognize faces?");
+        assert_eq!(ranked[0].title, "Scientists train crows to pick up litter");
+        // The rest keep their feed order
+        assert_eq!(ranked[1].title, "Stock markets close flat");
+        assert_eq!(ranked[2].title, "New volcano erupts in Iceland");
+    }
+}
@@ -1,12 +1,12 @@
-use crate::db_utils;
-use crate::gemini_api::GeminiClient;
-use crate::news_feed::{Headline, HeadlineCache};
+use crate::gemini_api::{GeminiClient, GeminiTask};
+use crate::message_store::MessageStore;
+use crate::news_feed::{rank_headlines, Headline, HeadlineCache};
 use crate::response_timing::apply_realistic_delay;
 use anyhow::Result;
 use serenity::model::channel::Message;
+use serenity::model::id::{ChannelId, MessageId};
 use serenity::prelude::*;
 use std::sync::Arc;
-use tokio_rusqlite::Connection;
 use tracing::{error, info};
 
 // Handle news interjection using real headlines from RSS feeds
@@ -14,56 +14,105 @@ pub async fn handle_news_interjection(
     ctx: &Context,
     msg: &Message,
     gemini_client: &GeminiClient,
-    message_db: &Option<Arc<tokio::sync::Mutex<Connection>>>,
-    _bot_name: &str,
+    message_store: &Option<Arc<dyn MessageStore>>,
+    bot_name: &str,
     gemini_context_messages: usize,
     headline_cache: &HeadlineCache,
-) -> Result<bool> {
-    // Get cached headlines
-    let headlines = headline_cache.read().await;
-    if headlines.is_empty() {
-        info!("News interjection: no headlines cached yet");
-        return Ok(false);
+) -> Result<Option<MessageId>> {
+    let context_text = recent_context(message_store, gemini_context_messages, msg.channel_id).await;
+
+    let Some(final_message) =
+        choose_news_interjection(gemini_client, bot_name, &context_text, headline_cache).await?
+    else {
+        return Ok(None);
+    };
+
+    if let Err(e) = msg.channel_id.broadcast_typing(&ctx.http).await {
+        error!("Failed to send typing indicator: {:?}", e);
     }
 
-    // Get recent conversation context
-    let context_text = if let Some(db) = message_db {
-        match db_utils::get_recent_messages_with_reply_context(
-            db.clone(),
+    apply_realistic_delay(&final_message, ctx, msg.channel_id).await;
+
+    match crate::utils::send_without_pings(&ctx.http, msg.channel_id, &final_message).await {
+        Ok(sent) => {
+            info!("News interjection sent: {}", final_message);
+            Ok(Some(sent.id))
+        }
+        Err(e) => {
+            error!("Error sending news interjection: {:?}", e);
+            Ok(None)
+        }
+    }
+}
+
+/// The channel's recent conversation, oldest first, as "name: message" lines
+pub async fn recent_context(
+    message_store: &Option<Arc<dyn MessageStore>>,
+    gemini_context_messages: usize,
+    channel_id: ChannelId,
+) -> String {
+    let Some(db) = message_store else {
+        return String::new();
+    };
+
+    match db
+        .get_recent(
             gemini_context_messages,
-            Some(msg.channel_id.to_string().as_str()),
+            Some(channel_id.to_string().as_str()),
         )
         .await
-        {
-            Ok(messages) => {
-                let mut chronological = messages;
-                chronological.reverse();
-                chronological
-                    .iter()
-                    .map(|(_author, display_name, _pronouns, content, _reply)| {
-                        format!("{}: {}", display_name, content)
-                    })
-                    .collect::<Vec<_>>()
-                    .join("\n")
-            }
-            Err(_) => String::new(),
+    {
+        Ok(messages) => messages
+            .iter()
+            .rev()
+            .map(|(_author, display_name, _pronouns, content, _reply)| {
+                format!("{}: {}", display_name, content)
+            })
+            .collect::<Vec<_>>()
+            .join("\n"),
+        Err(e) => {
+            error!(
+                "Error retrieving recent messages for news interjection: {:?}",
+                e
+            );
+            String::new()
         }
-    } else {
-        String::new()
-    };
+    }
+}
+
+/// Let Gemini pick a cached headline worth sharing, most relevant to the conversation
+/// first, and write the message for it: a short comment followed by the article's real
+/// URL. `None` when there are no headlines yet or Gemini passes.
+pub async fn choose_news_interjection(
+    gemini_client: &GeminiClient,
+    bot_name: &str,
+    context_text: &str,
+    headline_cache: &HeadlineCache,
+) -> Result<Option<String>> {
+    // Get cached headlines
+    let headlines = headline_cache.read().await;
+    if headlines.is_empty() {
+        info!("News interjection: no headlines cached yet");
+        return Ok(None);
+    }
 
     // Format headlines for Gemini to pick from
-    let headline_list: String = headlines
+    let candidates: Vec<Headline> = rank_headlines(&headlines, context_text)
+        .into_iter()
+        .take(30)
+        .cloned()
+        .collect();
+    drop(headlines);
+    let headline_list: String = candidates
         .iter()
         .enumerate()
-        .take(30)
         .map(|(i, h)| format!("{}. [{}] {} - {}", i + 1, h.source, h.title, h.url))
         .collect::<Vec<_>>()
         .join("\n");
 
     let personality = gemini_client.prompt_templates().personality();
     let prompt = format!(
-        "You are {_bot_name}, a Discord bot. {personality}\n\n\
+        "You are {bot_name}, a Discord bot. {personality}\n\n\
         Below are real headlines from news feeds, and the recent conversation.\n\n\
         HEADLINES:\n{headline_list}\n\n\
         RECENT CONVERSATION:\n{context_text}\n\n\
@@ -82,44 +131,27 @@ pub async fn handle_news_interjection(
         - If nothing genuinely stands out, just pass"
     );
 
-    match gemini_client.generate_content(&prompt).await {
-        Ok(response) => {
-            let trimmed = response.trim();
-
-            if trimmed.to_lowercase().starts_with("pass") {
-                info!("News interjection: AI decided to pass");
-                return Ok(false);
-            }
-
-            // Parse the response
-            if let Some((headline, comment)) = parse_selection(trimmed, &headlines) {
-                let final_message = format!("{} {}", comment, headline.url);
-
-                if let Err(e) = msg.channel_id.broadcast_typing(&ctx.http).await {
-                    error!("Failed to send typing indicator: {:?}", e);
-                }
-
-                apply_realistic_delay(&final_message, ctx, msg.channel_id).await;
-
-                if let Err(e) = msg.channel_id.say(&ctx.http, &final_message).await {
-                    error!("Error sending news interjection: {:?}", e);
-                } else {
-                    info!("News interjection sent: {}", final_message);
-                    return Ok(true);
-                }
-            } else {
-                info!(
-                    "News interjection: could not parse AI selection: {}",
-                    trimmed
-                );
-            }
-        }
-        Err(e) => {
-            error!("News interjection API error: {:?}", e);
-        }
+    let response = gemini_client
+        .generate_content_for(&prompt, GeminiTask::Interjection)
+        .await?;
+    let trimmed = response.trim();
+
+    if trimmed.to_lowercase().starts_with("pass") {
+        info!("News interjection: AI decided to pass");
+        return Ok(None);
     }
 
-    Ok(false)
+    // Parse the response
+    match parse_selection(trimmed, &candidates) {
+        Some((headline, comment)) => Ok(Some(format!("{} {}", comment, headline.url))),
+        None => {
+            info!(
+                "News interjection: could not parse AI selection: {}",
+                trimmed
+            );
+            Ok(None)
+        }
+    }
 }
 
 /// Parse the AI's selection response to extract the chosen headline and comment
@@ -1,4 +1,5 @@
-use crate::gemini_api::GeminiClient;
+use crate::gemini_api::{GeminiClient, GeminiTask};
+use crate::http_client;
 use anyhow::Result;
 use tracing::{error, info};
 
@@ -12,13 +13,12 @@ pub async fn verify_news_article(
     // First, fetch the actual page content
     info!("Fetching page content from: {}", article_url);
 
-    let client = reqwest::Client::builder()
+    let request = http_client::shared()
+        .get(article_url)
         .timeout(std::time::Duration::from_secs(15))
-        .user_agent("Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/121.0.0.0 Safari/537.36")
-        .redirect(reqwest::redirect::Policy::limited(3))
-        .build()?;
+        .header(reqwest::header::USER_AGENT, "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/121.0.0.0 Safari/537.36");
 
-    let response = match client.get(article_url).send().await {
+    let response = match request.send().await {
         Ok(resp) => resp,
         Err(e) => {
             error!("Failed to fetch URL {}: {:?}", article_url, e);
@@ -124,7 +124,10 @@ pub async fn verify_news_article(
     );
 
     // Send the prompt to Gemini
-    match gemini_client.generate_content(&prompt).await {
+    match gemini_client
+        .generate_content_for(&prompt, GeminiTask::Evaluation)
+        .await
+    {
         Ok(response) => {
             let response = response.trim().to_uppercase();
             info!("News verification response: {}", response);
@@ -186,67 +189,3 @@ fn extract_text_from_html(html: &str) -> String {
     // Clean up whitespace
     result.split_whitespace().collect::<Vec<_>>().join(" ")
 }
-
-/// Extract the article title and URL from a formatted news interjection
-pub fn extract_article_info(text: &str) -> Option<(String, String)> {
-    // Look for the pattern "Article title: https://..."
-    if let Some(colon_pos) = text.find(": http") {
-        let title = text[0..colon_pos].trim().to_string();
-
-        // Find the end of the URL (space, newline, or end of string)
-        let url_start = colon_pos + 2; // Skip the ": "
-        let url_end = text[url_start..]
-            .find(|c: char| c.is_whitespace())
-            .map_or(text.len(), |pos| url_start + pos);
-
-        let url = text[url_start..url_end].trim().to_string();
-
-        // Extract the summary (everything after the URL)
-        let _summary = if url_end < text.len() {
-            text[url_end..].trim().to_string()
-        } else {
-            String::new()
-        };
-
-        Some((title, url))
-    } else {
-        None
-    }
-}
-
-/// Validate if a URL actually exists, follow redirects, and check content type
-pub async fn validate_url_exists(url: &str) -> anyhow::Result<(bool, Option<String>)> {
-    info!("Validating URL exists: {}", url);
-
-    let client = reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(10))
-        .user_agent("Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36")
-        .build()?;
-
-    match client.get(url).send().await {
-        Ok(response) => {
-            let status = response.status();
-            let final_url = response.url().to_string();
-
-            let content_type = response
-                .headers()
-                .get(reqwest::header::CONTENT_TYPE)
-                .and_then(|v| v.to_str().ok())
-                .unwrap_or("");
-
-            let is_html = content_type.contains("text/html")
-                || content_type.contains("application/xhtml+xml");
-
-            if !is_html {
-                return Ok((false, None));
-            }
-
-            if status.is_success() {
-                Ok((true, Some(final_url)))
-            } else {
-                Ok((false, None))
-            }
-        }
-        Err(_) => Ok((false, None)),
-    }
-}
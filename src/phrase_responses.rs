@@ -0,0 +1,251 @@
+use rand::RngExt;
+use serde::Deserialize;
+use tracing::{info, warn};
+
+/// One `[[phrase_responses]]` entry in CrowConfig.toml, as written
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct PhraseResponseConfig {
+    pub trigger: Option<String>,
+    pub response: Option<String>,
+    #[serde(rename = "match")]
+    pub match_mode: Option<String>,
+    pub probability: Option<String>,
+}
+
+/// How a phrase response's trigger has to appear in a message
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchMode {
+    /// Anywhere in the message
+    Contains,
+    /// As the whole message, give or take surrounding spaces and punctuation
+    Exact,
+}
+
+/// A checked call-and-response pair
+#[derive(Debug, Clone, PartialEq)]
+pub struct PhraseResponse {
+    /// Lowercase, so matching ignores case
+    pub trigger: String,
+    pub response: String,
+    pub mode: MatchMode,
+    /// Chance of answering when the trigger matches, 0.0 to 1.0
+    pub probability: f64,
+}
+
+impl PhraseResponse {
+    fn new(trigger: &str, response: &str, mode: MatchMode) -> Self {
+        Self {
+            trigger: trigger.to_lowercase(),
+            response: response.to_string(),
+            mode,
+            probability: 1.0,
+        }
+    }
+
+    /// Whether a message's content sets this response off, ignoring case
+    pub fn matches(&self, content: &str) -> bool {
+        let content = content.to_lowercase();
+        match self.mode {
+            MatchMode::Contains => content.contains(&self.trigger),
+            MatchMode::Exact => {
+                content.trim_matches(|c: char| c.is_whitespace() || c.is_ascii_punctuation())
+                    == self.trigger
+            }
+        }
+    }
+}
+
+/// The responses used when none are configured
+pub fn default_phrase_responses() -> Vec<PhraseResponse> {
+    vec![
+        PhraseResponse::new("lisa needs braces", "DENTAL PLAN!", MatchMode::Contains),
+        PhraseResponse::new("stop", "Hammer time!", MatchMode::Exact),
+        PhraseResponse::new("my spoon is too big", "I am a banana!", MatchMode::Contains),
+        PhraseResponse::new("whoa", "I know kung fu!", MatchMode::Exact),
+        PhraseResponse::new("woah", "I know kung fu!", MatchMode::Exact),
+    ]
+}
+
+// Check one configured response, saying what's wrong with it if it can't be used
+fn validate_response(entry: &PhraseResponseConfig) -> Result<PhraseResponse, String> {
+    let trigger = entry
+        .trigger
+        .as_deref()
+        .map(str::trim)
+        .filter(|trigger| !trigger.is_empty())
+        .ok_or("missing trigger")?;
+
+    let response = entry
+        .response
+        .as_deref()
+        .map(str::trim)
+        .filter(|response| !response.is_empty())
+        .ok_or("missing response")?;
+
+    let mode = match entry
+        .match_mode
+        .as_deref()
+        .map(|mode| mode.trim().to_lowercase())
+        .as_deref()
+    {
+        None | Some("contains") => MatchMode::Contains,
+        Some("exact") => MatchMode::Exact,
+        Some(other) => {
+            return Err(format!(
+                "match \"{other}\" must be \"contains\" or \"exact\""
+            ))
+        }
+    };
+
+    let probability = match entry.probability.as_deref().map(str::trim) {
+        None => 1.0,
+        Some(value) => value
+            .parse::<f64>()
+            .ok()
+            .filter(|p| (0.0..=1.0).contains(p))
+            .ok_or_else(|| format!("probability \"{value}\" must be between 0 and 1"))?,
+    };
+
+    Ok(PhraseResponse {
+        probability,
+        ..PhraseResponse::new(trigger, response, mode)
+    })
+}
+
+/// Check the configured phrase responses, logging and skipping any that are invalid. With
+/// none configured, the built-in ones are used.
+pub fn parse_phrase_responses(entries: &[PhraseResponseConfig]) -> Vec<PhraseResponse> {
+    if entries.is_empty() {
+        return default_phrase_responses();
+    }
+
+    let mut responses = Vec::new();
+    for (i, entry) in entries.iter().enumerate() {
+        match validate_response(entry) {
+            Ok(response) => {
+                info!(
+                    "Phrase response to \"{}\" ({:?}, probability {})",
+                    response.trigger, response.mode, response.probability
+                );
+                responses.push(response);
+            }
+            Err(problem) => {
+                warn!("Skipping phrase response {}: {}", i + 1, problem);
+            }
+        }
+    }
+    responses
+}
+
+/// The response to a message: the first entry whose trigger matches and whose probability
+/// roll succeeds
+pub fn find_response<'a>(
+    responses: &'a [PhraseResponse],
+    content: &str,
+    rng: &mut impl RngExt,
+) -> Option<&'a str> {
+    responses
+        .iter()
+        .find(|response| {
+            response.matches(content) && rng.random_bool(response.probability.clamp(0.0, 1.0))
+        })
+        .map(|response| response.response.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_match_modes() {
+        let braces = PhraseResponse::new("Lisa Needs Braces", "DENTAL PLAN!", MatchMode::Contains);
+        assert!(braces.matches("lisa needs braces"));
+        assert!(braces.matches("Well, LISA NEEDS BRACES, so..."));
+        assert!(!braces.matches("lisa needs glasses"));
+
+        let whoa = PhraseResponse::new("whoa", "I know kung fu!", MatchMode::Exact);
+        assert!(whoa.matches("whoa"));
+        assert!(whoa.matches("  Whoa!! "));
+        assert!(!whoa.matches("whoa there"));
+        assert!(!whoa.matches("whoah"));
+        assert!(!whoa.matches(""));
+    }
+
+    #[test]
+    fn test_find_response() {
+        let mut rng = rand::rng();
+        let responses = default_phrase_responses();
+        assert_eq!(
+            find_response(&responses, "My spoon is too big!", &mut rng),
+            Some("I am a banana!")
+        );
+        assert_eq!(
+            find_response(&responses, "Woah.", &mut rng),
+            Some("I know kung fu!")
+        );
+        assert_eq!(
+            find_response(&responses, "Stop!", &mut rng),
+            Some("Hammer time!")
+        );
+        assert_eq!(find_response(&responses, "don't stop", &mut rng), None);
+
+        // An entry that never fires lets a later one answer
+        let mut never = PhraseResponse::new("spoon", "never", MatchMode::Contains);
+        never.probability = 0.0;
+        let responses = [
+            never,
+            PhraseResponse::new("spoon", "always", MatchMode::Contains),
+        ];
+        for _ in 0..20 {
+            assert_eq!(find_response(&responses, "spoon", &mut rng), Some("always"));
+        }
+    }
+
+    #[test]
+    fn test_parse_phrase_responses() {
+        let config: crate::config::Config = toml::from_str(
+            r#"
+discord_token = "token"
+
+[[phrase_responses]]
+trigger = "Hello There"
+response = "General Kenobi!"
+match = "exact"
+probability = "0.5"
+
+[[phrase_responses]]
+trigger = "ni"
+response = "We want a shrubbery"
+
+[[phrase_responses]]
+response = "No trigger"
+
+[[phrase_responses]]
+trigger = "hi"
+response = "hello"
+match = "regex"
+
+[[phrase_responses]]
+trigger = "hi"
+response = "hello"
+probability = "2"
+"#,
+        )
+        .unwrap();
+
+        let responses = parse_phrase_responses(&config.phrase_responses.unwrap());
+        assert_eq!(
+            responses,
+            vec![
+                PhraseResponse {
+                    probability: 0.5,
+                    ..PhraseResponse::new("hello there", "General Kenobi!", MatchMode::Exact)
+                },
+                PhraseResponse::new("ni", "We want a shrubbery", MatchMode::Contains),
+            ]
+        );
+
+        // Nothing configured keeps the built-in responses
+        assert_eq!(parse_phrase_responses(&[]), default_phrase_responses());
+    }
+}
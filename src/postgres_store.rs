@@ -0,0 +1,707 @@
+use crate::db_utils::{
+    self, like_pattern, ContextMessage, DateRange, HistorySearchHit, LastMessage, Memory, NameMatch,
+};
+use crate::media_utils::{with_media_markers, StoredMedia};
+use crate::message_history::StoredMessage;
+use crate::message_store::{LastSeen, MessageStore};
+use anyhow::{anyhow, Context as AnyhowContext, Result};
+use serenity::async_trait;
+use serenity::model::channel::Message;
+use serenity::model::id::{ChannelId, MessageId};
+use serenity::model::Timestamp;
+use sqlx::postgres::{PgPool, PgPoolOptions};
+use sqlx::Row;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tokio_rusqlite::Connection as SqliteConnection;
+use tracing::info;
+
+// Connections kept open to the shared database
+const MAX_CONNECTIONS: u32 = 5;
+
+// Same layout as the SQLite messages table. Messages saved without a Discord message
+// (message_id '0') are exempt from the uniqueness that makes saves idempotent.
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS messages (
+        id BIGSERIAL PRIMARY KEY,
+        message_id TEXT NOT NULL,
+        channel_id TEXT NOT NULL,
+        guild_id TEXT,
+        author_id TEXT NOT NULL,
+        author TEXT NOT NULL,
+        display_name TEXT,
+        content TEXT NOT NULL,
+        timestamp BIGINT NOT NULL,
+        referenced_message_id TEXT,
+        media TEXT
+    );
+    ALTER TABLE messages ADD COLUMN IF NOT EXISTS media TEXT;
+    CREATE UNIQUE INDEX IF NOT EXISTS idx_message_message_id
+        ON messages (message_id) WHERE message_id <> '0';
+    CREATE INDEX IF NOT EXISTS idx_message_channel_timestamp ON messages (channel_id, timestamp);
+    CREATE INDEX IF NOT EXISTS idx_message_author_id ON messages (author_id);
+    CREATE TABLE IF NOT EXISTS last_seen (
+        channel_id TEXT PRIMARY KEY,
+        message_id TEXT NOT NULL,
+        timestamp BIGINT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS no_store_users (
+        user_id TEXT PRIMARY KEY,
+        forgotten_at BIGINT NOT NULL
+    );
+";
+
+/// Message history in a PostgreSQL database, so several bot instances can share it.
+/// Messages, channel positions and !forgetme opt-outs live in Postgres; registered
+/// pronouns and protected messages are still read from the local SQLite database.
+pub struct PostgresMessageStore {
+    pool: PgPool,
+    local: Arc<Mutex<SqliteConnection>>,
+}
+
+impl PostgresMessageStore {
+    /// Connect and create the tables if they don't exist yet
+    pub async fn connect(url: &str, local: Arc<Mutex<SqliteConnection>>) -> Result<Self> {
+        let pool = PgPoolOptions::new()
+            .max_connections(MAX_CONNECTIONS)
+            .connect(url)
+            .await
+            .context("Couldn't connect to the PostgreSQL message store")?;
+        sqlx::raw_sql(SCHEMA)
+            .execute(&pool)
+            .await
+            .context("Couldn't create the PostgreSQL message tables")?;
+        info!("Connected to the PostgreSQL message store");
+        Ok(PostgresMessageStore { pool, local })
+    }
+}
+
+fn now() -> i64 {
+    chrono::Utc::now().timestamp()
+}
+
+#[async_trait]
+impl MessageStore for PostgresMessageStore {
+    fn name(&self) -> &'static str {
+        "postgres"
+    }
+
+    async fn save(
+        &self,
+        author: &str,
+        display_name: &str,
+        content: &str,
+        message: Option<&Message>,
+    ) -> Result<bool> {
+        let display_name = crate::display_name::clean_display_name(display_name);
+
+        let Some(msg) = message else {
+            sqlx::query(
+                "INSERT INTO messages (message_id, channel_id, author_id, author, display_name, content, timestamp)
+                 VALUES ('0', '0', '0', $1, $2, $3, $4)",
+            )
+            .bind(author)
+            .bind(&display_name)
+            .bind(content)
+            .bind(now())
+            .execute(&self.pool)
+            .await?;
+            return Ok(true);
+        };
+
+        let author_id = msg.author.id.to_string();
+        let opted_out = sqlx::query("SELECT 1 FROM no_store_users WHERE user_id = $1")
+            .bind(&author_id)
+            .fetch_optional(&self.pool)
+            .await?
+            .is_some();
+        if opted_out {
+            return Ok(false);
+        }
+
+        // Another instance may have stored the same message already
+        let inserted: bool = sqlx::query(
+            "INSERT INTO messages (
+                message_id, channel_id, guild_id, author_id, author, display_name, content, timestamp, referenced_message_id, media
+             ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+             ON CONFLICT (message_id) WHERE message_id <> '0'
+             DO UPDATE SET content = EXCLUDED.content, media = EXCLUDED.media
+             RETURNING (xmax = 0)",
+        )
+        .bind(msg.id.to_string())
+        .bind(msg.channel_id.to_string())
+        .bind(msg.guild_id.map(|id| id.to_string()))
+        .bind(&author_id)
+        .bind(author)
+        .bind(&display_name)
+        .bind(content)
+        .bind(now())
+        .bind(msg.referenced_message.as_ref().map(|m| m.id.to_string()))
+        .bind(StoredMedia::from_message(msg).to_json())
+        .fetch_one(&self.pool)
+        .await?
+        .try_get(0)?;
+
+        Ok(inserted)
+    }
+
+    async fn get_recent(
+        &self,
+        limit: usize,
+        channel_id: Option<&str>,
+    ) -> Result<Vec<ContextMessage>> {
+        let rows = sqlx::query(
+            "SELECT m.author, COALESCE(m.display_name, m.author), m.content, m.author_id,
+                    ref.display_name, ref.content, m.media
+             FROM messages m
+             LEFT JOIN messages ref
+                 ON ref.message_id = m.referenced_message_id
+             WHERE $1::TEXT IS NULL OR m.channel_id = $1
+             ORDER BY m.timestamp DESC, m.id DESC
+             LIMIT $2",
+        )
+        .bind(channel_id)
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let pronouns = db_utils::registered_pronouns_by_user(self.local.clone())
+            .await
+            .map_err(|e| anyhow!("Error reading registered pronouns: {}", e))?;
+
+        let mut messages = Vec::with_capacity(rows.len());
+        for row in rows {
+            let author_id: String = row.try_get(3)?;
+            let ref_display_name: Option<String> = row.try_get(4)?;
+            let ref_content: Option<String> = row.try_get(5)?;
+            let reply_context = ref_display_name
+                .zip(ref_content)
+                .map(|(name, content)| format!("{name}: {content}"));
+            messages.push((
+                row.try_get(0)?,
+                row.try_get(1)?,
+                pronouns.get(&author_id).cloned(),
+                with_media_markers(
+                    &row.try_get::<String, _>(2)?,
+                    row.try_get::<Option<String>, _>(6)?.as_deref(),
+                ),
+                reply_context,
+            ));
+        }
+
+        Ok(db_utils::finish_context_messages(messages))
+    }
+
+    async fn load_history(&self, limit: usize) -> Result<Vec<StoredMessage>> {
+        let rows = sqlx::query(
+            "SELECT message_id, channel_id, author, COALESCE(display_name, author), content,
+                    timestamp, referenced_message_id, media
+             FROM messages
+             ORDER BY timestamp DESC, id DESC
+             LIMIT $1",
+        )
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut messages = Vec::with_capacity(rows.len());
+        for row in rows.iter().rev() {
+            let Some(channel_id) = row
+                .try_get::<String, _>(1)?
+                .parse::<u64>()
+                .ok()
+                .filter(|id| *id != 0)
+            else {
+                continue;
+            };
+            messages.push(StoredMessage {
+                message_id: row.try_get::<String, _>(0)?.parse().unwrap_or(0),
+                channel_id,
+                author: row.try_get(2)?,
+                display_name: row.try_get(3)?,
+                content: with_media_markers(
+                    &row.try_get::<String, _>(4)?,
+                    row.try_get::<Option<String>, _>(7)?.as_deref(),
+                ),
+                timestamp: row.try_get(5)?,
+                referenced_message_id: row
+                    .try_get::<Option<String>, _>(6)?
+                    .and_then(|id| id.parse().ok()),
+            });
+        }
+
+        Ok(messages)
+    }
+
+    async fn trim(
+        &self,
+        default_limit: usize,
+        channel_limits: &HashMap<String, usize>,
+        keep_protected: bool,
+    ) -> Result<usize> {
+        let protected = if keep_protected {
+            db_utils::protected_message_ids(self.local.clone())
+                .await
+                .map_err(|e| anyhow!("Error reading protected messages: {}", e))?
+        } else {
+            Vec::new()
+        };
+
+        let channel_counts = sqlx::query(
+            "SELECT channel_id, COUNT(*) FROM messages
+             WHERE NOT (message_id = ANY($1))
+             GROUP BY channel_id",
+        )
+        .bind(&protected)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut total_deleted = 0;
+        let mut shared_count = 0;
+        for row in channel_counts {
+            let channel_id: String = row.try_get(0)?;
+            let count = row.try_get::<i64, _>(1)? as usize;
+            let Some(&limit) = channel_limits.get(&channel_id) else {
+                shared_count += count;
+                continue;
+            };
+            if count <= limit {
+                continue;
+            }
+
+            let deleted = sqlx::query(
+                "DELETE FROM messages WHERE id IN (
+                    SELECT id FROM messages WHERE channel_id = $1 AND NOT (message_id = ANY($2))
+                    ORDER BY timestamp ASC, id ASC LIMIT $3
+                 )",
+            )
+            .bind(&channel_id)
+            .bind(&protected)
+            .bind((count - limit) as i64)
+            .execute(&self.pool)
+            .await?;
+            total_deleted += deleted.rows_affected() as usize;
+        }
+
+        // Channels without their own limit share the default one
+        if shared_count > default_limit {
+            let configured: Vec<String> = channel_limits.keys().cloned().collect();
+            let deleted = sqlx::query(
+                "DELETE FROM messages WHERE id IN (
+                    SELECT id FROM messages
+                    WHERE NOT (channel_id = ANY($1)) AND NOT (message_id = ANY($2))
+                    ORDER BY timestamp ASC, id ASC LIMIT $3
+                 )",
+            )
+            .bind(&configured)
+            .bind(&protected)
+            .bind((shared_count - default_limit) as i64)
+            .execute(&self.pool)
+            .await?;
+            total_deleted += deleted.rows_affected() as usize;
+        }
+
+        Ok(total_deleted)
+    }
+
+    async fn last_seen_by_channel(&self) -> Result<LastSeen> {
+        let newest = sqlx::query(
+            "SELECT DISTINCT ON (channel_id) channel_id, timestamp, message_id
+             FROM messages
+             ORDER BY channel_id, timestamp DESC, id DESC",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut result = LastSeen::new();
+        for row in newest {
+            let channel_id: String = row.try_get(0)?;
+            let timestamp: i64 = row.try_get(1)?;
+            let message_id: String = row.try_get(2)?;
+            result.insert(
+                ChannelId::new(channel_id.parse::<u64>().unwrap_or_default()),
+                (
+                    Timestamp::from_unix_timestamp(timestamp).unwrap_or_default(),
+                    MessageId::new(message_id.parse::<u64>().unwrap_or_default()),
+                ),
+            );
+        }
+
+        let saved = sqlx::query("SELECT channel_id, message_id, timestamp FROM last_seen")
+            .fetch_all(&self.pool)
+            .await?;
+        for row in saved {
+            let channel_id: String = row.try_get(0)?;
+            let message_id: String = row.try_get(1)?;
+            let timestamp: i64 = row.try_get(2)?;
+            db_utils::merge_saved_position(&mut result, &channel_id, &message_id, timestamp);
+        }
+
+        Ok(result)
+    }
+
+    async fn persist_last_seen(&self, last_seen: &LastSeen) -> Result<usize> {
+        // Instances following the same channel only ever move its position forward
+        let mut tx = self.pool.begin().await?;
+        for (channel_id, (timestamp, message_id)) in last_seen {
+            sqlx::query(
+                "INSERT INTO last_seen (channel_id, message_id, timestamp) VALUES ($1, $2, $3)
+                 ON CONFLICT (channel_id) DO UPDATE SET
+                    message_id = EXCLUDED.message_id,
+                    timestamp = EXCLUDED.timestamp
+                 WHERE last_seen.timestamp <= EXCLUDED.timestamp",
+            )
+            .bind(channel_id.to_string())
+            .bind(message_id.to_string())
+            .bind(timestamp.unix_timestamp())
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(last_seen.len())
+    }
+
+    async fn random_dud_quotes(
+        &self,
+        username: Option<String>,
+        guild_id: Option<String>,
+        dates: DateRange,
+        min_length: usize,
+        count: usize,
+    ) -> Result<Vec<(String, String, String)>> {
+        let query = format!(
+            "SELECT author, COALESCE(display_name, author), content, media FROM messages
+             WHERE ($1::TEXT IS NULL OR lower(author) = lower($1) OR lower(display_name) = lower($1))
+               AND length(trim(content)) >= $2
+               AND ($3::TEXT IS NULL OR guild_id = $3)
+               AND {}
+             ORDER BY random() LIMIT $4",
+            dates.sql_predicate("timestamp")
+        );
+        let thresholds = if min_length > 0 {
+            vec![min_length, 0]
+        } else {
+            vec![0]
+        };
+
+        for threshold in thresholds {
+            let rows = sqlx::query(&query)
+                .bind(&username)
+                .bind(threshold as i32)
+                .bind(&guild_id)
+                .bind(count as i64)
+                .fetch_all(&self.pool)
+                .await?;
+            if !rows.is_empty() {
+                return rows
+                    .iter()
+                    .map(|row| {
+                        let content = with_media_markers(
+                            &row.try_get::<String, _>(2)?,
+                            row.try_get::<Option<String>, _>(3)?.as_deref(),
+                        );
+                        Ok((row.try_get(0)?, row.try_get(1)?, content))
+                    })
+                    .collect();
+            }
+        }
+        Ok(Vec::new())
+    }
+
+    async fn resolve_dud_name(
+        &self,
+        name: String,
+        guild_id: Option<String>,
+    ) -> Result<Option<NameMatch>> {
+        let exact: Option<i32> = sqlx::query_scalar(
+            "SELECT 1 FROM messages
+             WHERE (lower(author) = lower($1) OR lower(display_name) = lower($1))
+               AND ($2::TEXT IS NULL OR guild_id = $2)
+             LIMIT 1",
+        )
+        .bind(&name)
+        .bind(&guild_id)
+        .fetch_optional(&self.pool)
+        .await?;
+        if exact.is_some() {
+            return Ok(Some(NameMatch::Exact(name)));
+        }
+
+        let partial: Option<String> = sqlx::query_scalar(
+            "SELECT min(name) FROM (
+                 SELECT author AS name FROM messages
+                 WHERE author ILIKE $1 ESCAPE '\\' AND ($2::TEXT IS NULL OR guild_id = $2)
+                 UNION ALL
+                 SELECT display_name FROM messages
+                 WHERE display_name ILIKE $1 ESCAPE '\\' AND ($2::TEXT IS NULL OR guild_id = $2)
+             ) AS names
+             GROUP BY lower(name) ORDER BY COUNT(*) DESC, min(name) LIMIT 1",
+        )
+        .bind(like_pattern(&name))
+        .bind(&guild_id)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(partial.map(NameMatch::Partial))
+    }
+
+    async fn find_user_id_by_name(
+        &self,
+        name: String,
+        excluded_ids: Vec<String>,
+    ) -> Result<Option<String>> {
+        let user_id = sqlx::query_scalar(
+            "SELECT author_id FROM messages
+             WHERE author_id <> '0' AND NOT (author_id = ANY($2))
+               AND (lower(author) = lower($1) OR lower(display_name) = lower($1))
+             ORDER BY timestamp DESC LIMIT 1",
+        )
+        .bind(&name)
+        .bind(&excluded_ids)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(user_id)
+    }
+
+    async fn known_names(&self, user_id: String) -> Result<Vec<String>> {
+        let rows =
+            sqlx::query("SELECT DISTINCT author, display_name FROM messages WHERE author_id = $1")
+                .bind(&user_id)
+                .fetch_all(&self.pool)
+                .await?;
+
+        let mut names = Vec::new();
+        for row in rows {
+            names.push(row.try_get::<String, _>(0)?);
+            let display_name: Option<String> = row.try_get(1)?;
+            names.extend(display_name.filter(|name| !name.is_empty()));
+        }
+        Ok(names)
+    }
+
+    async fn forget_user(&self, user_id: String) -> Result<usize> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query(
+            "INSERT INTO no_store_users (user_id, forgotten_at) VALUES ($1, $2)
+             ON CONFLICT (user_id) DO NOTHING",
+        )
+        .bind(&user_id)
+        .bind(now())
+        .execute(&mut *tx)
+        .await?;
+        let deleted = sqlx::query("DELETE FROM messages WHERE author_id = $1")
+            .bind(&user_id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(deleted.rows_affected() as usize)
+    }
+
+    async fn delete_messages_by_author(&self, name: String) -> Result<usize> {
+        let deleted = sqlx::query(
+            "DELETE FROM messages WHERE lower(author) = lower($1) OR lower(display_name) = lower($1)",
+        )
+        .bind(&name)
+        .execute(&self.pool)
+        .await?;
+        Ok(deleted.rows_affected() as usize)
+    }
+
+    async fn count_messages(&self) -> Result<i64> {
+        let count = sqlx::query_scalar("SELECT COUNT(*) FROM messages")
+            .fetch_one(&self.pool)
+            .await?;
+        Ok(count)
+    }
+
+    async fn search_history(
+        &self,
+        term: String,
+        guild_id: String,
+        channel_id: String,
+        limit: usize,
+        offset: usize,
+    ) -> Result<Vec<HistorySearchHit>> {
+        let patterns: Vec<String> = term.split_whitespace().map(like_pattern).collect();
+        if patterns.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        // Like the SQLite search without FTS5: every word, anywhere, ignoring case. No guild
+        // (a DM) searches just the channel.
+        let guild_id = Some(guild_id).filter(|id| !id.is_empty());
+        let conditions: String = (0..patterns.len())
+            .map(|i| format!("content ILIKE ${} ESCAPE '\\' AND ", i + 5))
+            .collect();
+        let sql = format!(
+            "SELECT message_id, channel_id, COALESCE(guild_id, ''), author,
+                    COALESCE(display_name, ''), content, timestamp
+             FROM messages
+             WHERE {conditions}(guild_id = $1 OR ($1::TEXT IS NULL AND channel_id = $2))
+               AND content NOT LIKE '!%'
+             ORDER BY timestamp DESC, id DESC
+             LIMIT $3 OFFSET $4"
+        );
+        let mut query = sqlx::query(&sql)
+            .bind(&guild_id)
+            .bind(&channel_id)
+            .bind(limit as i64)
+            .bind(offset as i64);
+        for pattern in &patterns {
+            query = query.bind(pattern);
+        }
+
+        let rows = query.fetch_all(&self.pool).await?;
+        rows.iter()
+            .map(|row| {
+                Ok(HistorySearchHit {
+                    message_id: row.try_get(0)?,
+                    channel_id: row.try_get(1)?,
+                    guild_id: row.try_get(2)?,
+                    author: row.try_get(3)?,
+                    display_name: row.try_get(4)?,
+                    content: row.try_get(5)?,
+                    timestamp: row.try_get(6)?,
+                })
+            })
+            .collect()
+    }
+
+    async fn last_message(
+        &self,
+        author_id: Option<String>,
+        name: String,
+    ) -> Result<Option<LastMessage>> {
+        let row = sqlx::query(
+            "SELECT author, COALESCE(display_name, ''), content, timestamp FROM messages
+             WHERE (author_id = $1 OR ($1::TEXT IS NULL AND
+                    (lower(author) LIKE $2 ESCAPE '\\' OR lower(display_name) LIKE $2 ESCAPE '\\')))
+               AND content <> ''
+             ORDER BY timestamp DESC LIMIT 1",
+        )
+        .bind(&author_id)
+        .bind(like_pattern(&name.to_lowercase()))
+        .fetch_optional(&self.pool)
+        .await?;
+        row.map(|row| {
+            Ok((
+                row.try_get(0)?,
+                row.try_get(1)?,
+                row.try_get(2)?,
+                row.try_get::<i64, _>(3)?.max(0) as u64,
+            ))
+        })
+        .transpose()
+    }
+
+    async fn author_names(&self) -> Result<Vec<String>> {
+        let names: Vec<String> = sqlx::query_scalar(
+            "SELECT author FROM messages WHERE content <> ''
+             UNION
+             SELECT display_name FROM messages WHERE content <> '' AND display_name IS NOT NULL",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(names
+            .into_iter()
+            .filter(|name| !name.trim().is_empty())
+            .collect())
+    }
+
+    async fn random_memory(
+        &self,
+        exclude_name: String,
+        guild_id: Option<String>,
+    ) -> Result<Option<Memory>> {
+        let row = sqlx::query(
+            "SELECT content, author, COALESCE(display_name, author), timestamp FROM messages
+             WHERE length(content) >= 20 AND length(content) <= 300
+               AND content NOT LIKE '!%'
+               AND content NOT LIKE 'http://%'
+               AND content NOT LIKE 'https://%'
+               AND content NOT LIKE '%[Image:%'
+               AND content NOT LIKE '%[Video:%'
+               AND author <> $1 AND COALESCE(display_name, author) <> $1
+               AND ($2::TEXT IS NULL OR guild_id = $2)
+             ORDER BY random() * timestamp DESC
+             LIMIT 1",
+        )
+        .bind(&exclude_name)
+        .bind(&guild_id)
+        .fetch_optional(&self.pool)
+        .await?;
+        row.map(|row| {
+            Ok((
+                row.try_get(0)?,
+                row.try_get(1)?,
+                row.try_get(2)?,
+                row.try_get(3)?,
+            ))
+        })
+        .transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message_store::tests::check_store_contract;
+    use serenity::model::id::GuildId;
+
+    // Needs a scratch database, whose tables it empties:
+    // DATABASE_URL=postgres://... cargo test -- --ignored
+    #[tokio::test]
+    #[ignore]
+    async fn test_postgres_store_contract() {
+        let url = std::env::var("DATABASE_URL").expect("DATABASE_URL isn't set");
+        let local = db_utils::initialize_database(":memory:").await.unwrap();
+        let store = PostgresMessageStore::connect(&url, local).await.unwrap();
+        sqlx::raw_sql("TRUNCATE messages, last_seen, no_store_users")
+            .execute(&store.pool)
+            .await
+            .unwrap();
+
+        check_store_contract(&store).await;
+
+        // No guild and no reply are stored as NULL, and search scopes them to the channel
+        let mut dm = Message::default();
+        dm.id = MessageId::new(100);
+        dm.channel_id = ChannelId::new(30);
+        assert!(store
+            .save("alice", "Alice", "a direct message", Some(&dm))
+            .await
+            .unwrap());
+        let (guild_id, referenced): (Option<String>, Option<String>) = sqlx::query_as(
+            "SELECT guild_id, referenced_message_id FROM messages WHERE message_id = '100'",
+        )
+        .fetch_one(&store.pool)
+        .await
+        .unwrap();
+        assert_eq!((guild_id, referenced), (None, None));
+
+        let mut guild_message = Message::default();
+        guild_message.id = MessageId::new(101);
+        guild_message.channel_id = ChannelId::new(31);
+        guild_message.guild_id = Some(GuildId::new(5));
+        store
+            .save("bob", "Bob", "a direct reply", Some(&guild_message))
+            .await
+            .unwrap();
+
+        let search = |guild: &str, channel: &str| {
+            store.search_history(
+                "direct".to_string(),
+                guild.to_string(),
+                channel.to_string(),
+                10,
+                0,
+            )
+        };
+        let hits = search("", "30").await.unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].guild_id, "");
+        let hits = search("5", "99").await.unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].message_id, "101");
+    }
+}
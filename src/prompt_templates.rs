@@ -1,5 +1,19 @@
 use std::collections::HashMap;
 
+/// Instruction appended to reply prompts when uncertainty mode is enabled
+pub const UNCERTAINTY_INSTRUCTION: &str = "UNCERTAINTY RULE: If the message asks a factual question and you are not confident \
+your answer is correct, do NOT state a guess as fact. Hedge naturally (\"I think...\", \"not sure, but...\") or say you're unsure. \
+When an answer is a guess, end it with the marker [LOW CONFIDENCE].";
+
+/// Marker the model appends to answers it isn't confident about
+pub const LOW_CONFIDENCE_MARKER: &str = "[LOW CONFIDENCE]";
+
+/// Most persona examples included in a prompt; extras in the config are ignored
+pub const MAX_PERSONA_EXAMPLES: usize = 5;
+
+/// Longest input or response (in characters) kept from a persona example
+pub const MAX_PERSONA_EXAMPLE_CHARS: usize = 300;
+
 /// Struct to hold personality configuration and prompt templates
 #[derive(Clone)]
 pub struct PromptTemplates {
@@ -14,6 +28,12 @@ pub struct PromptTemplates {
 
     /// Default personality description
     default_personality: String,
+
+    /// Extra instruction added to reply prompts asking the model to flag uncertain answers
+    uncertainty_instruction: Option<String>,
+
+    /// Example (message, ideal response) pairs shown to the model to anchor the bot's voice
+    persona_examples: Vec<(String, String)>,
 }
 
 impl PromptTemplates {
@@ -179,11 +199,71 @@ impl PromptTemplates {
             NEVER put text in quotation marks. No quotes from movies, TV, or any media. Just share the topic in your own words.".to_string()
         );
 
+        templates.insert(
+            "vibe_check".to_string(),
+            "You are {bot_name}, a Discord bot with a {humor} sense of humor.\n\n\
+            Read the recent conversation below and describe the current mood or \"vibe\" of the channel.\n\n\
+            Recent conversation:\n{context}\n\n\
+            Rules:\n\
+            1. Respond with ONE short sentence (under 15 words) followed by ONE fitting emoji, e.g. \"Chaotic good energy 🔥\"\n\
+            2. Describe the MOOD, not the content - don't summarize what was said\n\
+            3. Don't name or single out individual people\n\
+            4. No quotation marks, no hashtags, no preamble like \"The vibe is\"\n\
+            5. Keep it playful and kind - never mean-spirited".to_string()
+        );
+
+        templates.insert(
+            "eightball".to_string(),
+            "You are {bot_name}, a Discord bot with a {humor} sense of humor, standing in for a Magic 8-Ball.\n\n\
+            Question: {question}\n\n\
+            Rules:\n\
+            1. Answer in ONE short sentence (under 20 words), like an 8-ball with attitude\n\
+            2. Give a real verdict - yes, no, or a cryptic maybe\n\
+            3. Be snarky but never mean-spirited\n\
+            4. No quotation marks and no preamble".to_string()
+        );
+
+        templates.insert(
+            "summarize".to_string(),
+            "Summarize the Discord conversation below for someone who just arrived.\n\n\
+            Conversation:\n{transcript}\n\n\
+            Rules:\n\
+            1. Respond with 3 to 6 short bullet points, each starting with \"- \"\n\
+            2. Cover the main topics, decisions, and open questions, not every message\n\
+            3. Mention people by the names shown when it matters who said something\n\
+            4. No preamble or closing remarks - just the bullets"
+                .to_string(),
+        );
+
+        templates.insert(
+            "translate".to_string(),
+            "Translate the text below into {language}.\n\n\
+            Text:\n{text}\n\n\
+            Rules:\n\
+            1. Respond with ONLY the translation - no notes, quotes, or preamble\n\
+            2. Keep the tone, slang, and emoji of the original\n\
+            3. If the text is already in {language}, respond with it unchanged"
+                .to_string(),
+        );
+
+        // Template for rewording image prompts the generator refused
+        templates.insert(
+            "image_prompt_rewrite".to_string(),
+            "An image generator refused this prompt, probably because of its content filter:\n\n\
+            {prompt}\n\n\
+            Rewrite it so it would be accepted while keeping as much of the original idea as possible. \
+            Soften or drop anything graphic, sexual, or about real people. \
+            Respond with ONLY the rewritten prompt on a single line, no quotes or explanation. \
+            If it can't be made acceptable, respond with just PASS.".to_string()
+        );
+
         Self {
             bot_name,
             personality_traits,
             templates,
             default_personality,
+            uncertainty_instruction: None,
+            persona_examples: Vec::new(),
         }
     }
 
@@ -211,6 +291,45 @@ impl PromptTemplates {
         self.default_personality = personality.to_string();
     }
 
+    /// Enable or disable the uncertainty instruction in reply prompts
+    pub fn set_uncertainty_instruction(&mut self, instruction: Option<&str>) {
+        self.uncertainty_instruction = instruction.map(|i| i.to_string());
+    }
+
+    /// Set the few-shot persona examples, keeping at most `MAX_PERSONA_EXAMPLES` and
+    /// cutting each side down to `MAX_PERSONA_EXAMPLE_CHARS`
+    pub fn set_persona_examples(&mut self, examples: Vec<(String, String)>) {
+        self.persona_examples = examples
+            .into_iter()
+            .take(MAX_PERSONA_EXAMPLES)
+            .map(|(input, response)| {
+                (
+                    truncate_example(&input, MAX_PERSONA_EXAMPLE_CHARS),
+                    truncate_example(&response, MAX_PERSONA_EXAMPLE_CHARS),
+                )
+            })
+            .collect();
+    }
+
+    /// Add the persona examples (if any) to the end of a prompt
+    pub fn with_persona_examples(&self, prompt: &str) -> String {
+        if self.persona_examples.is_empty() {
+            return prompt.to_string();
+        }
+
+        let examples = self
+            .persona_examples
+            .iter()
+            .map(|(input, response)| format!("Message: {input}\nYou: {response}"))
+            .collect::<Vec<_>>()
+            .join("\n\n");
+        format!(
+            "{prompt}\n\nHere are some examples of how {} talks. Match this voice and tone, \
+            but don't reuse these exact lines:\n\n{examples}",
+            self.bot_name
+        )
+    }
+
     /// Format a prompt using a template and provided values
     pub fn format_prompt(&self, template_name: &str, values: &HashMap<String, String>) -> String {
         let template = self
@@ -247,7 +366,12 @@ impl PromptTemplates {
         values.insert("user".to_string(), user_name.to_string());
         values.insert("context".to_string(), context.to_string());
 
-        self.format_prompt("general_response", &values)
+        let prompt = self.format_prompt("general_response", &values);
+        let prompt = self.with_persona_examples(&prompt);
+        match &self.uncertainty_instruction {
+            Some(instruction) => format!("{prompt}\n\n{instruction}"),
+            None => prompt,
+        }
     }
 
     /// Format a fact interjection prompt
@@ -255,7 +379,7 @@ impl PromptTemplates {
         let mut values = HashMap::new();
         values.insert("context".to_string(), context.to_string());
 
-        self.format_prompt("fact_interjection", &values)
+        self.with_persona_examples(&self.format_prompt("fact_interjection", &values))
     }
 
     /// Format a news interjection prompt
@@ -267,6 +391,47 @@ impl PromptTemplates {
         self.format_prompt("news_interjection", &values)
     }
 
+    /// Format a vibe check prompt
+    pub fn format_vibe_check(&self, context: &str) -> String {
+        let mut values = HashMap::new();
+        values.insert("context".to_string(), context.to_string());
+
+        self.format_prompt("vibe_check", &values)
+    }
+
+    /// Format a Magic 8-Ball prompt
+    pub fn format_eightball(&self, question: &str) -> String {
+        let mut values = HashMap::new();
+        values.insert("question".to_string(), question.to_string());
+
+        self.format_prompt("eightball", &values)
+    }
+
+    /// Format a prompt summarizing a channel transcript
+    pub fn format_summarize(&self, transcript: &str) -> String {
+        let mut values = HashMap::new();
+        values.insert("transcript".to_string(), transcript.to_string());
+
+        self.format_prompt("summarize", &values)
+    }
+
+    /// Format a translation prompt
+    pub fn format_translate(&self, language: &str, text: &str) -> String {
+        let mut values = HashMap::new();
+        values.insert("language".to_string(), language.to_string());
+        values.insert("text".to_string(), text.to_string());
+
+        self.format_prompt("translate", &values)
+    }
+
+    /// Format a request to reword a refused image prompt
+    pub fn format_image_prompt_rewrite(&self, prompt: &str) -> String {
+        let mut values = HashMap::new();
+        values.insert("prompt".to_string(), prompt.to_string());
+
+        self.format_prompt("image_prompt_rewrite", &values)
+    }
+
     /// Format a custom prompt with personality
     pub fn format_custom(&self, template: &str, values: &HashMap<String, String>) -> String {
         let mut formatted = template.replace("{bot_name}", &self.bot_name);
@@ -285,3 +450,160 @@ impl PromptTemplates {
         formatted
     }
 }
+
+/// Parse persona examples from config: one `message => response` pair per line,
+/// skipping blank and malformed lines
+pub fn parse_persona_examples(spec: &str) -> Vec<(String, String)> {
+    spec.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .filter_map(|line| {
+            let parsed = line.split_once("=>").and_then(|(input, response)| {
+                let input = input.trim();
+                let response = response.trim();
+                (!input.is_empty() && !response.is_empty())
+                    .then(|| (input.to_string(), response.to_string()))
+            });
+            if parsed.is_none() {
+                tracing::warn!("Ignoring malformed persona example: {}", line);
+            }
+            parsed
+        })
+        .collect()
+}
+
+// Cut an example down to `max_chars`, marking the cut with an ellipsis
+fn truncate_example(text: &str, max_chars: usize) -> String {
+    if text.chars().count() <= max_chars {
+        return text.to_string();
+    }
+    let truncated: String = text.chars().take(max_chars - 1).collect();
+    format!("{}…", truncated.trim_end())
+}
+
+/// Note telling the model which channel topic it's talking under, or None when the
+/// channel has no topic
+pub fn channel_topic_note(topic: Option<&str>) -> Option<String> {
+    let topic = topic.map(str::trim).filter(|topic| !topic.is_empty())?;
+    Some(format!(
+        "[This conversation is in a Discord channel with the topic \"{topic}\". Stay on-theme for the channel where it fits.]"
+    ))
+}
+
+/// Put the channel topic note (if any) ahead of a prompt or message
+pub fn with_channel_topic(prompt: &str, topic: Option<&str>) -> String {
+    match channel_topic_note(topic) {
+        Some(note) => format!("{note}\n\n{prompt}"),
+        None => prompt.to_string(),
+    }
+}
+
+/// Strip the low-confidence marker from a response, hedging it if the model flagged it
+pub fn apply_confidence_marker(response: &str) -> String {
+    if !response.contains(LOW_CONFIDENCE_MARKER) {
+        return response.to_string();
+    }
+
+    let stripped = response.replace(LOW_CONFIDENCE_MARKER, "");
+    let stripped = stripped.trim();
+    let lower = stripped.to_lowercase();
+
+    // Leave it alone if the model already hedged in its own words
+    let already_hedged = [
+        "i think",
+        "not sure",
+        "i'm not certain",
+        "i believe",
+        "probably",
+    ]
+    .iter()
+    .any(|hedge| lower.contains(hedge));
+
+    if already_hedged || stripped.is_empty() {
+        stripped.to_string()
+    } else {
+        format!("Not 100% sure on this one: {stripped}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_uncertainty_instruction_in_reply_prompt() {
+        let mut templates = PromptTemplates::new("TestBot".to_string());
+
+        let prompt = templates.format_general_response("What year was it?", "Alice", "");
+        assert!(!prompt.contains(UNCERTAINTY_INSTRUCTION));
+
+        templates.set_uncertainty_instruction(Some(UNCERTAINTY_INSTRUCTION));
+        let prompt = templates.format_general_response("What year was it?", "Alice", "");
+        assert!(prompt.contains(UNCERTAINTY_INSTRUCTION));
+        assert!(prompt.contains("What year was it?"));
+    }
+
+    #[test]
+    fn test_channel_topic_in_reply_prompt() {
+        let templates = PromptTemplates::new("TestBot".to_string());
+
+        let message = with_channel_topic("how do I borrow this?", Some("Rust help and questions"));
+        let prompt = templates.format_general_response(&message, "Alice", "");
+        assert!(prompt.contains("topic \"Rust help and questions\""));
+        assert!(prompt.contains("how do I borrow this?"));
+
+        // No topic (or a blank one) leaves the prompt alone
+        assert_eq!(with_channel_topic("hi", None), "hi");
+        assert_eq!(with_channel_topic("hi", Some("   ")), "hi");
+    }
+
+    #[test]
+    fn test_persona_examples_in_prompts() {
+        let mut templates = PromptTemplates::new("TestBot".to_string());
+
+        // None configured by default
+        let prompt = templates.format_general_response("hi", "Alice", "");
+        assert!(!prompt.contains("examples of how TestBot talks"));
+
+        let examples = parse_persona_examples(
+            "is the movie good? => It has a plot. Allegedly.\n\nno arrow here\n\
+            what's for dinner?=>Whatever's least likely to be a Mitchell.",
+        );
+        assert_eq!(examples.len(), 2);
+        templates.set_persona_examples(examples);
+
+        let prompt = templates.format_general_response("hi", "Alice", "");
+        assert!(prompt.contains("Message: is the movie good?\nYou: It has a plot. Allegedly."));
+        assert!(prompt.contains("You: Whatever's least likely to be a Mitchell."));
+        assert!(templates
+            .format_fact_interjection("")
+            .contains("It has a plot. Allegedly."));
+
+        // Count and size caps
+        let long = "x".repeat(MAX_PERSONA_EXAMPLE_CHARS * 2);
+        let many = (0..MAX_PERSONA_EXAMPLES + 3)
+            .map(|i| (format!("question {i}"), long.clone()))
+            .collect();
+        templates.set_persona_examples(many);
+        let prompt = templates.format_general_response("hi", "Alice", "");
+        assert_eq!(
+            prompt.matches("Message: question").count(),
+            MAX_PERSONA_EXAMPLES
+        );
+        assert!(!prompt.contains(&long));
+        assert!(prompt.contains(&format!("{}…", "x".repeat(MAX_PERSONA_EXAMPLE_CHARS - 1))));
+    }
+
+    #[test]
+    fn test_apply_confidence_marker() {
+        assert_eq!(apply_confidence_marker("It was 1969."), "It was 1969.");
+        assert_eq!(
+            apply_confidence_marker("It was 1969. [LOW CONFIDENCE]"),
+            "Not 100% sure on this one: It was 1969."
+        );
+        assert_eq!(
+            apply_confidence_marker("I think it was 1969 [LOW CONFIDENCE]"),
+            "I think it was 1969"
+        );
+    }
+}
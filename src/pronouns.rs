@@ -0,0 +1,202 @@
+use anyhow::Result;
+use rusqlite::OptionalExtension;
+use serenity::all::Http;
+use serenity::model::channel::Message;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tokio_rusqlite::Connection as SqliteConnection;
+use tracing::info;
+
+// Longest pronoun string we'll store
+const MAX_PRONOUNS_CHARS: usize = 30;
+
+/// Clean up pronouns given to !pronouns set, or None if they don't look like pronouns
+pub fn normalize_pronouns(input: &str) -> Option<String> {
+    let pronouns = input.split_whitespace().collect::<Vec<_>>().join(" ");
+    let valid = !pronouns.is_empty()
+        && pronouns.chars().count() <= MAX_PRONOUNS_CHARS
+        && pronouns
+            .chars()
+            .all(|c| c.is_alphabetic() || matches!(c, '/' | ' ' | '-' | '\''));
+    valid.then(|| pronouns.to_lowercase())
+}
+
+/// The pronouns to use for someone: the ones they registered with !pronouns set,
+/// otherwise whatever their display name carries
+pub fn preferred_pronouns(registered: Option<String>, display_name: &str) -> Option<String> {
+    registered.or_else(|| crate::utils::extract_pronouns(display_name))
+}
+
+// Save or replace a user's pronouns
+async fn set_pronouns(
+    conn: Arc<Mutex<SqliteConnection>>,
+    user_id: String,
+    pronouns: String,
+) -> Result<()> {
+    let conn_guard = conn.lock().await;
+    conn_guard
+        .call(move |conn| {
+            conn.execute(
+                "INSERT INTO user_pronouns (user_id, pronouns) VALUES (?1, ?2)
+                 ON CONFLICT(user_id) DO UPDATE SET pronouns = excluded.pronouns",
+                rusqlite::params![user_id, pronouns],
+            )?;
+            Ok::<_, rusqlite::Error>(())
+        })
+        .await?;
+    Ok(())
+}
+
+// Forget a user's pronouns, returning whether any were stored
+async fn clear_pronouns(conn: Arc<Mutex<SqliteConnection>>, user_id: String) -> Result<bool> {
+    let conn_guard = conn.lock().await;
+    let removed = conn_guard
+        .call(move |conn| {
+            let removed =
+                conn.execute("DELETE FROM user_pronouns WHERE user_id = ?1", [&user_id])?;
+            Ok::<_, rusqlite::Error>(removed > 0)
+        })
+        .await?;
+    Ok(removed)
+}
+
+/// The pronouns a user registered with !pronouns set, if any
+pub async fn registered_pronouns(
+    conn: Arc<Mutex<SqliteConnection>>,
+    user_id: String,
+) -> Result<Option<String>> {
+    let conn_guard = conn.lock().await;
+    let pronouns = conn_guard
+        .call(move |conn| {
+            conn.query_row(
+                "SELECT pronouns FROM user_pronouns WHERE user_id = ?1",
+                [&user_id],
+                |row| row.get::<_, String>(0),
+            )
+            .optional()
+        })
+        .await?;
+    Ok(pronouns)
+}
+
+// This function will be called from main.rs to handle the !pronouns command
+pub async fn handle_pronouns_command(
+    http: &Http,
+    msg: &Message,
+    args: &[&str],
+    message_db: Arc<Mutex<SqliteConnection>>,
+) -> Result<()> {
+    let usage = "Usage: !pronouns set they/them | !pronouns clear | !pronouns";
+    let user_id = msg.author.id.to_string();
+
+    match args.first().map(|a| a.to_lowercase()).as_deref() {
+        Some("set") => {
+            let Some(pronouns) = normalize_pronouns(&args[1..].join(" ")) else {
+                msg.reply(
+                    http,
+                    "Please give your pronouns like `!pronouns set they/them`.",
+                )
+                .await?;
+                return Ok(());
+            };
+
+            set_pronouns(message_db, user_id, pronouns.clone()).await?;
+            info!("Stored pronouns {} for {}", pronouns, msg.author.name);
+            msg.reply(http, format!("Got it, I'll use {pronouns} for you."))
+                .await?;
+        }
+        Some("clear") | Some("remove") => {
+            let reply = if clear_pronouns(message_db, user_id).await? {
+                "Your pronouns have been cleared. I'll go by your display name again."
+            } else {
+                "I don't have pronouns stored for you."
+            };
+            msg.reply(http, reply).await?;
+        }
+        None => {
+            let reply = match registered_pronouns(message_db, user_id).await? {
+                Some(pronouns) => format!("I have your pronouns as {pronouns}."),
+                None => format!("I don't have pronouns stored for you. {usage}"),
+            };
+            msg.reply(http, reply).await?;
+        }
+        _ => {
+            msg.reply(http, usage).await?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_registered_pronouns_take_precedence() {
+        let conn = crate::db_utils::initialize_database(":memory:")
+            .await
+            .unwrap();
+        conn.lock()
+            .await
+            .call(|conn| {
+                conn.execute(
+                    "INSERT INTO messages (message_id, channel_id, author_id, author, display_name, content, timestamp)
+                     VALUES ('1', 'c1', '42', 'alex', 'Alex (he/him)', 'hello there', 1700000000)",
+                    [],
+                )?;
+                Ok::<_, rusqlite::Error>(())
+            })
+            .await
+            .unwrap();
+
+        let context_pronouns = |conn| async move {
+            crate::db_utils::get_recent_messages_with_reply_context(conn, 5, Some("c1"))
+                .await
+                .unwrap()[0]
+                .2
+                .clone()
+        };
+
+        // Nothing registered: fall back to the display name
+        assert_eq!(
+            context_pronouns(conn.clone()).await.as_deref(),
+            Some("he/him")
+        );
+
+        // Registered pronouns win over the display name
+        set_pronouns(conn.clone(), "42".to_string(), "they/them".to_string())
+            .await
+            .unwrap();
+        assert_eq!(
+            context_pronouns(conn.clone()).await.as_deref(),
+            Some("they/them")
+        );
+        assert_eq!(
+            preferred_pronouns(
+                registered_pronouns(conn.clone(), "42".to_string())
+                    .await
+                    .unwrap(),
+                "Alex (he/him)"
+            )
+            .as_deref(),
+            Some("they/them")
+        );
+
+        // Clearing goes back to the display name
+        assert!(clear_pronouns(conn.clone(), "42".to_string())
+            .await
+            .unwrap());
+        assert_eq!(
+            context_pronouns(conn.clone()).await.as_deref(),
+            Some("he/him")
+        );
+
+        assert_eq!(
+            normalize_pronouns("  They/Them "),
+            Some("they/them".to_string())
+        );
+        assert_eq!(normalize_pronouns("<@123>"), None);
+        assert_eq!(normalize_pronouns(""), None);
+    }
+}
@@ -1,22 +1,86 @@
-use anyhow::{anyhow, Result};
+use anyhow::Result;
 use chrono::{DateTime, Utc};
-use std::collections::VecDeque;
+use serenity::model::id::UserId;
+use std::collections::{HashMap, VecDeque};
 use std::path::Path;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
-use tokio::sync::Mutex;
 use tracing::{error, info, warn};
 
-/// A rate limiter that enforces both per-minute and per-day limits
+/// What the bot says when a direct reply is rate limited
+pub const DEFAULT_RATE_LIMIT_REPLY: &str = "I'm a bit overloaded right now, try again in a moment.";
+
+/// How many LLM replies a user can get in a burst by default
+pub const DEFAULT_USER_REPLY_BURST: u32 = 5;
+
+/// How many replies a user earns back per minute by default
+pub const DEFAULT_USER_REPLY_REFILL_PER_MINUTE: f64 = 2.0;
+
+// Past this many tracked users, buckets that have filled back up are forgotten
+const MAX_TRACKED_BUCKETS: usize = 1000;
+
+/// Returned when our own rate limiter turns a request away, so callers can tell
+/// "try again shortly" apart from real API failures
+#[derive(Debug)]
+pub struct RateLimited {
+    message: String,
+}
+
+impl std::fmt::Display for RateLimited {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for RateLimited {}
+
+/// Whether an error came from the rate limiter rather than the API itself
+pub fn is_rate_limited(error: &anyhow::Error) -> bool {
+    error.downcast_ref::<RateLimited>().is_some()
+}
+
+// How long a request counts towards the per-minute limit
+const MINUTE: Duration = Duration::from_secs(60);
+
+/// How much of its limits a [`RateLimiter`] has used, for `!info`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimitSnapshot {
+    pub minute_used: u32,
+    pub minute_limit: u32,
+    /// When the oldest request of the last minute stops counting (None if there weren't any)
+    pub minute_resets_at: Option<DateTime<Utc>>,
+    pub day_used: u32,
+    pub day_limit: u32,
+    /// Midnight UTC, when the daily count starts over
+    pub day_resets_at: DateTime<Utc>,
+}
+
+impl std::fmt::Display for RateLimitSnapshot {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}/{} this minute, {}/{} today",
+            self.minute_used, self.minute_limit, self.day_used, self.day_limit
+        )
+    }
+}
+
+// Midnight UTC at the start of `now`'s day
+fn day_start(now: DateTime<Utc>) -> DateTime<Utc> {
+    now.date_naive().and_hms_opt(0, 0, 0).unwrap().and_utc()
+}
+
+/// A rate limiter that enforces both per-minute and per-day limits. Its locks are only
+/// held briefly and never across an await, so reading its state can't stall a handler.
 #[derive(Clone)]
 pub struct RateLimiter {
     // Per-minute tracking
     minute_limit: u32,
-    minute_requests: Arc<Mutex<VecDeque<Instant>>>,
+    minute_requests: Arc<std::sync::Mutex<VecDeque<Instant>>>,
 
     // Per-day tracking
     day_limit: u32,
-    day_requests: Arc<Mutex<VecDeque<DateTime<Utc>>>>,
+    day_requests: Arc<std::sync::Mutex<VecDeque<DateTime<Utc>>>>,
 
     // Persistence
     persistence_file: Option<String>,
@@ -31,9 +95,9 @@ impl RateLimiter {
     ) -> Self {
         let limiter = Self {
             minute_limit,
-            minute_requests: Arc::new(Mutex::new(VecDeque::new())),
+            minute_requests: Arc::new(std::sync::Mutex::new(VecDeque::new())),
             day_limit,
-            day_requests: Arc::new(Mutex::new(VecDeque::new())),
+            day_requests: Arc::new(std::sync::Mutex::new(VecDeque::new())),
             persistence_file: Some(persistence_file),
         };
 
@@ -45,6 +109,18 @@ impl RateLimiter {
         limiter
     }
 
+    /// Create a rate limiter that keeps its counts in memory only, for tests
+    #[cfg(test)]
+    pub fn in_memory(minute_limit: u32, day_limit: u32) -> Self {
+        Self {
+            minute_limit,
+            minute_requests: Arc::new(std::sync::Mutex::new(VecDeque::new())),
+            day_limit,
+            day_requests: Arc::new(std::sync::Mutex::new(VecDeque::new())),
+            persistence_file: None,
+        }
+    }
+
     /// Load daily usage from persistence file
     fn load_daily_usage(&self) -> Result<()> {
         if let Some(file_path) = &self.persistence_file {
@@ -53,28 +129,17 @@ impl RateLimiter {
                 let timestamps: Vec<DateTime<Utc>> = serde_json::from_str(&content)?;
 
                 // Only keep timestamps from today (current UTC day)
-                let today_start = Utc::now()
-                    .date_naive()
-                    .and_hms_opt(0, 0, 0)
-                    .unwrap()
-                    .and_utc();
+                let today_start = day_start(Utc::now());
                 let valid_timestamps: VecDeque<DateTime<Utc>> = timestamps
                     .into_iter()
                     .filter(|t| *t >= today_start)
                     .collect();
 
-                // Update the day_requests with loaded data
-                tokio::task::block_in_place(|| {
-                    tokio::runtime::Handle::current().block_on(async {
-                        let mut day_requests = self.day_requests.lock().await;
-                        *day_requests = valid_timestamps;
-                    })
-                });
-
                 info!(
                     "Loaded {} daily requests from persistence",
-                    self.day_requests.try_lock().map(|r| r.len()).unwrap_or(0)
+                    valid_timestamps.len()
                 );
+                *self.day_requests.lock().unwrap() = valid_timestamps;
             }
         }
         Ok(())
@@ -83,9 +148,8 @@ impl RateLimiter {
     /// Save daily usage to persistence file
     async fn save_daily_usage(&self) -> Result<()> {
         if let Some(file_path) = &self.persistence_file {
-            let day_requests = self.day_requests.lock().await;
-            let timestamps: Vec<DateTime<Utc>> = day_requests.iter().cloned().collect();
-            drop(day_requests);
+            let timestamps: Vec<DateTime<Utc>> =
+                self.day_requests.lock().unwrap().iter().cloned().collect();
 
             let content = serde_json::to_string(&timestamps)?;
             tokio::fs::write(file_path, content).await?;
@@ -93,71 +157,82 @@ impl RateLimiter {
         Ok(())
     }
 
-    /// Get current usage statistics
-    pub async fn get_usage_stats(&self) -> (u32, u32, u32, u32) {
-        let now_utc = Utc::now();
-        let now_instant = Instant::now();
+    /// Current usage of both limits
+    pub fn snapshot(&self) -> RateLimitSnapshot {
+        self.snapshot_at(Instant::now(), Utc::now())
+    }
 
-        // Clean up and count minute requests
-        let mut minute_requests = self.minute_requests.lock().await;
-        let minute_ago = now_instant - Duration::from_secs(60);
-        while minute_requests.front().is_some_and(|t| *t < minute_ago) {
-            minute_requests.pop_front();
-        }
-        let minute_used = minute_requests.len() as u32;
-        drop(minute_requests);
-
-        // Clean up and count day requests (current UTC day only)
-        let mut day_requests = self.day_requests.lock().await;
-        let today_start = now_utc.date_naive().and_hms_opt(0, 0, 0).unwrap().and_utc();
-        while day_requests.front().is_some_and(|t| *t < today_start) {
-            day_requests.pop_front();
+    fn snapshot_at(&self, now: Instant, now_utc: DateTime<Utc>) -> RateLimitSnapshot {
+        let (minute_used, oldest) = {
+            let minute_requests = self.minute_requests.lock().unwrap();
+            let mut counted = minute_requests
+                .iter()
+                .filter(|t| now.saturating_duration_since(**t) <= MINUTE);
+            let oldest = counted.next().copied();
+            (oldest.map_or(0, |_| 1 + counted.count()), oldest)
+        };
+        let minute_resets_at = oldest.map(|oldest| {
+            let remaining = MINUTE.saturating_sub(now.saturating_duration_since(oldest));
+            now_utc + chrono::Duration::from_std(remaining).unwrap_or_default()
+        });
+
+        let today_start = day_start(now_utc);
+        let day_used = self
+            .day_requests
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|t| **t >= today_start)
+            .count();
+
+        RateLimitSnapshot {
+            minute_used: minute_used as u32,
+            minute_limit: self.minute_limit,
+            minute_resets_at,
+            day_used: day_used as u32,
+            day_limit: self.day_limit,
+            day_resets_at: today_start + chrono::Duration::days(1),
         }
-        let day_used = day_requests.len() as u32;
-        drop(day_requests);
-
-        (minute_used, self.minute_limit, day_used, self.day_limit)
     }
 
     /// Check if a request can be made, and if not, how long to wait
     pub async fn check(&self) -> Result<()> {
         // First check the daily limit
         let now_utc = Utc::now();
-        let mut day_requests = self.day_requests.lock().await;
+        {
+            let mut day_requests = self.day_requests.lock().unwrap();
 
-        // Clean up old day requests (before today's start)
-        let today_start = now_utc.date_naive().and_hms_opt(0, 0, 0).unwrap().and_utc();
-        while day_requests.front().is_some_and(|t| *t < today_start) {
-            day_requests.pop_front();
-        }
+            // Clean up old day requests (before today's start)
+            let today_start = day_start(now_utc);
+            while day_requests.front().is_some_and(|t| *t < today_start) {
+                day_requests.pop_front();
+            }
 
-        // Check if we've hit the daily limit
-        if day_requests.len() >= self.day_limit as usize {
-            // Daily quota resets at midnight UTC (start of next day)
-            let tomorrow_start = (now_utc.date_naive() + chrono::Duration::days(1))
-                .and_hms_opt(0, 0, 0)
-                .unwrap()
-                .and_utc();
-            let wait_duration = tomorrow_start - now_utc;
-            let hours = wait_duration.num_hours();
-            let minutes = wait_duration.num_minutes() % 60;
-
-            let error_msg = format!(
-                "⛔ Daily rate limit reached ({} requests). Reset in {hours} hours {minutes} minutes",
-                self.day_limit
-            );
-            warn!("{}", error_msg);
-            return Err(anyhow!(error_msg));
+            // Check if we've hit the daily limit
+            if day_requests.len() >= self.day_limit as usize {
+                // Daily quota resets at midnight UTC (start of next day)
+                let tomorrow_start = today_start + chrono::Duration::days(1);
+                let wait_duration = tomorrow_start - now_utc;
+                let hours = wait_duration.num_hours();
+                let minutes = wait_duration.num_minutes() % 60;
+
+                let error_msg = format!(
+                    "⛔ Daily rate limit reached ({} requests). Reset in {hours} hours {minutes} minutes",
+                    self.day_limit
+                );
+                warn!("{}", error_msg);
+                return Err(RateLimited { message: error_msg }.into());
+            }
         }
 
         // Then check the per-minute limit
         let now = Instant::now();
-        let mut minute_requests = self.minute_requests.lock().await;
+        let mut minute_requests = self.minute_requests.lock().unwrap();
 
         // Clean up old minute requests (older than 1 minute)
         while minute_requests
             .front()
-            .is_some_and(|t| now.duration_since(*t) > Duration::from_secs(60))
+            .is_some_and(|t| now.duration_since(*t) > MINUTE)
         {
             minute_requests.pop_front();
         }
@@ -167,11 +242,11 @@ impl RateLimiter {
             // Calculate when the oldest request will expire
             if let Some(oldest) = minute_requests.front() {
                 let elapsed = now.duration_since(*oldest);
-                let wait_duration = if elapsed >= Duration::from_secs(60) {
+                let wait_duration = if elapsed >= MINUTE {
                     // This shouldn't happen due to cleanup above, but handle it gracefully
                     Duration::from_secs(1)
                 } else {
-                    Duration::from_secs(60) - elapsed
+                    MINUTE - elapsed
                 };
 
                 // Ensure minimum wait time of 1 second
@@ -182,7 +257,7 @@ impl RateLimiter {
                     self.minute_limit
                 );
                 warn!("{}", error_msg);
-                return Err(anyhow!(error_msg));
+                return Err(RateLimited { message: error_msg }.into());
             }
         }
 
@@ -192,16 +267,13 @@ impl RateLimiter {
     /// Record a successful request
     pub async fn record_request(&self) {
         // Record the request for per-minute tracking
-        let now = Instant::now();
-        let mut minute_requests = self.minute_requests.lock().await;
-        minute_requests.push_back(now);
-        drop(minute_requests);
+        self.minute_requests
+            .lock()
+            .unwrap()
+            .push_back(Instant::now());
 
         // Record the request for per-day tracking
-        let now_utc = Utc::now();
-        let mut day_requests = self.day_requests.lock().await;
-        day_requests.push_back(now_utc);
-        drop(day_requests);
+        self.day_requests.lock().unwrap().push_back(Utc::now());
 
         // Save daily usage to persistence
         if let Err(e) = self.save_daily_usage().await {
@@ -233,7 +305,13 @@ impl RateLimiter {
                                 "⛔ Giving up after {} attempts to acquire rate limit slot",
                                 MAX_ATTEMPTS
                             );
-                            return Err(anyhow!("Max retry attempts ({}) exceeded", MAX_ATTEMPTS));
+                            return Err(RateLimited {
+                                message: format!(
+                                    "Max retry attempts ({}) exceeded waiting for rate limit",
+                                    MAX_ATTEMPTS
+                                ),
+                            }
+                            .into());
                         }
 
                         // Log retry attempt and wait
@@ -254,3 +332,313 @@ impl RateLimiter {
         }
     }
 }
+
+/// A hard cap on how many events may happen within a sliding time window, shared by
+/// everything holding a clone. A limit of 0 means no cap.
+#[derive(Clone)]
+pub struct SlidingWindowCap {
+    name: String,
+    limit: usize,
+    window: Duration,
+    // Recent event times, plus whether we've logged that the cap was hit
+    state: Arc<std::sync::Mutex<(VecDeque<Instant>, bool)>>,
+}
+
+impl SlidingWindowCap {
+    pub fn new(name: &str, limit: usize, window: Duration) -> Self {
+        Self {
+            name: name.to_string(),
+            limit,
+            window,
+            state: Arc::new(std::sync::Mutex::new((VecDeque::new(), false))),
+        }
+    }
+
+    /// Whether another event is allowed right now
+    pub fn has_capacity(&self) -> bool {
+        self.has_capacity_at(Instant::now())
+    }
+
+    /// Record an event that happened now
+    pub fn record(&self) {
+        self.record_at(Instant::now());
+    }
+
+    fn has_capacity_at(&self, now: Instant) -> bool {
+        if self.limit == 0 {
+            return true;
+        }
+
+        let mut state = self.state.lock().unwrap();
+        let (events, capped) = &mut *state;
+        while events
+            .front()
+            .is_some_and(|t| now.duration_since(*t) >= self.window)
+        {
+            events.pop_front();
+        }
+
+        let has_capacity = events.len() < self.limit;
+        if !has_capacity && !*capped {
+            warn!(
+                "{} cap reached ({} in the last {} minutes), holding off",
+                self.name,
+                self.limit,
+                self.window.as_secs() / 60
+            );
+        } else if has_capacity && *capped {
+            info!("{} cap window has room again", self.name);
+        }
+        *capped = !has_capacity;
+        has_capacity
+    }
+
+    fn record_at(&self, now: Instant) {
+        if self.limit == 0 {
+            return;
+        }
+        self.state.lock().unwrap().0.push_back(now);
+    }
+}
+
+/// Whether a user may get another LLM reply right now
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UserBudget {
+    Allowed,
+    /// Out of tokens until `retry_after` has passed. `notify` is set the first time
+    /// they're turned away, so they get one notice rather than one per message.
+    Limited {
+        retry_after: Duration,
+        notify: bool,
+    },
+}
+
+// One user's tokens, when they were last topped up, and whether they've been told to wait
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+    notified: bool,
+}
+
+/// Per-user token buckets for replies that call the LLM, so one chatty user can't use up
+/// the shared per-minute budget. Each user holds up to `capacity` tokens, spends one per
+/// reply and earns them back at `refill_per_minute`. The global limits still apply on
+/// top. A capacity of 0 turns it off.
+#[derive(Clone)]
+pub struct UserTokenBuckets {
+    capacity: f64,
+    refill_per_sec: f64,
+    buckets: Arc<std::sync::Mutex<HashMap<UserId, Bucket>>>,
+}
+
+impl UserTokenBuckets {
+    pub fn new(capacity: u32, refill_per_minute: f64) -> Self {
+        Self {
+            capacity: f64::from(capacity),
+            refill_per_sec: refill_per_minute.max(0.0) / 60.0,
+            buckets: Arc::new(std::sync::Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Spend one of the user's tokens if they have one
+    pub fn try_take(&self, user_id: UserId) -> UserBudget {
+        self.try_take_at(user_id, Instant::now())
+    }
+
+    fn try_take_at(&self, user_id: UserId, now: Instant) -> UserBudget {
+        if self.capacity == 0.0 {
+            return UserBudget::Allowed;
+        }
+
+        let mut buckets = self.buckets.lock().unwrap();
+        if buckets.len() > MAX_TRACKED_BUCKETS {
+            buckets.retain(|_, bucket| self.refilled(bucket, now) < self.capacity);
+        }
+
+        let bucket = buckets.entry(user_id).or_insert(Bucket {
+            tokens: self.capacity,
+            updated: now,
+            notified: false,
+        });
+        bucket.tokens = self.refilled(bucket, now);
+        bucket.updated = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            bucket.notified = false;
+            return UserBudget::Allowed;
+        }
+
+        let retry_after = if self.refill_per_sec > 0.0 {
+            Duration::from_secs_f64((1.0 - bucket.tokens) / self.refill_per_sec)
+        } else {
+            Duration::MAX
+        };
+        let notify = !bucket.notified;
+        bucket.notified = true;
+        UserBudget::Limited {
+            retry_after,
+            notify,
+        }
+    }
+
+    // The bucket's tokens after topping up for the time since it was last updated
+    fn refilled(&self, bucket: &Bucket, now: Instant) -> f64 {
+        let elapsed = now.saturating_duration_since(bucket.updated).as_secs_f64();
+        (bucket.tokens + elapsed * self.refill_per_sec).min(self.capacity)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rate_limit_snapshot_minute_boundary() {
+        let limiter = RateLimiter::in_memory(15, 500);
+        let start = Instant::now();
+        let start_utc = DateTime::parse_from_rfc3339("2024-05-01T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let at = |secs: u64| {
+            (
+                start + Duration::from_secs(secs),
+                start_utc + chrono::Duration::seconds(secs as i64),
+            )
+        };
+
+        let empty = limiter.snapshot_at(start, start_utc);
+        assert_eq!((empty.minute_used, empty.day_used), (0, 0));
+        assert_eq!(empty.minute_resets_at, None);
+        assert_eq!(
+            empty.day_resets_at.to_rfc3339(),
+            "2024-05-02T00:00:00+00:00"
+        );
+
+        {
+            let mut minute = limiter.minute_requests.lock().unwrap();
+            minute.push_back(start);
+            minute.push_back(start + Duration::from_secs(30));
+            let mut day = limiter.day_requests.lock().unwrap();
+            // Yesterday's request doesn't count towards today
+            day.push_back(start_utc - chrono::Duration::days(1));
+            day.push_back(start_utc);
+            day.push_back(start_utc + chrono::Duration::seconds(30));
+        }
+
+        // Just before the minute is up both requests count, and the first expires in a second
+        let (now, now_utc) = at(59);
+        let snapshot = limiter.snapshot_at(now, now_utc);
+        assert_eq!(snapshot.minute_used, 2);
+        assert_eq!(snapshot.minute_resets_at, Some(at(60).1));
+        assert_eq!(snapshot.day_used, 2);
+        assert_eq!(snapshot.to_string(), "2/15 this minute, 2/500 today");
+
+        // Past it, only the second request is left, expiring 30 seconds after the first
+        let (now, now_utc) = at(61);
+        let snapshot = limiter.snapshot_at(now, now_utc);
+        assert_eq!(snapshot.minute_used, 1);
+        assert_eq!(snapshot.minute_resets_at, Some(at(90).1));
+        assert_eq!(snapshot.day_used, 2);
+
+        // Once both have aged out the minute is clear, but they still count for the day
+        let (now, now_utc) = at(120);
+        let snapshot = limiter.snapshot_at(now, now_utc);
+        assert_eq!((snapshot.minute_used, snapshot.minute_resets_at), (0, None));
+        assert_eq!(snapshot.day_used, 2);
+    }
+
+    #[test]
+    fn test_user_token_buckets_burst() {
+        let start = Instant::now();
+        let buckets = UserTokenBuckets::new(3, 6.0);
+        let alice = UserId::new(1);
+        let bob = UserId::new(2);
+
+        // A burst uses up the bucket, and only the first refusal asks for a notice
+        for _ in 0..3 {
+            assert_eq!(buckets.try_take_at(alice, start), UserBudget::Allowed);
+        }
+        assert_eq!(
+            buckets.try_take_at(alice, start),
+            UserBudget::Limited {
+                retry_after: Duration::from_secs(10),
+                notify: true
+            }
+        );
+        assert!(matches!(
+            buckets.try_take_at(alice, start + Duration::from_secs(1)),
+            UserBudget::Limited { notify: false, .. }
+        ));
+
+        // Other users have their own buckets
+        assert_eq!(buckets.try_take_at(bob, start), UserBudget::Allowed);
+
+        // A capacity of 0 never limits
+        let unlimited = UserTokenBuckets::new(0, 0.0);
+        for _ in 0..100 {
+            assert_eq!(unlimited.try_take_at(alice, start), UserBudget::Allowed);
+        }
+    }
+
+    #[test]
+    fn test_user_token_buckets_refill() {
+        let start = Instant::now();
+        // One token every 10 seconds
+        let buckets = UserTokenBuckets::new(2, 6.0);
+        let alice = UserId::new(1);
+        buckets.try_take_at(alice, start);
+        buckets.try_take_at(alice, start);
+        assert_ne!(
+            buckets.try_take_at(alice, start + Duration::from_secs(5)),
+            UserBudget::Allowed
+        );
+
+        // Ten seconds in, one token is back, and being refused again warrants a new notice
+        assert_eq!(
+            buckets.try_take_at(alice, start + Duration::from_secs(10)),
+            UserBudget::Allowed
+        );
+        assert!(matches!(
+            buckets.try_take_at(alice, start + Duration::from_secs(11)),
+            UserBudget::Limited { notify: true, .. }
+        ));
+
+        // A long wait refills only up to the capacity
+        let later = start + Duration::from_secs(3600);
+        assert_eq!(buckets.try_take_at(alice, later), UserBudget::Allowed);
+        assert_eq!(buckets.try_take_at(alice, later), UserBudget::Allowed);
+        assert_ne!(buckets.try_take_at(alice, later), UserBudget::Allowed);
+    }
+
+    #[test]
+    fn test_sliding_window_cap() {
+        let start = Instant::now();
+        let hour = Duration::from_secs(3600);
+        let cap = SlidingWindowCap::new("Interjection", 2, hour);
+
+        assert!(cap.has_capacity_at(start));
+        cap.record_at(start);
+        cap.record_at(start + Duration::from_secs(600));
+        assert!(!cap.has_capacity_at(start + Duration::from_secs(1200)));
+
+        // Clones share the same window
+        let shared = cap.clone();
+        assert!(!shared.has_capacity_at(start + Duration::from_secs(1800)));
+
+        // The first event ages out after an hour, freeing one slot
+        assert!(cap.has_capacity_at(start + hour));
+        cap.record_at(start + hour);
+        assert!(!cap.has_capacity_at(start + hour + Duration::from_secs(1)));
+
+        // Everything ages out eventually
+        assert!(cap.has_capacity_at(start + hour * 3));
+
+        // A limit of 0 never caps
+        let unlimited = SlidingWindowCap::new("Interjection", 0, hour);
+        for _ in 0..100 {
+            unlimited.record_at(start);
+        }
+        assert!(unlimited.has_capacity_at(start));
+    }
+}
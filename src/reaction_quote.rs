@@ -0,0 +1,187 @@
+use anyhow::Result;
+use serenity::all::{Context, Message, Reaction, UserId};
+use serenity::model::id::{ChannelId, MessageId};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+use tokio_rusqlite::Connection as SqliteConnection;
+use tracing::{error, info};
+
+use crate::db_utils;
+use crate::display_name::get_best_display_name;
+use crate::utils::no_ping_message;
+
+/// Emoji that asks the bot to quote a message when QUOTE_REACTION_EMOJI isn't set
+pub const DEFAULT_QUOTE_REACTION_EMOJI: &str = "💬";
+
+// A message is only quoted once in this window, however many people react
+const QUOTE_DEBOUNCE: Duration = Duration::from_secs(10 * 60);
+// Longer messages are cut off so the quote stays under Discord's 2000 character limit
+const MAX_QUOTED_CHARS: usize = 1500;
+
+/// Format a message as a quote: each line block-quoted, then who said it, when, and a link
+/// back to the original. Attachments are linked after the quote so images still show.
+pub fn format_quote(
+    author: &str,
+    content: &str,
+    attachments: &[String],
+    timestamp: i64,
+    link: &str,
+) -> String {
+    let mut text: String = content.trim().chars().take(MAX_QUOTED_CHARS).collect();
+    if text.len() < content.trim().len() {
+        text.push('…');
+    }
+
+    let mut quote = String::new();
+    for line in text.lines() {
+        quote.push_str("> ");
+        quote.push_str(line);
+        quote.push('\n');
+    }
+    quote.push_str(&format!(
+        "— **{}**, <t:{}:D> · [Jump to message]({})",
+        author, timestamp, link
+    ));
+    for attachment in attachments {
+        quote.push('\n');
+        quote.push_str(attachment);
+    }
+    quote
+}
+
+/// Reposts a message as a quote when someone reacts to it with the quote emoji
+pub struct ReactionQuotes {
+    emoji: String,
+    /// Where quotes are posted (None = the channel the message is in)
+    channel_id: Option<ChannelId>,
+    /// Messages quoted recently, so several reactions only quote once
+    recent: Mutex<HashMap<MessageId, Instant>>,
+}
+
+impl ReactionQuotes {
+    pub fn new(emoji: String, channel_id: Option<ChannelId>) -> Self {
+        Self {
+            emoji,
+            channel_id,
+            recent: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Whether the message can be quoted now; if so it's marked as quoted
+    async fn claim(&self, message_id: MessageId, now: Instant) -> bool {
+        let mut recent = self.recent.lock().await;
+        recent.retain(|_, quoted_at| now.duration_since(*quoted_at) < QUOTE_DEBOUNCE);
+        if recent.contains_key(&message_id) {
+            return false;
+        }
+        recent.insert(message_id, now);
+        true
+    }
+
+    /// Quote the reacted-to message if the reaction is the quote emoji. Reactions from the bot
+    /// and from the message's own author are ignored. Returns whether a quote was posted.
+    pub async fn handle_reaction(
+        &self,
+        ctx: &Context,
+        reaction: &Reaction,
+        bot_user_id: Option<UserId>,
+        message_db: Option<Arc<Mutex<SqliteConnection>>>,
+    ) -> Result<bool> {
+        let Some(user_id) = reaction.user_id else {
+            return Ok(false);
+        };
+        if !reaction.emoji.unicode_eq(&self.emoji) || Some(user_id) == bot_user_id {
+            return Ok(false);
+        }
+
+        let mut msg: Message = reaction.message(&ctx.http).await?;
+        if msg.author.id == user_id {
+            return Ok(false);
+        }
+        let attachments: Vec<String> = msg.attachments.iter().map(|a| a.url.clone()).collect();
+        if msg.content.trim().is_empty() && attachments.is_empty() {
+            return Ok(false);
+        }
+        if !self.claim(msg.id, Instant::now()).await {
+            return Ok(false);
+        }
+
+        // Messages fetched over HTTP don't say which server they're in
+        msg.guild_id = msg.guild_id.or(reaction.guild_id);
+        let author = get_best_display_name(ctx, &msg).await;
+        let quote = format_quote(
+            &author,
+            &msg.content,
+            &attachments,
+            msg.timestamp.unix_timestamp(),
+            &msg.id.link(msg.channel_id, msg.guild_id),
+        );
+
+        let destination = self.channel_id.unwrap_or(msg.channel_id);
+        destination
+            .send_message(&ctx.http, no_ping_message(quote))
+            .await?;
+        info!(
+            "Quoted message {} from {} after a reaction from {}",
+            msg.id, author, user_id
+        );
+
+        // Keep the quoted message in history even once it's old enough to be trimmed. Protected
+        // messages always live in the local database, which the shared store's trim also reads.
+        if let Some(db) = message_db {
+            if let Err(e) = db_utils::protect_message(db, msg.id.to_string(), "quote").await {
+                error!("Error protecting quoted message {}: {}", msg.id, e);
+            }
+        }
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serenity::all::GuildId;
+
+    #[test]
+    fn test_format_quote() {
+        let link = MessageId::new(3).link(ChannelId::new(2), Some(GuildId::new(1)));
+
+        assert_eq!(
+            format_quote("Tom Servo", "  I'm a robot.\nA *good* robot.  ", &[], 1_700_000_000, &link),
+            "> I'm a robot.\n> A *good* robot.\n— **Tom Servo**, <t:1700000000:D> · [Jump to message](https://discord.com/channels/1/2/3)"
+        );
+
+        // Attachment-only messages still get attribution, with the files after it
+        assert_eq!(
+            format_quote(
+                "Crow",
+                "",
+                &["https://cdn.example/a.png".to_string()],
+                1,
+                "https://discord.com/channels/1/2/3"
+            ),
+            "— **Crow**, <t:1:D> · [Jump to message](https://discord.com/channels/1/2/3)\nhttps://cdn.example/a.png"
+        );
+
+        // Long messages are cut short
+        let long = "a".repeat(MAX_QUOTED_CHARS + 10);
+        let quote = format_quote("Crow", &long, &[], 1, "link");
+        assert!(quote.starts_with(&format!("> {}…\n", "a".repeat(MAX_QUOTED_CHARS))));
+    }
+
+    #[tokio::test]
+    async fn test_quotes_are_debounced() {
+        let quotes = ReactionQuotes::new(DEFAULT_QUOTE_REACTION_EMOJI.to_string(), None);
+        let now = Instant::now();
+        assert!(quotes.claim(MessageId::new(1), now).await);
+        assert!(
+            !quotes
+                .claim(MessageId::new(1), now + Duration::from_secs(5))
+                .await
+        );
+        assert!(quotes.claim(MessageId::new(2), now).await);
+        assert!(quotes.claim(MessageId::new(1), now + QUOTE_DEBOUNCE).await);
+    }
+}
@@ -0,0 +1,96 @@
+use std::collections::VecDeque;
+
+// How many recent speakers !fightcrime picks from
+pub const MAX_RECENT_SPEAKERS: usize = 5;
+
+/// Recent speakers as (username, display_name), oldest first
+pub type RecentSpeakers = VecDeque<(String, String)>;
+
+/// Move a speaker to the end of the list, dropping the oldest once it's full
+pub fn record_speaker(speakers: &mut RecentSpeakers, username: String, display_name: String) {
+    if let Some(pos) = speakers.iter().position(|(name, _)| name == &username) {
+        speakers.remove(pos);
+    }
+
+    if speakers.len() >= MAX_RECENT_SPEAKERS {
+        speakers.pop_front();
+    }
+    speakers.push_back((username, display_name));
+}
+
+/// Drop anyone whose username or display name is one of `names` (ignoring case), returning
+/// how many were removed
+pub fn forget_speaker(speakers: &mut RecentSpeakers, names: &[String]) -> usize {
+    let before = speakers.len();
+    speakers.retain(|(username, display_name)| {
+        !names.iter().any(|name| {
+            name.eq_ignore_ascii_case(username) || name.eq_ignore_ascii_case(display_name)
+        })
+    });
+    before - speakers.len()
+}
+
+/// Dump of the speaker list for `!speakers`, newest last
+pub fn format_speakers_dump(speakers: &RecentSpeakers) -> String {
+    if speakers.is_empty() {
+        return "Recent speakers list is empty (!fightcrime will use default names)".to_string();
+    }
+
+    let lines = speakers
+        .iter()
+        .enumerate()
+        .map(|(i, (username, display_name))| {
+            format!(
+                "{}. `{}` - display name \"{}\"",
+                i + 1,
+                username,
+                display_name
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    format!(
+        "Recent speakers ({}/{}, oldest first):\n{}",
+        speakers.len(),
+        MAX_RECENT_SPEAKERS,
+        lines
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_speakers_dump_reflects_recorded_speakers() {
+        let mut speakers = RecentSpeakers::new();
+        assert!(format_speakers_dump(&speakers).contains("empty"));
+
+        record_speaker(&mut speakers, "alice".into(), "Alice".into());
+        record_speaker(&mut speakers, "bob".into(), "Bobby".into());
+        // Speaking again moves alice to the end instead of duplicating her
+        record_speaker(&mut speakers, "alice".into(), "Alice".into());
+
+        assert_eq!(
+            format_speakers_dump(&speakers),
+            "Recent speakers (2/5, oldest first):\n1. `bob` - display name \"Bobby\"\n2. `alice` - display name \"Alice\""
+        );
+
+        for i in 0..MAX_RECENT_SPEAKERS {
+            record_speaker(&mut speakers, format!("user{i}"), format!("User {i}"));
+        }
+        let dump = format_speakers_dump(&speakers);
+        assert!(dump.starts_with("Recent speakers (5/5"));
+        assert!(!dump.contains("alice") && !dump.contains("bob"));
+        assert!(dump.contains("`user4` - display name \"User 4\""));
+
+        // Forgetting matches either name, ignoring case
+        assert_eq!(
+            forget_speaker(&mut speakers, &["USER1".into(), "User 3".into()]),
+            2
+        );
+        assert_eq!(speakers.len(), MAX_RECENT_SPEAKERS - 2);
+        assert_eq!(forget_speaker(&mut speakers, &["nobody".into()]), 0);
+    }
+}
@@ -0,0 +1,389 @@
+use anyhow::Result;
+use chrono::{DateTime, Duration as ChronoDuration, FixedOffset, NaiveDate, NaiveTime, Utc};
+use serenity::all::{CreateAllowedMentions, CreateMessage, Http};
+use serenity::model::channel::Message;
+use serenity::model::id::{ChannelId, UserId};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio_rusqlite::Connection as SqliteConnection;
+use tracing::{error, info};
+
+// How often the scheduler looks for reminders that are due
+const REMINDER_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+// How far ahead a reminder can be set
+const MAX_REMINDER_DAYS: i64 = 365;
+// How many pending reminders one user can have
+const MAX_PENDING_PER_USER: i64 = 25;
+// Time of day used when only a date is given
+const DEFAULT_REMINDER_TIME: (u32, u32) = (9, 0);
+
+const USAGE: &str = "Usage: !remindme <when> <what>, e.g. `!remindme 2h check the oven`. \
+    <when> is a duration like 30m, 2h, 1d or 1h30m, a time like 14:30, or a date like 2025-12-24 (optionally followed by a time).";
+
+/// A reminder stored in the reminders table
+#[derive(Clone, Debug, PartialEq)]
+pub struct Reminder {
+    pub id: i64,
+    pub user_id: String,
+    pub channel_id: String,
+    /// Unix timestamp the reminder is due at
+    pub remind_at: i64,
+    pub text: String,
+}
+
+/// Parse a duration like 30m, 2h, 1d, 1w or 1h30m. None for anything else or zero.
+pub fn parse_duration(input: &str) -> Option<ChronoDuration> {
+    let input = input.trim().to_lowercase();
+    let mut total = ChronoDuration::zero();
+    let mut number = String::new();
+
+    for c in input.chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+        let amount = number.parse::<i64>().ok()?;
+        number.clear();
+        let part = match c {
+            's' => ChronoDuration::try_seconds(amount)?,
+            'm' => ChronoDuration::try_minutes(amount)?,
+            'h' => ChronoDuration::try_hours(amount)?,
+            'd' => ChronoDuration::try_days(amount)?,
+            'w' => ChronoDuration::try_weeks(amount)?,
+            _ => return None,
+        };
+        total = total.checked_add(&part)?;
+    }
+
+    // Every number needs a unit
+    if !number.is_empty() || total <= ChronoDuration::zero() {
+        return None;
+    }
+    Some(total)
+}
+
+fn parse_time(input: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(input, "%H:%M").ok()
+}
+
+/// Work out when a reminder is for from the start of the command's arguments: a duration,
+/// a time of day (the next one to come), or a date with an optional time. Times are in
+/// `now`'s timezone. Returns the time and how many arguments it used.
+pub fn parse_when(args: &[&str], now: DateTime<FixedOffset>) -> Option<(DateTime<Utc>, usize)> {
+    let first = args.first()?;
+
+    if let Some(duration) = parse_duration(first) {
+        return Some((now.with_timezone(&Utc) + duration, 1));
+    }
+
+    let offset = *now.offset();
+    let at = |date: NaiveDate, time: NaiveTime| {
+        date.and_time(time)
+            .and_local_timezone(offset)
+            .single()
+            .map(|at| at.with_timezone(&Utc))
+    };
+
+    if let Some(time) = parse_time(first) {
+        let today = at(now.date_naive(), time)?;
+        let when = if today > now {
+            today
+        } else {
+            at(now.date_naive().succ_opt()?, time)?
+        };
+        return Some((when, 1));
+    }
+
+    let date = NaiveDate::parse_from_str(first, "%Y-%m-%d").ok()?;
+    match args.get(1).and_then(|time| parse_time(time)) {
+        Some(time) => Some((at(date, time)?, 2)),
+        None => {
+            let (hour, minute) = DEFAULT_REMINDER_TIME;
+            Some((at(date, NaiveTime::from_hms_opt(hour, minute, 0)?)?, 1))
+        }
+    }
+}
+
+// Store a reminder, returning its id
+async fn add_reminder(
+    conn: Arc<Mutex<SqliteConnection>>,
+    user_id: String,
+    channel_id: String,
+    remind_at: i64,
+    text: String,
+) -> Result<i64> {
+    let conn_guard = conn.lock().await;
+    let id = conn_guard
+        .call(move |conn| {
+            conn.execute(
+                "INSERT INTO reminders (user_id, channel_id, remind_at, text) VALUES (?1, ?2, ?3, ?4)",
+                rusqlite::params![user_id, channel_id, remind_at, text],
+            )?;
+            Ok::<_, rusqlite::Error>(conn.last_insert_rowid())
+        })
+        .await?;
+    Ok(id)
+}
+
+// How many reminders a user has waiting
+async fn pending_count(conn: Arc<Mutex<SqliteConnection>>, user_id: String) -> Result<i64> {
+    let conn_guard = conn.lock().await;
+    let count = conn_guard
+        .call(move |conn| {
+            conn.query_row(
+                "SELECT COUNT(*) FROM reminders WHERE user_id = ?1",
+                [&user_id],
+                |row| row.get::<_, i64>(0),
+            )
+        })
+        .await?;
+    Ok(count)
+}
+
+/// Reminders due at or before `now` (a Unix timestamp), oldest first
+pub async fn due_reminders(conn: Arc<Mutex<SqliteConnection>>, now: i64) -> Result<Vec<Reminder>> {
+    let conn_guard = conn.lock().await;
+    let reminders = conn_guard
+        .call(move |conn| {
+            let mut stmt = conn.prepare(
+                "SELECT id, user_id, channel_id, remind_at, text FROM reminders
+                 WHERE remind_at <= ?1 ORDER BY remind_at, id",
+            )?;
+            let rows = stmt.query_map([now], |row| {
+                Ok(Reminder {
+                    id: row.get(0)?,
+                    user_id: row.get(1)?,
+                    channel_id: row.get(2)?,
+                    remind_at: row.get(3)?,
+                    text: row.get(4)?,
+                })
+            })?;
+            rows.collect::<Result<Vec<_>, _>>()
+        })
+        .await?;
+    Ok(reminders)
+}
+
+async fn delete_reminder(conn: Arc<Mutex<SqliteConnection>>, id: i64) -> Result<()> {
+    let conn_guard = conn.lock().await;
+    conn_guard
+        .call(move |conn| {
+            conn.execute("DELETE FROM reminders WHERE id = ?1", [id])?;
+            Ok::<_, rusqlite::Error>(())
+        })
+        .await?;
+    Ok(())
+}
+
+// This function will be called from main.rs to handle the !remindme command
+pub async fn handle_remindme_command(
+    http: &Http,
+    msg: &Message,
+    args: &[&str],
+    message_db: Arc<Mutex<SqliteConnection>>,
+    utc_offset: Option<FixedOffset>,
+) -> Result<()> {
+    let now = match utc_offset {
+        Some(offset) => Utc::now().with_timezone(&offset),
+        None => chrono::Local::now().fixed_offset(),
+    };
+
+    let Some((remind_at, used)) = parse_when(args, now) else {
+        msg.reply(http, USAGE).await?;
+        return Ok(());
+    };
+    let text = args[used..].join(" ");
+    if text.trim().is_empty() {
+        msg.reply(http, USAGE).await?;
+        return Ok(());
+    }
+    if remind_at <= now {
+        msg.reply(http, "That time has already passed.").await?;
+        return Ok(());
+    }
+    if remind_at.signed_duration_since(now) > ChronoDuration::days(MAX_REMINDER_DAYS) {
+        msg.reply(
+            http,
+            format!("I can only remind you up to {MAX_REMINDER_DAYS} days ahead."),
+        )
+        .await?;
+        return Ok(());
+    }
+
+    let user_id = msg.author.id.to_string();
+    if pending_count(message_db.clone(), user_id.clone()).await? >= MAX_PENDING_PER_USER {
+        msg.reply(
+            http,
+            format!(
+                "You already have {MAX_PENDING_PER_USER} reminders waiting. Let some of them go off first."
+            ),
+        )
+        .await?;
+        return Ok(());
+    }
+
+    let timestamp = remind_at.timestamp();
+    add_reminder(
+        message_db.clone(),
+        user_id,
+        msg.channel_id.to_string(),
+        timestamp,
+        text,
+    )
+    .await?;
+    info!("Stored a reminder for {} at {}", msg.author.name, remind_at);
+
+    // The request stays in history for as long as history is kept
+    if let Err(e) =
+        crate::db_utils::protect_message(message_db, msg.id.to_string(), "reminder").await
+    {
+        error!("Error protecting reminder message {}: {}", msg.id, e);
+    }
+
+    msg.reply(
+        http,
+        format!("Okay, I'll remind you <t:{timestamp}:R> (<t:{timestamp}:f>)."),
+    )
+    .await?;
+    Ok(())
+}
+
+/// Send every reminder that's due and delete it. Returns how many were sent.
+pub async fn send_due_reminders(http: &Http, conn: Arc<Mutex<SqliteConnection>>) -> Result<usize> {
+    let due = due_reminders(conn.clone(), Utc::now().timestamp()).await?;
+
+    let mut sent = 0;
+    for reminder in &due {
+        match (
+            reminder.user_id.parse::<u64>(),
+            reminder.channel_id.parse::<u64>(),
+        ) {
+            (Ok(user_id), Ok(channel_id)) => {
+                // Only the reminded user is pinged, whatever the reminder says
+                let message = CreateMessage::new()
+                    .content(format!("<@{user_id}> ⏰ Reminder: {}", reminder.text))
+                    .allowed_mentions(CreateAllowedMentions::new().users([UserId::new(user_id)]));
+                match ChannelId::new(channel_id).send_message(http, message).await {
+                    Ok(_) => sent += 1,
+                    Err(e) => error!("Error sending reminder {}: {:?}", reminder.id, e),
+                }
+            }
+            _ => error!("Reminder {} has an invalid user or channel", reminder.id),
+        }
+
+        // Delivered or undeliverable, it's done either way
+        delete_reminder(conn.clone(), reminder.id).await?;
+    }
+
+    Ok(sent)
+}
+
+/// Reminder scheduler: sends due reminders once a minute. Reminders live in the database,
+/// so any that came due while the bot was down go out on the first check.
+pub async fn run_reminder_scheduler(http: Arc<Http>, conn: Arc<Mutex<SqliteConnection>>) {
+    loop {
+        match send_due_reminders(&http, conn.clone()).await {
+            Ok(0) => {}
+            Ok(sent) => info!("Sent {} reminders", sent),
+            Err(e) => error!("Error sending reminders: {:?}", e),
+        }
+        tokio::time::sleep(REMINDER_CHECK_INTERVAL).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(input: &str) -> DateTime<FixedOffset> {
+        DateTime::parse_from_rfc3339(input).unwrap()
+    }
+
+    #[test]
+    fn test_parse_reminder_times() {
+        assert_eq!(parse_duration("30m"), Some(ChronoDuration::minutes(30)));
+        assert_eq!(parse_duration("2h"), Some(ChronoDuration::hours(2)));
+        assert_eq!(parse_duration("1D"), Some(ChronoDuration::days(1)));
+        assert_eq!(parse_duration("1h30m"), Some(ChronoDuration::minutes(90)));
+        assert_eq!(parse_duration("2w"), Some(ChronoDuration::weeks(2)));
+        for bad in ["", "30", "h", "0m", "2x", "soon", "14:30"] {
+            assert_eq!(parse_duration(bad), None, "{bad}");
+        }
+
+        // Durations count from now; times of day are the next one to come
+        let now = at("2025-03-14T15:00:00-05:00");
+        assert_eq!(
+            parse_when(&["2h", "check", "the", "oven"], now),
+            Some((at("2025-03-14T17:00:00-05:00").with_timezone(&Utc), 1))
+        );
+        assert_eq!(
+            parse_when(&["16:30", "call", "mom"], now),
+            Some((at("2025-03-14T16:30:00-05:00").with_timezone(&Utc), 1))
+        );
+        assert_eq!(
+            parse_when(&["09:15"], now),
+            Some((at("2025-03-15T09:15:00-05:00").with_timezone(&Utc), 1))
+        );
+
+        // Dates default to the morning unless a time follows
+        assert_eq!(
+            parse_when(&["2025-12-24", "wrap", "presents"], now),
+            Some((at("2025-12-24T09:00:00-05:00").with_timezone(&Utc), 1))
+        );
+        assert_eq!(
+            parse_when(&["2025-12-24", "18:00", "dinner"], now),
+            Some((at("2025-12-24T18:00:00-05:00").with_timezone(&Utc), 2))
+        );
+
+        assert_eq!(parse_when(&["tomorrow-ish", "x"], now), None);
+        assert_eq!(parse_when(&[], now), None);
+    }
+
+    #[tokio::test]
+    async fn test_due_reminders_query() {
+        let conn = crate::db_utils::initialize_database(":memory:")
+            .await
+            .unwrap();
+        let add = |remind_at: i64, text: &str| {
+            add_reminder(
+                conn.clone(),
+                "42".to_string(),
+                "7".to_string(),
+                remind_at,
+                text.to_string(),
+            )
+        };
+        let later = add(2_000, "later").await.unwrap();
+        let soon = add(1_000, "soon").await.unwrap();
+        add(3_000, "much later").await.unwrap();
+
+        assert!(due_reminders(conn.clone(), 999).await.unwrap().is_empty());
+        // Due ones come back oldest first, including any exactly due
+        let due = due_reminders(conn.clone(), 2_000).await.unwrap();
+        assert_eq!(
+            due.iter().map(|r| r.id).collect::<Vec<_>>(),
+            vec![soon, later]
+        );
+        assert_eq!(
+            due[0],
+            Reminder {
+                id: soon,
+                user_id: "42".to_string(),
+                channel_id: "7".to_string(),
+                remind_at: 1_000,
+                text: "soon".to_string(),
+            }
+        );
+
+        // Sent reminders are deleted and don't come back
+        delete_reminder(conn.clone(), soon).await.unwrap();
+        let due = due_reminders(conn.clone(), 2_000).await.unwrap();
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].id, later);
+        assert_eq!(
+            pending_count(conn.clone(), "42".to_string()).await.unwrap(),
+            2
+        );
+    }
+}
@@ -0,0 +1,226 @@
+use serenity::model::id::{MessageId, UserId};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use crate::gemini_api::MAX_TEMPERATURE;
+
+// How long the bot's replies can be rephrased
+pub const REPHRASE_TTL: Duration = Duration::from_secs(60 * 60);
+
+// Most replies remembered at once; the oldest is dropped beyond this
+pub const MAX_REMEMBERED_REPLIES: usize = 200;
+
+/// What went into one of the bot's AI replies, so `!rephrase` can generate it again
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReplyContext {
+    /// The prompt the reply was generated from
+    pub prompt: String,
+    pub user_name: String,
+    pub user_pronouns: Option<String>,
+    /// Recent messages as (author, display name, pronouns, content), newest first
+    pub context: Vec<(String, String, Option<String>, String)>,
+    /// The reply that was sent
+    pub response: String,
+}
+
+/// The bot's recent AI replies by message ID, forgotten after a TTL
+pub struct RecentReplies {
+    ttl: Duration,
+    max_entries: usize,
+    replies: std::sync::Mutex<HashMap<MessageId, (ReplyContext, Instant)>>,
+}
+
+impl RecentReplies {
+    pub fn new(ttl: Duration, max_entries: usize) -> Self {
+        Self {
+            ttl,
+            max_entries,
+            replies: std::sync::Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Remember what a sent reply was generated from
+    pub fn record(&self, message_id: MessageId, reply: ReplyContext) {
+        self.record_at(message_id, reply, Instant::now());
+    }
+
+    /// What a reply was generated from, if it's recent enough to rephrase
+    pub fn get(&self, message_id: MessageId) -> Option<ReplyContext> {
+        self.get_at(message_id, Instant::now())
+    }
+
+    fn record_at(&self, message_id: MessageId, reply: ReplyContext, now: Instant) {
+        let mut replies = self.replies.lock().unwrap();
+        replies.retain(|_, (_, at)| now.duration_since(*at) < self.ttl);
+        if replies.len() >= self.max_entries && !replies.contains_key(&message_id) {
+            let oldest = replies
+                .iter()
+                .min_by_key(|(_, (_, at))| *at)
+                .map(|(id, _)| *id);
+            if let Some(oldest) = oldest {
+                replies.remove(&oldest);
+            }
+        }
+        replies.insert(message_id, (reply, now));
+    }
+
+    fn get_at(&self, message_id: MessageId, now: Instant) -> Option<ReplyContext> {
+        let replies = self.replies.lock().unwrap();
+        replies
+            .get(&message_id)
+            .filter(|(_, at)| now.duration_since(*at) < self.ttl)
+            .map(|(reply, _)| reply.clone())
+    }
+
+    /// The reply a `!rephrase` points at: `replied_to` is the (message, author) the command
+    /// replies to. Errors are messages for the user.
+    pub fn rephrase_target(
+        &self,
+        replied_to: Option<(MessageId, UserId)>,
+        bot_user_id: UserId,
+    ) -> Result<(MessageId, ReplyContext), &'static str> {
+        let Some((message_id, author_id)) = replied_to else {
+            return Err("Reply to one of my messages with `!rephrase [style]` to get a different take on it.");
+        };
+        if author_id != bot_user_id {
+            return Err("I can only rephrase my own messages.");
+        }
+        self.get(message_id).map(|reply| (message_id, reply)).ok_or(
+            "I don't remember how I came up with that one. I can only rephrase my recent replies.",
+        )
+    }
+}
+
+/// How a `!rephrase` wants the reply changed
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RephraseRequest {
+    /// Free-form hint like "more concise" or "as a pirate"
+    pub style: Option<String>,
+    /// Reply temperature to use instead of the configured one
+    pub temperature: Option<f64>,
+}
+
+/// Parse `!rephrase [style hint] [-t temperature]`
+pub fn parse_rephrase_args(args: &[&str]) -> Result<RephraseRequest, String> {
+    let mut style = Vec::new();
+    let mut temperature = None;
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        if *arg == "-t" || *arg == "-temp" {
+            let value = args.next().ok_or_else(|| {
+                format!("`{arg}` needs a temperature from 0 to {MAX_TEMPERATURE}")
+            })?;
+            match value.parse::<f64>() {
+                Ok(t) if (0.0..=MAX_TEMPERATURE).contains(&t) => temperature = Some(t),
+                _ => {
+                    return Err(format!(
+                        "Temperature must be a number from 0 to {MAX_TEMPERATURE}, not \"{value}\""
+                    ))
+                }
+            }
+        } else {
+            style.push(*arg);
+        }
+    }
+
+    let style = style.join(" ");
+    Ok(RephraseRequest {
+        style: (!style.is_empty()).then_some(style),
+        temperature,
+    })
+}
+
+/// The original prompt, asking for a new take on the previous reply
+pub fn rephrase_prompt(reply: &ReplyContext, style: Option<&str>) -> String {
+    let how = match style {
+        Some(style) => format!("Give a different response in this style: {style}."),
+        None => "Give a different response that says it another way.".to_string(),
+    };
+    format!(
+        "{}\n\nYou already replied to this with: \"{}\"\n{how} Don't repeat your previous reply or mention that you're rephrasing.",
+        reply.prompt, reply.response
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reply(response: &str) -> ReplyContext {
+        ReplyContext {
+            prompt: "Crow, what's the best movie?".to_string(),
+            user_name: "Mike".to_string(),
+            user_pronouns: None,
+            context: vec![(
+                "mike".to_string(),
+                "Mike".to_string(),
+                None,
+                "Crow, what's the best movie?".to_string(),
+            )],
+            response: response.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_rephrase_target_lookup() {
+        let bot = UserId::new(1);
+        let replies = RecentReplies::new(Duration::from_secs(60), 2);
+        let start = Instant::now();
+        replies.record_at(MessageId::new(10), reply("Manos, obviously."), start);
+
+        // Only replies to the bot's own remembered messages can be rephrased
+        let (id, found) = replies
+            .rephrase_target(Some((MessageId::new(10), bot)), bot)
+            .unwrap();
+        assert_eq!(id, MessageId::new(10));
+        assert_eq!(found.response, "Manos, obviously.");
+        assert!(replies.rephrase_target(None, bot).is_err());
+        assert!(replies
+            .rephrase_target(Some((MessageId::new(10), UserId::new(2))), bot)
+            .is_err());
+        assert!(replies
+            .rephrase_target(Some((MessageId::new(11), bot)), bot)
+            .is_err());
+
+        // Entries expire, and the oldest is dropped when full
+        assert!(replies
+            .get_at(MessageId::new(10), start + Duration::from_secs(61))
+            .is_none());
+        replies.record_at(
+            MessageId::new(11),
+            reply("b"),
+            start + Duration::from_secs(1),
+        );
+        replies.record_at(
+            MessageId::new(12),
+            reply("c"),
+            start + Duration::from_secs(2),
+        );
+        assert!(replies.get_at(MessageId::new(10), start).is_none());
+        assert!(replies.get_at(MessageId::new(12), start).is_some());
+    }
+
+    #[test]
+    fn test_rephrase_request_parsing() {
+        assert_eq!(
+            parse_rephrase_args(&[]).unwrap(),
+            RephraseRequest::default()
+        );
+        assert_eq!(
+            parse_rephrase_args(&["more", "concise", "-t", "0.4"]).unwrap(),
+            RephraseRequest {
+                style: Some("more concise".to_string()),
+                temperature: Some(0.4),
+            }
+        );
+        assert!(parse_rephrase_args(&["-t"]).is_err());
+        assert!(parse_rephrase_args(&["-t", "5"]).is_err());
+        assert!(parse_rephrase_args(&["-t", "hot"]).is_err());
+
+        let prompt = rephrase_prompt(&reply("Manos, obviously."), Some("funnier"));
+        assert!(prompt.starts_with("Crow, what's the best movie?"));
+        assert!(prompt.contains("\"Manos, obviously.\""));
+        assert!(prompt.contains("style: funnier"));
+    }
+}
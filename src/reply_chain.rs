@@ -0,0 +1,166 @@
+use serenity::all::Http;
+use serenity::model::channel::Message;
+use serenity::model::id::{ChannelId, MessageId};
+use std::collections::HashSet;
+use std::future::Future;
+use tracing::debug;
+
+// Hard cap on hops no matter what the config says; each hop past the first is an API call
+pub const MAX_REPLY_CHAIN_DEPTH: usize = 10;
+
+/// One message in a reply chain, plus where the message it replied to lives
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplyLink {
+    pub author: String,
+    pub content: String,
+    pub parent: Option<(ChannelId, MessageId)>,
+}
+
+impl ReplyLink {
+    pub fn from_message(msg: &Message) -> Self {
+        let author = msg
+            .author
+            .global_name
+            .clone()
+            .unwrap_or_else(|| msg.author.name.clone());
+
+        Self {
+            author: crate::display_name::clean_display_name(&author),
+            content: msg.content.clone(),
+            parent: msg.message_reference.as_ref().and_then(|reference| {
+                reference
+                    .message_id
+                    .map(|message_id| (reference.channel_id, message_id))
+            }),
+        }
+    }
+}
+
+/// Follow reply links back from `first` for at most `max_depth` messages (including `first`).
+/// `fetch` looks up a parent message and returns None if it's gone or unreadable, which ends
+/// the walk. The chain is returned oldest first.
+pub async fn walk_reply_chain<F, Fut>(
+    first: Option<ReplyLink>,
+    max_depth: usize,
+    mut fetch: F,
+) -> Vec<ReplyLink>
+where
+    F: FnMut(ChannelId, MessageId) -> Fut,
+    Fut: Future<Output = Option<ReplyLink>>,
+{
+    let max_depth = max_depth.min(MAX_REPLY_CHAIN_DEPTH);
+    let mut chain = Vec::new();
+    let mut seen = HashSet::new();
+    let mut next = first;
+
+    while let Some(link) = next.take() {
+        if chain.len() >= max_depth {
+            break;
+        }
+
+        let parent = link.parent;
+        chain.push(link);
+
+        if chain.len() >= max_depth {
+            break;
+        }
+
+        if let Some((channel_id, message_id)) = parent {
+            // Guard against reference loops
+            if !seen.insert(message_id) {
+                break;
+            }
+            next = fetch(channel_id, message_id).await;
+            if next.is_none() {
+                debug!("Reply chain ends at missing message {}", message_id);
+            }
+        }
+    }
+
+    chain.reverse();
+    chain
+}
+
+/// Collect the reply thread leading up to `msg`, oldest first.
+/// The directly referenced message comes with the event; older hops are fetched.
+pub async fn collect_reply_thread(http: &Http, msg: &Message, max_depth: usize) -> Vec<ReplyLink> {
+    if max_depth == 0 {
+        return Vec::new();
+    }
+
+    let first = msg
+        .referenced_message
+        .as_deref()
+        .map(ReplyLink::from_message);
+
+    walk_reply_chain(first, max_depth, |channel_id, message_id| async move {
+        channel_id
+            .message(http, message_id)
+            .await
+            .ok()
+            .map(|parent| ReplyLink::from_message(&parent))
+    })
+    .await
+}
+
+/// Format a reply thread as prompt context, or None if there's no thread
+pub fn format_reply_thread(chain: &[ReplyLink]) -> Option<String> {
+    if chain.is_empty() {
+        return None;
+    }
+
+    let lines = chain
+        .iter()
+        .map(|link| format!("{}: {}", link.author, link.content))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    Some(format!(
+        "[This message is a reply in the following thread, oldest first:]\n{lines}\n[End of thread]\n\n"
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn link(id: u64, parent: Option<u64>) -> ReplyLink {
+        ReplyLink {
+            author: format!("user{id}"),
+            content: format!("message {id}"),
+            parent: parent.map(|p| (ChannelId::new(1), MessageId::new(p))),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_walk_reply_chain_depth_and_missing_messages() {
+        // 5 replies to 4 replies to 3 ... replies to 1; message 2 has been deleted
+        let store: HashMap<u64, ReplyLink> = [(4, link(4, Some(3))), (3, link(3, Some(2)))]
+            .into_iter()
+            .chain((10..20).map(|id| (id, link(id, Some(id - 1)))))
+            .collect();
+        let fetch = |_channel: ChannelId, id: MessageId| {
+            let found = store.get(&id.get()).cloned();
+            async move { found }
+        };
+
+        // Depth cap stops the walk early
+        let chain = walk_reply_chain(Some(link(5, Some(4))), 2, fetch).await;
+        let contents: Vec<_> = chain.iter().map(|l| l.content.as_str()).collect();
+        assert_eq!(contents, vec!["message 4", "message 5"]);
+
+        // A missing parent ends the chain without failing
+        let chain = walk_reply_chain(Some(link(5, Some(4))), 5, fetch).await;
+        let contents: Vec<_> = chain.iter().map(|l| l.content.as_str()).collect();
+        assert_eq!(contents, vec!["message 3", "message 4", "message 5"]);
+
+        // Configured depth can't exceed the hard cap
+        let chain = walk_reply_chain(Some(link(20, Some(19))), 100, fetch).await;
+        assert_eq!(chain.len(), MAX_REPLY_CHAIN_DEPTH);
+
+        // No referenced message means no thread
+        assert!(walk_reply_chain(None, 5, fetch).await.is_empty());
+        assert_eq!(format_reply_thread(&[]), None);
+    }
+}
@@ -1,4 +1,4 @@
-use serenity::prelude::*;
+use serenity::http::Http;
 use std::time::{Duration, Instant};
 use tokio::time::sleep;
 use tracing::info;
@@ -7,7 +7,7 @@ use serenity::model::id::ChannelId;
 
 /// Calculates and applies a realistic typing delay based on response length
 /// Also shows typing indicator in the channel during the delay
-pub async fn apply_realistic_delay(response: &str, ctx: &Context, channel_id: ChannelId) {
+pub async fn apply_realistic_delay(response: &str, http: impl AsRef<Http>, channel_id: ChannelId) {
     // Record when we got the response
     let response_received = Instant::now();
 
@@ -22,7 +22,7 @@ pub async fn apply_realistic_delay(response: &str, ctx: &Context, channel_id: Ch
     let delay = Duration::from_secs_f32(delay_seconds);
 
     // Start typing indicator
-    if let Err(e) = channel_id.broadcast_typing(&ctx.http).await {
+    if let Err(e) = channel_id.broadcast_typing(http).await {
         info!("Failed to send typing indicator: {:?}", e);
     } else {
         info!("Started typing indicator in channel {}", channel_id);
@@ -0,0 +1,2064 @@
+use crate::http_client;
+use ab_glyph::{point, Font, FontRef, GlyphId, PxScale, PxScaleFont, ScaleFont};
+use anyhow::{anyhow, Result};
+use rand::seq::IndexedRandom;
+use serenity::all::Http;
+use serenity::builder::{CreateAttachment, CreateMessage, EditMessage};
+use serenity::model::channel::Message;
+use serenity::model::channel::ReactionType;
+use serenity::model::id::{ChannelId, MessageId, UserId};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tracing::{error, info};
+use zune_jpeg::zune_core::bytestream::ZCursor;
+use zune_jpeg::zune_core::colorspace::ColorSpace;
+use zune_jpeg::zune_core::options::DecoderOptions;
+use zune_jpeg::JpegDecoder;
+
+// Discord's limit on attachment descriptions
+const MAX_ALT_TEXT_CHARS: usize = 1024;
+
+// How long !again remembers someone's last screenshot search
+pub const LAST_SEARCH_TTL: Duration = Duration::from_secs(30 * 60);
+
+// Most candidates listed by a -debug search
+const DEBUG_MAX_CANDIDATES: usize = 10;
+
+/// How long a paged screenshot search's ◀️/▶️ reactions keep working
+pub const SCREENSHOT_PAGE_TTL: Duration = Duration::from_secs(5 * 60);
+// Most of a search's frames that can be paged through
+const SCREENSHOT_PAGE_FRAMES: usize = 10;
+/// Reactions that page a screenshot search back and forward
+pub const PREVIOUS_PAGE_EMOJI: &str = "◀️";
+pub const NEXT_PAGE_EMOJI: &str = "▶️";
+
+// Spacing between caption samples when gathering dialogue context around a frame
+const CONTEXT_SAMPLE_STEP_MS: u64 = 3000;
+// Number of subtitle lines shown before and after the frame with -context
+const CONTEXT_LINES: usize = 3;
+
+/// Longest GIF rendered for a screenshot unless SCREENSHOT_GIF_MAX_SECS says otherwise
+pub const DEFAULT_GIF_MAX_DURATION: Duration = Duration::from_secs(10);
+
+/// A screenshot service whose searches `!again` can repeat
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScreenshotService {
+    Frinkiac,
+    Morbotron,
+}
+
+impl ScreenshotService {
+    /// The command that searches this service
+    pub fn command(&self) -> &'static str {
+        match self {
+            ScreenshotService::Frinkiac => "frinkiac",
+            ScreenshotService::Morbotron => "morbotron",
+        }
+    }
+}
+
+/// A screenshot search as typed: the service and its arguments (None for a random frame)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LastSearch {
+    pub service: ScreenshotService,
+    pub args: Option<String>,
+}
+
+/// Each user's most recent screenshot search per channel, for `!again`. Repeating the exact
+/// query makes the service clients rotate to their next result, so the frame changes.
+pub struct LastSearches {
+    ttl: Duration,
+    searches: std::sync::Mutex<HashMap<(UserId, ChannelId), (LastSearch, Instant)>>,
+}
+
+impl LastSearches {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            searches: std::sync::Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Remember a search, replacing the user's previous one in that channel
+    pub fn record(&self, user_id: UserId, channel_id: ChannelId, search: LastSearch) {
+        self.record_at(user_id, channel_id, search, Instant::now());
+    }
+
+    /// The user's last search in the channel, if it hasn't expired
+    pub fn last(&self, user_id: UserId, channel_id: ChannelId) -> Option<LastSearch> {
+        self.last_at(user_id, channel_id, Instant::now())
+    }
+
+    fn record_at(&self, user_id: UserId, channel_id: ChannelId, search: LastSearch, now: Instant) {
+        let mut searches = self.searches.lock().unwrap();
+        // Drop expired entries so the map doesn't grow forever
+        searches.retain(|_, (_, at)| now.duration_since(*at) < self.ttl);
+        searches.insert((user_id, channel_id), (search, now));
+    }
+
+    fn last_at(&self, user_id: UserId, channel_id: ChannelId, now: Instant) -> Option<LastSearch> {
+        let searches = self.searches.lock().unwrap();
+        searches
+            .get(&(user_id, channel_id))
+            .filter(|(_, at)| now.duration_since(*at) < self.ttl)
+            .map(|(search, _)| search.clone())
+    }
+}
+
+/// A screenshot search posted with ◀️/▶️ reactions for flicking through its frames
+#[derive(Clone, Debug, PartialEq)]
+pub struct ScreenshotPage {
+    /// Who searched; only their reactions turn the page
+    pub user_id: UserId,
+    /// The command that searched, to find the site again
+    pub command: String,
+    pub frames: Vec<Frame>,
+    /// Which of the frames the message shows
+    pub index: usize,
+    /// Whether the frames are posted captioned (-caption)
+    pub caption: bool,
+}
+
+/// Paged screenshot searches by the message showing them, forgotten once `ttl` has passed
+/// since the search
+pub struct ScreenshotPages {
+    ttl: Duration,
+    pages: std::sync::Mutex<HashMap<MessageId, (ScreenshotPage, Instant)>>,
+}
+
+impl ScreenshotPages {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            pages: std::sync::Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Remember the frames a posted search can page through
+    pub fn record(&self, message_id: MessageId, page: ScreenshotPage) {
+        self.record_at(message_id, page, Instant::now());
+    }
+
+    /// Move a paged search on by one frame (`forward`) or back one, wrapping at the ends.
+    /// Returns the page now showing, or None if the message isn't a paged search, it has
+    /// expired, or `user_id` isn't who searched.
+    pub fn turn(
+        &self,
+        message_id: MessageId,
+        user_id: UserId,
+        forward: bool,
+    ) -> Option<ScreenshotPage> {
+        self.turn_at(message_id, user_id, forward, Instant::now())
+    }
+
+    fn record_at(&self, message_id: MessageId, page: ScreenshotPage, now: Instant) {
+        let mut pages = self.pages.lock().unwrap();
+        // Drop expired entries so the map doesn't grow forever
+        pages.retain(|_, (_, at)| now.duration_since(*at) < self.ttl);
+        pages.insert(message_id, (page, now));
+    }
+
+    fn turn_at(
+        &self,
+        message_id: MessageId,
+        user_id: UserId,
+        forward: bool,
+        now: Instant,
+    ) -> Option<ScreenshotPage> {
+        let mut pages = self.pages.lock().unwrap();
+        let (page, _) = pages
+            .get_mut(&message_id)
+            .filter(|(page, at)| now.duration_since(*at) < self.ttl && page.user_id == user_id)?;
+
+        let len = page.frames.len();
+        page.index = if forward {
+            (page.index + 1) % len
+        } else {
+            (page.index + len - 1) % len
+        };
+        Some(page.clone())
+    }
+}
+
+/// A frame a screenshot search considered
+#[derive(Clone, Debug, PartialEq)]
+pub struct Frame {
+    pub episode: String,
+    pub timestamp: u64,
+    /// Position in the search API's own relevance order (1 = best match)
+    pub api_rank: usize,
+    /// Why our ordering moved it, if it did
+    pub note: Option<&'static str>,
+}
+
+impl Frame {
+    /// A frame picked by timestamp rather than by a search
+    pub fn at(episode: &str, timestamp: u64) -> Self {
+        Self {
+            episode: episode.to_string(),
+            timestamp,
+            api_rank: 0,
+            note: None,
+        }
+    }
+}
+
+/// The candidates a search ranked, in the order it serves them, and which one it showed
+#[derive(Clone, Debug, PartialEq)]
+pub struct SearchRanking {
+    pub query: String,
+    pub candidates: Vec<Frame>,
+    pub chosen: usize,
+}
+
+impl SearchRanking {
+    /// Owner-facing breakdown of the candidates and how they ranked, for `-debug`
+    pub fn format_debug(&self) -> String {
+        let mut lines = vec![format!(
+            "🔍 **Search debug** for \"{}\": {} candidate{} (one per episode, by API relevance rank)",
+            self.query,
+            self.candidates.len(),
+            if self.candidates.len() == 1 { "" } else { "s" }
+        )];
+
+        for (i, candidate) in self
+            .candidates
+            .iter()
+            .enumerate()
+            .take(DEBUG_MAX_CANDIDATES)
+        {
+            let marker = if i == self.chosen { "▶" } else { "•" };
+            let note = candidate
+                .note
+                .map(|note| format!(", {note}"))
+                .unwrap_or_default();
+            lines.push(format!(
+                "{marker} {}. {} @ {} — API rank #{}{note}",
+                i + 1,
+                candidate.episode,
+                format_episode_time(candidate.timestamp),
+                candidate.api_rank
+            ));
+        }
+
+        if self.candidates.len() > DEBUG_MAX_CANDIDATES {
+            lines.push(format!(
+                "…and {} more",
+                self.candidates.len() - DEBUG_MAX_CANDIDATES
+            ));
+        }
+        if self.chosen >= DEBUG_MAX_CANDIDATES {
+            lines.push(format!("▶ Showing candidate {}", self.chosen + 1));
+        }
+
+        lines.join("\n")
+    }
+}
+
+/// What a screenshot site remembers between searches: the last query and which of its
+/// frames was shown, so repeating the query moves on to the next one, and how the most
+/// recent search ranked its frames
+#[derive(Default)]
+pub struct SearchState {
+    last: std::sync::Mutex<Option<(String, usize)>>,
+    ranking: std::sync::Mutex<Option<SearchRanking>>,
+}
+
+impl SearchState {
+    /// Which of a query's `len` frames to show: the first for a new query, the one after
+    /// last time's for a repeated one
+    pub fn next_index(&self, query: &str, len: usize) -> usize {
+        let mut last = self.last.lock().unwrap();
+        let index = match last.as_ref() {
+            Some((last_query, index)) if last_query == query => (index + 1) % len.max(1),
+            _ => 0,
+        };
+        *last = Some((query.to_string(), index));
+        index
+    }
+
+    pub fn record_ranking(&self, ranking: Option<SearchRanking>) {
+        *self.ranking.lock().unwrap() = ranking;
+    }
+
+    pub fn last_ranking(&self) -> Option<SearchRanking> {
+        self.ranking.lock().unwrap().clone()
+    }
+}
+
+/// A subtitle with timing for GIF overlay
+#[derive(Debug, Clone)]
+pub struct TimedSubtitle {
+    pub text: String,
+    pub start: u64,
+    pub end: u64,
+    /// A valid frame timestamp for this subtitle (always maps to an indexed frame)
+    pub representative_ts: u64,
+}
+
+/// A frame with its episode details and subtitles, ready to post
+#[derive(Debug, Clone)]
+pub struct Screenshot {
+    pub episode: String,
+    pub timestamp: u64,
+    pub season: u32,
+    pub episode_number: u32,
+    pub episode_title: String,
+    pub image_url: String,
+    pub caption: String,
+    pub start_timestamp: u64,
+    pub end_timestamp: u64,
+    pub subtitles: Vec<TimedSubtitle>,
+    pub gif_url: Option<String>,
+}
+
+impl Screenshot {
+    /// The subtitle lines joined into one piece of text
+    pub fn subtitle_text(&self, separator: &str) -> String {
+        self.subtitles
+            .iter()
+            .map(|sub| sub.text.as_str())
+            .collect::<Vec<_>>()
+            .join(separator)
+    }
+}
+
+/// The fixed details of a screenshot site and the messages its command posts
+pub struct ScreenshotSite {
+    /// Site root, which renders and serves the GIFs
+    pub base_url: Cow<'static, str>,
+    /// Font the subtitles are drawn in on GIFs
+    pub gif_font: &'static str,
+    /// File name for uploaded screenshots, without the extension
+    pub upload_name: Cow<'static, str>,
+    /// The show, as in "Couldn't find any Simpsons screenshots"
+    pub show: Cow<'static, str>,
+    pub no_random_message: Cow<'static, str>,
+    pub random_error_message: Cow<'static, str>,
+    pub search_error_message: Cow<'static, str>,
+    /// Whether the command takes -s/-e season and episode filters
+    pub options: bool,
+    /// Set once the site has gone away; the command just posts this
+    pub shut_down_notice: Option<&'static str>,
+}
+
+/// A Frinkiac-family screenshot site. Each show implements this once and
+/// `handle_screenshot_command` does the searching, rotating, GIF rendering and posting.
+pub trait ScreenshotProvider: Sync {
+    /// The site's name, for logs and errors
+    fn name(&self) -> &str;
+
+    fn site(&self) -> &ScreenshotSite;
+
+    /// The frames a search turns up, in the order they should be shown
+    async fn search(&self, term: &str) -> Result<Vec<Frame>>;
+
+    /// The caption API URL for a frame
+    fn caption_url(&self, frame: &Frame) -> String;
+
+    /// The frame with its episode details and subtitles, or None if it has none
+    async fn caption(&self, frame: &Frame) -> Result<Option<Screenshot>>;
+
+    /// Searches to pick from for a random screenshot
+    fn random_terms(&self) -> &[&str];
+
+    /// A random frame; by default a random result for one of the random terms
+    async fn random_frame(&self) -> Result<Option<Frame>> {
+        random_term_frame(self).await
+    }
+
+    fn search_state(&self) -> &SearchState;
+
+    /// How the most recent search ranked its candidates, for `-debug`
+    fn last_ranking(&self) -> Option<SearchRanking> {
+        self.search_state().last_ranking()
+    }
+
+    /// The heading posted with a screenshot
+    fn title(&self, shot: &Screenshot) -> String {
+        format!(
+            "S{:02}E{:02} - {}",
+            shot.season, shot.episode_number, shot.episode_title
+        )
+    }
+
+    /// A screenshot posted as text: heading, media link and (unless the GIF shows it)
+    /// the caption
+    fn format_result(&self, shot: &Screenshot) -> String {
+        let title = self.title(shot);
+        match &shot.gif_url {
+            Some(gif_url) => format!("**{title}**\n{gif_url}"),
+            None => format!("**{title}**\n{}\n\n{}", shot.image_url, shot.caption),
+        }
+    }
+
+    /// The text posted above an uploaded still frame
+    fn format_still(&self, shot: &Screenshot) -> String {
+        format!("**{}**\n\n{}", self.title(shot), shot.caption)
+    }
+}
+
+/// A random frame from a search for one of the provider's random terms
+pub async fn random_term_frame<P: ScreenshotProvider + ?Sized>(
+    provider: &P,
+) -> Result<Option<Frame>> {
+    let term = provider
+        .random_terms()
+        .choose(&mut rand::rng())
+        .ok_or_else(|| anyhow!("Failed to choose random search term"))?
+        .to_string();
+    info!("Using random search term: {}", term);
+
+    let frames = provider.search(&term).await?;
+    Ok(frames.choose(&mut rand::rng()).cloned())
+}
+
+/// A random frame from a site's random API (`/api/random`)
+pub async fn random_api_frame(http_client: &reqwest::Client, random_url: &str) -> Result<Frame> {
+    let random_response = http_client
+        .get(random_url)
+        .send()
+        .await
+        .map_err(|e| anyhow!("Failed to get random screenshot: {}", e))?;
+
+    if !random_response.status().is_success() {
+        return Err(anyhow!(
+            "Random screenshot request failed with status: {}",
+            random_response.status()
+        ));
+    }
+
+    // Parse the random result as a generic JSON Value first
+    let random_result: serde_json::Value = random_response
+        .json()
+        .await
+        .map_err(|e| anyhow!("Failed to parse random screenshot result: {}", e))?;
+
+    // Extract the episode and timestamp
+    let episode = random_result
+        .get("Episode")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| anyhow!("Missing Episode in random result"))?;
+
+    let timestamp = random_result
+        .get("Timestamp")
+        .and_then(|v| v.as_u64())
+        .ok_or_else(|| anyhow!("Missing Timestamp in random result"))?;
+
+    Ok(Frame::at(episode, timestamp))
+}
+
+/// Read a caption API response, which has the same shape on every site, into a screenshot
+/// of the frame at `timestamp`. The caption text is left for the site to format.
+pub fn parse_caption(
+    json: &serde_json::Value,
+    episode: &str,
+    timestamp: u64,
+    image_url: String,
+) -> Result<Screenshot> {
+    let episode_info = json
+        .get("Episode")
+        .ok_or_else(|| anyhow!("Missing Episode info in caption result"))?;
+
+    let episode_title = episode_info
+        .get("Title")
+        .and_then(|v| v.as_str())
+        .unwrap_or("Unknown")
+        .to_string();
+
+    let season = episode_info
+        .get("Season")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(0) as u32;
+
+    let episode_number = episode_info
+        .get("EpisodeNumber")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(0) as u32;
+
+    let subtitles: Vec<TimedSubtitle> = json
+        .get("Subtitles")
+        .and_then(|v| v.as_array())
+        .ok_or_else(|| anyhow!("Missing Subtitles in caption result"))?
+        .iter()
+        .filter_map(|s| {
+            Some(TimedSubtitle {
+                text: s.get("Content")?.as_str()?.to_string(),
+                start: s.get("StartTimestamp")?.as_u64()?,
+                end: s.get("EndTimestamp")?.as_u64()?,
+                representative_ts: s.get("RepresentativeTimestamp")?.as_u64()?,
+            })
+        })
+        .collect();
+
+    // Extract subtitle time range
+    let start_timestamp = subtitles.first().map(|s| s.start).unwrap_or(timestamp);
+    let end_timestamp = subtitles.last().map(|s| s.end).unwrap_or(timestamp + 4000);
+
+    Ok(Screenshot {
+        episode: episode.to_string(),
+        timestamp,
+        season,
+        episode_number,
+        episode_title,
+        image_url,
+        caption: String::new(),
+        start_timestamp,
+        end_timestamp,
+        subtitles,
+        gif_url: None,
+    })
+}
+
+/// Pull a `-debug` flag out of screenshot command arguments
+pub fn take_debug_flag(args: Option<String>) -> (Option<String>, bool) {
+    take_flag(args, "debug")
+}
+
+// Pull `-<name>` (or `--<name>`) out of command arguments, saying whether it was there
+fn take_flag(args: Option<String>, name: &str) -> (Option<String>, bool) {
+    let Some(args) = args else {
+        return (None, false);
+    };
+
+    let mut found = false;
+    let rest: Vec<&str> = args
+        .split_whitespace()
+        .filter(|part| {
+            let is_flag = part.strip_prefix("--").or_else(|| part.strip_prefix('-')) == Some(name);
+            found |= is_flag;
+            !is_flag
+        })
+        .collect();
+
+    let rest = (!rest.is_empty()).then(|| rest.join(" "));
+    (rest, found)
+}
+
+/// Format a millisecond timestamp as m:ss
+pub fn format_episode_time(ms: u64) -> String {
+    let secs = ms / 1000;
+    format!("{}:{:02}", secs / 60, secs % 60)
+}
+
+/// Alt text for a screenshot built from the frame's subtitle, or None if there isn't one
+pub fn alt_text_from_caption(caption: &str) -> Option<String> {
+    let text = caption.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.is_empty() {
+        return None;
+    }
+
+    if text.chars().count() > MAX_ALT_TEXT_CHARS {
+        let truncated: String = text.chars().take(MAX_ALT_TEXT_CHARS - 1).collect();
+        Some(format!("{}…", truncated.trim_end()))
+    } else {
+        Some(text)
+    }
+}
+
+/// The render endpoint that turns a clip into a GIF, on any of the Frinkiac-family sites
+/// (Frinkiac, Morbotron, Master of All Science)
+pub fn gif_render_url(base_url: &str) -> String {
+    format!("{}/api/render/gif/stream", base_url.trim_end_matches('/'))
+}
+
+/// The part of a clip (episode timestamps in milliseconds) to render as a GIF: from the
+/// start, cut to at most `max_duration`
+pub fn gif_range(start: u64, end: u64, max_duration: Duration) -> (u64, u64) {
+    let max_ms = max_duration.as_millis() as u64;
+    (start, end.max(start).min(start.saturating_add(max_ms)))
+}
+
+/// Merge subtitle fragments that are continuations of the same sentence.
+/// If a subtitle starts with lowercase or continues a sentence ending with a comma,
+/// merge it with the previous one.
+pub fn merge_subtitle_fragments(subs: &[TimedSubtitle]) -> Vec<TimedSubtitle> {
+    if subs.is_empty() {
+        return Vec::new();
+    }
+
+    let mut merged: Vec<TimedSubtitle> = Vec::new();
+
+    for sub in subs {
+        let should_merge = if let Some(prev) = merged.last() {
+            // Merge if: previous ends with comma, or current starts with lowercase
+            prev.text.ends_with(',') || sub.text.chars().next().is_some_and(|c| c.is_lowercase())
+        } else {
+            false
+        };
+
+        if should_merge {
+            let prev = merged.last_mut().unwrap();
+            prev.text = format!("{} {}", prev.text, sub.text);
+            prev.end = sub.end;
+        } else {
+            merged.push(sub.clone());
+        }
+    }
+
+    merged
+}
+
+/// Pick the subtitle at `timestamp` plus up to `before`/`after` lines around it.
+/// Input may be unsorted and contain duplicates from overlapping caption lookups.
+pub fn window_subtitles(
+    subs: &[TimedSubtitle],
+    timestamp: u64,
+    before: usize,
+    after: usize,
+) -> Vec<TimedSubtitle> {
+    let mut sorted: Vec<TimedSubtitle> = subs.to_vec();
+    sorted.sort_by_key(|s| (s.start, s.end));
+    sorted.dedup_by(|a, b| a.start == b.start && a.text == b.text);
+
+    if sorted.is_empty() {
+        return sorted;
+    }
+
+    // The line being spoken at the frame, or failing that the next one to start
+    let center = sorted
+        .iter()
+        .position(|s| s.start <= timestamp && timestamp <= s.end)
+        .or_else(|| sorted.iter().position(|s| s.start > timestamp))
+        .unwrap_or(sorted.len() - 1);
+
+    let first = center.saturating_sub(before);
+    let last = (center + after + 1).min(sorted.len());
+    sorted[first..last].to_vec()
+}
+
+/// Format subtitle lines as a short script block, marking the lines at the frame
+pub fn format_context_script(subs: &[TimedSubtitle], timestamp: u64) -> String {
+    let lines = subs
+        .iter()
+        .map(|s| {
+            let marker = if s.start <= timestamp && timestamp <= s.end {
+                "> "
+            } else {
+                "  "
+            };
+            format!("{marker}{}", s.text)
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    format!("```\n{lines}\n```")
+}
+
+// Generate a GIF of a clip using the site's render API, cut to at most `max_duration`.
+// None if rendering fails, so the caller can post the still frame instead.
+#[allow(clippy::too_many_arguments)]
+pub async fn generate_gif(
+    base_url: &str,
+    episode: &str,
+    start: u64,
+    end: u64,
+    subtitles: &[TimedSubtitle],
+    font_size: u32,
+    font: &str,
+    max_duration: Duration,
+) -> Option<String> {
+    let url = gif_render_url(base_url);
+    let (start, end) = gif_range(start, end, max_duration);
+
+    let overlays: Vec<serde_json::Value> = subtitles
+        .iter()
+        .map(|sub| {
+            serde_json::json!({
+                "text": sub.text,
+                "font": font,
+                "x": 50,
+                "y": 90,
+                "text_align": "c",
+                "all_caps": true,
+                "size": font_size,
+                "color": [255, 255, 255, 255],
+                "start": sub.start.saturating_sub(start),
+                "end": sub.end.saturating_sub(start)
+            })
+        })
+        .collect();
+
+    let body = serde_json::json!([{
+        "episode": episode,
+        "start": start,
+        "end": end,
+        "overlays": overlays
+    }]);
+
+    let response = http_client::shared()
+        .post(&url)
+        .header("Content-Type", "application/json")
+        .body(body.to_string())
+        .send()
+        .await
+        .ok()?;
+
+    if !response.status().is_success() {
+        error!("GIF generation failed with status: {}", response.status());
+        return None;
+    }
+
+    let text = response.text().await.ok()?;
+
+    // Parse newline-delimited JSON, find the line with "url"
+    for line in text.lines().rev() {
+        if let Ok(val) = serde_json::from_str::<serde_json::Value>(line) {
+            if let Some(gif_path) = val.get("url").and_then(|v| v.as_str()) {
+                let full_url = format!("{base_url}{gif_path}");
+                info!("Generated GIF: {}", full_url);
+                return Some(full_url);
+            }
+        }
+    }
+
+    error!("GIF generation did not return a URL");
+    None
+}
+
+/// Build the upload for a screenshot, using the alt text as the attachment description
+pub fn screenshot_attachment(
+    bytes: Vec<u8>,
+    filename: &str,
+    alt_text: Option<&str>,
+) -> CreateAttachment {
+    let attachment = CreateAttachment::bytes(bytes, filename.to_string());
+    match alt_text {
+        Some(text) => attachment.description(text),
+        None => attachment,
+    }
+}
+
+// Download a screenshot or GIF, or None if it can't be fetched
+async fn download_media(url: &str) -> Option<Vec<u8>> {
+    match http_client::shared().get(url).send().await {
+        Ok(resp) if resp.status().is_success() => resp.bytes().await.ok().map(|b| b.to_vec()),
+        Ok(resp) => {
+            error!("Download of {} failed with status: {}", url, resp.status());
+            None
+        }
+        Err(e) => {
+            error!("Error downloading {}: {:?}", url, e);
+            None
+        }
+    }
+}
+
+/// Longest caption written onto a frame with -caption; longer ones are cut at a word
+const MEME_CAPTION_MAX_CHARS: usize = 200;
+// Most lines of caption on a frame before the rest is cut
+const MEME_CAPTION_MAX_LINES: usize = 4;
+
+// The font -caption writes with (DejaVu Sans Bold, see assets/fonts/DejaVuSans-LICENSE)
+static MEME_FONT: &[u8] = include_bytes!("../assets/fonts/DejaVuSans-Bold.ttf");
+
+/// A decoded frame, 3 bytes (RGB) per pixel
+pub struct RgbFrame {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+impl RgbFrame {
+    // Mix `color` into the pixel at (x, y) by `coverage`; off-frame pixels are ignored
+    fn blend(&mut self, x: i32, y: i32, color: [u8; 3], coverage: f32) {
+        if x < 0 || y < 0 || x >= self.width as i32 || y >= self.height as i32 {
+            return;
+        }
+        let coverage = coverage.clamp(0.0, 1.0);
+        let offset = (y as usize * self.width as usize + x as usize) * 3;
+        for (channel, value) in self.pixels[offset..offset + 3].iter_mut().zip(color) {
+            *channel = (*channel as f32 * (1.0 - coverage) + value as f32 * coverage).round() as u8;
+        }
+    }
+}
+
+/// Decode a JPEG screenshot into RGB pixels
+pub fn decode_jpeg(bytes: &[u8]) -> Result<RgbFrame> {
+    let options = DecoderOptions::default().jpeg_set_out_colorspace(ColorSpace::RGB);
+    let mut decoder = JpegDecoder::new_with_options(ZCursor::new(bytes), options);
+    let pixels = decoder
+        .decode()
+        .map_err(|e| anyhow!("Failed to decode screenshot: {}", e))?;
+    let info = decoder
+        .info()
+        .ok_or_else(|| anyhow!("Screenshot has no image header"))?;
+
+    let (width, height) = (u32::from(info.width), u32::from(info.height));
+    if pixels.len() != width as usize * height as usize * 3 {
+        return Err(anyhow!("Screenshot didn't decode to RGB"));
+    }
+
+    Ok(RgbFrame {
+        width,
+        height,
+        pixels,
+    })
+}
+
+/// Encode a frame as a PNG
+pub fn encode_png(frame: &RgbFrame) -> Result<Vec<u8>> {
+    let mut png_bytes = Vec::new();
+    {
+        let mut encoder = png::Encoder::new(&mut png_bytes, frame.width, frame.height);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&frame.pixels)?;
+        writer.finish()?;
+    }
+    Ok(png_bytes)
+}
+
+/// The caption as it goes on a frame: one entry per caption line, whitespace tidied and the
+/// whole thing cut to MEME_CAPTION_MAX_CHARS at a word boundary
+fn meme_caption_lines(caption: &str) -> Vec<String> {
+    let text = caption
+        .lines()
+        .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let text = if text.chars().count() > MEME_CAPTION_MAX_CHARS {
+        let cut: String = text.chars().take(MEME_CAPTION_MAX_CHARS - 1).collect();
+        let next = text.chars().nth(MEME_CAPTION_MAX_CHARS - 1);
+        // Back up to the last whole word unless the cut already falls between words
+        let cut = match cut.rfind(char::is_whitespace) {
+            Some(space) if !next.is_some_and(char::is_whitespace) => &cut[..space],
+            _ => cut.as_str(),
+        };
+        format!("{}…", cut.trim_end())
+    } else {
+        text
+    };
+
+    text.lines().map(str::to_string).collect()
+}
+
+// Width of `text` in pixels at the font's scale
+fn text_width<F: Font>(font: &PxScaleFont<F>, text: &str) -> f32 {
+    let mut width = 0.0;
+    let mut previous: Option<GlyphId> = None;
+    for c in text.chars() {
+        let id = font.glyph_id(c);
+        if let Some(previous) = previous {
+            width += font.kern(previous, id);
+        }
+        width += font.h_advance(id);
+        previous = Some(id);
+    }
+    width
+}
+
+/// Word-wrap caption lines to fit `max_width` pixels; a word too long for a line on its own
+/// is broken between letters
+fn wrap_caption<F: Font>(font: &PxScaleFont<F>, lines: &[String], max_width: f32) -> Vec<String> {
+    let mut wrapped = Vec::new();
+
+    for line in lines {
+        let mut current = String::new();
+        for word in line.split_whitespace() {
+            let candidate = if current.is_empty() {
+                word.to_string()
+            } else {
+                format!("{current} {word}")
+            };
+            if text_width(font, &candidate) <= max_width {
+                current = candidate;
+                continue;
+            }
+
+            if !current.is_empty() {
+                wrapped.push(std::mem::take(&mut current));
+            }
+            for c in word.chars() {
+                current.push(c);
+                if text_width(font, &current) > max_width && current.chars().count() > 1 {
+                    current.pop();
+                    wrapped.push(std::mem::replace(&mut current, c.to_string()));
+                }
+            }
+        }
+        if !current.is_empty() {
+            wrapped.push(current);
+        }
+    }
+
+    wrapped
+}
+
+// Draw one line of text with its baseline at `baseline`, starting at `x`
+fn draw_text<F: Font>(
+    frame: &mut RgbFrame,
+    font: &PxScaleFont<F>,
+    text: &str,
+    x: f32,
+    baseline: f32,
+    color: [u8; 3],
+) {
+    let mut caret = x;
+    let mut previous: Option<GlyphId> = None;
+    for c in text.chars() {
+        let id = font.glyph_id(c);
+        if let Some(previous) = previous {
+            caret += font.kern(previous, id);
+        }
+        let glyph = id.with_scale_and_position(font.scale(), point(caret, baseline));
+        caret += font.h_advance(id);
+        previous = Some(id);
+
+        if let Some(outlined) = font.outline_glyph(glyph) {
+            let bounds = outlined.px_bounds();
+            outlined.draw(|gx, gy, coverage| {
+                frame.blend(
+                    bounds.min.x as i32 + gx as i32,
+                    bounds.min.y as i32 + gy as i32,
+                    color,
+                    coverage,
+                );
+            });
+        }
+    }
+}
+
+/// Write a caption across the bottom of a frame, meme style: centered white text with a
+/// black outline, shrunk (down to half size) until it fits in MEME_CAPTION_MAX_LINES lines
+pub fn draw_meme_caption(frame: &mut RgbFrame, caption: &str) -> Result<()> {
+    let font = FontRef::try_from_slice(MEME_FONT)?;
+    let lines = meme_caption_lines(caption);
+    if lines.is_empty() {
+        return Ok(());
+    }
+
+    let max_width = frame.width as f32 * 0.92;
+    let largest = (frame.height as f32 / 10.0).max(12.0);
+    let mut size = largest;
+    let (scaled, mut wrapped) = loop {
+        let scaled = font.as_scaled(PxScale::from(size));
+        let wrapped = wrap_caption(&scaled, &lines, max_width);
+        if wrapped.len() <= MEME_CAPTION_MAX_LINES || size <= largest / 2.0 {
+            break (scaled, wrapped);
+        }
+        size *= 0.85;
+    };
+    if wrapped.len() > MEME_CAPTION_MAX_LINES {
+        wrapped.truncate(MEME_CAPTION_MAX_LINES);
+        if let Some(last) = wrapped.last_mut() {
+            last.push('…');
+        }
+    }
+
+    let line_height = scaled.height() + scaled.line_gap();
+    let outline = (size / 16.0).round().max(1.0) as i32;
+    // descent is negative, so the last baseline sits that far above the margin
+    let last_baseline = frame.height as f32 - size / 2.0 + scaled.descent();
+
+    for (i, line) in wrapped.iter().enumerate() {
+        let baseline = last_baseline - (wrapped.len() - 1 - i) as f32 * line_height;
+        let x = (frame.width as f32 - text_width(&scaled, line)) / 2.0;
+
+        for dx in -outline..=outline {
+            for dy in -outline..=outline {
+                if (dx, dy) != (0, 0) && dx * dx + dy * dy <= outline * outline {
+                    draw_text(
+                        frame,
+                        &scaled,
+                        line,
+                        x + dx as f32,
+                        baseline + dy as f32,
+                        [0, 0, 0],
+                    );
+                }
+            }
+        }
+        draw_text(frame, &scaled, line, x, baseline, [255, 255, 255]);
+    }
+
+    Ok(())
+}
+
+/// Turn a JPEG screenshot into a PNG with `caption` written across the bottom
+pub fn render_meme_caption(jpeg: &[u8], caption: &str) -> Result<Vec<u8>> {
+    let mut frame = decode_jpeg(jpeg)?;
+    draw_meme_caption(&mut frame, caption)?;
+    encode_png(&frame)
+}
+
+/// Find the screenshot to show for a search: the next of the search's frames (repeating a
+/// search moves on to the following one), then the words as a quoted phrase if that turns
+/// up nothing, and failing both a random frame
+pub async fn find_screenshot<P: ScreenshotProvider>(
+    provider: &P,
+    term: &str,
+) -> Result<Option<Screenshot>> {
+    info!("{} search for: {}", provider.name(), term);
+    let state = provider.search_state();
+    state.record_ranking(None);
+
+    let mut queries = vec![term.to_string()];
+    // A multi-word search that finds nothing may still match as an exact phrase
+    if term.contains(' ') {
+        queries.push(format!("\"{term}\""));
+    }
+
+    for query in queries {
+        let frames = provider.search(&query).await?;
+        if frames.is_empty() {
+            info!("No results found for query: {}", query);
+            continue;
+        }
+
+        let index = state.next_index(&query, frames.len());
+        let frame = frames[index].clone();
+        info!(
+            "Found {} results for {}, using result {}",
+            frames.len(),
+            query,
+            index + 1
+        );
+        state.record_ranking(Some(SearchRanking {
+            query,
+            candidates: frames,
+            chosen: index,
+        }));
+
+        if let Some(shot) = provider.caption(&frame).await? {
+            return Ok(Some(shot));
+        }
+    }
+
+    info!(
+        "No results found for query: {}, returning random result",
+        term
+    );
+    random_screenshot(provider).await
+}
+
+/// A random screenshot from the site
+pub async fn random_screenshot<P: ScreenshotProvider>(provider: &P) -> Result<Option<Screenshot>> {
+    info!("Getting random {} screenshot", provider.name());
+    provider.search_state().record_ranking(None);
+
+    match provider.random_frame().await? {
+        Some(frame) => provider.caption(&frame).await,
+        None => Ok(None),
+    }
+}
+
+/// Expand subtitles to sentence boundaries by fetching adjacent captions.
+/// If the first subtitle starts mid-sentence, fetches earlier context.
+/// If the last subtitle ends mid-sentence, fetches later context.
+pub async fn expand_to_sentence_boundaries<P: ScreenshotProvider>(
+    provider: &P,
+    result: &mut Screenshot,
+) {
+    let episode = result.episode.clone();
+
+    // Check if first subtitle starts mid-sentence (lowercase first char)
+    let first_starts_mid = result
+        .subtitles
+        .first()
+        .is_some_and(|s| s.text.chars().next().is_some_and(|c| c.is_lowercase()));
+    let first_start = result.subtitles.first().map(|s| s.start).unwrap_or(0);
+
+    if first_starts_mid {
+        let earlier_ts = first_start.saturating_sub(2000);
+        let expanded =
+            if let Ok(Some(earlier)) = provider.caption(&Frame::at(&episode, earlier_ts)).await {
+                let mut to_prepend = Vec::new();
+                for sub in earlier.subtitles.iter().rev() {
+                    if sub.end <= first_start {
+                        to_prepend.push(sub.clone());
+                        if sub.text.chars().next().is_some_and(|c| c.is_uppercase()) {
+                            break;
+                        }
+                    }
+                }
+                to_prepend.reverse();
+                if let Some(first_new) = to_prepend.first() {
+                    result.start_timestamp = first_new.start;
+                    for (i, sub) in to_prepend.into_iter().enumerate() {
+                        result.subtitles.insert(i, sub);
+                    }
+                    true
+                } else {
+                    false
+                }
+            } else {
+                false
+            };
+
+        // If we couldn't find the sentence start, drop the dangling fragment
+        if !expanded && !result.subtitles.is_empty() {
+            let removed = result.subtitles.remove(0);
+            if let Some(new_first) = result.subtitles.first() {
+                result.start_timestamp = new_first.start;
+            }
+            info!("Dropped dangling subtitle fragment: {:?}", removed.text);
+        }
+    }
+
+    // Check if last subtitle ends mid-sentence
+    let last_ends_mid = result.subtitles.last().is_some_and(|s| {
+        s.text.ends_with(',')
+            || (!s.text.ends_with('.')
+                && !s.text.ends_with('!')
+                && !s.text.ends_with('?')
+                && !s.text.ends_with('"'))
+    });
+    let last_rep_ts = result
+        .subtitles
+        .last()
+        .map(|s| s.representative_ts)
+        .unwrap_or(0);
+    let last_end = result.subtitles.last().map(|s| s.end).unwrap_or(0);
+
+    if last_ends_mid {
+        let mut expanded = false;
+
+        if let Ok(Some(later)) = provider.caption(&Frame::at(&episode, last_rep_ts)).await {
+            for sub in &later.subtitles {
+                if sub.start >= last_end {
+                    result.end_timestamp = sub.end;
+                    result.subtitles.push(sub.clone());
+                    expanded = true;
+                    if sub.text.ends_with('.')
+                        || sub.text.ends_with('!')
+                        || sub.text.ends_with('?')
+                        || sub.text.ends_with('"')
+                    {
+                        break;
+                    }
+                }
+            }
+        }
+
+        // If we couldn't find the sentence end, drop the trailing fragment
+        if !expanded && result.subtitles.len() > 1 {
+            result.subtitles.pop();
+            if let Some(new_last) = result.subtitles.last() {
+                result.end_timestamp = new_last.end;
+            }
+        }
+    }
+}
+
+/// Fetch the subtitle lines surrounding a frame by sampling captions at
+/// nearby timestamps, then keep `lines` lines either side of the frame.
+pub async fn context_subtitles<P: ScreenshotProvider>(
+    provider: &P,
+    episode: &str,
+    timestamp: u64,
+    lines: usize,
+) -> Vec<TimedSubtitle> {
+    let mut collected: Vec<TimedSubtitle> = Vec::new();
+
+    // Roughly one subtitle every few seconds, so sample that far out in each direction
+    let steps = lines as u64 + 1;
+    let mut sample_points = vec![timestamp];
+    for step in 1..=steps {
+        let offset = step * CONTEXT_SAMPLE_STEP_MS;
+        // Frames near the start of an episode simply have fewer earlier lines
+        if timestamp >= offset {
+            sample_points.push(timestamp - offset);
+        }
+        sample_points.push(timestamp + offset);
+    }
+
+    for ts in sample_points {
+        // Past the end of the episode the caption lookup fails; just skip those samples
+        if let Ok(Some(frame)) = provider.caption(&Frame::at(episode, ts)).await {
+            collected.extend(frame.subtitles);
+        }
+    }
+
+    window_subtitles(&collected, timestamp, lines, lines)
+}
+
+/// What a screenshot command asked for
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ScreenshotOptions {
+    pub term: Option<String>,
+    pub season: Option<u32>,
+    pub episode: Option<u32>,
+    /// Also post the dialogue around the frame
+    pub context: bool,
+    /// Post the frame with its subtitle written on it instead of a GIF
+    pub caption: bool,
+}
+
+/// Parse screenshot command arguments: search words plus `-s <season>` and `-e <episode>`.
+/// `-caption` and `-context` are taken out beforehand, as every site supports them.
+pub fn parse_options(args: &str) -> ScreenshotOptions {
+    let mut options = ScreenshotOptions::default();
+
+    let mut current_arg = String::new();
+    let mut expecting_season = false;
+    let mut expecting_episode = false;
+
+    for part in args.split_whitespace() {
+        if expecting_season {
+            if let Ok(season) = part.parse::<u32>() {
+                options.season = Some(season);
+            }
+            expecting_season = false;
+            continue;
+        }
+
+        if expecting_episode {
+            if let Ok(episode) = part.parse::<u32>() {
+                options.episode = Some(episode);
+            }
+            expecting_episode = false;
+            continue;
+        }
+
+        if part == "-s" || part == "--season" {
+            expecting_season = true;
+        } else if part == "-e" || part == "--episode" {
+            expecting_episode = true;
+        } else {
+            if !current_arg.is_empty() {
+                current_arg.push(' ');
+            }
+            current_arg.push_str(part);
+        }
+    }
+
+    if !current_arg.is_empty() {
+        options.term = Some(current_arg);
+    }
+
+    options
+}
+
+// Draw a screenshot's caption onto its frame, or None if that can't be done
+async fn render_meme<P: ScreenshotProvider>(provider: &P, shot: &Screenshot) -> Option<Vec<u8>> {
+    let jpeg = download_media(&shot.image_url).await?;
+    let caption = shot.caption.clone();
+    match tokio::task::spawn_blocking(move || render_meme_caption(&jpeg, &caption)).await {
+        Ok(Ok(png)) => Some(png),
+        Ok(Err(e)) => {
+            error!("Error captioning {} frame: {:?}", provider.name(), e);
+            None
+        }
+        Err(e) => {
+            error!("Captioning {} frame panicked: {:?}", provider.name(), e);
+            None
+        }
+    }
+}
+
+/// A screenshot ready to send: the message text and, when the media could be fetched,
+/// the upload that goes with it
+struct ScreenshotPost {
+    content: String,
+    attachment: Option<CreateAttachment>,
+}
+
+// Build a finished screenshot's post: the frame captioned (with -caption) or rendered as a
+// GIF, uploaded, with plain text and links as the fallback. With `alt_text` on, the
+// frame's subtitle becomes the attachment description.
+async fn build_post<P: ScreenshotProvider>(
+    provider: &P,
+    shot: &mut Screenshot,
+    caption: bool,
+    alt_text: bool,
+    gif_max_duration: Duration,
+) -> ScreenshotPost {
+    let site = provider.site();
+    let upload_name = &site.upload_name;
+    let title = provider.title(shot);
+    let description = if alt_text {
+        alt_text_from_caption(&shot.caption)
+    } else {
+        None
+    };
+
+    // A frame that can't be captioned is posted the usual way
+    if caption {
+        if let Some(png) = render_meme(provider, shot).await {
+            let filename = format!("{upload_name}.png");
+            return ScreenshotPost {
+                content: title,
+                attachment: Some(screenshot_attachment(
+                    png,
+                    &filename,
+                    description.as_deref(),
+                )),
+            };
+        }
+    }
+
+    shot.gif_url = generate_gif(
+        &site.base_url,
+        &shot.episode,
+        shot.start_timestamp,
+        shot.end_timestamp,
+        &merge_subtitle_fragments(&shot.subtitles),
+        0,
+        site.gif_font,
+        gif_max_duration,
+    )
+    .await;
+
+    if let Some(gif_url) = &shot.gif_url {
+        // Download the GIF and upload as attachment for reliable display
+        return match download_media(gif_url).await {
+            Some(bytes) => {
+                let filename = format!("{upload_name}.gif");
+                ScreenshotPost {
+                    content: title,
+                    attachment: Some(screenshot_attachment(
+                        bytes,
+                        &filename,
+                        description.as_deref(),
+                    )),
+                }
+            }
+            None => ScreenshotPost {
+                content: format!("{title}\n{gif_url}"),
+                attachment: None,
+            },
+        };
+    }
+
+    if description.is_some() {
+        // Upload the frame itself so screen readers get the caption as its description
+        if let Some(bytes) = download_media(&shot.image_url).await {
+            let filename = format!("{upload_name}.jpg");
+            return ScreenshotPost {
+                content: provider.format_still(shot),
+                attachment: Some(screenshot_attachment(
+                    bytes,
+                    &filename,
+                    description.as_deref(),
+                )),
+            };
+        }
+    }
+
+    ScreenshotPost {
+        content: provider.format_result(shot),
+        attachment: None,
+    }
+}
+
+/// Post the dialogue surrounding a screenshot's frame as a script block
+async fn send_context<P: ScreenshotProvider>(
+    http: &Http,
+    msg: &Message,
+    provider: &P,
+    shot: &Screenshot,
+) {
+    let subs = context_subtitles(provider, &shot.episode, shot.timestamp, CONTEXT_LINES).await;
+    if subs.is_empty() {
+        return;
+    }
+
+    let script = format_context_script(&subs, shot.timestamp);
+    if let Err(e) = msg.channel_id.say(http, &script).await {
+        error!("Error sending {} context: {:?}", provider.name(), e);
+    }
+}
+
+// Finish a found screenshot (whole sentences, rendered as a GIF or captioned with -caption)
+// and post it, returning the message it went out in
+async fn post_screenshot<P: ScreenshotProvider>(
+    http: &Http,
+    msg: &Message,
+    provider: &P,
+    mut shot: Screenshot,
+    options: &ScreenshotOptions,
+    alt_text: bool,
+    gif_max_duration: Duration,
+) -> Option<Message> {
+    expand_to_sentence_boundaries(provider, &mut shot).await;
+    let post = build_post(
+        provider,
+        &mut shot,
+        options.caption,
+        alt_text,
+        gif_max_duration,
+    )
+    .await;
+
+    let mut message = CreateMessage::new().content(post.content);
+    if let Some(attachment) = post.attachment {
+        message = message.add_file(attachment);
+    }
+    let sent = match msg.channel_id.send_message(http, message).await {
+        Ok(sent) => Some(sent),
+        Err(e) => {
+            error!("Error sending {} result: {:?}", provider.name(), e);
+            None
+        }
+    };
+
+    if options.context {
+        send_context(http, msg, provider, &shot).await;
+    }
+    sent
+}
+
+// Put ◀️/▶️ on a posted search result and remember its frames, if there are others to show
+async fn offer_pages(http: &Http, pages: &ScreenshotPages, sent: &Message, page: ScreenshotPage) {
+    if page.frames.len() < 2 {
+        return;
+    }
+
+    pages.record(sent.id, page);
+    for emoji in [PREVIOUS_PAGE_EMOJI, NEXT_PAGE_EMOJI] {
+        let reaction = ReactionType::Unicode(emoji.to_string());
+        if let Err(e) = sent.react(http, reaction).await {
+            error!("Error adding screenshot page reaction: {:?}", e);
+            return;
+        }
+    }
+}
+
+/// Show another frame of a paged search in the message that's showing it
+#[allow(clippy::too_many_arguments)]
+pub async fn show_screenshot_page<P: ScreenshotProvider>(
+    http: &Http,
+    channel_id: ChannelId,
+    message_id: MessageId,
+    provider: &P,
+    frame: &Frame,
+    caption: bool,
+    alt_text: bool,
+    gif_max_duration: Duration,
+) -> Result<()> {
+    let Some(mut shot) = provider.caption(frame).await? else {
+        info!(
+            "No caption for {} {} at {}; leaving the page as it is",
+            provider.name(),
+            frame.episode,
+            frame.timestamp
+        );
+        return Ok(());
+    };
+
+    expand_to_sentence_boundaries(provider, &mut shot).await;
+    let post = build_post(provider, &mut shot, caption, alt_text, gif_max_duration).await;
+
+    let edit = EditMessage::new().content(post.content);
+    let edit = match post.attachment {
+        Some(attachment) => edit.new_attachment(attachment),
+        None => edit.remove_all_attachments(),
+    };
+    channel_id.edit_message(http, message_id, edit).await?;
+    Ok(())
+}
+
+// This function will be called from main.rs to handle !frinkiac, !morbotron and
+// !masterofallscience. With `pages`, a search's other frames can be paged through with
+// ◀️/▶️ reactions.
+pub async fn handle_screenshot_command<P: ScreenshotProvider>(
+    http: &Http,
+    msg: &Message,
+    args: Option<String>,
+    provider: &P,
+    alt_text: bool,
+    gif_max_duration: Duration,
+    pages: Option<&ScreenshotPages>,
+) -> Result<()> {
+    let site = provider.site();
+    if let Some(notice) = site.shut_down_notice {
+        let _ = msg.channel_id.say(http, notice).await;
+        return Ok(());
+    }
+
+    // -caption and -context work on every site. Sites with options also support filtering
+    // by season/episode; the rest search the words as typed.
+    let (args, caption) = take_flag(args, "caption");
+    let (args, context) = take_flag(args, "context");
+    let mut options = match args {
+        Some(args) if site.options => parse_options(&args),
+        Some(args) => ScreenshotOptions {
+            term: Some(args),
+            ..Default::default()
+        },
+        None => ScreenshotOptions::default(),
+    };
+    options.caption = caption;
+    options.context = context;
+
+    // Show typing indicator while we search
+    let _ = msg.channel_id.broadcast_typing(http).await;
+
+    // If no search term is provided, get a random screenshot
+    if options.term.is_none() && options.season.is_none() && options.episode.is_none() {
+        info!("{} request for random screenshot", provider.name());
+
+        match random_screenshot(provider).await {
+            Ok(Some(shot)) => {
+                post_screenshot(
+                    http,
+                    msg,
+                    provider,
+                    shot,
+                    &options,
+                    alt_text,
+                    gif_max_duration,
+                )
+                .await;
+            }
+            Ok(None) => {
+                let _ = msg.channel_id.say(http, &*site.no_random_message).await;
+            }
+            Err(e) => {
+                error!(
+                    "Error getting random {} screenshot: {:?}",
+                    provider.name(),
+                    e
+                );
+                let _ = msg.channel_id.say(http, &*site.random_error_message).await;
+            }
+        }
+
+        return Ok(());
+    }
+
+    let Some(term) = options.term.take() else {
+        let error_msg = "Please provide a search term with season/episode filters.";
+        if let Err(e) = msg.channel_id.say(http, error_msg).await {
+            error!("Error sending error message: {:?}", e);
+        }
+        return Ok(());
+    };
+
+    match find_screenshot(provider, &term).await {
+        Ok(Some(shot)) => {
+            let ranking = provider.last_ranking();
+            let filtered_out = options.season.is_some_and(|s| shot.season != s)
+                || options.episode.is_some_and(|e| shot.episode_number != e);
+
+            if filtered_out {
+                let _ = msg
+                    .channel_id
+                    .say(
+                        http,
+                        format!(
+                            "Couldn't find any {} screenshots matching \"{term}\" in the specified season/episode.",
+                            site.show
+                        ),
+                    )
+                    .await;
+            } else {
+                let sent = post_screenshot(
+                    http,
+                    msg,
+                    provider,
+                    shot,
+                    &options,
+                    alt_text,
+                    gif_max_duration,
+                )
+                .await;
+
+                // Other frames could fall outside a season/episode filter, so only
+                // unfiltered searches are paged
+                let unfiltered = options.season.is_none() && options.episode.is_none();
+                if let (Some(pages), Some(sent), Some(ranking), true) =
+                    (pages, sent, ranking, unfiltered)
+                {
+                    let mut frames = ranking.candidates;
+                    frames.truncate(SCREENSHOT_PAGE_FRAMES.max(ranking.chosen + 1));
+                    let page = ScreenshotPage {
+                        user_id: msg.author.id,
+                        // The upload name is the command that searched
+                        command: site.upload_name.to_string(),
+                        frames,
+                        index: ranking.chosen,
+                        caption: options.caption,
+                    };
+                    offer_pages(http, pages, &sent, page).await;
+                }
+            }
+        }
+        Ok(None) => {
+            let _ = msg
+                .channel_id
+                .say(
+                    http,
+                    format!(
+                        "Couldn't find any {} screenshots matching \"{term}\".",
+                        site.show
+                    ),
+                )
+                .await;
+        }
+        Err(e) => {
+            error!("Error searching {}: {:?}", provider.name(), e);
+            let _ = msg.channel_id.say(http, &*site.search_error_message).await;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sub(text: &str, start: u64, end: u64) -> TimedSubtitle {
+        TimedSubtitle {
+            text: text.to_string(),
+            start,
+            end,
+            representative_ts: start,
+        }
+    }
+
+    #[test]
+    fn test_window_subtitles() {
+        let subs = vec![
+            sub("Four", 4000, 4900),
+            sub("One", 1000, 1900),
+            sub("Three", 3000, 3900),
+            sub("Two", 2000, 2900),
+            sub("Three", 3000, 3900), // duplicate from an overlapping lookup
+            sub("Five", 5000, 5900),
+        ];
+
+        // Middle of the episode: one line either side, sorted and deduplicated
+        let window = window_subtitles(&subs, 3500, 1, 1);
+        let texts: Vec<&str> = window.iter().map(|s| s.text.as_str()).collect();
+        assert_eq!(texts, vec!["Two", "Three", "Four"]);
+
+        // Near the start: fewer earlier lines are available
+        let window = window_subtitles(&subs, 1200, 3, 1);
+        let texts: Vec<&str> = window.iter().map(|s| s.text.as_str()).collect();
+        assert_eq!(texts, vec!["One", "Two"]);
+
+        // Near the end: fewer later lines are available
+        let window = window_subtitles(&subs, 5500, 1, 3);
+        let texts: Vec<&str> = window.iter().map(|s| s.text.as_str()).collect();
+        assert_eq!(texts, vec!["Four", "Five"]);
+
+        // Frame between lines centers on the next line to start
+        let window = window_subtitles(&subs, 2950, 0, 0);
+        assert_eq!(window[0].text, "Three");
+
+        assert!(window_subtitles(&[], 1000, 2, 2).is_empty());
+    }
+
+    const MOCK_SITE: ScreenshotSite = ScreenshotSite {
+        base_url: Cow::Borrowed("https://example.com"),
+        gif_font: "akbar",
+        upload_name: Cow::Borrowed("mock"),
+        show: Cow::Borrowed("Simpsons"),
+        no_random_message: Cow::Borrowed("Couldn't find any Simpsons screenshots."),
+        random_error_message: Cow::Borrowed("Error getting screenshot."),
+        search_error_message: Cow::Borrowed("Error searching."),
+        options: true,
+        shut_down_notice: None,
+    };
+
+    // A site whose searches and captions come from memory. A caption covers the subtitles
+    // within a second and a half either side of the frame, like the real caption API.
+    #[derive(Default)]
+    struct MockProvider {
+        results: HashMap<String, Vec<Frame>>,
+        subtitles: Vec<TimedSubtitle>,
+        searches: std::sync::Mutex<Vec<String>>,
+        search_state: SearchState,
+    }
+
+    impl ScreenshotProvider for MockProvider {
+        fn name(&self) -> &str {
+            "Mock"
+        }
+
+        fn site(&self) -> &ScreenshotSite {
+            &MOCK_SITE
+        }
+
+        async fn search(&self, term: &str) -> Result<Vec<Frame>> {
+            self.searches.lock().unwrap().push(term.to_string());
+            Ok(self.results.get(term).cloned().unwrap_or_default())
+        }
+
+        fn caption_url(&self, frame: &Frame) -> String {
+            format!(
+                "https://example.com/api/caption?e={}&t={}",
+                frame.episode, frame.timestamp
+            )
+        }
+
+        async fn caption(&self, frame: &Frame) -> Result<Option<Screenshot>> {
+            let subtitles: Vec<serde_json::Value> = self
+                .subtitles
+                .iter()
+                .filter(|s| s.start <= frame.timestamp + 1500 && s.end + 1500 >= frame.timestamp)
+                .map(|s| {
+                    serde_json::json!({
+                        "Content": s.text,
+                        "StartTimestamp": s.start,
+                        "EndTimestamp": s.end,
+                        "RepresentativeTimestamp": s.representative_ts
+                    })
+                })
+                .collect();
+            let json = serde_json::json!({
+                "Episode": {"Title": "22 Short Films About Springfield", "Season": 7, "EpisodeNumber": 21},
+                "Subtitles": subtitles
+            });
+
+            let image_url = format!(
+                "https://example.com/img/{}/{}.jpg",
+                frame.episode, frame.timestamp
+            );
+            let mut shot = parse_caption(&json, &frame.episode, frame.timestamp, image_url)?;
+            shot.caption = shot.subtitle_text(" ");
+            Ok(Some(shot))
+        }
+
+        fn random_terms(&self) -> &[&str] {
+            &["excellent"]
+        }
+
+        fn search_state(&self) -> &SearchState {
+            &self.search_state
+        }
+    }
+
+    fn frame(episode: &str, timestamp: u64) -> Frame {
+        Frame {
+            api_rank: 1,
+            ..Frame::at(episode, timestamp)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_find_screenshot_rotates_and_falls_back() {
+        let provider = MockProvider {
+            results: HashMap::from([
+                (
+                    "steamed hams".to_string(),
+                    vec![frame("S07E21", 125500), frame("S07E21", 131000)],
+                ),
+                (
+                    "\"dental plan lisa\"".to_string(),
+                    vec![frame("S04E17", 64000)],
+                ),
+                ("excellent".to_string(), vec![frame("S01E01", 5000)]),
+            ]),
+            ..Default::default()
+        };
+
+        // Repeating a search moves on through its frames and wraps around
+        let timestamps = [
+            find_screenshot(&provider, "steamed hams").await.unwrap(),
+            find_screenshot(&provider, "steamed hams").await.unwrap(),
+            find_screenshot(&provider, "steamed hams").await.unwrap(),
+        ]
+        .map(|shot| shot.unwrap().timestamp);
+        assert_eq!(timestamps, [125500, 131000, 125500]);
+        let ranking = provider.last_ranking().unwrap();
+        assert_eq!(
+            (ranking.query.as_str(), ranking.chosen),
+            ("steamed hams", 0)
+        );
+
+        // Words that find nothing are tried as a quoted phrase
+        let shot = find_screenshot(&provider, "dental plan lisa")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!((shot.episode.as_str(), shot.season), ("S04E17", 7));
+        assert_eq!(
+            provider.last_ranking().unwrap().query,
+            "\"dental plan lisa\""
+        );
+
+        // And failing that the search shows a random frame, with no ranking to report
+        provider.searches.lock().unwrap().clear();
+        let shot = find_screenshot(&provider, "nothing here")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(shot.episode, "S01E01");
+        assert_eq!(provider.last_ranking(), None);
+        assert_eq!(
+            *provider.searches.lock().unwrap(),
+            vec!["nothing here", "\"nothing here\"", "excellent"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_expand_to_sentence_boundaries() {
+        let provider = MockProvider {
+            subtitles: vec![
+                sub("Well, Seymour, I made it", 6000, 7900),
+                sub("despite your directions.", 9000, 10500),
+                sub("Superintendent Chalmers,", 10600, 11500),
+                sub("welcome!", 11600, 12500),
+                sub(
+                    "I hope you're prepared for an unforgettable luncheon.",
+                    13000,
+                    15000,
+                ),
+            ],
+            ..Default::default()
+        };
+
+        // The frame's caption starts and ends mid-sentence, so both ends are filled in
+        let mut shot = provider
+            .caption(&Frame::at("S07E21", 10000))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            shot.caption,
+            "despite your directions. Superintendent Chalmers,"
+        );
+        expand_to_sentence_boundaries(&provider, &mut shot).await;
+        assert_eq!(
+            shot.subtitle_text(" "),
+            "Well, Seymour, I made it despite your directions. Superintendent Chalmers, welcome!"
+        );
+        assert_eq!((shot.start_timestamp, shot.end_timestamp), (6000, 12500));
+    }
+
+    #[test]
+    fn test_parse_options() {
+        assert_eq!(
+            parse_options("steamed hams -s 7 --episode 21"),
+            ScreenshotOptions {
+                term: Some("steamed hams".to_string()),
+                season: Some(7),
+                episode: Some(21),
+                ..Default::default()
+            }
+        );
+        // A filter without a number is dropped along with its value
+        assert_eq!(parse_options("-s seven -e"), ScreenshotOptions::default());
+
+        // -caption and -context are taken out before the rest is parsed, on every site
+        assert_eq!(
+            take_flag(Some("steamed hams --caption -s 7".to_string()), "caption"),
+            (Some("steamed hams -s 7".to_string()), true)
+        );
+        assert_eq!(
+            take_flag(Some("good news everyone -context".to_string()), "context"),
+            (Some("good news everyone".to_string()), true)
+        );
+        assert_eq!(
+            take_flag(Some("-captions".to_string()), "caption"),
+            (Some("-captions".to_string()), false)
+        );
+    }
+
+    #[test]
+    fn test_default_result_format() {
+        let provider = MockProvider::default();
+        let mut shot = parse_caption(
+            &serde_json::json!({
+                "Episode": {"Title": "Space Pilot 3000", "Season": 1, "EpisodeNumber": 1},
+                "Subtitles": []
+            }),
+            "S01E01",
+            5000,
+            "https://example.com/img/S01E01/5000.jpg".to_string(),
+        )
+        .unwrap();
+        shot.caption = "\"Welcome to the world of tomorrow!\"".to_string();
+
+        assert_eq!(provider.title(&shot), "S01E01 - Space Pilot 3000");
+        assert_eq!(
+            provider.format_result(&shot),
+            "**S01E01 - Space Pilot 3000**\nhttps://example.com/img/S01E01/5000.jpg\n\n\"Welcome to the world of tomorrow!\""
+        );
+        assert_eq!(
+            provider.format_still(&shot),
+            "**S01E01 - Space Pilot 3000**\n\n\"Welcome to the world of tomorrow!\""
+        );
+        // Without subtitles the clip runs four seconds from the frame
+        assert_eq!((shot.start_timestamp, shot.end_timestamp), (5000, 9000));
+
+        shot.gif_url = Some("https://example.com/gif/1.gif".to_string());
+        assert_eq!(
+            provider.format_result(&shot),
+            "**S01E01 - Space Pilot 3000**\nhttps://example.com/gif/1.gif"
+        );
+    }
+
+    #[test]
+    fn test_attachment_description_from_caption() {
+        let alt_text = alt_text_from_caption("Me fail English?\nThat's unpossible!");
+        let attachment = screenshot_attachment(vec![1, 2, 3], "frinkiac.jpg", alt_text.as_deref());
+        assert_eq!(
+            attachment.description.as_deref(),
+            Some("Me fail English? That's unpossible!")
+        );
+        assert_eq!(attachment.filename, "frinkiac.jpg");
+
+        // No caption, no description
+        let alt_text = alt_text_from_caption("  \n ");
+        assert_eq!(alt_text, None);
+        let attachment = screenshot_attachment(vec![1, 2, 3], "frinkiac.jpg", alt_text.as_deref());
+        assert_eq!(attachment.description, None);
+
+        // Long captions are cut to Discord's limit
+        let long = alt_text_from_caption(&"d'oh ".repeat(500)).unwrap();
+        assert_eq!(long.chars().count(), MAX_ALT_TEXT_CHARS);
+    }
+
+    #[test]
+    fn test_gif_url_and_range() {
+        assert_eq!(
+            gif_render_url("https://frinkiac.com/"),
+            "https://frinkiac.com/api/render/gif/stream"
+        );
+        assert_eq!(
+            gif_render_url("https://morbotron.com"),
+            "https://morbotron.com/api/render/gif/stream"
+        );
+
+        // Short clips are left alone, long ones cut to the maximum
+        let max = Duration::from_secs(10);
+        assert_eq!(gif_range(60_000, 64_500, max), (60_000, 64_500));
+        assert_eq!(gif_range(60_000, 95_000, max), (60_000, 70_000));
+        assert_eq!(gif_range(60_000, 50_000, max), (60_000, 60_000));
+    }
+
+    #[test]
+    fn test_last_search_storage() {
+        let searches = LastSearches::new(Duration::from_secs(60));
+        let (alice, bob) = (UserId::new(1), UserId::new(2));
+        let (general, simpsons) = (ChannelId::new(10), ChannelId::new(20));
+        let start = Instant::now();
+        let steamed_hams = LastSearch {
+            service: ScreenshotService::Frinkiac,
+            args: Some("steamed hams".to_string()),
+        };
+
+        assert_eq!(searches.last_at(alice, general, start), None);
+
+        searches.record_at(alice, general, steamed_hams.clone(), start);
+        // Asking again repeats the same search, so the client rotates to its next frame
+        assert_eq!(
+            searches.last_at(alice, general, start),
+            Some(steamed_hams.clone())
+        );
+        assert_eq!(searches.last_at(alice, general, start), Some(steamed_hams));
+
+        // Searches are per user and per channel
+        assert_eq!(searches.last_at(bob, general, start), None);
+        assert_eq!(searches.last_at(alice, simpsons, start), None);
+
+        // A newer search replaces the old one
+        let bender = LastSearch {
+            service: ScreenshotService::Morbotron,
+            args: Some("bite my shiny".to_string()),
+        };
+        searches.record_at(
+            alice,
+            general,
+            bender.clone(),
+            start + Duration::from_secs(30),
+        );
+        assert_eq!(
+            searches.last_at(alice, general, start + Duration::from_secs(30)),
+            Some(bender)
+        );
+        assert_eq!(ScreenshotService::Morbotron.command(), "morbotron");
+
+        // And searches are forgotten after the TTL
+        assert_eq!(
+            searches.last_at(alice, general, start + Duration::from_secs(90)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_screenshot_pages() {
+        let pages = ScreenshotPages::new(Duration::from_secs(60));
+        let (alice, bob) = (UserId::new(1), UserId::new(2));
+        let message = MessageId::new(100);
+        let start = Instant::now();
+        let page = ScreenshotPage {
+            user_id: alice,
+            command: "frinkiac".to_string(),
+            frames: vec![
+                Frame::at("S07E21", 1000),
+                Frame::at("S07E21", 2000),
+                Frame::at("S04E12", 3000),
+            ],
+            index: 0,
+            caption: false,
+        };
+        pages.record_at(message, page, start);
+
+        // Forward and back, wrapping around at both ends
+        let index = |forward| {
+            pages
+                .turn_at(message, alice, forward, start)
+                .map(|page| page.index)
+        };
+        assert_eq!(index(true), Some(1));
+        assert_eq!(index(true), Some(2));
+        assert_eq!(index(true), Some(0));
+        assert_eq!(index(false), Some(2));
+
+        // Only the person who searched turns the page, and only on a paged message
+        assert_eq!(pages.turn_at(message, bob, true, start), None);
+        assert_eq!(pages.turn_at(MessageId::new(101), alice, true, start), None);
+        assert_eq!(index(false), Some(1));
+
+        // Pages stop turning once they expire
+        let later = start + Duration::from_secs(90);
+        assert_eq!(pages.turn_at(message, alice, true, later), None);
+    }
+
+    #[test]
+    fn test_meme_caption_lines() {
+        assert_eq!(
+            meme_caption_lines("  Ah, Superintendent   Chalmers! \n\nWelcome.  "),
+            vec!["Ah, Superintendent Chalmers!", "Welcome."]
+        );
+
+        // Long captions are cut at a word and marked
+        let long = "steamed hams ".repeat(30);
+        let lines = meme_caption_lines(&long);
+        assert_eq!(lines.len(), 1);
+        assert!(lines[0].ends_with("hams…"));
+        assert!(lines[0].chars().count() <= MEME_CAPTION_MAX_CHARS);
+    }
+
+    #[test]
+    fn test_render_meme_caption() {
+        let (width, height) = (480, 360);
+        let mut frame = RgbFrame {
+            width,
+            height,
+            pixels: vec![128; (width * height * 3) as usize],
+        };
+        let caption = "Well, Seymour, you are an odd fellow, but I must say you steam a \
+                       good ham.\nThank you.";
+        draw_meme_caption(&mut frame, caption).unwrap();
+
+        // The caption is on the bottom of the frame, in white, and the top is left alone
+        let row = |y: u32| {
+            let start = (y * width * 3) as usize;
+            &frame.pixels[start..start + (width * 3) as usize]
+        };
+        assert!(row(0).iter().all(|&v| v == 128));
+        assert!((height / 2..height).any(|y| row(y).chunks(3).any(|p| p == [255, 255, 255])));
+
+        // The PNG is the frame's size
+        let png_bytes = encode_png(&frame).unwrap();
+        assert!(!png_bytes.is_empty());
+        let reader = png::Decoder::new(std::io::Cursor::new(png_bytes))
+            .read_info()
+            .unwrap();
+        assert_eq!((reader.info().width, reader.info().height), (width, height));
+        assert_eq!(reader.info().color_type, png::ColorType::Rgb);
+
+        // A frame that isn't a JPEG is an error rather than a blank image
+        assert!(render_meme_caption(b"not a jpeg", caption).is_err());
+    }
+}
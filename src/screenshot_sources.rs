@@ -0,0 +1,335 @@
+use crate::channel_gating::SCREENSHOT_COMMANDS;
+use crate::http_client;
+use crate::screenshot::{self, Frame, Screenshot, ScreenshotProvider, ScreenshotSite, SearchState};
+use crate::text_formatting;
+use anyhow::{anyhow, Result};
+use reqwest::Client as HttpClient;
+use serde::Deserialize;
+use std::borrow::Cow;
+use std::collections::HashSet;
+use tracing::{info, warn};
+
+/// One `[[screenshot_sources]]` entry in CrowConfig.toml, as written
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct ScreenshotSourceConfig {
+    pub name: Option<String>,
+    pub base_url: Option<String>,
+    pub command: Option<String>,
+}
+
+/// A checked screenshot source: a Frinkiac-family site served by its own command
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScreenshotSource {
+    /// The show, as the bot talks about it ("West Wing")
+    pub name: String,
+    /// Site root without a trailing slash ("https://capitalbeat.us")
+    pub base_url: String,
+    /// The command, lowercase and without the `!`
+    pub command: String,
+}
+
+// Check one configured source, saying what's wrong with it if it can't be used
+fn validate_source(entry: &ScreenshotSourceConfig) -> Result<ScreenshotSource, String> {
+    let name = entry
+        .name
+        .as_deref()
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .ok_or("missing name")?;
+
+    let command = entry
+        .command
+        .as_deref()
+        .map(|command| command.trim().trim_start_matches('!').to_lowercase())
+        .filter(|command| !command.is_empty())
+        .ok_or("missing command")?;
+    if !command
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(format!(
+            "command \"{command}\" must be letters, digits, - or _"
+        ));
+    }
+
+    let base_url = entry
+        .base_url
+        .as_deref()
+        .map(str::trim)
+        .ok_or("missing base_url")?;
+    let url = reqwest::Url::parse(base_url)
+        .map_err(|e| format!("base_url \"{base_url}\" is not a valid URL: {e}"))?;
+    if !matches!(url.scheme(), "http" | "https") || url.host_str().is_none() {
+        return Err(format!("base_url \"{base_url}\" must be an http(s) site"));
+    }
+    if url.query().is_some() {
+        return Err(format!("base_url \"{base_url}\" can't have a query string"));
+    }
+
+    Ok(ScreenshotSource {
+        name: name.to_string(),
+        base_url: base_url.trim_end_matches('/').to_string(),
+        command,
+    })
+}
+
+/// Check the configured screenshot sources, logging and skipping any that are invalid or
+/// whose command is already taken
+pub fn parse_screenshot_sources(entries: &[ScreenshotSourceConfig]) -> Vec<ScreenshotSource> {
+    let mut commands: HashSet<String> = SCREENSHOT_COMMANDS
+        .iter()
+        .map(|command| command.to_string())
+        .collect();
+    let mut sources = Vec::new();
+
+    for (i, entry) in entries.iter().enumerate() {
+        match validate_source(entry) {
+            Ok(source) if !commands.insert(source.command.clone()) => {
+                warn!(
+                    "Skipping screenshot source {} ({}): !{} is already a screenshot command",
+                    i + 1,
+                    source.name,
+                    source.command
+                );
+            }
+            Ok(source) => {
+                info!(
+                    "Screenshot source {} on !{} from {}",
+                    source.name, source.command, source.base_url
+                );
+                sources.push(source);
+            }
+            Err(problem) => {
+                warn!("Skipping screenshot source {}: {}", i + 1, problem);
+            }
+        }
+    }
+
+    sources
+}
+
+/// Any Frinkiac-family site, set up from a `[[screenshot_sources]]` entry. Results come
+/// in the site's own order and random frames from its random API.
+pub struct GenericFrinkiacLikeClient {
+    name: String,
+    command: String,
+    site: ScreenshotSite,
+    http_client: HttpClient,
+    search_state: SearchState,
+}
+
+impl GenericFrinkiacLikeClient {
+    pub fn new(source: &ScreenshotSource) -> Self {
+        info!("Creating {} screenshot client", source.name);
+
+        let name = &source.name;
+        Self {
+            name: name.clone(),
+            command: source.command.clone(),
+            site: ScreenshotSite {
+                base_url: Cow::Owned(source.base_url.clone()),
+                gif_font: "akbar",
+                upload_name: Cow::Owned(source.command.clone()),
+                show: Cow::Owned(name.clone()),
+                no_random_message: Cow::Owned(format!("Couldn't find any {name} screenshots.")),
+                random_error_message: Cow::Owned(format!("Error getting {name} screenshot.")),
+                search_error_message: Cow::Owned(format!("Error searching {name}.")),
+                options: true,
+                shut_down_notice: None,
+            },
+            http_client: http_client::shared(),
+            search_state: SearchState::default(),
+        }
+    }
+
+    /// The command this source answers to, without the `!`
+    pub fn command(&self) -> &str {
+        &self.command
+    }
+}
+
+impl ScreenshotProvider for GenericFrinkiacLikeClient {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn site(&self) -> &ScreenshotSite {
+        &self.site
+    }
+
+    async fn search(&self, query: &str) -> Result<Vec<Frame>> {
+        let encoded_query = urlencoding::encode(query);
+        let search_url = format!("{}/api/search?q={encoded_query}", self.site.base_url);
+
+        let search_response = self
+            .http_client
+            .get(&search_url)
+            .send()
+            .await
+            .map_err(|e| anyhow!("Failed to search {}: {}", self.name, e))?;
+
+        if !search_response.status().is_success() {
+            return Err(anyhow!(
+                "{} search failed with status: {}",
+                self.name,
+                search_response.status()
+            ));
+        }
+
+        let search_results: Vec<serde_json::Value> = search_response
+            .json()
+            .await
+            .map_err(|e| anyhow!("Failed to parse {} search results: {}", self.name, e))?;
+
+        // Results missing an episode or timestamp are skipped
+        Ok(search_results
+            .iter()
+            .enumerate()
+            .filter_map(|(i, result)| {
+                Some(Frame {
+                    episode: result.get("Episode")?.as_str()?.to_string(),
+                    timestamp: result.get("Timestamp")?.as_u64()?,
+                    api_rank: i + 1,
+                    note: None,
+                })
+            })
+            .collect())
+    }
+
+    fn caption_url(&self, frame: &Frame) -> String {
+        format!(
+            "{}/api/caption?e={}&t={}",
+            self.site.base_url, frame.episode, frame.timestamp
+        )
+    }
+
+    async fn caption(&self, frame: &Frame) -> Result<Option<Screenshot>> {
+        let caption_url = self.caption_url(frame);
+        info!("Fetching caption from URL: {}", caption_url);
+
+        let caption_response = self
+            .http_client
+            .get(&caption_url)
+            .send()
+            .await
+            .map_err(|e| anyhow!("Failed to get {} caption: {}", self.name, e))?;
+
+        let status = caption_response.status();
+        if !status.is_success() {
+            // The search falls back to a random frame rather than failing
+            info!("Caption request failed with status: {}", status);
+            return Ok(None);
+        }
+
+        let caption_result: serde_json::Value = caption_response
+            .json()
+            .await
+            .map_err(|e| anyhow!("Failed to parse {} caption: {}", self.name, e))?;
+
+        let image_url = format!(
+            "{}/img/{}/{}.jpg",
+            self.site.base_url, frame.episode, frame.timestamp
+        );
+        let mut result =
+            screenshot::parse_caption(&caption_result, &frame.episode, frame.timestamp, image_url)?;
+        result.caption = text_formatting::format_caption(&result.subtitle_text("\n"), &[]);
+        Ok(Some(result))
+    }
+
+    // There are no show-specific searches to fall back on
+    fn random_terms(&self) -> &[&str] {
+        &[]
+    }
+
+    async fn random_frame(&self) -> Result<Option<Frame>> {
+        let random_url = format!("{}/api/random", self.site.base_url);
+        screenshot::random_api_frame(&self.http_client, &random_url)
+            .await
+            .map(Some)
+    }
+
+    fn search_state(&self) -> &SearchState {
+        &self.search_state
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    #[test]
+    fn test_sources_from_config() {
+        let config: Config = toml::from_str(
+            r#"
+discord_token = "token"
+
+[[screenshot_sources]]
+name = "West Wing"
+base_url = "https://capitalbeat.us/"
+command = "!WestWing"
+
+[[screenshot_sources]]
+name = "Missing URL"
+command = "missing"
+
+[[screenshot_sources]]
+name = "Not a site"
+base_url = "ftp://example.com"
+command = "ftp"
+
+[[screenshot_sources]]
+name = "Simpsons again"
+base_url = "https://frinkiac.com"
+command = "frinkiac"
+
+[[screenshot_sources]]
+name = "Bad command"
+base_url = "https://example.com"
+command = "two words"
+"#,
+        )
+        .unwrap();
+
+        // Only the valid entry survives, tidied up
+        let sources = parse_screenshot_sources(&config.screenshot_sources.unwrap());
+        assert_eq!(
+            sources,
+            vec![ScreenshotSource {
+                name: "West Wing".to_string(),
+                base_url: "https://capitalbeat.us".to_string(),
+                command: "westwing".to_string(),
+            }]
+        );
+
+        let provider = GenericFrinkiacLikeClient::new(&sources[0]);
+        assert_eq!(provider.name(), "West Wing");
+        assert_eq!(provider.command(), "westwing");
+        assert_eq!(
+            provider.caption_url(&Frame::at("S01E01", 42000)),
+            "https://capitalbeat.us/api/caption?e=S01E01&t=42000"
+        );
+        assert_eq!(provider.site().base_url, "https://capitalbeat.us");
+        assert_eq!(
+            provider.site().no_random_message,
+            "Couldn't find any West Wing screenshots."
+        );
+
+        // A second source can't take a command that's already in use
+        let duplicate = ScreenshotSourceConfig {
+            name: Some("Also West Wing".to_string()),
+            base_url: Some("https://example.com".to_string()),
+            command: Some("westwing".to_string()),
+        };
+        let entries = [config_entry(&sources[0]), duplicate];
+        assert_eq!(parse_screenshot_sources(&entries).len(), 1);
+    }
+
+    fn config_entry(source: &ScreenshotSource) -> ScreenshotSourceConfig {
+        ScreenshotSourceConfig {
+            name: Some(source.name.clone()),
+            base_url: Some(source.base_url.clone()),
+            command: Some(source.command.clone()),
+        }
+    }
+}
@@ -0,0 +1,128 @@
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+// How often the shutdown wait checks whether in-flight work has finished
+const IDLE_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Tracks whether the bot is shutting down and how much work is still in flight, so a
+/// shutdown can stop taking new messages and give the current ones a moment to finish
+#[derive(Debug, Default)]
+pub struct ShutdownState {
+    shutting_down: AtomicBool,
+    in_flight: AtomicUsize,
+}
+
+/// Held while a piece of work runs; dropping it marks the work as done
+pub struct WorkGuard {
+    state: Arc<ShutdownState>,
+}
+
+impl Drop for WorkGuard {
+    fn drop(&mut self) {
+        self.state.in_flight.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl ShutdownState {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self::default())
+    }
+
+    pub fn is_shutting_down(&self) -> bool {
+        self.shutting_down.load(Ordering::SeqCst)
+    }
+
+    /// Start a piece of work, or None once shutdown has begun
+    pub fn begin_work(self: &Arc<Self>) -> Option<WorkGuard> {
+        self.in_flight.fetch_add(1, Ordering::SeqCst);
+        // Checked after counting so a shutdown can't miss work that slipped in
+        if self.is_shutting_down() {
+            self.in_flight.fetch_sub(1, Ordering::SeqCst);
+            return None;
+        }
+        Some(WorkGuard {
+            state: Arc::clone(self),
+        })
+    }
+
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.load(Ordering::SeqCst)
+    }
+
+    /// Stop accepting new work, then wait up to `timeout` for what's running to finish.
+    /// Returns whether everything finished in time.
+    pub async fn begin_shutdown(&self, timeout: Duration) -> bool {
+        self.shutting_down.store(true, Ordering::SeqCst);
+        let deadline = Instant::now() + timeout;
+        while self.in_flight() > 0 {
+            if Instant::now() >= deadline {
+                warn!(
+                    "Shutting down with {} tasks still running",
+                    self.in_flight()
+                );
+                return false;
+            }
+            tokio::time::sleep(IDLE_POLL_INTERVAL).await;
+        }
+        info!("All in-flight work finished");
+        true
+    }
+}
+
+/// Resolves on Ctrl+C, or on SIGTERM on Unix (what container runtimes send on stop)
+pub async fn wait_for_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            warn!("Could not listen for Ctrl+C: {:?}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                warn!("Could not listen for SIGTERM: {:?}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => info!("Received Ctrl+C"),
+        _ = terminate => info!("Received SIGTERM"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_shutdown_waits_for_in_flight_work() {
+        let state = ShutdownState::new();
+        let guard = state.begin_work().unwrap();
+        assert_eq!(state.in_flight(), 1);
+
+        // Work still running when the timeout passes is reported
+        assert!(!state.begin_shutdown(Duration::from_millis(10)).await);
+        // No new work once shutdown has begun
+        assert!(state.begin_work().is_none());
+        assert_eq!(state.in_flight(), 1);
+
+        let finish = tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            drop(guard);
+        });
+        assert!(state.begin_shutdown(Duration::from_secs(5)).await);
+        assert_eq!(state.in_flight(), 0);
+        finish.await.unwrap();
+    }
+}
@@ -0,0 +1,369 @@
+use serenity::all::{
+    CommandDataOption, CommandDataOptionValue, CommandOptionType, CreateCommand,
+    CreateCommandOption,
+};
+
+// What kind of value a slash command option takes
+#[derive(Clone, Copy)]
+enum OptionKind {
+    Text,
+    Integer,
+    /// A true/false switch written as a bare flag, e.g. -context
+    Switch,
+}
+
+// A slash command option and how it's written in the `!` command it stands for
+struct SlashOption {
+    name: &'static str,
+    description: &'static str,
+    kind: OptionKind,
+    required: bool,
+    /// The flag the value follows in the `!` command, or "" for a bare argument
+    flag: &'static str,
+}
+
+const fn text(name: &'static str, description: &'static str, flag: &'static str) -> SlashOption {
+    SlashOption {
+        name,
+        description,
+        kind: OptionKind::Text,
+        required: false,
+        flag,
+    }
+}
+
+const fn required_text(name: &'static str, description: &'static str) -> SlashOption {
+    SlashOption {
+        name,
+        description,
+        kind: OptionKind::Text,
+        required: true,
+        flag: "",
+    }
+}
+
+const fn integer(name: &'static str, description: &'static str, flag: &'static str) -> SlashOption {
+    SlashOption {
+        name,
+        description,
+        kind: OptionKind::Integer,
+        required: false,
+        flag,
+    }
+}
+
+const fn switch(name: &'static str, description: &'static str, flag: &'static str) -> SlashOption {
+    SlashOption {
+        name,
+        description,
+        kind: OptionKind::Switch,
+        required: false,
+        flag,
+    }
+}
+
+// A `!` command offered as a slash command. Options are written out in this order, so
+// bare arguments come first.
+struct SlashCommand {
+    name: &'static str,
+    description: &'static str,
+    options: &'static [SlashOption],
+}
+
+const SLASH_COMMANDS: &[SlashCommand] = &[
+    SlashCommand {
+        name: "help",
+        description: "Show help",
+        options: &[],
+    },
+    SlashCommand {
+        name: "quote",
+        description: "Get a random quote",
+        options: &[
+            text("term", "Only quotes containing this", ""),
+            text("show", "Only quotes from this show", "-show"),
+            text("user", "Quote something this user said instead", "-dud"),
+            switch("all", "With user: include short messages", "-all"),
+        ],
+    },
+    SlashCommand {
+        name: "frinkiac",
+        description: "Get a Simpsons screenshot",
+        options: &[
+            text("term", "Quote to search for", ""),
+            integer("season", "Only this season", "-s"),
+            integer("episode", "Only this episode", "-e"),
+            switch("context", "Include the surrounding lines", "-context"),
+            switch(
+                "caption",
+                "Write the quote on the frame instead of a GIF",
+                "-caption",
+            ),
+        ],
+    },
+    SlashCommand {
+        name: "morbotron",
+        description: "Get a Futurama screenshot",
+        options: &[
+            text("term", "Quote to search for", ""),
+            switch("context", "Include the surrounding lines", "-context"),
+            switch(
+                "caption",
+                "Write the quote on the frame instead of a GIF",
+                "-caption",
+            ),
+        ],
+    },
+    SlashCommand {
+        name: "masterofallscience",
+        description: "Get a Rick and Morty screenshot",
+        options: &[text("term", "Quote to search for", "")],
+    },
+    SlashCommand {
+        name: "again",
+        description: "Show a different frame for your last screenshot search",
+        options: &[],
+    },
+    SlashCommand {
+        name: "subtitles",
+        description: "Show a Simpsons episode's subtitles",
+        options: &[
+            required_text("episode", "Episode, e.g. S07E21 or 7x21"),
+            integer("page", "Page to start from", ""),
+        ],
+    },
+    SlashCommand {
+        name: "slogan",
+        description: "Get a random advertising slogan",
+        options: &[text("term", "Only slogans containing this", "")],
+    },
+    SlashCommand {
+        name: "search",
+        description: "Search message history",
+        options: &[
+            required_text("term", "What to search for"),
+            integer("page", "Page of results", "-p"),
+        ],
+    },
+    SlashCommand {
+        name: "lastseen",
+        description: "Find when a user was last active",
+        options: &[required_text("name", "Who to look for")],
+    },
+    SlashCommand {
+        name: "calc",
+        description: "Calculate something",
+        options: &[required_text("expression", "e.g. 3 * (4 + 5) / 2")],
+    },
+    SlashCommand {
+        name: "roll",
+        description: "Roll dice",
+        options: &[required_text("dice", "e.g. 2d6+3, d20 or 4d6kh3")],
+    },
+    SlashCommand {
+        name: "choose",
+        description: "Pick one of some options",
+        options: &[required_text("options", "e.g. pizza, tacos, sushi")],
+    },
+    SlashCommand {
+        name: "8ball",
+        description: "Ask the Magic 8-Ball",
+        options: &[required_text("question", "A yes or no question")],
+    },
+    SlashCommand {
+        name: "alive",
+        description: "Check if a celebrity is alive or dead",
+        options: &[required_text("name", "Who to check")],
+    },
+    SlashCommand {
+        name: "wiki",
+        description: "Summarize a Wikipedia article",
+        options: &[required_text("topic", "What to look up")],
+    },
+    SlashCommand {
+        name: "urban",
+        description: "Look up slang on Urban Dictionary",
+        options: &[required_text("term", "What to look up")],
+    },
+    SlashCommand {
+        name: "translate",
+        description: "Translate text (into English by default)",
+        options: &[
+            required_text("text", "What to translate"),
+            text(
+                "language",
+                "Language name or code, e.g. spanish or ja",
+                "-to",
+            ),
+        ],
+    },
+    SlashCommand {
+        name: "bandname",
+        description: "Generate music genre for a band",
+        options: &[text("name", "The band's name", "")],
+    },
+    SlashCommand {
+        name: "buzz",
+        description: "Generate corporate buzzwords",
+        options: &[],
+    },
+    SlashCommand {
+        name: "fightcrime",
+        description: "Generate a crime fighting duo",
+        options: &[],
+    },
+    SlashCommand {
+        name: "trump",
+        description: "Generate a Trump insult",
+        options: &[],
+    },
+    SlashCommand {
+        name: "vibecheck",
+        description: "Read the room",
+        options: &[],
+    },
+    SlashCommand {
+        name: "summarize",
+        description: "Summarize the channel's recent messages",
+        options: &[
+            integer(
+                "count",
+                "How many messages to read (default 50, up to 200)",
+                "",
+            ),
+            switch("nobot", "Leave my own messages out", "-nobot"),
+        ],
+    },
+    SlashCommand {
+        name: "info",
+        description: "Show bot statistics",
+        options: &[switch(
+            "fresh",
+            "Recount messages instead of using the cache",
+            "fresh",
+        )],
+    },
+    SlashCommand {
+        name: "remindme",
+        description: "Get a reminder later",
+        options: &[
+            required_text("when", "e.g. 30m, 2h, 1d, 14:30 or 2025-12-24"),
+            required_text("what", "What to remind you about"),
+        ],
+    },
+    SlashCommand {
+        name: "forgetme",
+        description: "Delete your stored messages and stop storing new ones",
+        options: &[],
+    },
+    SlashCommand {
+        name: "uptime",
+        description: "Show how long the bot has been running",
+        options: &[],
+    },
+    SlashCommand {
+        name: "stats",
+        description: "Show how often each interjection type has fired since startup",
+        options: &[],
+    },
+    SlashCommand {
+        name: "imagine",
+        description: "Generate an image",
+        options: &[required_text("prompt", "What to draw")],
+    },
+];
+
+/// The slash commands to register with Discord. /imagine is left out when no channel
+/// allows image generation.
+pub fn slash_commands(include_imagine: bool) -> Vec<CreateCommand> {
+    SLASH_COMMANDS
+        .iter()
+        .filter(|command| include_imagine || command.name != "imagine")
+        .map(|command| {
+            command.options.iter().fold(
+                CreateCommand::new(command.name).description(command.description),
+                |create, option| {
+                    let kind = match option.kind {
+                        OptionKind::Text => CommandOptionType::String,
+                        OptionKind::Integer => CommandOptionType::Integer,
+                        OptionKind::Switch => CommandOptionType::Boolean,
+                    };
+                    let mut create_option =
+                        CreateCommandOption::new(kind, option.name, option.description)
+                            .required(option.required);
+                    if let OptionKind::Integer = option.kind {
+                        create_option = create_option.min_int_value(1);
+                    }
+                    create.add_option(create_option)
+                },
+            )
+        })
+        .collect()
+}
+
+/// The `!` command a slash command stands for, e.g. `/frinkiac term:steamed hams season:7`
+/// becomes `!frinkiac steamed hams -s 7`. None for commands we didn't register.
+pub fn text_command(name: &str, options: &[CommandDataOption]) -> Option<String> {
+    let command = SLASH_COMMANDS.iter().find(|command| command.name == name)?;
+
+    let mut words = vec![format!("!{}", command.name)];
+    for spec in command.options {
+        let Some(option) = options.iter().find(|option| option.name == spec.name) else {
+            continue;
+        };
+        let value = match &option.value {
+            CommandDataOptionValue::String(value) => value.trim().to_string(),
+            CommandDataOptionValue::Integer(value) => value.to_string(),
+            CommandDataOptionValue::Boolean(true) => String::new(),
+            _ => continue,
+        };
+        if !spec.flag.is_empty() {
+            words.push(spec.flag.to_string());
+        }
+        if !value.is_empty() {
+            words.push(value);
+        }
+    }
+
+    Some(words.join(" "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn option(json: serde_json::Value) -> CommandDataOption {
+        serde_json::from_value(json).unwrap()
+    }
+
+    #[test]
+    fn test_slash_options_become_text_flags() {
+        let options = vec![
+            option(serde_json::json!({"name": "context", "type": 5, "value": true})),
+            option(serde_json::json!({"name": "season", "type": 4, "value": 7})),
+            option(serde_json::json!({"name": "term", "type": 3, "value": "steamed hams"})),
+        ];
+        assert_eq!(
+            text_command("frinkiac", &options).as_deref(),
+            Some("!frinkiac steamed hams -s 7 -context")
+        );
+
+        let options = vec![
+            option(serde_json::json!({"name": "show", "type": 3, "value": "Futurama"})),
+            option(serde_json::json!({"name": "term", "type": 3, "value": "robot"})),
+        ];
+        assert_eq!(
+            text_command("quote", &options).as_deref(),
+            Some("!quote robot -show Futurama")
+        );
+
+        // Switches that are off are left out
+        let options = vec![option(
+            serde_json::json!({"name": "fresh", "type": 5, "value": false}),
+        )];
+        assert_eq!(text_command("info", &options).as_deref(), Some("!info"));
+
+        assert_eq!(text_command("nope", &[]), None);
+        assert_eq!(slash_commands(false).len(), SLASH_COMMANDS.len() - 1);
+    }
+}
@@ -0,0 +1,190 @@
+use anyhow::Result;
+use futures::{Stream, StreamExt};
+use serenity::all::Http;
+use serenity::builder::{CreateMessage, EditMessage};
+use serenity::model::channel::{Message, MessageReference};
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+/// Discord's limit on a message's length, in characters
+pub const DISCORD_MESSAGE_LIMIT: usize = 2000;
+
+// How often the message is edited while text streams in, to stay clear of rate limits
+const EDIT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Split text into pieces of at most `limit` characters, breaking at the last newline or
+/// space before the limit where there is one
+pub fn split_for_discord(text: &str, limit: usize) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut rest = text.trim();
+    while rest.chars().count() > limit {
+        let hard_end = rest
+            .char_indices()
+            .nth(limit)
+            .map(|(i, _)| i)
+            .unwrap_or(rest.len());
+        let window = &rest[..hard_end];
+        let end = window
+            .rfind('\n')
+            .or_else(|| window.rfind(' '))
+            .filter(|&at| at > 0)
+            .unwrap_or(hard_end);
+        parts.push(rest[..end].trim_end().to_string());
+        rest = rest[end..].trim_start();
+    }
+    if !rest.is_empty() {
+        parts.push(rest.to_string());
+    }
+    parts
+}
+
+/// Join lines into as few messages of at most `limit` characters as possible, never
+/// breaking a line that fits in one message
+pub fn pack_lines(lines: &[String], limit: usize) -> Vec<String> {
+    let mut messages: Vec<String> = Vec::new();
+    for line in lines {
+        for piece in split_for_discord(line, limit) {
+            match messages.last_mut() {
+                Some(last) if last.chars().count() + 1 + piece.chars().count() <= limit => {
+                    last.push('\n');
+                    last.push_str(&piece);
+                }
+                _ => messages.push(piece),
+            }
+        }
+    }
+    messages
+}
+
+// Bring the posted messages up to date with the text so far: edit the ones whose piece
+// changed, post follow-ups for new pieces, and delete any left over once the text has
+// shrunk. The first piece replies to `msg`.
+async fn sync_messages(
+    http: &Http,
+    msg: &Message,
+    messages: &mut Vec<Message>,
+    text: &str,
+) -> Result<()> {
+    let parts = split_for_discord(text, DISCORD_MESSAGE_LIMIT);
+    while messages.len() > parts.len() {
+        if let Some(stale) = messages.pop() {
+            stale.delete(http).await?;
+        }
+    }
+    for (i, part) in parts.into_iter().enumerate() {
+        if let Some(posted) = messages.get_mut(i) {
+            if posted.content != part {
+                posted.edit(http, EditMessage::new().content(part)).await?;
+            }
+        } else {
+            let mut create_message = CreateMessage::new().content(part);
+            if i == 0 {
+                create_message = create_message.reference_message(MessageReference::from(msg));
+            }
+            messages.push(msg.channel_id.send_message(http, create_message).await?);
+        }
+    }
+    Ok(())
+}
+
+/// Reply to `msg` with text that fills in as `chunks` arrive, editing the reply about once
+/// a second and continuing in follow-up messages past Discord's length limit. `finish` is
+/// applied to the complete text for the last edit. Returns the messages posted and the full
+/// text. Fails, without posting anything, if the stream fails before producing any text, so
+/// the caller can fall back to a regular reply; a failure after that keeps what was shown.
+/// If `finish` leaves nothing to send, whatever was shown is deleted and this fails too.
+pub async fn stream_reply<S>(
+    http: &Http,
+    msg: &Message,
+    chunks: S,
+    finish: impl Fn(&str) -> String,
+) -> Result<(Vec<Message>, String)>
+where
+    S: Stream<Item = Result<String>>,
+{
+    let mut chunks = std::pin::pin!(chunks);
+    let mut messages = Vec::new();
+    let mut text = String::new();
+    let mut last_sync = Instant::now();
+
+    while let Some(chunk) = chunks.next().await {
+        match chunk {
+            Ok(chunk) => text.push_str(&chunk),
+            Err(e) if messages.is_empty() => return Err(e),
+            Err(e) => {
+                warn!(
+                    "Reply stream failed partway, keeping what was sent: {:?}",
+                    e
+                );
+                break;
+            }
+        }
+
+        let due = messages.is_empty() || last_sync.elapsed() >= EDIT_INTERVAL;
+        if due && !text.trim().is_empty() {
+            sync_messages(http, msg, &mut messages, &text).await?;
+            last_sync = Instant::now();
+        }
+    }
+
+    let text = finish(&text);
+    sync_messages(http, msg, &mut messages, &text).await?;
+    if messages.is_empty() {
+        return Err(anyhow::anyhow!("The reply stream produced no text"));
+    }
+    Ok((messages, text))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_for_discord() {
+        assert_eq!(
+            split_for_discord("  short reply ", 2000),
+            vec!["short reply"]
+        );
+        assert!(split_for_discord("   ", 2000).is_empty());
+
+        // Breaks at the last newline, then space, before the limit
+        assert_eq!(
+            split_for_discord("first line\nsecond line", 15),
+            vec!["first line", "second line"]
+        );
+        assert_eq!(
+            split_for_discord("one two three four", 9),
+            vec!["one two", "three", "four"]
+        );
+        // Words longer than the limit are cut where they must be, counting characters
+        assert_eq!(split_for_discord("ééééé", 2), vec!["éé", "éé", "é"]);
+
+        let long = "word ".repeat(1000);
+        let parts = split_for_discord(&long, DISCORD_MESSAGE_LIMIT);
+        assert_eq!(parts.len(), 3);
+        assert!(parts
+            .iter()
+            .all(|part| part.chars().count() <= DISCORD_MESSAGE_LIMIT));
+        assert_eq!(parts.join(" "), long.trim());
+    }
+
+    #[test]
+    fn test_pack_lines() {
+        let lines = |items: &[&str]| items.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            pack_lines(&lines(&["one", "two", "three"]), 2000),
+            vec!["one\ntwo\nthree"]
+        );
+        // A line that would overflow starts the next message instead of being split
+        assert_eq!(
+            pack_lines(&lines(&["aaaa", "bbbb", "cc"]), 9),
+            vec!["aaaa\nbbbb", "cc"]
+        );
+        // Lines longer than a message are split on their own
+        assert_eq!(
+            pack_lines(&lines(&["short", "one two three four"]), 9),
+            vec!["short", "one two", "three", "four"]
+        );
+        assert!(pack_lines(&lines(&[" ", ""]), 2000).is_empty());
+    }
+}
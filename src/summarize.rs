@@ -0,0 +1,267 @@
+use crate::db_utils::ContextMessage;
+use crate::gemini_api::GeminiClient;
+use crate::message_store::MessageStore;
+use crate::unknown_command::is_prompt_echo;
+use crate::utils::send_without_pings;
+use anyhow::Result;
+use serenity::all::Http;
+use serenity::model::channel::Message;
+use tracing::{error, info};
+
+/// How many recent messages are summarized when no count is given
+pub const DEFAULT_SUMMARY_MESSAGES: usize = 50;
+/// Most messages one summary will read
+pub const MAX_SUMMARY_MESSAGES: usize = 200;
+// Fewer messages than this and there's nothing to summarize
+const MIN_SUMMARY_MESSAGES: usize = 5;
+// Longest a single message gets in the transcript, so one paste can't crowd out the rest
+const TRANSCRIPT_MESSAGE_MAX_CHARS: usize = 300;
+// Longest summary we'll post
+const SUMMARY_MAX_CHARS: usize = 1900;
+
+/// What `!summarize` was asked for
+#[derive(Debug, PartialEq, Eq)]
+pub struct SummarizeOptions {
+    /// How many recent messages to read, already clamped
+    pub count: usize,
+    /// Leave the bot's own messages out of the transcript (-nobot)
+    pub exclude_bot: bool,
+}
+
+/// Read `!summarize [count] [-nobot]`. The count is clamped to
+/// [`MIN_SUMMARY_MESSAGES`]..=[`MAX_SUMMARY_MESSAGES`]; None for anything else.
+pub fn parse_summarize_args(args: &[&str]) -> Option<SummarizeOptions> {
+    let mut options = SummarizeOptions {
+        count: DEFAULT_SUMMARY_MESSAGES,
+        exclude_bot: false,
+    };
+    let mut count_given = false;
+
+    for arg in args {
+        if arg.eq_ignore_ascii_case("-nobot") {
+            options.exclude_bot = true;
+        } else if !count_given {
+            let count = arg.parse::<usize>().ok()?;
+            options.count = count.clamp(MIN_SUMMARY_MESSAGES, MAX_SUMMARY_MESSAGES);
+            count_given = true;
+        } else {
+            return None;
+        }
+    }
+
+    Some(options)
+}
+
+/// Turn recent messages (newest first, as the database returns them) into an oldest-first
+/// "name: message" transcript, skipping commands and, given its username, the bot's own
+/// messages. Returns the transcript and how many messages made it in.
+pub fn build_transcript(messages: &[ContextMessage], bot_author: Option<&str>) -> (String, usize) {
+    let lines: Vec<String> = messages
+        .iter()
+        .rev()
+        .filter(|(author, _, _, content, _)| {
+            let content = content.trim();
+            !content.is_empty()
+                && !content.starts_with('!')
+                && bot_author.is_none_or(|bot| author != bot)
+        })
+        .map(|(author, display_name, _, content, _)| {
+            let name = if display_name.is_empty() {
+                author
+            } else {
+                display_name
+            };
+            let content = content.trim().replace('\n', " ");
+            if content.chars().count() > TRANSCRIPT_MESSAGE_MAX_CHARS {
+                let truncated: String =
+                    content.chars().take(TRANSCRIPT_MESSAGE_MAX_CHARS).collect();
+                format!("{name}: {}…", truncated.trim_end())
+            } else {
+                format!("{name}: {content}")
+            }
+        })
+        .collect();
+
+    let count = lines.len();
+    (lines.join("\n"), count)
+}
+
+/// Clean up the model's summary, or None if it's unusable
+fn sanitize_summary(response: &str) -> Option<String> {
+    let summary = response.trim();
+    if summary.is_empty() || summary.eq_ignore_ascii_case("pass") || is_prompt_echo(summary) {
+        return None;
+    }
+
+    if summary.chars().count() > SUMMARY_MAX_CHARS {
+        let truncated: String = summary.chars().take(SUMMARY_MAX_CHARS).collect();
+        Some(format!("{}…", truncated.trim_end()))
+    } else {
+        Some(summary.to_string())
+    }
+}
+
+// This function will be called from main.rs to handle the !summarize command
+pub async fn handle_summarize_command(
+    http: &Http,
+    msg: &Message,
+    gemini_client: &GeminiClient,
+    message_store: &dyn MessageStore,
+    args: &[&str],
+) -> Result<()> {
+    let Some(options) = parse_summarize_args(args) else {
+        msg.reply(
+            http,
+            format!(
+                "Usage: `!summarize [count] [-nobot]` - count defaults to {DEFAULT_SUMMARY_MESSAGES}, up to {MAX_SUMMARY_MESSAGES}"
+            ),
+        )
+        .await?;
+        return Ok(());
+    };
+
+    // Commands (including this one) are dropped from the transcript, so read a few extra
+    let channel_id = msg.channel_id.to_string();
+    let messages = match message_store
+        .get_recent(options.count + 1, Some(&channel_id))
+        .await
+    {
+        Ok(messages) => Some(messages),
+        Err(e) => {
+            error!("Error fetching messages to summarize: {:?}", e);
+            None
+        }
+    };
+    let Some(messages) = messages else {
+        msg.reply(http, "Couldn't read the channel history right now.")
+            .await?;
+        return Ok(());
+    };
+
+    let bot_author = if options.exclude_bot {
+        match http.get_current_user().await {
+            Ok(user) => Some(user.name.clone()),
+            Err(e) => {
+                error!("Error looking up the bot's own name: {:?}", e);
+                None
+            }
+        }
+    } else {
+        None
+    };
+    let (transcript, included) = build_transcript(&messages, bot_author.as_deref());
+
+    if included < MIN_SUMMARY_MESSAGES {
+        msg.reply(
+            http,
+            "Not much has been said here lately. Nothing to summarize!",
+        )
+        .await?;
+        return Ok(());
+    }
+
+    let _ = msg.channel_id.broadcast_typing(http).await;
+
+    let prompt = gemini_client
+        .prompt_templates()
+        .format_summarize(&transcript);
+    info!("Summarizing {} messages in {}", included, msg.channel_id);
+
+    match gemini_client.generate_content(&prompt).await {
+        Ok(response) => match sanitize_summary(&response) {
+            Some(summary) => {
+                send_without_pings(
+                    http,
+                    msg.channel_id,
+                    format!("**TL;DR of the last {included} messages:**\n{summary}"),
+                )
+                .await?;
+            }
+            None => {
+                error!("Summary returned an unusable response: {}", response);
+                msg.reply(http, "Couldn't make sense of that conversation, sorry.")
+                    .await?;
+            }
+        },
+        Err(e) => {
+            error!("Error generating summary: {:?}", e);
+            if !e.to_string().contains("SILENT_ERROR") {
+                msg.reply(http, "Couldn't summarize right now.").await?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(author: &str, display_name: &str, content: &str) -> ContextMessage {
+        (
+            author.to_string(),
+            display_name.to_string(),
+            None,
+            content.to_string(),
+            None,
+        )
+    }
+
+    #[test]
+    fn test_parse_summarize_args() {
+        let options = |count, exclude_bot| Some(SummarizeOptions { count, exclude_bot });
+
+        assert_eq!(
+            parse_summarize_args(&[]),
+            options(DEFAULT_SUMMARY_MESSAGES, false)
+        );
+        assert_eq!(parse_summarize_args(&["30"]), options(30, false));
+        assert_eq!(parse_summarize_args(&["-nobot", "80"]), options(80, true));
+
+        // Counts are clamped into range
+        assert_eq!(
+            parse_summarize_args(&["5000"]),
+            options(MAX_SUMMARY_MESSAGES, false)
+        );
+        assert_eq!(
+            parse_summarize_args(&["0"]),
+            options(MIN_SUMMARY_MESSAGES, false)
+        );
+
+        assert_eq!(parse_summarize_args(&["lots"]), None);
+        assert_eq!(parse_summarize_args(&["-5"]), None);
+        assert_eq!(parse_summarize_args(&["10", "20"]), None);
+    }
+
+    #[test]
+    fn test_build_transcript() {
+        // Newest first, as the database returns them
+        let messages = [
+            message("crow", "Crow", "Sounds like a plan"),
+            message("bob", "", "!summarize"),
+            message("alice", "Alice", "Pizza\non friday?"),
+            message("bob", "", "  "),
+            message("bob", "Bob", "Who's hungry"),
+        ];
+
+        let (transcript, count) = build_transcript(&messages, None);
+        assert_eq!(count, 3);
+        assert_eq!(
+            transcript,
+            "Bob: Who's hungry\nAlice: Pizza on friday?\nCrow: Sounds like a plan"
+        );
+
+        let (transcript, count) = build_transcript(&messages, Some("crow"));
+        assert_eq!(count, 2);
+        assert!(!transcript.contains("Crow"));
+
+        // Long messages are cut short
+        let long = "a".repeat(TRANSCRIPT_MESSAGE_MAX_CHARS + 50);
+        let (transcript, _) = build_transcript(&[message("bob", "Bob", &long)], None);
+        assert_eq!(
+            transcript.chars().count(),
+            "Bob: ".len() + TRANSCRIPT_MESSAGE_MAX_CHARS + 1
+        );
+    }
+}
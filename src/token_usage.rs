@@ -0,0 +1,222 @@
+use anyhow::Result;
+use chrono::{NaiveDate, Utc};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tokio_rusqlite::Connection as SqliteConnection;
+use tracing::warn;
+
+/// Tokens used by one or more AI calls
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TokenUsage {
+    pub prompt_tokens: u64,
+    pub candidates_tokens: u64,
+    pub total_tokens: u64,
+}
+
+impl TokenUsage {
+    /// Read the usage a response reports: Gemini's `usageMetadata`, or the `usage` block of
+    /// an OpenAI-compatible server. None when the response doesn't say.
+    pub fn from_response(json: &serde_json::Value) -> Option<Self> {
+        let count = |usage: &serde_json::Value, key: &str| {
+            usage.get(key).and_then(|count| count.as_u64()).unwrap_or(0)
+        };
+
+        if let Some(usage) = json.get("usageMetadata") {
+            let prompt_tokens = count(usage, "promptTokenCount");
+            let candidates_tokens = count(usage, "candidatesTokenCount");
+            Some(Self {
+                prompt_tokens,
+                candidates_tokens,
+                total_tokens: usage
+                    .get("totalTokenCount")
+                    .and_then(|count| count.as_u64())
+                    .unwrap_or(prompt_tokens + candidates_tokens),
+            })
+        } else if let Some(usage) = json.get("usage") {
+            let prompt_tokens = count(usage, "prompt_tokens");
+            let candidates_tokens = count(usage, "completion_tokens");
+            Some(Self {
+                prompt_tokens,
+                candidates_tokens,
+                total_tokens: usage
+                    .get("total_tokens")
+                    .and_then(|count| count.as_u64())
+                    .unwrap_or(prompt_tokens + candidates_tokens),
+            })
+        } else {
+            None
+        }
+    }
+
+    fn add(&mut self, other: TokenUsage) {
+        self.prompt_tokens += other.prompt_tokens;
+        self.candidates_tokens += other.candidates_tokens;
+        self.total_tokens += other.total_tokens;
+    }
+}
+
+/// Today's (UTC) token totals across every AI call, for `!info`. With a database the
+/// totals are saved as they grow, so the day's figure survives a restart.
+#[derive(Debug)]
+pub struct UsageTracker {
+    today: std::sync::Mutex<(NaiveDate, TokenUsage)>,
+    db: Option<Arc<Mutex<SqliteConnection>>>,
+}
+
+impl UsageTracker {
+    pub fn new(db: Option<Arc<Mutex<SqliteConnection>>>) -> Self {
+        Self {
+            today: std::sync::Mutex::new((Utc::now().date_naive(), TokenUsage::default())),
+            db,
+        }
+    }
+
+    /// Pick up today's totals saved before a restart
+    pub async fn load(&self) -> Result<()> {
+        let Some(db) = &self.db else {
+            return Ok(());
+        };
+        let day = Utc::now().date_naive();
+        let key = day.to_string();
+
+        let conn_guard = db.lock().await;
+        let saved = conn_guard
+            .call(move |conn| {
+                let mut stmt = conn.prepare(
+                    "SELECT prompt_tokens, candidates_tokens, total_tokens
+                     FROM token_usage WHERE day = ?1",
+                )?;
+                let mut rows = stmt.query([key])?;
+                match rows.next()? {
+                    Some(row) => Ok::<_, rusqlite::Error>(Some(TokenUsage {
+                        prompt_tokens: row.get::<_, i64>(0)? as u64,
+                        candidates_tokens: row.get::<_, i64>(1)? as u64,
+                        total_tokens: row.get::<_, i64>(2)? as u64,
+                    })),
+                    None => Ok(None),
+                }
+            })
+            .await?;
+
+        if let Some(saved) = saved {
+            self.add_at(day, saved);
+        }
+        Ok(())
+    }
+
+    /// Count a call's usage toward today's totals
+    pub async fn record(&self, usage: TokenUsage) {
+        let day = Utc::now().date_naive();
+        self.add_at(day, usage);
+
+        if let Some(db) = &self.db {
+            if let Err(e) = save_usage(db.clone(), day, usage).await {
+                warn!("Failed to save token usage: {}", e);
+            }
+        }
+    }
+
+    /// Today's totals so far
+    pub fn today(&self) -> TokenUsage {
+        self.today_at(Utc::now().date_naive())
+    }
+
+    fn add_at(&self, day: NaiveDate, usage: TokenUsage) {
+        let mut today = self.today.lock().unwrap();
+        // A new day starts the count over
+        if today.0 != day {
+            *today = (day, TokenUsage::default());
+        }
+        today.1.add(usage);
+    }
+
+    fn today_at(&self, day: NaiveDate) -> TokenUsage {
+        let today = self.today.lock().unwrap();
+        if today.0 == day {
+            today.1
+        } else {
+            TokenUsage::default()
+        }
+    }
+}
+
+// Add a call's usage to the day's row in the token_usage table
+async fn save_usage(
+    conn: Arc<Mutex<SqliteConnection>>,
+    day: NaiveDate,
+    usage: TokenUsage,
+) -> Result<()> {
+    let params = (
+        day.to_string(),
+        usage.prompt_tokens as i64,
+        usage.candidates_tokens as i64,
+        usage.total_tokens as i64,
+    );
+
+    let conn_guard = conn.lock().await;
+    conn_guard
+        .call(move |conn| {
+            conn.execute(
+                "INSERT INTO token_usage (day, prompt_tokens, candidates_tokens, total_tokens)
+                 VALUES (?1, ?2, ?3, ?4)
+                 ON CONFLICT(day) DO UPDATE SET
+                     prompt_tokens = prompt_tokens + excluded.prompt_tokens,
+                     candidates_tokens = candidates_tokens + excluded.candidates_tokens,
+                     total_tokens = total_tokens + excluded.total_tokens",
+                params,
+            )?;
+            Ok::<_, rusqlite::Error>(())
+        })
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_usage_accumulates_per_day() {
+        let gemini = serde_json::json!({
+            "candidates": [],
+            "usageMetadata": {
+                "promptTokenCount": 120,
+                "candidatesTokenCount": 30,
+                "totalTokenCount": 150
+            }
+        });
+        let openai = serde_json::json!({
+            "choices": [],
+            "usage": {"prompt_tokens": 10, "completion_tokens": 5}
+        });
+        assert_eq!(
+            TokenUsage::from_response(&openai),
+            Some(TokenUsage {
+                prompt_tokens: 10,
+                candidates_tokens: 5,
+                total_tokens: 15,
+            })
+        );
+        assert_eq!(TokenUsage::from_response(&serde_json::json!({})), None);
+
+        let tracker = UsageTracker::new(None);
+        let monday = NaiveDate::from_ymd_opt(2026, 10, 12).unwrap();
+        let tuesday = monday.succ_opt().unwrap();
+        tracker.add_at(monday, TokenUsage::from_response(&gemini).unwrap());
+        tracker.add_at(monday, TokenUsage::from_response(&openai).unwrap());
+        assert_eq!(
+            tracker.today_at(monday),
+            TokenUsage {
+                prompt_tokens: 130,
+                candidates_tokens: 35,
+                total_tokens: 165,
+            }
+        );
+
+        // Nothing counted yet on a new day, and the first call there starts over
+        assert_eq!(tracker.today_at(tuesday), TokenUsage::default());
+        tracker.add_at(tuesday, TokenUsage::from_response(&openai).unwrap());
+        assert_eq!(tracker.today_at(tuesday).total_tokens, 15);
+        assert_eq!(tracker.today_at(monday), TokenUsage::default());
+    }
+}
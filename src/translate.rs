@@ -0,0 +1,255 @@
+use crate::gemini_api::GeminiClient;
+use crate::unknown_command::is_prompt_echo;
+use crate::utils::send_without_pings;
+use anyhow::Result;
+use serenity::all::Http;
+use serenity::model::channel::Message;
+use tracing::{error, info};
+
+/// What text is translated into when no language is given
+pub const DEFAULT_LANGUAGE: &str = "English";
+
+// Longest translation we'll post, leaving room for the language label
+const TRANSLATION_MAX_CHARS: usize = 1900;
+
+// Language codes and the names they stand for
+const LANGUAGES: &[(&str, &str)] = &[
+    ("ar", "Arabic"),
+    ("cs", "Czech"),
+    ("cy", "Welsh"),
+    ("da", "Danish"),
+    ("de", "German"),
+    ("el", "Greek"),
+    ("en", "English"),
+    ("eo", "Esperanto"),
+    ("es", "Spanish"),
+    ("fi", "Finnish"),
+    ("fr", "French"),
+    ("ga", "Irish"),
+    ("he", "Hebrew"),
+    ("hi", "Hindi"),
+    ("hu", "Hungarian"),
+    ("id", "Indonesian"),
+    ("it", "Italian"),
+    ("ja", "Japanese"),
+    ("ko", "Korean"),
+    ("la", "Latin"),
+    ("nl", "Dutch"),
+    ("no", "Norwegian"),
+    ("pl", "Polish"),
+    ("pt", "Portuguese"),
+    ("ro", "Romanian"),
+    ("ru", "Russian"),
+    ("sv", "Swedish"),
+    ("th", "Thai"),
+    ("tl", "Tagalog"),
+    ("tr", "Turkish"),
+    ("uk", "Ukrainian"),
+    ("vi", "Vietnamese"),
+    ("zh", "Chinese"),
+];
+
+// Codes that are also everyday English words, so "!translate it is raining" translates the
+// whole sentence. They still work with -to.
+const AMBIGUOUS_CODES: &[&str] = &["he", "id", "it", "la", "no"];
+
+/// The language a name or code stands for, e.g. "es" or "spanish" -> "Spanish"
+fn language_name(word: &str) -> Option<&'static str> {
+    let word = word.to_lowercase();
+    LANGUAGES
+        .iter()
+        .find(|(code, name)| *code == word || name.to_lowercase() == word)
+        .map(|(_, name)| *name)
+}
+
+/// Split `!translate` arguments into the target language and the text to translate.
+/// The language is `-to <lang>` anywhere, or a leading language name or code (optionally
+/// after "to"); otherwise it's [`DEFAULT_LANGUAGE`]. Unknown `-to` languages are passed
+/// through as written, since the model knows more of them than we do.
+pub fn parse_translate_args(args: &[&str]) -> Option<(String, String)> {
+    let mut words: Vec<&str> = args.to_vec();
+    let mut language = None;
+
+    if let Some(i) = words.iter().position(|word| *word == "-to") {
+        if let Some(lang) = words.get(i + 1) {
+            language = Some(language_name(lang).map_or_else(|| lang.to_string(), str::to_string));
+            words.drain(i..=i + 1);
+        } else {
+            words.remove(i);
+        }
+    }
+
+    if language.is_none() {
+        let skip = usize::from(words.len() > 2 && words[0].eq_ignore_ascii_case("to"));
+        if let (Some(first), true) = (words.get(skip), words.len() > skip + 1) {
+            let ambiguous = AMBIGUOUS_CODES.contains(&first.to_lowercase().as_str());
+            if let Some(name) = language_name(first).filter(|_| !ambiguous) {
+                language = Some(name.to_string());
+                words.drain(..=skip);
+            }
+        }
+    }
+
+    let text = words.join(" ");
+    let text = text.trim();
+    if text.is_empty() {
+        return None;
+    }
+
+    Some((
+        language.unwrap_or_else(|| DEFAULT_LANGUAGE.to_string()),
+        text.to_string(),
+    ))
+}
+
+/// Clean up the model's translation, or None if it's unusable
+fn sanitize_translation(response: &str) -> Option<String> {
+    let mut text = response.trim();
+
+    // Drop quotes wrapped around the whole answer
+    for quote in ['"', '“', '«', '`'] {
+        let close = match quote {
+            '“' => '”',
+            '«' => '»',
+            other => other,
+        };
+        let inner = text
+            .strip_prefix(quote)
+            .and_then(|rest| rest.strip_suffix(close));
+        if let Some(inner) = inner.filter(|inner| !inner.contains(close)) {
+            text = inner.trim();
+        }
+    }
+
+    if text.is_empty() || is_prompt_echo(text) {
+        return None;
+    }
+
+    if text.chars().count() > TRANSLATION_MAX_CHARS {
+        let truncated: String = text.chars().take(TRANSLATION_MAX_CHARS).collect();
+        Some(format!("{}…", truncated.trim_end()))
+    } else {
+        Some(text.to_string())
+    }
+}
+
+// This function will be called from main.rs to handle the !translate command
+pub async fn handle_translate_command(
+    http: &Http,
+    msg: &Message,
+    gemini_client: &GeminiClient,
+    args: &[&str],
+) -> Result<()> {
+    let Some((language, text)) = parse_translate_args(args) else {
+        msg.reply(
+            http,
+            "Usage: `!translate [language] <text>`, e.g. `!translate spanish hello there` (defaults to English)",
+        )
+        .await?;
+        return Ok(());
+    };
+
+    let _ = msg.channel_id.broadcast_typing(http).await;
+
+    let prompt = gemini_client
+        .prompt_templates()
+        .format_translate(&language, &text);
+    info!("Translating {} characters into {}", text.len(), language);
+
+    match gemini_client.generate_content(&prompt).await {
+        Ok(response) => match sanitize_translation(&response) {
+            Some(translation) => {
+                send_without_pings(
+                    http,
+                    msg.channel_id,
+                    format!("**{language}:** {translation}"),
+                )
+                .await?;
+            }
+            None => {
+                error!("Translation returned an unusable response: {}", response);
+                msg.reply(http, "Couldn't translate that, sorry.").await?;
+            }
+        },
+        Err(e) => {
+            error!("Error generating translation: {:?}", e);
+            if !e.to_string().contains("SILENT_ERROR") {
+                msg.reply(http, "Couldn't translate that right now.")
+                    .await?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &str) -> Option<(String, String)> {
+        let args: Vec<&str> = args.split_whitespace().collect();
+        parse_translate_args(&args)
+    }
+
+    fn parsed(language: &str, text: &str) -> Option<(String, String)> {
+        Some((language.to_string(), text.to_string()))
+    }
+
+    #[test]
+    fn test_parse_translate_args() {
+        // Leading language name or code, in any case
+        assert_eq!(
+            parse("spanish hello there"),
+            parsed("Spanish", "hello there")
+        );
+        assert_eq!(parse("FR good morning"), parsed("French", "good morning"));
+        assert_eq!(parse("to german thank you"), parsed("German", "thank you"));
+
+        // No language means English, and a lone word is text, not a language
+        assert_eq!(parse("hola amigos"), parsed("English", "hola amigos"));
+        assert_eq!(parse("japanese"), parsed("English", "japanese"));
+        assert_eq!(parse("to be or not"), parsed("English", "to be or not"));
+
+        // Codes that are English words start the text unless given with -to
+        assert_eq!(parse("it is raining"), parsed("English", "it is raining"));
+        assert_eq!(parse("no way"), parsed("English", "no way"));
+        assert_eq!(parse("IT is down"), parsed("English", "IT is down"));
+        assert_eq!(
+            parse("-to it it is raining"),
+            parsed("Italian", "it is raining")
+        );
+
+        // -to can go anywhere and takes languages we don't list
+        assert_eq!(parse("good night -to ja"), parsed("Japanese", "good night"));
+        assert_eq!(parse("ahoy -to Klingon"), parsed("Klingon", "ahoy"));
+
+        assert_eq!(parse(""), None);
+        assert_eq!(parse("-to spanish"), None);
+    }
+
+    #[test]
+    fn test_sanitize_translation() {
+        assert_eq!(
+            sanitize_translation("  \"Hola, ¿qué tal?\"\n"),
+            Some("Hola, ¿qué tal?".to_string())
+        );
+        assert_eq!(
+            sanitize_translation("«Bonjour»"),
+            Some("Bonjour".to_string())
+        );
+        assert_eq!(
+            sanitize_translation("Er sagte \"Hallo\""),
+            Some("Er sagte \"Hallo\"".to_string())
+        );
+        assert_eq!(
+            sanitize_translation("\"Sí\" y \"no\""),
+            Some("\"Sí\" y \"no\"".to_string())
+        );
+        assert_eq!(sanitize_translation("   "), None);
+        assert_eq!(
+            sanitize_translation("You are {bot_name}, a Discord bot"),
+            None
+        );
+    }
+}
@@ -4,7 +4,7 @@ use serenity::all::Http;
 use serenity::model::channel::Message;
 use tracing::error;
 
-fn is_prompt_echo(response: &str) -> bool {
+pub fn is_prompt_echo(response: &str) -> bool {
     response.contains("{bot_name}")
         || response.contains("{user}")
         || response.contains("{message}")
@@ -0,0 +1,256 @@
+use crate::http_client;
+use anyhow::Result;
+use serde::Deserialize;
+use serenity::all::Http;
+use serenity::model::channel::Message;
+use tracing::{error, info};
+
+const API_URL: &str = "https://api.urbandictionary.com/v0/define";
+// Longest definition and example we'll post, well inside Discord's 2000 characters
+const MAX_DEFINITION_CHARS: usize = 900;
+const MAX_EXAMPLE_CHARS: usize = 400;
+
+/// Words that keep a definition out of the channel while the filter is on; add to them
+/// with URBAN_BLOCKED_TERMS
+pub const DEFAULT_BLOCKED_TERMS: &[&str] = &[
+    "anal",
+    "blowjob",
+    "boobs",
+    "cock",
+    "cocks",
+    "cum",
+    "cunt",
+    "dick",
+    "dicks",
+    "dildo",
+    "fuck",
+    "fucked",
+    "fucking",
+    "horny",
+    "jizz",
+    "masturbate",
+    "masturbating",
+    "nude",
+    "nudes",
+    "orgasm",
+    "penis",
+    "porn",
+    "pussy",
+    "sex",
+    "sexual",
+    "slut",
+    "tits",
+    "vagina",
+    "whore",
+];
+
+#[derive(Debug, Deserialize)]
+struct DefineResponse {
+    #[serde(default)]
+    list: Vec<UrbanEntry>,
+}
+
+/// One definition as the API returns it
+#[derive(Debug, Clone, Deserialize)]
+pub struct UrbanEntry {
+    pub word: String,
+    pub definition: String,
+    #[serde(default)]
+    pub example: String,
+    #[serde(default)]
+    pub permalink: String,
+}
+
+/// Drop the `[term]` cross-reference markup, leaving the words
+pub fn strip_brackets(text: &str) -> String {
+    text.replace(['[', ']'], "")
+}
+
+/// Tidy entry text for the channel: no bracket markup, no blank-line runs, and cut at a
+/// word boundary with an ellipsis when longer than `max_chars`
+pub fn clean_text(text: &str, max_chars: usize) -> String {
+    let text = strip_brackets(text)
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    if text.chars().count() <= max_chars {
+        return text;
+    }
+    let cut: String = text.chars().take(max_chars - 1).collect();
+    let cut = cut
+        .rsplit_once(char::is_whitespace)
+        .map_or(cut.as_str(), |(head, _)| head);
+    format!("{}…", cut.trim_end())
+}
+
+/// Whether any blocked term appears as a word in the entry
+fn is_blocked(entry: &UrbanEntry, blocked_terms: &[String]) -> bool {
+    [&entry.word, &entry.definition, &entry.example]
+        .iter()
+        .flat_map(|text| text.split(|c: char| !c.is_alphanumeric()))
+        .any(|word| {
+            blocked_terms
+                .iter()
+                .any(|term| word.eq_ignore_ascii_case(term))
+        })
+}
+
+/// The top entry the filter allows (every entry when `blocked_terms` is None)
+fn top_entry(entries: Vec<UrbanEntry>, blocked_terms: Option<&[String]>) -> Option<UrbanEntry> {
+    entries
+        .into_iter()
+        .find(|entry| blocked_terms.is_none_or(|terms| !is_blocked(entry, terms)))
+}
+
+fn format_entry(entry: &UrbanEntry) -> String {
+    let mut reply = format!(
+        "**{}**: {}",
+        entry.word.trim(),
+        clean_text(&entry.definition, MAX_DEFINITION_CHARS)
+    );
+
+    let example = clean_text(&entry.example, MAX_EXAMPLE_CHARS);
+    if !example.is_empty() {
+        let example = example
+            .lines()
+            .map(|line| format!("> *{line}*"))
+            .collect::<Vec<_>>()
+            .join("\n");
+        reply.push_str(&format!("\n{example}"));
+    }
+
+    if !entry.permalink.is_empty() {
+        reply.push_str(&format!("\n<{}>", entry.permalink));
+    }
+    reply
+}
+
+async fn fetch_definitions(term: &str) -> Result<Vec<UrbanEntry>> {
+    let url = format!("{}?term={}", API_URL, urlencoding::encode(term));
+    let response: DefineResponse = http_client::shared()
+        .get(&url)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    Ok(response.list)
+}
+
+// This function will be called from main.rs to handle the !urban command. With
+// `blocked_terms` set, definitions using any of them are skipped.
+pub async fn handle_urban_command(
+    http: &Http,
+    msg: &Message,
+    term: &str,
+    blocked_terms: Option<&[String]>,
+) -> Result<()> {
+    let term = term.trim();
+    if term.is_empty() {
+        msg.reply(http, "Usage: !urban <term>").await?;
+        return Ok(());
+    }
+    info!("Handling !urban command for: {}", term);
+
+    if let Err(e) = msg.channel_id.broadcast_typing(http).await {
+        error!("Failed to send typing indicator: {:?}", e);
+    }
+
+    match fetch_definitions(term).await {
+        Ok(entries) if entries.is_empty() => {
+            msg.reply(
+                http,
+                format!("Urban Dictionary has nothing for '{term}'. Too niche, even for them."),
+            )
+            .await?;
+        }
+        Ok(entries) => match top_entry(entries, blocked_terms) {
+            Some(entry) => {
+                msg.channel_id.say(http, format_entry(&entry)).await?;
+            }
+            None => {
+                msg.reply(
+                    http,
+                    format!("Every definition of '{term}' is too spicy for this channel."),
+                )
+                .await?;
+            }
+        },
+        Err(e) => {
+            error!("Error fetching Urban Dictionary definition: {:?}", e);
+            msg.reply(http, "Sorry, I couldn't reach Urban Dictionary right now.")
+                .await?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(word: &str, definition: &str, example: &str) -> UrbanEntry {
+        UrbanEntry {
+            word: word.to_string(),
+            definition: definition.to_string(),
+            example: example.to_string(),
+            permalink: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_clean_text() {
+        assert_eq!(
+            strip_brackets("A [yeet] is a [strong throw]"),
+            "A yeet is a strong throw"
+        );
+        assert_eq!(
+            clean_text("[He] yeeted it.\r\n\r\n\r\n[She] yeeted it back. ", 100),
+            "He yeeted it.\nShe yeeted it back."
+        );
+
+        // Long text is cut between words
+        let long = clean_text("one [two] three four five", 15);
+        assert_eq!(long, "one two three…");
+        assert!(long.chars().count() <= 15);
+        assert_eq!(
+            clean_text(&"x".repeat(20), 10),
+            format!("{}…", "x".repeat(9))
+        );
+    }
+
+    #[test]
+    fn test_blocked_terms() {
+        let blocked = vec!["spicy".to_string()];
+        let entries = vec![
+            entry("yeet", "Something SPICY", ""),
+            entry("yeet", "To throw", "He yeeted it, spicily"),
+        ];
+
+        let top = top_entry(entries.clone(), Some(blocked.as_slice())).unwrap();
+        assert_eq!(top.definition, "To throw");
+        assert_eq!(
+            top_entry(entries.clone(), None).unwrap().definition,
+            "Something SPICY"
+        );
+        assert!(top_entry(entries[..1].to_vec(), Some(blocked.as_slice())).is_none());
+    }
+
+    #[test]
+    fn test_format_entry() {
+        let mut yeet = entry("yeet", "To [throw]", "[He] yeeted it.\nFar.");
+        yeet.permalink = "https://yeet.urbanup.com/1".to_string();
+        assert_eq!(
+            format_entry(&yeet),
+            "**yeet**: To throw\n> *He yeeted it.*\n> *Far.*\n<https://yeet.urbanup.com/1>"
+        );
+        assert_eq!(
+            format_entry(&entry("yeet", "To throw", "")),
+            "**yeet**: To throw"
+        );
+    }
+}
@@ -1,5 +1,8 @@
 use lazy_static::lazy_static;
 use regex::Regex;
+use serenity::all::{CreateAllowedMentions, CreateMessage, Http, Message};
+use serenity::model::id::ChannelId;
+use std::collections::HashSet;
 
 lazy_static! {
     // Regex to match common pronoun patterns in usernames
@@ -18,10 +21,99 @@ pub fn extract_pronouns(name: &str) -> Option<String> {
     None
 }
 
+/// Build a message that can't ping anyone. Interjections are model-generated and
+/// often echo names from the conversation, so they go out with mentions disabled.
+pub fn no_ping_message(content: impl Into<String>) -> CreateMessage {
+    CreateMessage::new()
+        .content(content)
+        .allowed_mentions(CreateAllowedMentions::new())
+}
+
+/// Send bot-initiated chatter (interjections, spontaneous messages) without pinging anyone
+pub async fn send_without_pings(
+    http: &Http,
+    channel_id: ChannelId,
+    content: impl Into<String>,
+) -> serenity::Result<Message> {
+    channel_id
+        .send_message(http, no_ping_message(content))
+        .await
+}
+
+// Messages with fewer distinct words than this are too short to call an echo
+const MIN_SIMILARITY_TOKENS: usize = 3;
+
+// Lowercased distinct words of a message, ignoring punctuation
+fn similarity_tokens(text: &str) -> HashSet<String> {
+    text.split(|c: char| !c.is_alphanumeric() && c != '\'')
+        .map(|word| word.trim_matches('\'').to_lowercase())
+        .filter(|word| !word.is_empty())
+        .collect()
+}
+
+/// How much of the shorter message's wording also appears in the longer one, from 0.0 to 1.0.
+/// Measured against the shorter message so quoting something back counts as an echo.
+pub fn token_overlap(a: &str, b: &str) -> f64 {
+    let a = similarity_tokens(a);
+    let b = similarity_tokens(b);
+    let smaller = a.len().min(b.len());
+    if smaller < MIN_SIMILARITY_TOKENS {
+        return 0.0;
+    }
+    a.intersection(&b).count() as f64 / smaller as f64
+}
+
+/// Whether an interjection would just repeat one of the recent messages, i.e. its overlap
+/// with any of them reaches `threshold`. A threshold of 0 turns the check off.
+pub fn is_echo_of_recent<'a>(
+    candidate: &str,
+    recent: impl IntoIterator<Item = &'a str>,
+    threshold: f64,
+) -> bool {
+    threshold > 0.0
+        && recent
+            .into_iter()
+            .any(|message| token_overlap(candidate, message) >= threshold)
+}
+
+/// The bot's resident memory in bytes, or None if the OS won't say
+pub fn current_rss_bytes() -> Option<u64> {
+    let pid = sysinfo::get_current_pid().ok()?;
+    let mut system = sysinfo::System::new();
+    system.refresh_processes_specifics(
+        sysinfo::ProcessesToUpdate::Some(&[pid]),
+        false,
+        sysinfo::ProcessRefreshKind::nothing().with_memory(),
+    );
+    system
+        .process(pid)
+        .map(|process| process.memory())
+        .filter(|&bytes| bytes > 0)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_current_rss_bytes() {
+        let rss = current_rss_bytes().expect("RSS should be available on the test host");
+        // A running test binary takes at least a few hundred KB
+        assert!(rss > 100 * 1024, "implausible RSS: {rss}");
+    }
+
+    #[test]
+    fn test_no_ping_message_disables_mentions() {
+        let message = no_ping_message("Hey <@123> and @everyone");
+        let json = serde_json::to_value(&message).unwrap();
+
+        assert_eq!(json["content"], "Hey <@123> and @everyone");
+        let allowed = &json["allowed_mentions"];
+        assert_eq!(allowed["parse"], serde_json::json!([]));
+        assert_eq!(allowed["users"], serde_json::json!([]));
+        assert_eq!(allowed["roles"], serde_json::json!([]));
+    }
+
     #[test]
     fn test_extract_pronouns() {
         // Test with parentheses
@@ -54,4 +146,27 @@ mod tests {
             Some("he/him".to_string())
         );
     }
+
+    #[test]
+    fn test_interjection_echo_threshold() {
+        assert_eq!(token_overlap("The cat sat down", "the CAT sat, down!"), 1.0);
+        assert_eq!(token_overlap("one two three four", "one two five six"), 0.5);
+        // Too short to judge
+        assert_eq!(token_overlap("lol", "lol"), 0.0);
+        // Quoting a message back counts, however much is added around it
+        assert_eq!(
+            token_overlap(
+                "Remember when Bob said the pizza place closed? Truly dark times.",
+                "the pizza place closed"
+            ),
+            1.0
+        );
+
+        let recent = ["one two five six", "something else entirely"];
+        // Exactly at the threshold is skipped; just below it goes through
+        assert!(is_echo_of_recent("one two three four", recent, 0.5));
+        assert!(!is_echo_of_recent("one two three four", recent, 0.51));
+        // 0 turns the filter off
+        assert!(!is_echo_of_recent("one two five six", recent, 0.0));
+    }
 }
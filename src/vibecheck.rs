@@ -0,0 +1,175 @@
+use crate::gemini_api::GeminiClient;
+use crate::message_store::MessageStore;
+use crate::unknown_command::is_prompt_echo;
+use anyhow::Result;
+use serenity::all::Http;
+use serenity::model::channel::Message;
+use serenity::model::id::ChannelId;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+use tracing::{error, info};
+
+// How many recent messages to sample for the vibe
+const VIBE_SAMPLE_SIZE: usize = 20;
+// Fewer messages than this and the channel is just quiet
+const VIBE_MIN_MESSAGES: usize = 5;
+// Per-channel cooldown between vibe checks
+const VIBE_COOLDOWN: Duration = Duration::from_secs(120);
+// Longest vibe we'll post
+const VIBE_MAX_CHARS: usize = 150;
+
+/// Clean up a model response into a single short vibe line, or None if unusable
+fn sanitize_vibe(response: &str) -> Option<String> {
+    let line = response
+        .lines()
+        .map(|l| l.trim())
+        .find(|l| !l.is_empty())?
+        .trim_matches(['"', '\'', '`'])
+        .trim();
+
+    if line.is_empty() || line.eq_ignore_ascii_case("pass") || is_prompt_echo(line) {
+        return None;
+    }
+
+    // Never let a vibe ping anyone
+    let line = line
+        .replace("@everyone", "everyone")
+        .replace("@here", "here")
+        .replace("<@", "<");
+
+    if line.chars().count() > VIBE_MAX_CHARS {
+        let truncated: String = line.chars().take(VIBE_MAX_CHARS).collect();
+        Some(format!("{}…", truncated.trim_end()))
+    } else {
+        Some(line)
+    }
+}
+
+// This function will be called from main.rs to handle the !vibecheck command
+pub async fn handle_vibecheck_command(
+    http: &Http,
+    msg: &Message,
+    gemini_client: &GeminiClient,
+    message_store: &dyn MessageStore,
+    cooldowns: &RwLock<HashMap<ChannelId, Instant>>,
+) -> Result<()> {
+    // Rate-limit per channel so the command can't be spammed
+    if let Some(last) = cooldowns.read().await.get(&msg.channel_id) {
+        let remaining = VIBE_COOLDOWN.saturating_sub(last.elapsed());
+        if !remaining.is_zero() {
+            msg.reply(
+                http,
+                format!(
+                    "The vibe hasn't changed that much. Try again in {} seconds.",
+                    remaining.as_secs().max(1)
+                ),
+            )
+            .await?;
+            return Ok(());
+        }
+    }
+
+    let channel_id = msg.channel_id.to_string();
+    let messages = match message_store
+        .get_recent(VIBE_SAMPLE_SIZE, Some(&channel_id))
+        .await
+    {
+        Ok(messages) => Some(messages),
+        Err(e) => {
+            error!("Error fetching messages for vibe check: {:?}", e);
+            None
+        }
+    };
+    let Some(messages) = messages else {
+        msg.channel_id
+            .say(http, "Couldn't read the room right now.")
+            .await?;
+        return Ok(());
+    };
+
+    // Ignore other commands (including this one) when judging the mood
+    let chatter: Vec<_> = messages
+        .iter()
+        .filter(|(_, _, _, content, _)| !content.trim_start().starts_with('!'))
+        .collect();
+
+    if chatter.len() < VIBE_MIN_MESSAGES {
+        msg.channel_id
+            .say(
+                http,
+                "It's pretty quiet in here. The vibe is... tumbleweeds 🌵",
+            )
+            .await?;
+        return Ok(());
+    }
+
+    cooldowns
+        .write()
+        .await
+        .insert(msg.channel_id, Instant::now());
+
+    let _ = msg.channel_id.broadcast_typing(http).await;
+
+    // Messages come back newest first
+    let context = chatter
+        .iter()
+        .rev()
+        .map(|(author, display_name, _, content, _)| {
+            let name = if display_name.is_empty() {
+                author
+            } else {
+                display_name
+            };
+            format!("{name}: {content}")
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let prompt = gemini_client.prompt_templates().format_vibe_check(&context);
+    info!("Running vibe check on {} messages", chatter.len());
+
+    match gemini_client.generate_content(&prompt).await {
+        Ok(response) => match sanitize_vibe(&response) {
+            Some(vibe) => {
+                msg.channel_id.say(http, vibe).await?;
+            }
+            None => {
+                error!("Vibe check returned an unusable response: {}", response);
+                msg.channel_id
+                    .say(http, "Couldn't get a read on the vibe. Inscrutable 🤷")
+                    .await?;
+            }
+        },
+        Err(e) => {
+            error!("Error generating vibe check: {:?}", e);
+            if !e.to_string().contains("SILENT_ERROR") {
+                msg.channel_id
+                    .say(http, "Couldn't read the room right now.")
+                    .await?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sanitize_vibe() {
+        assert_eq!(
+            sanitize_vibe("\"Chaotic good energy 🔥\"\nextra line"),
+            Some("Chaotic good energy 🔥".to_string())
+        );
+        assert_eq!(
+            sanitize_vibe("Hyped for @everyone <@123> 🎉"),
+            Some("Hyped for everyone <123> 🎉".to_string())
+        );
+        assert_eq!(sanitize_vibe("  \n "), None);
+        assert_eq!(sanitize_vibe("pass"), None);
+        assert_eq!(sanitize_vibe("You are {bot_name}, a Discord bot"), None);
+    }
+}
@@ -0,0 +1,609 @@
+use crate::http_client;
+use anyhow::{anyhow, Result};
+use reqwest::Client;
+use serde_json::Value;
+use serenity::all::Http;
+use serenity::model::channel::Message;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tracing::{error, info};
+
+const API_URL: &str = "https://en.wikipedia.org/w/api.php";
+const ARTICLE_URL: &str = "https://en.wikipedia.org/wiki";
+
+// Search results considered when picking an article
+const SEARCH_LIMIT: usize = 5;
+// Longest summary !wiki posts; longer ones are cut at a word
+const MAX_SUMMARY_CHARS: usize = 600;
+
+/// How long a lookup is reused unless WIKIPEDIA_CACHE_SECS says otherwise
+pub const DEFAULT_CACHE_TTL: Duration = Duration::from_secs(3 * 60 * 60);
+/// Most names remembered unless WIKIPEDIA_CACHE_MAX_ENTRIES says otherwise
+pub const DEFAULT_CACHE_MAX_ENTRIES: usize = 500;
+
+/// The start of a Wikipedia article
+#[derive(Debug, Clone, PartialEq)]
+pub struct WikiIntro {
+    pub title: String,
+    /// The intro section as plain text
+    pub extract: String,
+    pub thumbnail_url: Option<String>,
+    /// The article's Wikidata item, e.g. "Q42"
+    pub wikidata_id: Option<String>,
+}
+
+/// A search result: the article title and its snippet, lowercased
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchHit {
+    pub title: String,
+    pub snippet: String,
+}
+
+/// How the Wikipedia API is reached; a trait so tests can count requests
+pub trait WikiFetch: Sync {
+    /// GET an API URL as JSON
+    async fn get_json(&self, url: &str) -> Result<Value>;
+}
+
+impl WikiFetch for Client {
+    // Logs what came back if it isn't JSON
+    async fn get_json(&self, url: &str) -> Result<Value> {
+        let response = self.get(url).send().await?;
+
+        if !response.status().is_success() {
+            error!(
+                "Wikipedia API returned HTTP {}: {}",
+                response.status(),
+                response.status().canonical_reason().unwrap_or("Unknown")
+            );
+            return Err(anyhow!("Wikipedia API returned HTTP {}", response.status()));
+        }
+
+        // Get response text first to log it if JSON parsing fails
+        let response_text = response.text().await?;
+        serde_json::from_str(&response_text).map_err(|e| {
+            error!(
+                "Failed to parse Wikipedia response as JSON. Response was: {}",
+                response_text.chars().take(200).collect::<String>()
+            );
+            anyhow!("JSON parsing failed: {}", e)
+        })
+    }
+}
+
+// One cached lookup
+struct CacheEntry<V> {
+    value: V,
+    stored: Instant,
+    used: Instant,
+}
+
+/// Lookups kept for `ttl`, keyed by normalized name. When full, the least recently used
+/// entry makes way for a new one.
+struct TtlCache<V> {
+    ttl: Duration,
+    max_entries: usize,
+    entries: std::sync::Mutex<HashMap<String, CacheEntry<V>>>,
+}
+
+impl<V: Clone> TtlCache<V> {
+    fn new(ttl: Duration, max_entries: usize) -> Self {
+        Self {
+            ttl,
+            max_entries,
+            entries: std::sync::Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn get_at(&self, key: &str, now: Instant) -> Option<V> {
+        let mut entries = self.entries.lock().unwrap();
+        let entry = entries
+            .get_mut(&cache_key(key))
+            .filter(|entry| now.duration_since(entry.stored) < self.ttl)?;
+        entry.used = now;
+        Some(entry.value.clone())
+    }
+
+    fn insert_at(&self, key: &str, value: V, now: Instant) {
+        if self.max_entries == 0 {
+            return;
+        }
+
+        let key = cache_key(key);
+        let mut entries = self.entries.lock().unwrap();
+        // Drop expired entries so the map doesn't grow forever
+        entries.retain(|_, entry| now.duration_since(entry.stored) < self.ttl);
+        if entries.len() >= self.max_entries && !entries.contains_key(&key) {
+            let least_used = entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.used)
+                .map(|(key, _)| key.clone());
+            if let Some(least_used) = least_used {
+                entries.remove(&least_used);
+            }
+        }
+        entries.insert(
+            key,
+            CacheEntry {
+                value,
+                stored: now,
+                used: now,
+            },
+        );
+    }
+}
+
+// Names that differ only in case or spacing share a cache entry
+fn cache_key(name: &str) -> String {
+    name.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// Wikipedia lookups, cached so the same name asked for again (including ones that found
+/// nothing) doesn't go back to the API until `ttl` has passed
+pub struct Wikipedia<F = Client> {
+    fetch: F,
+    searches: TtlCache<Vec<SearchHit>>,
+    pages: TtlCache<Option<WikiIntro>>,
+}
+
+impl Wikipedia {
+    pub fn new(ttl: Duration, max_entries: usize) -> Self {
+        Self::with_fetch(http_client::shared(), ttl, max_entries)
+    }
+
+    /// The HTTP client, for the Wikidata lookups that go with an article
+    pub fn client(&self) -> &Client {
+        &self.fetch
+    }
+}
+
+impl<F: WikiFetch> Wikipedia<F> {
+    pub fn with_fetch(fetch: F, ttl: Duration, max_entries: usize) -> Self {
+        Self {
+            fetch,
+            searches: TtlCache::new(ttl, max_entries),
+            pages: TtlCache::new(ttl, max_entries),
+        }
+    }
+
+    /// Find the best article for `name` and fetch its intro: `(title, extract)`
+    pub async fn fetch_intro(&self, name: &str) -> Result<Option<(String, String)>> {
+        Ok(self
+            .find_intro(name, |_, _| false)
+            .await?
+            .map(|intro| (intro.title, intro.extract)))
+    }
+
+    /// Search for `name` and fetch the intro of the best match: a result titled exactly
+    /// `name`, then the first result `prefer` accepts (given the title and its lowercased
+    /// snippet), then the top result
+    pub async fn find_intro(
+        &self,
+        name: &str,
+        prefer: impl Fn(&str, &str) -> bool,
+    ) -> Result<Option<WikiIntro>> {
+        let hits = self.search(name).await?;
+        let Some(title) = resolve_title(&hits, name, prefer) else {
+            info!("No search results found for: {}", name);
+            return Ok(None);
+        };
+
+        info!("Found Wikipedia page: {}", title);
+        self.page_intro(&title).await
+    }
+
+    /// The top search results for `query`
+    pub async fn search(&self, query: &str) -> Result<Vec<SearchHit>> {
+        if let Some(hits) = self.searches.get_at(query, Instant::now()) {
+            info!("Using cached Wikipedia search for: {}", query);
+            return Ok(hits);
+        }
+
+        info!("Searching Wikipedia for: {}", query);
+        let search_url = format!(
+            "{API_URL}?action=query&list=search&srsearch={}&format=json&srlimit={SEARCH_LIMIT}",
+            urlencoding::encode(query)
+        );
+        let hits = parse_search_hits(&self.fetch.get_json(&search_url).await?);
+        self.searches.insert_at(query, hits.clone(), Instant::now());
+        Ok(hits)
+    }
+
+    /// The intro, thumbnail and Wikidata item of the article titled `title`, following
+    /// redirects
+    pub async fn page_intro(&self, title: &str) -> Result<Option<WikiIntro>> {
+        if let Some(intro) = self.pages.get_at(title, Instant::now()) {
+            info!("Using cached Wikipedia page for: {}", title);
+            return Ok(intro);
+        }
+
+        let page_url = format!(
+            "{API_URL}?action=query&prop=extracts|pageprops|pageimages&exintro&explaintext&redirects=1&pithumbsize=300&titles={}&format=json",
+            urlencoding::encode(title)
+        );
+        let intro = parse_page_intro(&self.fetch.get_json(&page_url).await?, title);
+        self.pages.insert_at(title, intro.clone(), Instant::now());
+        Ok(intro)
+    }
+}
+
+// The results of a list=search query
+fn parse_search_hits(search_json: &Value) -> Vec<SearchHit> {
+    search_json
+        .get("query")
+        .and_then(|q| q.get("search"))
+        .and_then(|s| s.as_array())
+        .map(|results| {
+            results
+                .iter()
+                .filter_map(|result| {
+                    Some(SearchHit {
+                        title: result.get("title")?.as_str()?.to_string(),
+                        snippet: result
+                            .get("snippet")
+                            .and_then(|s| s.as_str())
+                            .unwrap_or("")
+                            .to_lowercase(),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Pick the search result to use for `name` (see `Wikipedia::find_intro`)
+pub fn resolve_title(
+    hits: &[SearchHit],
+    name: &str,
+    prefer: impl Fn(&str, &str) -> bool,
+) -> Option<String> {
+    let name = name.trim().to_lowercase();
+    hits.iter()
+        .find(|hit| hit.title.to_lowercase() == name)
+        .or_else(|| hits.iter().find(|hit| prefer(&hit.title, &hit.snippet)))
+        .or_else(|| hits.first())
+        .map(|hit| hit.title.clone())
+}
+
+// The page in a prop=extracts query, or None if there's no such article
+fn parse_page_intro(page_json: &Value, title: &str) -> Option<WikiIntro> {
+    // There's only one page, keyed by its ID
+    let Some(page) = page_json
+        .get("query")
+        .and_then(|q| q.get("pages"))
+        .and_then(|p| p.as_object())
+        .and_then(|pages| pages.values().next())
+    else {
+        info!("No page data found for: {}", title);
+        return None;
+    };
+
+    let Some(extract) = page.get("extract").and_then(|e| e.as_str()) else {
+        info!("No extract found for page: {}", title);
+        return None;
+    };
+
+    Some(WikiIntro {
+        // After a redirect this is the article's real title
+        title: page
+            .get("title")
+            .and_then(|t| t.as_str())
+            .unwrap_or(title)
+            .to_string(),
+        extract: extract.to_string(),
+        thumbnail_url: page
+            .get("thumbnail")
+            .and_then(|t| t.get("source"))
+            .and_then(|s| s.as_str())
+            .map(str::to_string),
+        wikidata_id: page
+            .get("pageprops")
+            .and_then(|pp| pp.get("wikibase_item"))
+            .and_then(|w| w.as_str())
+            .map(str::to_string),
+    })
+}
+
+/// The first `count` sentences of an extract. Initials and abbreviations like "J. R." or
+/// "U.S." don't end a sentence.
+pub fn first_sentences(text: &str, count: usize) -> String {
+    let mut sentences: Vec<String> = Vec::new();
+    let mut current = String::new();
+
+    // A period only ends a sentence when a space or the end of the text follows it
+    let mut parts = Vec::new();
+    let mut start = 0;
+    for (i, c) in text.char_indices() {
+        if c == '.' && text[i + 1..].chars().next().is_none_or(char::is_whitespace) {
+            parts.push(&text[start..i]);
+            start = i + 1;
+        }
+    }
+    parts.push(&text[start..]);
+
+    for part in parts {
+        let trimmed = part.split_whitespace().collect::<Vec<_>>().join(" ");
+        if trimmed.is_empty() {
+            continue;
+        }
+        if current.is_empty() {
+            current = trimmed;
+            continue;
+        }
+
+        let last_word: String = current
+            .split_whitespace()
+            .last()
+            .unwrap_or("")
+            .chars()
+            .filter(|c| *c != '.')
+            .collect();
+        let ends_with_initial = last_word.chars().count() <= 2
+            && last_word
+                .chars()
+                .all(|c| c.is_uppercase() || c.is_ascii_digit());
+
+        if ends_with_initial {
+            current.push_str(&format!(". {trimmed}"));
+        } else {
+            current.push('.');
+            sentences.push(std::mem::replace(&mut current, trimmed));
+            if sentences.len() >= count {
+                current.clear();
+                break;
+            }
+        }
+    }
+    if !current.is_empty() && sentences.len() < count {
+        current.push('.');
+        sentences.push(current);
+    }
+
+    sentences.join(" ")
+}
+
+/// The article's address on Wikipedia
+pub fn article_url(title: &str) -> String {
+    format!(
+        "{ARTICLE_URL}/{}",
+        urlencoding::encode(&title.replace(' ', "_"))
+    )
+}
+
+// What !wiki posts: the title, the first two sentences and a link
+fn format_summary(title: &str, extract: &str) -> String {
+    let mut summary = first_sentences(extract, 2);
+    if summary.chars().count() > MAX_SUMMARY_CHARS {
+        let cut: String = summary.chars().take(MAX_SUMMARY_CHARS - 1).collect();
+        let cut = cut.rsplit_once(' ').map_or(cut.as_str(), |(head, _)| head);
+        summary = format!("{}…", cut.trim_end());
+    }
+
+    let url = article_url(title);
+    if summary.is_empty() {
+        format!("**{title}**\n<{url}>")
+    } else {
+        format!("**{title}**: {summary}\n<{url}>")
+    }
+}
+
+pub async fn handle_wiki_command(
+    http: &Http,
+    msg: &Message,
+    wikipedia: &Wikipedia,
+    topic: &str,
+) -> Result<()> {
+    let topic = topic.trim();
+    if topic.is_empty() {
+        msg.reply(http, "Usage: !wiki <topic>").await?;
+        return Ok(());
+    }
+    info!("Handling !wiki command for: {}", topic);
+
+    // Show typing indicator while processing
+    if let Err(e) = msg.channel_id.broadcast_typing(http).await {
+        error!("Failed to send typing indicator: {:?}", e);
+    }
+
+    match wikipedia.fetch_intro(topic).await {
+        Ok(Some((title, extract))) => {
+            let summary = format_summary(&title, &extract);
+            if let Err(e) = msg.channel_id.say(http, summary).await {
+                error!("Error sending Wikipedia summary: {:?}", e);
+            }
+        }
+        Ok(None) => {
+            msg.reply(
+                http,
+                format!("Sorry, I couldn't find a Wikipedia article about '{topic}'."),
+            )
+            .await?;
+        }
+        Err(e) => {
+            error!("Error fetching Wikipedia summary: {:?}", e);
+            msg.reply(http, "Sorry, I couldn't reach Wikipedia right now.")
+                .await?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    // Answers searches and page fetches from fixed JSON, counting the requests
+    struct MockFetch {
+        requests: AtomicUsize,
+    }
+
+    impl WikiFetch for MockFetch {
+        async fn get_json(&self, url: &str) -> Result<Value> {
+            self.requests.fetch_add(1, Ordering::SeqCst);
+            if url.contains("list=search") {
+                if url.contains("srsearch=Steamed%20hams") {
+                    Ok(serde_json::json!({"query": {"search": [
+                        {"title": "Steamed hams", "snippet": "A regional dish"}
+                    ]}}))
+                } else {
+                    Ok(serde_json::json!({"query": {"search": []}}))
+                }
+            } else {
+                Ok(serde_json::json!({"query": {"pages": {"42": {
+                    "title": "Steamed hams",
+                    "extract": "Steamed hams are a regional dish.",
+                    "pageprops": {"wikibase_item": "Q42"}
+                }}}}))
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_lookups_are_cached() {
+        let wikipedia = Wikipedia::with_fetch(
+            MockFetch {
+                requests: AtomicUsize::new(0),
+            },
+            Duration::from_secs(60),
+            10,
+        );
+        let requests = || wikipedia.fetch.requests.load(Ordering::SeqCst);
+
+        let intro = wikipedia.fetch_intro("Steamed hams").await.unwrap();
+        assert_eq!(
+            intro,
+            Some((
+                "Steamed hams".to_string(),
+                "Steamed hams are a regional dish.".to_string()
+            ))
+        );
+        assert_eq!(requests(), 2);
+
+        // Asking again, however it's written, doesn't go back to the API
+        let again = wikipedia.fetch_intro("  steamed   HAMS ").await.unwrap();
+        assert_eq!(again, intro);
+        let page = wikipedia
+            .find_intro("Steamed hams", |_, _| true)
+            .await
+            .unwrap();
+        assert_eq!(page.unwrap().wikidata_id.as_deref(), Some("Q42"));
+        assert_eq!(requests(), 2);
+
+        // Nor does a name that found nothing
+        assert_eq!(
+            wikipedia.fetch_intro("Aurora borealis").await.unwrap(),
+            None
+        );
+        assert_eq!(
+            wikipedia.fetch_intro("aurora borealis").await.unwrap(),
+            None
+        );
+        assert_eq!(requests(), 3);
+    }
+
+    #[test]
+    fn test_cache_expiry_and_eviction() {
+        let cache = TtlCache::new(Duration::from_secs(60), 2);
+        let start = Instant::now();
+        let later = |secs| start + Duration::from_secs(secs);
+
+        cache.insert_at("Homer", 1, start);
+        cache.insert_at("Marge", 2, later(1));
+        assert_eq!(cache.get_at("HOMER", later(2)), Some(1));
+
+        // Full: the least recently used name (Marge, as Homer was just read) goes
+        cache.insert_at("Bart", 3, later(3));
+        assert_eq!(cache.get_at("Marge", later(4)), None);
+        assert_eq!(cache.get_at("Homer", later(4)), Some(1));
+        assert_eq!(cache.get_at("Bart", later(4)), Some(3));
+
+        // Entries expire after the TTL from when they were stored
+        assert_eq!(cache.get_at("Homer", later(61)), None);
+        assert_eq!(cache.get_at("Bart", later(62)), Some(3));
+
+        // A cache with no room stores nothing
+        let off = TtlCache::new(Duration::from_secs(60), 0);
+        off.insert_at("Homer", 1, start);
+        assert_eq!(off.get_at("Homer", start), None);
+    }
+
+    #[test]
+    fn test_resolve_title() {
+        let results = parse_search_hits(&serde_json::json!({"query": {"search": [
+            {"title": "Homer Simpson", "snippet": "A fictional character in The Simpsons"},
+            {"title": "Homer", "snippet": "Ancient Greek AUTHOR of the Iliad"},
+            {"title": "Homer, Alaska", "snippet": "a city in Kenai Peninsula Borough"},
+            {"snippet": "a result without a title"}
+        ]}}));
+        assert_eq!(results.len(), 3);
+        let author = |_: &str, snippet: &str| snippet.contains("author");
+
+        // An exact title wins, whatever the case
+        assert_eq!(
+            resolve_title(&results, " homer, alaska ", author).as_deref(),
+            Some("Homer, Alaska")
+        );
+        // Then the first result the caller prefers
+        assert_eq!(
+            resolve_title(&results, "homer poet", author).as_deref(),
+            Some("Homer")
+        );
+        // Then the top result
+        assert_eq!(
+            resolve_title(&results, "homer poet", |_, _| false).as_deref(),
+            Some("Homer Simpson")
+        );
+        assert_eq!(resolve_title(&[], "homer", author), None);
+    }
+
+    #[test]
+    fn test_first_sentences() {
+        let extract = "J. R. R. Tolkien was an English writer and philologist. He was the \
+                       author of The Hobbit. He served in the U.S. Army, probably not.";
+        assert_eq!(
+            first_sentences(extract, 2),
+            "J. R. R. Tolkien was an English writer and philologist. He was the author of The Hobbit."
+        );
+        assert_eq!(
+            first_sentences("Pi is about 3.14 and\nirrational", 2),
+            "Pi is about 3.14 and irrational."
+        );
+        assert_eq!(
+            first_sentences("He served in the U.S. Army. Then he retired. Then more.", 1),
+            "He served in the U.S. Army."
+        );
+        assert_eq!(first_sentences("  ", 2), "");
+    }
+
+    #[test]
+    fn test_format_summary() {
+        assert_eq!(
+            format_summary(
+                "Steamed hams",
+                "Steamed hams are a regional dish. They are grilled. Nobody asks."
+            ),
+            "**Steamed hams**: Steamed hams are a regional dish. They are grilled.\n<https://en.wikipedia.org/wiki/Steamed_hams>"
+        );
+
+        // Very long sentences are cut at a word
+        let long = format!("{} end.", "word ".repeat(200));
+        let summary = format_summary("Words", &long);
+        let text = summary.lines().next().unwrap();
+        assert!(text.ends_with("word…"));
+        assert!(text.chars().count() <= MAX_SUMMARY_CHARS + "**Words**: ".len());
+
+        assert_eq!(
+            format_summary("Empty", ""),
+            "**Empty**\n<https://en.wikipedia.org/wiki/Empty>"
+        );
+        assert_eq!(
+            article_url("AC/DC (band)"),
+            "https://en.wikipedia.org/wiki/AC%2FDC_%28band%29"
+        );
+    }
+}